@@ -38,6 +38,7 @@ use numfmt::{Formatter, Scales};
 use quickwit_actors::ActorHandle;
 use quickwit_common::uri::Uri;
 use quickwit_config::{ConfigFormat, IndexConfig};
+use quickwit_index_management::{IndexRestoreOptions, RestoreSplitsMode};
 use quickwit_indexing::models::IndexingStatistics;
 use quickwit_indexing::IndexingPipeline;
 use quickwit_metastore::{IndexMetadata, Split, SplitState};
@@ -186,6 +187,39 @@ pub fn build_index_command() -> Command {
                         .required(false),
                 ])
             )
+        .subcommand(
+            Command::new("snapshot")
+                .display_order(8)
+                .about("Writes a snapshot of an index to a storage.")
+                .long_about("Writes a snapshot of an index to a storage: the published split files and a manifest holding the index metadata, sources, checkpoints, and the metadata of the splits.")
+                .args(&[
+                    arg!(--index <INDEX> "ID of the target index")
+                        .display_order(1)
+                        .required(true),
+                    arg!(--"snapshot-uri" <SNAPSHOT_URI> "URI of the snapshot, for instance `s3://my-bucket/snapshots/my-index`.")
+                        .display_order(2)
+                        .required(true),
+                ])
+            )
+        .subcommand(
+            Command::new("restore")
+                .display_order(9)
+                .about("Restores an index from a snapshot.")
+                .long_about("Restores an index from a snapshot: creates the index and its sources, restores the published splits and the source checkpoints. The split files are copied to the index storage unless `--reference` is set.")
+                .args(&[
+                    arg!(--"snapshot-uri" <SNAPSHOT_URI> "URI of the snapshot to restore.")
+                        .display_order(1)
+                        .required(true),
+                    arg!(--index <INDEX> "ID of the restored index. Defaults to the ID of the snapshotted index.")
+                        .display_order(2)
+                        .required(false),
+                    arg!(--"index-uri" <INDEX_URI> "URI of the restored index. Defaults to `<default index root URI>/<index ID>`.")
+                        .required(false)
+                        .conflicts_with("reference"),
+                    arg!(--reference "Leaves the split files in place and uses the snapshot URI as the index URI. The snapshot is then owned by the restored index.")
+                        .required(false),
+                ])
+            )
         .arg_required_else_help(true)
 }
 
@@ -247,6 +281,22 @@ pub struct ListIndexesArgs {
     pub client_args: ClientArgs,
 }
 
+#[derive(Debug, Eq, PartialEq)]
+pub struct SnapshotIndexArgs {
+    pub client_args: ClientArgs,
+    pub index_id: String,
+    pub snapshot_uri: Uri,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct RestoreIndexArgs {
+    pub client_args: ClientArgs,
+    pub snapshot_uri: Uri,
+    pub index_id_opt: Option<String>,
+    pub index_uri_opt: Option<Uri>,
+    pub splits_mode: RestoreSplitsMode,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum IndexCliCommand {
     Clear(ClearIndexArgs),
@@ -255,7 +305,9 @@ pub enum IndexCliCommand {
     Describe(DescribeIndexArgs),
     Ingest(IngestDocsArgs),
     List(ListIndexesArgs),
+    Restore(RestoreIndexArgs),
     Search(SearchIndexArgs),
+    Snapshot(SnapshotIndexArgs),
 }
 
 impl IndexCliCommand {
@@ -277,7 +329,9 @@ impl IndexCliCommand {
             "describe" => Self::parse_describe_args(submatches),
             "ingest" => Self::parse_ingest_args(submatches),
             "list" => Self::parse_list_args(submatches),
+            "restore" => Self::parse_restore_args(submatches),
             "search" => Self::parse_search_args(submatches),
+            "snapshot" => Self::parse_snapshot_args(submatches),
             _ => bail!("unknown index subcommand `{subcommand}`"),
         }
     }
@@ -429,6 +483,47 @@ impl IndexCliCommand {
         }))
     }
 
+    fn parse_snapshot_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let client_args = ClientArgs::parse(&mut matches)?;
+        let index_id = matches
+            .remove_one::<String>("index")
+            .expect("`index` should be a required arg.");
+        let snapshot_uri = matches
+            .remove_one::<String>("snapshot-uri")
+            .map(|uri| Uri::from_str(&uri))
+            .expect("`snapshot-uri` should be a required arg.")?;
+        Ok(Self::Snapshot(SnapshotIndexArgs {
+            client_args,
+            index_id,
+            snapshot_uri,
+        }))
+    }
+
+    fn parse_restore_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let client_args = ClientArgs::parse(&mut matches)?;
+        let snapshot_uri = matches
+            .remove_one::<String>("snapshot-uri")
+            .map(|uri| Uri::from_str(&uri))
+            .expect("`snapshot-uri` should be a required arg.")?;
+        let index_id_opt = matches.remove_one::<String>("index");
+        let index_uri_opt = matches
+            .remove_one::<String>("index-uri")
+            .map(|uri| Uri::from_str(&uri))
+            .transpose()?;
+        let splits_mode = if matches.get_flag("reference") {
+            RestoreSplitsMode::Reference
+        } else {
+            RestoreSplitsMode::Copy
+        };
+        Ok(Self::Restore(RestoreIndexArgs {
+            client_args,
+            snapshot_uri,
+            index_id_opt,
+            index_uri_opt,
+            splits_mode,
+        }))
+    }
+
     pub async fn execute(self) -> anyhow::Result<()> {
         match self {
             Self::Clear(args) => clear_index_cli(args).await,
@@ -437,7 +532,9 @@ impl IndexCliCommand {
             Self::Describe(args) => describe_index_cli(args).await,
             Self::Ingest(args) => ingest_docs_cli(args).await,
             Self::List(args) => list_index_cli(args).await,
+            Self::Restore(args) => restore_index_cli(args).await,
             Self::Search(args) => search_index_cli(args).await,
+            Self::Snapshot(args) => snapshot_index_cli(args).await,
         }
     }
 }
@@ -1058,6 +1155,43 @@ pub async fn delete_index_cli(args: DeleteIndexArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+pub async fn snapshot_index_cli(args: SnapshotIndexArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "snapshot-index");
+    println!("❯ Snapshotting index...");
+    let qw_client = args.client_args.client();
+    let snapshot_summary = qw_client
+        .indexes()
+        .snapshot(&args.index_id, args.snapshot_uri.as_str())
+        .await?;
+    println!(
+        "{} Index successfully snapshotted to `{}` ({} splits, {}).",
+        "✔".color(GREEN_COLOR),
+        snapshot_summary.snapshot_uri,
+        snapshot_summary.num_splits,
+        ByteSize(snapshot_summary.num_bytes)
+    );
+    Ok(())
+}
+
+pub async fn restore_index_cli(args: RestoreIndexArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "restore-index");
+    println!("❯ Restoring index...");
+    let qw_client = args.client_args.client();
+    let restore_options = IndexRestoreOptions {
+        snapshot_uri: args.snapshot_uri,
+        index_id: args.index_id_opt,
+        index_uri: args.index_uri_opt,
+        splits_mode: args.splits_mode,
+    };
+    let index_metadata = qw_client.indexes().restore(&restore_options).await?;
+    println!(
+        "{} Index `{}` successfully restored.",
+        "✔".color(GREEN_COLOR),
+        index_metadata.index_id()
+    );
+    Ok(())
+}
+
 /// Starts a tokio task that displays the indexing statistics
 /// every once in awhile.
 pub async fn start_statistics_reporting_loop(
@@ -31,7 +31,7 @@ use quickwit_common::uri::Uri;
 use quickwit_config::service::QuickwitService;
 use quickwit_config::{
     ConfigFormat, MetastoreConfigs, NodeConfig, SourceConfig, StorageConfigs,
-    DEFAULT_QW_CONFIG_PATH,
+    StorageEncryptionConfig, DEFAULT_QW_CONFIG_PATH,
 };
 use quickwit_indexing::check_source_connectivity;
 use quickwit_metastore::{IndexMetadataResponseExt, MetastoreResolver};
@@ -230,20 +230,27 @@ async fn load_node_config(config_uri: &Uri) -> anyhow::Result<NodeConfig> {
 
 fn get_resolvers(
     storage_configs: &StorageConfigs,
+    storage_encryption_config_opt: Option<&StorageEncryptionConfig>,
     metastore_configs: &MetastoreConfigs,
-) -> (StorageResolver, MetastoreResolver) {
+) -> anyhow::Result<(StorageResolver, MetastoreResolver)> {
     // The CLI tests rely on the unconfigured singleton resolvers, so it's better to return them if
     // the storage and metastore configs are not set.
-    if storage_configs.is_empty() && metastore_configs.is_empty() {
-        return (
+    if storage_configs.is_empty()
+        && storage_encryption_config_opt.is_none()
+        && metastore_configs.is_empty()
+    {
+        return Ok((
             StorageResolver::unconfigured(),
             MetastoreResolver::unconfigured(),
-        );
+        ));
+    }
+    let mut storage_resolver = StorageResolver::configured(storage_configs);
+    if let Some(storage_encryption_config) = storage_encryption_config_opt {
+        storage_resolver = storage_resolver.with_encryption(storage_encryption_config)?;
     }
-    let storage_resolver = StorageResolver::configured(storage_configs);
     let metastore_resolver =
         MetastoreResolver::configured(storage_resolver.clone(), metastore_configs);
-    (storage_resolver, metastore_resolver)
+    Ok((storage_resolver, metastore_resolver))
 }
 
 /// Runs connectivity checks for a given `metastore_uri` and `index_id`.
@@ -442,6 +449,6 @@ mod tests {
         let storage_configs = StorageConfigs::new(vec![s3_storage_config.into()]);
         let metastore_configs = MetastoreConfigs::default();
         let (_storage_resolver, _metastore_resolver) =
-            get_resolvers(&storage_configs, &metastore_configs);
+            get_resolvers(&storage_configs, None, &metastore_configs).unwrap();
     }
 }
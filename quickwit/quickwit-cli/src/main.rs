@@ -96,16 +96,17 @@ mod tests {
     use quickwit_cli::cli::{build_cli, CliCommand};
     use quickwit_cli::index::{
         ClearIndexArgs, CreateIndexArgs, DeleteIndexArgs, DescribeIndexArgs, IndexCliCommand,
-        IngestDocsArgs, SearchIndexArgs,
+        IngestDocsArgs, RestoreIndexArgs, SearchIndexArgs, SnapshotIndexArgs,
     };
     use quickwit_cli::split::{DescribeSplitArgs, SplitCliCommand};
     use quickwit_cli::tool::{
         ExtractSplitArgs, GarbageCollectIndexArgs, LocalIngestDocsArgs, LocalSearchArgs, MergeArgs,
-        ToolCliCommand,
+        MigrateMetastoreArgs, ToolCliCommand,
     };
     use quickwit_cli::ClientArgs;
     use quickwit_common::uri::Uri;
     use quickwit_config::SourceInputFormat;
+    use quickwit_index_management::RestoreSplitsMode;
     use quickwit_rest_client::models::Timeout;
     use quickwit_rest_client::rest_client::CommitType;
     use reqwest::Url;
@@ -592,6 +593,82 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_parse_snapshot_and_restore_args() {
+        let app = build_cli().no_binary_name(true);
+        let matches = app
+            .try_get_matches_from([
+                "index",
+                "snapshot",
+                "--index",
+                "wikipedia",
+                "--snapshot-uri",
+                "s3://quickwit-snapshots/wikipedia",
+            ])
+            .unwrap();
+        let command = CliCommand::parse_cli_args(matches).unwrap();
+        let expected_command = CliCommand::Index(IndexCliCommand::Snapshot(SnapshotIndexArgs {
+            client_args: ClientArgs::default(),
+            index_id: "wikipedia".to_string(),
+            snapshot_uri: Uri::for_test("s3://quickwit-snapshots/wikipedia"),
+        }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app
+            .try_get_matches_from([
+                "index",
+                "restore",
+                "--snapshot-uri",
+                "s3://quickwit-snapshots/wikipedia",
+                "--index",
+                "wikipedia-restored",
+            ])
+            .unwrap();
+        let command = CliCommand::parse_cli_args(matches).unwrap();
+        let expected_command = CliCommand::Index(IndexCliCommand::Restore(RestoreIndexArgs {
+            client_args: ClientArgs::default(),
+            snapshot_uri: Uri::for_test("s3://quickwit-snapshots/wikipedia"),
+            index_id_opt: Some("wikipedia-restored".to_string()),
+            index_uri_opt: None,
+            splits_mode: RestoreSplitsMode::Copy,
+        }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app
+            .try_get_matches_from([
+                "index",
+                "restore",
+                "--snapshot-uri",
+                "s3://quickwit-snapshots/wikipedia",
+                "--reference",
+            ])
+            .unwrap();
+        let command = CliCommand::parse_cli_args(matches).unwrap();
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::Restore(RestoreIndexArgs {
+                index_id_opt: None,
+                splits_mode: RestoreSplitsMode::Reference,
+                ..
+            }))
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        assert!(app
+            .try_get_matches_from([
+                "index",
+                "restore",
+                "--snapshot-uri",
+                "s3://quickwit-snapshots/wikipedia",
+                "--index-uri",
+                "s3://quickwit-indexes/wikipedia",
+                "--reference",
+            ])
+            .is_err());
+    }
+
     #[test]
     fn test_parse_describe_index_args() {
         let app = build_cli().no_binary_name(true);
@@ -708,6 +785,45 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_migrate_metastore_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from([
+            "tool",
+            "migrate-metastore",
+            "--from",
+            "s3://quickwit-indexes",
+            "--to",
+            "postgres://localhost/metastore",
+            "--config",
+            "/config.yaml",
+            "--dry-run",
+        ])?;
+        let command = CliCommand::parse_cli_args(matches)?;
+        let expected_command =
+            CliCommand::Tool(ToolCliCommand::MigrateMetastore(MigrateMetastoreArgs {
+                config_uri: Uri::from_str("file:///config.yaml").unwrap(),
+                from_metastore_uri: Uri::from_str("s3://quickwit-indexes").unwrap(),
+                to_metastore_uri: Uri::from_str("postgres://localhost/metastore").unwrap(),
+                dry_run: true,
+            }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from([
+            "tool",
+            "migrate-metastore",
+            "--from",
+            "s3://quickwit-indexes",
+            "--to",
+            "s3://quickwit-indexes",
+            "--config",
+            "/config.yaml",
+        ])?;
+        CliCommand::parse_cli_args(matches).unwrap_err();
+        Ok(())
+    }
+
     #[test]
     fn test_parse_merge_args() -> anyhow::Result<()> {
         let app = build_cli().no_binary_name(true);
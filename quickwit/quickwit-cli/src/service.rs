@@ -76,8 +76,11 @@ impl RunCliCommand {
     pub async fn execute(&self) -> anyhow::Result<()> {
         debug!(args = ?self, "run-service");
         let mut node_config = load_node_config(&self.config_uri).await?;
-        let (storage_resolver, metastore_resolver) =
-            get_resolvers(&node_config.storage_configs, &node_config.metastore_configs);
+        let (storage_resolver, metastore_resolver) = get_resolvers(
+            &node_config.storage_configs,
+            node_config.storage_encryption_config.as_ref(),
+            &node_config.metastore_configs,
+        )?;
         crate::busy_detector::set_enabled(true);
 
         if let Some(services) = &self.services {
@@ -126,10 +129,10 @@ fn quickwit_telemetry_info(config: &NodeConfig) -> QuickwitTelemetryInfo {
     }
     // The metastore URI is only relevant if the metastore is enabled.
     if config.is_service_enabled(QuickwitService::Metastore) {
-        let feature = if config.metastore_uri.protocol() == Protocol::PostgreSQL {
-            QuickwitFeature::PostgresqMetastore
-        } else {
-            QuickwitFeature::FileBackedMetastore
+        let feature = match config.metastore_uri.protocol() {
+            Protocol::PostgreSQL => QuickwitFeature::PostgresqMetastore,
+            Protocol::SQLite => QuickwitFeature::SqliteMetastore,
+            _ => QuickwitFeature::FileBackedMetastore,
         };
         features.insert(feature);
     }
@@ -46,7 +46,7 @@ use quickwit_indexing::models::{
 };
 use quickwit_indexing::IndexingPipeline;
 use quickwit_ingest::IngesterPool;
-use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_metastore::{migrate_metastore, IndexMetadataResponseExt};
 use quickwit_proto::indexing::CpuCapacity;
 use quickwit_proto::metastore::{IndexMetadataRequest, MetastoreService, MetastoreServiceClient};
 use quickwit_proto::search::{CountHits, SearchResponse};
@@ -166,6 +166,21 @@ pub fn build_tool_command() -> Command {
                         .required(true),
                 ])
             )
+        .subcommand(
+            Command::new("migrate-metastore")
+                .display_order(10)
+                .about("Migrates the indexes, sources, checkpoints, splits, delete tasks, and shards of a metastore to another metastore. All the nodes using the source metastore must be stopped during the migration.")
+                .args(&[
+                    arg!(--from <FROM_METASTORE_URI> "URI of the source metastore.")
+                        .display_order(1)
+                        .required(true),
+                    arg!(--to <TO_METASTORE_URI> "URI of the target metastore.")
+                        .display_order(2)
+                        .required(true),
+                    arg!(--"dry-run" "Executes the command in dry run mode and only displays what would be migrated.")
+                        .required(false),
+                ])
+            )
         .arg_required_else_help(true)
 }
 
@@ -218,6 +233,14 @@ pub struct ExtractSplitArgs {
     pub target_dir: PathBuf,
 }
 
+#[derive(Debug, Eq, PartialEq)]
+pub struct MigrateMetastoreArgs {
+    pub config_uri: Uri,
+    pub from_metastore_uri: Uri,
+    pub to_metastore_uri: Uri,
+    pub dry_run: bool,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum ToolCliCommand {
     GarbageCollect(GarbageCollectIndexArgs),
@@ -225,6 +248,7 @@ pub enum ToolCliCommand {
     LocalSearch(LocalSearchArgs),
     Merge(MergeArgs),
     ExtractSplit(ExtractSplitArgs),
+    MigrateMetastore(MigrateMetastoreArgs),
 }
 
 impl ToolCliCommand {
@@ -238,6 +262,7 @@ impl ToolCliCommand {
             "local-search" => Self::parse_local_search_args(submatches),
             "merge" => Self::parse_merge_args(submatches),
             "extract-split" => Self::parse_extract_split_args(submatches),
+            "migrate-metastore" => Self::parse_migrate_metastore_args(submatches),
             _ => bail!("unknown tool subcommand `{subcommand}`"),
         }
     }
@@ -390,6 +415,31 @@ impl ToolCliCommand {
         }))
     }
 
+    fn parse_migrate_metastore_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let config_uri = matches
+            .remove_one::<String>("config")
+            .map(|uri_str| Uri::from_str(&uri_str))
+            .expect("`config` should be a required arg.")?;
+        let from_metastore_uri = matches
+            .remove_one::<String>("from")
+            .map(|uri_str| Uri::from_str(&uri_str))
+            .expect("`from` should be a required arg.")?;
+        let to_metastore_uri = matches
+            .remove_one::<String>("to")
+            .map(|uri_str| Uri::from_str(&uri_str))
+            .expect("`to` should be a required arg.")?;
+        if from_metastore_uri == to_metastore_uri {
+            bail!("source and target metastores must be different");
+        }
+        let dry_run = matches.get_flag("dry-run");
+        Ok(Self::MigrateMetastore(MigrateMetastoreArgs {
+            config_uri,
+            from_metastore_uri,
+            to_metastore_uri,
+            dry_run,
+        }))
+    }
+
     pub async fn execute(self) -> anyhow::Result<()> {
         match self {
             Self::GarbageCollect(args) => garbage_collect_index_cli(args).await,
@@ -397,6 +447,7 @@ impl ToolCliCommand {
             Self::LocalSearch(args) => local_search_cli(args).await,
             Self::Merge(args) => merge_cli(args).await,
             Self::ExtractSplit(args) => extract_split_cli(args).await,
+            Self::MigrateMetastore(args) => migrate_metastore_cli(args).await,
         }
     }
 }
@@ -406,8 +457,11 @@ pub async fn local_ingest_docs_cli(args: LocalIngestDocsArgs) -> anyhow::Result<
     println!("❯ Ingesting documents locally...");
 
     let config = load_node_config(&args.config_uri).await?;
-    let (storage_resolver, metastore_resolver) =
-        get_resolvers(&config.storage_configs, &config.metastore_configs);
+    let (storage_resolver, metastore_resolver) = get_resolvers(
+        &config.storage_configs,
+        config.storage_encryption_config.as_ref(),
+        &config.metastore_configs,
+    )?;
     let mut metastore = metastore_resolver.resolve(&config.metastore_uri).await?;
 
     let source_params = if let Some(filepath) = args.input_path_opt.as_ref() {
@@ -530,8 +584,11 @@ pub async fn local_search_cli(args: LocalSearchArgs) -> anyhow::Result<()> {
     debug!(args=?args, "local-search");
     println!("❯ Searching directly on the index storage (without calling REST API)...");
     let config = load_node_config(&args.config_uri).await?;
-    let (storage_resolver, metastore_resolver) =
-        get_resolvers(&config.storage_configs, &config.metastore_configs);
+    let (storage_resolver, metastore_resolver) = get_resolvers(
+        &config.storage_configs,
+        config.storage_encryption_config.as_ref(),
+        &config.metastore_configs,
+    )?;
     let metastore: MetastoreServiceClient =
         metastore_resolver.resolve(&config.metastore_uri).await?;
     let aggs = args
@@ -551,6 +608,8 @@ pub async fn local_search_cli(args: LocalSearchArgs) -> anyhow::Result<()> {
         format: BodyFormat::Json,
         sort_by,
         count_all: CountHits::CountAll,
+        profile: false,
+        runtime_mappings: None,
     };
     let search_request =
         search_request_from_api_request(vec![args.index_id], search_request_query_string)?;
@@ -567,8 +626,11 @@ pub async fn merge_cli(args: MergeArgs) -> anyhow::Result<()> {
     debug!(args=?args, "run-merge-operations");
     println!("❯ Merging splits locally...");
     let config = load_node_config(&args.config_uri).await?;
-    let (storage_resolver, metastore_resolver) =
-        get_resolvers(&config.storage_configs, &config.metastore_configs);
+    let (storage_resolver, metastore_resolver) = get_resolvers(
+        &config.storage_configs,
+        config.storage_encryption_config.as_ref(),
+        &config.metastore_configs,
+    )?;
     let mut metastore = metastore_resolver.resolve(&config.metastore_uri).await?;
     run_index_checklist(&mut metastore, &storage_resolver, &args.index_id, None).await?;
     // The indexing service needs to update its cluster chitchat state so that the control plane is
@@ -655,8 +717,11 @@ pub async fn garbage_collect_index_cli(args: GarbageCollectIndexArgs) -> anyhow:
     println!("❯ Garbage collecting index...");
 
     let config = load_node_config(&args.config_uri).await?;
-    let (storage_resolver, metastore_resolver) =
-        get_resolvers(&config.storage_configs, &config.metastore_configs);
+    let (storage_resolver, metastore_resolver) = get_resolvers(
+        &config.storage_configs,
+        config.storage_encryption_config.as_ref(),
+        &config.metastore_configs,
+    )?;
     let metastore = metastore_resolver.resolve(&config.metastore_uri).await?;
     let mut index_service = IndexService::new(metastore, storage_resolver);
     let removal_info = index_service
@@ -720,8 +785,11 @@ async fn extract_split_cli(args: ExtractSplitArgs) -> anyhow::Result<()> {
     println!("❯ Extracting split...");
 
     let config = load_node_config(&args.config_uri).await?;
-    let (storage_resolver, metastore_resolver) =
-        get_resolvers(&config.storage_configs, &config.metastore_configs);
+    let (storage_resolver, metastore_resolver) = get_resolvers(
+        &config.storage_configs,
+        config.storage_encryption_config.as_ref(),
+        &config.metastore_configs,
+    )?;
     let mut metastore = metastore_resolver.resolve(&config.metastore_uri).await?;
     let index_metadata = metastore
         .index_metadata(IndexMetadataRequest::for_index_id(args.index_id))
@@ -747,6 +815,46 @@ async fn extract_split_cli(args: ExtractSplitArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+async fn migrate_metastore_cli(args: MigrateMetastoreArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "migrate-metastore");
+    println!("❯ Migrating metastore...");
+
+    let config = load_node_config(&args.config_uri).await?;
+    let (_storage_resolver, metastore_resolver) = get_resolvers(
+        &config.storage_configs,
+        config.storage_encryption_config.as_ref(),
+        &config.metastore_configs,
+    )?;
+    let source_metastore = metastore_resolver.resolve(&args.from_metastore_uri).await?;
+    let target_metastore = metastore_resolver.resolve(&args.to_metastore_uri).await?;
+    let summaries = migrate_metastore(source_metastore, target_metastore, args.dry_run).await?;
+
+    if summaries.is_empty() {
+        println!("No indexes to migrate.");
+        return Ok(());
+    }
+    if args.dry_run {
+        println!("The following indexes will be migrated.");
+    }
+    for summary in &summaries {
+        println!(
+            " - {}: {} sources, {} splits, {} delete tasks, {} shards",
+            summary.index_id,
+            summary.num_sources,
+            summary.num_splits,
+            summary.num_delete_tasks,
+            summary.num_shards
+        );
+    }
+    if !args.dry_run {
+        println!(
+            "{} Metastore successfully migrated.",
+            "✔".color(GREEN_COLOR)
+        );
+    }
+    Ok(())
+}
+
 /// Starts a tokio task that displays the indexing statistics
 /// every once in awhile.
 pub async fn start_statistics_reporting_loop(
@@ -65,6 +65,14 @@ const MARKED_FOR_DELETION_GRACE_PERIOD: usize = if cfg!(any(test, feature = "tes
 // `{INDEXING_TASK_PREFIX}{PIPELINE_ULID}`.
 const INDEXING_TASK_PREFIX: &str = "indexer.task:";
 
+// The splits stored in the split cache of a searcher are advertised in a single key, as a sorted
+// comma-separated list of split IDs.
+const WARM_SPLITS_KEY: &str = "searcher.warm_splits";
+
+// Maximum number of splits advertised by a searcher, so that the warm splits value stays well
+// below the size of a gossip message.
+const MAX_NUM_ADVERTISED_WARM_SPLITS: usize = 256;
+
 #[derive(Clone)]
 pub struct Cluster {
     cluster_id: String,
@@ -380,6 +388,30 @@ impl Cluster {
         Ok(())
     }
 
+    /// Updates the splits stored in the split cache of the searcher in chitchat state.
+    /// `split_ids` must be sorted by decreasing priority, typically from the most to the least
+    /// recently accessed split: only the first `MAX_NUM_ADVERTISED_WARM_SPLITS` splits are
+    /// advertised, in a single key as follows:
+    /// - key: `{WARM_SPLITS_KEY}`
+    /// - value: sorted comma-separated list of split IDs.
+    /// The key is only updated when the set of advertised splits changes.
+    pub async fn update_self_node_warm_splits(&self, split_ids: &[String]) {
+        let mut advertised_split_ids: Vec<&str> = split_ids
+            .iter()
+            .take(MAX_NUM_ADVERTISED_WARM_SPLITS)
+            .map(|split_id| split_id.as_str())
+            .collect();
+        advertised_split_ids.sort_unstable();
+        let warm_splits_value = advertised_split_ids.join(",");
+
+        let chitchat = self.chitchat().await;
+        let mut chitchat_guard = chitchat.lock().await;
+        let node_state = chitchat_guard.self_node_state();
+        if node_state.get(WARM_SPLITS_KEY).unwrap_or_default() != warm_splits_value {
+            node_state.set(WARM_SPLITS_KEY, warm_splits_value);
+        }
+    }
+
     pub async fn chitchat(&self) -> Arc<Mutex<Chitchat>> {
         self.inner.read().await.chitchat_handle.chitchat()
     }
@@ -443,6 +475,19 @@ pub fn parse_indexing_tasks(node_state: &NodeState) -> Vec<IndexingTask> {
         .collect()
 }
 
+/// Parses the IDs of the splits stored in the split cache of a searcher from the chitchat node
+/// state.
+pub(crate) fn parse_warm_split_ids(node_state: &NodeState) -> HashSet<String> {
+    let Some(warm_splits_value) = node_state.get(WARM_SPLITS_KEY) else {
+        return HashSet::new();
+    };
+    warm_splits_value
+        .split(',')
+        .filter(|split_id| !split_id.is_empty())
+        .map(|split_id| split_id.to_string())
+        .collect()
+}
+
 /// Writes the given indexing tasks in the given node state.
 ///
 /// If previous indexing tasks were present in the node state but were not in the given tasks, they
@@ -1101,6 +1146,63 @@ mod tests {
         assert_eq!(ready_members[0].indexing_tasks.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_cluster_update_self_node_warm_splits() {
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["searcher"], &transport, true)
+            .await
+            .unwrap();
+        cluster
+            .update_self_node_warm_splits(&["split-2".to_string(), "split-1".to_string()])
+            .await;
+        {
+            let chitchat_handle = cluster.chitchat().await;
+            let mut chitchat_guard = chitchat_handle.lock().await;
+            let node_state = chitchat_guard.self_node_state();
+            assert_eq!(node_state.get(WARM_SPLITS_KEY), Some("split-1,split-2"));
+            assert_eq!(
+                parse_warm_split_ids(node_state),
+                HashSet::from_iter(["split-1".to_string(), "split-2".to_string()])
+            );
+        }
+        let version = {
+            let chitchat_handle = cluster.chitchat().await;
+            let mut chitchat_guard = chitchat_handle.lock().await;
+            let node_state = chitchat_guard.self_node_state();
+            node_state.get_versioned(WARM_SPLITS_KEY).unwrap().version
+        };
+        // The same set of splits in a different order does not update the key.
+        cluster
+            .update_self_node_warm_splits(&["split-1".to_string(), "split-2".to_string()])
+            .await;
+        {
+            let chitchat_handle = cluster.chitchat().await;
+            let mut chitchat_guard = chitchat_handle.lock().await;
+            let node_state = chitchat_guard.self_node_state();
+            assert_eq!(
+                node_state.get_versioned(WARM_SPLITS_KEY).unwrap().version,
+                version
+            );
+        }
+        // Only the first splits are advertised.
+        let split_ids: Vec<String> = (0..MAX_NUM_ADVERTISED_WARM_SPLITS + 10)
+            .map(|split_ord| format!("split-{split_ord:04}"))
+            .collect();
+        cluster.update_self_node_warm_splits(&split_ids).await;
+        {
+            let chitchat_handle = cluster.chitchat().await;
+            let mut chitchat_guard = chitchat_handle.lock().await;
+            let warm_split_ids = parse_warm_split_ids(chitchat_guard.self_node_state());
+            assert_eq!(warm_split_ids.len(), MAX_NUM_ADVERTISED_WARM_SPLITS);
+            assert!(warm_split_ids.contains("split-0000"));
+            assert!(!warm_split_ids.contains(&split_ids[MAX_NUM_ADVERTISED_WARM_SPLITS]));
+        }
+        cluster.update_self_node_warm_splits(&[]).await;
+        let chitchat_handle = cluster.chitchat().await;
+        let mut chitchat_guard = chitchat_handle.lock().await;
+        assert!(parse_warm_split_ids(chitchat_guard.self_node_state()).is_empty());
+    }
+
     #[tokio::test]
     async fn test_cluster_id_isolation() -> anyhow::Result<()> {
         quickwit_common::setup_logging_for_tests();
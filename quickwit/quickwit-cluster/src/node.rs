@@ -27,6 +27,7 @@ use quickwit_config::service::QuickwitService;
 use quickwit_proto::indexing::{CpuCapacity, IndexingTask};
 use tonic::transport::Channel;
 
+use crate::cluster::parse_warm_split_ids;
 use crate::member::build_cluster_member;
 
 #[derive(Clone)]
@@ -43,6 +44,7 @@ impl ClusterNode {
         is_self_node: bool,
     ) -> anyhow::Result<Self> {
         let member = build_cluster_member(chitchat_id.clone(), node_state)?;
+        let warm_split_ids = parse_warm_split_ids(node_state);
         let inner = InnerNode {
             chitchat_id,
             channel,
@@ -50,6 +52,7 @@ impl ClusterNode {
             grpc_advertise_addr: member.grpc_advertise_addr,
             indexing_tasks: member.indexing_tasks,
             indexing_capacity: member.indexing_cpu_capacity,
+            warm_split_ids,
             is_ready: member.is_ready,
             is_self_node,
         };
@@ -111,6 +114,11 @@ impl ClusterNode {
         self.inner.indexing_capacity
     }
 
+    /// Returns the IDs of the splits stored in the split cache of the node, if it is a searcher.
+    pub fn warm_split_ids(&self) -> &HashSet<String> {
+        &self.inner.warm_split_ids
+    }
+
     pub fn is_ready(&self) -> bool {
         self.inner.is_ready
     }
@@ -137,6 +145,7 @@ impl PartialEq for ClusterNode {
             && self.inner.enabled_services == other.inner.enabled_services
             && self.inner.grpc_advertise_addr == other.inner.grpc_advertise_addr
             && self.inner.indexing_tasks == other.inner.indexing_tasks
+            && self.inner.warm_split_ids == other.inner.warm_split_ids
             && self.inner.is_ready == other.inner.is_ready
             && self.inner.is_self_node == other.inner.is_self_node
     }
@@ -149,6 +158,7 @@ struct InnerNode {
     grpc_advertise_addr: SocketAddr,
     indexing_tasks: Vec<IndexingTask>,
     indexing_capacity: CpuCapacity,
+    warm_split_ids: HashSet<String>,
     is_ready: bool,
     is_self_node: bool,
 }
@@ -22,6 +22,13 @@ use std::time::Duration;
 /// Field name reserved for storing the dynamically indexed fields.
 pub const FIELD_PRESENCE_FIELD_NAME: &str = "_field_presence";
 
+/// Field name reserved for storing the path of the nested object indexed by a nested document.
+pub const NESTED_FIELD_NAME: &str = "_nested";
+
+/// Value of the nested field of root documents. It is not a valid field path, so it cannot be the
+/// path of a nested field.
+pub const NESTED_FIELD_ROOT_VALUE: &str = "#root";
+
 /// We cannot safely delete splits right away as a:
 /// - in-flight queries could actually have selected this split,
 /// - scroll queries may also have a point in time on these splits.
@@ -43,6 +43,7 @@ pub enum Protocol {
     Ram = 6,
     S3 = 7,
     Google = 8,
+    SQLite = 9,
 }
 
 impl Protocol {
@@ -56,6 +57,7 @@ impl Protocol {
             Protocol::Ram => "ram",
             Protocol::S3 => "s3",
             Protocol::Google => "gs",
+            Protocol::SQLite => "sqlite",
         }
     }
 
@@ -72,7 +74,7 @@ impl Protocol {
     }
 
     pub fn is_database(&self) -> bool {
-        matches!(&self, Protocol::PostgreSQL)
+        matches!(&self, Protocol::PostgreSQL | Protocol::SQLite)
     }
 }
 
@@ -95,6 +97,7 @@ impl FromStr for Protocol {
             "ram" => Ok(Protocol::Ram),
             "s3" => Ok(Protocol::S3),
             "gs" => Ok(Protocol::Google),
+            "sqlite" => Ok(Protocol::SQLite),
             _ => bail!("unknown URI protocol `{protocol}`"),
         }
     }
@@ -174,7 +177,7 @@ impl Uri {
     }
 
     /// Returns the parent URI.
-    /// Does not apply to PostgreSQL URIs.
+    /// Does not apply to database URIs.
     pub fn parent(&self) -> Option<Uri> {
         if self.protocol().is_database() {
             return None;
@@ -205,7 +208,7 @@ impl Uri {
 
     /// Returns the last component of the URI.
     pub fn file_name(&self) -> Option<&Path> {
-        if self.protocol() == Protocol::PostgreSQL {
+        if self.protocol().is_database() {
             return None;
         }
         let path = self.path();
@@ -242,8 +245,8 @@ impl Uri {
                 .join(path)
                 .to_string_lossy()
                 .to_string(),
-            Protocol::PostgreSQL => bail!(
-                "cannot join PostgreSQL URI `{}` with path `{:?}`",
+            Protocol::PostgreSQL | Protocol::SQLite => bail!(
+                "cannot join database URI `{}` with path `{:?}`",
                 self.uri,
                 path
             ),
@@ -524,6 +527,10 @@ mod tests {
             Uri::for_test("postgresql://localhost:5432/metastore").protocol(),
             Protocol::PostgreSQL
         );
+        assert_eq!(
+            Uri::for_test("sqlite:///var/lib/quickwit/metastore.db").protocol(),
+            Protocol::SQLite
+        );
     }
 
     #[test]
@@ -32,8 +32,8 @@ use cron::Schedule;
 use humantime::parse_duration;
 use quickwit_common::uri::Uri;
 use quickwit_doc_mapper::{
-    DefaultDocMapper, DefaultDocMapperBuilder, DocMapper, FieldMappingEntry, Mode, ModeType,
-    QuickwitJsonOptions, TokenizerEntry,
+    DefaultDocMapper, DefaultDocMapperBuilder, DocMapper, DynamicTemplate, FieldMappingEntry, Mode,
+    ModeType, QuickwitJsonOptions, TokenizerEntry,
 };
 use quickwit_proto::types::IndexId;
 use serde::{Deserialize, Serialize};
@@ -83,6 +83,9 @@ pub struct DocMapping {
     )]
     pub mode: Mode,
     #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dynamic_templates: Vec<DynamicTemplate>,
+    #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub partition_key: Option<String>,
     #[schema(value_type = u32)]
@@ -286,6 +289,125 @@ impl RetentionPolicy {
     }
 }
 
+/// A storage tier of a [`TieringPolicy`].
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct StorageTier {
+    /// Age from which the splits are moved to this tier, expressed in a human-friendly way
+    /// (`1 day`, `2 weeks`, ...). The age of a split is measured from the end of its time range.
+    #[serde(rename = "min_age")]
+    min_age: String,
+
+    /// URI of the storage receiving the splits of this tier.
+    #[schema(value_type = String)]
+    pub storage_uri: Uri,
+}
+
+impl StorageTier {
+    pub fn new(min_age: String, storage_uri: Uri) -> Self {
+        Self {
+            min_age,
+            storage_uri,
+        }
+    }
+
+    pub fn min_age(&self) -> anyhow::Result<Duration> {
+        parse_duration(&self.min_age)
+            .with_context(|| format!("failed to parse storage tier age `{}`", self.min_age))
+    }
+}
+
+/// Moves mature splits to cheaper storages as they get older. Splits start their life in the index
+/// storage and are copied to the storage of the oldest tier whose `min_age` they reached.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TieringPolicy {
+    /// Storage tiers, sorted by increasing age.
+    pub tiers: Vec<StorageTier>,
+
+    /// Defines the frequency at which the tiering policy is evaluated and applied, expressed in
+    /// a human-friendly way (`hourly`, `daily`, ...) or as a cron expression (`0 0 * * * *`,
+    /// `0 0 0 * * *`).
+    #[serde(default = "TieringPolicy::default_schedule")]
+    #[serde(rename = "schedule")]
+    evaluation_schedule: String,
+}
+
+impl TieringPolicy {
+    pub fn new(tiers: Vec<StorageTier>, evaluation_schedule: String) -> Self {
+        Self {
+            tiers,
+            evaluation_schedule,
+        }
+    }
+
+    fn default_schedule() -> String {
+        "hourly".to_string()
+    }
+
+    /// Returns the tier a split whose time range ends at `split_end_timestamp` belongs to at
+    /// `now_timestamp`, or `None` if the split should stay in the index storage.
+    pub fn target_tier(
+        &self,
+        split_end_timestamp: i64,
+        now_timestamp: i64,
+    ) -> anyhow::Result<Option<&StorageTier>> {
+        let split_age_secs = now_timestamp.saturating_sub(split_end_timestamp);
+        let mut target_tier_opt = None;
+
+        for tier in &self.tiers {
+            if split_age_secs < tier.min_age()?.as_secs() as i64 {
+                break;
+            }
+            target_tier_opt = Some(tier);
+        }
+        Ok(target_tier_opt)
+    }
+
+    pub fn evaluation_schedule(&self) -> anyhow::Result<Schedule> {
+        let evaluation_schedule = prepend_at_char(&self.evaluation_schedule);
+
+        Schedule::from_str(&evaluation_schedule).with_context(|| {
+            format!(
+                "failed to parse tiering evaluation schedule `{}`",
+                self.evaluation_schedule
+            )
+        })
+    }
+
+    pub fn duration_until_next_evaluation(&self) -> anyhow::Result<Duration> {
+        let schedule = self.evaluation_schedule()?;
+        let future_date = schedule
+            .upcoming(Utc)
+            .next()
+            .expect("Failed to obtain next evaluation date.");
+        let duration = (future_date - Utc::now())
+            .to_std()
+            .map_err(|err| anyhow::anyhow!(err.to_string()))?;
+        Ok(duration)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.tiers.is_empty() {
+            anyhow::bail!("tiering policy must define at least one storage tier");
+        }
+        let mut previous_min_age_opt: Option<Duration> = None;
+
+        for tier in &self.tiers {
+            let min_age = tier.min_age()?;
+
+            if let Some(previous_min_age) = previous_min_age_opt {
+                if min_age <= previous_min_age {
+                    anyhow::bail!("tiering policy storage tiers must be sorted by increasing age");
+                }
+            }
+            previous_min_age_opt = Some(min_age);
+        }
+        self.evaluation_schedule()?;
+        Ok(())
+    }
+}
+
 /// Prepends an `@` char at the start of the cron expression if necessary:
 /// `hourly` -> `@hourly`
 fn prepend_at_char(schedule: &str) -> String {
@@ -311,6 +433,7 @@ pub struct IndexConfig {
     pub indexing_settings: IndexingSettings,
     pub search_settings: SearchSettings,
     pub retention_policy: Option<RetentionPolicy>,
+    pub tiering_policy: Option<TieringPolicy>,
 }
 
 impl IndexConfig {
@@ -396,6 +519,7 @@ impl IndexConfig {
             indexing_settings,
             search_settings,
             retention_policy: Default::default(),
+            tiering_policy: Default::default(),
         }
     }
 }
@@ -457,6 +581,7 @@ impl TestableForRegression for IndexConfig {
                 .collect::<BTreeSet<String>>(),
             store_source: true,
             mode: Mode::default(),
+            dynamic_templates: Vec::new(),
             partition_key: Some("tenant_id".to_string()),
             max_num_partitions: NonZeroU32::new(100).unwrap(),
             timestamp_field: Some("timestamp".to_string()),
@@ -492,6 +617,7 @@ impl TestableForRegression for IndexConfig {
             doc_mapping,
             indexing_settings,
             retention_policy,
+            tiering_policy: None,
             search_settings,
         }
     }
@@ -535,6 +661,7 @@ pub fn build_doc_mapper(
         field_mappings: doc_mapping.field_mappings.clone(),
         tag_fields: doc_mapping.tag_fields.iter().cloned().collect(),
         mode: doc_mapping.mode.clone(),
+        dynamic_templates: doc_mapping.dynamic_templates.clone(),
         partition_key: doc_mapping.partition_key.clone(),
         max_num_partitions: doc_mapping.max_num_partitions,
         tokenizers: doc_mapping.tokenizers.clone(),
@@ -898,6 +1025,89 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tiering_policy_deserialization() {
+        let tiering_policy_yaml = r#"
+            tiers:
+              - min_age: 7 days
+                storage_uri: s3://quickwit-warm/my-index
+              - min_age: 90 days
+                storage_uri: s3://quickwit-cold/my-index
+        "#;
+        let tiering_policy = serde_yaml::from_str::<TieringPolicy>(tiering_policy_yaml).unwrap();
+        let expected_tiering_policy = TieringPolicy {
+            tiers: vec![
+                StorageTier::new(
+                    "7 days".to_string(),
+                    Uri::for_test("s3://quickwit-warm/my-index"),
+                ),
+                StorageTier::new(
+                    "90 days".to_string(),
+                    Uri::for_test("s3://quickwit-cold/my-index"),
+                ),
+            ],
+            evaluation_schedule: "hourly".to_string(),
+        };
+        assert_eq!(tiering_policy, expected_tiering_policy);
+        tiering_policy.validate().unwrap();
+    }
+
+    #[test]
+    fn test_tiering_policy_validate() {
+        let tier = |min_age: &str| {
+            StorageTier::new(min_age.to_string(), Uri::for_test("s3://quickwit-cold"))
+        };
+        TieringPolicy::new(Vec::new(), "hourly".to_string())
+            .validate()
+            .unwrap_err();
+        TieringPolicy::new(vec![tier("foo")], "hourly".to_string())
+            .validate()
+            .unwrap_err();
+        TieringPolicy::new(vec![tier("1 day")], "foo".to_string())
+            .validate()
+            .unwrap_err();
+        TieringPolicy::new(vec![tier("7 days"), tier("1 day")], "hourly".to_string())
+            .validate()
+            .unwrap_err();
+        TieringPolicy::new(vec![tier("1 day"), tier("7 days")], "daily".to_string())
+            .validate()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_tiering_policy_target_tier() {
+        let tiering_policy = TieringPolicy::new(
+            vec![
+                StorageTier::new("1 day".to_string(), Uri::for_test("s3://quickwit-warm")),
+                StorageTier::new("7 days".to_string(), Uri::for_test("s3://quickwit-cold")),
+            ],
+            "hourly".to_string(),
+        );
+        let day_secs = 24 * 3600;
+        let now_timestamp = 100 * day_secs;
+
+        assert!(tiering_policy
+            .target_tier(now_timestamp, now_timestamp)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            tiering_policy
+                .target_tier(now_timestamp - day_secs, now_timestamp)
+                .unwrap()
+                .unwrap()
+                .storage_uri,
+            "s3://quickwit-warm"
+        );
+        assert_eq!(
+            tiering_policy
+                .target_tier(now_timestamp - 30 * day_secs, now_timestamp)
+                .unwrap()
+                .unwrap()
+                .storage_uri,
+            "s3://quickwit-cold"
+        );
+    }
+
     #[test]
     fn test_retention_schedule_duration() {
         let schedule_test_helper_fn = |schedule_str: &str| {
@@ -24,7 +24,7 @@ use tracing::info;
 
 use crate::{
     build_doc_mapper, validate_identifier, ConfigFormat, DocMapping, IndexConfig, IndexingSettings,
-    RetentionPolicy, SearchSettings,
+    RetentionPolicy, SearchSettings, TieringPolicy,
 };
 
 /// Alias for the latest serialization format.
@@ -99,6 +99,17 @@ impl IndexConfigForSerialization {
             }
         }
 
+        if let Some(tiering_policy) = &self.tiering_policy {
+            tiering_policy.validate()?;
+
+            if self.doc_mapping.timestamp_field.is_none() {
+                anyhow::bail!(
+                    "failed to validate index config. the tiering policy requires a timestamp \
+                     field, but the indexing settings do not declare one"
+                );
+            }
+        }
+
         // Note: this needs a deep refactoring to separate the doc mapping configuration,
         // and doc mapper implementations.
         // TODO see if we should store the byproducton the IndexConfig.
@@ -114,6 +125,7 @@ impl IndexConfigForSerialization {
             indexing_settings: self.indexing_settings,
             search_settings: self.search_settings,
             retention_policy: self.retention_policy,
+            tiering_policy: self.tiering_policy,
         })
     }
 }
@@ -149,6 +161,9 @@ pub struct IndexConfigV0_7 {
     #[serde(rename = "retention")]
     #[serde(default)]
     pub retention_policy: Option<RetentionPolicy>,
+    #[serde(rename = "tiering")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tiering_policy: Option<TieringPolicy>,
 }
 
 impl From<IndexConfig> for IndexConfigV0_7 {
@@ -160,6 +175,7 @@ impl From<IndexConfig> for IndexConfigV0_7 {
             indexing_settings: index_config.indexing_settings,
             search_settings: index_config.search_settings,
             retention_policy: index_config.retention_policy,
+            tiering_policy: index_config.tiering_policy,
         }
     }
 }
@@ -168,6 +184,7 @@ impl From<IndexConfig> for IndexConfigV0_7 {
 mod test {
     use super::*;
     use crate::merge_policy_config::{MergePolicyConfig, StableLogMergePolicyConfig};
+    use crate::StorageTier;
 
     fn minimal_index_config_for_serialization() -> IndexConfigForSerialization {
         serde_yaml::from_str(
@@ -227,6 +244,24 @@ mod test {
         assert!(validation_err.contains("the retention policy requires a timestamp field"));
     }
 
+    #[test]
+    fn test_validate_tiering_policy() {
+        let mut invalid_index_config: IndexConfigForSerialization =
+            minimal_index_config_for_serialization();
+        invalid_index_config.tiering_policy = Some(TieringPolicy::new(
+            vec![StorageTier::new(
+                "30 days".to_string(),
+                Uri::for_test("s3://quickwit-cold/hdfs-logs"),
+            )],
+            "hourly".to_string(),
+        ));
+        let validation_err = invalid_index_config
+            .validate_and_build(None)
+            .unwrap_err()
+            .to_string();
+        assert!(validation_err.contains("the tiering policy requires a timestamp field"));
+    }
+
     #[test]
     fn test_minimal_index_config_missing_root_uri_no_default_uri() {
         let config_yaml = r#"
@@ -44,7 +44,8 @@ mod templating;
 use index_config::serialize::{IndexConfigV0_7, VersionedIndexConfig};
 pub use index_config::{
     build_doc_mapper, load_index_config_from_user_config, DocMapping, IndexConfig,
-    IndexingResources, IndexingSettings, RetentionPolicy, SearchSettings,
+    IndexingResources, IndexingSettings, RetentionPolicy, SearchSettings, StorageTier,
+    TieringPolicy,
 };
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -62,15 +63,17 @@ use crate::merge_policy_config::{
 };
 pub use crate::metastore_config::{
     MetastoreBackend, MetastoreConfig, MetastoreConfigs, PostgresMetastoreConfig,
+    SqliteMetastoreConfig,
 };
 pub use crate::node_config::{
-    enable_ingest_v2, IndexerConfig, IngestApiConfig, JaegerConfig, NodeConfig, SearcherConfig,
-    SplitCacheLimits, DEFAULT_QW_CONFIG_PATH,
+    enable_ingest_v2, IndexerConfig, IngestApiConfig, JaegerConfig, MetastoreCacheConfig,
+    NodeConfig, SearcherConfig, SplitCacheLimits, SplitWarmupConfig, DEFAULT_QW_CONFIG_PATH,
 };
 use crate::source_config::serialize::{SourceConfigV0_7, VersionedSourceConfig};
 pub use crate::storage_config::{
-    AzureStorageConfig, FileStorageConfig, GoogleCloudStorageConfig, RamStorageConfig,
-    S3StorageConfig, StorageBackend, StorageBackendFlavor, StorageConfig, StorageConfigs,
+    AzureStorageConfig, FileStorageConfig, GoogleCloudStorageConfig, HedgedReadsConfig,
+    RamStorageConfig, S3StorageConfig, StorageBackend, StorageBackendFlavor, StorageConfig,
+    StorageConfigs, StorageEncryptionConfig,
 };
 
 #[derive(utoipa::OpenApi)]
@@ -79,6 +82,8 @@ pub use crate::storage_config::{
     IndexingSettings,
     SearchSettings,
     RetentionPolicy,
+    TieringPolicy,
+    StorageTier,
     MergePolicyConfig,
     DocMapping,
     VersionedSourceConfig,
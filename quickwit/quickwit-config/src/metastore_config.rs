@@ -31,6 +31,8 @@ pub enum MetastoreBackend {
     File,
     #[serde(alias = "pg", alias = "postgres")]
     PostgreSQL,
+    #[serde(rename = "sqlite")]
+    SQLite,
 }
 
 /// Holds the metastore configurations defined in the `metastore` section of node config files.
@@ -42,6 +44,8 @@ pub enum MetastoreBackend {
 ///
 ///   postgres:
 ///     max_num_connections: 12
+///
+///   sqlite: {}
 /// ```
 #[serde_as]
 #[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
@@ -90,6 +94,15 @@ impl MetastoreConfigs {
                 _ => None,
             })
     }
+
+    pub fn find_sqlite(&self) -> Option<&SqliteMetastoreConfig> {
+        self.0
+            .iter()
+            .find_map(|metastore_config| match metastore_config {
+                MetastoreConfig::SQLite(sqlite_metastore_config) => Some(sqlite_metastore_config),
+                _ => None,
+            })
+    }
 }
 
 impl Deref for MetastoreConfigs {
@@ -106,6 +119,8 @@ pub enum MetastoreConfig {
     File(FileMetastoreConfig),
     #[serde(alias = "pg", alias = "postgres")]
     PostgreSQL(PostgresMetastoreConfig),
+    #[serde(rename = "sqlite")]
+    SQLite(SqliteMetastoreConfig),
 }
 
 impl MetastoreConfig {
@@ -113,6 +128,7 @@ impl MetastoreConfig {
         match self {
             Self::File(_) => MetastoreBackend::File,
             Self::PostgreSQL(_) => MetastoreBackend::PostgreSQL,
+            Self::SQLite(_) => MetastoreBackend::SQLite,
         }
     }
 
@@ -130,6 +146,13 @@ impl MetastoreConfig {
         }
     }
 
+    pub fn as_sqlite(&self) -> Option<&SqliteMetastoreConfig> {
+        match self {
+            Self::SQLite(sqlite_metastore_config) => Some(sqlite_metastore_config),
+            _ => None,
+        }
+    }
+
     pub fn redact(&mut self) {
         // TODO: Implement this method when we end up storing secrets in the
         // metastore config.
@@ -148,6 +171,12 @@ impl From<PostgresMetastoreConfig> for MetastoreConfig {
     }
 }
 
+impl From<SqliteMetastoreConfig> for MetastoreConfig {
+    fn from(sqlite_metastore_config: SqliteMetastoreConfig) -> Self {
+        Self::SQLite(sqlite_metastore_config)
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct PostgresMetastoreConfig {
@@ -173,6 +202,12 @@ impl PostgresMetastoreConfig {
 #[serde(deny_unknown_fields)]
 pub struct FileMetastoreConfig;
 
+/// The SQLite metastore serializes all the operations through a single connection, so it does
+/// not expose any connection pool settings.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SqliteMetastoreConfig {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -196,6 +231,17 @@ mod tests {
         }
         .into()]);
         assert_eq!(metastore_configs, expected_metastore_configs);
+
+        let metastore_configs_yaml = r#"
+                sqlite: {}
+            "#;
+        let metastore_configs: MetastoreConfigs =
+            serde_yaml::from_str(metastore_configs_yaml).unwrap();
+
+        let expected_metastore_configs =
+            MetastoreConfigs(vec![SqliteMetastoreConfig::default().into()]);
+        assert_eq!(metastore_configs, expected_metastore_configs);
+        assert!(metastore_configs.find_sqlite().is_some());
     }
 
     #[test]
@@ -38,7 +38,7 @@ use tracing::warn;
 
 use crate::node_config::serialize::load_node_config_with_env;
 use crate::service::QuickwitService;
-use crate::storage_config::StorageConfigs;
+use crate::storage_config::{StorageConfigs, StorageEncryptionConfig};
 use crate::{ConfigFormat, MetastoreConfigs};
 
 pub const DEFAULT_QW_CONFIG_PATH: &str = "config/quickwit.yaml";
@@ -198,6 +198,64 @@ impl Default for SplitCacheLimits {
     }
 }
 
+/// Configures the cache of published splits kept by searchers to avoid listing splits from the
+/// metastore on every search request.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetastoreCacheConfig {
+    /// Maximum age of the cached list of splits of an index before it is refreshed. The cache is
+    /// also refreshed whenever splits are published for the index.
+    #[serde(default = "MetastoreCacheConfig::default_max_staleness_secs")]
+    pub max_staleness_secs: NonZeroU64,
+}
+
+impl MetastoreCacheConfig {
+    fn default_max_staleness_secs() -> NonZeroU64 {
+        NonZeroU64::new(30).unwrap()
+    }
+
+    pub fn max_staleness(&self) -> Duration {
+        Duration::from_secs(self.max_staleness_secs.get())
+    }
+}
+
+impl Default for MetastoreCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_staleness_secs: Self::default_max_staleness_secs(),
+        }
+    }
+}
+
+/// Configuration of the warm-up of freshly uploaded splits. When enabled, searchers prefetch the
+/// footer of the splits they are most likely to be assigned, and optionally some fast fields,
+/// before the first search request hits them.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SplitWarmupConfig {
+    /// Fast fields loaded into the fast field cache during warm-up.
+    #[serde(default)]
+    pub fast_fields: Vec<String>,
+    /// Maximum number of splits warmed up concurrently.
+    #[serde(default = "SplitWarmupConfig::default_max_concurrent_warmups")]
+    pub max_concurrent_warmups: NonZeroUsize,
+}
+
+impl SplitWarmupConfig {
+    fn default_max_concurrent_warmups() -> NonZeroUsize {
+        NonZeroUsize::new(2).unwrap()
+    }
+}
+
+impl Default for SplitWarmupConfig {
+    fn default() -> Self {
+        Self {
+            fast_fields: Vec::new(),
+            max_concurrent_warmups: Self::default_max_concurrent_warmups(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields, default)]
 pub struct SearcherConfig {
@@ -206,13 +264,19 @@ pub struct SearcherConfig {
     pub fast_field_cache_capacity: ByteSize,
     pub split_footer_cache_capacity: ByteSize,
     pub partial_request_cache_capacity: ByteSize,
+    pub root_search_cache_capacity: ByteSize,
     pub max_num_concurrent_split_searches: usize,
     pub max_num_concurrent_split_streams: usize,
+    pub max_num_runtime_field_docs_per_split: usize,
     // Strangely, if None, this will also have the effect of not forwarding
     // to searcher.
     // TODO document and fix if necessary.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub split_cache: Option<SplitCacheLimits>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metastore_cache: Option<MetastoreCacheConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split_warmup: Option<SplitWarmupConfig>,
 }
 
 impl Default for SearcherConfig {
@@ -221,11 +285,15 @@ impl Default for SearcherConfig {
             fast_field_cache_capacity: ByteSize::gb(1),
             split_footer_cache_capacity: ByteSize::mb(500),
             partial_request_cache_capacity: ByteSize::mb(64),
+            root_search_cache_capacity: ByteSize::mb(64),
             max_num_concurrent_split_streams: 100,
             max_num_concurrent_split_searches: 100,
+            max_num_runtime_field_docs_per_split: 100_000,
             aggregation_memory_limit: ByteSize::mb(500),
             aggregation_bucket_limit: 65000,
             split_cache: None,
+            metastore_cache: None,
+            split_warmup: None,
         }
     }
 }
@@ -380,6 +448,7 @@ pub struct NodeConfig {
     pub rest_config: RestConfig,
     pub grpc_config: GrpcConfig,
     pub storage_configs: StorageConfigs,
+    pub storage_encryption_config: Option<StorageEncryptionConfig>,
     pub metastore_configs: MetastoreConfigs,
     pub indexer_config: IndexerConfig,
     pub searcher_config: SearcherConfig,
@@ -33,7 +33,7 @@ use super::{GrpcConfig, RestConfig};
 use crate::config_value::ConfigValue;
 use crate::qw_env_vars::*;
 use crate::service::QuickwitService;
-use crate::storage_config::StorageConfigs;
+use crate::storage_config::{StorageConfigs, StorageEncryptionConfig};
 use crate::templating::render_config;
 use crate::{
     validate_identifier, validate_node_id, ConfigFormat, IndexerConfig, IngestApiConfig,
@@ -191,6 +191,9 @@ struct NodeConfigBuilder {
     #[serde(rename = "storage")]
     #[serde(default)]
     storage_configs: StorageConfigs,
+    #[serde(rename = "storage_encryption")]
+    #[serde(default)]
+    storage_encryption_config: Option<StorageEncryptionConfig>,
     #[serde(rename = "metastore")]
     #[serde(default)]
     metastore_configs: MetastoreConfigs,
@@ -287,6 +290,9 @@ impl NodeConfigBuilder {
 
         self.storage_configs.validate()?;
         self.storage_configs.apply_flavors();
+        if let Some(storage_encryption_config) = &self.storage_encryption_config {
+            storage_encryption_config.validate()?;
+        }
         self.ingest_api_config.validate()?;
 
         let node_config = NodeConfig {
@@ -305,6 +311,7 @@ impl NodeConfigBuilder {
             grpc_config: self.grpc_config,
             metastore_configs: self.metastore_configs,
             storage_configs: self.storage_configs,
+            storage_encryption_config: self.storage_encryption_config,
             indexer_config: self.indexer_config,
             searcher_config: self.searcher_config,
             ingest_api_config: self.ingest_api_config,
@@ -351,6 +358,7 @@ impl Default for NodeConfigBuilder {
             rest_config_builder: RestConfigBuilder::default(),
             grpc_config: GrpcConfig::default(),
             storage_configs: StorageConfigs::default(),
+            storage_encryption_config: None,
             metastore_configs: MetastoreConfigs::default(),
             indexer_config: IndexerConfig::default(),
             searcher_config: SearcherConfig::default(),
@@ -443,6 +451,7 @@ pub fn node_config_for_test() -> NodeConfig {
         rest_config,
         grpc_config: GrpcConfig::default(),
         storage_configs: StorageConfigs::default(),
+        storage_encryption_config: None,
         metastore_configs: MetastoreConfigs::default(),
         indexer_config: IndexerConfig::default(),
         searcher_config: SearcherConfig::default(),
@@ -455,13 +464,14 @@ pub fn node_config_for_test() -> NodeConfig {
 mod tests {
     use std::env;
     use std::net::Ipv4Addr;
-    use std::num::NonZeroU64;
+    use std::num::{NonZeroU64, NonZeroUsize};
     use std::path::Path;
 
     use bytesize::ByteSize;
     use itertools::Itertools;
 
     use super::*;
+    use crate::node_config::{MetastoreCacheConfig, SplitWarmupConfig};
     use crate::storage_config::StorageBackendFlavor;
 
     fn get_config_filepath(config_filename: &str) -> String {
@@ -574,9 +584,18 @@ mod tests {
                 fast_field_cache_capacity: ByteSize::gb(10),
                 split_footer_cache_capacity: ByteSize::gb(1),
                 partial_request_cache_capacity: ByteSize::mb(64),
+                root_search_cache_capacity: ByteSize::mb(64),
                 max_num_concurrent_split_searches: 150,
                 max_num_concurrent_split_streams: 120,
+                max_num_runtime_field_docs_per_split: 50_000,
                 split_cache: None,
+                metastore_cache: Some(MetastoreCacheConfig {
+                    max_staleness_secs: NonZeroU64::new(10).unwrap(),
+                }),
+                split_warmup: Some(SplitWarmupConfig {
+                    fast_fields: vec!["timestamp".to_string()],
+                    max_concurrent_warmups: NonZeroUsize::new(4).unwrap(),
+                }),
             }
         );
         assert_eq!(
@@ -18,6 +18,8 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::ops::Deref;
+use std::path::PathBuf;
+use std::time::Duration;
 use std::{env, fmt};
 
 use anyhow::ensure;
@@ -104,6 +106,11 @@ impl StorageConfigs {
                 "{left:?} storage config is defined multiple times",
             );
         }
+        for storage_config in self.0.iter() {
+            if let Some(hedged_reads_config) = storage_config.hedged_reads() {
+                hedged_reads_config.validate()?;
+            }
+        }
         Ok(())
     }
 
@@ -180,6 +187,16 @@ impl StorageConfig {
         }
     }
 
+    /// Returns the hedged reads configuration of the backends supporting them.
+    pub fn hedged_reads(&self) -> Option<&HedgedReadsConfig> {
+        match self {
+            Self::Azure(azure_storage_config) => azure_storage_config.hedged_reads.as_ref(),
+            Self::S3(s3_storage_config) => s3_storage_config.hedged_reads.as_ref(),
+            Self::Google(google_storage_config) => google_storage_config.hedged_reads.as_ref(),
+            Self::File(_) | Self::Ram(_) => None,
+        }
+    }
+
     pub fn as_azure(&self) -> Option<&AzureStorageConfig> {
         match self {
             Self::Azure(azure_storage_config) => Some(azure_storage_config),
@@ -258,6 +275,75 @@ impl StorageConfig {
     }
 }
 
+/// Configures hedged ranged reads for an object storage backend. When a `get_slice` request takes
+/// longer than the configured percentile of the recent request latencies, a duplicate request is
+/// issued and the first response wins.
+///
+/// ```yaml
+/// storage:
+///   s3:
+///     hedged_reads:
+///       latency_percentile: 95
+///       min_delay_ms: 20
+///       max_hedged_requests_percent: 5
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HedgedReadsConfig {
+    /// Percentile of the recent request latencies after which a request is hedged.
+    #[serde(default = "HedgedReadsConfig::default_latency_percentile")]
+    pub latency_percentile: u8,
+    /// Lower bound of the delay after which a request is hedged.
+    #[serde(default = "HedgedReadsConfig::default_min_delay_ms")]
+    pub min_delay_ms: u64,
+    /// Maximum share of requests that can be hedged, which bounds the extra load put on the
+    /// object storage.
+    #[serde(default = "HedgedReadsConfig::default_max_hedged_requests_percent")]
+    pub max_hedged_requests_percent: u8,
+}
+
+impl HedgedReadsConfig {
+    fn default_latency_percentile() -> u8 {
+        95
+    }
+
+    fn default_min_delay_ms() -> u64 {
+        20
+    }
+
+    fn default_max_hedged_requests_percent() -> u8 {
+        5
+    }
+
+    pub fn min_delay(&self) -> Duration {
+        Duration::from_millis(self.min_delay_ms)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            (1..=99).contains(&self.latency_percentile),
+            "hedged reads latency percentile must be between 1 and 99, got {}",
+            self.latency_percentile
+        );
+        ensure!(
+            self.max_hedged_requests_percent <= 100,
+            "hedged reads max hedged requests percent must be at most 100, got {}",
+            self.max_hedged_requests_percent
+        );
+        Ok(())
+    }
+}
+
+impl Default for HedgedReadsConfig {
+    fn default() -> Self {
+        Self {
+            latency_percentile: Self::default_latency_percentile(),
+            min_delay_ms: Self::default_min_delay_ms(),
+            max_hedged_requests_percent: Self::default_max_hedged_requests_percent(),
+        }
+    }
+}
+
 #[derive(Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct AzureStorageConfig {
@@ -268,6 +354,9 @@ pub struct AzureStorageConfig {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub access_key: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hedged_reads: Option<HedgedReadsConfig>,
 }
 
 impl AzureStorageConfig {
@@ -307,6 +396,7 @@ impl fmt::Debug for AzureStorageConfig {
                 "access_key",
                 &self.access_key.as_ref().map(|_| "***redacted***"),
             )
+            .field("hedged_reads", &self.hedged_reads)
             .finish()
     }
 }
@@ -332,6 +422,9 @@ pub struct S3StorageConfig {
     pub disable_multi_object_delete: bool,
     #[serde(default)]
     pub disable_multipart_upload: bool,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hedged_reads: Option<HedgedReadsConfig>,
 }
 
 impl S3StorageConfig {
@@ -388,6 +481,7 @@ impl fmt::Debug for S3StorageConfig {
                 "disable_multi_object_delete",
                 &self.disable_multi_object_delete,
             )
+            .field("hedged_reads", &self.hedged_reads)
             .finish()
     }
 }
@@ -406,6 +500,9 @@ pub struct GoogleCloudStorageConfig {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub credential_path: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hedged_reads: Option<HedgedReadsConfig>,
 }
 
 impl GoogleCloudStorageConfig {
@@ -421,6 +518,53 @@ impl GoogleCloudStorageConfig {
     }
 }
 
+/// Holds the client-side encryption configuration defined in the `storage_encryption` section of
+/// node config files. When set, every object written to or read from storage is encrypted with a
+/// per-object data key, itself wrapped by the configured key provider.
+///
+/// ```yaml
+/// storage_encryption:
+///   key_provider: local
+///   keyfile: /etc/quickwit/storage.key
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "key_provider", rename_all = "snake_case")]
+pub enum StorageEncryptionConfig {
+    /// The key encryption key is read from a local file containing 32 base64-encoded bytes.
+    Local { keyfile: PathBuf },
+    /// The data keys are wrapped and unwrapped by a key management service compatible with the
+    /// AWS KMS `Encrypt` and `Decrypt` APIs.
+    Kms {
+        key_id: String,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        region: Option<String>,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        endpoint: Option<String>,
+    },
+}
+
+impl StorageEncryptionConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Local { keyfile } => {
+                ensure!(
+                    !keyfile.as_os_str().is_empty(),
+                    "storage encryption keyfile path must not be empty"
+                );
+            }
+            Self::Kms { key_id, .. } => {
+                ensure!(
+                    !key_id.trim().is_empty(),
+                    "storage encryption KMS key ID must not be empty"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -575,6 +719,7 @@ mod tests {
             let expected_azure_config = AzureStorageConfig {
                 account_name: Some("test-account".to_string()),
                 access_key: Some("test-access-key".to_string()),
+                hedged_reads: None,
             };
             assert_eq!(azure_storage_config, expected_azure_config);
         }
@@ -591,6 +736,7 @@ mod tests {
 
             let expected_google_cloud_storage_config = GoogleCloudStorageConfig {
                 credential_path: Some("/path/to/credential.json".to_string()),
+                hedged_reads: None,
             };
             assert_eq!(
                 google_cloud_storage_config,
@@ -679,4 +825,80 @@ mod tests {
             assert_eq!(s3_storage_config.flavor, Some(StorageBackendFlavor::MinIO));
         }
     }
+
+    #[test]
+    fn test_storage_encryption_config_serde() {
+        let storage_encryption_config_yaml = r#"
+                key_provider: local
+                keyfile: /etc/quickwit/storage.key
+            "#;
+        let storage_encryption_config: StorageEncryptionConfig =
+            serde_yaml::from_str(storage_encryption_config_yaml).unwrap();
+        assert_eq!(
+            storage_encryption_config,
+            StorageEncryptionConfig::Local {
+                keyfile: PathBuf::from("/etc/quickwit/storage.key"),
+            }
+        );
+        storage_encryption_config.validate().unwrap();
+
+        let storage_encryption_config_yaml = r#"
+                key_provider: kms
+                key_id: alias/quickwit
+                endpoint: http://localhost:4566
+            "#;
+        let storage_encryption_config: StorageEncryptionConfig =
+            serde_yaml::from_str(storage_encryption_config_yaml).unwrap();
+        assert_eq!(
+            storage_encryption_config,
+            StorageEncryptionConfig::Kms {
+                key_id: "alias/quickwit".to_string(),
+                region: None,
+                endpoint: Some("http://localhost:4566".to_string()),
+            }
+        );
+        storage_encryption_config.validate().unwrap();
+
+        let storage_encryption_config = StorageEncryptionConfig::Kms {
+            key_id: "".to_string(),
+            region: None,
+            endpoint: None,
+        };
+        storage_encryption_config.validate().unwrap_err();
+    }
+
+    #[test]
+    fn test_storage_configs_hedged_reads_serde() {
+        let storage_configs_yaml = r#"
+                s3:
+                    hedged_reads:
+                        latency_percentile: 90
+                google:
+                    hedged_reads: {}
+            "#;
+        let storage_configs: StorageConfigs = serde_yaml::from_str(storage_configs_yaml).unwrap();
+        storage_configs.validate().unwrap();
+
+        let s3_hedged_reads_config = storage_configs.find_s3().unwrap().hedged_reads.unwrap();
+        assert_eq!(s3_hedged_reads_config.latency_percentile, 90);
+        assert_eq!(
+            s3_hedged_reads_config.min_delay(),
+            Duration::from_millis(20)
+        );
+        assert_eq!(s3_hedged_reads_config.max_hedged_requests_percent, 5);
+
+        let google_hedged_reads_config = storage_configs.find_google().unwrap().hedged_reads;
+        assert_eq!(
+            google_hedged_reads_config,
+            Some(HedgedReadsConfig::default())
+        );
+
+        let storage_configs_yaml = r#"
+                azure:
+                    hedged_reads:
+                        latency_percentile: 100
+            "#;
+        let storage_configs: StorageConfigs = serde_yaml::from_str(storage_configs_yaml).unwrap();
+        storage_configs.validate().unwrap_err();
+    }
 }
@@ -22,18 +22,23 @@ use std::num::NonZeroU32;
 
 use anyhow::{bail, Context};
 use fnv::FnvHashSet;
+use quickwit_common::shared_consts::{NESTED_FIELD_NAME, NESTED_FIELD_ROOT_VALUE};
 use quickwit_common::PathHasher;
-use quickwit_query::create_default_quickwit_tokenizer_manager;
-use quickwit_query::query_ast::QueryAst;
+use quickwit_query::query_ast::{KnnQuery, QueryAst, QueryAstVisitor};
 use quickwit_query::tokenizers::TokenizerManager;
+use quickwit_query::{create_default_quickwit_tokenizer_manager, InvalidQuery};
 use serde::{Deserialize, Serialize};
 use serde_json::{self, Value as JsonValue};
 use tantivy::query::Query;
 use tantivy::schema::{
-    Field, FieldType, FieldValue, OwnedValue as TantivyValue, Schema, INDEXED, STORED,
+    Field, FieldType, FieldValue, OwnedValue as TantivyValue, Schema, INDEXED, STORED, STRING,
 };
 use tantivy::TantivyDocument as Document;
 
+use super::dynamic_template::{
+    apply_dynamic_templates, build_dynamic_templates, dynamic_template_field_name,
+    resolve_dynamic_template_fast_field, resolve_dynamic_templates, DynamicTemplateMapping,
+};
 use super::field_mapping_entry::RAW_TOKENIZER_NAME;
 use super::DefaultDocMapperBuilder;
 use crate::default_doc_mapper::mapping_tree::{build_mapping_tree, MappingNode};
@@ -41,7 +46,7 @@ use crate::default_doc_mapper::FieldMappingType;
 pub use crate::default_doc_mapper::QuickwitJsonOptions;
 use crate::doc_mapper::{JsonObject, Partition};
 use crate::query_builder::build_query;
-use crate::routing_expression::RoutingExpr;
+use crate::routing_expression::{DerivedTagField, RoutingExpr};
 use crate::{
     Cardinality, DocMapper, DocParsingError, Mode, QueryParserError, TokenizerEntry, WarmupInfo,
     DYNAMIC_FIELD_NAME, FIELD_PRESENCE_FIELD_NAME, SOURCE_FIELD_NAME,
@@ -67,6 +72,12 @@ pub struct DefaultDocMapper {
     /// This field is only valid when using the schema associated with the default
     /// doc mapper, and therefore cannot be used in the `query` method.
     dynamic_field: Option<Field>,
+    /// Dynamic templates, along with the fields in which the dynamically mapped fields matching
+    /// them are stored.
+    dynamic_templates: Vec<DynamicTemplateMapping>,
+    /// Field holding the path of the nested field of nested documents. It only exists if the
+    /// field mappings contain nested fields.
+    nested_field: Option<Field>,
     /// Default list of field names used for search.
     default_search_field_names: Vec<String>,
     /// Timestamp field name.
@@ -110,6 +121,40 @@ impl DefaultDocMapper {
     pub fn default_max_num_partitions() -> NonZeroU32 {
         NonZeroU32::new(200).unwrap()
     }
+
+    fn populate_field_presence(&self, document: &mut Document) {
+        // The capacity is inexact here.
+        let mut field_presence_hashes: FnvHashSet<u64> =
+            FnvHashSet::with_capacity_and_hasher(document.field_values().len(), Default::default());
+        for FieldValue { field, value } in document.field_values() {
+            let field_entry = self.schema.get_field_entry(*field);
+            if !field_entry.is_indexed() || field_entry.is_fast() {
+                // We are using an tantivy's ExistsQuery for fast fields.
+                continue;
+            }
+            let mut path_hasher: PathHasher = PathHasher::default();
+            path_hasher.append(&field.field_id().to_le_bytes()[..]);
+            if let TantivyValue::Object(json_obj) = value {
+                let is_expand_dots_enabled: bool =
+                    if let FieldType::JsonObject(json_options) = field_entry.field_type() {
+                        json_options.is_expand_dots_enabled()
+                    } else {
+                        false
+                    };
+                populate_field_presence_for_json_obj(
+                    json_obj,
+                    path_hasher,
+                    is_expand_dots_enabled,
+                    &mut field_presence_hashes,
+                );
+            } else {
+                field_presence_hashes.insert(path_hasher.finish());
+            }
+        }
+        for field_presence_hash in field_presence_hashes {
+            document.add_field_value(FIELD_PRESENCE_FIELD, field_presence_hash);
+        }
+    }
 }
 
 fn validate_timestamp_field(
@@ -156,12 +201,41 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
 
         // Adding regular fields.
         let field_mappings = build_mapping_tree(&builder.field_mappings, &mut schema_builder)?;
+        let nested_field_paths = field_mappings.nested_field_paths();
+        let nested_field = if nested_field_paths.is_empty() {
+            None
+        } else {
+            Some(schema_builder.add_text_field(NESTED_FIELD_NAME, STRING))
+        };
         let source_field = if builder.store_source {
             Some(schema_builder.add_json_field(SOURCE_FIELD_NAME, STORED))
         } else {
             None
         };
 
+        if !builder.dynamic_templates.is_empty() && dynamic_field.is_none() {
+            bail!(
+                "`dynamic_templates` is only allowed with mode=dynamic. (here mode=`{:?}`)",
+                builder.mode.mode_type()
+            );
+        }
+        for dynamic_template in &builder.dynamic_templates {
+            let template_field_name = dynamic_template_field_name(&dynamic_template.name);
+            if builder.field_mappings.iter().any(|field_mapping| {
+                field_mapping.name == template_field_name
+                    || field_mapping
+                        .name
+                        .starts_with(&format!("{template_field_name}."))
+            }) {
+                bail!(
+                    "field name `{template_field_name}` is reserved for dynamic template `{}`",
+                    dynamic_template.name
+                );
+            }
+        }
+        let dynamic_templates =
+            build_dynamic_templates(&builder.dynamic_templates, &mut schema_builder)?;
+
         if let Some(timestamp_field_path) = builder.timestamp_field.as_ref() {
             validate_timestamp_field(timestamp_field_path, &field_mappings)?;
         };
@@ -201,7 +275,7 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
                 .config
                 .filters
                 .iter()
-                .any(|filter| matches!(filter, crate::TokenFilterType::LowerCaser));
+                .any(|filter| filter.does_lowercasing());
             tokenizer_manager.register(&tokenizer_config_entry.name, tokenizer, does_lowercasing);
             custom_tokenizer_names.insert(&tokenizer_config_entry.name);
         }
@@ -232,6 +306,14 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
         let mut tag_field_names: BTreeSet<String> = builder.tag_fields.iter().cloned().collect();
         for tag_field_name in &builder.tag_fields {
             validate_tag(tag_field_name, &schema)?;
+            if let Some(nested_field_path) =
+                find_nested_field_path(tag_field_name, &nested_field_paths)
+            {
+                bail!(
+                    "tag field `{tag_field_name}` cannot belong to nested field \
+                     `{nested_field_path}`"
+                );
+            }
         }
 
         let partition_key_expr: &str = builder.partition_key.as_deref().unwrap_or("");
@@ -241,7 +323,9 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
 
         // If valid, partition key fields should be considered as tags.
         for partition_key in partition_key.field_names() {
-            if validate_tag(&partition_key, &schema).is_ok() {
+            if validate_tag(&partition_key, &schema).is_ok()
+                && find_nested_field_path(&partition_key, &nested_field_paths).is_none()
+            {
                 tag_field_names.insert(partition_key);
             }
         }
@@ -252,6 +336,8 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             index_field_presence: builder.index_field_presence,
             source_field,
             dynamic_field,
+            dynamic_templates,
+            nested_field,
             default_search_field_names,
             timestamp_field_name: builder.timestamp_field,
             field_mappings,
@@ -266,6 +352,22 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
     }
 }
 
+/// Returns the path of the nested field a given field belongs to, if any.
+fn find_nested_field_path<'a>(
+    field_name: &str,
+    nested_field_paths: &'a [String],
+) -> Option<&'a str> {
+    nested_field_paths
+        .iter()
+        .find(|nested_field_path| {
+            field_name
+                .strip_prefix(nested_field_path.as_str())
+                .map(|field_sub_path| field_sub_path.starts_with('.'))
+                .unwrap_or(false)
+        })
+        .map(String::as_str)
+}
+
 /// Checks that a given field name is a valid candidate for a tag.
 ///
 /// The conditions are:
@@ -364,6 +466,11 @@ impl From<DefaultDocMapper> for DefaultDocMapperBuilder {
             tag_fields: default_doc_mapper.tag_field_names.into_iter().collect(),
             default_search_fields: default_doc_mapper.default_search_field_names,
             mode: default_doc_mapper.mode,
+            dynamic_templates: default_doc_mapper
+                .dynamic_templates
+                .iter()
+                .map(|dynamic_template| dynamic_template.template().clone())
+                .collect(),
             partition_key: partition_key_opt,
             max_num_partitions: default_doc_mapper.max_num_partitions,
             tokenizers: default_doc_mapper.tokenizer_entries,
@@ -386,6 +493,40 @@ impl std::fmt::Debug for DefaultDocMapper {
     }
 }
 
+/// Checks that the knn queries of a query target dense vector fields and that their query vector
+/// has the dimension of the field.
+struct KnnQueryValidator<'a> {
+    field_mappings: &'a MappingNode,
+}
+
+impl<'a, 'b> QueryAstVisitor<'a> for KnnQueryValidator<'b> {
+    type Err = InvalidQuery;
+
+    fn visit_knn(&mut self, knn_query: &'a KnnQuery) -> Result<(), InvalidQuery> {
+        let Some(FieldMappingType::DenseVector(dense_vector_options)) = self
+            .field_mappings
+            .find_field_mapping_type(&knn_query.field)
+        else {
+            return Err(InvalidQuery::SchemaError(format!(
+                "field `{}` is not a dense vector field",
+                knn_query.field
+            )));
+        };
+        if knn_query.query_vector.len() != dense_vector_options.dimension {
+            return Err(InvalidQuery::SchemaError(format!(
+                "query vector has dimension {}, but field `{}` has dimension {}",
+                knn_query.query_vector.len(),
+                knn_query.field,
+                dense_vector_options.dimension
+            )));
+        }
+        for ast in &knn_query.filter {
+            self.visit(ast)?;
+        }
+        Ok(())
+    }
+}
+
 fn extract_single_obj(
     doc: &mut BTreeMap<String, Vec<TantivyValue>>,
     key: &str,
@@ -414,6 +555,23 @@ fn extract_single_obj(
     }
 }
 
+/// Recursively merges the entries of `other_json_obj` into `json_obj`.
+fn merge_json_objs(
+    json_obj: &mut serde_json::Map<String, JsonValue>,
+    other_json_obj: serde_json::Map<String, JsonValue>,
+) {
+    for (key, other_val) in other_json_obj {
+        match (json_obj.get_mut(&key), other_val) {
+            (Some(JsonValue::Object(child_json_obj)), JsonValue::Object(other_child_json_obj)) => {
+                merge_json_objs(child_json_obj, other_child_json_obj);
+            }
+            (_, other_val) => {
+                json_obj.insert(key, other_val);
+            }
+        }
+    }
+}
+
 // TODO: Formatting according to mapper if applicable
 fn tantivy_value_to_json(val: TantivyValue) -> JsonValue {
     match val {
@@ -508,11 +666,23 @@ impl DocMapper for DefaultDocMapper {
         &self,
         json_obj: JsonObject,
     ) -> Result<(Partition, Document), DocParsingError> {
+        let (partition, mut documents) = self.doc_block_from_json_obj(json_obj)?;
+        let document = documents
+            .pop()
+            .expect("a document block should end with its root document");
+        Ok((partition, document))
+    }
+
+    fn doc_block_from_json_obj(
+        &self,
+        json_obj: JsonObject,
+    ) -> Result<(Partition, Vec<Document>), DocParsingError> {
         let partition: Partition = self.partition_key.eval_hash(&json_obj);
 
         let mut dynamic_json_obj = serde_json::Map::default();
         let mut field_path = Vec::new();
         let mut document = Document::default();
+        let mut nested_docs = Vec::new();
 
         if let Some(source_field) = self.source_field {
             document.add_object(
@@ -532,8 +702,24 @@ impl DocMapper for DefaultDocMapper {
             &mut document,
             &mut field_path,
             &mut dynamic_json_obj,
+            &mut nested_docs,
         )?;
 
+        let template_json_objs =
+            apply_dynamic_templates(&mut dynamic_json_obj, &self.dynamic_templates);
+        for (dynamic_template, template_json_obj) in
+            self.dynamic_templates.iter().zip(template_json_objs)
+        {
+            if !template_json_obj.is_empty() {
+                document.add_object(
+                    dynamic_template.field(),
+                    template_json_obj
+                        .into_iter()
+                        .map(|(key, val)| (key, TantivyValue::from(val)))
+                        .collect(),
+                );
+            }
+        }
         if let Some(dynamic_field) = self.dynamic_field {
             if !dynamic_json_obj.is_empty() {
                 document.add_object(
@@ -546,45 +732,27 @@ impl DocMapper for DefaultDocMapper {
             }
         }
 
-        // The capacity is inexact here.
-
         if self.index_field_presence {
-            let mut field_presence_hashes: FnvHashSet<u64> = FnvHashSet::with_capacity_and_hasher(
-                document.field_values().len(),
-                Default::default(),
-            );
-            for FieldValue { field, value } in document.field_values() {
-                let field_entry = self.schema.get_field_entry(*field);
-                if !field_entry.is_indexed() || field_entry.is_fast() {
-                    // We are using an tantivy's ExistsQuery for fast fields.
-                    continue;
-                }
-                let mut path_hasher: PathHasher = PathHasher::default();
-                path_hasher.append(&field.field_id().to_le_bytes()[..]);
-                if let TantivyValue::Object(json_obj) = value {
-                    let is_expand_dots_enabled: bool =
-                        if let FieldType::JsonObject(json_options) = field_entry.field_type() {
-                            json_options.is_expand_dots_enabled()
-                        } else {
-                            false
-                        };
-                    populate_field_presence_for_json_obj(
-                        json_obj,
-                        path_hasher,
-                        is_expand_dots_enabled,
-                        &mut field_presence_hashes,
-                    );
-                } else {
-                    field_presence_hashes.insert(path_hasher.finish());
+            self.populate_field_presence(&mut document);
+        }
+        self.check_missing_required_fields(&document)?;
+
+        let mut documents = Vec::with_capacity(nested_docs.len() + 1);
+
+        if let Some(nested_field) = self.nested_field {
+            document.add_text(nested_field, NESTED_FIELD_ROOT_VALUE);
+
+            for (nested_field_path, mut nested_doc) in nested_docs {
+                nested_doc.add_text(nested_field, nested_field_path);
+
+                if self.index_field_presence {
+                    self.populate_field_presence(&mut nested_doc);
                 }
-            }
-            for field_presence_hash in field_presence_hashes {
-                document.add_field_value(FIELD_PRESENCE_FIELD, field_presence_hash);
+                documents.push(nested_doc);
             }
         }
-
-        self.check_missing_required_fields(&document)?;
-        Ok((partition, document))
+        documents.push(document);
+        Ok((partition, documents))
     }
 
     fn doc_to_json(
@@ -593,6 +761,13 @@ impl DocMapper for DefaultDocMapper {
     ) -> anyhow::Result<serde_json::Map<String, JsonValue>> {
         let mut doc_json =
             extract_single_obj(&mut named_doc, DYNAMIC_FIELD_NAME)?.unwrap_or_default();
+        for dynamic_template in &self.dynamic_templates {
+            if let Some(template_json_obj) =
+                extract_single_obj(&mut named_doc, &dynamic_template.field_name())?
+            {
+                merge_json_objs(&mut doc_json, template_json_obj);
+            }
+        }
         let mut field_path: Vec<&str> = Vec::new();
         self.field_mappings
             .populate_json(&mut named_doc, &mut field_path, &mut doc_json);
@@ -613,6 +788,25 @@ impl DocMapper for DefaultDocMapper {
         query_ast: &QueryAst,
         with_validation: bool,
     ) -> Result<(Box<dyn Query>, WarmupInfo), QueryParserError> {
+        KnnQueryValidator {
+            field_mappings: &self.field_mappings,
+        }
+        .visit(query_ast)?;
+
+        if !self.dynamic_templates.is_empty() {
+            let query_ast = resolve_dynamic_templates(
+                query_ast.clone(),
+                &split_schema,
+                &self.dynamic_templates,
+            );
+            return build_query(
+                &query_ast,
+                split_schema,
+                self.tokenizer_manager(),
+                &self.default_search_field_names[..],
+                with_validation,
+            );
+        }
         build_query(
             query_ast,
             split_schema,
@@ -622,6 +816,13 @@ impl DocMapper for DefaultDocMapper {
         )
     }
 
+    fn resolve_fast_field_name(&self, split_schema: &Schema, field_name: &str) -> Option<String> {
+        if self.dynamic_templates.is_empty() {
+            return None;
+        }
+        resolve_dynamic_template_fast_field(field_name, split_schema, &self.dynamic_templates)
+    }
+
     fn default_search_fields(&self) -> &[String] {
         &self.default_search_field_names
     }
@@ -638,6 +839,17 @@ impl DocMapper for DefaultDocMapper {
         self.tag_field_names.clone()
     }
 
+    fn derived_tag_fields(&self) -> Vec<DerivedTagField> {
+        self.partition_key
+            .derived_tag_fields()
+            .into_iter()
+            .filter(|derived_tag_field| {
+                self.tag_field_names
+                    .contains(derived_tag_field.field_name())
+            })
+            .collect()
+    }
+
     fn max_num_partitions(&self) -> NonZeroU32 {
         self.max_num_partitions
     }
@@ -651,15 +863,18 @@ impl DocMapper for DefaultDocMapper {
 mod tests {
     use std::collections::{HashMap, HashSet};
 
+    use quickwit_common::shared_consts::{NESTED_FIELD_NAME, NESTED_FIELD_ROOT_VALUE};
     use quickwit_common::PathHasher;
-    use quickwit_query::query_ast::query_ast_from_user_text;
+    use quickwit_query::query_ast::{query_ast_from_user_text, QueryAst};
+    use quickwit_query::InvalidQuery;
     use serde_json::{self, json, Value as JsonValue};
     use tantivy::schema::{FieldType, IndexRecordOption, OwnedValue as TantivyValue, Type, Value};
 
-    use super::DefaultDocMapper;
+    use super::{tantivy_value_to_json, DefaultDocMapper};
     use crate::default_doc_mapper::field_mapping_entry::DEFAULT_TOKENIZER_NAME;
+    use crate::default_doc_mapper::FieldMappingType;
     use crate::{
-        DefaultDocMapperBuilder, DocMapper, DocParsingError, DYNAMIC_FIELD_NAME,
+        DefaultDocMapperBuilder, DocMapper, DocParsingError, QueryParserError, DYNAMIC_FIELD_NAME,
         FIELD_PRESENCE_FIELD_NAME, SOURCE_FIELD_NAME,
     };
 
@@ -1463,6 +1678,76 @@ mod tests {
         default_doc_mapper.default_search_field_names.is_empty();
     }
 
+    #[test]
+    fn test_dynamic_templates() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_value(json!({
+            "mode": "dynamic",
+            "dynamic_templates": [
+                {
+                    "name": "ids",
+                    "path_match": "*_id",
+                    "match_mapping_type": "string",
+                    "mapping": {"tokenizer": "raw", "fast": true}
+                }
+            ]
+        }))
+        .unwrap();
+        let schema = doc_mapper.schema();
+        assert_eq!(schema.num_fields(), 3);
+        let dynamic_field = schema.get_field(DYNAMIC_FIELD_NAME).unwrap();
+        let ids_field = schema.get_field("_dynamic_ids").unwrap();
+
+        let json_doc = json!({
+            "user": {"user_id": "Abc-123", "name": "Paul"},
+            "trace_id": 42
+        });
+        let (_, doc) = doc_mapper
+            .doc_from_json_obj(json_doc.as_object().unwrap().clone())
+            .unwrap();
+        let ids_json = tantivy_value_to_json(TantivyValue::from(
+            doc.get_first(ids_field).unwrap().as_value(),
+        ));
+        assert_eq!(ids_json, json!({"user": {"user_id": "Abc-123"}}));
+        let dynamic_json = tantivy_value_to_json(TantivyValue::from(
+            doc.get_first(dynamic_field).unwrap().as_value(),
+        ));
+        assert_eq!(
+            dynamic_json,
+            json!({"user": {"name": "Paul"}, "trace_id": 42})
+        );
+
+        let named_doc = schema.to_named_doc(&doc).0;
+        let doc_json = JsonValue::Object(doc_mapper.doc_to_json(named_doc).unwrap());
+        assert_eq!(doc_json, json_doc);
+
+        let serialized_doc_mapper = serde_json::to_value(&doc_mapper).unwrap();
+        assert_eq!(
+            serialized_doc_mapper["dynamic_templates"][0]["name"],
+            json!("ids")
+        );
+    }
+
+    #[test]
+    fn test_dynamic_templates_invalid() {
+        let error = serde_json::from_value::<DefaultDocMapper>(json!({
+            "mode": "lenient",
+            "dynamic_templates": [{"name": "ids", "mapping": {}}]
+        }))
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`dynamic_templates` is only allowed with mode=dynamic"));
+
+        let error = serde_json::from_value::<DefaultDocMapper>(json!({
+            "field_mappings": [{"name": "_dynamic_ids", "type": "text"}],
+            "dynamic_templates": [{"name": "ids", "mapping": {}}]
+        }))
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("is reserved for dynamic template"));
+    }
+
     #[test]
     fn test_strict_mode_simple() {
         let default_doc_mapper: DefaultDocMapper =
@@ -1827,6 +2112,367 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn test_concatenate_fields() {
+        let doc_mapper = serde_json::from_str::<DefaultDocMapper>(
+            r#"{
+            "default_search_fields": ["all"],
+            "field_mappings": [
+                {
+                    "name": "title",
+                    "type": "text"
+                },
+                {
+                    "name": "attributes",
+                    "type": "object",
+                    "field_mappings": [
+                        {
+                            "name": "server",
+                            "type": "text",
+                            "tokenizer": "raw"
+                        }
+                    ]
+                },
+                {
+                    "name": "status_codes",
+                    "type": "array<u64>"
+                },
+                {
+                    "name": "all",
+                    "type": "concatenate",
+                    "concatenate_fields": ["title", "attributes.server", "status_codes"]
+                }
+            ]
+        }"#,
+        )
+        .unwrap();
+        let schema = doc_mapper.schema();
+        let all_field = schema.get_field("all").unwrap();
+        let all_field_entry = schema.get_field_entry(all_field);
+        assert!(all_field_entry.is_indexed());
+        assert!(!all_field_entry.is_stored());
+
+        let (_, document) = doc_mapper
+            .doc_from_json_obj(
+                json!({
+                    "title": "Hello",
+                    "attributes": {"server": "srv-1"},
+                    "status_codes": [200, 404]
+                })
+                .as_object()
+                .unwrap()
+                .clone(),
+            )
+            .unwrap();
+        let mut concatenated_values: Vec<&str> = document
+            .get_all(all_field)
+            .map(|value| value.as_str().unwrap())
+            .collect();
+        concatenated_values.sort();
+        assert_eq!(concatenated_values, ["200", "404", "Hello", "srv-1"]);
+
+        let error = doc_mapper
+            .doc_from_json_obj(json!({"all": "foo"}).as_object().unwrap().clone())
+            .unwrap_err();
+        assert!(matches!(error, DocParsingError::ValueError(_, _)));
+
+        let error = serde_json::from_str::<DefaultDocMapper>(
+            r#"{
+            "field_mappings": [
+                {
+                    "name": "all",
+                    "type": "concatenate",
+                    "concatenate_fields": ["unknown"]
+                }
+            ]
+        }"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("concatenate field `all` references unknown field `unknown`"));
+    }
+
+    #[test]
+    fn test_dense_vector_field() {
+        let doc_mapper = serde_json::from_str::<DefaultDocMapper>(
+            r#"{
+            "field_mappings": [
+                {
+                    "name": "embedding",
+                    "type": "dense_vector",
+                    "dimension": 3,
+                    "quantization": "int8"
+                },
+                {
+                    "name": "blob",
+                    "type": "bytes",
+                    "fast": true
+                }
+            ]
+        }"#,
+        )
+        .unwrap();
+        let schema = doc_mapper.schema();
+        let embedding_field = schema.get_field("embedding").unwrap();
+        let embedding_field_entry = schema.get_field_entry(embedding_field);
+        assert_eq!(embedding_field_entry.field_type().value_type(), Type::Bytes);
+        assert!(embedding_field_entry.is_fast());
+        assert!(!embedding_field_entry.is_stored());
+
+        let (_, document) = doc_mapper
+            .doc_from_json_obj(
+                json!({"embedding": [0.5, -1.0, 0.0]})
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+            )
+            .unwrap();
+        let encoded_vector = document
+            .get_first(embedding_field)
+            .unwrap()
+            .as_bytes()
+            .unwrap();
+        // Header, scale, and one byte per component.
+        assert_eq!(encoded_vector.len(), 2 + 4 + 3);
+
+        for invalid_vector in [json!([0.5, -1.0]), json!(["0.5", -1.0, 0.0]), json!(0.5)] {
+            let error = doc_mapper
+                .doc_from_json_obj(
+                    json!({ "embedding": invalid_vector })
+                        .as_object()
+                        .unwrap()
+                        .clone(),
+                )
+                .unwrap_err();
+            assert!(matches!(error, DocParsingError::ValueError(_, _)));
+        }
+
+        let query_ast: QueryAst = serde_json::from_value(json!({
+            "type": "knn",
+            "field": "embedding",
+            "query_vector": [1.0, 0.0, 0.0],
+            "k": 5
+        }))
+        .unwrap();
+        let (_query, warmup_info) = doc_mapper
+            .query(doc_mapper.schema(), &query_ast, true)
+            .unwrap();
+        assert!(warmup_info.fast_field_names.contains("embedding"));
+
+        let query_ast: QueryAst = serde_json::from_value(json!({
+            "type": "knn",
+            "field": "embedding",
+            "query_vector": [1.0, 0.0],
+            "k": 5
+        }))
+        .unwrap();
+        let error = doc_mapper
+            .query(doc_mapper.schema(), &query_ast, true)
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("query vector has dimension 2, but field `embedding` has dimension 3"));
+
+        let query_ast: QueryAst = serde_json::from_value(json!({
+            "type": "knn",
+            "field": "blob",
+            "query_vector": [1.0, 0.0, 0.0],
+            "k": 5
+        }))
+        .unwrap();
+        let error = doc_mapper
+            .query(doc_mapper.schema(), &query_ast, true)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            QueryParserError::InvalidQuery(InvalidQuery::SchemaError(_))
+        ));
+
+        let error = serde_json::from_str::<DefaultDocMapper>(
+            r#"{
+            "field_mappings": [
+                {
+                    "name": "embedding",
+                    "type": "dense_vector",
+                    "dimension": 0
+                }
+            ]
+        }"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("dense_vector type must have a strictly positive dimension"));
+    }
+
+    #[test]
+    fn test_nested_field() {
+        let doc_mapper = serde_json::from_str::<DefaultDocMapper>(
+            r#"{
+            "field_mappings": [
+                {"name": "name", "type": "text", "tokenizer": "raw"},
+                {
+                    "name": "tags",
+                    "type": "nested",
+                    "field_mappings": [
+                        {"name": "k", "type": "text", "tokenizer": "raw"},
+                        {"name": "v", "type": "text", "tokenizer": "raw"}
+                    ]
+                }
+            ]
+        }"#,
+        )
+        .unwrap();
+        let schema = doc_mapper.schema();
+        let nested_field = schema.get_field(NESTED_FIELD_NAME).unwrap();
+        let name_field = schema.get_field("name").unwrap();
+        let key_field = schema.get_field("tags.k").unwrap();
+
+        let json_doc = json!({
+            "name": "doc",
+            "tags": [{"k": "env", "v": "prod"}, {"k": "team", "v": "x"}]
+        });
+        let (_, documents) = doc_mapper
+            .doc_block_from_json_obj(json_doc.as_object().unwrap().clone())
+            .unwrap();
+        assert_eq!(documents.len(), 3);
+
+        for (nested_doc, expected_key) in documents.iter().zip(["env", "team"]) {
+            assert_eq!(
+                nested_doc.get_first(nested_field).unwrap().as_str(),
+                Some("tags")
+            );
+            assert_eq!(
+                nested_doc.get_first(key_field).unwrap().as_str(),
+                Some(expected_key)
+            );
+            assert!(nested_doc.get_first(name_field).is_none());
+        }
+        let root_doc = &documents[2];
+        assert_eq!(
+            root_doc.get_first(nested_field).unwrap().as_str(),
+            Some(NESTED_FIELD_ROOT_VALUE)
+        );
+        assert!(root_doc.get_first(key_field).is_none());
+
+        let named_doc = schema.to_named_doc(root_doc).0;
+        let doc_json = JsonValue::Object(doc_mapper.doc_to_json(named_doc).unwrap());
+        assert_eq!(doc_json, json_doc);
+
+        let (_, root_doc) = doc_mapper
+            .doc_from_json_obj(json_doc.as_object().unwrap().clone())
+            .unwrap();
+        assert_eq!(
+            root_doc.get_first(name_field).unwrap().as_str(),
+            Some("doc")
+        );
+
+        let error = doc_mapper
+            .doc_block_from_json_obj(json!({"tags": ["env"]}).as_object().unwrap().clone())
+            .unwrap_err();
+        assert!(matches!(error, DocParsingError::ValueError(_, _)));
+
+        let error = serde_json::from_str::<DefaultDocMapper>(
+            r#"{
+            "field_mappings": [
+                {
+                    "name": "tags",
+                    "type": "nested",
+                    "field_mappings": [{"name": "k", "type": "text", "tokenizer": "raw"}]
+                }
+            ],
+            "tag_fields": ["tags.k"]
+        }"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("tag field `tags.k` cannot belong to nested field `tags`"));
+    }
+
+    #[test]
+    fn test_multi_fields() {
+        let doc_mapper = serde_json::from_str::<DefaultDocMapper>(
+            r#"{
+            "field_mappings": [
+                {
+                    "name": "message",
+                    "type": "text",
+                    "fields": [
+                        {
+                            "name": "raw",
+                            "type": "text",
+                            "tokenizer": "raw",
+                            "fast": true
+                        },
+                        {
+                            "name": "stem",
+                            "type": "text",
+                            "tokenizer": "en_stem"
+                        }
+                    ]
+                }
+            ]
+        }"#,
+        )
+        .unwrap();
+        let schema = doc_mapper.schema();
+        let message_field = schema.get_field("message").unwrap();
+        let message_raw_field = schema.get_field("message.raw").unwrap();
+        let message_stem_field = schema.get_field("message.stem").unwrap();
+
+        let message_raw_field_entry = schema.get_field_entry(message_raw_field);
+        assert!(message_raw_field_entry.is_fast());
+        assert!(!message_raw_field_entry.is_stored());
+
+        let (_, document) = doc_mapper
+            .doc_from_json_obj(
+                json!({"message": "Hello Worlds"})
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+            )
+            .unwrap();
+        for field in [message_field, message_raw_field, message_stem_field] {
+            assert_eq!(
+                document.get_first(field).unwrap().as_str(),
+                Some("Hello Worlds")
+            );
+        }
+        let FieldMappingType::Text(text_options, _) = doc_mapper
+            .field_mappings
+            .find_field_mapping_type("message.stem")
+            .unwrap()
+        else {
+            panic!("expected text field mapping");
+        };
+        assert_eq!(
+            text_options.indexing_options.unwrap().tokenizer.name(),
+            "en_stem"
+        );
+        assert!(doc_mapper
+            .field_mappings
+            .find_field_mapping_type("message.unknown")
+            .is_none());
+
+        let error = serde_json::from_str::<DefaultDocMapper>(
+            r#"{
+            "field_mappings": [
+                {
+                    "name": "message",
+                    "type": "text",
+                    "fields": [{"name": "length", "type": "u64"}]
+                }
+            ]
+        }"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("multi-field `length` must be of type `text`"));
+    }
+
     #[test]
     fn test_build_doc_mapper_with_custom_ngram_tokenizer() {
         let mapper = serde_json::from_str::<DefaultDocMapper>(
@@ -21,6 +21,7 @@ use std::num::NonZeroU32;
 
 use serde::{Deserialize, Serialize};
 
+use super::dynamic_template::DynamicTemplate;
 use super::tokenizer_entry::TokenizerEntry;
 use super::FieldMappingEntry;
 use crate::default_doc_mapper::QuickwitJsonOptions;
@@ -77,6 +78,11 @@ pub struct DefaultDocMapperBuilder {
     )]
     /// Defines how the unmapped fields should be handled.
     pub mode: Mode,
+    /// If mode is set to dynamic, rules overriding `dynamic_mapping` for the unmapped fields
+    /// matching them.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dynamic_templates: Vec<DynamicTemplate>,
     /// User-defined tokenizers.
     #[serde(default)]
     pub tokenizers: Vec<TokenizerEntry>,
@@ -0,0 +1,563 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use anyhow::bail;
+use once_cell::sync::Lazy;
+use quickwit_query::query_ast::{BoolQuery, QueryAst, TermSetQuery};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tantivy::schema::{Field, Schema, SchemaBuilder};
+
+use super::mapping_tree::get_or_insert_path;
+use super::QuickwitJsonOptions;
+use crate::DYNAMIC_FIELD_NAME;
+
+/// Type of the values matched by a dynamic template, as detected from the JSON documents.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DynamicMappingType {
+    /// JSON strings.
+    String,
+    /// JSON integers.
+    Long,
+    /// JSON floating point numbers.
+    Double,
+    /// JSON booleans.
+    Boolean,
+}
+
+impl DynamicMappingType {
+    fn detect(json_value: &JsonValue) -> Option<DynamicMappingType> {
+        match json_value {
+            JsonValue::String(_) => Some(DynamicMappingType::String),
+            JsonValue::Number(number) if number.is_f64() => Some(DynamicMappingType::Double),
+            JsonValue::Number(_) => Some(DynamicMappingType::Long),
+            JsonValue::Bool(_) => Some(DynamicMappingType::Boolean),
+            // The type of an array is the type of its first non-null element.
+            JsonValue::Array(json_values) => json_values
+                .iter()
+                .find(|json_value| !json_value.is_null())
+                .and_then(DynamicMappingType::detect),
+            JsonValue::Null | JsonValue::Object(_) => None,
+        }
+    }
+}
+
+/// Rule defining how the unmapped fields matching it should be indexed when the mode is
+/// `dynamic`.
+///
+/// Unmapped fields are checked against the dynamic templates in order, and are indexed with the
+/// mapping of the first template matching them. Fields matching no template are indexed according
+/// to `dynamic_mapping`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DynamicTemplate {
+    /// Name of the template.
+    pub name: String,
+    /// Glob pattern matched against the dot-separated path of the field, where `*` matches any
+    /// sequence of characters. If not set, the template matches all paths.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_match: Option<String>,
+    /// Type of the values matched by the template. If not set, the template matches all types.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub match_mapping_type: Option<DynamicMappingType>,
+    /// Options used to index the fields matching the template.
+    pub mapping: QuickwitJsonOptions,
+}
+
+/// A dynamic template along with the JSON field of the schema indexing the fields it matches.
+#[derive(Clone)]
+pub(crate) struct DynamicTemplateMapping {
+    template: DynamicTemplate,
+    path_regex_opt: Option<Regex>,
+    field: Field,
+}
+
+impl DynamicTemplateMapping {
+    pub(crate) fn template(&self) -> &DynamicTemplate {
+        &self.template
+    }
+
+    pub(crate) fn field(&self) -> Field {
+        self.field
+    }
+
+    /// Returns the name of the JSON field indexing the fields matching the template.
+    pub(crate) fn field_name(&self) -> String {
+        dynamic_template_field_name(&self.template.name)
+    }
+
+    fn matches_path(&self, field_path: &str) -> bool {
+        self.path_regex_opt
+            .as_ref()
+            .map(|path_regex| path_regex.is_match(field_path))
+            .unwrap_or(true)
+    }
+
+    fn matches(&self, field_path: &str, json_value: &JsonValue) -> bool {
+        if !self.matches_path(field_path) {
+            return false;
+        }
+        match self.template.match_mapping_type {
+            Some(mapping_type) => DynamicMappingType::detect(json_value) == Some(mapping_type),
+            None => DynamicMappingType::detect(json_value).is_some(),
+        }
+    }
+}
+
+/// Prefix of the names of the JSON fields created for the dynamic templates.
+pub const DYNAMIC_TEMPLATE_FIELD_PREFIX: &str = "_dynamic_";
+
+pub(crate) fn dynamic_template_field_name(template_name: &str) -> String {
+    format!("{DYNAMIC_TEMPLATE_FIELD_PREFIX}{template_name}")
+}
+
+fn path_glob_to_regex(path_glob: &str) -> anyhow::Result<Regex> {
+    let path_regex_str = regex::escape(path_glob).replace(r"\*", ".*");
+    Ok(Regex::new(&format!("^{path_regex_str}$"))?)
+}
+
+/// Validates the dynamic templates and adds their JSON fields to the schema.
+pub(crate) fn build_dynamic_templates(
+    dynamic_templates: &[DynamicTemplate],
+    schema_builder: &mut SchemaBuilder,
+) -> anyhow::Result<Vec<DynamicTemplateMapping>> {
+    static TEMPLATE_NAME_PTN: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"^[a-zA-Z0-9_\-]{1,64}$").unwrap());
+
+    let mut dynamic_template_mappings: Vec<DynamicTemplateMapping> =
+        Vec::with_capacity(dynamic_templates.len());
+    for dynamic_template in dynamic_templates {
+        if !TEMPLATE_NAME_PTN.is_match(&dynamic_template.name) {
+            bail!(
+                "dynamic template name `{}` is invalid. dynamic template names must only contain \
+                 ASCII letters, digits, hyphens `-` and underscores `_`, and must not be longer \
+                 than 64 characters",
+                dynamic_template.name
+            );
+        }
+        if dynamic_template_mappings
+            .iter()
+            .any(|mapping| mapping.template.name == dynamic_template.name)
+        {
+            bail!("duplicated dynamic template: `{}`", dynamic_template.name);
+        }
+        let path_regex_opt = dynamic_template
+            .path_match
+            .as_deref()
+            .map(path_glob_to_regex)
+            .transpose()?;
+        let field = schema_builder.add_json_field(
+            &dynamic_template_field_name(&dynamic_template.name),
+            dynamic_template.mapping.clone(),
+        );
+        let dynamic_template_mapping = DynamicTemplateMapping {
+            template: dynamic_template.clone(),
+            path_regex_opt,
+            field,
+        };
+        dynamic_template_mappings.push(dynamic_template_mapping);
+    }
+    Ok(dynamic_template_mappings)
+}
+
+/// Moves the values of `dynamic_json_obj` matching a dynamic template to the JSON object of the
+/// first template they match. Returns the JSON objects of the templates, in order.
+pub(crate) fn apply_dynamic_templates(
+    dynamic_json_obj: &mut serde_json::Map<String, JsonValue>,
+    dynamic_templates: &[DynamicTemplateMapping],
+) -> Vec<serde_json::Map<String, JsonValue>> {
+    let mut template_json_objs = vec![serde_json::Map::default(); dynamic_templates.len()];
+
+    if !dynamic_templates.is_empty() {
+        let json_obj = std::mem::take(dynamic_json_obj);
+        *dynamic_json_obj = route_json_obj(
+            json_obj,
+            &mut Vec::new(),
+            dynamic_templates,
+            &mut template_json_objs,
+        );
+    }
+    template_json_objs
+}
+
+fn route_json_obj(
+    json_obj: serde_json::Map<String, JsonValue>,
+    path: &mut Vec<String>,
+    dynamic_templates: &[DynamicTemplateMapping],
+    template_json_objs: &mut [serde_json::Map<String, JsonValue>],
+) -> serde_json::Map<String, JsonValue> {
+    let mut remaining_json_obj = serde_json::Map::default();
+
+    for (field_name, json_value) in json_obj {
+        match json_value {
+            JsonValue::Object(child_json_obj) if !child_json_obj.is_empty() => {
+                path.push(field_name);
+                let remaining_child_json_obj =
+                    route_json_obj(child_json_obj, path, dynamic_templates, template_json_objs);
+                let field_name = path.pop().expect("path should not be empty");
+
+                if !remaining_child_json_obj.is_empty() {
+                    remaining_json_obj
+                        .insert(field_name, JsonValue::Object(remaining_child_json_obj));
+                }
+            }
+            json_value => {
+                let field_path = if path.is_empty() {
+                    field_name.clone()
+                } else {
+                    format!("{}.{field_name}", path.join("."))
+                };
+                let template_ord_opt = dynamic_templates
+                    .iter()
+                    .position(|template| template.matches(&field_path, &json_value));
+
+                if let Some(template_ord) = template_ord_opt {
+                    get_or_insert_path(path, &mut template_json_objs[template_ord])
+                        .insert(field_name, json_value);
+                } else {
+                    remaining_json_obj.insert(field_name, json_value);
+                }
+            }
+        }
+    }
+    remaining_json_obj
+}
+
+/// Returns the paths, prefixed with the name of the JSON field of their template, under which
+/// the values of an unmapped field may have been indexed, or `None` if the field is not affected
+/// by the dynamic templates.
+///
+/// Since templates can match on the type of the values, a field may be spread across several
+/// templates as well as the dynamic field.
+fn dynamic_template_field_paths(
+    field_path: &str,
+    schema: &Schema,
+    dynamic_templates: &[DynamicTemplateMapping],
+) -> Option<Vec<String>> {
+    if schema.find_field(field_path).is_some() {
+        return None;
+    }
+    let mut field_paths = Vec::new();
+
+    for dynamic_template in dynamic_templates {
+        if !dynamic_template.matches_path(field_path) {
+            continue;
+        }
+        let template_field_name = dynamic_template.field_name();
+
+        // Splits created before the template was added do not have its field.
+        if schema.get_field(&template_field_name).is_err() {
+            continue;
+        }
+        field_paths.push(format!("{template_field_name}.{field_path}"));
+
+        if dynamic_template.template.match_mapping_type.is_none() {
+            // The template captures all the values of the field.
+            return Some(field_paths);
+        }
+    }
+    if field_paths.is_empty() {
+        return None;
+    }
+    if schema.get_field(DYNAMIC_FIELD_NAME).is_ok() {
+        field_paths.push(field_path.to_string());
+    }
+    Some(field_paths)
+}
+
+/// Returns the path, prefixed with the name of the JSON field of its template, of the fast field
+/// to sort or aggregate on for an unmapped field, or `None` if the field is not affected by the
+/// dynamic templates.
+///
+/// Sorting and aggregating target a single column, so a field spread across several templates, or
+/// across a template and the dynamic field, resolves to the first template capturing it.
+pub(crate) fn resolve_dynamic_template_fast_field(
+    field_path: &str,
+    schema: &Schema,
+    dynamic_templates: &[DynamicTemplateMapping],
+) -> Option<String> {
+    dynamic_template_field_paths(field_path, schema, dynamic_templates)?
+        .into_iter()
+        .next()
+}
+
+fn resolve_field_query<Q>(
+    mut query: Q,
+    field: fn(&mut Q) -> &mut String,
+    schema: &Schema,
+    dynamic_templates: &[DynamicTemplateMapping],
+) -> QueryAst
+where
+    Q: Clone + Into<QueryAst>,
+{
+    let Some(field_paths) =
+        dynamic_template_field_paths(field(&mut query), schema, dynamic_templates)
+    else {
+        return query.into();
+    };
+    let mut clauses: Vec<QueryAst> = field_paths
+        .into_iter()
+        .map(|field_path| {
+            let mut clause = query.clone();
+            *field(&mut clause) = field_path;
+            clause.into()
+        })
+        .collect();
+    if clauses.len() == 1 {
+        clauses.pop().unwrap()
+    } else {
+        BoolQuery {
+            should: clauses,
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// Rewrites the clauses of a query targeting unmapped fields so that they target the JSON fields
+/// of the dynamic templates.
+pub(crate) fn resolve_dynamic_templates(
+    query_ast: QueryAst,
+    schema: &Schema,
+    dynamic_templates: &[DynamicTemplateMapping],
+) -> QueryAst {
+    let resolve = |query_ast| resolve_dynamic_templates(query_ast, schema, dynamic_templates);
+    let resolve_all = |query_asts: Vec<QueryAst>| -> Vec<QueryAst> {
+        query_asts.into_iter().map(resolve).collect()
+    };
+
+    match query_ast {
+        QueryAst::Bool(bool_query) => BoolQuery {
+            must: resolve_all(bool_query.must),
+            must_not: resolve_all(bool_query.must_not),
+            should: resolve_all(bool_query.should),
+            filter: resolve_all(bool_query.filter),
+        }
+        .into(),
+        QueryAst::Term(term_query) => resolve_field_query(
+            term_query,
+            |query| &mut query.field,
+            schema,
+            dynamic_templates,
+        ),
+        QueryAst::TermSet(term_set_query) => {
+            let mut terms_per_field = HashMap::with_capacity(term_set_query.terms_per_field.len());
+
+            for (field, terms) in term_set_query.terms_per_field {
+                let field_paths = dynamic_template_field_paths(&field, schema, dynamic_templates)
+                    .unwrap_or_else(|| vec![field]);
+                for field_path in field_paths {
+                    terms_per_field
+                        .entry(field_path)
+                        .or_insert_with(Default::default)
+                        .extend(terms.iter().cloned());
+                }
+            }
+            TermSetQuery { terms_per_field }.into()
+        }
+        QueryAst::FieldPresence(field_presence_query) => resolve_field_query(
+            field_presence_query,
+            |query| &mut query.field,
+            schema,
+            dynamic_templates,
+        ),
+        QueryAst::FullText(full_text_query) => resolve_field_query(
+            full_text_query,
+            |query| &mut query.field,
+            schema,
+            dynamic_templates,
+        ),
+        QueryAst::PhrasePrefix(phrase_prefix_query) => resolve_field_query(
+            phrase_prefix_query,
+            |query| &mut query.field,
+            schema,
+            dynamic_templates,
+        ),
+        QueryAst::Range(range_query) => resolve_field_query(
+            range_query,
+            |query| &mut query.field,
+            schema,
+            dynamic_templates,
+        ),
+        QueryAst::Wildcard(wildcard_query) => resolve_field_query(
+            wildcard_query,
+            |query| &mut query.field,
+            schema,
+            dynamic_templates,
+        ),
+        QueryAst::Knn(mut knn_query) => {
+            knn_query.filter = resolve_all(knn_query.filter);
+            knn_query.into()
+        }
+        QueryAst::Boost { underlying, boost } => QueryAst::Boost {
+            underlying: Box::new(resolve(*underlying)),
+            boost,
+        },
+        // Nested fields are always mapped.
+        QueryAst::Nested(_) | QueryAst::UserInput(_) | QueryAst::MatchAll | QueryAst::MatchNone => {
+            query_ast
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_query::query_ast::query_ast_from_user_text;
+    use serde_json::json;
+
+    use super::*;
+
+    fn dynamic_templates_for_test() -> (Schema, Vec<DynamicTemplateMapping>) {
+        let dynamic_templates: Vec<DynamicTemplate> = serde_json::from_value(json!([
+            {
+                "name": "ids",
+                "path_match": "*_id",
+                "match_mapping_type": "string",
+                "mapping": {"tokenizer": "raw", "fast": true}
+            },
+            {
+                "name": "metrics",
+                "path_match": "metrics.*",
+                "mapping": {"indexed": false, "fast": true}
+            }
+        ]))
+        .unwrap();
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_json_field(DYNAMIC_FIELD_NAME, QuickwitJsonOptions::default());
+        let dynamic_template_mappings =
+            build_dynamic_templates(&dynamic_templates, &mut schema_builder).unwrap();
+        (schema_builder.build(), dynamic_template_mappings)
+    }
+
+    #[test]
+    fn test_build_dynamic_templates_invalid() {
+        for dynamic_templates in [
+            json!([{"name": "", "mapping": {}}]),
+            json!([{"name": "a.b", "mapping": {}}]),
+            json!([{"name": "ids", "mapping": {}}, {"name": "ids", "mapping": {}}]),
+        ] {
+            let dynamic_templates: Vec<DynamicTemplate> =
+                serde_json::from_value(dynamic_templates).unwrap();
+            build_dynamic_templates(&dynamic_templates, &mut Schema::builder()).unwrap_err();
+        }
+        serde_json::from_value::<DynamicTemplate>(
+            json!({"name": "ids", "match_mapping_type": "object", "mapping": {}}),
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn test_apply_dynamic_templates() {
+        let (_schema, dynamic_templates) = dynamic_templates_for_test();
+        let mut dynamic_json_obj = json!({
+            "user_id": "abc-123",
+            "trace_id": 42,
+            "message": "hello",
+            "metrics": {"cpu": 0.5, "labels": {"host_id": "h1"}},
+            "request": {"session_id": ["s1", "s2"], "empty": {}}
+        })
+        .as_object()
+        .unwrap()
+        .clone();
+        let template_json_objs = apply_dynamic_templates(&mut dynamic_json_obj, &dynamic_templates);
+        assert_eq!(
+            JsonValue::Object(dynamic_json_obj),
+            json!({
+                "trace_id": 42,
+                "message": "hello",
+                "request": {"empty": {}}
+            })
+        );
+        assert_eq!(
+            JsonValue::Object(template_json_objs[0].clone()),
+            json!({
+                "user_id": "abc-123",
+                "metrics": {"labels": {"host_id": "h1"}},
+                "request": {"session_id": ["s1", "s2"]}
+            })
+        );
+        assert_eq!(
+            JsonValue::Object(template_json_objs[1].clone()),
+            json!({"metrics": {"cpu": 0.5}})
+        );
+    }
+
+    #[test]
+    fn test_resolve_dynamic_templates() {
+        let (schema, dynamic_templates) = dynamic_templates_for_test();
+        let resolve = |user_text: &str| {
+            let query_ast = query_ast_from_user_text(user_text, None)
+                .parse_user_query(&[])
+                .unwrap();
+            resolve_dynamic_templates(query_ast, &schema, &dynamic_templates)
+        };
+        assert_eq!(
+            resolve("metrics.cpu:0.5"),
+            query_ast_from_user_text("_dynamic_metrics.metrics.cpu:0.5", None)
+                .parse_user_query(&[])
+                .unwrap()
+        );
+        assert_eq!(
+            resolve("message:hello"),
+            query_ast_from_user_text("message:hello", None)
+                .parse_user_query(&[])
+                .unwrap()
+        );
+        // `user_id` values which are not strings are indexed in the dynamic field.
+        let QueryAst::Bool(bool_query) = resolve("user_id:abc") else {
+            panic!("expected bool query");
+        };
+        assert_eq!(bool_query.should.len(), 2);
+        assert_eq!(
+            bool_query.should[0],
+            query_ast_from_user_text("_dynamic_ids.user_id:abc", None)
+                .parse_user_query(&[])
+                .unwrap()
+        );
+        assert_eq!(
+            bool_query.should[1],
+            query_ast_from_user_text("user_id:abc", None)
+                .parse_user_query(&[])
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_resolve_dynamic_template_fast_field() {
+        let (schema, dynamic_templates) = dynamic_templates_for_test();
+        assert_eq!(
+            resolve_dynamic_template_fast_field("metrics.cpu", &schema, &dynamic_templates)
+                .as_deref(),
+            Some("_dynamic_metrics.metrics.cpu")
+        );
+        assert_eq!(
+            resolve_dynamic_template_fast_field("user_id", &schema, &dynamic_templates).as_deref(),
+            Some("_dynamic_ids.user_id")
+        );
+        assert!(
+            resolve_dynamic_template_fast_field("message", &schema, &dynamic_templates).is_none()
+        );
+    }
+}
@@ -18,15 +18,17 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::convert::TryFrom;
 
 use anyhow::bail;
 use base64::prelude::{Engine, BASE64_STANDARD};
+use quickwit_query::dense_vector::{VectorMetric, VectorQuantization};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use tantivy::schema::{
-    IndexRecordOption, JsonObjectOptions, OwnedValue as TantivyValue, TextFieldIndexing,
-    TextOptions, Type,
+    BytesOptions, IndexRecordOption, JsonObjectOptions, OwnedValue as TantivyValue,
+    TextFieldIndexing, TextOptions, Type,
 };
 
 use super::date_time_type::QuickwitDateTimeOptions;
@@ -432,6 +434,11 @@ pub struct QuickwitTextOptions {
     pub stored: bool,
     #[serde(default)]
     pub fast: FastFieldOptions,
+    /// Additional text mappings of the same value, addressable as `<field>.<sub_field>`.
+    #[schema(value_type = Vec<FieldMappingEntryForSerialization>)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldMappingEntry>,
 }
 
 #[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
@@ -497,6 +504,7 @@ impl Default for QuickwitTextOptions {
             indexing_options: Some(TextIndexingOptions::default()),
             stored: true,
             fast: FastFieldOptions::default(),
+            fields: Vec::new(),
         }
     }
 }
@@ -525,6 +533,67 @@ impl From<QuickwitTextOptions> for TextOptions {
     }
 }
 
+/// Options associated to a concatenate field. A concatenate field is a synthetic text field
+/// indexing the values of several other fields. It is neither stored nor fast.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct QuickwitConcatenateOptions {
+    #[schema(value_type = String)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Paths of the fields whose values are indexed into the concatenate field. Text, numeric,
+    /// bool, and IP address fields can be concatenated.
+    pub concatenate_fields: Vec<String>,
+    #[schema(value_type = String)]
+    #[serde(default)]
+    pub tokenizer: QuickwitTextTokenizer,
+    #[schema(value_type = IndexRecordOptionSchema)]
+    #[serde(default = "default_record_option")]
+    pub record: IndexRecordOption,
+    #[serde(default)]
+    pub fieldnorms: bool,
+}
+
+fn default_record_option() -> IndexRecordOption {
+    IndexRecordOption::Basic
+}
+
+impl From<QuickwitConcatenateOptions> for TextOptions {
+    fn from(quickwit_concatenate_options: QuickwitConcatenateOptions) -> Self {
+        let text_field_indexing = TextFieldIndexing::default()
+            .set_index_option(quickwit_concatenate_options.record)
+            .set_fieldnorms(quickwit_concatenate_options.fieldnorms)
+            .set_tokenizer(quickwit_concatenate_options.tokenizer.name());
+        TextOptions::default().set_indexing_options(text_field_indexing)
+    }
+}
+
+/// Options associated to a dense vector field. Dense vectors are stored in a bytes fast field
+/// and can be searched with `knn` queries. They are not stored in the doc store.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct QuickwitDenseVectorOptions {
+    #[schema(value_type = String)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Number of components of the vectors.
+    pub dimension: usize,
+    #[schema(value_type = String)]
+    #[serde(default)]
+    pub metric: VectorMetric,
+    #[schema(value_type = String)]
+    #[serde(default)]
+    pub quantization: VectorQuantization,
+}
+
+impl From<QuickwitDenseVectorOptions> for BytesOptions {
+    fn from(_quickwit_dense_vector_options: QuickwitDenseVectorOptions) -> Self {
+        BytesOptions::default().set_fast()
+    }
+}
+
 #[allow(unused)]
 #[derive(utoipa::ToSchema)]
 pub enum IndexRecordOptionSchema {
@@ -635,6 +704,32 @@ impl From<QuickwitJsonOptions> for JsonObjectOptions {
     }
 }
 
+/// Nested objects are indexed as separate documents: they cannot contain nested fields themselves,
+/// nor concatenate fields, which are populated from the fields of the root document.
+fn validate_nested_field_mappings(field_mappings: &[FieldMappingEntry]) -> anyhow::Result<()> {
+    for field_mapping in field_mappings {
+        match &field_mapping.mapping_type {
+            FieldMappingType::Nested(_) => {
+                anyhow::bail!(
+                    "nested field `{}` cannot be declared within a nested field",
+                    field_mapping.name
+                );
+            }
+            FieldMappingType::Concatenate(_) => {
+                anyhow::bail!(
+                    "concatenate field `{}` cannot be declared within a nested field",
+                    field_mapping.name
+                );
+            }
+            FieldMappingType::Object(object_options) => {
+                validate_nested_field_mappings(&object_options.field_mappings)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
 fn deserialize_mapping_type(
     quickwit_field_type: QuickwitFieldType,
     json: JsonValue,
@@ -649,10 +744,33 @@ fn deserialize_mapping_type(
             }
             return Ok(FieldMappingType::Object(object_options));
         }
+        QuickwitFieldType::Nested => {
+            let nested_options: QuickwitObjectOptions = serde_json::from_value(json)?;
+            if nested_options.field_mappings.is_empty() {
+                anyhow::bail!("nested type must have at least one field mapping");
+            }
+            validate_nested_field_mappings(&nested_options.field_mappings)?;
+            return Ok(FieldMappingType::Nested(nested_options));
+        }
+        QuickwitFieldType::Concatenate => {
+            let concatenate_options: QuickwitConcatenateOptions = serde_json::from_value(json)?;
+            if concatenate_options.concatenate_fields.is_empty() {
+                anyhow::bail!("concatenate type must have at least one field to concatenate");
+            }
+            return Ok(FieldMappingType::Concatenate(concatenate_options));
+        }
+        QuickwitFieldType::DenseVector => {
+            let dense_vector_options: QuickwitDenseVectorOptions = serde_json::from_value(json)?;
+            if dense_vector_options.dimension == 0 {
+                anyhow::bail!("dense_vector type must have a strictly positive dimension");
+            }
+            return Ok(FieldMappingType::DenseVector(dense_vector_options));
+        }
     };
     match typ {
         Type::Str => {
             let text_options: QuickwitTextOptions = serde_json::from_value(json)?;
+            validate_multi_fields(&text_options.fields)?;
             Ok(FieldMappingType::Text(text_options, cardinality))
         }
         Type::U64 => {
@@ -694,6 +812,33 @@ fn deserialize_mapping_type(
     }
 }
 
+/// Multi-fields are text fields without multi-fields of their own. Their cardinality is the one
+/// of their parent field.
+fn validate_multi_fields(multi_field_entries: &[FieldMappingEntry]) -> anyhow::Result<()> {
+    let mut multi_field_names = HashSet::new();
+
+    for multi_field_entry in multi_field_entries {
+        if !multi_field_names.insert(&multi_field_entry.name) {
+            bail!("duplicated multi-field `{}`", multi_field_entry.name);
+        }
+        match &multi_field_entry.mapping_type {
+            FieldMappingType::Text(text_options, Cardinality::SingleValue) => {
+                if !text_options.fields.is_empty() {
+                    bail!(
+                        "multi-field `{}` cannot have multi-fields",
+                        multi_field_entry.name
+                    );
+                }
+            }
+            _ => bail!(
+                "multi-field `{}` must be of type `text`",
+                multi_field_entry.name
+            ),
+        }
+    }
+    Ok(())
+}
+
 impl TryFrom<FieldMappingEntryForSerialization> for FieldMappingEntry {
     type Error = String;
 
@@ -741,7 +886,15 @@ fn typed_mapping_to_json_params(
         FieldMappingType::IpAddr(options, _) => serialize_to_map(&options),
         FieldMappingType::DateTime(date_time_options, _) => serialize_to_map(&date_time_options),
         FieldMappingType::Json(json_options, _) => serialize_to_map(&json_options),
-        FieldMappingType::Object(object_options) => serialize_to_map(&object_options),
+        FieldMappingType::Object(object_options) | FieldMappingType::Nested(object_options) => {
+            serialize_to_map(&object_options)
+        }
+        FieldMappingType::Concatenate(concatenate_options) => {
+            serialize_to_map(&concatenate_options)
+        }
+        FieldMappingType::DenseVector(dense_vector_options) => {
+            serialize_to_map(&dense_vector_options)
+        }
     }
     .unwrap()
 }
@@ -1077,6 +1230,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_deserialize_nested_mapping_entry() {
+        let mapping_entry = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+            {
+                "name": "tags",
+                "type": "nested",
+                "field_mappings": [
+                    {"name": "k", "type": "text", "tokenizer": "raw"},
+                    {"name": "v", "type": "text", "tokenizer": "raw"}
+                ]
+            }
+            "#,
+        )
+        .unwrap();
+        let FieldMappingType::Nested(options) = &mapping_entry.mapping_type else {
+            panic!("wrong property type");
+        };
+        assert_eq!(options.field_mappings.len(), 2);
+
+        let mapping_entry_json = serde_json::to_value(&mapping_entry).unwrap();
+        assert_eq!(mapping_entry_json["type"], "nested");
+
+        let error = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+            {
+                "name": "tags",
+                "type": "nested",
+                "field_mappings": [
+                    {
+                        "name": "inner",
+                        "type": "nested",
+                        "field_mappings": [{"name": "k", "type": "text"}]
+                    }
+                ]
+            }
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "error while parsing field `tags`: nested field `inner` cannot be declared within a \
+             nested field"
+        );
+    }
+
     #[test]
     fn test_deserialize_mapping_with_unknown_type() {
         let result = serde_json::from_str::<FieldMappingEntry>(
@@ -22,8 +22,9 @@ use tantivy::schema::Type;
 use super::date_time_type::QuickwitDateTimeOptions;
 use super::field_mapping_entry::QuickwitBoolOptions;
 use crate::default_doc_mapper::field_mapping_entry::{
-    QuickwitBytesOptions, QuickwitIpAddrOptions, QuickwitJsonOptions, QuickwitNumericOptions,
-    QuickwitObjectOptions, QuickwitTextOptions,
+    QuickwitBytesOptions, QuickwitConcatenateOptions, QuickwitDenseVectorOptions,
+    QuickwitIpAddrOptions, QuickwitJsonOptions, QuickwitNumericOptions, QuickwitObjectOptions,
+    QuickwitTextOptions,
 };
 use crate::Cardinality;
 
@@ -51,6 +52,12 @@ pub enum FieldMappingType {
     Json(QuickwitJsonOptions, Cardinality),
     /// Object mapping type configuration.
     Object(QuickwitObjectOptions),
+    /// Nested mapping type configuration.
+    Nested(QuickwitObjectOptions),
+    /// Concatenate field mapping type configuration.
+    Concatenate(QuickwitConcatenateOptions),
+    /// Dense vector mapping type configuration.
+    DenseVector(QuickwitDenseVectorOptions),
 }
 
 impl FieldMappingType {
@@ -69,6 +76,15 @@ impl FieldMappingType {
             FieldMappingType::Object(_) => {
                 return QuickwitFieldType::Object;
             }
+            FieldMappingType::Nested(_) => {
+                return QuickwitFieldType::Nested;
+            }
+            FieldMappingType::Concatenate(_) => {
+                return QuickwitFieldType::Concatenate;
+            }
+            FieldMappingType::DenseVector(_) => {
+                return QuickwitFieldType::DenseVector;
+            }
         };
         match cardinality {
             Cardinality::SingleValue => QuickwitFieldType::Simple(primitive_type),
@@ -81,6 +97,9 @@ impl FieldMappingType {
 pub enum QuickwitFieldType {
     Simple(Type),
     Object,
+    Nested,
+    Concatenate,
+    DenseVector,
     Array(Type),
 }
 
@@ -89,6 +108,9 @@ impl QuickwitFieldType {
         match self {
             QuickwitFieldType::Simple(typ) => primitive_type_to_str(typ).to_string(),
             QuickwitFieldType::Object => "object".to_string(),
+            QuickwitFieldType::Nested => "nested".to_string(),
+            QuickwitFieldType::Concatenate => "concatenate".to_string(),
+            QuickwitFieldType::DenseVector => "dense_vector".to_string(),
             QuickwitFieldType::Array(typ) => format!("array<{}>", primitive_type_to_str(typ)),
         }
     }
@@ -97,6 +119,15 @@ impl QuickwitFieldType {
         if type_str == "object" {
             return Some(QuickwitFieldType::Object);
         }
+        if type_str == "nested" {
+            return Some(QuickwitFieldType::Nested);
+        }
+        if type_str == "concatenate" {
+            return Some(QuickwitFieldType::Concatenate);
+        }
+        if type_str == "dense_vector" {
+            return Some(QuickwitFieldType::DenseVector);
+        }
         if type_str.starts_with("array<") && type_str.ends_with('>') {
             let parsed_type_str = parse_primitive_type(&type_str[6..type_str.len() - 1])?;
             return Some(QuickwitFieldType::Array(parsed_type_str));
@@ -158,6 +189,10 @@ mod tests {
         test_parse_type_aux("text", Some(QuickwitFieldType::Simple(Type::Str)));
         test_parse_type_aux("object", Some(QuickwitFieldType::Object));
         test_parse_type_aux("object2", None);
+        test_parse_type_aux("nested", Some(QuickwitFieldType::Nested));
+        test_parse_type_aux("concatenate", Some(QuickwitFieldType::Concatenate));
+        test_parse_type_aux("dense_vector", Some(QuickwitFieldType::DenseVector));
+        test_parse_type_aux("array<concatenate>", None);
         test_parse_type_aux("bool", Some(QuickwitFieldType::Simple(Type::Bool)));
         test_parse_type_aux("ip", Some(QuickwitFieldType::Simple(Type::IpAddr)));
     }
@@ -24,6 +24,7 @@ use std::str::FromStr;
 
 use anyhow::bail;
 use itertools::Itertools;
+use quickwit_query::dense_vector::encode_dense_vector;
 use serde_json::Value as JsonValue;
 use tantivy::schema::{
     BytesOptions, Field, IntoIpv6Addr, IpAddrOptions, JsonObjectOptions, NumericOptions,
@@ -35,8 +36,8 @@ use tracing::warn;
 use super::date_time_type::QuickwitDateTimeOptions;
 use super::field_mapping_entry::{NumericOutputFormat, QuickwitBoolOptions};
 use crate::default_doc_mapper::field_mapping_entry::{
-    QuickwitBytesOptions, QuickwitIpAddrOptions, QuickwitNumericOptions, QuickwitObjectOptions,
-    QuickwitTextOptions,
+    QuickwitBytesOptions, QuickwitConcatenateOptions, QuickwitDenseVectorOptions,
+    QuickwitIpAddrOptions, QuickwitNumericOptions, QuickwitObjectOptions, QuickwitTextOptions,
 };
 use crate::default_doc_mapper::{FieldMappingType, QuickwitJsonOptions};
 use crate::{Cardinality, DocParsingError, FieldMappingEntry, ModeType};
@@ -52,6 +53,8 @@ pub enum LeafType {
     IpAddr(QuickwitIpAddrOptions),
     Json(QuickwitJsonOptions),
     Text(QuickwitTextOptions),
+    Concatenate(QuickwitConcatenateOptions),
+    DenseVector(QuickwitDenseVectorOptions),
 }
 
 impl LeafType {
@@ -98,15 +101,51 @@ impl LeafType {
                     Err(format!("expected JSON object  got `{json_val}`"))
                 }
             }
+            LeafType::Concatenate(_) => {
+                Err("concatenate fields cannot be set directly".to_string())
+            }
+            LeafType::DenseVector(dense_vector_options) => {
+                let vector = parse_dense_vector(json_val, dense_vector_options.dimension)?;
+                let encoded_vector = encode_dense_vector(
+                    &vector,
+                    dense_vector_options.metric,
+                    dense_vector_options.quantization,
+                );
+                Ok(TantivyValue::Bytes(encoded_vector))
+            }
         }
     }
 }
 
+fn parse_dense_vector(json_val: JsonValue, dimension: usize) -> Result<Vec<f32>, String> {
+    let JsonValue::Array(components) = json_val else {
+        return Err(format!("expected JSON array of numbers, got `{json_val}`"));
+    };
+    if components.len() != dimension {
+        return Err(format!(
+            "expected dense vector of dimension {dimension}, got {}",
+            components.len()
+        ));
+    }
+    components
+        .iter()
+        .map(|component| {
+            component
+                .as_f64()
+                .map(|component_f64| component_f64 as f32)
+                .filter(|component_f32| component_f32.is_finite())
+                .ok_or_else(|| format!("expected finite JSON number, got `{component}`"))
+        })
+        .collect()
+}
+
 #[derive(Clone)]
 pub(crate) struct MappingLeaf {
     field: Field,
     typ: LeafType,
     cardinality: Cardinality,
+    /// Concatenate fields into which the values of this leaf are also indexed.
+    concatenate: Vec<Field>,
 }
 
 impl MappingLeaf {
@@ -120,6 +159,10 @@ impl MappingLeaf {
             // We just ignore `null`.
             return Ok(());
         }
+        if let LeafType::DenseVector(_) = self.typ {
+            // A dense vector is a single value represented as a JSON array.
+            return self.add_value(json_val, document, path);
+        }
         if let JsonValue::Array(els) = json_val {
             if self.cardinality == Cardinality::SingleValue {
                 return Err(DocParsingError::MultiValuesNotSupported(path.join(".")));
@@ -129,19 +172,38 @@ impl MappingLeaf {
                     // We just ignore `null`.
                     continue;
                 }
-                let value = self
-                    .typ
-                    .value_from_json(el_json_val)
-                    .map_err(|err_msg| DocParsingError::ValueError(path.join("."), err_msg))?;
-                document.add_field_value(self.field, value);
+                self.add_value(el_json_val, document, path)?;
             }
             return Ok(());
         }
+        self.add_value(json_val, document, path)
+    }
+
+    fn add_value(
+        &self,
+        json_val: JsonValue,
+        document: &mut Document,
+        path: &[String],
+    ) -> Result<(), DocParsingError> {
+        let concatenated_text_opt = if self.concatenate.is_empty() {
+            None
+        } else {
+            concatenated_text(&json_val)
+        };
         let value = self
             .typ
             .value_from_json(json_val)
             .map_err(|err_msg| DocParsingError::ValueError(path.join("."), err_msg))?;
         document.add_field_value(self.field, value);
+
+        if let Some(concatenated_text) = concatenated_text_opt {
+            for &concatenate_field in &self.concatenate {
+                document.add_field_value(
+                    concatenate_field,
+                    TantivyValue::Str(concatenated_text.clone()),
+                );
+            }
+        }
         Ok(())
     }
 
@@ -163,6 +225,15 @@ impl MappingLeaf {
     }
 }
 
+/// Returns the text indexed into concatenate fields for a given value.
+fn concatenated_text(json_val: &JsonValue) -> Option<String> {
+    match json_val {
+        JsonValue::String(text) => Some(text.clone()),
+        JsonValue::Bool(_) | JsonValue::Number(_) => Some(json_val.to_string()),
+        _ => None,
+    }
+}
+
 fn extract_json_val(
     leaf_type: &LeafType,
     named_doc: &mut BTreeMap<String, Vec<TantivyValue>>,
@@ -333,9 +404,11 @@ impl NumVal for f64 {
 pub(crate) struct MappingNode {
     pub branches: fnv::FnvHashMap<String, MappingTree>,
     branches_order: Vec<String>,
+    /// Multi-fields of the leaves of this node, indexed by the name of their parent branch.
+    multi_fields: fnv::FnvHashMap<String, Vec<(String, MappingLeaf)>>,
 }
 
-fn get_or_insert_path<'a>(
+pub(super) fn get_or_insert_path<'a>(
     path: &[String],
     mut dynamic_json_obj: &'a mut serde_json::Map<String, JsonValue>,
 ) -> &'a mut serde_json::Map<String, JsonValue> {
@@ -370,10 +443,85 @@ impl MappingNode {
         let child_tree = self.branches.get(field_name).expect("Missing field");
         match (child_tree, sub_field_path.is_empty()) {
             (_, true) => Some(child_tree.clone().into()),
-            (MappingTree::Leaf(_), false) => None,
+            (MappingTree::Leaf(_), false) => {
+                let [multi_field_name] = sub_field_path else {
+                    return None;
+                };
+                self.multi_fields
+                    .get(field_name)?
+                    .iter()
+                    .find(|(name, _)| name == multi_field_name)
+                    .map(|(_, multi_field_leaf)| multi_field_leaf.clone().into())
+            }
             (MappingTree::Node(child_node), false) => {
                 child_node.internal_find_field_mapping_type(sub_field_path)
             }
+            // The fields of nested objects are not part of the root document.
+            (MappingTree::Nested(_), false) => None,
+        }
+    }
+
+    fn find_leaf_mut(&mut self, field_path: &[String]) -> Option<&mut MappingLeaf> {
+        let (first_path_fragment, sub_field_path) = field_path.split_first()?;
+        match (
+            self.branches.get_mut(first_path_fragment)?,
+            sub_field_path.is_empty(),
+        ) {
+            (MappingTree::Leaf(leaf), true) => Some(leaf),
+            (MappingTree::Node(child_node), false) => child_node.find_leaf_mut(sub_field_path),
+            _ => None,
+        }
+    }
+
+    /// Collects the name, the field, and the source field paths of the concatenate fields of this
+    /// node and its descendants.
+    fn collect_concatenate_fields<'a>(
+        &'a self,
+        field_path: &mut Vec<&'a str>,
+        concatenate_fields: &mut Vec<(String, Field, Vec<String>)>,
+    ) {
+        for field_name in &self.branches_order {
+            let child_tree = self.branches.get(field_name).expect("Missing field");
+            field_path.push(field_name);
+            match child_tree {
+                MappingTree::Leaf(MappingLeaf {
+                    field,
+                    typ: LeafType::Concatenate(options),
+                    ..
+                }) => {
+                    concatenate_fields.push((
+                        field_name_for_field_path(field_path),
+                        *field,
+                        options.concatenate_fields.clone(),
+                    ));
+                }
+                MappingTree::Leaf(_) | MappingTree::Nested(_) => {}
+                MappingTree::Node(child_node) => {
+                    child_node.collect_concatenate_fields(field_path, concatenate_fields);
+                }
+            }
+            field_path.pop();
+        }
+    }
+
+    /// Returns the paths of the nested fields of this node and its descendants.
+    pub fn nested_field_paths(&self) -> Vec<String> {
+        let mut nested_field_paths = Vec::new();
+        self.collect_nested_field_paths(&mut nested_field_paths);
+        nested_field_paths
+    }
+
+    fn collect_nested_field_paths(&self, nested_field_paths: &mut Vec<String>) {
+        for field_name in &self.branches_order {
+            match self.branches.get(field_name).expect("Missing field") {
+                MappingTree::Leaf(_) => {}
+                MappingTree::Node(child_node) => {
+                    child_node.collect_nested_field_paths(nested_field_paths);
+                }
+                MappingTree::Nested(nested_mapping) => {
+                    nested_field_paths.push(nested_mapping.path.clone());
+                }
+            }
         }
     }
 
@@ -401,6 +549,8 @@ impl MappingNode {
         field_mapping_entries
     }
 
+    /// Populates `document` from `json_obj`. The objects of nested fields are parsed into separate
+    /// documents, appended to `nested_docs` along with the path of their nested field.
     pub fn doc_from_json(
         &self,
         json_obj: serde_json::Map<String, JsonValue>,
@@ -408,11 +558,26 @@ impl MappingNode {
         document: &mut Document,
         path: &mut Vec<String>,
         dynamic_json_obj: &mut serde_json::Map<String, JsonValue>,
+        nested_docs: &mut Vec<(String, Document)>,
     ) -> Result<(), DocParsingError> {
         for (field_name, val) in json_obj {
             if let Some(child_tree) = self.branches.get(&field_name) {
+                let multi_field_leaves_opt = self.multi_fields.get(&field_name);
                 path.push(field_name);
-                child_tree.doc_from_json(val, mode, document, path, dynamic_json_obj)?;
+
+                if let Some(multi_field_leaves) = multi_field_leaves_opt {
+                    for (_, multi_field_leaf) in multi_field_leaves {
+                        multi_field_leaf.doc_from_json(val.clone(), document, path)?;
+                    }
+                }
+                child_tree.doc_from_json(
+                    val,
+                    mode,
+                    document,
+                    path,
+                    dynamic_json_obj,
+                    nested_docs,
+                )?;
                 path.pop();
             } else {
                 match mode {
@@ -456,6 +621,11 @@ impl From<MappingTree> for FieldMappingType {
             MappingTree::Node(node) => FieldMappingType::Object(QuickwitObjectOptions {
                 field_mappings: node.into(),
             }),
+            MappingTree::Nested(nested_mapping) => {
+                FieldMappingType::Nested(QuickwitObjectOptions {
+                    field_mappings: nested_mapping.node.into(),
+                })
+            }
         }
     }
 }
@@ -472,6 +642,8 @@ impl From<MappingLeaf> for FieldMappingType {
             LeafType::DateTime(opt) => FieldMappingType::DateTime(opt, leaf.cardinality),
             LeafType::Bytes(opt) => FieldMappingType::Bytes(opt, leaf.cardinality),
             LeafType::Json(opt) => FieldMappingType::Json(opt, leaf.cardinality),
+            LeafType::Concatenate(opt) => FieldMappingType::Concatenate(opt),
+            LeafType::DenseVector(opt) => FieldMappingType::DenseVector(opt),
         }
     }
 }
@@ -482,10 +654,70 @@ impl From<MappingNode> for Vec<FieldMappingEntry> {
     }
 }
 
+/// Mapping of a nested field. Each object of a nested field is indexed as a separate nested
+/// document, written right before the document it belongs to.
+#[derive(Clone)]
+pub(crate) struct NestedMapping {
+    /// Path of the nested field, identifying its nested documents.
+    path: String,
+    /// Stored-only JSON leaf keeping the objects of the nested field in the root document.
+    stored_leaf: MappingLeaf,
+    node: MappingNode,
+}
+
+impl NestedMapping {
+    fn doc_from_json(
+        &self,
+        json_value: JsonValue,
+        mode: ModeType,
+        document: &mut Document,
+        path: &mut Vec<String>,
+        nested_docs: &mut Vec<(String, Document)>,
+    ) -> Result<(), DocParsingError> {
+        let json_values = match json_value {
+            JsonValue::Array(json_values) => json_values,
+            json_value => vec![json_value],
+        };
+        // Unmapped fields of nested objects are not indexed, but they are kept in the stored
+        // objects.
+        let nested_mode = if mode == ModeType::Strict {
+            ModeType::Strict
+        } else {
+            ModeType::Lenient
+        };
+        for json_value in json_values {
+            if json_value.is_null() {
+                // We just ignore `null`.
+                continue;
+            }
+            let JsonValue::Object(json_obj) = json_value else {
+                return Err(DocParsingError::ValueError(
+                    path.join("."),
+                    format!("expected JSON object, got `{json_value}`"),
+                ));
+            };
+            let mut nested_doc = Document::default();
+            self.node.doc_from_json(
+                json_obj.clone(),
+                nested_mode,
+                &mut nested_doc,
+                path,
+                &mut serde_json::Map::default(),
+                &mut Vec::new(),
+            )?;
+            self.stored_leaf
+                .add_value(JsonValue::Object(json_obj), document, path)?;
+            nested_docs.push((self.path.clone(), nested_doc));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone)]
 pub(crate) enum MappingTree {
     Leaf(MappingLeaf),
     Node(MappingNode),
+    Nested(NestedMapping),
 }
 
 impl MappingTree {
@@ -496,14 +728,25 @@ impl MappingTree {
         document: &mut Document,
         path: &mut Vec<String>,
         dynamic_json_obj: &mut serde_json::Map<String, JsonValue>,
+        nested_docs: &mut Vec<(String, Document)>,
     ) -> Result<(), DocParsingError> {
         match self {
             MappingTree::Leaf(mapping_leaf) => {
                 mapping_leaf.doc_from_json(json_value, document, path)
             }
+            MappingTree::Nested(nested_mapping) => {
+                nested_mapping.doc_from_json(json_value, mode, document, path, nested_docs)
+            }
             MappingTree::Node(mapping_node) => {
                 if let JsonValue::Object(json_obj) = json_value {
-                    mapping_node.doc_from_json(json_obj, mode, document, path, dynamic_json_obj)
+                    mapping_node.doc_from_json(
+                        json_obj,
+                        mode,
+                        document,
+                        path,
+                        dynamic_json_obj,
+                        nested_docs,
+                    )
                 } else {
                     Err(DocParsingError::ValueError(
                         path.join("."),
@@ -527,6 +770,11 @@ impl MappingTree {
             MappingTree::Node(mapping_node) => {
                 mapping_node.populate_json(named_doc, field_path, doc_json);
             }
+            MappingTree::Nested(nested_mapping) => {
+                nested_mapping
+                    .stored_leaf
+                    .populate_json(named_doc, field_path, doc_json);
+            }
         }
     }
 }
@@ -536,7 +784,39 @@ pub(crate) fn build_mapping_tree(
     schema: &mut SchemaBuilder,
 ) -> anyhow::Result<MappingNode> {
     let mut field_path = Vec::new();
-    build_mapping_tree_from_entries(entries, &mut field_path, schema)
+    let mut mapping_root_node = build_mapping_tree_from_entries(entries, &mut field_path, schema)?;
+
+    let mut concatenate_fields = Vec::new();
+    mapping_root_node.collect_concatenate_fields(&mut Vec::new(), &mut concatenate_fields);
+
+    for (concatenate_field_name, concatenate_field, source_field_paths) in concatenate_fields {
+        for source_field_path_as_str in &source_field_paths {
+            let source_field_path = build_field_path_from_str(source_field_path_as_str);
+            let Some(source_leaf) = mapping_root_node.find_leaf_mut(&source_field_path) else {
+                bail!(
+                    "concatenate field `{concatenate_field_name}` references unknown field \
+                     `{source_field_path_as_str}`"
+                );
+            };
+            match source_leaf.typ {
+                LeafType::Text(_)
+                | LeafType::I64(_)
+                | LeafType::U64(_)
+                | LeafType::F64(_)
+                | LeafType::Bool(_)
+                | LeafType::IpAddr(_) => {}
+                _ => bail!(
+                    "concatenate field `{concatenate_field_name}` cannot concatenate field \
+                     `{source_field_path_as_str}`: only text, numeric, bool, and ip fields can be \
+                     concatenated"
+                ),
+            }
+            if !source_leaf.concatenate.contains(&concatenate_field) {
+                source_leaf.concatenate.push(concatenate_field);
+            }
+        }
+    }
+    Ok(mapping_root_node)
 }
 
 fn build_mapping_tree_from_entries<'a>(
@@ -551,12 +831,64 @@ fn build_mapping_tree_from_entries<'a>(
             bail!("duplicated field definition `{}`", entry.name);
         }
         let child_tree = build_mapping_from_field_type(&entry.mapping_type, field_path, schema)?;
+
+        if let FieldMappingType::Text(text_options, cardinality) = &entry.mapping_type {
+            if !text_options.fields.is_empty() {
+                let multi_field_leaves = build_multi_field_leaves(
+                    &text_options.fields,
+                    *cardinality,
+                    field_path,
+                    schema,
+                )?;
+                mapping_node
+                    .multi_fields
+                    .insert(entry.name.clone(), multi_field_leaves);
+            }
+        }
         field_path.pop();
         mapping_node.insert(&entry.name, child_tree);
     }
     Ok(mapping_node)
 }
 
+/// Builds the leaves of the multi-fields of a text field. Multi-fields are never stored: the
+/// original value is already stored by their parent field.
+fn build_multi_field_leaves<'a>(
+    multi_field_entries: &'a [FieldMappingEntry],
+    cardinality: Cardinality,
+    field_path: &mut Vec<&'a str>,
+    schema_builder: &mut SchemaBuilder,
+) -> anyhow::Result<Vec<(String, MappingLeaf)>> {
+    let mut multi_field_leaves = Vec::with_capacity(multi_field_entries.len());
+
+    for multi_field_entry in multi_field_entries {
+        let FieldMappingType::Text(options, _) = &multi_field_entry.mapping_type else {
+            bail!(
+                "multi-field `{}` must be of type `text`",
+                multi_field_entry.name
+            );
+        };
+        field_path.push(&multi_field_entry.name);
+        let field_name = field_name_for_field_path(field_path);
+        field_path.pop();
+
+        let text_options: TextOptions = QuickwitTextOptions {
+            stored: false,
+            ..options.clone()
+        }
+        .into();
+        let field = schema_builder.add_text_field(&field_name, text_options);
+        let multi_field_leaf = MappingLeaf {
+            field,
+            typ: LeafType::Text(options.clone()),
+            cardinality,
+            concatenate: Vec::new(),
+        };
+        multi_field_leaves.push((multi_field_entry.name.clone(), multi_field_leaf));
+    }
+    Ok(multi_field_leaves)
+}
+
 fn get_numeric_options_for_bool_field(
     quickwit_bool_options: &QuickwitBoolOptions,
 ) -> NumericOptions {
@@ -696,6 +1028,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::Text(options.clone()),
                 cardinality: *cardinality,
+                concatenate: Vec::new(),
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -706,6 +1039,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::I64(options.clone()),
                 cardinality: *cardinality,
+                concatenate: Vec::new(),
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -716,6 +1050,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::U64(options.clone()),
                 cardinality: *cardinality,
+                concatenate: Vec::new(),
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -726,6 +1061,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::F64(options.clone()),
                 cardinality: *cardinality,
+                concatenate: Vec::new(),
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -736,6 +1072,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::Bool(options.clone()),
                 cardinality: *cardinality,
+                concatenate: Vec::new(),
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -746,6 +1083,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::IpAddr(options.clone()),
                 cardinality: *cardinality,
+                concatenate: Vec::new(),
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -756,6 +1094,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::DateTime(options.clone()),
                 cardinality: *cardinality,
+                concatenate: Vec::new(),
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -766,6 +1105,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::Bytes(options.clone()),
                 cardinality: *cardinality,
+                concatenate: Vec::new(),
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -776,6 +1116,27 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::Json(options.clone()),
                 cardinality: *cardinality,
+                concatenate: Vec::new(),
+            }))
+        }
+        FieldMappingType::Concatenate(options) => {
+            let text_options: TextOptions = options.clone().into();
+            let field = schema_builder.add_text_field(&field_name, text_options);
+            Ok(MappingTree::Leaf(MappingLeaf {
+                field,
+                typ: LeafType::Concatenate(options.clone()),
+                cardinality: Cardinality::MultiValues,
+                concatenate: Vec::new(),
+            }))
+        }
+        FieldMappingType::DenseVector(options) => {
+            let bytes_options: BytesOptions = options.clone().into();
+            let field = schema_builder.add_bytes_field(&field_name, bytes_options);
+            Ok(MappingTree::Leaf(MappingLeaf {
+                field,
+                typ: LeafType::DenseVector(options.clone()),
+                cardinality: Cardinality::SingleValue,
+                concatenate: Vec::new(),
             }))
         }
         FieldMappingType::Object(entries) => {
@@ -786,6 +1147,32 @@ fn build_mapping_from_field_type<'a>(
             )?;
             Ok(MappingTree::Node(mapping_node))
         }
+        FieldMappingType::Nested(entries) => {
+            let mapping_node = build_mapping_tree_from_entries(
+                &entries.field_mappings,
+                field_path,
+                schema_builder,
+            )?;
+            let stored_json_options = QuickwitJsonOptions {
+                indexing_options: None,
+                stored: true,
+                expand_dots: false,
+                ..Default::default()
+            };
+            let json_options = JsonObjectOptions::from(stored_json_options.clone());
+            let field = schema_builder.add_json_field(&field_name, json_options);
+            let stored_leaf = MappingLeaf {
+                field,
+                typ: LeafType::Json(stored_json_options),
+                cardinality: Cardinality::MultiValues,
+                concatenate: Vec::new(),
+            };
+            Ok(MappingTree::Nested(NestedMapping {
+                path: field_name,
+                stored_leaf,
+                node: mapping_node,
+            }))
+        }
     }
 }
 
@@ -981,6 +1368,7 @@ mod tests {
             field,
             typ,
             cardinality: Cardinality::MultiValues,
+            concatenate: Vec::new(),
         };
         let mut document = Document::default();
         let mut path = Vec::new();
@@ -1032,6 +1420,7 @@ mod tests {
             field,
             typ,
             cardinality: Cardinality::MultiValues,
+            concatenate: Vec::new(),
         };
         let mut document = Document::default();
         let mut path = Vec::new();
@@ -1054,6 +1443,7 @@ mod tests {
             field,
             typ,
             cardinality: Cardinality::MultiValues,
+            concatenate: Vec::new(),
         };
         let mut document = Document::default();
         let mut path = Vec::new();
@@ -1071,6 +1461,7 @@ mod tests {
             field,
             typ,
             cardinality: Cardinality::MultiValues,
+            concatenate: Vec::new(),
         };
         let mut document = Document::default();
         let mut path = Vec::new();
@@ -1089,6 +1480,7 @@ mod tests {
             field,
             typ,
             cardinality: Cardinality::MultiValues,
+            concatenate: Vec::new(),
         };
         let mut document = Document::default();
         let mut path = vec!["root".to_string(), "my_field".to_string()];
@@ -1234,6 +1626,7 @@ mod tests {
             field,
             typ,
             cardinality: Cardinality::MultiValues,
+            concatenate: Vec::new(),
         };
         let mut document = Document::default();
         let mut path = vec!["root".to_string(), "my_field".to_string()];
@@ -20,6 +20,7 @@
 mod date_time_type;
 mod default_mapper;
 mod default_mapper_builder;
+mod dynamic_template;
 mod field_mapping_entry;
 mod field_mapping_type;
 mod mapping_tree;
@@ -31,8 +32,12 @@ use regex::Regex;
 
 pub use self::default_mapper::DefaultDocMapper;
 pub use self::default_mapper_builder::{DefaultDocMapperBuilder, Mode, ModeType};
+pub use self::dynamic_template::{
+    DynamicMappingType, DynamicTemplate, DYNAMIC_TEMPLATE_FIELD_PREFIX,
+};
 pub use self::field_mapping_entry::{
-    BinaryFormat, FastFieldOptions, FieldMappingEntry, QuickwitBytesOptions, QuickwitJsonOptions,
+    BinaryFormat, FastFieldOptions, FieldMappingEntry, QuickwitBytesOptions,
+    QuickwitConcatenateOptions, QuickwitDenseVectorOptions, QuickwitJsonOptions,
     QuickwitNumericOptions, QuickwitTextNormalizer, QuickwitTextOptions, TextIndexingOptions,
 };
 pub(crate) use self::field_mapping_entry::{
@@ -41,7 +46,10 @@ pub(crate) use self::field_mapping_entry::{
 pub use self::field_mapping_type::FieldMappingType;
 pub use self::tokenizer_entry::{analyze_text, TokenizerConfig, TokenizerEntry};
 pub(crate) use self::tokenizer_entry::{
-    NgramTokenizerOption, RegexTokenizerOption, TokenFilterType, TokenizerType,
+    ConfiguredTokenFilter, EdgeNgramFilterOption, NamedTokenFilter, NgramTokenizerOption,
+    RegexTokenizerOption, RemoveLongFilterOption, SplitCompoundWordsFilterOption,
+    StemmerFilterOption, StopWordsFilterOption, SynonymsFilterOption, TokenFilterLanguage,
+    TokenFilterType, TokenizerType,
 };
 use crate::QW_RESERVED_FIELD_NAMES;
 
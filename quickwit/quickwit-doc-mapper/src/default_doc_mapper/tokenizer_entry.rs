@@ -17,12 +17,16 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use anyhow::Context;
-use quickwit_query::{CodeTokenizer, DEFAULT_REMOVE_TOKEN_LENGTH};
-use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context};
+use quickwit_query::{CodeTokenizer, EdgeNgramFilter, SynonymFilter, DEFAULT_REMOVE_TOKEN_LENGTH};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value as JsonValue;
 use tantivy::tokenizer::{
-    AsciiFoldingFilter, LowerCaser, NgramTokenizer, RegexTokenizer, RemoveLongFilter,
-    SimpleTokenizer, TextAnalyzer, Token,
+    AsciiFoldingFilter, Language, LowerCaser, NgramTokenizer, RegexTokenizer, RemoveLongFilter,
+    SimpleTokenizer, SplitCompoundWords, Stemmer, StopWordFilter, TextAnalyzer,
+    TextAnalyzerBuilder, Token,
 };
 
 /// A `TokenizerEntry` defines a custom tokenizer with its name and configuration.
@@ -67,17 +71,7 @@ impl TokenizerConfig {
             }
         };
         for filter in &self.filters {
-            match filter.tantivy_token_filter_enum() {
-                TantivyTokenFilterEnum::RemoveLong(token_filter) => {
-                    text_analyzer_builder = text_analyzer_builder.filter_dynamic(token_filter);
-                }
-                TantivyTokenFilterEnum::LowerCaser(token_filter) => {
-                    text_analyzer_builder = text_analyzer_builder.filter_dynamic(token_filter);
-                }
-                TantivyTokenFilterEnum::AsciiFolding(token_filter) => {
-                    text_analyzer_builder = text_analyzer_builder.filter_dynamic(token_filter);
-                }
-            }
+            text_analyzer_builder = filter.add_to_text_analyzer_builder(text_analyzer_builder)?;
         }
         Ok(text_analyzer_builder.build())
     }
@@ -94,30 +88,209 @@ pub fn analyze_text(text: &str, tokenizer: &TokenizerConfig) -> anyhow::Result<V
     Ok(tokens)
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
-#[serde(rename_all = "snake_case")]
+/// A token filter, either referenced by its name (e.g. `"lower_caser"`), or configured with an
+/// object tagged by `type` (e.g. `{"type": "stemmer", "language": "french"}`).
+#[derive(Clone, Debug, PartialEq, Serialize, utoipa::ToSchema)]
+#[serde(untagged)]
 pub enum TokenFilterType {
+    Named(NamedTokenFilter),
+    Configured(ConfiguredTokenFilter),
+}
+
+impl<'de> Deserialize<'de> for TokenFilterType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        // Dispatching on the JSON type rather than relying on `#[serde(untagged)]` preserves the
+        // error messages of the underlying enums.
+        let json_value = JsonValue::deserialize(deserializer)?;
+        let token_filter_res = if json_value.is_string() {
+            serde_json::from_value(json_value).map(TokenFilterType::Named)
+        } else {
+            serde_json::from_value(json_value).map(TokenFilterType::Configured)
+        };
+        token_filter_res.map_err(serde::de::Error::custom)
+    }
+}
+
+impl TokenFilterType {
+    fn add_to_text_analyzer_builder(
+        &self,
+        text_analyzer_builder: TextAnalyzerBuilder,
+    ) -> anyhow::Result<TextAnalyzerBuilder> {
+        let text_analyzer_builder = match self {
+            Self::Named(NamedTokenFilter::RemoveLong) => text_analyzer_builder
+                .filter_dynamic(RemoveLongFilter::limit(DEFAULT_REMOVE_TOKEN_LENGTH)),
+            Self::Named(NamedTokenFilter::LowerCaser) => {
+                text_analyzer_builder.filter_dynamic(LowerCaser)
+            }
+            Self::Named(NamedTokenFilter::AsciiFolding) => {
+                text_analyzer_builder.filter_dynamic(AsciiFoldingFilter)
+            }
+            Self::Configured(ConfiguredTokenFilter::RemoveLong(options)) => {
+                text_analyzer_builder.filter_dynamic(RemoveLongFilter::limit(options.length_limit))
+            }
+            Self::Configured(ConfiguredTokenFilter::Stemmer(options)) => text_analyzer_builder
+                .filter_dynamic(Stemmer::new(options.language.tantivy_language())),
+            Self::Configured(ConfiguredTokenFilter::StopWords(options)) => {
+                if options.language.is_none() && options.words.is_empty() {
+                    bail!("stop words filter requires a `language` or a non-empty list of `words`");
+                }
+                let mut text_analyzer_builder = text_analyzer_builder;
+
+                if let Some(language) = options.language {
+                    let stop_word_filter = StopWordFilter::new(language.tantivy_language())
+                        .with_context(|| {
+                            let language_name = format!("{language:?}").to_lowercase();
+                            format!("no built-in stop words list for language `{language_name}`")
+                        })?;
+                    text_analyzer_builder = text_analyzer_builder.filter_dynamic(stop_word_filter);
+                }
+                if !options.words.is_empty() {
+                    let stop_word_filter = StopWordFilter::remove(options.words.clone());
+                    text_analyzer_builder = text_analyzer_builder.filter_dynamic(stop_word_filter);
+                }
+                text_analyzer_builder
+            }
+            Self::Configured(ConfiguredTokenFilter::Synonyms(options)) => {
+                let synonym_filter = SynonymFilter::new(options.synonyms.clone());
+                text_analyzer_builder.filter_dynamic(synonym_filter)
+            }
+            Self::Configured(ConfiguredTokenFilter::SplitCompoundWords(options)) => {
+                if options.dictionary.is_empty() {
+                    bail!("split compound words filter requires a non-empty `dictionary`");
+                }
+                let split_compound_words = SplitCompoundWords::from_dictionary(&options.dictionary)
+                    .with_context(|| "invalid split compound words filter".to_string())?;
+                text_analyzer_builder.filter_dynamic(split_compound_words)
+            }
+            Self::Configured(ConfiguredTokenFilter::EdgeNgram(options)) => {
+                let edge_ngram_filter = EdgeNgramFilter::new(options.min_gram, options.max_gram)
+                    .with_context(|| "invalid edge ngram filter".to_string())?;
+                text_analyzer_builder.filter_dynamic(edge_ngram_filter)
+            }
+        };
+        Ok(text_analyzer_builder)
+    }
+
+    /// Returns true if the filter lowercases tokens.
+    pub(crate) fn does_lowercasing(&self) -> bool {
+        matches!(self, Self::Named(NamedTokenFilter::LowerCaser))
+    }
+}
+
+/// Token filters that do not require any configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NamedTokenFilter {
     RemoveLong,
     LowerCaser,
     AsciiFolding,
 }
 
-/// Tantivy token filter enum to build
-/// a `TextAnalyzer` with dynamic token filters.
-enum TantivyTokenFilterEnum {
-    RemoveLong(RemoveLongFilter),
-    LowerCaser(LowerCaser),
-    AsciiFolding(AsciiFoldingFilter),
+/// Token filters configured with options.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConfiguredTokenFilter {
+    RemoveLong(RemoveLongFilterOption),
+    Stemmer(StemmerFilterOption),
+    StopWords(StopWordsFilterOption),
+    Synonyms(SynonymsFilterOption),
+    SplitCompoundWords(SplitCompoundWordsFilterOption),
+    EdgeNgram(EdgeNgramFilterOption),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RemoveLongFilterOption {
+    /// Tokens whose length in bytes is greater or equal to this limit are removed.
+    pub length_limit: usize,
 }
 
-impl TokenFilterType {
-    fn tantivy_token_filter_enum(&self) -> TantivyTokenFilterEnum {
-        match &self {
-            Self::RemoveLong => TantivyTokenFilterEnum::RemoveLong(RemoveLongFilter::limit(
-                DEFAULT_REMOVE_TOKEN_LENGTH,
-            )),
-            Self::LowerCaser => TantivyTokenFilterEnum::LowerCaser(LowerCaser),
-            Self::AsciiFolding => TantivyTokenFilterEnum::AsciiFolding(AsciiFoldingFilter),
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct StemmerFilterOption {
+    pub language: TokenFilterLanguage,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct StopWordsFilterOption {
+    /// Language of the built-in stop words list to apply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<TokenFilterLanguage>,
+    /// Additional stop words.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SynonymsFilterOption {
+    /// Maps a term to its synonyms. A synonym made of several words expands into a single token.
+    pub synonyms: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SplitCompoundWordsFilterOption {
+    /// Words compound tokens are split into. Tokens that cannot be entirely split into words of
+    /// the dictionary are left untouched.
+    pub dictionary: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct EdgeNgramFilterOption {
+    pub min_gram: usize,
+    pub max_gram: usize,
+}
+
+/// Languages supported by the `stemmer` and `stop_words` token filters. Built-in stop words lists
+/// are not available for Arabic, Greek, Romanian, Tamil, and Turkish.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenFilterLanguage {
+    Arabic,
+    Danish,
+    Dutch,
+    English,
+    Finnish,
+    French,
+    German,
+    Greek,
+    Hungarian,
+    Italian,
+    Norwegian,
+    Portuguese,
+    Romanian,
+    Russian,
+    Spanish,
+    Swedish,
+    Tamil,
+    Turkish,
+}
+
+impl TokenFilterLanguage {
+    fn tantivy_language(self) -> Language {
+        match self {
+            Self::Arabic => Language::Arabic,
+            Self::Danish => Language::Danish,
+            Self::Dutch => Language::Dutch,
+            Self::English => Language::English,
+            Self::Finnish => Language::Finnish,
+            Self::French => Language::French,
+            Self::German => Language::German,
+            Self::Greek => Language::Greek,
+            Self::Hungarian => Language::Hungarian,
+            Self::Italian => Language::Italian,
+            Self::Norwegian => Language::Norwegian,
+            Self::Portuguese => Language::Portuguese,
+            Self::Romanian => Language::Romanian,
+            Self::Russian => Language::Russian,
+            Self::Spanish => Language::Spanish,
+            Self::Swedish => Language::Swedish,
+            Self::Tamil => Language::Tamil,
+            Self::Turkish => Language::Turkish,
         }
     }
 }
@@ -150,9 +323,23 @@ pub struct RegexTokenizerOption {
 
 #[cfg(test)]
 mod tests {
-    use super::{NgramTokenizerOption, TokenizerType};
+    use super::{
+        analyze_text, ConfiguredTokenFilter, NamedTokenFilter, NgramTokenizerOption,
+        StemmerFilterOption, TokenFilterLanguage, TokenFilterType, TokenizerType,
+    };
     use crate::default_doc_mapper::RegexTokenizerOption;
-    use crate::TokenizerEntry;
+    use crate::{TokenizerConfig, TokenizerEntry};
+
+    fn analyze_text_with_filters(text: &str, filters_json: &str) -> anyhow::Result<Vec<String>> {
+        let tokenizer_config: TokenizerConfig = serde_json::from_str(&format!(
+            r#"{{"type": "simple", "filters": {filters_json}}}"#
+        ))?;
+        let tokens = analyze_text(text, &tokenizer_config)?
+            .into_iter()
+            .map(|token| token.text)
+            .collect();
+        Ok(tokens)
+    }
 
     #[test]
     fn test_deserialize_tokenizer_entry() {
@@ -243,4 +430,116 @@ mod tests {
             _ => panic!("Unexpected tokenizer type"),
         }
     }
+
+    #[test]
+    fn test_deserialize_configured_token_filters() {
+        let tokenizer_config_entry: TokenizerEntry = serde_json::from_str(
+            r#"
+            {
+                "name": "my_tokenizer",
+                "type": "simple",
+                "filters": [
+                    "lower_caser",
+                    {"type": "stemmer", "language": "french"}
+                ]
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            tokenizer_config_entry.config.filters,
+            [
+                TokenFilterType::Named(NamedTokenFilter::LowerCaser),
+                TokenFilterType::Configured(ConfiguredTokenFilter::Stemmer(StemmerFilterOption {
+                    language: TokenFilterLanguage::French,
+                })),
+            ]
+        );
+        let serialized = serde_json::to_value(&tokenizer_config_entry).unwrap();
+        assert_eq!(
+            serialized["filters"],
+            serde_json::json!(["lower_caser", {"type": "stemmer", "language": "french"}])
+        );
+
+        let error = serde_json::from_str::<TokenizerEntry>(
+            r#"
+            {
+                "name": "my_tokenizer",
+                "type": "simple",
+                "filters": [{"type": "stemmer", "language": "klingon"}]
+            }
+            "#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("unknown variant `klingon`"));
+    }
+
+    #[test]
+    fn test_token_filters() {
+        assert_eq!(
+            analyze_text_with_filters(
+                "Chevaux",
+                r#"["lower_caser", {"type": "stemmer", "language": "french"}]"#
+            )
+            .unwrap(),
+            ["cheval"]
+        );
+        assert_eq!(
+            analyze_text_with_filters(
+                "the quick brown fox",
+                r#"[{"type": "stop_words", "language": "english", "words": ["quick"]}]"#
+            )
+            .unwrap(),
+            ["brown", "fox"]
+        );
+        assert_eq!(
+            analyze_text_with_filters(
+                "hi hello",
+                r#"[{"type": "remove_long", "length_limit": 5}]"#
+            )
+            .unwrap(),
+            ["hi"]
+        );
+        assert_eq!(
+            analyze_text_with_filters(
+                "usa",
+                r#"[{"type": "synonyms", "synonyms": {"usa": ["united states"]}}]"#
+            )
+            .unwrap(),
+            ["usa", "united states"]
+        );
+        assert_eq!(
+            analyze_text_with_filters(
+                "dampfschiff",
+                r#"[{"type": "split_compound_words", "dictionary": ["dampf", "schiff"]}]"#
+            )
+            .unwrap(),
+            ["dampf", "schiff"]
+        );
+        assert_eq!(
+            analyze_text_with_filters(
+                "quick",
+                r#"[{"type": "edge_ngram", "min_gram": 2, "max_gram": 3}]"#
+            )
+            .unwrap(),
+            ["qu", "qui"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_token_filters() {
+        let error = analyze_text_with_filters(
+            "hello",
+            r#"[{"type": "stop_words", "language": "turkish"}]"#,
+        )
+        .unwrap_err();
+        assert!(format!("{error:?}").contains("no built-in stop words list for language `turkish`"));
+
+        analyze_text_with_filters("hello", r#"[{"type": "stop_words"}]"#).unwrap_err();
+        analyze_text_with_filters(
+            "hello",
+            r#"[{"type": "edge_ngram", "min_gram": 4, "max_gram": 2}]"#,
+        )
+        .unwrap_err();
+    }
 }
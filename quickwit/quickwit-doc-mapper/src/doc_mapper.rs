@@ -36,7 +36,7 @@ pub type Partition = u64;
 /// An alias for serde_json's object type.
 pub type JsonObject = serde_json::Map<String, JsonValue>;
 
-use crate::{DocParsingError, QueryParserError};
+use crate::{DerivedTagField, DocParsingError, QueryParserError};
 
 /// The `DocMapper` trait defines the way of defining how a (json) document,
 /// and the fields it contains, are stored and indexed.
@@ -54,6 +54,17 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
         json_obj: JsonObject,
     ) -> Result<(Partition, Document), DocParsingError>;
 
+    /// Transforms a JSON object into a block of tantivy [`Document`]s: the nested documents of
+    /// the object, followed by its root document. The documents of a block must be indexed
+    /// contiguously and in order.
+    fn doc_block_from_json_obj(
+        &self,
+        json_obj: JsonObject,
+    ) -> Result<(Partition, Vec<Document>), DocParsingError> {
+        let (partition, document) = self.doc_from_json_obj(json_obj)?;
+        Ok((partition, vec![document]))
+    }
+
     /// Parses a JSON byte slice into a tantivy [`Document`].
     fn doc_from_json_bytes(
         &self,
@@ -109,6 +120,15 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
         with_validation: bool,
     ) -> Result<(Box<dyn Query>, WarmupInfo), QueryParserError>;
 
+    /// Returns the name of the fast field of the split to sort or aggregate on when the request
+    /// targets `field_name`, if it differs from `field_name`.
+    ///
+    /// Unmapped fields captured by a dynamic template are stored in the JSON field of the
+    /// template.
+    fn resolve_fast_field_name(&self, _split_schema: &Schema, _field_name: &str) -> Option<String> {
+        None
+    }
+
     /// Returns the timestamp field name.
     fn timestamp_field_name(&self) -> Option<&str> {
         None
@@ -142,6 +162,33 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
             .collect::<Result<Vec<_>, _>>()
     }
 
+    /// Returns the tag fields whose values are transformed by the partition key before being
+    /// recorded in the tags of a split.
+    fn derived_tag_fields(&self) -> Vec<DerivedTagField> {
+        Vec::new()
+    }
+
+    /// Returns the derived tag fields along with the `NamedField` they are derived from.
+    /// Returns an error if a source field is not found in this schema.
+    fn derived_tag_named_fields(&self) -> anyhow::Result<Vec<(DerivedTagField, NamedField)>> {
+        let index_schema = self.schema();
+        self.derived_tag_fields()
+            .into_iter()
+            .map(|derived_tag_field| {
+                let field_name = derived_tag_field.field_name();
+                let field = index_schema
+                    .get_field(field_name)
+                    .context(format!("field `{field_name}` must exist in the schema"))?;
+                let named_field = NamedField {
+                    name: field_name.to_string(),
+                    field,
+                    field_type: index_schema.get_field_entry(field).field_type().clone(),
+                };
+                Ok((derived_tag_field, named_field))
+            })
+            .collect()
+    }
+
     /// Returns the maximum number of partitions.
     fn max_num_partitions(&self) -> NonZeroU32;
 
@@ -35,18 +35,22 @@ mod routing_expression;
 pub mod tag_pruning;
 
 pub use default_doc_mapper::{
-    analyze_text, BinaryFormat, DefaultDocMapper, DefaultDocMapperBuilder, FieldMappingEntry,
-    FieldMappingType, Mode, ModeType, QuickwitBytesOptions, QuickwitJsonOptions, TokenizerConfig,
-    TokenizerEntry,
+    analyze_text, BinaryFormat, DefaultDocMapper, DefaultDocMapperBuilder, DynamicMappingType,
+    DynamicTemplate, FieldMappingEntry, FieldMappingType, Mode, ModeType, QuickwitBytesOptions,
+    QuickwitJsonOptions, TokenizerConfig, TokenizerEntry, DYNAMIC_TEMPLATE_FIELD_PREFIX,
 };
 use default_doc_mapper::{
-    FastFieldOptions, FieldMappingEntryForSerialization, IndexRecordOptionSchema,
+    ConfiguredTokenFilter, EdgeNgramFilterOption, FastFieldOptions,
+    FieldMappingEntryForSerialization, IndexRecordOptionSchema, NamedTokenFilter,
     NgramTokenizerOption, QuickwitTextNormalizer, QuickwitTextTokenizer, RegexTokenizerOption,
-    TokenFilterType, TokenizerType,
+    RemoveLongFilterOption, SplitCompoundWordsFilterOption, StemmerFilterOption,
+    StopWordsFilterOption, SynonymsFilterOption, TokenFilterLanguage, TokenFilterType,
+    TokenizerType,
 };
 pub use doc_mapper::{DocMapper, JsonObject, NamedField, TermRange, WarmupInfo};
 pub use error::{DocParsingError, QueryParserError};
-use quickwit_common::shared_consts::FIELD_PRESENCE_FIELD_NAME;
+use quickwit_common::shared_consts::{FIELD_PRESENCE_FIELD_NAME, NESTED_FIELD_NAME};
+pub use routing_expression::DerivedTagField;
 
 /// Field name reserved for storing the source document.
 pub const SOURCE_FIELD_NAME: &str = "_source";
@@ -59,6 +63,7 @@ const QW_RESERVED_FIELD_NAMES: &[&str] = &[
     SOURCE_FIELD_NAME,
     DYNAMIC_FIELD_NAME,
     FIELD_PRESENCE_FIELD_NAME,
+    NESTED_FIELD_NAME,
 ];
 
 /// Cardinality of a field.
@@ -72,15 +77,26 @@ pub enum Cardinality {
 
 #[derive(utoipa::OpenApi)]
 #[openapi(components(schemas(
+    ConfiguredTokenFilter,
+    DynamicMappingType,
+    DynamicTemplate,
+    EdgeNgramFilterOption,
     FastFieldOptions,
     FieldMappingEntryForSerialization,
     IndexRecordOptionSchema,
     ModeType,
+    NamedTokenFilter,
     NgramTokenizerOption,
     QuickwitJsonOptions,
     QuickwitTextNormalizer,
     QuickwitTextTokenizer,
     RegexTokenizerOption,
+    RemoveLongFilterOption,
+    SplitCompoundWordsFilterOption,
+    StemmerFilterOption,
+    StopWordsFilterOption,
+    SynonymsFilterOption,
+    TokenFilterLanguage,
     TokenFilterType,
     TokenizerConfig,
     TokenizerEntry,
@@ -21,9 +21,10 @@ use std::collections::{HashMap, HashSet};
 use std::convert::Infallible;
 use std::ops::Bound;
 
+use quickwit_query::block_join::exclude_nested_docs;
 use quickwit_query::query_ast::{
-    FieldPresenceQuery, FullTextQuery, PhrasePrefixQuery, QueryAst, QueryAstVisitor, RangeQuery,
-    TermSetQuery, WildcardQuery,
+    FieldPresenceQuery, FullTextQuery, KnnQuery, PhrasePrefixQuery, QueryAst, QueryAstVisitor,
+    RangeQuery, TermSetQuery, WildcardQuery,
 };
 use quickwit_query::tokenizers::TokenizerManager;
 use quickwit_query::{find_field_or_hit_dynamic, InvalidQuery};
@@ -68,6 +69,24 @@ impl<'a> QueryAstVisitor<'a> for ExistsQueryFields {
     }
 }
 
+#[derive(Default)]
+struct KnnQueryFields {
+    knn_query_field_names: HashSet<String>,
+}
+
+impl<'a> QueryAstVisitor<'a> for KnnQueryFields {
+    type Err = Infallible;
+
+    fn visit_knn(&mut self, knn_query: &'a KnnQuery) -> Result<(), Infallible> {
+        self.knn_query_field_names
+            .insert(knn_query.field.to_string());
+        for ast in &knn_query.filter {
+            self.visit(ast)?;
+        }
+        Ok(())
+    }
+}
+
 /// Build a `Query` with field resolution & forbidding range clauses.
 pub(crate) fn build_query(
     query_ast: &QueryAst,
@@ -84,8 +103,13 @@ pub(crate) fn build_query(
     // This cannot fail. The error type is Infallible.
     let _: Result<(), Infallible> = exists_query_fields.visit(query_ast);
 
+    let mut knn_query_fields = KnnQueryFields::default();
+    // This cannot fail. The error type is Infallible.
+    let _: Result<(), Infallible> = knn_query_fields.visit(query_ast);
+
     let mut fast_field_names = HashSet::new();
     fast_field_names.extend(range_query_fields.range_query_field_names);
+    fast_field_names.extend(knn_query_fields.knn_query_field_names);
     fast_field_names.extend(
         exists_query_fields
             .exists_query_field_names
@@ -99,6 +123,7 @@ pub(crate) fn build_query(
         search_fields,
         with_validation,
     )?;
+    let query = exclude_nested_docs(query, &schema);
 
     let term_set_query_fields = extract_term_set_query_fields(query_ast, &schema)?;
     let term_ranges_grouped_by_field =
@@ -17,16 +17,23 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
 use std::fmt::{self, Display};
 use std::hash::{Hash, Hasher};
 use std::str::FromStr;
 use std::sync::Arc;
 
+use quickwit_datetime::{
+    parse_date_time_str, parse_timestamp_float, parse_timestamp_int, DateTimeInputFormat,
+};
 use serde_json::Value as JsonValue;
 use siphasher::sip::SipHasher;
+use time::{Month, OffsetDateTime, Time};
 
 pub trait RoutingExprContext {
     fn hash_attribute<H: Hasher>(&self, attr_name: &str, hasher: &mut H);
+
+    fn attribute(&self, attr_name: &str) -> Option<&JsonValue>;
 }
 
 /// This is a bit overkill but this function has the merit of
@@ -71,13 +78,164 @@ fn hash_json_val<H: Hasher>(json_val: &JsonValue, hasher: &mut H) {
 
 impl RoutingExprContext for serde_json::Map<String, JsonValue> {
     fn hash_attribute<H: Hasher>(&self, attr_name: &str, hasher: &mut H) {
-        if let Some(json_val) = self.get(attr_name) {
-            hasher.write_u8(1u8);
-            hash_json_val(json_val, hasher);
-        } else {
-            hasher.write_u8(0u8);
+        hash_opt_json_val(self.get(attr_name), hasher);
+    }
+
+    fn attribute(&self, attr_name: &str) -> Option<&JsonValue> {
+        self.get(attr_name)
+    }
+}
+
+fn hash_opt_json_val<H: Hasher>(json_val_opt: Option<&JsonValue>, hasher: &mut H) {
+    if let Some(json_val) = json_val_opt {
+        hasher.write_u8(1u8);
+        hash_json_val(json_val, hasher);
+    } else {
+        hasher.write_u8(0u8);
+    }
+}
+
+/// Returns the string representation of a scalar value, as recorded in the tags of a split.
+fn json_val_to_tag_value(json_val: &JsonValue) -> Option<String> {
+    match json_val {
+        JsonValue::String(text) => Some(text.clone()),
+        JsonValue::Number(number) => Some(number.to_string()),
+        JsonValue::Bool(boolean) => Some(boolean.to_string()),
+        _ => None,
+    }
+}
+
+/// Time unit to which `date_trunc` truncates dates.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum DateTruncUnit {
+    Hour,
+    Day,
+    Month,
+    Year,
+}
+
+impl DateTruncUnit {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DateTruncUnit::Hour => "hour",
+            DateTruncUnit::Day => "day",
+            DateTruncUnit::Month => "month",
+            DateTruncUnit::Year => "year",
+        }
+    }
+
+    /// Parses a date given as a Unix timestamp or as a RFC 3339, ISO 8601 or RFC 2822 string,
+    /// truncates it, and returns the resulting Unix timestamp in seconds.
+    fn truncate(&self, json_val: &JsonValue) -> Option<i64> {
+        const DATE_TIME_FORMATS: [DateTimeInputFormat; 4] = [
+            DateTimeInputFormat::Rfc3339,
+            DateTimeInputFormat::Iso8601,
+            DateTimeInputFormat::Rfc2822,
+            DateTimeInputFormat::Timestamp,
+        ];
+        let date_time = match json_val {
+            JsonValue::Number(number) => {
+                if let Some(timestamp) = number.as_i64() {
+                    parse_timestamp_int(timestamp, &DATE_TIME_FORMATS).ok()?
+                } else {
+                    parse_timestamp_float(number.as_f64()?, &DATE_TIME_FORMATS).ok()?
+                }
+            }
+            JsonValue::String(date_time_str) => {
+                parse_date_time_str(date_time_str, &DATE_TIME_FORMATS).ok()?
+            }
+            _ => return None,
+        };
+        let date_time: OffsetDateTime = date_time.into_utc();
+        let truncated_date_time = match self {
+            DateTruncUnit::Hour => {
+                date_time.replace_time(Time::from_hms(date_time.hour(), 0, 0).ok()?)
+            }
+            DateTruncUnit::Day => date_time.replace_time(Time::MIDNIGHT),
+            DateTruncUnit::Month => date_time.replace_time(Time::MIDNIGHT).replace_day(1).ok()?,
+            DateTruncUnit::Year => date_time
+                .replace_time(Time::MIDNIGHT)
+                .replace_day(1)
+                .ok()?
+                .replace_month(Month::January)
+                .ok()?,
+        };
+        Some(truncated_date_time.unix_timestamp())
+    }
+}
+
+impl FromStr for DateTruncUnit {
+    type Err = anyhow::Error;
+
+    fn from_str(unit_str: &str) -> anyhow::Result<Self> {
+        match unit_str {
+            "hour" => Ok(DateTruncUnit::Hour),
+            "day" => Ok(DateTruncUnit::Day),
+            "month" => Ok(DateTruncUnit::Month),
+            "year" => Ok(DateTruncUnit::Year),
+            _ => anyhow::bail!(
+                "unknown time unit `{unit_str}`: expected `hour`, `day`, `month` or `year`"
+            ),
+        }
+    }
+}
+
+/// Function applied by a partition key to the values of a field.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+enum ValueFunction {
+    /// Keeps the first characters of the value.
+    Prefix(usize),
+    /// Replaces the value by the value it is mapped to, if any.
+    Map(BTreeMap<String, String>),
+}
+
+impl ValueFunction {
+    fn apply(&self, value: &str) -> String {
+        match self {
+            ValueFunction::Prefix(num_chars) => value.chars().take(*num_chars).collect(),
+            ValueFunction::Map(mapping) => mapping
+                .get(value)
+                .cloned()
+                .unwrap_or_else(|| value.to_string()),
+        }
+    }
+}
+
+/// Field whose values are transformed by a `prefix` or `map` function of the partition key.
+///
+/// On top of the values of the field, splits record the transformed values of their documents in
+/// their tags. Since partitioning groups documents by transformed value, a split only has a few of
+/// them, and tag pruning can discard splits even when the field has too many values to be
+/// recorded.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct DerivedTagField {
+    field_name: String,
+    function: ValueFunction,
+}
+
+impl DerivedTagField {
+    /// Returns the name of the field the tag is derived from.
+    pub fn field_name(&self) -> &str {
+        &self.field_name
+    }
+
+    /// Returns the name under which the derived values are recorded in the tags of a split.
+    pub fn tag_name(&self) -> String {
+        match &self.function {
+            ValueFunction::Prefix(num_chars) => format!("prefix({}, {num_chars})", self.field_name),
+            ValueFunction::Map(mapping) => {
+                // The mapping itself may contain `:`, which separates tag names from values.
+                let mut hasher = SipHasher::new();
+                mapping.hash(&mut hasher);
+                format!("map({}, {:016x})", self.field_name, hasher.finish())
+            }
         }
     }
+
+    /// Returns the derived value of a value of the field.
+    pub fn derive_value(&self, value: &str) -> String {
+        self.function.apply(value)
+    }
 }
 
 #[derive(Clone, Default)]
@@ -131,6 +289,15 @@ impl RoutingExpr {
             Vec::new()
         }
     }
+
+    /// Returns the fields transformed by a `prefix` or `map` function of the expression.
+    pub fn derived_tag_fields(&self) -> Vec<DerivedTagField> {
+        let mut derived_tag_fields = Vec::new();
+        if let Some(inner) = self.inner_opt.as_ref() {
+            inner.collect_derived_tag_fields(&mut derived_tag_fields);
+        }
+        derived_tag_fields
+    }
 }
 
 impl Display for RoutingExpr {
@@ -148,7 +315,8 @@ enum InnerRoutingExpr {
     Field(String),
     Composite(Vec<InnerRoutingExpr>),
     Modulo(Box<InnerRoutingExpr>, u64),
-    // TODO Enrich me! Map / ...
+    DateTrunc(String, DateTruncUnit),
+    Value(String, ValueFunction),
 }
 
 impl InnerRoutingExpr {
@@ -171,6 +339,45 @@ impl InnerRoutingExpr {
                 inner_expr.eval_hash(ctx, &mut sub_hasher);
                 hasher.write_u64(sub_hasher.finish() % modulo);
             }
+            InnerRoutingExpr::DateTrunc(field_name, unit) => {
+                ExprType::DateTrunc.hash(hasher);
+                let truncated_timestamp_opt = ctx
+                    .attribute(field_name)
+                    .and_then(|json_val| unit.truncate(json_val))
+                    .map(JsonValue::from);
+                hash_opt_json_val(truncated_timestamp_opt.as_ref(), hasher);
+            }
+            InnerRoutingExpr::Value(field_name, function) => {
+                ExprType::Value.hash(hasher);
+                let derived_value_opt = ctx
+                    .attribute(field_name)
+                    .and_then(json_val_to_tag_value)
+                    .map(|value| JsonValue::String(function.apply(&value)));
+                hash_opt_json_val(derived_value_opt.as_ref(), hasher);
+            }
+        }
+    }
+
+    fn collect_derived_tag_fields(&self, derived_tag_fields: &mut Vec<DerivedTagField>) {
+        match self {
+            InnerRoutingExpr::Field(_) | InnerRoutingExpr::DateTrunc(..) => {}
+            InnerRoutingExpr::Composite(children) => {
+                for child in children {
+                    child.collect_derived_tag_fields(derived_tag_fields);
+                }
+            }
+            InnerRoutingExpr::Modulo(inner_expr, _) => {
+                inner_expr.collect_derived_tag_fields(derived_tag_fields)
+            }
+            InnerRoutingExpr::Value(field_name, function) => {
+                let derived_tag_field = DerivedTagField {
+                    field_name: field_name.clone(),
+                    function: function.clone(),
+                };
+                if !derived_tag_fields.contains(&derived_tag_field) {
+                    derived_tag_fields.push(derived_tag_field);
+                }
+            }
         }
     }
 
@@ -186,6 +393,9 @@ impl InnerRoutingExpr {
                 fields
             }
             InnerRoutingExpr::Modulo(inner_expr, _) => inner_expr.field_names(),
+            InnerRoutingExpr::DateTrunc(field_name, _) | InnerRoutingExpr::Value(field_name, _) => {
+                vec![field_name.to_string()]
+            }
         }
     }
 }
@@ -212,6 +422,33 @@ impl Hash for InnerRoutingExpr {
                 inner_expr.hash(hasher);
                 hasher.write_u64(*modulo);
             }
+            InnerRoutingExpr::DateTrunc(field_name, unit) => {
+                ExprType::DateTrunc.hash(hasher);
+                hasher.write_u64(field_name.len() as u64);
+                hasher.write(field_name.as_bytes());
+                hasher.write_u8(*unit as u8);
+            }
+            InnerRoutingExpr::Value(field_name, function) => {
+                ExprType::Value.hash(hasher);
+                hasher.write_u64(field_name.len() as u64);
+                hasher.write(field_name.as_bytes());
+                match function {
+                    ValueFunction::Prefix(num_chars) => {
+                        hasher.write_u8(0u8);
+                        hasher.write_u64(*num_chars as u64);
+                    }
+                    ValueFunction::Map(mapping) => {
+                        hasher.write_u8(1u8);
+                        hasher.write_u64(mapping.len() as u64);
+                        for (key, value) in mapping {
+                            hasher.write_u64(key.len() as u64);
+                            hasher.write(key.as_bytes());
+                            hasher.write_u64(value.len() as u64);
+                            hasher.write(value.as_bytes());
+                        }
+                    }
+                }
+            }
         }
     }
 }
@@ -261,6 +498,72 @@ fn convert_ast(ast: Vec<expression_dsl::ExpressionAst>) -> anyhow::Result<InnerR
                         modulo,
                     ))
                 }
+                "date_trunc" => {
+                    if args.len() != 2 {
+                        anyhow::bail!(
+                            "invalid arguments for `date_trunc`: expected 2 arguments, found {}",
+                            args.len()
+                        );
+                    }
+                    let field_name = field_argument(&name, args.remove(0))?;
+
+                    let unit_str = field_argument(&name, args.remove(0)).map_err(|_| {
+                        anyhow::anyhow!("invalid 2nd argument for `date_trunc`: expected time unit")
+                    })?;
+                    let unit = DateTruncUnit::from_str(&unit_str)?;
+
+                    Ok(InnerRoutingExpr::DateTrunc(field_name, unit))
+                }
+                "prefix" => {
+                    if args.len() != 2 {
+                        anyhow::bail!(
+                            "invalid arguments for `prefix`: expected 2 arguments, found {}",
+                            args.len()
+                        );
+                    }
+                    let field_name = field_argument(&name, args.remove(0))?;
+
+                    let Argument::Number(num_chars) = args.remove(0) else {
+                        anyhow::bail!("invalid 2nd argument for `prefix`: expected number");
+                    };
+                    if num_chars == 0 {
+                        anyhow::bail!(
+                            "invalid 2nd argument for `prefix`: expected positive number"
+                        );
+                    }
+                    Ok(InnerRoutingExpr::Value(
+                        field_name,
+                        ValueFunction::Prefix(num_chars as usize),
+                    ))
+                }
+                "map" => {
+                    if args.len() < 2 {
+                        anyhow::bail!(
+                            "invalid arguments for `map`: expected at least 2 arguments, found {}",
+                            args.len()
+                        );
+                    }
+                    let field_name = field_argument(&name, args.remove(0))?;
+
+                    let mut mapping = BTreeMap::new();
+                    for arg in args {
+                        let Argument::Mapping(key, value) = arg else {
+                            anyhow::bail!(
+                                "invalid argument for `map`: expected `\"value\": \
+                                 \"mapped_value\"`"
+                            );
+                        };
+                        if mapping.insert(key.clone(), value).is_some() {
+                            anyhow::bail!(
+                                "invalid argument for `map`: value `{key}` is mapped twice"
+                            );
+                        }
+                    }
+                    Ok(InnerRoutingExpr::Value(
+                        field_name,
+                        ValueFunction::Map(mapping),
+                    ))
+                }
                 _ => anyhow::bail!("unknown function `{}`", name),
             },
         })
@@ -274,6 +577,30 @@ fn convert_ast(ast: Vec<expression_dsl::ExpressionAst>) -> anyhow::Result<InnerR
     }
 }
 
+/// Extracts the field name passed as first argument of a function.
+fn field_argument(function_name: &str, arg: expression_dsl::Argument) -> anyhow::Result<String> {
+    use expression_dsl::{Argument, ExpressionAst};
+
+    match arg {
+        Argument::Expression(mut fields) if fields.len() == 1 => match fields.pop() {
+            Some(ExpressionAst::Field(field_name)) => Ok(field_name),
+            _ => anyhow::bail!("invalid 1st argument for `{function_name}`: expected field"),
+        },
+        _ => anyhow::bail!("invalid 1st argument for `{function_name}`: expected field"),
+    }
+}
+
+fn write_string_literal(f: &mut fmt::Formatter, text: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in text.chars() {
+        if c == '"' || c == '\\' {
+            f.write_str("\\")?;
+        }
+        write!(f, "{c}")?;
+    }
+    f.write_str("\"")
+}
+
 // The display implementation should be consistent with `FromString`.
 impl Display for InnerRoutingExpr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -293,6 +620,22 @@ impl Display for InnerRoutingExpr {
             InnerRoutingExpr::Modulo(inner_expr, modulo) => {
                 write!(f, "hash_mod(({inner_expr}), {modulo})")?;
             }
+            InnerRoutingExpr::DateTrunc(field_name, unit) => {
+                write!(f, "date_trunc({field_name}, {})", unit.as_str())?;
+            }
+            InnerRoutingExpr::Value(field_name, ValueFunction::Prefix(num_chars)) => {
+                write!(f, "prefix({field_name}, {num_chars})")?;
+            }
+            InnerRoutingExpr::Value(field_name, ValueFunction::Map(mapping)) => {
+                write!(f, "map({field_name}")?;
+                for (key, value) in mapping {
+                    f.write_str(", ")?;
+                    write_string_literal(f, key)?;
+                    f.write_str(": ")?;
+                    write_string_literal(f, value)?;
+                }
+                f.write_str(")")?;
+            }
         }
         Ok(())
     }
@@ -304,11 +647,13 @@ enum ExprType {
     Field,
     Composite,
     Modulo,
+    DateTrunc,
+    Value,
 }
 
 mod expression_dsl {
     use nom::bytes::complete::tag;
-    use nom::character::complete::multispace0;
+    use nom::character::complete::{char, multispace0};
     use nom::combinator::{eof, opt};
     use nom::error::ErrorKind;
     use nom::multi::separated_list0;
@@ -326,6 +671,7 @@ mod expression_dsl {
     pub(crate) enum Argument {
         Expression(Vec<ExpressionAst>),
         Number(u64),
+        Mapping(String, String),
     }
 
     pub(crate) fn parse_expression(expr_dsl_str: &str) -> anyhow::Result<Vec<ExpressionAst>> {
@@ -346,8 +692,10 @@ mod expression_dsl {
     // Arguments := Argument [ , Arguments ]
     // Argument := { \( RoutingExpr \) | RoutingSubExpr | DirectValue }
     // # We may want other DirectValue in the future
-    // DirectValue := Number
+    // DirectValue := { Number | Mapping }
     // Number := { 0..9 } [ Number ]
+    // Mapping := String : String
+    // String := " { any char except " and \ | \" | \\ } "
 
     fn routing_expr(input: &str) -> IResult<&str, Vec<ExpressionAst>> {
         separated_list0(wtag(","), routing_sub_expr)(input)
@@ -381,6 +729,10 @@ mod expression_dsl {
     fn argument(input: &str) -> IResult<&str, Argument> {
         if let Ok((input, number)) = number(input) {
             Ok((input, Argument::Number(number)))
+        } else if let Ok((input, (key, _, value))) =
+            tuple((string_literal, wtag(":"), string_literal))(input)
+        {
+            Ok((input, Argument::Mapping(key, value)))
         } else if let Ok((input, (_, arg, _))) = tuple((wtag("("), routing_expr, wtag(")")))(input)
         {
             Ok((input, Argument::Expression(arg)))
@@ -393,6 +745,32 @@ mod expression_dsl {
         nom::character::complete::u64(input)
     }
 
+    fn string_literal(input: &str) -> IResult<&str, String> {
+        let (input, _) = char('"')(input)?;
+        let mut text = String::new();
+        let mut chars = input.char_indices();
+
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '"' => return Ok((&input[pos + 1..], text)),
+                '\\' => match chars.next() {
+                    Some((_, escaped_char @ ('"' | '\\'))) => text.push(escaped_char),
+                    _ => {
+                        return Err(nom::Err::Error(nom::error::Error::new(
+                            &input[pos..],
+                            ErrorKind::Escaped,
+                        )))
+                    }
+                },
+                _ => text.push(c),
+            }
+        }
+        Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            ErrorKind::Char,
+        )))
+    }
+
     // tag, but ignore leading and trailing whitespaces
     pub fn wtag<'a, Error: nom::error::ParseError<&'a str>>(
         t: &'a str,
@@ -528,4 +906,112 @@ mod tests {
 
         assert_eq!(seen.len(), 10);
     }
+
+    #[test]
+    fn test_routing_expr_value_functions() {
+        let routing_expr = deser_util(
+            r#"tenant_id,date_trunc(timestamp, day),prefix(service, 3),map(region, "us-east-1": "us", "say \"hi\"": "back\\slash")"#,
+        );
+        let mapping = BTreeMap::from([
+            ("us-east-1".to_string(), "us".to_string()),
+            ("say \"hi\"".to_string(), "back\\slash".to_string()),
+        ]);
+        assert_eq!(
+            routing_expr,
+            InnerRoutingExpr::Composite(vec![
+                InnerRoutingExpr::Field("tenant_id".to_owned()),
+                InnerRoutingExpr::DateTrunc("timestamp".to_owned(), DateTruncUnit::Day),
+                InnerRoutingExpr::Value("service".to_owned(), ValueFunction::Prefix(3)),
+                InnerRoutingExpr::Value("region".to_owned(), ValueFunction::Map(mapping)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_routing_expr_value_functions_invalid() {
+        for invalid_expr in [
+            "date_trunc(timestamp)",
+            "date_trunc(timestamp, week)",
+            "date_trunc((timestamp, tenant_id), day)",
+            "prefix(service, 0)",
+            "prefix(service, service)",
+            "map(region)",
+            "map(region, 3)",
+            r#"map(region, "a": "b", "a": "c")"#,
+            r#"map(region, "a: "b")"#,
+        ] {
+            assert!(
+                InnerRoutingExpr::from_str(invalid_expr).is_err(),
+                "`{invalid_expr}` should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_routing_expr_date_trunc() {
+        let routing_expr = RoutingExpr::new("date_trunc(timestamp, day)").unwrap();
+        let eval_hash = |timestamp: JsonValue| {
+            let ctx: serde_json::Map<String, JsonValue> =
+                serde_json::from_value(serde_json::json!({ "timestamp": timestamp })).unwrap();
+            routing_expr.eval_hash(&ctx)
+        };
+        let morning_hash = eval_hash(JsonValue::from("2024-01-15T08:00:00Z"));
+        assert_eq!(
+            eval_hash(JsonValue::from("2024-01-15T23:59:59Z")),
+            morning_hash
+        );
+        assert_eq!(eval_hash(JsonValue::from(1705334400)), morning_hash);
+        assert_eq!(eval_hash(JsonValue::from(1705334400.5)), morning_hash);
+        assert_ne!(
+            eval_hash(JsonValue::from("2024-01-16T00:00:00Z")),
+            morning_hash
+        );
+
+        let routing_expr = RoutingExpr::new("date_trunc(timestamp, month)").unwrap();
+        let ctx: serde_json::Map<String, JsonValue> =
+            serde_json::from_str(r#"{"timestamp": "2024-01-15T08:00:00Z"}"#).unwrap();
+        let ctx2: serde_json::Map<String, JsonValue> =
+            serde_json::from_str(r#"{"timestamp": "2024-01-31T12:30:00+02:00"}"#).unwrap();
+        assert_eq!(routing_expr.eval_hash(&ctx), routing_expr.eval_hash(&ctx2));
+    }
+
+    #[test]
+    fn test_routing_expr_prefix_and_map() {
+        let routing_expr = RoutingExpr::new(
+            r#"prefix(tenant_id, 2),map(region, "us-east-1": "us", "us-west-2": "us")"#,
+        )
+        .unwrap();
+        let ctx: serde_json::Map<String, JsonValue> =
+            serde_json::from_str(r#"{"tenant_id": "acme", "region": "us-east-1"}"#).unwrap();
+        let ctx2: serde_json::Map<String, JsonValue> =
+            serde_json::from_str(r#"{"tenant_id": "acorn", "region": "us-west-2"}"#).unwrap();
+        let ctx3: serde_json::Map<String, JsonValue> =
+            serde_json::from_str(r#"{"tenant_id": "acme", "region": "eu-west-1"}"#).unwrap();
+        assert_eq!(routing_expr.eval_hash(&ctx), routing_expr.eval_hash(&ctx2));
+        assert_ne!(routing_expr.eval_hash(&ctx), routing_expr.eval_hash(&ctx3));
+    }
+
+    #[test]
+    fn test_routing_expr_derived_tag_fields() {
+        let routing_expr = RoutingExpr::new(
+            r#"hash_mod((tenant_id, prefix(service, 2)), 10),date_trunc(timestamp, day),map(region, "us-east-1": "us")"#,
+        )
+        .unwrap();
+        assert_eq!(
+            routing_expr.field_names(),
+            vec!["tenant_id", "service", "timestamp", "region"]
+        );
+        let derived_tag_fields = routing_expr.derived_tag_fields();
+        assert_eq!(derived_tag_fields.len(), 2);
+
+        assert_eq!(derived_tag_fields[0].field_name(), "service");
+        assert_eq!(derived_tag_fields[0].tag_name(), "prefix(service, 2)");
+        assert_eq!(derived_tag_fields[0].derive_value("frontend"), "fr");
+        assert_eq!(derived_tag_fields[0].derive_value("f"), "f");
+
+        assert_eq!(derived_tag_fields[1].field_name(), "region");
+        assert!(!derived_tag_fields[1].tag_name().contains(':'));
+        assert_eq!(derived_tag_fields[1].derive_value("us-east-1"), "us");
+        assert_eq!(derived_tag_fields[1].derive_value("eu-west-1"), "eu-west-1");
+    }
 }
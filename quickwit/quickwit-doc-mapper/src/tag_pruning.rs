@@ -24,6 +24,8 @@ use quickwit_query::query_ast::QueryAst;
 use serde::{Deserialize, Serialize};
 use tantivy::query_grammar::Occur;
 
+use crate::DerivedTagField;
+
 /// Returns true if and only if tag is of form `{field_name}:any_value`.
 pub fn match_tag_field_name(field_name: &str, tag: &str) -> bool {
     tag.len() > field_name.len()
@@ -38,9 +40,18 @@ pub fn match_tag_field_name(field_name: &str, tag: &str) -> bool {
 /// associated with a split, we are guaranteed that no documents
 /// in the split matches the query.
 pub fn extract_tags_from_query(query_ast: QueryAst) -> Option<TagFilterAst> {
+    extract_tags_from_query_with_derived_tags(query_ast, &[])
+}
+
+/// Same as [`extract_tags_from_query`], but also takes advantage of the tags derived
+/// from the values of a field by the `prefix` and `map` functions of the partition key.
+pub fn extract_tags_from_query_with_derived_tags(
+    query_ast: QueryAst,
+    derived_tag_fields: &[DerivedTagField],
+) -> Option<TagFilterAst> {
     let unsimplified_tag_filter_ast = extract_unsimplified_tags_filter_ast(query_ast);
     let term_filters_ast = simplify_ast(unsimplified_tag_filter_ast)?;
-    Some(expand_to_tag_ast(term_filters_ast))
+    Some(expand_to_tag_ast(term_filters_ast, derived_tag_fields))
 }
 
 fn extract_unsimplified_tags_filter_ast(query_ast: QueryAst) -> UnsimplifiedTagFilterAst {
@@ -118,6 +129,20 @@ fn extract_unsimplified_tags_filter_ast(query_ast: QueryAst) -> UnsimplifiedTagF
             panic!("Extract unsimplified should only be called on AST without UserInputQuery.");
         }
         QueryAst::FieldPresence(_) => UnsimplifiedTagFilterAst::Uninformative,
+        // Tag fields cannot be nested.
+        QueryAst::Nested(_) => UnsimplifiedTagFilterAst::Uninformative,
+        QueryAst::Knn(knn_query) => {
+            // The kNN hits of a split are always among the documents matching its filter.
+            if knn_query.filter.is_empty() {
+                return UnsimplifiedTagFilterAst::Uninformative;
+            }
+            let children: Vec<UnsimplifiedTagFilterAst> = knn_query
+                .filter
+                .into_iter()
+                .map(extract_unsimplified_tags_filter_ast)
+                .collect();
+            UnsimplifiedTagFilterAst::And(children)
+        }
     }
 }
 
@@ -286,14 +311,23 @@ fn term_tag(field: &str, value: &str) -> String {
     format!("{field}:{value}")
 }
 
-fn expand_to_tag_ast(terms_filter_ast: TermFilterAst) -> TagFilterAst {
+fn expand_to_tag_ast(
+    terms_filter_ast: TermFilterAst,
+    derived_tag_fields: &[DerivedTagField],
+) -> TagFilterAst {
     match terms_filter_ast {
-        TermFilterAst::And(children) => {
-            TagFilterAst::And(children.into_iter().map(expand_to_tag_ast).collect())
-        }
-        TermFilterAst::Or(children) => {
-            TagFilterAst::Or(children.into_iter().map(expand_to_tag_ast).collect())
-        }
+        TermFilterAst::And(children) => TagFilterAst::And(
+            children
+                .into_iter()
+                .map(|child| expand_to_tag_ast(child, derived_tag_fields))
+                .collect(),
+        ),
+        TermFilterAst::Or(children) => TagFilterAst::Or(
+            children
+                .into_iter()
+                .map(|child| expand_to_tag_ast(child, derived_tag_fields))
+                .collect(),
+        ),
         TermFilterAst::Term {
             is_present,
             field,
@@ -303,11 +337,35 @@ fn expand_to_tag_ast(terms_filter_ast: TermFilterAst) -> TagFilterAst {
                 is_present: false,
                 tag: field_tag(&field),
             };
-            let term_tag = TagFilterAst::Tag {
+            let term_tag_ast = TagFilterAst::Tag {
                 is_present,
                 tag: term_tag(&field, &value),
             };
-            TagFilterAst::Or(vec![field_is_tag, term_tag])
+            let field_tag_ast = TagFilterAst::Or(vec![field_is_tag, term_tag_ast]);
+
+            // A split not containing the derived value of a term cannot contain the term.
+            // The converse does not hold, so derived tags are of no help for negated terms.
+            // Wildcard values are skipped as well: their derived value is meaningless.
+            if !is_present || value.contains(['*', '?']) {
+                return field_tag_ast;
+            }
+            let mut tag_asts = vec![field_tag_ast];
+            for derived_tag_field in derived_tag_fields {
+                if derived_tag_field.field_name() != field {
+                    continue;
+                }
+                let tag_name = derived_tag_field.tag_name();
+                let derived_value = derived_tag_field.derive_value(&value);
+                tag_asts.push(TagFilterAst::Or(vec![
+                    no_tag(field_tag(&tag_name)),
+                    tag(term_tag(&tag_name, &derived_value)),
+                ]));
+            }
+            if tag_asts.len() == 1 {
+                tag_asts.pop().unwrap()
+            } else {
+                TagFilterAst::And(tag_asts)
+            }
         }
     }
 }
@@ -389,10 +447,13 @@ pub fn no_tag(tag: impl ToString) -> TagFilterAst {
 }
 #[cfg(test)]
 mod test {
+    use std::collections::BTreeSet;
+
     use quickwit_query::query_ast::{QueryAst, UserInputQuery};
     use quickwit_query::BooleanOperand;
 
-    use super::extract_tags_from_query;
+    use super::{extract_tags_from_query, extract_tags_from_query_with_derived_tags};
+    use crate::routing_expression::RoutingExpr;
     use crate::tag_pruning::TagFilterAst;
 
     fn extract_tags_from_query_helper(user_query: &str) -> Option<TagFilterAst> {
@@ -406,6 +467,23 @@ mod test {
         extract_tags_from_query(parsed_query_ast)
     }
 
+    fn extract_tags_from_query_with_partition_key_helper(
+        user_query: &str,
+        partition_key: &str,
+    ) -> Option<TagFilterAst> {
+        let query_ast: QueryAst = UserInputQuery {
+            user_text: user_query.to_string(),
+            default_fields: None,
+            default_operator: BooleanOperand::Or,
+        }
+        .into();
+        let parsed_query_ast = query_ast.parse_user_query(&[]).unwrap();
+        let derived_tag_fields = RoutingExpr::new(partition_key)
+            .unwrap()
+            .derived_tag_fields();
+        extract_tags_from_query_with_derived_tags(parsed_query_ast, &derived_tag_fields)
+    }
+
     #[test]
     fn test_extract_tags_from_query_all() {
         assert_eq!(extract_tags_from_query_helper("*"), None);
@@ -507,4 +585,55 @@ mod test {
         assert!(!super::match_tag_field_name("tagfield", "tagfiele:val"));
         assert!(!super::match_tag_field_name("tagfield", "t:val"));
     }
+
+    #[test]
+    fn test_extract_tags_from_query_with_derived_tags() {
+        assert_eq!(
+            extract_tags_from_query_with_partition_key_helper(
+                "tenant_id:acme AND user:bart",
+                "prefix(tenant_id, 2)"
+            )
+            .unwrap()
+            .to_string(),
+            "(¬tenant_id! ∨ tenant_id:acme) ∧ (¬prefix(tenant_id, 2)! ∨ prefix(tenant_id, 2):ac) \
+             ∧ (¬user! ∨ user:bart)"
+        );
+        assert_eq!(
+            extract_tags_from_query_with_partition_key_helper(
+                "region:us-east-1",
+                r#"map(region, "us-east-1": "us", "us-west-2": "us")"#
+            )
+            .unwrap()
+            .to_string(),
+            "(¬region! ∨ region:us-east-1) ∧ (¬map(region, 0d20ff94306a747e)! ∨ map(region, \
+             0d20ff94306a747e):us)"
+        );
+        // Derived tags do not help pruning negated terms.
+        assert_eq!(
+            extract_tags_from_query_with_partition_key_helper(
+                "-tenant_id:acme",
+                "prefix(tenant_id, 2)"
+            )
+            .unwrap()
+            .to_string(),
+            "(¬tenant_id! ∨ ¬tenant_id:acme)"
+        );
+    }
+
+    #[test]
+    fn test_evaluate_derived_tags() {
+        let tag_filter_ast = extract_tags_from_query_with_partition_key_helper(
+            "tenant_id:acme",
+            "prefix(tenant_id, 2)",
+        )
+        .unwrap();
+        // The split has too many tenants for their values to be recorded.
+        let mut tags = BTreeSet::new();
+        tags.insert("prefix(tenant_id, 2)!".to_string());
+        tags.insert("prefix(tenant_id, 2):gl".to_string());
+        assert!(!tag_filter_ast.evaluate(&tags));
+
+        tags.insert("prefix(tenant_id, 2):ac".to_string());
+        assert!(tag_filter_ast.evaluate(&tags));
+    }
 }
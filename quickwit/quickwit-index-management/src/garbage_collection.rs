@@ -23,6 +23,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use futures::Future;
+use quickwit_common::uri::Uri;
 use quickwit_common::{PrettySample, Progress, ServiceStream};
 use quickwit_metastore::{
     ListSplitsQuery, ListSplitsRequestExt, MetastoreServiceStreamSplitsExt, SplitInfo,
@@ -33,7 +34,7 @@ use quickwit_proto::metastore::{
     MetastoreError, MetastoreResult, MetastoreService, MetastoreServiceClient,
 };
 use quickwit_proto::types::{IndexUid, SplitId};
-use quickwit_storage::{BulkDeleteError, Storage};
+use quickwit_storage::{BulkDeleteError, Storage, StorageResolver};
 use thiserror::Error;
 use time::OffsetDateTime;
 use tracing::{error, instrument};
@@ -76,6 +77,8 @@ pub struct SplitRemovalInfo {
 ///
 /// * `index_id` - The target index id.
 /// * `storage - The storage managing the target index.
+/// * `storage_resolver` - The resolver of the storages holding the splits moved by a tiering
+///   policy.
 /// * `metastore` - The metastore managing the target index.
 /// * `staged_grace_period` -  Threshold period after which a staged split can be safely garbage
 ///   collected.
@@ -86,6 +89,7 @@ pub struct SplitRemovalInfo {
 pub async fn run_garbage_collect(
     index_uid: IndexUid,
     storage: Arc<dyn Storage>,
+    storage_resolver: &StorageResolver,
     mut metastore: MetastoreServiceClient,
     staged_grace_period: Duration,
     deletion_grace_period: Duration,
@@ -157,6 +161,7 @@ pub async fn run_garbage_collect(
         index_uid,
         updated_before_timestamp,
         storage,
+        storage_resolver,
         metastore,
         progress_opt,
     )
@@ -164,7 +169,7 @@ pub async fn run_garbage_collect(
 
     Ok(deleted_splits)
 }
-#[instrument(skip(storage, metastore, progress_opt))]
+#[instrument(skip(storage, storage_resolver, metastore, progress_opt))]
 /// Removes any splits marked for deletion which haven't been
 /// updated after `updated_before_timestamp` in batches of 1000 splits.
 ///
@@ -174,6 +179,7 @@ async fn delete_splits_marked_for_deletion(
     index_uid: IndexUid,
     updated_before_timestamp: i64,
     storage: Arc<dyn Storage>,
+    storage_resolver: &StorageResolver,
     mut metastore: MetastoreServiceClient,
     progress_opt: Option<&Progress>,
 ) -> SplitRemovalInfo {
@@ -221,6 +227,7 @@ async fn delete_splits_marked_for_deletion(
         let delete_splits_result = delete_splits_from_storage_and_metastore(
             index_uid.clone(),
             storage.clone(),
+            storage_resolver,
             metastore.clone(),
             splits_metadata_to_delete,
             progress_opt,
@@ -250,21 +257,29 @@ async fn delete_splits_marked_for_deletion(
 ///
 /// * `index_id` - The target index id.
 /// * `storage - The storage managing the target index.
+/// * `storage_resolver` - The resolver of the storages holding the splits moved by a tiering
+///   policy.
 /// * `metastore` - The metastore managing the target index.
 /// * `splits`  - The list of splits to delete.
 /// * `progress` - For reporting progress (useful when called from within a quickwit actor).
 pub async fn delete_splits_from_storage_and_metastore(
     index_uid: IndexUid,
     storage: Arc<dyn Storage>,
+    storage_resolver: &StorageResolver,
     mut metastore: MetastoreServiceClient,
     splits: Vec<SplitMetadata>,
     progress_opt: Option<&Progress>,
 ) -> anyhow::Result<Vec<SplitInfo>, DeleteSplitsError> {
     let mut split_infos: HashMap<PathBuf, SplitInfo> = HashMap::with_capacity(splits.len());
+    let mut tiered_split_storage_uris: HashMap<SplitId, Uri> = HashMap::new();
 
     for split in splits {
         let split_info = split.as_split_info();
         split_infos.insert(split_info.file_name.clone(), split_info);
+
+        if let Some(storage_uri) = split.storage_uri {
+            tiered_split_storage_uris.insert(split.split_id, storage_uri);
+        }
     }
     let split_paths = split_infos
         .keys()
@@ -304,6 +319,17 @@ pub async fn delete_splits_from_storage_and_metastore(
             storage_error = Some(bulk_delete_error);
         }
     };
+    if !tiered_split_storage_uris.is_empty() {
+        let tiered_storage_failures = delete_tiered_split_files(
+            &index_uid,
+            storage_resolver,
+            &tiered_split_storage_uris,
+            &mut successes,
+            progress_opt,
+        )
+        .await;
+        storage_failures.extend(tiered_storage_failures);
+    }
     if !successes.is_empty() {
         let split_ids: Vec<SplitId> = successes
             .iter()
@@ -346,6 +372,74 @@ pub async fn delete_splits_from_storage_and_metastore(
     Ok(successes)
 }
 
+/// Deletes the files of the splits moved to another storage by a tiering policy. The splits whose
+/// file could not be deleted are removed from `successes` and returned.
+async fn delete_tiered_split_files(
+    index_uid: &IndexUid,
+    storage_resolver: &StorageResolver,
+    tiered_split_storage_uris: &HashMap<SplitId, Uri>,
+    successes: &mut Vec<SplitInfo>,
+    progress_opt: Option<&Progress>,
+) -> Vec<SplitInfo> {
+    let mut split_paths_per_storage_uri: HashMap<&Uri, Vec<&Path>> = HashMap::new();
+
+    for split_info in successes.iter() {
+        if let Some(storage_uri) = tiered_split_storage_uris.get(&split_info.split_id) {
+            split_paths_per_storage_uri
+                .entry(storage_uri)
+                .or_default()
+                .push(split_info.file_name.as_path());
+        }
+    }
+    let mut failed_split_paths: HashSet<PathBuf> = HashSet::new();
+
+    for (storage_uri, split_paths) in split_paths_per_storage_uri {
+        let tier_storage = match storage_resolver.resolve(storage_uri).await {
+            Ok(tier_storage) => tier_storage,
+            Err(error) => {
+                error!(
+                    error=?error,
+                    index_id=index_uid.index_id(),
+                    "failed to resolve storage tier `{storage_uri}`"
+                );
+                failed_split_paths.extend(split_paths.iter().map(|path| path.to_path_buf()));
+                continue;
+            }
+        };
+        let delete_result =
+            protect_future(progress_opt, tier_storage.bulk_delete(&split_paths)).await;
+
+        if let Err(bulk_delete_error) = delete_result {
+            let success_split_paths: HashSet<&Path> = bulk_delete_error
+                .successes
+                .iter()
+                .map(PathBuf::as_path)
+                .collect();
+            let tier_failed_split_paths: Vec<&Path> = split_paths
+                .into_iter()
+                .filter(|split_path| !success_split_paths.contains(split_path))
+                .collect();
+            error!(
+                error=?bulk_delete_error.error,
+                index_id=index_uid.index_id(),
+                "Failed to delete split file(s) {:?} from storage tier `{storage_uri}`.",
+                PrettySample::new(&tier_failed_split_paths, 5),
+            );
+            failed_split_paths.extend(
+                tier_failed_split_paths
+                    .into_iter()
+                    .map(|split_path| split_path.to_path_buf()),
+            );
+        }
+    }
+    let (failures, remaining_successes): (Vec<SplitInfo>, Vec<SplitInfo>) =
+        std::mem::take(successes)
+            .into_iter()
+            .partition(|split_info| failed_split_paths.contains(&split_info.file_name));
+    *successes = remaining_successes;
+    failures
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
@@ -411,6 +505,7 @@ mod tests {
         run_garbage_collect(
             index_uid.clone(),
             storage.clone(),
+            &StorageResolver::for_test(),
             metastore.clone(),
             Duration::from_secs(30),
             Duration::from_secs(30),
@@ -439,6 +534,7 @@ mod tests {
         run_garbage_collect(
             index_uid.clone(),
             storage.clone(),
+            &StorageResolver::for_test(),
             metastore.clone(),
             Duration::from_secs(0),
             Duration::from_secs(30),
@@ -515,6 +611,7 @@ mod tests {
         run_garbage_collect(
             index_uid.clone(),
             storage.clone(),
+            &StorageResolver::for_test(),
             metastore.clone(),
             Duration::from_secs(30),
             Duration::from_secs(30),
@@ -543,6 +640,7 @@ mod tests {
         run_garbage_collect(
             index_uid.clone(),
             storage.clone(),
+            &StorageResolver::for_test(),
             metastore.clone(),
             Duration::from_secs(30),
             Duration::from_secs(0),
@@ -579,6 +677,7 @@ mod tests {
         run_garbage_collect(
             IndexUid::new_with_random_ulid("index-test-gc-deletes"),
             storage.clone(),
+            &StorageResolver::for_test(),
             MetastoreServiceClient::from(metastore),
             Duration::from_secs(30),
             Duration::from_secs(30),
@@ -640,6 +739,7 @@ mod tests {
         let deleted_split_infos = delete_splits_from_storage_and_metastore(
             index_uid.clone(),
             storage.clone(),
+            &StorageResolver::for_test(),
             metastore.clone(),
             vec![split_metadata],
             None,
@@ -664,6 +764,73 @@ mod tests {
             .is_empty());
     }
 
+    #[tokio::test]
+    async fn test_delete_splits_from_storage_and_metastore_tiered_split() {
+        let storage = storage_for_test();
+        let storage_resolver = StorageResolver::for_test();
+        let mut metastore = metastore_for_test();
+
+        let index_id = "test-delete-splits-tiered--index";
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(index_id, &index_uri);
+        let create_index_request = CreateIndexRequest::try_from_index_config(index_config).unwrap();
+        let index_uid: IndexUid = metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid
+            .into();
+
+        let split_id = "test-delete-splits-tiered--split";
+        let tier_storage_uri = Uri::for_test("ram:///cold/test-delete-splits-tiered--index");
+        let split_metadata = SplitMetadata {
+            split_id: split_id.to_string(),
+            index_uid: index_uid.clone(),
+            storage_uri: Some(tier_storage_uri.clone()),
+            ..Default::default()
+        };
+        let stage_splits_request =
+            StageSplitsRequest::try_from_split_metadata(index_uid.clone(), split_metadata.clone())
+                .unwrap();
+        metastore.stage_splits(stage_splits_request).await.unwrap();
+        let mark_splits_for_deletion =
+            MarkSplitsForDeletionRequest::new(index_uid.clone(), vec![split_id.to_string()]);
+        metastore
+            .mark_splits_for_deletion(mark_splits_for_deletion)
+            .await
+            .unwrap();
+
+        let tier_storage = storage_resolver.resolve(&tier_storage_uri).await.unwrap();
+        let split_path_str = format!("{}.split", split_id);
+        let split_path = Path::new(&split_path_str);
+        let payload: Box<dyn PutPayload> = Box::new(vec![0]);
+        tier_storage.put(split_path, payload).await.unwrap();
+        assert!(tier_storage.exists(split_path).await.unwrap());
+
+        let deleted_split_infos = delete_splits_from_storage_and_metastore(
+            index_uid.clone(),
+            storage.clone(),
+            &storage_resolver,
+            metastore.clone(),
+            vec![split_metadata],
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(deleted_split_infos.len(), 1);
+        assert_eq!(deleted_split_infos[0].split_id, split_id);
+        assert!(!tier_storage.exists(split_path).await.unwrap());
+        assert!(metastore
+            .list_splits(ListSplitsRequest::try_from_index_uid(index_uid).unwrap())
+            .await
+            .unwrap()
+            .collect_splits()
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
     #[tokio::test]
     async fn test_delete_splits_from_storage_and_metastore_storage_error() {
         let mut mock_storage = MockStorage::new();
@@ -737,6 +904,7 @@ mod tests {
         let error = delete_splits_from_storage_and_metastore(
             index_uid.clone(),
             storage.clone(),
+            &StorageResolver::for_test(),
             metastore.clone(),
             vec![split_metadata_0, split_metadata_1],
             None,
@@ -810,6 +978,7 @@ mod tests {
         let error = delete_splits_from_storage_and_metastore(
             index_uid.clone(),
             storage.clone(),
+            &StorageResolver::for_test(),
             MetastoreServiceClient::from(mock_metastore),
             vec![split_metadata_0, split_metadata_1],
             None,
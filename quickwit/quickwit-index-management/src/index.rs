@@ -58,6 +58,8 @@ pub enum IndexServiceError {
     InvalidIdentifier(String),
     #[error("operation not allowed: {0}")]
     OperationNotAllowed(String),
+    #[error("invalid snapshot: {0}")]
+    InvalidSnapshot(String),
     #[error("internal error: {0}")]
     Internal(String),
 }
@@ -68,6 +70,7 @@ impl ServiceError for IndexServiceError {
             Self::Internal(_) => ServiceErrorCode::Internal,
             Self::InvalidConfig(_) => ServiceErrorCode::BadRequest,
             Self::InvalidIdentifier(_) => ServiceErrorCode::BadRequest,
+            Self::InvalidSnapshot(_) => ServiceErrorCode::BadRequest,
             Self::Metastore(error) => error.error_code(),
             Self::OperationNotAllowed(_) => ServiceErrorCode::MethodNotAllowed,
             Self::SplitDeletion(_) => ServiceErrorCode::Internal,
@@ -79,8 +82,8 @@ impl ServiceError for IndexServiceError {
 /// Index service responsible for creating, updating and deleting indexes.
 #[derive(Clone)]
 pub struct IndexService {
-    metastore: MetastoreServiceClient,
-    storage_resolver: StorageResolver,
+    pub(crate) metastore: MetastoreServiceClient,
+    pub(crate) storage_resolver: StorageResolver,
 }
 
 impl IndexService {
@@ -215,6 +218,7 @@ impl IndexService {
         let deleted_splits = delete_splits_from_storage_and_metastore(
             index_uid.clone(),
             storage,
+            &self.storage_resolver,
             self.metastore.clone(),
             splits_metadata_to_delete,
             None,
@@ -255,6 +259,7 @@ impl IndexService {
         let deleted_entries = run_garbage_collect(
             index_uid,
             storage,
+            &self.storage_resolver,
             self.metastore.clone(),
             grace_period,
             // deletion_grace_period of zero, so that a cli call directly deletes splits after
@@ -309,6 +314,7 @@ impl IndexService {
         if let Err(err) = delete_splits_from_storage_and_metastore(
             index_uid.clone(),
             storage,
+            &self.storage_resolver,
             self.metastore.clone(),
             splits_metadata,
             None,
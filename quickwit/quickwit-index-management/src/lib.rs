@@ -19,6 +19,11 @@
 
 mod garbage_collection;
 mod index;
+mod snapshot;
 
 pub use garbage_collection::run_garbage_collect;
 pub use index::{clear_cache_directory, validate_storage_uri, IndexService, IndexServiceError};
+pub use snapshot::{
+    IndexRestoreOptions, IndexSnapshotManifest, IndexSnapshotSummary, RestoreSplitsMode,
+    SNAPSHOT_MANIFEST_FILE_NAME,
+};
@@ -0,0 +1,695 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use futures::{stream, TryStreamExt};
+use quickwit_common::split_file;
+use quickwit_common::uri::Uri;
+use quickwit_config::{validate_identifier, INGEST_V2_SOURCE_ID};
+use quickwit_metastore::checkpoint::{IndexCheckpointDelta, SourceCheckpointDelta};
+use quickwit_metastore::{
+    AddSourceRequestExt, CreateIndexRequestExt, IndexMetadata, IndexMetadataResponseExt,
+    ListSplitsQuery, ListSplitsRequestExt, MetastoreServiceStreamSplitsExt, SplitMetadata,
+    SplitState, StageSplitsRequestExt,
+};
+use quickwit_proto::metastore::{
+    serde_utils, AddSourceRequest, CreateIndexRequest, IndexMetadataRequest, ListSplitsRequest,
+    MetastoreService, PublishSplitsRequest, StageSplitsRequest,
+};
+use quickwit_proto::types::{IndexUid, SplitId};
+use quickwit_storage::Storage;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::{error, info};
+
+use crate::{IndexService, IndexServiceError};
+
+/// Name of the file holding the manifest of a snapshot, at the root of the snapshot URI.
+pub const SNAPSHOT_MANIFEST_FILE_NAME: &str = "snapshot-manifest.json";
+
+const SNAPSHOT_MANIFEST_VERSION: u32 = 1;
+
+/// Number of attempts made to read a consistent state of an index that is being updated.
+const MAX_SNAPSHOT_ATTEMPTS: usize = 5;
+
+const MAX_CONCURRENT_SPLIT_COPIES: usize = 4;
+
+/// Describes the content of an index snapshot: the metadata of the index, including its sources
+/// and checkpoints, and the published splits at the time of the snapshot.
+///
+/// The split files are stored next to the manifest, under their usual `<split ID>.split` file
+/// name, so that a snapshot can directly serve as the storage of a restored index.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IndexSnapshotManifest {
+    pub version: u32,
+    /// Time at which the snapshot was taken.
+    pub create_timestamp: i64,
+    pub index_metadata: IndexMetadata,
+    pub splits: Vec<SplitMetadata>,
+}
+
+/// Summary of a snapshot returned once it is complete.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct IndexSnapshotSummary {
+    pub index_id: String,
+    #[schema(value_type = String)]
+    pub snapshot_uri: Uri,
+    pub num_splits: usize,
+    /// Total size of the split files in bytes.
+    pub num_bytes: u64,
+}
+
+/// Defines how the split files of a snapshot are restored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreSplitsMode {
+    /// The split files are copied to the storage of the restored index.
+    #[default]
+    Copy,
+    /// The split files are left in place and the snapshot URI becomes the URI of the restored
+    /// index. The snapshot is then owned by the restored index and must not be restored again.
+    Reference,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IndexRestoreOptions {
+    #[schema(value_type = String)]
+    pub snapshot_uri: Uri,
+    /// ID of the restored index. Defaults to the ID of the snapshotted index.
+    #[serde(default)]
+    pub index_id: Option<String>,
+    /// URI of the restored index. Defaults to `<default index root URI>/<index ID>` when the
+    /// split files are copied.
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub index_uri: Option<Uri>,
+    #[serde(default)]
+    pub splits_mode: RestoreSplitsMode,
+}
+
+impl IndexService {
+    /// Writes a snapshot of the index `index_id` to `snapshot_uri`: the published split files,
+    /// followed by a manifest holding the index metadata and the metadata of the splits.
+    ///
+    /// The manifest is written last, so a snapshot without a manifest is incomplete and cannot be
+    /// restored.
+    pub async fn snapshot_index(
+        &mut self,
+        index_id: &str,
+        snapshot_uri: &Uri,
+    ) -> Result<IndexSnapshotSummary, IndexServiceError> {
+        let (index_metadata, splits) = self.fetch_consistent_index_state(index_id).await?;
+
+        if index_metadata.index_uri() == snapshot_uri {
+            return Err(IndexServiceError::OperationNotAllowed(format!(
+                "snapshot URI `{snapshot_uri}` cannot be the URI of index `{index_id}`"
+            )));
+        }
+        let snapshot_storage = self.storage_resolver.resolve(snapshot_uri).await?;
+        let manifest_path = Path::new(SNAPSHOT_MANIFEST_FILE_NAME);
+
+        if snapshot_storage
+            .exists(manifest_path)
+            .await
+            .map_err(|error| internal_storage_error(snapshot_uri, error))?
+        {
+            return Err(IndexServiceError::OperationNotAllowed(format!(
+                "a snapshot already exists at `{snapshot_uri}`"
+            )));
+        }
+        let mut split_storages: HashMap<Uri, Arc<dyn Storage>> = HashMap::new();
+
+        for split in &splits {
+            let split_storage_uri = split
+                .storage_uri
+                .as_ref()
+                .unwrap_or(index_metadata.index_uri());
+
+            if !split_storages.contains_key(split_storage_uri) {
+                let split_storage = self.storage_resolver.resolve(split_storage_uri).await?;
+                split_storages.insert(split_storage_uri.clone(), split_storage);
+            }
+        }
+        stream::iter(splits.iter().map(Ok::<_, IndexServiceError>))
+            .try_for_each_concurrent(MAX_CONCURRENT_SPLIT_COPIES, |split| {
+                let split_storage_uri = split
+                    .storage_uri
+                    .as_ref()
+                    .unwrap_or(index_metadata.index_uri());
+                let split_storage = split_storages[split_storage_uri].clone();
+                let snapshot_storage = snapshot_storage.clone();
+                async move {
+                    copy_split_file(&*split_storage, &*snapshot_storage, &split.split_id).await
+                }
+            })
+            .await?;
+
+        let num_splits = splits.len();
+        let num_bytes = splits.iter().map(|split| split.footer_offsets.end).sum();
+        let splits = splits
+            .into_iter()
+            .map(|mut split| {
+                split.storage_uri = None;
+                split
+            })
+            .collect();
+        let manifest = IndexSnapshotManifest {
+            version: SNAPSHOT_MANIFEST_VERSION,
+            create_timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+            index_metadata,
+            splits,
+        };
+        let manifest_json = serde_json::to_vec_pretty(&manifest).map_err(|error| {
+            IndexServiceError::Internal(format!("failed to serialize snapshot manifest: {error}"))
+        })?;
+        snapshot_storage
+            .put(manifest_path, Box::new(manifest_json))
+            .await
+            .map_err(|error| internal_storage_error(snapshot_uri, error))?;
+
+        info!(
+            index_id=%index_id,
+            snapshot_uri=%snapshot_uri,
+            num_splits=num_splits,
+            "snapshot of index `{index_id}` complete"
+        );
+        Ok(IndexSnapshotSummary {
+            index_id: index_id.to_string(),
+            snapshot_uri: snapshot_uri.clone(),
+            num_splits,
+            num_bytes,
+        })
+    }
+
+    /// Restores the index snapshot located at `restore_options.snapshot_uri`: creates the index
+    /// and its sources, restores the published splits, and finally the source checkpoints.
+    ///
+    /// * `default_index_root_uri` - Root URI of the restored index when none is provided and the
+    ///   split files are copied.
+    pub async fn restore_index(
+        &mut self,
+        restore_options: IndexRestoreOptions,
+        default_index_root_uri: &Uri,
+    ) -> Result<IndexMetadata, IndexServiceError> {
+        let IndexRestoreOptions {
+            snapshot_uri,
+            index_id: index_id_opt,
+            index_uri: index_uri_opt,
+            splits_mode,
+        } = restore_options;
+        let snapshot_storage = self.storage_resolver.resolve(&snapshot_uri).await?;
+        let manifest = load_manifest(&*snapshot_storage, &snapshot_uri).await?;
+
+        let mut index_config = manifest.index_metadata.index_config.clone();
+
+        if let Some(index_id) = index_id_opt {
+            validate_identifier("Index ID", &index_id).map_err(|_| {
+                IndexServiceError::InvalidIdentifier(format!("invalid index ID: `{index_id}`"))
+            })?;
+            index_config.index_id = index_id;
+        }
+        index_config.index_uri = match splits_mode {
+            RestoreSplitsMode::Copy => {
+                if let Some(index_uri) = index_uri_opt {
+                    index_uri
+                } else {
+                    default_index_root_uri
+                        .join(&index_config.index_id)
+                        .map_err(IndexServiceError::InvalidConfig)?
+                }
+            }
+            RestoreSplitsMode::Reference => {
+                if index_uri_opt.is_some() {
+                    return Err(IndexServiceError::InvalidConfig(anyhow::anyhow!(
+                        "the index URI of an index restored by reference is the snapshot URI"
+                    )));
+                }
+                snapshot_uri.clone()
+            }
+        };
+        let index_id = index_config.index_id.clone();
+        let index_storage = self
+            .storage_resolver
+            .resolve(&index_config.index_uri)
+            .await?;
+
+        let create_index_request = CreateIndexRequest::try_from_index_config(index_config)?;
+        let index_uid: IndexUid = self
+            .metastore
+            .create_index(create_index_request)
+            .await?
+            .index_uid
+            .into();
+
+        let restore_result = self
+            .restore_index_content(
+                index_uid,
+                &manifest,
+                snapshot_storage,
+                index_storage,
+                splits_mode,
+            )
+            .await;
+
+        if let Err(restore_error) = restore_result {
+            // When the split files are referenced, deleting the index would delete the files of
+            // the snapshot.
+            if splits_mode == RestoreSplitsMode::Copy {
+                if let Err(delete_error) = self.delete_index(&index_id, false).await {
+                    error!(index_id=%index_id, error=?delete_error, "failed to delete partially restored index");
+                }
+            }
+            return Err(restore_error);
+        }
+        info!(
+            index_id=%index_id,
+            snapshot_uri=%snapshot_uri,
+            num_splits=manifest.splits.len(),
+            "restored index `{index_id}` from snapshot"
+        );
+        let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+        let index_metadata = self
+            .metastore
+            .index_metadata(index_metadata_request)
+            .await?
+            .deserialize_index_metadata()?;
+        Ok(index_metadata)
+    }
+
+    /// Reads the index metadata and the published splits of an index, retrying until the index
+    /// metadata, and therefore the source checkpoints, did not change while the splits were
+    /// listed.
+    async fn fetch_consistent_index_state(
+        &mut self,
+        index_id: &str,
+    ) -> Result<(IndexMetadata, Vec<SplitMetadata>), IndexServiceError> {
+        let mut index_metadata = self.fetch_index_metadata(index_id).await?;
+
+        for _ in 0..MAX_SNAPSHOT_ATTEMPTS {
+            let query = ListSplitsQuery::for_index(index_metadata.index_uid.clone())
+                .with_split_state(SplitState::Published);
+            let list_splits_request = ListSplitsRequest::try_from_list_splits_query(query)?;
+            let splits = self
+                .metastore
+                .list_splits(list_splits_request)
+                .await?
+                .collect_splits_metadata()
+                .await?;
+            let new_index_metadata = self.fetch_index_metadata(index_id).await?;
+
+            if new_index_metadata == index_metadata {
+                return Ok((index_metadata, splits));
+            }
+            index_metadata = new_index_metadata;
+        }
+        Err(IndexServiceError::Internal(format!(
+            "failed to read a consistent state of index `{index_id}` after \
+             {MAX_SNAPSHOT_ATTEMPTS} attempts"
+        )))
+    }
+
+    async fn fetch_index_metadata(
+        &mut self,
+        index_id: &str,
+    ) -> Result<IndexMetadata, IndexServiceError> {
+        let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
+        let index_metadata = self
+            .metastore
+            .index_metadata(index_metadata_request)
+            .await?
+            .deserialize_index_metadata()?;
+        Ok(index_metadata)
+    }
+
+    async fn restore_index_content(
+        &mut self,
+        index_uid: IndexUid,
+        manifest: &IndexSnapshotManifest,
+        snapshot_storage: Arc<dyn Storage>,
+        index_storage: Arc<dyn Storage>,
+        splits_mode: RestoreSplitsMode,
+    ) -> Result<(), IndexServiceError> {
+        for source_config in manifest.index_metadata.sources.values() {
+            let add_source_request =
+                AddSourceRequest::try_from_source_config(index_uid.clone(), source_config.clone())?;
+            self.metastore.add_source(add_source_request).await?;
+        }
+        if !manifest.splits.is_empty() {
+            let splits_metadata = manifest.splits.iter().cloned().map(|mut split| {
+                split.index_uid = index_uid.clone();
+                split.storage_uri = None;
+                split
+            });
+            let stage_splits_request =
+                StageSplitsRequest::try_from_splits_metadata(index_uid.clone(), splits_metadata)?;
+            self.metastore.stage_splits(stage_splits_request).await?;
+
+            if splits_mode == RestoreSplitsMode::Copy {
+                stream::iter(manifest.splits.iter().map(Ok::<_, IndexServiceError>))
+                    .try_for_each_concurrent(MAX_CONCURRENT_SPLIT_COPIES, |split| {
+                        let snapshot_storage = snapshot_storage.clone();
+                        let index_storage = index_storage.clone();
+                        async move {
+                            copy_split_file(&*snapshot_storage, &*index_storage, &split.split_id)
+                                .await
+                        }
+                    })
+                    .await?;
+            }
+            let staged_split_ids: Vec<SplitId> = manifest
+                .splits
+                .iter()
+                .map(|split| split.split_id.clone())
+                .collect();
+            let publish_splits_request = PublishSplitsRequest {
+                index_uid: index_uid.to_string(),
+                staged_split_ids,
+                ..Default::default()
+            };
+            self.metastore
+                .publish_splits(publish_splits_request)
+                .await?;
+        }
+        // The checkpoints are restored by publishing, for each source, an empty set of splits
+        // along with a checkpoint delta going from the beginning to the snapshotted positions.
+        // The checkpoint of the ingest V2 source refers to shards that do not outlive the
+        // snapshotted index, so it is not restored.
+        for source_id in manifest.index_metadata.sources.keys() {
+            if source_id == INGEST_V2_SOURCE_ID {
+                continue;
+            }
+            let Some(source_checkpoint) = manifest
+                .index_metadata
+                .checkpoint
+                .source_checkpoint(source_id)
+            else {
+                continue;
+            };
+            let source_delta = SourceCheckpointDelta::from_source_checkpoint(source_checkpoint);
+
+            if source_delta.is_empty() {
+                continue;
+            }
+            let index_checkpoint_delta = IndexCheckpointDelta {
+                source_id: source_id.clone(),
+                source_delta,
+            };
+            let publish_splits_request = PublishSplitsRequest {
+                index_uid: index_uid.to_string(),
+                index_checkpoint_delta_json_opt: Some(serde_utils::to_json_str(
+                    &index_checkpoint_delta,
+                )?),
+                ..Default::default()
+            };
+            self.metastore
+                .publish_splits(publish_splits_request)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+async fn load_manifest(
+    snapshot_storage: &dyn Storage,
+    snapshot_uri: &Uri,
+) -> Result<IndexSnapshotManifest, IndexServiceError> {
+    let manifest_path = Path::new(SNAPSHOT_MANIFEST_FILE_NAME);
+
+    if !snapshot_storage
+        .exists(manifest_path)
+        .await
+        .map_err(|error| internal_storage_error(snapshot_uri, error))?
+    {
+        return Err(IndexServiceError::InvalidSnapshot(format!(
+            "no snapshot manifest found at `{snapshot_uri}`"
+        )));
+    }
+    let manifest_bytes = snapshot_storage
+        .get_all(manifest_path)
+        .await
+        .map_err(|error| internal_storage_error(snapshot_uri, error))?;
+    let manifest: IndexSnapshotManifest =
+        serde_json::from_slice(&manifest_bytes).map_err(|error| {
+            IndexServiceError::InvalidSnapshot(format!(
+                "failed to deserialize snapshot manifest at `{snapshot_uri}`: {error}"
+            ))
+        })?;
+    if manifest.version != SNAPSHOT_MANIFEST_VERSION {
+        return Err(IndexServiceError::InvalidSnapshot(format!(
+            "unsupported snapshot manifest version `{}`",
+            manifest.version
+        )));
+    }
+    Ok(manifest)
+}
+
+async fn copy_split_file(
+    source_storage: &dyn Storage,
+    target_storage: &dyn Storage,
+    split_id: &str,
+) -> Result<(), IndexServiceError> {
+    let split_path_str = split_file(split_id);
+    let split_path = Path::new(&split_path_str);
+    let split_bytes = source_storage
+        .get_all(split_path)
+        .await
+        .map_err(|error| internal_storage_error(source_storage.uri(), error))?;
+    target_storage
+        .put(split_path, Box::new(split_bytes.to_vec()))
+        .await
+        .map_err(|error| internal_storage_error(target_storage.uri(), error))?;
+    Ok(())
+}
+
+fn internal_storage_error(
+    storage_uri: &Uri,
+    error: quickwit_storage::StorageError,
+) -> IndexServiceError {
+    IndexServiceError::Internal(format!("storage error on `{storage_uri}`: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_config::{IndexConfig, CLI_INGEST_SOURCE_ID};
+    use quickwit_metastore::{metastore_for_test, MetastoreServiceExt};
+    use quickwit_proto::metastore::MetastoreError;
+    use quickwit_storage::StorageResolver;
+
+    use super::*;
+
+    async fn create_index_with_split(index_service: &mut IndexService, index_id: &str) {
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(index_id, &index_uri);
+        let index_uid = index_service
+            .create_index(index_config, false)
+            .await
+            .unwrap()
+            .index_uid;
+
+        let split_metadata = SplitMetadata {
+            split_id: "test-split".to_string(),
+            index_uid: index_uid.clone(),
+            num_docs: 10,
+            footer_offsets: 0..11,
+            ..Default::default()
+        };
+        let stage_splits_request =
+            StageSplitsRequest::try_from_split_metadata(index_uid.clone(), split_metadata).unwrap();
+        index_service
+            .metastore
+            .stage_splits(stage_splits_request)
+            .await
+            .unwrap();
+
+        let index_storage = index_service
+            .storage_resolver
+            .resolve(&Uri::for_test(&index_uri))
+            .await
+            .unwrap();
+        index_storage
+            .put(
+                Path::new("test-split.split"),
+                Box::new(b"split-bytes".to_vec()),
+            )
+            .await
+            .unwrap();
+
+        let index_checkpoint_delta = IndexCheckpointDelta::for_test(CLI_INGEST_SOURCE_ID, 0..10);
+        let publish_splits_request = PublishSplitsRequest {
+            index_uid: index_uid.to_string(),
+            staged_split_ids: vec!["test-split".to_string()],
+            index_checkpoint_delta_json_opt: Some(
+                serde_utils::to_json_str(&index_checkpoint_delta).unwrap(),
+            ),
+            ..Default::default()
+        };
+        index_service
+            .metastore
+            .publish_splits(publish_splits_request)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_and_restore_index() {
+        let metastore = metastore_for_test();
+        let storage_resolver = StorageResolver::for_test();
+        let mut index_service = IndexService::new(metastore.clone(), storage_resolver.clone());
+        create_index_with_split(&mut index_service, "test-index").await;
+
+        let snapshot_uri = Uri::for_test("ram:///snapshots/test-index");
+        let snapshot_summary = index_service
+            .snapshot_index("test-index", &snapshot_uri)
+            .await
+            .unwrap();
+        assert_eq!(snapshot_summary.num_splits, 1);
+        assert_eq!(snapshot_summary.num_bytes, 11);
+
+        let snapshot_storage = storage_resolver.resolve(&snapshot_uri).await.unwrap();
+        assert!(snapshot_storage
+            .exists(Path::new("test-split.split"))
+            .await
+            .unwrap());
+
+        let error = index_service
+            .snapshot_index("test-index", &snapshot_uri)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, IndexServiceError::OperationNotAllowed(_)));
+
+        let restore_options = IndexRestoreOptions {
+            snapshot_uri: snapshot_uri.clone(),
+            index_id: Some("test-index-restored".to_string()),
+            index_uri: None,
+            splits_mode: RestoreSplitsMode::Copy,
+        };
+        let default_index_root_uri = Uri::for_test("ram:///indexes");
+        let restored_index_metadata = index_service
+            .restore_index(restore_options.clone(), &default_index_root_uri)
+            .await
+            .unwrap();
+        assert_eq!(restored_index_metadata.index_id(), "test-index-restored");
+        assert_eq!(
+            restored_index_metadata.index_uri(),
+            &Uri::for_test("ram:///indexes/test-index-restored")
+        );
+        let index_metadata = index_service
+            .fetch_index_metadata("test-index")
+            .await
+            .unwrap();
+        assert_eq!(
+            restored_index_metadata.checkpoint,
+            index_metadata.checkpoint
+        );
+        assert_eq!(
+            restored_index_metadata.sources.len(),
+            index_metadata.sources.len()
+        );
+
+        let (_, restored_splits) = index_service
+            .fetch_consistent_index_state("test-index-restored")
+            .await
+            .unwrap();
+        assert_eq!(restored_splits.len(), 1);
+        assert_eq!(restored_splits[0].split_id, "test-split");
+        assert_eq!(
+            restored_splits[0].index_uid,
+            restored_index_metadata.index_uid
+        );
+
+        let restored_index_storage = storage_resolver
+            .resolve(restored_index_metadata.index_uri())
+            .await
+            .unwrap();
+        let split_bytes = restored_index_storage
+            .get_all(Path::new("test-split.split"))
+            .await
+            .unwrap();
+        assert_eq!(split_bytes.as_slice(), b"split-bytes");
+
+        let error = index_service
+            .restore_index(restore_options, &default_index_root_uri)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            IndexServiceError::Metastore(MetastoreError::AlreadyExists(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_restore_index_by_reference() {
+        let mut metastore = metastore_for_test();
+        let storage_resolver = StorageResolver::for_test();
+        let mut index_service = IndexService::new(metastore.clone(), storage_resolver);
+        create_index_with_split(&mut index_service, "test-index").await;
+
+        let snapshot_uri = Uri::for_test("ram:///snapshots/test-index");
+        index_service
+            .snapshot_index("test-index", &snapshot_uri)
+            .await
+            .unwrap();
+        index_service
+            .delete_index("test-index", false)
+            .await
+            .unwrap();
+        assert!(!metastore.index_exists("test-index").await.unwrap());
+
+        let restore_options = IndexRestoreOptions {
+            snapshot_uri: snapshot_uri.clone(),
+            index_id: None,
+            index_uri: None,
+            splits_mode: RestoreSplitsMode::Reference,
+        };
+        let restored_index_metadata = index_service
+            .restore_index(restore_options, &Uri::for_test("ram:///indexes"))
+            .await
+            .unwrap();
+        assert_eq!(restored_index_metadata.index_id(), "test-index");
+        assert_eq!(restored_index_metadata.index_uri(), &snapshot_uri);
+
+        let (_, restored_splits) = index_service
+            .fetch_consistent_index_state("test-index")
+            .await
+            .unwrap();
+        assert_eq!(restored_splits.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_restore_index_missing_manifest() {
+        let metastore = metastore_for_test();
+        let storage_resolver = StorageResolver::for_test();
+        let mut index_service = IndexService::new(metastore, storage_resolver);
+
+        let restore_options = IndexRestoreOptions {
+            snapshot_uri: Uri::for_test("ram:///snapshots/missing"),
+            index_id: None,
+            index_uri: None,
+            splits_mode: RestoreSplitsMode::Copy,
+        };
+        let error = index_service
+            .restore_index(restore_options, &Uri::for_test("ram:///indexes"))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, IndexServiceError::InvalidSnapshot(_)));
+    }
+}
@@ -49,8 +49,8 @@ use crate::models::{
 const PLAIN_TEXT: &str = "plain_text";
 
 pub(super) struct JsonDoc {
-    json_obj: JsonObject,
-    num_bytes: usize,
+    pub json_obj: JsonObject,
+    pub num_bytes: usize,
 }
 
 impl JsonDoc {
@@ -172,7 +172,7 @@ fn try_into_json_docs(
 }
 
 #[cfg(feature = "vrl")]
-fn parse_raw_doc(
+pub(super) fn parse_raw_doc(
     input_format: SourceInputFormat,
     raw_doc: Bytes,
     num_bytes: usize,
@@ -189,7 +189,7 @@ fn parse_raw_doc(
 }
 
 #[cfg(not(feature = "vrl"))]
-fn parse_raw_doc(
+pub(super) fn parse_raw_doc(
     input_format: SourceInputFormat,
     raw_doc: Bytes,
     num_bytes: usize,
@@ -198,7 +198,7 @@ fn parse_raw_doc(
     try_into_json_docs(input_format, raw_doc, num_bytes)
 }
 
-enum JsonDocIterator {
+pub(super) enum JsonDocIterator {
     One(Option<Result<JsonDoc, DocProcessorError>>),
     Spans(JsonSpanIterator),
 }
@@ -383,16 +383,7 @@ impl DocProcessor {
         &self,
         doc: &TantivyDocument,
     ) -> Result<Option<DateTime>, DocProcessorError> {
-        let Some(timestamp_field) = self.timestamp_field_opt else {
-            return Ok(None);
-        };
-        let timestamp = doc
-            .get_first(timestamp_field)
-            .and_then(|val| val.as_datetime())
-            .ok_or(DocProcessorError::from(DocParsingError::RequiredField(
-                "timestamp field is required".to_string(),
-            )))?;
-        Ok(Some(timestamp))
+        extract_timestamp(doc, self.timestamp_field_opt)
     }
 
     fn process_raw_doc(&mut self, raw_doc: Bytes, processed_docs: &mut Vec<ProcessedDoc>) {
@@ -428,10 +419,14 @@ impl DocProcessor {
     fn process_json_doc(&self, json_doc: JsonDoc) -> Result<ProcessedDoc, DocProcessorError> {
         let num_bytes = json_doc.num_bytes;
 
-        let (partition, doc) = self.doc_mapper.doc_from_json_obj(json_doc.json_obj)?;
+        let (partition, mut docs) = self.doc_mapper.doc_block_from_json_obj(json_doc.json_obj)?;
+        let doc = docs
+            .pop()
+            .expect("a document block should end with its root document");
         let timestamp_opt = self.extract_timestamp(&doc)?;
         Ok(ProcessedDoc {
             doc,
+            nested_docs: docs,
             timestamp_opt,
             partition,
             num_bytes,
@@ -439,7 +434,23 @@ impl DocProcessor {
     }
 }
 
-fn extract_timestamp_field(doc_mapper: &dyn DocMapper) -> anyhow::Result<Option<Field>> {
+pub(super) fn extract_timestamp(
+    doc: &TantivyDocument,
+    timestamp_field_opt: Option<Field>,
+) -> Result<Option<DateTime>, DocProcessorError> {
+    let Some(timestamp_field) = timestamp_field_opt else {
+        return Ok(None);
+    };
+    let timestamp = doc
+        .get_first(timestamp_field)
+        .and_then(|val| val.as_datetime())
+        .ok_or(DocProcessorError::from(DocParsingError::RequiredField(
+            "timestamp field is required".to_string(),
+        )))?;
+    Ok(Some(timestamp))
+}
+
+pub(super) fn extract_timestamp_field(doc_mapper: &dyn DocMapper) -> anyhow::Result<Option<Field>> {
     let schema = doc_mapper.schema();
     let Some(timestamp_field_name) = doc_mapper.timestamp_field_name() else {
         return Ok(None);
@@ -451,7 +462,7 @@ fn extract_timestamp_field(doc_mapper: &dyn DocMapper) -> anyhow::Result<Option<
 }
 
 #[cfg(not(feature = "vrl"))]
-struct VrlProgram {}
+pub(super) struct VrlProgram {}
 
 #[async_trait]
 impl Actor for DocProcessor {
@@ -0,0 +1,311 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use anyhow::bail;
+use bytes::Bytes;
+use quickwit_config::{SourceInputFormat, TransformConfig};
+use quickwit_doc_mapper::{
+    DocMapper, JsonObject, DYNAMIC_FIELD_NAME, DYNAMIC_TEMPLATE_FIELD_PREFIX,
+};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use tantivy::schema::{Field, NamedFieldDocument};
+use tantivy::Document;
+
+#[cfg(not(feature = "vrl"))]
+use super::doc_processor::VrlProgram;
+use super::doc_processor::{
+    extract_timestamp, extract_timestamp_field, parse_raw_doc, DocProcessorError, JsonDoc,
+};
+#[cfg(feature = "vrl")]
+use super::vrl_processing::VrlProgram;
+
+/// Outcome of the simulated processing of a sample document.
+#[derive(Debug, Default, Serialize)]
+pub struct SimulatedDoc {
+    /// Document returned by the VRL transform of the source, if the source has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transformed_doc: Option<JsonObject>,
+    /// Resulting tantivy document, keyed by field name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc: Option<JsonValue>,
+    /// Nested documents indexed alongside the document.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub nested_docs: Vec<JsonValue>,
+    /// Partition the document would be routed to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partition: Option<u64>,
+    /// Paths of the fields captured by the dynamic mapping.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dynamic_fields: Vec<String>,
+    /// Error that would cause the document to be rejected at indexing time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Runs sample documents through the same steps as the [`DocProcessor`](super::DocProcessor):
+/// the VRL transform of the source, the doc mapper, and the partition key, without indexing
+/// them.
+pub struct DocSimulator {
+    doc_mapper: Arc<dyn DocMapper>,
+    timestamp_field_opt: Option<Field>,
+    transform_opt: Option<VrlProgram>,
+    input_format: SourceInputFormat,
+}
+
+impl DocSimulator {
+    pub fn try_new(
+        doc_mapper: Arc<dyn DocMapper>,
+        transform_config_opt: Option<TransformConfig>,
+        input_format: SourceInputFormat,
+    ) -> anyhow::Result<Self> {
+        if matches!(
+            input_format,
+            SourceInputFormat::OtlpTraceJson | SourceInputFormat::OtlpTraceProtobuf
+        ) {
+            bail!("simulating documents is not supported for OTLP input formats")
+        }
+        let timestamp_field_opt = extract_timestamp_field(&*doc_mapper)?;
+        #[cfg(not(feature = "vrl"))]
+        if transform_config_opt.is_some() {
+            bail!("VRL is not enabled. please recompile with the `vrl` feature")
+        }
+        #[cfg(feature = "vrl")]
+        let transform_opt = transform_config_opt
+            .map(VrlProgram::try_from_transform_config)
+            .transpose()?;
+        #[cfg(not(feature = "vrl"))]
+        let transform_opt = None;
+
+        Ok(Self {
+            doc_mapper,
+            timestamp_field_opt,
+            transform_opt,
+            input_format,
+        })
+    }
+
+    /// Simulates the processing of a sample document. For the plain text input format, string
+    /// values are processed as raw text.
+    pub fn simulate_doc(&mut self, sample_doc: &JsonValue) -> SimulatedDoc {
+        let raw_doc = match (self.input_format, sample_doc) {
+            (SourceInputFormat::PlainText, JsonValue::String(text)) => {
+                Bytes::from(text.clone().into_bytes())
+            }
+            _ => Bytes::from(sample_doc.to_string().into_bytes()),
+        };
+        let num_bytes = raw_doc.len();
+        let has_transform = self.transform_opt.is_some();
+        let mut simulated_doc = SimulatedDoc::default();
+
+        let json_doc_result = parse_raw_doc(
+            self.input_format,
+            raw_doc,
+            num_bytes,
+            self.transform_opt.as_mut(),
+        )
+        .next()
+        .expect("JSON and plain text documents should yield exactly one document");
+
+        let simulation_result = json_doc_result.and_then(|json_doc| {
+            if has_transform {
+                simulated_doc.transformed_doc = Some(json_doc.json_obj.clone());
+            }
+            self.simulate_json_doc(json_doc, &mut simulated_doc)
+        });
+        if let Err(error) = simulation_result {
+            simulated_doc.error = Some(error.to_string());
+        }
+        simulated_doc
+    }
+
+    fn simulate_json_doc(
+        &self,
+        json_doc: JsonDoc,
+        simulated_doc: &mut SimulatedDoc,
+    ) -> Result<(), DocProcessorError> {
+        let (partition, mut docs) = self.doc_mapper.doc_block_from_json_obj(json_doc.json_obj)?;
+        let doc = docs
+            .pop()
+            .expect("a document block should end with its root document");
+        simulated_doc.partition = Some(partition);
+
+        let schema = self.doc_mapper.schema();
+        let doc_json = named_doc_to_json(doc.to_named_doc(&schema))?;
+        simulated_doc.dynamic_fields = dynamic_field_paths(&doc_json);
+        simulated_doc.doc = Some(doc_json);
+        simulated_doc.nested_docs = docs
+            .iter()
+            .map(|nested_doc| named_doc_to_json(nested_doc.to_named_doc(&schema)))
+            .collect::<Result<_, _>>()?;
+
+        extract_timestamp(&doc, self.timestamp_field_opt)?;
+        Ok(())
+    }
+}
+
+fn named_doc_to_json(named_doc: NamedFieldDocument) -> Result<JsonValue, DocProcessorError> {
+    let doc_json = serde_json::to_value(named_doc)?;
+    Ok(doc_json)
+}
+
+/// Returns the paths of the leaves of the values of the dynamic field and of the fields of the
+/// dynamic templates of a document.
+fn dynamic_field_paths(doc_json: &JsonValue) -> Vec<String> {
+    let mut paths = BTreeSet::new();
+
+    if let Some(doc_json_obj) = doc_json.as_object() {
+        for (field_name, dynamic_values) in doc_json_obj {
+            if field_name == DYNAMIC_FIELD_NAME
+                || field_name.starts_with(DYNAMIC_TEMPLATE_FIELD_PREFIX)
+            {
+                collect_leaf_paths(dynamic_values, &mut String::new(), &mut paths);
+            }
+        }
+    }
+    paths.into_iter().collect()
+}
+
+fn collect_leaf_paths(json_value: &JsonValue, path: &mut String, paths: &mut BTreeSet<String>) {
+    match json_value {
+        JsonValue::Object(json_obj) => {
+            for (key, child_value) in json_obj {
+                let path_len = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(&key.replace('.', r"\."));
+                collect_leaf_paths(child_value, path, paths);
+                path.truncate(path_len);
+            }
+        }
+        JsonValue::Array(values) => {
+            for value in values {
+                collect_leaf_paths(value, path, paths);
+            }
+        }
+        _ => {
+            if !path.is_empty() {
+                paths.insert(path.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_config::{build_doc_mapper, DocMapping, SearchSettings};
+    use serde_json::json;
+
+    use super::*;
+
+    fn test_doc_mapper() -> Arc<dyn DocMapper> {
+        let doc_mapping: DocMapping = serde_json::from_value(json!({
+            "field_mappings": [
+                {"name": "timestamp", "type": "datetime", "fast": true},
+                {"name": "tenant_id", "type": "u64"},
+                {"name": "body", "type": "text"}
+            ],
+            "timestamp_field": "timestamp",
+            "mode": "dynamic",
+            "partition_key": "tenant_id",
+            "max_num_partitions": 10
+        }))
+        .unwrap();
+        build_doc_mapper(&doc_mapping, &SearchSettings::default()).unwrap()
+    }
+
+    #[test]
+    fn test_doc_simulator() {
+        let mut doc_simulator =
+            DocSimulator::try_new(test_doc_mapper(), None, SourceInputFormat::Json).unwrap();
+
+        let simulated_doc = doc_simulator.simulate_doc(&json!({
+            "timestamp": 1_700_000_000,
+            "tenant_id": 1,
+            "body": "hello",
+            "attributes": {"http.method": "GET", "status": [200, 404]}
+        }));
+        assert!(simulated_doc.error.is_none());
+        assert!(simulated_doc.transformed_doc.is_none());
+        assert!(simulated_doc.partition.is_some());
+        assert_eq!(
+            simulated_doc.doc.as_ref().unwrap()["body"],
+            json!(["hello"])
+        );
+        assert_eq!(
+            simulated_doc.dynamic_fields,
+            [r"attributes.http\.method", "attributes.status"]
+        );
+
+        let simulated_doc = doc_simulator.simulate_doc(&json!({"tenant_id": 1}));
+        assert!(simulated_doc
+            .error
+            .unwrap()
+            .contains("timestamp field is required"));
+
+        let simulated_doc = doc_simulator.simulate_doc(&json!({
+            "timestamp": 1_700_000_000,
+            "tenant_id": "not a number"
+        }));
+        assert!(simulated_doc.doc.is_none());
+        assert!(simulated_doc.error.is_some());
+
+        let simulated_doc = doc_simulator.simulate_doc(&json!(["not an object"]));
+        assert!(simulated_doc.error.is_some());
+    }
+
+    #[test]
+    fn test_doc_simulator_rejects_otlp_input_formats() {
+        DocSimulator::try_new(test_doc_mapper(), None, SourceInputFormat::OtlpTraceJson)
+            .err()
+            .unwrap();
+    }
+
+    #[cfg(feature = "vrl")]
+    #[test]
+    fn test_doc_simulator_with_transform() {
+        let transform_config = TransformConfig::new(
+            r#".tenant_id = to_int!(.tenant_id)
+            del(.debug)"#
+                .to_string(),
+            None,
+        );
+        let mut doc_simulator = DocSimulator::try_new(
+            test_doc_mapper(),
+            Some(transform_config),
+            SourceInputFormat::Json,
+        )
+        .unwrap();
+        let simulated_doc = doc_simulator.simulate_doc(&json!({
+            "timestamp": 1_700_000_000,
+            "tenant_id": "3",
+            "debug": true
+        }));
+        assert!(simulated_doc.error.is_none());
+        assert_eq!(
+            JsonValue::Object(simulated_doc.transformed_doc.unwrap()),
+            json!({"timestamp": 1_700_000_000, "tenant_id": 3})
+        );
+        assert!(simulated_doc.dynamic_fields.is_empty());
+    }
+}
@@ -299,6 +299,7 @@ impl IndexerState {
         for doc in batch.docs {
             let ProcessedDoc {
                 doc,
+                nested_docs,
                 timestamp_opt,
                 partition,
                 num_bytes,
@@ -319,6 +320,13 @@ impl IndexerState {
                 record_timestamp(timestamp, &mut indexed_split.split_attrs.time_range);
             }
             let _protect_guard = ctx.protect_zone();
+            // Nested documents are written right before their root document.
+            for nested_doc in nested_docs {
+                indexed_split
+                    .index_writer
+                    .add_document(nested_doc)
+                    .context("failed to add nested document")?;
+            }
             indexed_split
                 .index_writer
                 .add_document(doc)
@@ -777,6 +785,7 @@ mod tests {
                             body_field=>"this is a test document",
                             timestamp_field=>DateTime::from_timestamp_secs(1_662_529_435)
                         ),
+                        nested_docs: Vec::new(),
                         timestamp_opt: Some(DateTime::from_timestamp_secs(1_662_529_435)),
                         partition: 1,
                         num_bytes: 30,
@@ -786,6 +795,7 @@ mod tests {
                             body_field=>"this is a test document 2",
                             timestamp_field=>DateTime::from_timestamp_secs(1_662_529_435)
                         ),
+                        nested_docs: Vec::new(),
                         timestamp_opt: Some(DateTime::from_timestamp_secs(1_662_529_435)),
                         partition: 1,
                         num_bytes: 30,
@@ -803,6 +813,7 @@ mod tests {
                             body_field=>"this is a test document 3",
                             timestamp_field=>DateTime::from_timestamp_secs(1_662_529_435i64)
                         ),
+                        nested_docs: Vec::new(),
                         timestamp_opt: Some(DateTime::from_timestamp_secs(1_662_529_435i64)),
                         partition: 1,
                         num_bytes: 30,
@@ -812,6 +823,7 @@ mod tests {
                             body_field=>"this is a test document 4",
                             timestamp_field=>DateTime::from_timestamp_secs(1_662_529_435)
                         ),
+                        nested_docs: Vec::new(),
                         timestamp_opt: Some(DateTime::from_timestamp_secs(1_662_529_435)),
                         partition: 1,
                         num_bytes: 30,
@@ -828,6 +840,7 @@ mod tests {
                         body_field=>"this is a test document 5",
                         timestamp_field=>DateTime::from_timestamp_secs(1_662_529_435)
                     ),
+                    nested_docs: Vec::new(),
                     timestamp_opt: Some(DateTime::from_timestamp_secs(1_662_529_435)),
                     partition: 1,
                     num_bytes: 30,
@@ -914,6 +927,7 @@ mod tests {
             let num_bytes = body.len() * 2;
             ProcessedDoc {
                 doc: doc!(body_field=>body),
+                nested_docs: Vec::new(),
                 timestamp_opt: None,
                 partition: 0,
                 num_bytes,
@@ -992,6 +1006,7 @@ mod tests {
                                 body_field=>"this is a test document",
                                 timestamp_field=>DateTime::from_timestamp_secs(1_662_529_435)
                             ),
+                            nested_docs: Vec::new(),
                             timestamp_opt: Some(DateTime::from_timestamp_secs(1_662_529_435)),
                             partition: 1,
                             num_bytes: 30,
@@ -1070,6 +1085,7 @@ mod tests {
                         body_field=>"this is a test document 5",
                         timestamp_field=>DateTime::from_timestamp_secs(1_662_529_435)
                     ),
+                    nested_docs: Vec::new(),
                     timestamp_opt: Some(DateTime::from_timestamp_secs(1_662_529_435)),
                     partition: 1,
                     num_bytes: 30,
@@ -1157,6 +1173,7 @@ mod tests {
                         body_field=>"this is a test document 5",
                         timestamp_field=> DateTime::from_timestamp_secs(1_662_529_435)
                     ),
+                    nested_docs: Vec::new(),
                     timestamp_opt: Some(DateTime::from_timestamp_secs(1_662_529_435)),
                     partition: 1,
                     num_bytes: 30,
@@ -1241,6 +1258,7 @@ mod tests {
                             body_field=>"doc 2",
                             tenant_field=>"tenant_1",
                         ),
+                        nested_docs: Vec::new(),
                         timestamp_opt: None,
                         partition: 1,
                         num_bytes: 30,
@@ -1250,6 +1268,7 @@ mod tests {
                             body_field=>"doc 2",
                             tenant_field=>"tenant_2",
                         ),
+                        nested_docs: Vec::new(),
                         timestamp_opt: None,
                         partition: 3,
                         num_bytes: 30,
@@ -1334,6 +1353,7 @@ mod tests {
                 .send_message(ProcessedDocBatch {
                     docs: vec![ProcessedDoc {
                         doc: doc!(body_field=>"doc {i}"),
+                        nested_docs: Vec::new(),
                         timestamp_opt: None,
                         partition,
                         num_bytes: 30,
@@ -1412,6 +1432,7 @@ mod tests {
                 .send_message(ProcessedDocBatch {
                     docs: vec![ProcessedDoc {
                         doc: doc!(body_field=>"doc 1"),
+                        nested_docs: Vec::new(),
                         timestamp_opt: None,
                         partition: 0,
                         num_bytes: 30,
@@ -1483,6 +1504,7 @@ mod tests {
             .send_message(ProcessedDocBatch {
                 docs: vec![ProcessedDoc {
                     doc: doc!(body_field=>"doc 1"),
+                    nested_docs: Vec::new(),
                     timestamp_opt: None,
                     partition: 0,
                     num_bytes: 30,
@@ -1539,6 +1561,7 @@ mod tests {
             .send_message(ProcessedDocBatch {
                 docs: vec![ProcessedDoc {
                     doc: doc!(body_field=>"doc 1"),
+                    nested_docs: Vec::new(),
                     timestamp_opt: None,
                     partition: 0,
                     num_bytes: 30,
@@ -377,7 +377,8 @@ impl IndexingPipeline {
 
         // Packager
         let tag_fields = self.params.doc_mapper.tag_named_fields()?;
-        let packager = Packager::new("Packager", tag_fields, uploader_mailbox);
+        let derived_tag_fields = self.params.doc_mapper.derived_tag_named_fields()?;
+        let packager = Packager::new("Packager", tag_fields, derived_tag_fields, uploader_mailbox);
         let (packager_mailbox, packager_handle) = ctx
             .spawn_actor()
             .set_kill_switch(self.kill_switch.clone())
@@ -40,6 +40,7 @@ use quickwit_proto::metastore::{
     MetastoreServiceClient,
 };
 use quickwit_proto::types::PipelineUid;
+use quickwit_query::block_join::{num_root_docs, IncludeNestedDocsQuery};
 use quickwit_query::get_quickwit_fastfield_normalizer_manager;
 use quickwit_query::query_ast::QueryAst;
 use tantivy::directory::{Advice, DirectoryClone, MmapDirectory, RamDirectory};
@@ -415,7 +416,8 @@ impl MergeExecutor {
             };
 
         let merged_segment_reader = SegmentReader::open(&merged_segment)?;
-        let num_docs = merged_segment_reader.num_docs() as u64;
+        // Nested documents are not accounted for in the number of documents of a split.
+        let num_docs = num_root_docs(&merged_segment_reader)? as u64;
         let uncompressed_docs_size_in_bytes = (num_docs as f32
             * split.uncompressed_docs_size_in_bytes as f32
             / split.num_docs as f32) as u64;
@@ -511,7 +513,9 @@ impl MergeExecutor {
                 );
                 let (query, _) =
                     doc_mapper.query(union_index.schema(), &parsed_query_ast, false)?;
-                index_writer.delete_query(query)?;
+                // Nested documents are deleted along with their root document.
+                let delete_query = IncludeNestedDocsQuery::new(query, &union_index.schema());
+                index_writer.delete_query(Box::new(delete_query))?;
             }
             debug!("commit-delete-operations");
             index_writer.commit()?;
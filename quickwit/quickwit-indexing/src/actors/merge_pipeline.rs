@@ -263,7 +263,13 @@ impl MergePipeline {
 
         // Merge Packager
         let tag_fields = self.params.doc_mapper.tag_named_fields()?;
-        let merge_packager = Packager::new("MergePackager", tag_fields, merge_uploader_mailbox);
+        let derived_tag_fields = self.params.doc_mapper.derived_tag_named_fields()?;
+        let merge_packager = Packager::new(
+            "MergePackager",
+            tag_fields,
+            derived_tag_fields,
+            merge_uploader_mailbox,
+        );
         let (merge_packager_mailbox, merge_packager_handler) = ctx
             .spawn_actor()
             .set_kill_switch(self.kill_switch.clone())
@@ -18,6 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 mod doc_processor;
+mod doc_simulator;
 mod index_serializer;
 mod indexer;
 mod indexing_pipeline;
@@ -34,6 +35,7 @@ mod uploader;
 mod vrl_processing;
 
 pub use doc_processor::{DocProcessor, DocProcessorCounters};
+pub use doc_simulator::{DocSimulator, SimulatedDoc};
 pub use index_serializer::IndexSerializer;
 pub use indexer::{Indexer, IndexerCounters};
 pub use indexing_pipeline::{IndexingPipeline, IndexingPipelineParams};
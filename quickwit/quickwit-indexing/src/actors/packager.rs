@@ -31,7 +31,7 @@ use quickwit_common::runtimes::RuntimeType;
 use quickwit_common::temp_dir::TempDirectory;
 use quickwit_directories::write_hotcache;
 use quickwit_doc_mapper::tag_pruning::append_to_tag_set;
-use quickwit_doc_mapper::NamedField;
+use quickwit_doc_mapper::{DerivedTagField, NamedField};
 use quickwit_proto::search::{
     serialize_split_fields, ListFieldType, ListFields, ListFieldsEntryResponse,
 };
@@ -69,18 +69,22 @@ pub struct Packager {
     uploader_mailbox: Mailbox<Uploader>,
     /// List of tag fields ([`Vec<NamedField>`]) defined in the index config.
     tag_fields: Vec<NamedField>,
+    /// List of tag fields derived from a field by the partition key, along with the field.
+    derived_tag_fields: Vec<(DerivedTagField, NamedField)>,
 }
 
 impl Packager {
     pub fn new(
         actor_name: &'static str,
         tag_fields: Vec<NamedField>,
+        derived_tag_fields: Vec<(DerivedTagField, NamedField)>,
         uploader_mailbox: Mailbox<Uploader>,
     ) -> Packager {
         Packager {
             actor_name,
             uploader_mailbox,
             tag_fields,
+            derived_tag_fields,
         }
     }
 
@@ -91,8 +95,13 @@ impl Packager {
     ) -> anyhow::Result<PackagedSplit> {
         let segment_metas = split.index.searchable_segment_metas()?;
         assert_eq!(segment_metas.len(), 1);
-        let packaged_split =
-            create_packaged_split(&segment_metas[..], split, &self.tag_fields, ctx)?;
+        let packaged_split = create_packaged_split(
+            &segment_metas[..],
+            split,
+            &self.tag_fields,
+            &self.derived_tag_fields,
+            ctx,
+        )?;
         Ok(packaged_split)
     }
 }
@@ -247,35 +256,66 @@ fn try_extract_terms(
     for inv_index in inv_indexes {
         let mut terms_streamer = inv_index.terms().stream()?;
         while let Some((term_data, _)) = terms_streamer.next() {
-            let term = match named_field.field_type {
-                FieldType::U64(_) => u64_from_term_data(term_data)?.to_string(),
-                FieldType::I64(_) => {
-                    tantivy::u64_to_i64(u64_from_term_data(term_data)?).to_string()
-                }
-                FieldType::F64(_) => {
-                    tantivy::u64_to_f64(u64_from_term_data(term_data)?).to_string()
-                }
-                FieldType::Bool(_) => match u64_from_term_data(term_data)? {
-                    0 => false,
-                    1 => true,
-                    _ => bail!("invalid boolean value"),
-                }
-                .to_string(),
-                FieldType::Bytes(_) => {
-                    bail!("tags collection is not allowed on `bytes` fields")
-                }
-                _ => std::str::from_utf8(term_data)?.to_string(),
-            };
-            terms.push(term);
+            terms.push(term_data_to_string(named_field, term_data)?);
         }
     }
     Ok(terms)
 }
 
+/// Attempts to exhaustively extract the list of values derived from the terms of
+/// a field term dictionary.
+///
+/// Contrary to [`try_extract_terms`], the field itself may have any number of terms:
+/// only the number of distinct derived values is bounded by `max_values`.
+fn try_extract_derived_terms(
+    derived_tag_field: &DerivedTagField,
+    named_field: &NamedField,
+    inv_indexes: &[Arc<InvertedIndexReader>],
+    max_values: usize,
+) -> anyhow::Result<Vec<String>> {
+    let mut derived_values = BTreeSet::new();
+    for inv_index in inv_indexes {
+        let mut terms_streamer = inv_index.terms().stream()?;
+        while let Some((term_data, _)) = terms_streamer.next() {
+            let term = term_data_to_string(named_field, term_data)?;
+            derived_values.insert(derived_tag_field.derive_value(&term));
+
+            if derived_values.len() > max_values {
+                bail!(
+                    "number of unique values for tag field {} > {}",
+                    derived_tag_field.tag_name(),
+                    max_values
+                );
+            }
+        }
+    }
+    Ok(derived_values.into_iter().collect())
+}
+
+fn term_data_to_string(named_field: &NamedField, term_data: &[u8]) -> anyhow::Result<String> {
+    let term = match named_field.field_type {
+        FieldType::U64(_) => u64_from_term_data(term_data)?.to_string(),
+        FieldType::I64(_) => tantivy::u64_to_i64(u64_from_term_data(term_data)?).to_string(),
+        FieldType::F64(_) => tantivy::u64_to_f64(u64_from_term_data(term_data)?).to_string(),
+        FieldType::Bool(_) => match u64_from_term_data(term_data)? {
+            0 => false,
+            1 => true,
+            _ => bail!("invalid boolean value"),
+        }
+        .to_string(),
+        FieldType::Bytes(_) => {
+            bail!("tags collection is not allowed on `bytes` fields")
+        }
+        _ => std::str::from_utf8(term_data)?.to_string(),
+    };
+    Ok(term)
+}
+
 fn create_packaged_split(
     segment_metas: &[SegmentMeta],
     split: IndexedSplit,
     tag_fields: &[NamedField],
+    derived_tag_fields: &[(DerivedTagField, NamedField)],
     ctx: &ActorContext<Packager>,
 ) -> anyhow::Result<PackagedSplit> {
     debug!(split_id = split.split_id(), "create-packaged-split");
@@ -310,6 +350,28 @@ fn create_packaged_split(
             }
         }
     }
+    for (derived_tag_field, named_field) in derived_tag_fields {
+        let inverted_indexes = index_reader
+            .searcher()
+            .segment_readers()
+            .iter()
+            .map(|segment| segment.inverted_index(named_field.field))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match try_extract_derived_terms(
+            derived_tag_field,
+            named_field,
+            &inverted_indexes,
+            MAX_VALUES_PER_TAG_FIELD,
+        ) {
+            Ok(derived_values) => {
+                append_to_tag_set(&derived_tag_field.tag_name(), &derived_values, &mut tags);
+            }
+            Err(tag_extraction_error) => {
+                warn!(err=?tag_extraction_error,  "no derived values will be registered in the split metadata");
+            }
+        }
+    }
 
     ctx.record_progress();
 
@@ -565,7 +627,7 @@ mod tests {
                 "tag_str", "tag_many", "tag_u64", "tag_i64", "tag_f64", "tag_bool",
             ],
         );
-        let packager = Packager::new("TestPackager", tag_fields, mailbox);
+        let packager = Packager::new("TestPackager", tag_fields, Vec::new(), mailbox);
         let (packager_mailbox, packager_handle) = universe.spawn_builder().spawn(packager);
         packager_mailbox
             .send_message(IndexedSplitBatch {
@@ -332,6 +332,8 @@ impl Handler<PackagedSplitBatch> for Uploader {
                     report_splits.push(ReportSplit {
                         storage_uri: split_store.remote_uri().to_string(),
                         split_id: packaged_split.split_id().to_string(),
+                        split_footer_start: split_metadata.footer_offsets.start,
+                        split_footer_end: split_metadata.footer_offsets.end,
                     });
 
                     split_metadata_list.push(split_metadata);
@@ -347,7 +349,10 @@ impl Handler<PackagedSplitBatch> for Uploader {
 
                 let mut packaged_splits_and_metadata = Vec::with_capacity(batch.splits.len());
 
-                event_broker.publish(ReportSplitsRequest { report_splits });
+                event_broker.publish(ReportSplitsRequest {
+                    report_splits: report_splits.clone(),
+                    warm_up: false,
+                });
 
                 for (packaged_split, metadata) in batch.splits.into_iter().zip(split_metadata_list) {
                     let upload_result = upload_split(
@@ -367,6 +372,13 @@ impl Handler<PackagedSplitBatch> for Uploader {
                     packaged_splits_and_metadata.push((packaged_split, metadata));
                 }
 
+                // The splits are now available on the storage: searchers can warm them up before
+                // they get published.
+                event_broker.publish(ReportSplitsRequest {
+                    report_splits,
+                    warm_up: true,
+                });
+
                 let splits_update = make_publish_operation(
                     index_uid,
                     packaged_splits_and_metadata,
@@ -1019,10 +1031,21 @@ mod tests {
         let report_splits: ReportSplitsRequest = report_splits_rx
             .recv_timeout(Duration::from_secs(1))
             .unwrap();
+        assert!(!report_splits.warm_up);
         assert_eq!(report_splits.report_splits.len(), 1);
         let split = &report_splits.report_splits[0];
         assert_eq!(split.storage_uri, "ram:///");
         assert_eq!(split.split_id, SPLIT_ULID_STR);
+        assert!(split.split_footer_start < split.split_footer_end);
+
+        let warm_up_report_splits: ReportSplitsRequest = report_splits_rx
+            .recv_timeout(Duration::from_secs(1))
+            .unwrap();
+        assert!(warm_up_report_splits.warm_up);
+        assert_eq!(
+            warm_up_report_splits.report_splits,
+            report_splits.report_splits
+        );
         universe.assert_quit().await;
         Ok(())
     }
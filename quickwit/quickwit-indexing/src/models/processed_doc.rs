@@ -24,6 +24,8 @@ use tantivy::{DateTime, TantivyDocument};
 
 pub struct ProcessedDoc {
     pub doc: TantivyDocument,
+    /// Nested documents of `doc`, which must be indexed right before it.
+    pub nested_docs: Vec<TantivyDocument>,
     pub timestamp_opt: Option<DateTime>,
     pub partition: u64,
     pub num_bytes: usize,
@@ -32,6 +34,7 @@ pub struct ProcessedDoc {
 impl fmt::Debug for ProcessedDoc {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ProcessedDoc")
+            .field("num_nested_docs", &self.nested_docs.len())
             .field("timestamp_opt", &self.timestamp_opt)
             .field("partition", &self.partition)
             .field("num_bytes", &self.num_bytes)
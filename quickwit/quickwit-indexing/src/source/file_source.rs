@@ -151,7 +151,7 @@ impl TypedSourceFactory for FileSourceFactory {
                 })
                 .unwrap_or(0);
             let (dir_uri, file_name) = dir_and_filename(filepath)?;
-            let storage = ctx.storage_resolver.resolve(&dir_uri).await?;
+            let storage = ctx.storage_resolver.resolve_unencrypted(&dir_uri).await?;
             let file_size = storage.file_num_bytes(file_name).await?.try_into().unwrap();
             if offset > file_size {
                 return Err(anyhow::anyhow!(
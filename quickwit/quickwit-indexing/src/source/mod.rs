@@ -408,7 +408,7 @@ pub async fn check_source_connectivity(
         SourceParams::File(params) => {
             if let Some(filepath) = &params.filepath {
                 let (dir_uri, file_name) = dir_and_filename(filepath)?;
-                let storage = storage_resolver.resolve(&dir_uri).await?;
+                let storage = storage_resolver.resolve_unencrypted(&dir_uri).await?;
                 storage.file_num_bytes(file_name).await?;
             }
             Ok(())
@@ -29,7 +29,10 @@ use bytesize::ByteSize;
 use quickwit_common::io::{IoControls, IoControlsAccess};
 use quickwit_common::uri::Uri;
 use quickwit_metastore::SplitMetadata;
-use quickwit_storage::{PutPayload, Storage, StorageResult};
+use quickwit_storage::{
+    wrap_storage_with_request_accounting, PutPayload, Storage, StorageOperation,
+    StorageRequestContext, StorageResult,
+};
 use tantivy::directory::{Advice, MmapDirectory};
 use tantivy::Directory;
 use time::OffsetDateTime;
@@ -102,6 +105,21 @@ impl IndexingSplitStore {
         PathBuf::from(quickwit_common::split_file(split_id))
     }
 
+    /// Returns the remote storage, recording the requests issued through it on behalf of
+    /// `operation` on the index of `split`.
+    fn remote_storage_for(
+        &self,
+        split: &SplitMetadata,
+        operation: StorageOperation,
+    ) -> Arc<dyn Storage> {
+        let storage_request_context =
+            StorageRequestContext::new(split.index_uid.index_id(), operation);
+        wrap_storage_with_request_accounting(
+            self.inner.remote_storage.clone(),
+            storage_request_context,
+        )
+    }
+
     /// Stores a split.
     ///
     /// If a split is identified as mature by the merge policy,
@@ -123,8 +141,7 @@ impl IndexingSplitStore {
 
         let key = self.split_path(split.split_id());
         let is_mature = split.is_mature(OffsetDateTime::now_utc());
-        self.inner
-            .remote_storage
+        self.remote_storage_for(split, StorageOperation::IndexingUpload)
             .put(&key, put_payload)
             .instrument(info_span!("store_split_in_remote_storage", split=?split.split_id(), is_mature=is_mature, num_bytes=split_num_bytes))
             .await
@@ -180,13 +197,14 @@ impl IndexingSplitStore {
     ///
     /// As we fetch the split, we optimistically assume that this is for a merge
     /// operation that will be successful and we remove the split from the cache.
-    #[instrument(skip(self, output_dir_path, io_controls), fields(cache_hit))]
+    #[instrument(skip_all, fields(split_id=split.split_id(), cache_hit))]
     pub async fn fetch_and_open_split(
         &self,
-        split_id: &str,
+        split: &SplitMetadata,
         output_dir_path: &Path,
         io_controls: &IoControls,
     ) -> StorageResult<Box<dyn Directory>> {
+        let split_id = split.split_id();
         let path = PathBuf::from(quickwit_common::split_file(split_id));
         if let Some(split_path) = self
             .inner
@@ -206,8 +224,7 @@ impl IndexingSplitStore {
         let dest_filepath = output_dir_path.join(&path);
         let dest_file = tokio::fs::File::create(&dest_filepath).await?;
         let mut dest_file_with_write_limit = io_controls.clone().wrap_write(dest_file);
-        self.inner
-            .remote_storage
+        self.remote_storage_for(split, StorageOperation::Merge)
             .copy_to(&path, &mut dest_file_with_write_limit)
             .instrument(info_span!("fetch_split_from_remote_storage", path=?path))
             .await?;
@@ -393,11 +410,19 @@ mod tests {
             let io_controls = IoControls::default();
             // get from cache
             let _split1 = split_store
-                .fetch_and_open_split(&split_id1, output.path(), &io_controls)
+                .fetch_and_open_split(
+                    &create_test_split_metadata(&split_id1),
+                    output.path(),
+                    &io_controls,
+                )
                 .await?;
             // get from remote storage
             let _split2 = split_store
-                .fetch_and_open_split(&split_id2, output.path(), &io_controls)
+                .fetch_and_open_split(
+                    &create_test_split_metadata(&split_id2),
+                    output.path(),
+                    &io_controls,
+                )
                 .await?;
         }
         Ok(())
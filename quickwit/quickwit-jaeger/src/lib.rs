@@ -111,6 +111,8 @@ impl JaegerService {
             end_timestamp: None,
             start_key: None,
             end_key: None,
+            prefix: None,
+            regex: None,
         };
         let search_response = self.search_service.root_list_terms(search_request).await?;
         let services: Vec<String> = search_response
@@ -148,6 +150,8 @@ impl JaegerService {
             end_timestamp: None,
             start_key,
             end_key,
+            prefix: None,
+            regex: None,
         };
         let search_response = self.search_service.root_list_terms(search_request).await?;
         let operations: Vec<Operation> = search_response
@@ -2436,6 +2440,7 @@ mod tests {
                     ],
                     elapsed_time_micros: 0,
                     errors: Vec::new(),
+                    doc_freqs: Vec::new(),
                 })
             });
 
@@ -2467,6 +2472,7 @@ mod tests {
                     ],
                     elapsed_time_micros: 0,
                     errors: Vec::new(),
+                    doc_freqs: Vec::new(),
                 })
             });
 
@@ -31,7 +31,9 @@ use quickwit_metastore::ListIndexesMetadataResponseExt;
 use quickwit_proto::metastore::{
     ListIndexesMetadataRequest, MetastoreService, MetastoreServiceClient,
 };
-use quickwit_storage::StorageResolver;
+use quickwit_storage::{
+    wrap_storage_with_request_accounting, StorageOperation, StorageRequestContext, StorageResolver,
+};
 use serde::Serialize;
 use tracing::{error, info};
 
@@ -108,7 +110,10 @@ impl GarbageCollector {
             async move {
             let index_uri = index.index_uri();
             let storage = match storage_resolver.resolve(index_uri).await {
-                Ok(storage) => storage,
+                Ok(storage) => wrap_storage_with_request_accounting(
+                    storage,
+                    StorageRequestContext::new(index.index_id(), StorageOperation::GarbageCollection),
+                ),
                 Err(error) => {
                     error!(index=%index.index_id(), error=?error, "failed to resolve the index storage Uri");
                     return None;
@@ -118,6 +123,7 @@ impl GarbageCollector {
             let gc_res = run_garbage_collect(
                 index_uid.clone(),
                 storage,
+                &storage_resolver,
                 metastore,
                 STAGED_GRACE_PERIOD,
                 DELETION_GRACE_PERIOD,
@@ -330,6 +336,7 @@ mod tests {
         let result = run_garbage_collect(
             "test-index:11111111111111111111111111".to_string().into(),
             Arc::new(mock_storage),
+            &StorageResolver::for_test(),
             MetastoreServiceClient::from(mock_metastore),
             STAGED_GRACE_PERIOD,
             DELETION_GRACE_PERIOD,
@@ -22,7 +22,9 @@ mod delete_task_planner;
 mod delete_task_service;
 mod garbage_collector;
 mod retention_policy_executor;
+mod tiering_policy_executor;
 
 pub use delete_task_service::{DeleteTaskService, DELETE_SERVICE_TASK_DIR_NAME};
 pub use garbage_collector::GarbageCollector;
 pub use retention_policy_executor::RetentionPolicyExecutor;
+pub use tiering_policy_executor::TieringPolicyExecutor;
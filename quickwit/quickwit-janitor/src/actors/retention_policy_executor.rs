@@ -236,7 +236,7 @@ impl Handler<Execute> for RetentionPolicyExecutor {
 }
 
 /// Extract the list of deleted indexes.
-fn compute_deleted_indexes<'a>(
+pub(super) fn compute_deleted_indexes<'a>(
     cached_indexes: impl Iterator<Item = &'a str>,
     indexes: impl Iterator<Item = &'a str>,
 ) -> HashSet<String> {
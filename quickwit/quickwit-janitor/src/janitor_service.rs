@@ -23,12 +23,15 @@ use quickwit_actors::{
 };
 use serde_json::{json, Value as JsonValue};
 
-use crate::actors::{DeleteTaskService, GarbageCollector, RetentionPolicyExecutor};
+use crate::actors::{
+    DeleteTaskService, GarbageCollector, RetentionPolicyExecutor, TieringPolicyExecutor,
+};
 
 pub struct JanitorService {
     delete_task_service_handle: ActorHandle<DeleteTaskService>,
     garbage_collector_handle: ActorHandle<GarbageCollector>,
     retention_policy_executor_handle: ActorHandle<RetentionPolicyExecutor>,
+    tiering_policy_executor_handle: ActorHandle<TieringPolicyExecutor>,
 }
 
 impl JanitorService {
@@ -36,11 +39,13 @@ impl JanitorService {
         delete_task_service_handle: ActorHandle<DeleteTaskService>,
         garbage_collector_handle: ActorHandle<GarbageCollector>,
         retention_policy_executor_handle: ActorHandle<RetentionPolicyExecutor>,
+        tiering_policy_executor_handle: ActorHandle<TieringPolicyExecutor>,
     ) -> Self {
         Self {
             delete_task_service_handle,
             garbage_collector_handle,
             retention_policy_executor_handle,
+            tiering_policy_executor_handle,
         }
     }
 
@@ -48,6 +53,7 @@ impl JanitorService {
         self.delete_task_service_handle.state() != ActorState::Failure
             && self.garbage_collector_handle.state() != ActorState::Failure
             && self.retention_policy_executor_handle.state() != ActorState::Failure
+            && self.tiering_policy_executor_handle.state() != ActorState::Failure
     }
 }
 
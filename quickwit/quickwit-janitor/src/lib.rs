@@ -33,10 +33,13 @@ pub mod error;
 mod janitor_service;
 mod metrics;
 mod retention_policy_execution;
+mod tiering_policy_execution;
 
 pub use janitor_service::JanitorService;
 
-use crate::actors::{DeleteTaskService, GarbageCollector, RetentionPolicyExecutor};
+use crate::actors::{
+    DeleteTaskService, GarbageCollector, RetentionPolicyExecutor, TieringPolicyExecutor,
+};
 
 #[derive(utoipa::OpenApi)]
 #[openapi(components(schemas(SplitInfo)))]
@@ -58,6 +61,15 @@ pub async fn start_janitor_service(
     let retention_policy_executor = RetentionPolicyExecutor::new(metastore.clone());
     let (_, retention_policy_executor_handle) =
         universe.spawn_builder().spawn(retention_policy_executor);
+
+    let tiering_policy_executor = TieringPolicyExecutor::new(
+        metastore.clone(),
+        storage_resolver.clone(),
+        config.data_dir_path.clone(),
+    )
+    .await?;
+    let (_, tiering_policy_executor_handle) =
+        universe.spawn_builder().spawn(tiering_policy_executor);
     let delete_task_service = DeleteTaskService::new(
         metastore,
         search_job_placer,
@@ -73,6 +85,7 @@ pub async fn start_janitor_service(
         delete_task_service_handle,
         garbage_collector_handle,
         retention_policy_executor_handle,
+        tiering_policy_executor_handle,
     );
     let (janitor_service_mailbox, _janitor_service_handle) =
         universe.spawn_builder().spawn(janitor_service);
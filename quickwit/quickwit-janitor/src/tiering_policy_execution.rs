@@ -0,0 +1,164 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use quickwit_actors::ActorContext;
+use quickwit_common::uri::Uri;
+use quickwit_common::{split_file, PrettySample};
+use quickwit_config::TieringPolicy;
+use quickwit_indexing::new_split_id;
+use quickwit_metastore::{
+    ListSplitsQuery, ListSplitsRequestExt, MetastoreServiceStreamSplitsExt, SplitMetadata,
+    SplitState, StageSplitsRequestExt,
+};
+use quickwit_proto::metastore::{
+    ListSplitsRequest, MetastoreService, MetastoreServiceClient, PublishSplitsRequest,
+    StageSplitsRequest,
+};
+use quickwit_proto::types::{IndexUid, SplitId};
+use quickwit_storage::{FilePayload, StorageResolver};
+use time::OffsetDateTime;
+use tracing::info;
+
+use crate::actors::TieringPolicyExecutor;
+
+/// Detect all mature splits that reached the age of a storage tier and move them to the tier
+/// storage. Each split is copied under a new split ID to the tier storage, and the copy is
+/// published in place of the split. Like the splits replaced by a merge, the replaced split is
+/// marked for deletion and its file is removed by the garbage collector once the deletion grace
+/// period has elapsed, so that in-flight searches can still read it.
+///
+/// * `index_uid` - The target index uid.
+/// * `index_uri` - The URI of the target index storage.
+/// * `metastore` - The metastore managing the target index.
+/// * `storage_resolver` - The resolver of the index and tier storages.
+/// * `tiering_policy` - The tiering policy used to evaluate the splits.
+/// * `scratch_directory` - The directory in which split files are downloaded before being uploaded
+///   to the tier storage.
+/// * `ctx` - A context for reporting progress.
+pub async fn run_execute_tiering_policy(
+    index_uid: IndexUid,
+    index_uri: &Uri,
+    mut metastore: MetastoreServiceClient,
+    storage_resolver: &StorageResolver,
+    tiering_policy: &TieringPolicy,
+    scratch_directory: &Path,
+    ctx: &ActorContext<TieringPolicyExecutor>,
+) -> anyhow::Result<Vec<SplitMetadata>> {
+    let Some(first_tier) = tiering_policy.tiers.first() else {
+        return Ok(Vec::new());
+    };
+    // Select mature published splits older than the age of the first tier.
+    let now = OffsetDateTime::now_utc();
+    let current_timestamp = now.unix_timestamp();
+    let max_tiering_timestamp = current_timestamp - first_tier.min_age()?.as_secs() as i64;
+    let query = ListSplitsQuery::for_index(index_uid.clone())
+        .with_split_state(SplitState::Published)
+        .with_time_range_end_lte(max_tiering_timestamp)
+        .retain_mature(now);
+
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(query)?;
+    let candidate_splits: Vec<SplitMetadata> = ctx
+        .protect_future(metastore.list_splits(list_splits_request))
+        .await?
+        .collect_splits_metadata()
+        .await?;
+
+    let mut splits_per_tier: HashMap<&Uri, Vec<SplitMetadata>> = HashMap::new();
+
+    for split_metadata in candidate_splits {
+        let Some(time_range) = &split_metadata.time_range else {
+            continue;
+        };
+        let Some(target_tier) = tiering_policy.target_tier(*time_range.end(), current_timestamp)?
+        else {
+            continue;
+        };
+        if split_metadata.storage_uri.as_ref() == Some(&target_tier.storage_uri) {
+            continue;
+        }
+        splits_per_tier
+            .entry(&target_tier.storage_uri)
+            .or_default()
+            .push(split_metadata);
+    }
+    let mut moved_splits = Vec::new();
+
+    for (tier_storage_uri, splits) in splits_per_tier {
+        let split_ids: Vec<SplitId> = splits
+            .iter()
+            .map(|split_metadata| split_metadata.split_id.clone())
+            .collect();
+        info!(
+            index_id=%index_uid.index_id(),
+            split_ids=?PrettySample::new(&split_ids, 5),
+            "Moving {} splits to storage tier `{tier_storage_uri}` based on tiering policy.",
+            split_ids.len()
+        );
+        let tier_storage = storage_resolver.resolve(tier_storage_uri).await?;
+
+        // Splits are copied one at a time to bound the disk space used by the executor.
+        for split_metadata in splits {
+            let source_storage_uri = split_metadata.storage_uri.as_ref().unwrap_or(index_uri);
+            let source_storage = storage_resolver.resolve(source_storage_uri).await?;
+            let split_path = PathBuf::from(split_file(split_metadata.split_id()));
+
+            let tiered_split_metadata = SplitMetadata {
+                split_id: new_split_id(),
+                storage_uri: Some(tier_storage_uri.clone()),
+                ..split_metadata.clone()
+            };
+            let tiered_split_path = PathBuf::from(split_file(tiered_split_metadata.split_id()));
+
+            // The copy is staged before being uploaded: if the upload or the publication fails,
+            // the garbage collector removes the staged split and its file.
+            let stage_splits_request = StageSplitsRequest::try_from_split_metadata(
+                index_uid.clone(),
+                tiered_split_metadata.clone(),
+            )?;
+            ctx.protect_future(metastore.stage_splits(stage_splits_request))
+                .await?;
+
+            let scratch_split_path = scratch_directory.join(&split_path);
+            ctx.protect_future(source_storage.copy_to_file(&split_path, &scratch_split_path))
+                .await?;
+            let split_payload = FilePayload::from_path(&scratch_split_path)?;
+            let upload_result = ctx
+                .protect_future(tier_storage.put(&tiered_split_path, Box::new(split_payload)))
+                .await;
+            tokio::fs::remove_file(&scratch_split_path).await?;
+            upload_result?;
+
+            let publish_splits_request = PublishSplitsRequest {
+                index_uid: index_uid.to_string(),
+                staged_split_ids: vec![tiered_split_metadata.split_id.clone()],
+                replaced_split_ids: vec![split_metadata.split_id.clone()],
+                index_checkpoint_delta_json_opt: None,
+                publish_token_opt: None,
+            };
+            ctx.protect_future(metastore.publish_splits(publish_splits_request))
+                .await?;
+            ctx.record_progress();
+            moved_splits.push(tiered_split_metadata);
+        }
+    }
+    Ok(moved_splits)
+}
@@ -106,7 +106,7 @@ async fn load_index_config(
     default_index_root_uri: &Uri,
 ) -> anyhow::Result<IndexConfig> {
     let (dir, file) = dir_and_filename(Path::new(&*INDEX_CONFIG_URI))?;
-    let index_config_storage = resolver.resolve(&dir).await?;
+    let index_config_storage = resolver.resolve_unencrypted(&dir).await?;
     let bytes = index_config_storage.get_all(file).await?;
     let mut index_config = load_index_config_from_user_config(
         ConfigFormat::Yaml,
@@ -34,7 +34,10 @@ pub(crate) async fn load_node_config(
         .await
         .with_context(|| format!("Failed to parse node config `{config_template}`."))?;
     info!(config=?config, "loaded node config");
-    let storage_resolver = StorageResolver::configured(&config.storage_configs);
+    let mut storage_resolver = StorageResolver::configured(&config.storage_configs);
+    if let Some(storage_encryption_config) = &config.storage_encryption_config {
+        storage_resolver = storage_resolver.with_encryption(storage_encryption_config)?;
+    }
     let metastore_resolver =
         MetastoreResolver::configured(storage_resolver.clone(), &config.metastore_configs);
     let metastore: MetastoreServiceClient =
@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS shards;
+DROP TABLE IF EXISTS delete_tasks;
+DROP TABLE IF EXISTS splits;
+DROP TABLE IF EXISTS indexes;
@@ -0,0 +1,55 @@
+-- The SQLite schema mirrors the latest version of the PostgreSQL schema with the following
+-- differences:
+-- - timestamps are stored as UNIX timestamps in seconds;
+-- - split tags are stored as a JSON array;
+-- - shard states are stored as text.
+
+CREATE TABLE IF NOT EXISTS indexes (
+    index_uid VARCHAR(282) PRIMARY KEY,
+    index_id VARCHAR(255) NOT NULL UNIQUE,
+    index_metadata_json TEXT NOT NULL,
+    create_timestamp INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER))
+);
+
+CREATE TABLE IF NOT EXISTS splits (
+    split_id VARCHAR(50) PRIMARY KEY,
+    split_state VARCHAR(30) NOT NULL,
+    time_range_start BIGINT,
+    time_range_end BIGINT,
+    tags TEXT NOT NULL DEFAULT '[]',
+    split_metadata_json TEXT NOT NULL,
+    index_uid VARCHAR(282) NOT NULL,
+    create_timestamp INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER)),
+    update_timestamp INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER)),
+    publish_timestamp INTEGER DEFAULT NULL,
+    maturity_timestamp INTEGER NOT NULL DEFAULT 0,
+    delete_opstamp BIGINT NOT NULL DEFAULT 0 CHECK (delete_opstamp >= 0),
+
+    FOREIGN KEY (index_uid) REFERENCES indexes (index_uid) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS splits_index_uid_idx ON splits (index_uid);
+
+CREATE TABLE IF NOT EXISTS delete_tasks (
+    create_timestamp INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER)),
+    opstamp INTEGER PRIMARY KEY AUTOINCREMENT,
+    index_uid VARCHAR(282) NOT NULL,
+    delete_query_json TEXT NOT NULL,
+
+    FOREIGN KEY (index_uid) REFERENCES indexes (index_uid) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS shards (
+    index_uid VARCHAR(282) NOT NULL,
+    source_id VARCHAR(255) NOT NULL,
+    shard_id VARCHAR(255) NOT NULL,
+    leader_id VARCHAR(255) NOT NULL,
+    follower_id VARCHAR(255),
+    shard_state VARCHAR(30) NOT NULL DEFAULT 'open'
+        CHECK (shard_state IN ('unspecified', 'open', 'unavailable', 'closed')),
+    publish_position_inclusive VARCHAR(255) NOT NULL DEFAULT '',
+    publish_token VARCHAR(255),
+
+    PRIMARY KEY (index_uid, source_id, shard_id),
+    FOREIGN KEY (index_uid) REFERENCES indexes (index_uid) ON DELETE CASCADE
+);
@@ -463,6 +463,23 @@ impl SourceCheckpointDelta {
         Ok(delta)
     }
 
+    /// Creates the checkpoint delta going from the beginning of every partition of a source
+    /// checkpoint to its positions. Applying it to an empty checkpoint recreates the source
+    /// checkpoint.
+    pub fn from_source_checkpoint(source_checkpoint: &SourceCheckpoint) -> Self {
+        let mut delta = SourceCheckpointDelta::default();
+
+        for (partition_id, position) in source_checkpoint.iter() {
+            if position == Position::Beginning {
+                continue;
+            }
+            delta
+                .record_partition_delta(partition_id, Position::Beginning, position)
+                .expect("partitions should be unique and positions should be past the beginning");
+        }
+        delta
+    }
+
     /// Returns the checkpoint associated with the endpoint of the delta.
     pub fn get_source_checkpoint(&self) -> SourceCheckpoint {
         let mut source_checkpoint = SourceCheckpoint::default();
@@ -817,4 +834,29 @@ mod tests {
             &Position::offset(43u64)
         );
     }
+
+    #[test]
+    fn test_source_checkpoint_delta_from_source_checkpoint() {
+        let mut checkpoint = SourceCheckpoint::default();
+        checkpoint.add_partition(PartitionId::from("a"), Position::offset(42u64));
+        checkpoint.add_partition(PartitionId::from("b"), Position::Beginning);
+        checkpoint.add_partition(PartitionId::from("c"), Position::eof(7u64));
+
+        let delta = SourceCheckpointDelta::from_source_checkpoint(&checkpoint);
+        assert_eq!(delta.num_partitions(), 2);
+
+        let mut restored_checkpoint = SourceCheckpoint::default();
+        restored_checkpoint.try_apply_delta(delta).unwrap();
+        assert_eq!(
+            restored_checkpoint.position_for_partition(&PartitionId::from("a")),
+            Some(&Position::offset(42u64))
+        );
+        assert!(restored_checkpoint
+            .position_for_partition(&PartitionId::from("b"))
+            .is_none());
+        assert_eq!(
+            restored_checkpoint.position_for_partition(&PartitionId::from("c")),
+            Some(&Position::eof(7u64))
+        );
+    }
 }
@@ -33,6 +33,7 @@ pub mod checkpoint;
 mod error;
 mod metastore;
 mod metastore_factory;
+mod metastore_migration;
 mod metastore_resolver;
 mod split_metadata;
 mod split_metadata_version;
@@ -42,11 +43,14 @@ pub(crate) mod tests;
 use std::ops::Range;
 
 pub use error::MetastoreResolverError;
+pub use metastore::caching_metastore::CachingMetastore;
 pub use metastore::control_plane_metastore::ControlPlaneMetastore;
 pub use metastore::file_backed::FileBackedMetastore;
 pub(crate) use metastore::index_metadata::serialize::{IndexMetadataV0_7, VersionedIndexMetadata};
 #[cfg(feature = "postgres")]
 pub use metastore::postgres::PostgresqlMetastore;
+#[cfg(feature = "sqlite")]
+pub use metastore::sqlite::SqliteMetastore;
 pub use metastore::{
     file_backed, AddSourceRequestExt, CreateIndexRequestExt, IndexMetadata,
     IndexMetadataResponseExt, ListIndexesMetadataResponseExt, ListSplitsQuery,
@@ -54,6 +58,7 @@ pub use metastore::{
     MetastoreServiceStreamSplitsExt, PublishSplitsRequestExt, StageSplitsRequestExt,
 };
 pub use metastore_factory::{MetastoreFactory, UnsupportedMetastore};
+pub use metastore_migration::{migrate_metastore, IndexMigrationSummary};
 pub use metastore_resolver::MetastoreResolver;
 use quickwit_common::is_disjoint;
 use quickwit_doc_mapper::tag_pruning::TagFilterAst;
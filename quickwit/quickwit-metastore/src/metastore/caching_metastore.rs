@@ -0,0 +1,619 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use quickwit_common::uri::Uri;
+use quickwit_common::ServiceStream;
+use quickwit_proto::metastore::{
+    AcquireShardsRequest, AcquireShardsResponse, AddSourceRequest, CreateIndexRequest,
+    CreateIndexResponse, DeleteIndexRequest, DeleteQuery, DeleteShardsRequest,
+    DeleteShardsResponse, DeleteSourceRequest, DeleteSplitsRequest, DeleteTask, EmptyResponse,
+    IndexMetadataRequest, IndexMetadataResponse, LastDeleteOpstampRequest,
+    LastDeleteOpstampResponse, ListDeleteTasksRequest, ListDeleteTasksResponse,
+    ListIndexesMetadataRequest, ListIndexesMetadataResponse, ListShardsRequest, ListShardsResponse,
+    ListSplitsRequest, ListSplitsResponse, ListStaleSplitsRequest, MarkSplitsForDeletionRequest,
+    MetastoreResult, MetastoreService, MetastoreServiceClient, MetastoreServiceStream,
+    OpenShardsRequest, OpenShardsResponse, PublishSplitsRequest, ResetSourceCheckpointRequest,
+    StageSplitsRequest, ToggleSourceRequest, UpdateSplitsDeleteOpstampRequest,
+    UpdateSplitsDeleteOpstampResponse,
+};
+use quickwit_proto::types::{IndexUid, SplitId};
+use tracing::debug;
+
+use super::STREAM_SPLITS_CHUNK_SIZE;
+use crate::file_backed::file_backed_index::split_query_predicate;
+use crate::{
+    ListSplitsQuery, ListSplitsRequestExt, ListSplitsResponseExt, MetastoreServiceStreamSplitsExt,
+    Split, SplitState,
+};
+
+/// Splits updated within this many seconds before the most recent update timestamp observed in a
+/// refresh are fetched again during the next refresh. This accounts for transactions committing
+/// with an update timestamp older than the ones already observed.
+const REFRESH_OVERLAP_SECS: i64 = 60;
+
+/// A [`MetastoreService`] implementation that caches the list of published splits of each index.
+///
+/// It is meant to be used by searchers, which list the published splits of the targeted indexes on
+/// every search request. The cached list of an index is refreshed incrementally, i.e. by only
+/// fetching the splits updated since the last refresh, when it is older than `max_staleness` or
+/// after it has been invalidated. All the other requests are forwarded to the underlying
+/// metastore.
+#[derive(Clone)]
+pub struct CachingMetastore {
+    metastore: MetastoreServiceClient,
+    max_staleness: Duration,
+    entries: Arc<Mutex<HashMap<IndexUid, Arc<CacheEntry>>>>,
+}
+
+impl fmt::Debug for CachingMetastore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CachingMetastore")
+            .field("max_staleness", &self.max_staleness)
+            .finish()
+    }
+}
+
+#[derive(Default)]
+struct CacheEntry {
+    invalidated: AtomicBool,
+    cached_splits_opt: tokio::sync::Mutex<Option<CachedSplits>>,
+}
+
+struct CachedSplits {
+    splits: HashMap<SplitId, Split>,
+    max_update_timestamp: i64,
+    refreshed_at: Instant,
+}
+
+impl CachedSplits {
+    fn apply_updates(&mut self, updated_splits: Vec<Split>) {
+        for split in updated_splits {
+            self.max_update_timestamp = self.max_update_timestamp.max(split.update_timestamp);
+
+            if split.split_state == SplitState::Published {
+                self.splits.insert(split.split_id().to_string(), split);
+            } else {
+                self.splits.remove(split.split_id());
+            }
+        }
+        self.refreshed_at = Instant::now();
+    }
+}
+
+/// Returns whether the splits matching `query` can be served from the cache, i.e. whether the
+/// query targets published splits only and does not require pagination or sorting.
+fn is_cacheable(query: &ListSplitsQuery) -> bool {
+    query.split_states == [SplitState::Published]
+        && query.limit.is_none()
+        && query.offset.is_none()
+        && !query.sort_by_staleness
+}
+
+impl CachingMetastore {
+    /// Creates a new [`CachingMetastore`].
+    pub fn new(metastore: MetastoreServiceClient, max_staleness: Duration) -> Self {
+        Self {
+            metastore,
+            max_staleness,
+            entries: Default::default(),
+        }
+    }
+
+    /// Marks the cached list of splits of the index as stale so that it gets refreshed on the next
+    /// request.
+    pub fn invalidate(&self, index_uid: &IndexUid) {
+        let entries = self.entries.lock().expect("lock should not be poisoned");
+
+        if let Some(entry) = entries.get(index_uid) {
+            entry.invalidated.store(true, Ordering::Release);
+        }
+    }
+
+    fn entry(&self, index_uid: &IndexUid) -> Arc<CacheEntry> {
+        self.entries
+            .lock()
+            .expect("lock should not be poisoned")
+            .entry(index_uid.clone())
+            .or_default()
+            .clone()
+    }
+
+    async fn refresh(
+        &mut self,
+        index_uid: &IndexUid,
+        entry: &CacheEntry,
+        cached_splits_opt: &mut Option<CachedSplits>,
+    ) -> MetastoreResult<()> {
+        if cached_splits_opt.is_none() {
+            // Clear the flag before fetching the splits so that an invalidation occurring in the
+            // meantime is not lost.
+            entry.invalidated.store(false, Ordering::Release);
+
+            let query = ListSplitsQuery::for_index(index_uid.clone())
+                .with_split_state(SplitState::Published);
+            let request = ListSplitsRequest::try_from_list_splits_query(query)?;
+            let splits = self
+                .metastore
+                .list_splits(request)
+                .await?
+                .collect_splits()
+                .await?;
+
+            debug!(
+                index_uid=%index_uid,
+                num_splits=splits.len(),
+                "loaded published splits into cache"
+            );
+            let mut cached_splits = CachedSplits {
+                splits: HashMap::with_capacity(splits.len()),
+                max_update_timestamp: 0,
+                refreshed_at: Instant::now(),
+            };
+            cached_splits.apply_updates(splits);
+            *cached_splits_opt = Some(cached_splits);
+            return Ok(());
+        }
+        let cached_splits = cached_splits_opt
+            .as_mut()
+            .expect("splits should have been loaded");
+        let is_invalidated = entry.invalidated.swap(false, Ordering::AcqRel);
+
+        if !is_invalidated && cached_splits.refreshed_at.elapsed() < self.max_staleness {
+            return Ok(());
+        }
+        let query = ListSplitsQuery::for_index(index_uid.clone())
+            .with_update_timestamp_gte(cached_splits.max_update_timestamp - REFRESH_OVERLAP_SECS);
+        let request = ListSplitsRequest::try_from_list_splits_query(query)?;
+        let updated_splits_res = match self.metastore.list_splits(request).await {
+            Ok(stream) => stream.collect_splits().await,
+            Err(error) => Err(error),
+        };
+        let updated_splits = match updated_splits_res {
+            Ok(updated_splits) => updated_splits,
+            Err(error) => {
+                if is_invalidated {
+                    entry.invalidated.store(true, Ordering::Release);
+                }
+                return Err(error);
+            }
+        };
+        debug!(
+            index_uid=%index_uid,
+            num_updated_splits=updated_splits.len(),
+            "refreshed cached published splits"
+        );
+        cached_splits.apply_updates(updated_splits);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MetastoreService for CachingMetastore {
+    fn endpoints(&self) -> Vec<Uri> {
+        self.metastore.endpoints()
+    }
+
+    async fn check_connectivity(&mut self) -> anyhow::Result<()> {
+        self.metastore.check_connectivity().await
+    }
+
+    async fn create_index(
+        &mut self,
+        request: CreateIndexRequest,
+    ) -> MetastoreResult<CreateIndexResponse> {
+        self.metastore.create_index(request).await
+    }
+
+    async fn delete_index(
+        &mut self,
+        request: DeleteIndexRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        let index_uid: IndexUid = request.index_uid.clone().into();
+        self.metastore.delete_index(request).await?;
+        self.entries
+            .lock()
+            .expect("lock should not be poisoned")
+            .remove(&index_uid);
+        Ok(EmptyResponse {})
+    }
+
+    async fn add_source(&mut self, request: AddSourceRequest) -> MetastoreResult<EmptyResponse> {
+        self.metastore.add_source(request).await
+    }
+
+    async fn toggle_source(
+        &mut self,
+        request: ToggleSourceRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        self.metastore.toggle_source(request).await
+    }
+
+    async fn delete_source(
+        &mut self,
+        request: DeleteSourceRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        self.metastore.delete_source(request).await
+    }
+
+    async fn index_metadata(
+        &mut self,
+        request: IndexMetadataRequest,
+    ) -> MetastoreResult<IndexMetadataResponse> {
+        self.metastore.index_metadata(request).await
+    }
+
+    async fn list_indexes_metadata(
+        &mut self,
+        request: ListIndexesMetadataRequest,
+    ) -> MetastoreResult<ListIndexesMetadataResponse> {
+        self.metastore.list_indexes_metadata(request).await
+    }
+
+    async fn stage_splits(
+        &mut self,
+        request: StageSplitsRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        self.metastore.stage_splits(request).await
+    }
+
+    async fn publish_splits(
+        &mut self,
+        request: PublishSplitsRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        self.metastore.publish_splits(request).await
+    }
+
+    async fn list_splits(
+        &mut self,
+        request: ListSplitsRequest,
+    ) -> MetastoreResult<MetastoreServiceStream<ListSplitsResponse>> {
+        let query = request.deserialize_list_splits_query()?;
+
+        if !is_cacheable(&query) {
+            return self.metastore.list_splits(request).await;
+        }
+        let mut splits = Vec::new();
+
+        for index_uid in &query.index_uids {
+            let entry = self.entry(index_uid);
+            let mut cached_splits_opt = entry.cached_splits_opt.lock().await;
+            self.refresh(index_uid, &entry, &mut cached_splits_opt)
+                .await?;
+            let cached_splits = cached_splits_opt
+                .as_ref()
+                .expect("splits should have been loaded");
+            splits.extend(
+                cached_splits
+                    .splits
+                    .values()
+                    .filter(|split| split_query_predicate(split, &query))
+                    .cloned(),
+            );
+        }
+        let mut split_iter = splits.into_iter().peekable();
+        let mut list_splits_responses = Vec::new();
+
+        while split_iter.peek().is_some() {
+            let splits_chunk: Vec<Split> =
+                split_iter.by_ref().take(STREAM_SPLITS_CHUNK_SIZE).collect();
+            list_splits_responses.push(ListSplitsResponse::try_from_splits(splits_chunk));
+        }
+        Ok(ServiceStream::from(list_splits_responses))
+    }
+
+    async fn list_stale_splits(
+        &mut self,
+        request: ListStaleSplitsRequest,
+    ) -> MetastoreResult<ListSplitsResponse> {
+        self.metastore.list_stale_splits(request).await
+    }
+
+    async fn mark_splits_for_deletion(
+        &mut self,
+        request: MarkSplitsForDeletionRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        self.metastore.mark_splits_for_deletion(request).await
+    }
+
+    async fn delete_splits(
+        &mut self,
+        request: DeleteSplitsRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        self.metastore.delete_splits(request).await
+    }
+
+    async fn reset_source_checkpoint(
+        &mut self,
+        request: ResetSourceCheckpointRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        self.metastore.reset_source_checkpoint(request).await
+    }
+
+    // Delete tasks API
+
+    async fn create_delete_task(
+        &mut self,
+        delete_query: DeleteQuery,
+    ) -> MetastoreResult<DeleteTask> {
+        self.metastore.create_delete_task(delete_query).await
+    }
+
+    async fn last_delete_opstamp(
+        &mut self,
+        request: LastDeleteOpstampRequest,
+    ) -> MetastoreResult<LastDeleteOpstampResponse> {
+        self.metastore.last_delete_opstamp(request).await
+    }
+
+    async fn update_splits_delete_opstamp(
+        &mut self,
+        request: UpdateSplitsDeleteOpstampRequest,
+    ) -> MetastoreResult<UpdateSplitsDeleteOpstampResponse> {
+        self.metastore.update_splits_delete_opstamp(request).await
+    }
+
+    async fn list_delete_tasks(
+        &mut self,
+        request: ListDeleteTasksRequest,
+    ) -> MetastoreResult<ListDeleteTasksResponse> {
+        self.metastore.list_delete_tasks(request).await
+    }
+
+    // Shard API
+
+    async fn open_shards(
+        &mut self,
+        request: OpenShardsRequest,
+    ) -> MetastoreResult<OpenShardsResponse> {
+        self.metastore.open_shards(request).await
+    }
+
+    async fn acquire_shards(
+        &mut self,
+        request: AcquireShardsRequest,
+    ) -> MetastoreResult<AcquireShardsResponse> {
+        self.metastore.acquire_shards(request).await
+    }
+
+    async fn list_shards(
+        &mut self,
+        request: ListShardsRequest,
+    ) -> MetastoreResult<ListShardsResponse> {
+        self.metastore.list_shards(request).await
+    }
+
+    async fn delete_shards(
+        &mut self,
+        request: DeleteShardsRequest,
+    ) -> MetastoreResult<DeleteShardsResponse> {
+        self.metastore.delete_shards(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use quickwit_proto::metastore::MetastoreError;
+
+    use super::*;
+    use crate::SplitMetadata;
+
+    fn make_split(index_uid: &IndexUid, split_id: &str, split_state: SplitState) -> Split {
+        Split {
+            split_state,
+            update_timestamp: 1_000,
+            publish_timestamp: None,
+            split_metadata: SplitMetadata {
+                split_id: split_id.to_string(),
+                index_uid: index_uid.clone(),
+                time_range: Some(0..=100),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn make_list_splits_stream(
+        splits: Vec<Split>,
+    ) -> MetastoreResult<MetastoreServiceStream<ListSplitsResponse>> {
+        let response = ListSplitsResponse::try_from_splits(splits).unwrap();
+        Ok(ServiceStream::from(vec![Ok(response)]))
+    }
+
+    async fn list_split_ids(
+        metastore: &mut CachingMetastore,
+        query: ListSplitsQuery,
+    ) -> MetastoreResult<Vec<SplitId>> {
+        let request = ListSplitsRequest::try_from_list_splits_query(query).unwrap();
+        let mut split_ids = metastore
+            .list_splits(request)
+            .await?
+            .collect_split_ids()
+            .await?;
+        split_ids.sort();
+        Ok(split_ids)
+    }
+
+    #[tokio::test]
+    async fn test_caching_metastore_serves_published_splits_from_cache() {
+        let index_uid = IndexUid::new_with_random_ulid("test-index");
+        let index_uid_clone = index_uid.clone();
+
+        let mut mock_metastore = MetastoreServiceClient::mock();
+        mock_metastore
+            .expect_list_splits()
+            .times(1)
+            .returning(move |request| {
+                let query = request.deserialize_list_splits_query().unwrap();
+                assert_eq!(query.index_uids, vec![index_uid_clone.clone()]);
+                assert_eq!(query.split_states, vec![SplitState::Published]);
+
+                let mut split_1 = make_split(&index_uid_clone, "split-1", SplitState::Published);
+                split_1.split_metadata.time_range = Some(200..=300);
+                let split_2 = make_split(&index_uid_clone, "split-2", SplitState::Published);
+                make_list_splits_stream(vec![split_1, split_2])
+            });
+        let mut caching_metastore = CachingMetastore::new(
+            MetastoreServiceClient::from(mock_metastore),
+            Duration::from_secs(3600),
+        );
+        let query =
+            ListSplitsQuery::for_index(index_uid.clone()).with_split_state(SplitState::Published);
+        let split_ids = list_split_ids(&mut caching_metastore, query.clone())
+            .await
+            .unwrap();
+        assert_eq!(split_ids, ["split-1", "split-2"]);
+
+        let split_ids =
+            list_split_ids(&mut caching_metastore, query.with_time_range_start_gte(150))
+                .await
+                .unwrap();
+        assert_eq!(split_ids, ["split-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_caching_metastore_refreshes_splits_incrementally() {
+        let index_uid = IndexUid::new_with_random_ulid("test-index");
+        let index_uid_clone = index_uid.clone();
+
+        let mut mock_metastore = MetastoreServiceClient::mock();
+        mock_metastore
+            .expect_list_splits()
+            .times(2)
+            .returning(move |request| {
+                let query = request.deserialize_list_splits_query().unwrap();
+
+                match query.update_timestamp.start {
+                    Bound::Unbounded => {
+                        let split_1 =
+                            make_split(&index_uid_clone, "split-1", SplitState::Published);
+                        let split_2 =
+                            make_split(&index_uid_clone, "split-2", SplitState::Published);
+                        make_list_splits_stream(vec![split_1, split_2])
+                    }
+                    Bound::Included(update_timestamp) => {
+                        assert_eq!(update_timestamp, 1_000 - REFRESH_OVERLAP_SECS);
+                        assert!(query.split_states.is_empty());
+
+                        let mut split_1 =
+                            make_split(&index_uid_clone, "split-1", SplitState::MarkedForDeletion);
+                        split_1.update_timestamp = 1_010;
+                        let mut split_3 =
+                            make_split(&index_uid_clone, "split-3", SplitState::Published);
+                        split_3.update_timestamp = 1_010;
+                        make_list_splits_stream(vec![split_1, split_3])
+                    }
+                    Bound::Excluded(_) => panic!("unexpected update timestamp bound"),
+                }
+            });
+        let mut caching_metastore =
+            CachingMetastore::new(MetastoreServiceClient::from(mock_metastore), Duration::ZERO);
+        let query =
+            ListSplitsQuery::for_index(index_uid.clone()).with_split_state(SplitState::Published);
+        let split_ids = list_split_ids(&mut caching_metastore, query.clone())
+            .await
+            .unwrap();
+        assert_eq!(split_ids, ["split-1", "split-2"]);
+
+        let split_ids = list_split_ids(&mut caching_metastore, query).await.unwrap();
+        assert_eq!(split_ids, ["split-2", "split-3"]);
+    }
+
+    #[tokio::test]
+    async fn test_caching_metastore_invalidate() {
+        let index_uid = IndexUid::new_with_random_ulid("test-index");
+        let index_uid_clone = index_uid.clone();
+
+        let mut mock_metastore = MetastoreServiceClient::mock();
+        mock_metastore
+            .expect_list_splits()
+            .times(1)
+            .returning(move |_request| {
+                let split_1 = make_split(&index_uid_clone, "split-1", SplitState::Published);
+                make_list_splits_stream(vec![split_1])
+            });
+        mock_metastore
+            .expect_list_splits()
+            .times(1)
+            .returning(|_request| {
+                Err(MetastoreError::Unavailable(
+                    "metastore is unavailable".to_string(),
+                ))
+            });
+        let mut caching_metastore = CachingMetastore::new(
+            MetastoreServiceClient::from(mock_metastore),
+            Duration::from_secs(3600),
+        );
+        let query =
+            ListSplitsQuery::for_index(index_uid.clone()).with_split_state(SplitState::Published);
+        list_split_ids(&mut caching_metastore, query.clone())
+            .await
+            .unwrap();
+        list_split_ids(&mut caching_metastore, query.clone())
+            .await
+            .unwrap();
+
+        caching_metastore.invalidate(&index_uid);
+
+        let error = list_split_ids(&mut caching_metastore, query.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, MetastoreError::Unavailable(_)));
+
+        // The failed refresh does not clear the invalidation.
+        let cache_entry = caching_metastore.entry(&index_uid);
+        assert!(cache_entry.invalidated.load(Ordering::Acquire));
+    }
+
+    #[tokio::test]
+    async fn test_caching_metastore_forwards_uncacheable_queries() {
+        let index_uid = IndexUid::new_with_random_ulid("test-index");
+        let index_uid_clone = index_uid.clone();
+
+        let mut mock_metastore = MetastoreServiceClient::mock();
+        mock_metastore
+            .expect_list_splits()
+            .times(2)
+            .returning(move |request| {
+                let query = request.deserialize_list_splits_query().unwrap();
+                assert_eq!(query.limit, Some(1));
+
+                let split_1 = make_split(&index_uid_clone, "split-1", SplitState::Staged);
+                make_list_splits_stream(vec![split_1])
+            });
+        let mut caching_metastore = CachingMetastore::new(
+            MetastoreServiceClient::from(mock_metastore),
+            Duration::from_secs(3600),
+        );
+        for _ in 0..2 {
+            let query = ListSplitsQuery::for_index(index_uid.clone())
+                .with_split_state(SplitState::Staged)
+                .with_limit(1);
+            let split_ids = list_split_ids(&mut caching_metastore, query).await.unwrap();
+            assert_eq!(split_ids, ["split-1"]);
+        }
+    }
+}
@@ -675,7 +675,7 @@ impl Debug for Stamper {
     }
 }
 
-fn split_query_predicate(split: &&Split, query: &ListSplitsQuery) -> bool {
+pub(crate) fn split_query_predicate(split: &&Split, query: &ListSplitsQuery) -> bool {
     if !split_tag_filter(&split.split_metadata, query.tags.as_ref()) {
         return false;
     }
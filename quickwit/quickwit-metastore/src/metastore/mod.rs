@@ -21,7 +21,12 @@ pub mod file_backed;
 pub(crate) mod index_metadata;
 #[cfg(feature = "postgres")]
 pub mod postgres;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+mod sql_utils;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 
+pub mod caching_metastore;
 pub mod control_plane_metastore;
 
 use std::ops::{Bound, RangeInclusive};
@@ -24,14 +24,14 @@ mod model;
 mod split_stream;
 mod utils;
 
-use std::fmt::{self, Write};
+use std::fmt;
 use std::time::Duration;
 
 use async_trait::async_trait;
 use futures::StreamExt;
 use quickwit_common::uri::Uri;
 use quickwit_common::{PrettySample, ServiceStream};
-use quickwit_config::{validate_index_id_pattern, PostgresMetastoreConfig, INGEST_V2_SOURCE_ID};
+use quickwit_config::{PostgresMetastoreConfig, INGEST_V2_SOURCE_ID};
 use quickwit_doc_mapper::tag_pruning::TagFilterAst;
 use quickwit_proto::ingest::{Shard, ShardState};
 use quickwit_proto::metastore::{
@@ -63,7 +63,7 @@ use super::STREAM_SPLITS_CHUNK_SIZE;
 use crate::checkpoint::{
     IndexCheckpointDelta, PartitionId, SourceCheckpoint, SourceCheckpointDelta,
 };
-use crate::metastore::postgres::utils::split_maturity_timestamp;
+use crate::metastore::sql_utils::{build_index_id_patterns_sql_query, split_maturity_timestamp};
 use crate::metastore::PublishSplitsRequestExt;
 use crate::{
     AddSourceRequestExt, CreateIndexRequestExt, IndexMetadata, IndexMetadataResponseExt,
@@ -1400,70 +1400,6 @@ fn tags_filter_expression_helper(tags: &TagFilterAst) -> Cond {
     }
 }
 
-/// Builds a SQL query that returns indexes which match at least one pattern in
-/// `index_id_patterns`, and none of the patterns starting with '-'
-///
-/// For each pattern, we check if the pattern is valid and replace `*` by `%`
-/// to build a SQL `LIKE` query.
-fn build_index_id_patterns_sql_query(index_id_patterns: &[String]) -> anyhow::Result<String> {
-    let mut positive_patterns = Vec::new();
-    let mut negative_patterns = Vec::new();
-    for pattern in index_id_patterns {
-        if let Some(negative_pattern) = pattern.strip_prefix('-') {
-            negative_patterns.push(negative_pattern.to_string());
-        } else {
-            positive_patterns.push(pattern);
-        }
-    }
-
-    if positive_patterns.is_empty() {
-        anyhow::bail!("The list of index id patterns may not be empty.");
-    }
-
-    if index_id_patterns.iter().any(|pattern| pattern == "*") && negative_patterns.is_empty() {
-        return Ok("SELECT * FROM indexes".to_string());
-    }
-
-    let mut where_like_query = String::new();
-    for (index_id_pattern_idx, index_id_pattern) in positive_patterns.iter().enumerate() {
-        validate_index_id_pattern(index_id_pattern, false).map_err(|error| {
-            MetastoreError::Internal {
-                message: "failed to build list indexes query".to_string(),
-                cause: error.to_string(),
-            }
-        })?;
-        if index_id_pattern_idx != 0 {
-            where_like_query.push_str(" OR ");
-        }
-        if index_id_pattern.contains('*') {
-            let sql_pattern = index_id_pattern.replace('*', "%");
-            let _ = write!(where_like_query, "index_id LIKE '{sql_pattern}'");
-        } else {
-            let _ = write!(where_like_query, "index_id = '{index_id_pattern}'");
-        }
-    }
-    let mut negative_like_query = String::new();
-    for index_id_pattern in negative_patterns.iter() {
-        validate_index_id_pattern(index_id_pattern, false).map_err(|error| {
-            MetastoreError::Internal {
-                message: "failed to build list indexes query".to_string(),
-                cause: error.to_string(),
-            }
-        })?;
-        negative_like_query.push_str(" AND ");
-        if index_id_pattern.contains('*') {
-            let sql_pattern = index_id_pattern.replace('*', "%");
-            let _ = write!(negative_like_query, "index_id NOT LIKE '{sql_pattern}'");
-        } else {
-            let _ = write!(negative_like_query, "index_id <> '{index_id_pattern}'");
-        }
-    }
-
-    Ok(format!(
-        "SELECT * FROM indexes WHERE ({where_like_query}){negative_like_query}"
-    ))
-}
-
 /// A postgres metastore factory
 #[cfg(test)]
 #[async_trait]
@@ -1505,7 +1441,6 @@ mod tests {
 
     use super::model::PgShard;
     use super::{append_query_filters, tags_filter_expression_helper, PostgresqlMetastore};
-    use crate::metastore::postgres::build_index_id_patterns_sql_query;
     use crate::metastore::postgres::model::Splits;
     use crate::tests::shard::ReadWriteShardsForTest;
     use crate::tests::DefaultForTest;
@@ -1898,57 +1833,4 @@ mod tests {
             )
         );
     }
-
-    #[test]
-    fn test_index_id_pattern_like_query() {
-        assert_eq!(
-            &build_index_id_patterns_sql_query(&["*-index-*-last*".to_string()]).unwrap(),
-            "SELECT * FROM indexes WHERE (index_id LIKE '%-index-%-last%')"
-        );
-        assert_eq!(
-            &build_index_id_patterns_sql_query(&[
-                "*-index-*-last*".to_string(),
-                "another-index".to_string()
-            ])
-            .unwrap(),
-            "SELECT * FROM indexes WHERE (index_id LIKE '%-index-%-last%' OR index_id = \
-             'another-index')"
-        );
-        assert_eq!(
-            &build_index_id_patterns_sql_query(&[
-                "*-index-*-last**".to_string(),
-                "another-index".to_string(),
-                "*".to_string()
-            ])
-            .unwrap(),
-            "SELECT * FROM indexes"
-        );
-        assert_eq!(
-            build_index_id_patterns_sql_query(&["*-index-*-&-last**".to_string()])
-                .unwrap_err()
-                .to_string(),
-            "internal error: failed to build list indexes query; cause: `index ID pattern \
-             `*-index-*-&-last**` is invalid: patterns must match the following regular \
-             expression: `^[a-zA-Z\\*][a-zA-Z0-9-_\\.\\*]{0,254}$``"
-        );
-
-        assert_eq!(
-            &build_index_id_patterns_sql_query(&["*".to_string(), "-index-name".to_string()])
-                .unwrap(),
-            "SELECT * FROM indexes WHERE (index_id LIKE '%') AND index_id <> 'index-name'"
-        );
-
-        assert_eq!(
-            &build_index_id_patterns_sql_query(&[
-                "*-index-*-last*".to_string(),
-                "another-index".to_string(),
-                "-*-index-1-last*".to_string(),
-                "-index-2-last".to_string(),
-            ])
-            .unwrap(),
-            "SELECT * FROM indexes WHERE (index_id LIKE '%-index-%-last%' OR index_id = \
-             'another-index') AND index_id NOT LIKE '%-index-1-last%' AND index_id <> \
-             'index-2-last'"
-        );
-    }
 }
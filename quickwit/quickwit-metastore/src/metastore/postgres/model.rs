@@ -64,23 +64,7 @@ impl PgIndex {
     }
 }
 
-#[derive(Iden, Clone, Copy)]
-#[allow(dead_code)]
-pub enum Splits {
-    Table,
-    SplitId,
-    SplitState,
-    TimeRangeStart,
-    TimeRangeEnd,
-    CreateTimestamp,
-    UpdateTimestamp,
-    PublishTimestamp,
-    MaturityTimestamp,
-    Tags,
-    SplitMetadataJson,
-    IndexUid,
-    DeleteOpstamp,
-}
+pub use crate::metastore::sql_utils::Splits;
 
 pub struct ToTimestampFunc;
 
@@ -33,7 +33,7 @@ use tracing::log::LevelFilter;
 use super::model::{Splits, ToTimestampFunc};
 use super::tags_filter_expression_helper;
 use crate::metastore::FilterRange;
-use crate::{ListSplitsQuery, SplitMaturity, SplitMetadata};
+use crate::ListSplitsQuery;
 
 /// Establishes a connection to the given database URI.
 pub(super) async fn establish_connection(
@@ -187,15 +187,3 @@ pub(super) fn append_query_filters(sql: &mut SelectStatement, query: &ListSplits
             .offset(offset as u64);
     }
 }
-
-/// Returns the unix timestamp at which the split becomes mature.
-/// If the split is mature (`SplitMaturity::Mature`), we return 0
-/// as we don't want the maturity to depend on datetime.
-pub(super) fn split_maturity_timestamp(split_metadata: &SplitMetadata) -> i64 {
-    match split_metadata.maturity {
-        SplitMaturity::Mature => 0,
-        SplitMaturity::Immature { maturation_period } => {
-            split_metadata.create_timestamp + maturation_period.as_secs() as i64
-        }
-    }
-}
@@ -0,0 +1,179 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//! Helpers shared by the SQL metastore backends (PostgreSQL and SQLite).
+
+use std::fmt::Write;
+
+use quickwit_config::validate_index_id_pattern;
+use quickwit_proto::metastore::MetastoreError;
+use sea_query::Iden;
+
+use crate::{SplitMaturity, SplitMetadata};
+
+#[derive(Iden, Clone, Copy)]
+#[allow(dead_code)]
+pub enum Splits {
+    Table,
+    SplitId,
+    SplitState,
+    TimeRangeStart,
+    TimeRangeEnd,
+    CreateTimestamp,
+    UpdateTimestamp,
+    PublishTimestamp,
+    MaturityTimestamp,
+    Tags,
+    SplitMetadataJson,
+    IndexUid,
+    DeleteOpstamp,
+}
+
+/// Returns the timestamp at which a split becomes mature, or 0 if the split is already mature.
+pub(super) fn split_maturity_timestamp(split_metadata: &SplitMetadata) -> i64 {
+    match split_metadata.maturity {
+        SplitMaturity::Mature => 0,
+        SplitMaturity::Immature { maturation_period } => {
+            split_metadata.create_timestamp + maturation_period.as_secs() as i64
+        }
+    }
+}
+
+/// Builds a SQL query that returns indexes which match at least one pattern in
+/// `index_id_patterns`, and none of the patterns starting with '-'
+///
+/// For each pattern, we check if the pattern is valid and replace `*` by `%`
+/// to build a SQL `LIKE` query.
+pub(super) fn build_index_id_patterns_sql_query(
+    index_id_patterns: &[String],
+) -> anyhow::Result<String> {
+    let mut positive_patterns = Vec::new();
+    let mut negative_patterns = Vec::new();
+    for pattern in index_id_patterns {
+        if let Some(negative_pattern) = pattern.strip_prefix('-') {
+            negative_patterns.push(negative_pattern.to_string());
+        } else {
+            positive_patterns.push(pattern);
+        }
+    }
+
+    if positive_patterns.is_empty() {
+        anyhow::bail!("The list of index id patterns may not be empty.");
+    }
+
+    if index_id_patterns.iter().any(|pattern| pattern == "*") && negative_patterns.is_empty() {
+        return Ok("SELECT * FROM indexes".to_string());
+    }
+
+    let mut where_like_query = String::new();
+    for (index_id_pattern_idx, index_id_pattern) in positive_patterns.iter().enumerate() {
+        validate_index_id_pattern(index_id_pattern, false).map_err(|error| {
+            MetastoreError::Internal {
+                message: "failed to build list indexes query".to_string(),
+                cause: error.to_string(),
+            }
+        })?;
+        if index_id_pattern_idx != 0 {
+            where_like_query.push_str(" OR ");
+        }
+        if index_id_pattern.contains('*') {
+            let sql_pattern = index_id_pattern.replace('*', "%");
+            let _ = write!(where_like_query, "index_id LIKE '{sql_pattern}'");
+        } else {
+            let _ = write!(where_like_query, "index_id = '{index_id_pattern}'");
+        }
+    }
+    let mut negative_like_query = String::new();
+    for index_id_pattern in negative_patterns.iter() {
+        validate_index_id_pattern(index_id_pattern, false).map_err(|error| {
+            MetastoreError::Internal {
+                message: "failed to build list indexes query".to_string(),
+                cause: error.to_string(),
+            }
+        })?;
+        negative_like_query.push_str(" AND ");
+        if index_id_pattern.contains('*') {
+            let sql_pattern = index_id_pattern.replace('*', "%");
+            let _ = write!(negative_like_query, "index_id NOT LIKE '{sql_pattern}'");
+        } else {
+            let _ = write!(negative_like_query, "index_id <> '{index_id_pattern}'");
+        }
+    }
+
+    Ok(format!(
+        "SELECT * FROM indexes WHERE ({where_like_query}){negative_like_query}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_id_pattern_like_query() {
+        assert_eq!(
+            &build_index_id_patterns_sql_query(&["*-index-*-last*".to_string()]).unwrap(),
+            "SELECT * FROM indexes WHERE (index_id LIKE '%-index-%-last%')"
+        );
+        assert_eq!(
+            &build_index_id_patterns_sql_query(&[
+                "*-index-*-last*".to_string(),
+                "another-index".to_string()
+            ])
+            .unwrap(),
+            "SELECT * FROM indexes WHERE (index_id LIKE '%-index-%-last%' OR index_id = \
+             'another-index')"
+        );
+        assert_eq!(
+            &build_index_id_patterns_sql_query(&[
+                "*-index-*-last**".to_string(),
+                "another-index".to_string(),
+                "*".to_string()
+            ])
+            .unwrap(),
+            "SELECT * FROM indexes"
+        );
+        assert_eq!(
+            build_index_id_patterns_sql_query(&["*-index-*-&-last**".to_string()])
+                .unwrap_err()
+                .to_string(),
+            "internal error: failed to build list indexes query; cause: `index ID pattern \
+             `*-index-*-&-last**` is invalid: patterns must match the following regular \
+             expression: `^[a-zA-Z\\*][a-zA-Z0-9-_\\.\\*]{0,254}$``"
+        );
+
+        assert_eq!(
+            &build_index_id_patterns_sql_query(&["*".to_string(), "-index-name".to_string()])
+                .unwrap(),
+            "SELECT * FROM indexes WHERE (index_id LIKE '%') AND index_id <> 'index-name'"
+        );
+
+        assert_eq!(
+            &build_index_id_patterns_sql_query(&[
+                "*-index-*-last*".to_string(),
+                "another-index".to_string(),
+                "-*-index-1-last*".to_string(),
+                "-index-2-last".to_string(),
+            ])
+            .unwrap(),
+            "SELECT * FROM indexes WHERE (index_id LIKE '%-index-%-last%' OR index_id = \
+             'another-index') AND index_id NOT LIKE '%-index-1-last%' AND index_id <> \
+             'index-2-last'"
+        );
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use quickwit_proto::metastore::{EntityKind, MetastoreError};
+use sqlx::error::ErrorKind;
+use tracing::error;
+
+pub(super) fn convert_sqlx_err(index_id: &str, sqlx_error: sqlx::Error) -> MetastoreError {
+    match &sqlx_error {
+        sqlx::Error::Database(boxed_db_error) => match boxed_db_error.kind() {
+            ErrorKind::ForeignKeyViolation => MetastoreError::NotFound(EntityKind::Index {
+                index_id: index_id.to_string(),
+            }),
+            // SQLite reports the violated constraint in the error message, for instance:
+            // `UNIQUE constraint failed: indexes.index_id`.
+            ErrorKind::UniqueViolation if boxed_db_error.message().contains(" indexes.") => {
+                MetastoreError::AlreadyExists(EntityKind::Index {
+                    index_id: index_id.to_string(),
+                })
+            }
+            ErrorKind::UniqueViolation => {
+                error!(error=?boxed_db_error, "sqlite-error");
+                MetastoreError::Internal {
+                    message: "unique key violation".to_string(),
+                    cause: format!("DB error {boxed_db_error:?}"),
+                }
+            }
+            _ => {
+                error!(error=?boxed_db_error, "sqlite-error");
+                MetastoreError::Db {
+                    message: boxed_db_error.to_string(),
+                }
+            }
+        },
+        _ => {
+            error!(error=?sqlx_error, "an error has occurred in the database operation");
+            MetastoreError::Db {
+                message: sqlx_error.to_string(),
+            }
+        }
+    }
+}
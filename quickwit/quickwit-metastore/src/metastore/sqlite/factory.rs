@@ -0,0 +1,73 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use quickwit_common::uri::Uri;
+use quickwit_config::{MetastoreBackend, MetastoreConfig};
+use quickwit_proto::metastore::MetastoreServiceClient;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::metastore::instrument_metastore;
+use crate::{MetastoreFactory, MetastoreResolverError, SqliteMetastore};
+
+#[derive(Clone, Default)]
+pub struct SqliteMetastoreFactory {
+    // Each metastore holds the only connection to its database file, so we must make sure that a
+    // single `Metastore` exists per URI.
+    cache: Arc<Mutex<HashMap<Uri, MetastoreServiceClient>>>,
+}
+
+#[async_trait]
+impl MetastoreFactory for SqliteMetastoreFactory {
+    fn backend(&self) -> MetastoreBackend {
+        MetastoreBackend::SQLite
+    }
+
+    async fn resolve(
+        &self,
+        metastore_config: &MetastoreConfig,
+        uri: &Uri,
+    ) -> Result<MetastoreServiceClient, MetastoreResolverError> {
+        // The lock is held during the initialization of the metastore so that two concurrent
+        // calls do not open the same database file twice.
+        let mut cache_lock = self.cache.lock().await;
+
+        if let Some(metastore) = cache_lock.get(uri) {
+            debug!("using metastore from cache");
+            return Ok(metastore.clone());
+        }
+        debug!("metastore not found in cache");
+        let sqlite_metastore_config = metastore_config.as_sqlite().ok_or_else(|| {
+            let message = format!(
+                "expected SQLite metastore config, got `{:?}`",
+                metastore_config.backend()
+            );
+            MetastoreResolverError::InvalidConfig(message)
+        })?;
+        let sqlite_metastore = SqliteMetastore::new(sqlite_metastore_config, uri)
+            .await
+            .map_err(MetastoreResolverError::Initialization)?;
+        let instrumented_metastore = instrument_metastore(sqlite_metastore);
+        cache_lock.insert(uri.clone(), instrumented_metastore.clone());
+        Ok(instrumented_metastore)
+    }
+}
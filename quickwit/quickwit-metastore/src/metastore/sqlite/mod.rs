@@ -0,0 +1,1400 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+mod error;
+mod factory;
+mod migrator;
+mod model;
+mod utils;
+
+use std::collections::HashMap;
+use std::fmt;
+
+use async_trait::async_trait;
+use quickwit_common::uri::Uri;
+use quickwit_common::{PrettySample, ServiceStream};
+use quickwit_config::{SqliteMetastoreConfig, INGEST_V2_SOURCE_ID};
+use quickwit_proto::ingest::{Shard, ShardState};
+use quickwit_proto::metastore::{
+    AcquireShardsRequest, AcquireShardsResponse, AcquireShardsSubresponse, AddSourceRequest,
+    CreateIndexRequest, CreateIndexResponse, DeleteIndexRequest, DeleteQuery, DeleteShardsRequest,
+    DeleteShardsResponse, DeleteSourceRequest, DeleteSplitsRequest, DeleteTask, EmptyResponse,
+    EntityKind, IndexMetadataRequest, IndexMetadataResponse, LastDeleteOpstampRequest,
+    LastDeleteOpstampResponse, ListDeleteTasksRequest, ListDeleteTasksResponse,
+    ListIndexesMetadataRequest, ListIndexesMetadataResponse, ListShardsRequest, ListShardsResponse,
+    ListShardsSubresponse, ListSplitsRequest, ListSplitsResponse, ListStaleSplitsRequest,
+    MarkSplitsForDeletionRequest, MetastoreError, MetastoreResult, MetastoreService,
+    MetastoreServiceStream, OpenShardsRequest, OpenShardsResponse, OpenShardsSubrequest,
+    OpenShardsSubresponse, PublishSplitsRequest, ResetSourceCheckpointRequest, StageSplitsRequest,
+    ToggleSourceRequest, UpdateSplitsDeleteOpstampRequest, UpdateSplitsDeleteOpstampResponse,
+};
+use quickwit_proto::types::{IndexUid, Position, PublishToken, SourceId};
+use sea_query::{Asterisk, Query, SqliteQueryBuilder};
+use sea_query_binder::SqlxBinder;
+use sqlx::{Executor, Pool, Sqlite, Transaction};
+use tracing::{debug, info, instrument, warn};
+
+use self::error::convert_sqlx_err;
+pub use self::factory::SqliteMetastoreFactory;
+use self::migrator::run_migrations;
+use self::model::{shard_state_str, SqliteDeleteTask, SqliteIndex, SqliteShard, SqliteSplit};
+use self::utils::{append_query_filters, establish_connection};
+use super::STREAM_SPLITS_CHUNK_SIZE;
+use crate::checkpoint::{
+    IndexCheckpointDelta, PartitionId, SourceCheckpoint, SourceCheckpointDelta,
+};
+use crate::metastore::sql_utils::{
+    build_index_id_patterns_sql_query, split_maturity_timestamp, Splits,
+};
+use crate::metastore::PublishSplitsRequestExt;
+use crate::{
+    AddSourceRequestExt, CreateIndexRequestExt, IndexMetadata, IndexMetadataResponseExt,
+    ListIndexesMetadataResponseExt, ListSplitsRequestExt, ListSplitsResponseExt,
+    MetastoreServiceExt, Split, SplitState, StageSplitsRequestExt,
+};
+
+/// SQLite metastore implementation.
+///
+/// The SQLite metastore uses the same data model as the PostgreSQL metastore. It is meant for
+/// single-node deployments: the database file must not be shared between several nodes.
+#[derive(Clone)]
+pub struct SqliteMetastore {
+    uri: Uri,
+    connection_pool: Pool<Sqlite>,
+}
+
+impl fmt::Debug for SqliteMetastore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SqliteMetastore")
+            .field("uri", &self.uri)
+            .finish()
+    }
+}
+
+impl SqliteMetastore {
+    /// Creates a metastore given a database URI, for instance
+    /// `sqlite:///var/lib/quickwit/metastore.db`.
+    pub async fn new(
+        _sqlite_metastore_config: &SqliteMetastoreConfig,
+        connection_uri: &Uri,
+    ) -> MetastoreResult<Self> {
+        let connection_pool = establish_connection(connection_uri).await?;
+        run_migrations(&connection_pool).await?;
+
+        Ok(SqliteMetastore {
+            uri: connection_uri.clone(),
+            connection_pool,
+        })
+    }
+}
+
+/// Serializes a list of values into a JSON array. SQLite does not support array parameters, so
+/// lists are bound as JSON arrays and expanded with `json_each`.
+fn to_json_array<T: serde::Serialize>(values: &[T]) -> String {
+    serde_json::to_string(values).expect("list of strings should be JSON serializable")
+}
+
+/// Returns an Index object given an index_id or None if it does not exist.
+async fn index_opt<'a, E>(executor: E, index_id: &str) -> MetastoreResult<Option<SqliteIndex>>
+where E: sqlx::Executor<'a, Database = Sqlite> {
+    let index_opt: Option<SqliteIndex> = sqlx::query_as::<_, SqliteIndex>(
+        r#"
+        SELECT *
+        FROM indexes
+        WHERE index_id = ?1
+        "#,
+    )
+    .bind(index_id)
+    .fetch_optional(executor)
+    .await
+    .map_err(|error| MetastoreError::Db {
+        message: error.to_string(),
+    })?;
+    Ok(index_opt)
+}
+
+/// Returns an Index object given an index_uid or None if it does not exist.
+async fn index_opt_for_uid<'a, E>(
+    executor: E,
+    index_uid: IndexUid,
+) -> MetastoreResult<Option<SqliteIndex>>
+where
+    E: sqlx::Executor<'a, Database = Sqlite>,
+{
+    let index_opt: Option<SqliteIndex> = sqlx::query_as::<_, SqliteIndex>(
+        r#"
+        SELECT *
+        FROM indexes
+        WHERE index_uid = ?1
+        "#,
+    )
+    .bind(index_uid.as_str())
+    .fetch_optional(executor)
+    .await
+    .map_err(|error| MetastoreError::Db {
+        message: error.to_string(),
+    })?;
+    Ok(index_opt)
+}
+
+async fn index_metadata(
+    tx: &mut Transaction<'_, Sqlite>,
+    index_id: &str,
+) -> MetastoreResult<IndexMetadata> {
+    index_opt(tx.as_mut(), index_id)
+        .await?
+        .ok_or_else(|| {
+            MetastoreError::NotFound(EntityKind::Index {
+                index_id: index_id.to_string(),
+            })
+        })?
+        .index_metadata()
+}
+
+/// Returns the states of the splits of an index among `split_ids`. Splits that do not exist are
+/// omitted.
+async fn split_states(
+    tx: &mut Transaction<'_, Sqlite>,
+    index_uid: &IndexUid,
+    split_ids: &[String],
+) -> MetastoreResult<HashMap<String, String>> {
+    let split_states: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT split_id, split_state
+        FROM splits
+        WHERE
+            index_uid = ?1
+            AND split_id IN (SELECT value FROM json_each(?2))
+        "#,
+    )
+    .bind(index_uid.as_str())
+    .bind(to_json_array(split_ids))
+    .fetch_all(tx.as_mut())
+    .await?;
+    Ok(split_states.into_iter().collect())
+}
+
+async fn try_apply_delta_v2(
+    tx: &mut Transaction<'_, Sqlite>,
+    index_uid: &IndexUid,
+    source_id: &SourceId,
+    checkpoint_delta: SourceCheckpointDelta,
+    publish_token: PublishToken,
+) -> MetastoreResult<()> {
+    let num_partitions = checkpoint_delta.num_partitions();
+    let shard_ids: Vec<String> = checkpoint_delta
+        .partitions()
+        .map(|partition_id| partition_id.to_string())
+        .collect();
+
+    let shards: Vec<(String, String, Option<PublishToken>)> = sqlx::query_as(
+        r#"
+        SELECT
+            shard_id, publish_position_inclusive, publish_token
+        FROM
+            shards
+        WHERE
+            index_uid = ?1
+            AND source_id = ?2
+            AND shard_id IN (SELECT value FROM json_each(?3))
+        "#,
+    )
+    .bind(index_uid.as_str())
+    .bind(source_id)
+    .bind(to_json_array(&shard_ids))
+    .fetch_all(tx.as_mut())
+    .await?;
+
+    if shards.len() != num_partitions {
+        let queue_id = format!("{index_uid}/{source_id}");
+        let entity_kind = EntityKind::Shard { queue_id };
+        return Err(MetastoreError::NotFound(entity_kind));
+    }
+    let mut current_checkpoint = SourceCheckpoint::default();
+
+    for (shard_id, current_position, current_publish_token_opt) in shards {
+        if current_publish_token_opt.is_none()
+            || current_publish_token_opt.unwrap() != publish_token
+        {
+            let message = "failed to apply checkpoint delta: invalid publish token".to_string();
+            return Err(MetastoreError::InvalidArgument { message });
+        }
+        let partition_id = PartitionId::from(shard_id);
+        let current_position = Position::from(current_position);
+        current_checkpoint.add_partition(partition_id, current_position);
+    }
+    current_checkpoint
+        .try_apply_delta(checkpoint_delta)
+        .map_err(|error| MetastoreError::InvalidArgument {
+            message: error.to_string(),
+        })?;
+
+    for (partition_id, new_position) in current_checkpoint.iter() {
+        sqlx::query(
+            r#"
+            UPDATE
+                shards
+            SET
+                publish_position_inclusive = ?4,
+                shard_state = CASE WHEN ?4 LIKE '~%' THEN 'closed' ELSE shard_state END
+            WHERE
+                index_uid = ?1
+                AND source_id = ?2
+                AND shard_id = ?3
+            "#,
+        )
+        .bind(index_uid.as_str())
+        .bind(source_id)
+        .bind(partition_id.to_string())
+        .bind(new_position.to_string())
+        .execute(tx.as_mut())
+        .await?;
+    }
+    Ok(())
+}
+
+/// This macro is used to systematically wrap the metastore
+/// into transaction, commit them on Result::Ok and rollback on Error.
+macro_rules! run_with_tx {
+    ($connection_pool:expr, $tx_refmut:ident, $x:block) => {{
+        let mut tx: Transaction<'_, Sqlite> = $connection_pool.begin().await?;
+        let $tx_refmut = &mut tx;
+        let op_fut = move || async move { $x };
+        let op_result: MetastoreResult<_> = op_fut().await;
+        if op_result.is_ok() {
+            debug!("commit");
+            tx.commit().await?;
+        } else {
+            warn!("rollback");
+            tx.rollback().await?;
+        }
+        op_result
+    }};
+}
+
+async fn mutate_index_metadata<E, M: FnOnce(&mut IndexMetadata) -> Result<bool, E>>(
+    tx: &mut Transaction<'_, Sqlite>,
+    index_uid: IndexUid,
+    mutate_fn: M,
+) -> MetastoreResult<bool>
+where
+    MetastoreError: From<E>,
+{
+    let index_id = index_uid.index_id();
+    let mut index_metadata = index_metadata(tx, index_id).await?;
+    if index_metadata.index_uid != index_uid {
+        return Err(MetastoreError::NotFound(EntityKind::Index {
+            index_id: index_id.to_string(),
+        }));
+    }
+    let mutation_occurred = mutate_fn(&mut index_metadata)?;
+    if !mutation_occurred {
+        return Ok(mutation_occurred);
+    }
+    let index_metadata_json = serde_json::to_string(&index_metadata).map_err(|error| {
+        MetastoreError::JsonSerializeError {
+            struct_name: "IndexMetadata".to_string(),
+            message: error.to_string(),
+        }
+    })?;
+    let update_index_res = sqlx::query(
+        r#"
+        UPDATE indexes
+        SET index_metadata_json = ?1
+        WHERE index_uid = ?2
+        "#,
+    )
+    .bind(index_metadata_json)
+    .bind(index_uid.as_str())
+    .execute(tx.as_mut())
+    .await?;
+    if update_index_res.rows_affected() == 0 {
+        return Err(MetastoreError::NotFound(EntityKind::Index {
+            index_id: index_id.to_string(),
+        }));
+    }
+    Ok(mutation_occurred)
+}
+
+#[async_trait]
+impl MetastoreService for SqliteMetastore {
+    async fn check_connectivity(&mut self) -> anyhow::Result<()> {
+        self.connection_pool.acquire().await?;
+        Ok(())
+    }
+
+    fn endpoints(&self) -> Vec<quickwit_common::uri::Uri> {
+        vec![self.uri.clone()]
+    }
+
+    #[instrument(skip(self))]
+    async fn list_indexes_metadata(
+        &mut self,
+        request: ListIndexesMetadataRequest,
+    ) -> MetastoreResult<ListIndexesMetadataResponse> {
+        let sql =
+            build_index_id_patterns_sql_query(&request.index_id_patterns).map_err(|error| {
+                MetastoreError::Internal {
+                    message: "failed to build `list_indexes_metadatas` SQL query".to_string(),
+                    cause: error.to_string(),
+                }
+            })?;
+        let sqlite_indexes = sqlx::query_as::<_, SqliteIndex>(&sql)
+            .fetch_all(&self.connection_pool)
+            .await?;
+        let indexes_metadata = sqlite_indexes
+            .into_iter()
+            .map(|sqlite_index| sqlite_index.index_metadata())
+            .collect::<MetastoreResult<Vec<IndexMetadata>>>()?;
+        let response = ListIndexesMetadataResponse::try_from_indexes_metadata(indexes_metadata)?;
+        Ok(response)
+    }
+
+    #[instrument(skip(self))]
+    async fn create_index(
+        &mut self,
+        request: CreateIndexRequest,
+    ) -> MetastoreResult<CreateIndexResponse> {
+        let index_config = request.deserialize_index_config()?;
+        let index_metadata = IndexMetadata::new(index_config);
+        let index_metadata_json = serde_json::to_string(&index_metadata).map_err(|error| {
+            MetastoreError::JsonSerializeError {
+                struct_name: "IndexMetadata".to_string(),
+                message: error.to_string(),
+            }
+        })?;
+        sqlx::query(
+            "INSERT INTO indexes (index_uid, index_id, index_metadata_json) VALUES (?1, ?2, ?3)",
+        )
+        .bind(index_metadata.index_uid.to_string())
+        .bind(index_metadata.index_uid.index_id())
+        .bind(&index_metadata_json)
+        .execute(&self.connection_pool)
+        .await
+        .map_err(|sqlx_error| convert_sqlx_err(index_metadata.index_id(), sqlx_error))?;
+        Ok(CreateIndexResponse {
+            index_uid: index_metadata.index_uid.to_string(),
+        })
+    }
+
+    #[instrument(skip_all, fields(index_id=request.index_uid))]
+    async fn delete_index(
+        &mut self,
+        request: DeleteIndexRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        let index_uid: IndexUid = request.index_uid.into();
+        // The splits, delete tasks, and shards of the index are deleted by cascade.
+        let delete_result = sqlx::query("DELETE FROM indexes WHERE index_uid = ?1")
+            .bind(index_uid.as_str())
+            .execute(&self.connection_pool)
+            .await?;
+        if delete_result.rows_affected() == 0 {
+            return Err(MetastoreError::NotFound(EntityKind::Index {
+                index_id: index_uid.index_id().to_string(),
+            }));
+        }
+        info!(
+            index_id = index_uid.index_id(),
+            "deleted index successfully"
+        );
+        Ok(EmptyResponse {})
+    }
+
+    #[instrument(skip_all, fields(split_ids))]
+    async fn stage_splits(
+        &mut self,
+        request: StageSplitsRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        let split_metadata_list = request.deserialize_splits_metadata()?;
+        let index_uid: IndexUid = request.index_uid.into();
+        let split_ids: Vec<String> = split_metadata_list
+            .iter()
+            .map(|split_metadata| split_metadata.split_id.clone())
+            .collect();
+        tracing::Span::current().record("split_ids", format!("{split_ids:?}"));
+
+        run_with_tx!(self.connection_pool, tx, {
+            let mut failed_split_ids = Vec::new();
+
+            for split_metadata in split_metadata_list {
+                let split_metadata_json =
+                    serde_json::to_string(&split_metadata).map_err(|error| {
+                        MetastoreError::JsonSerializeError {
+                            struct_name: "SplitMetadata".to_string(),
+                            message: error.to_string(),
+                        }
+                    })?;
+                let time_range_start = split_metadata
+                    .time_range
+                    .as_ref()
+                    .map(|range| *range.start());
+                let time_range_end = split_metadata.time_range.as_ref().map(|range| *range.end());
+                let tags: Vec<&String> = split_metadata.tags.iter().collect();
+                let maturity_timestamp = split_maturity_timestamp(&split_metadata);
+
+                let upserted_split_id_opt: Option<String> = sqlx::query_scalar(r#"
+                    INSERT INTO splits
+                        (split_id, time_range_start, time_range_end, tags, split_metadata_json, delete_opstamp, maturity_timestamp, split_state, index_uid)
+                    VALUES
+                        (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                    ON CONFLICT(split_id) DO UPDATE
+                        SET
+                            time_range_start = excluded.time_range_start,
+                            time_range_end = excluded.time_range_end,
+                            tags = excluded.tags,
+                            split_metadata_json = excluded.split_metadata_json,
+                            delete_opstamp = excluded.delete_opstamp,
+                            maturity_timestamp = excluded.maturity_timestamp,
+                            index_uid = excluded.index_uid,
+                            update_timestamp = CAST(strftime('%s', 'now') AS INTEGER),
+                            create_timestamp = CAST(strftime('%s', 'now') AS INTEGER)
+                        WHERE splits.split_state = 'Staged'
+                    RETURNING split_id
+                    "#)
+                    .bind(&split_metadata.split_id)
+                    .bind(time_range_start)
+                    .bind(time_range_end)
+                    .bind(to_json_array(&tags))
+                    .bind(split_metadata_json)
+                    .bind(split_metadata.delete_opstamp as i64)
+                    .bind(maturity_timestamp)
+                    .bind(SplitState::Staged.as_str())
+                    .bind(index_uid.as_str())
+                    .fetch_optional(tx.as_mut())
+                    .await
+                    .map_err(|sqlx_error| convert_sqlx_err(index_uid.index_id(), sqlx_error))?;
+
+                if upserted_split_id_opt.is_none() {
+                    failed_split_ids.push(split_metadata.split_id);
+                }
+            }
+            if !failed_split_ids.is_empty() {
+                let entity = EntityKind::Splits {
+                    split_ids: failed_split_ids,
+                };
+                let message = "splits are not staged".to_string();
+                return Err(MetastoreError::FailedPrecondition { entity, message });
+            }
+            info!(
+                index_id=%index_uid.index_id(),
+                "staged `{}` splits successfully", split_ids.len()
+            );
+            Ok(EmptyResponse {})
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn publish_splits(
+        &mut self,
+        request: PublishSplitsRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        let checkpoint_delta_opt: Option<IndexCheckpointDelta> =
+            request.deserialize_index_checkpoint()?;
+        let index_uid: IndexUid = request.index_uid.into();
+        let staged_split_ids = request.staged_split_ids;
+        let replaced_split_ids = request.replaced_split_ids;
+
+        run_with_tx!(self.connection_pool, tx, {
+            let mut index_metadata = index_metadata(tx, index_uid.index_id()).await?;
+            if index_metadata.index_uid != index_uid {
+                return Err(MetastoreError::NotFound(EntityKind::Index {
+                    index_id: index_uid.index_id().to_string(),
+                }));
+            }
+            if let Some(checkpoint_delta) = checkpoint_delta_opt {
+                let source_id = checkpoint_delta.source_id.clone();
+
+                if source_id == INGEST_V2_SOURCE_ID {
+                    let publish_token = request.publish_token_opt.ok_or_else(|| {
+                        let message = format!(
+                            "publish token is required for publishing splits for source \
+                             `{source_id}`"
+                        );
+                        MetastoreError::InvalidArgument { message }
+                    })?;
+                    try_apply_delta_v2(
+                        tx,
+                        &index_uid,
+                        &source_id,
+                        checkpoint_delta.source_delta,
+                        publish_token,
+                    )
+                    .await?;
+                } else {
+                    index_metadata
+                        .checkpoint
+                        .try_apply_delta(checkpoint_delta)
+                        .map_err(|error| {
+                            let entity = EntityKind::CheckpointDelta {
+                                index_id: index_uid.index_id().to_string(),
+                                source_id,
+                            };
+                            let message = error.to_string();
+                            MetastoreError::FailedPrecondition { entity, message }
+                        })?;
+                }
+            }
+            let index_metadata_json = serde_json::to_string(&index_metadata).map_err(|error| {
+                MetastoreError::JsonSerializeError {
+                    struct_name: "IndexMetadata".to_string(),
+                    message: error.to_string(),
+                }
+            })?;
+            let staged_split_states = split_states(tx, &index_uid, &staged_split_ids).await?;
+            let replaced_split_states = split_states(tx, &index_uid, &replaced_split_ids).await?;
+
+            let mut not_found_split_ids = Vec::new();
+            let mut not_staged_split_ids = Vec::new();
+            let mut not_marked_split_ids = Vec::new();
+
+            for split_id in &staged_split_ids {
+                match staged_split_states.get(split_id).map(String::as_str) {
+                    None => not_found_split_ids.push(split_id.clone()),
+                    Some("Staged") => {}
+                    Some(_) => not_staged_split_ids.push(split_id.clone()),
+                }
+            }
+            for split_id in &replaced_split_ids {
+                match replaced_split_states.get(split_id).map(String::as_str) {
+                    None => not_found_split_ids.push(split_id.clone()),
+                    Some("Published") => {}
+                    Some(_) => not_marked_split_ids.push(split_id.clone()),
+                }
+            }
+            if !not_found_split_ids.is_empty() {
+                return Err(MetastoreError::NotFound(EntityKind::Splits {
+                    split_ids: not_found_split_ids,
+                }));
+            }
+            if !not_staged_split_ids.is_empty() {
+                let entity = EntityKind::Splits {
+                    split_ids: not_staged_split_ids,
+                };
+                let message = "splits are not staged".to_string();
+                return Err(MetastoreError::FailedPrecondition { entity, message });
+            }
+            if !not_marked_split_ids.is_empty() {
+                let entity = EntityKind::Splits {
+                    split_ids: not_marked_split_ids,
+                };
+                let message = "splits are not marked for deletion".to_string();
+                return Err(MetastoreError::FailedPrecondition { entity, message });
+            }
+            sqlx::query(
+                r#"
+                UPDATE indexes
+                SET index_metadata_json = ?2
+                WHERE index_uid = ?1
+                "#,
+            )
+            .bind(index_uid.as_str())
+            .bind(index_metadata_json)
+            .execute(tx.as_mut())
+            .await?;
+
+            const UPDATE_SPLITS_STATE_QUERY: &str = r#"
+                UPDATE splits
+                SET
+                    split_state = ?3,
+                    update_timestamp = CAST(strftime('%s', 'now') AS INTEGER),
+                    publish_timestamp = CAST(strftime('%s', 'now') AS INTEGER)
+                WHERE
+                    index_uid = ?1
+                    AND split_id IN (SELECT value FROM json_each(?2))
+            "#;
+            sqlx::query(UPDATE_SPLITS_STATE_QUERY)
+                .bind(index_uid.as_str())
+                .bind(to_json_array(&staged_split_ids))
+                .bind(SplitState::Published.as_str())
+                .execute(tx.as_mut())
+                .await?;
+            sqlx::query(UPDATE_SPLITS_STATE_QUERY)
+                .bind(index_uid.as_str())
+                .bind(to_json_array(&replaced_split_ids))
+                .bind(SplitState::MarkedForDeletion.as_str())
+                .execute(tx.as_mut())
+                .await?;
+
+            info!(
+                index_id=%index_uid.index_id(),
+                "published {} splits and marked {} for deletion successfully",
+                staged_split_ids.len(), replaced_split_ids.len()
+            );
+            Ok(EmptyResponse {})
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn list_splits(
+        &mut self,
+        request: ListSplitsRequest,
+    ) -> MetastoreResult<MetastoreServiceStream<ListSplitsResponse>> {
+        let query = request.deserialize_list_splits_query()?;
+        let mut sql_builder = Query::select();
+        sql_builder.column(Asterisk).from(Splits::Table);
+        append_query_filters(&mut sql_builder, &query);
+
+        let (sql, values) = sql_builder.build_sqlx(SqliteQueryBuilder);
+        // The database is local, so the splits are fetched all at once instead of being streamed
+        // from a cursor, which would hold the only connection of the pool.
+        let sqlite_splits: Vec<SqliteSplit> =
+            sqlx::query_as_with::<_, SqliteSplit, _>(&sql, values)
+                .fetch_all(&self.connection_pool)
+                .await
+                .map_err(|error| MetastoreError::Internal {
+                    message: "failed to fetch splits".to_string(),
+                    cause: error.to_string(),
+                })?;
+        let splits: Vec<Split> = sqlite_splits
+            .into_iter()
+            .map(|sqlite_split| sqlite_split.try_into())
+            .collect::<MetastoreResult<_>>()?;
+
+        let mut split_iter = splits.into_iter().peekable();
+        let mut list_splits_responses = Vec::new();
+
+        while split_iter.peek().is_some() {
+            let splits_chunk: Vec<Split> =
+                split_iter.by_ref().take(STREAM_SPLITS_CHUNK_SIZE).collect();
+            list_splits_responses.push(ListSplitsResponse::try_from_splits(splits_chunk));
+        }
+        let service_stream = ServiceStream::from(list_splits_responses);
+        Ok(service_stream)
+    }
+
+    #[instrument(skip(self))]
+    async fn mark_splits_for_deletion(
+        &mut self,
+        request: MarkSplitsForDeletionRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        let index_uid: IndexUid = request.index_uid.into();
+        let split_ids = request.split_ids;
+
+        run_with_tx!(self.connection_pool, tx, {
+            let split_states = split_states(tx, &index_uid, &split_ids).await?;
+
+            if split_states.is_empty()
+                && index_opt_for_uid(tx.as_mut(), index_uid.clone())
+                    .await?
+                    .is_none()
+            {
+                return Err(MetastoreError::NotFound(EntityKind::Index {
+                    index_id: index_uid.index_id().to_string(),
+                }));
+            }
+            let marked_splits_result = sqlx::query(
+                r#"
+                UPDATE splits
+                SET
+                    split_state = 'MarkedForDeletion',
+                    update_timestamp = CAST(strftime('%s', 'now') AS INTEGER)
+                WHERE
+                    index_uid = ?1
+                    AND split_id IN (SELECT value FROM json_each(?2))
+                    AND split_state IN ('Staged', 'Published')
+                "#,
+            )
+            .bind(index_uid.as_str())
+            .bind(to_json_array(&split_ids))
+            .execute(tx.as_mut())
+            .await
+            .map_err(|sqlx_error| convert_sqlx_err(index_uid.index_id(), sqlx_error))?;
+
+            let not_found_split_ids: Vec<String> = split_ids
+                .iter()
+                .filter(|split_id| !split_states.contains_key(*split_id))
+                .cloned()
+                .collect();
+            info!(
+                index_id=%index_uid.index_id(),
+                "Marked {} splits for deletion, among which {} were newly marked.",
+                split_ids.len() - not_found_split_ids.len(),
+                marked_splits_result.rows_affected()
+            );
+            if !not_found_split_ids.is_empty() {
+                warn!(
+                    index_id=%index_uid.index_id(),
+                    split_ids=?PrettySample::new(&not_found_split_ids, 5),
+                    "{} splits were not found and could not be marked for deletion.",
+                    not_found_split_ids.len()
+                );
+            }
+            Ok(EmptyResponse {})
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_splits(
+        &mut self,
+        request: DeleteSplitsRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        let index_uid: IndexUid = request.index_uid.into();
+        let split_ids = request.split_ids;
+
+        run_with_tx!(self.connection_pool, tx, {
+            let split_states = split_states(tx, &index_uid, &split_ids).await?;
+
+            if split_states.is_empty()
+                && index_opt_for_uid(tx.as_mut(), index_uid.clone())
+                    .await?
+                    .is_none()
+            {
+                return Err(MetastoreError::NotFound(EntityKind::Index {
+                    index_id: index_uid.index_id().to_string(),
+                }));
+            }
+            // The splits are deleted if and only if all of them are marked for deletion.
+            let not_deletable_split_ids: Vec<String> = split_ids
+                .iter()
+                .filter(|split_id| {
+                    matches!(
+                        split_states.get(*split_id).map(String::as_str),
+                        Some("Staged") | Some("Published")
+                    )
+                })
+                .cloned()
+                .collect();
+            if !not_deletable_split_ids.is_empty() {
+                let message = format!(
+                    "splits `{}` are not deletable",
+                    not_deletable_split_ids.join(", ")
+                );
+                let entity = EntityKind::Splits {
+                    split_ids: not_deletable_split_ids,
+                };
+                return Err(MetastoreError::FailedPrecondition { entity, message });
+            }
+            let delete_result = sqlx::query(
+                r#"
+                DELETE FROM splits
+                WHERE
+                    index_uid = ?1
+                    AND split_id IN (SELECT value FROM json_each(?2))
+                "#,
+            )
+            .bind(index_uid.as_str())
+            .bind(to_json_array(&split_ids))
+            .execute(tx.as_mut())
+            .await
+            .map_err(|sqlx_error| convert_sqlx_err(index_uid.index_id(), sqlx_error))?;
+
+            info!(index_id=%index_uid.index_id(), "Deleted {} splits from index.", delete_result.rows_affected());
+
+            let not_found_split_ids: Vec<String> = split_ids
+                .iter()
+                .filter(|split_id| !split_states.contains_key(*split_id))
+                .cloned()
+                .collect();
+            if !not_found_split_ids.is_empty() {
+                warn!(
+                    index_id=%index_uid.index_id(),
+                    split_ids=?PrettySample::new(&not_found_split_ids, 5),
+                    "{} splits were not found and could not be deleted.",
+                    not_found_split_ids.len()
+                );
+            }
+            Ok(EmptyResponse {})
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn index_metadata(
+        &mut self,
+        request: IndexMetadataRequest,
+    ) -> MetastoreResult<IndexMetadataResponse> {
+        let response = if let Some(index_uid) = &request.index_uid {
+            let index_uid: IndexUid = index_uid.to_string().into();
+            index_opt_for_uid(&self.connection_pool, index_uid).await?
+        } else if let Some(index_id) = &request.index_id {
+            index_opt(&self.connection_pool, index_id).await?
+        } else {
+            return Err(MetastoreError::Internal {
+                message: "either `index_id` or `index_uid` must be set".to_string(),
+                cause: "missing index identifier".to_string(),
+            });
+        };
+        let index_metadata = response
+            .ok_or({
+                MetastoreError::NotFound(EntityKind::Index {
+                    index_id: request.get_index_id().expect("index_id is set").to_string(),
+                })
+            })?
+            .index_metadata()?;
+        let response = IndexMetadataResponse::try_from_index_metadata(index_metadata)?;
+        Ok(response)
+    }
+
+    #[instrument(skip(self))]
+    async fn add_source(&mut self, request: AddSourceRequest) -> MetastoreResult<EmptyResponse> {
+        let source_config = request.deserialize_source_config()?;
+        let index_uid: IndexUid = request.index_uid.into();
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata::<MetastoreError, _>(
+                tx,
+                index_uid,
+                |index_metadata: &mut IndexMetadata| {
+                    index_metadata.add_source(source_config)?;
+                    Ok(true)
+                },
+            )
+            .await?;
+            Ok(())
+        })?;
+        Ok(EmptyResponse {})
+    }
+
+    #[instrument(skip(self))]
+    async fn toggle_source(
+        &mut self,
+        request: ToggleSourceRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        let index_uid: IndexUid = request.index_uid.into();
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_uid, |index_metadata| {
+                index_metadata.toggle_source(&request.source_id, request.enable)
+            })
+            .await?;
+            Ok(())
+        })?;
+        Ok(EmptyResponse {})
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_source(
+        &mut self,
+        request: DeleteSourceRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        let index_uid: IndexUid = request.index_uid.into();
+        let source_id = request.source_id.clone();
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_uid.clone(), |index_metadata| {
+                index_metadata.delete_source(&source_id)
+            })
+            .await?;
+            sqlx::query(
+                r#"
+                    DELETE FROM shards
+                    WHERE
+                        index_uid = ?1
+                        AND source_id = ?2
+                "#,
+            )
+            .bind(index_uid.as_str())
+            .bind(source_id)
+            .execute(tx.as_mut())
+            .await?;
+            Ok(())
+        })?;
+        Ok(EmptyResponse {})
+    }
+
+    #[instrument(skip(self))]
+    async fn reset_source_checkpoint(
+        &mut self,
+        request: ResetSourceCheckpointRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        let index_uid: IndexUid = request.index_uid.into();
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_uid, |index_metadata| {
+                Ok::<_, MetastoreError>(index_metadata.checkpoint.reset_source(&request.source_id))
+            })
+            .await?;
+            Ok(())
+        })?;
+        Ok(EmptyResponse {})
+    }
+
+    /// Retrieves the last delete opstamp for a given `index_id`.
+    #[instrument(skip(self))]
+    async fn last_delete_opstamp(
+        &mut self,
+        request: LastDeleteOpstampRequest,
+    ) -> MetastoreResult<LastDeleteOpstampResponse> {
+        let max_opstamp: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COALESCE(MAX(opstamp), 0)
+            FROM delete_tasks
+            WHERE index_uid = ?1
+        "#,
+        )
+        .bind(request.index_uid)
+        .fetch_one(&self.connection_pool)
+        .await
+        .map_err(|error| MetastoreError::Db {
+            message: error.to_string(),
+        })?;
+
+        Ok(LastDeleteOpstampResponse::new(max_opstamp as u64))
+    }
+
+    /// Creates a delete task from a delete query.
+    #[instrument(skip(self))]
+    async fn create_delete_task(
+        &mut self,
+        delete_query: DeleteQuery,
+    ) -> MetastoreResult<DeleteTask> {
+        let delete_query_json = serde_json::to_string(&delete_query).map_err(|error| {
+            MetastoreError::JsonSerializeError {
+                struct_name: "DeleteQuery".to_string(),
+                message: error.to_string(),
+            }
+        })?;
+        let (create_timestamp, opstamp): (i64, i64) = sqlx::query_as(
+            r#"
+                INSERT INTO delete_tasks (index_uid, delete_query_json) VALUES (?1, ?2)
+                RETURNING create_timestamp, opstamp
+            "#,
+        )
+        .bind(delete_query.index_uid.to_string())
+        .bind(&delete_query_json)
+        .fetch_one(&self.connection_pool)
+        .await
+        .map_err(|error| {
+            convert_sqlx_err(
+                IndexUid::from(delete_query.index_uid.to_string()).index_id(),
+                error,
+            )
+        })?;
+
+        Ok(DeleteTask {
+            create_timestamp,
+            opstamp: opstamp as u64,
+            delete_query: Some(delete_query),
+        })
+    }
+
+    /// Update splits delete opstamps.
+    #[instrument(skip(self))]
+    async fn update_splits_delete_opstamp(
+        &mut self,
+        request: UpdateSplitsDeleteOpstampRequest,
+    ) -> MetastoreResult<UpdateSplitsDeleteOpstampResponse> {
+        let index_uid: IndexUid = request.index_uid.into();
+        let split_ids = request.split_ids;
+        if split_ids.is_empty() {
+            return Ok(UpdateSplitsDeleteOpstampResponse {});
+        }
+        let update_result = sqlx::query(
+            r#"
+            UPDATE splits
+            SET
+                delete_opstamp = ?1,
+                -- The values we compare with are *before* the modification:
+                update_timestamp = CASE
+                    WHEN delete_opstamp != ?1 THEN CAST(strftime('%s', 'now') AS INTEGER)
+                    ELSE update_timestamp
+                END
+            WHERE
+                index_uid = ?2
+                AND split_id IN (SELECT value FROM json_each(?3))
+        "#,
+        )
+        .bind(request.delete_opstamp as i64)
+        .bind(index_uid.as_str())
+        .bind(to_json_array(&split_ids))
+        .execute(&self.connection_pool)
+        .await?;
+
+        // If no splits were updated, maybe the index does not exist in the first place?
+        if update_result.rows_affected() == 0
+            && index_opt_for_uid(&self.connection_pool, index_uid.clone())
+                .await?
+                .is_none()
+        {
+            return Err(MetastoreError::NotFound(EntityKind::Index {
+                index_id: index_uid.index_id().to_string(),
+            }));
+        }
+        Ok(UpdateSplitsDeleteOpstampResponse {})
+    }
+
+    /// Lists the delete tasks with opstamp > `opstamp_start`.
+    #[instrument(skip(self))]
+    async fn list_delete_tasks(
+        &mut self,
+        request: ListDeleteTasksRequest,
+    ) -> MetastoreResult<ListDeleteTasksResponse> {
+        let index_uid: IndexUid = request.index_uid.into();
+        let sqlite_delete_tasks: Vec<SqliteDeleteTask> = sqlx::query_as::<_, SqliteDeleteTask>(
+            r#"
+                SELECT * FROM delete_tasks
+                WHERE
+                    index_uid = ?1
+                    AND opstamp > ?2
+                "#,
+        )
+        .bind(index_uid.as_str())
+        .bind(request.opstamp_start as i64)
+        .fetch_all(&self.connection_pool)
+        .await?;
+        let delete_tasks: Vec<DeleteTask> = sqlite_delete_tasks
+            .into_iter()
+            .map(|sqlite_delete_task| sqlite_delete_task.try_into())
+            .collect::<MetastoreResult<_>>()?;
+        Ok(ListDeleteTasksResponse { delete_tasks })
+    }
+
+    /// Returns `num_splits` published splits with `split.delete_opstamp` < `delete_opstamp`.
+    /// Results are ordered by ascending `split.delete_opstamp` and `split.publish_timestamp`
+    /// values.
+    #[instrument(skip(self))]
+    async fn list_stale_splits(
+        &mut self,
+        request: ListStaleSplitsRequest,
+    ) -> MetastoreResult<ListSplitsResponse> {
+        let index_uid: IndexUid = request.index_uid.into();
+        let stale_sqlite_splits: Vec<SqliteSplit> = sqlx::query_as::<_, SqliteSplit>(
+            r#"
+                SELECT *
+                FROM splits
+                WHERE
+                    index_uid = ?1
+                    AND delete_opstamp < ?2
+                    AND split_state = ?3
+                    AND (maturity_timestamp = 0 OR CAST(strftime('%s', 'now') AS INTEGER) >= maturity_timestamp)
+                ORDER BY delete_opstamp ASC, publish_timestamp ASC
+                LIMIT ?4
+            "#,
+        )
+        .bind(index_uid.as_str())
+        .bind(request.delete_opstamp as i64)
+        .bind(SplitState::Published.as_str())
+        .bind(request.num_splits as i64)
+        .fetch_all(&self.connection_pool)
+        .await?;
+
+        let stale_splits: Vec<Split> = stale_sqlite_splits
+            .into_iter()
+            .map(|sqlite_split| sqlite_split.try_into())
+            .collect::<MetastoreResult<_>>()?;
+        let response = ListSplitsResponse::try_from_splits(stale_splits)?;
+        Ok(response)
+    }
+
+    async fn open_shards(
+        &mut self,
+        request: OpenShardsRequest,
+    ) -> MetastoreResult<OpenShardsResponse> {
+        let mut subresponses = Vec::with_capacity(request.subrequests.len());
+
+        for subrequest in request.subrequests {
+            let shard: Shard = open_or_fetch_shard(&self.connection_pool, &subrequest).await?;
+
+            subresponses.push(OpenShardsSubresponse {
+                subrequest_id: subrequest.subrequest_id,
+                index_uid: subrequest.index_uid,
+                source_id: subrequest.source_id,
+                opened_shards: vec![shard],
+            });
+        }
+        Ok(OpenShardsResponse { subresponses })
+    }
+
+    async fn acquire_shards(
+        &mut self,
+        request: AcquireShardsRequest,
+    ) -> MetastoreResult<AcquireShardsResponse> {
+        const ACQUIRE_SHARDS_QUERY: &str = include_str!("queries/acquire_shards.sql");
+
+        let mut subresponses = Vec::with_capacity(request.subrequests.len());
+
+        for subrequest in request.subrequests {
+            let shard_ids: Vec<&str> = subrequest
+                .shard_ids
+                .iter()
+                .map(|shard_id| shard_id.as_str())
+                .collect();
+            let sqlite_shards: Vec<SqliteShard> = sqlx::query_as(ACQUIRE_SHARDS_QUERY)
+                .bind(&subrequest.index_uid)
+                .bind(&subrequest.source_id)
+                .bind(to_json_array(&shard_ids))
+                .bind(subrequest.publish_token)
+                .fetch_all(&self.connection_pool)
+                .await?;
+
+            let acquired_shards = sqlite_shards
+                .into_iter()
+                .map(|sqlite_shard| sqlite_shard.into())
+                .collect();
+
+            subresponses.push(AcquireShardsSubresponse {
+                index_uid: subrequest.index_uid,
+                source_id: subrequest.source_id,
+                acquired_shards,
+            });
+        }
+        Ok(AcquireShardsResponse { subresponses })
+    }
+
+    async fn list_shards(
+        &mut self,
+        request: ListShardsRequest,
+    ) -> MetastoreResult<ListShardsResponse> {
+        const LIST_SHARDS_QUERY: &str = include_str!("queries/list_shards.sql");
+
+        let mut subresponses = Vec::with_capacity(request.subrequests.len());
+
+        for subrequest in request.subrequests {
+            let shard_state: Option<&'static str> = match subrequest.shard_state() {
+                ShardState::Unspecified => None,
+                shard_state => Some(shard_state_str(shard_state)),
+            };
+            let sqlite_shards: Vec<SqliteShard> = sqlx::query_as(LIST_SHARDS_QUERY)
+                .bind(&subrequest.index_uid)
+                .bind(&subrequest.source_id)
+                .bind(shard_state)
+                .fetch_all(&self.connection_pool)
+                .await?;
+
+            let shards = sqlite_shards
+                .into_iter()
+                .map(|sqlite_shard| sqlite_shard.into())
+                .collect();
+
+            subresponses.push(ListShardsSubresponse {
+                index_uid: subrequest.index_uid,
+                source_id: subrequest.source_id,
+                shards,
+            });
+        }
+        Ok(ListShardsResponse { subresponses })
+    }
+
+    async fn delete_shards(
+        &mut self,
+        request: DeleteShardsRequest,
+    ) -> MetastoreResult<DeleteShardsResponse> {
+        const DELETE_SHARDS_QUERY: &str = include_str!("queries/delete_shards.sql");
+
+        for subrequest in request.subrequests {
+            let shard_ids: Vec<&str> = subrequest
+                .shard_ids
+                .iter()
+                .map(|shard_id| shard_id.as_str())
+                .collect();
+
+            sqlx::query(DELETE_SHARDS_QUERY)
+                .bind(&subrequest.index_uid)
+                .bind(&subrequest.source_id)
+                .bind(to_json_array(&shard_ids))
+                .bind(request.force)
+                .execute(&self.connection_pool)
+                .await?;
+        }
+        Ok(DeleteShardsResponse {})
+    }
+}
+
+async fn open_or_fetch_shard<'e>(
+    executor: impl Executor<'e, Database = Sqlite> + Clone,
+    subrequest: &OpenShardsSubrequest,
+) -> MetastoreResult<Shard> {
+    const OPEN_SHARDS_QUERY: &str = include_str!("queries/open_shard.sql");
+
+    let sqlite_shard_opt: Option<SqliteShard> = sqlx::query_as(OPEN_SHARDS_QUERY)
+        .bind(&subrequest.index_uid)
+        .bind(&subrequest.source_id)
+        .bind(subrequest.shard_id().as_str())
+        .bind(&subrequest.leader_id)
+        .bind(&subrequest.follower_id)
+        .fetch_optional(executor.clone())
+        .await?;
+
+    if let Some(sqlite_shard) = sqlite_shard_opt {
+        let shard: Shard = sqlite_shard.into();
+        info!(
+            index_id=%shard.index_uid,
+            source_id=%shard.source_id,
+            shard_id=%shard.shard_id(),
+            leader_id=%shard.leader_id,
+            follower_id=?shard.follower_id,
+            "opened shard"
+        );
+        return Ok(shard);
+    }
+    const FETCH_SHARD_QUERY: &str = include_str!("queries/fetch_shard.sql");
+
+    let sqlite_shard_opt: Option<SqliteShard> = sqlx::query_as(FETCH_SHARD_QUERY)
+        .bind(&subrequest.index_uid)
+        .bind(&subrequest.source_id)
+        .bind(subrequest.shard_id().as_str())
+        .fetch_optional(executor)
+        .await?;
+
+    if let Some(sqlite_shard) = sqlite_shard_opt {
+        return Ok(sqlite_shard.into());
+    }
+    Err(MetastoreError::NotFound(EntityKind::Source {
+        index_id: subrequest.index_uid.clone(),
+        source_id: subrequest.source_id.clone(),
+    }))
+}
+
+impl MetastoreServiceExt for SqliteMetastore {}
+
+#[cfg(test)]
+#[async_trait]
+impl crate::tests::DefaultForTest for SqliteMetastore {
+    async fn default_for_test() -> Self {
+        // Each test gets its own in-memory database.
+        let uri = Uri::for_test("sqlite://:memory:");
+        SqliteMetastore::new(&SqliteMetastoreConfig::default(), &uri)
+            .await
+            .expect("failed to initialize SQLite metastore test")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use quickwit_common::uri::{Protocol, Uri};
+    use quickwit_config::{IndexConfig, SqliteMetastoreConfig};
+    use quickwit_doc_mapper::tag_pruning::{no_tag, tag, TagFilterAst};
+    use quickwit_proto::ingest::Shard;
+    use quickwit_proto::metastore::{CreateIndexRequest, MetastoreService};
+    use quickwit_proto::types::{IndexUid, SourceId};
+    use sea_query::{Asterisk, Query, SqliteQueryBuilder};
+
+    use super::model::{shard_state_str, SqliteShard};
+    use super::{append_query_filters, SqliteMetastore};
+    use crate::metastore::sql_utils::Splits;
+    use crate::tests::shard::ReadWriteShardsForTest;
+    use crate::tests::DefaultForTest;
+    use crate::{
+        metastore_test_suite, CreateIndexRequestExt, ListSplitsQuery, MetastoreServiceExt,
+        SplitState,
+    };
+
+    #[async_trait]
+    impl ReadWriteShardsForTest for SqliteMetastore {
+        async fn insert_shards(
+            &mut self,
+            index_uid: &IndexUid,
+            source_id: &SourceId,
+            shards: Vec<Shard>,
+        ) {
+            const INSERT_SHARD_QUERY: &str = include_str!("queries/insert_shard.sql");
+
+            for shard in shards {
+                sqlx::query(INSERT_SHARD_QUERY)
+                    .bind(index_uid.as_str())
+                    .bind(source_id)
+                    .bind(shard.shard_id().as_str())
+                    .bind(shard_state_str(shard.shard_state()))
+                    .bind(&shard.leader_id)
+                    .bind(&shard.follower_id)
+                    .bind(&shard.publish_position_inclusive().to_string())
+                    .bind(&shard.publish_token)
+                    .execute(&self.connection_pool)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        async fn list_all_shards(&self, index_uid: &IndexUid, source_id: &SourceId) -> Vec<Shard> {
+            let sqlite_shards: Vec<SqliteShard> = sqlx::query_as(
+                r#"
+                SELECT *
+                FROM shards
+                WHERE
+                    index_uid = ?1
+                    AND source_id = ?2
+                "#,
+            )
+            .bind(index_uid.as_str())
+            .bind(source_id.as_str())
+            .fetch_all(&self.connection_pool)
+            .await
+            .unwrap();
+
+            sqlite_shards
+                .into_iter()
+                .map(|sqlite_shard| sqlite_shard.into())
+                .collect()
+        }
+    }
+
+    metastore_test_suite!(crate::SqliteMetastore);
+
+    #[tokio::test]
+    async fn test_metastore_connectivity_and_endpoints() {
+        let mut metastore = SqliteMetastore::default_for_test().await;
+        metastore.check_connectivity().await.unwrap();
+        assert_eq!(metastore.endpoints()[0].protocol(), Protocol::SQLite);
+    }
+
+    #[tokio::test]
+    async fn test_metastore_persists_across_restarts() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let uri: Uri = format!("sqlite://{}/metastore.db", tmp_dir.path().display())
+            .parse()
+            .unwrap();
+        {
+            let mut metastore = SqliteMetastore::new(&SqliteMetastoreConfig::default(), &uri)
+                .await
+                .unwrap();
+            let index_config = IndexConfig::for_test("test-index", "ram:///indexes/test-index");
+            let create_index_request =
+                CreateIndexRequest::try_from_index_config(index_config).unwrap();
+            metastore.create_index(create_index_request).await.unwrap();
+            metastore.connection_pool.close().await;
+        }
+        let mut metastore = SqliteMetastore::new(&SqliteMetastoreConfig::default(), &uri)
+            .await
+            .unwrap();
+        assert!(metastore.index_exists("test-index").await.unwrap());
+    }
+
+    #[test]
+    fn test_tags_filter_sql_query_builder() {
+        let index_uid = IndexUid::new_with_random_ulid("test-index");
+
+        let mut select_statement = Query::select();
+        let sql = select_statement.column(Asterisk).from(Splits::Table);
+        let query = ListSplitsQuery::for_index(index_uid.clone())
+            .with_tags_filter(TagFilterAst::Or(vec![tag("tag:val1"), no_tag("tag:'val2")]));
+        append_query_filters(sql, &query);
+
+        assert_eq!(
+            sql.to_string(SqliteQueryBuilder),
+            format!(
+                r#"SELECT * FROM "splits" WHERE "index_uid" = '{index_uid}' AND (EXISTS (SELECT 1 FROM json_each(tags) WHERE json_each.value = 'tag:val1') OR (NOT EXISTS (SELECT 1 FROM json_each(tags) WHERE json_each.value = 'tag:''val2')))"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_timestamps_sql_query_builder() {
+        let index_uid = IndexUid::new_with_random_ulid("test-index");
+
+        let mut select_statement = Query::select();
+        let sql = select_statement.column(Asterisk).from(Splits::Table);
+        let query = ListSplitsQuery::for_index(index_uid.clone())
+            .with_split_state(SplitState::Published)
+            .with_update_timestamp_lt(51)
+            .with_create_timestamp_lte(63);
+        append_query_filters(sql, &query);
+
+        assert_eq!(
+            sql.to_string(SqliteQueryBuilder),
+            format!(
+                r#"SELECT * FROM "splits" WHERE "index_uid" = '{index_uid}' AND "split_state" IN ('Published') AND "update_timestamp" < 51 AND "create_timestamp" <= 63"#
+            )
+        );
+    }
+}
@@ -0,0 +1,9 @@
+UPDATE
+    shards
+SET
+    publish_token = ?4
+WHERE
+    index_uid = ?1
+    AND source_id = ?2
+    AND shard_id IN (SELECT value FROM json_each(?3))
+RETURNING *
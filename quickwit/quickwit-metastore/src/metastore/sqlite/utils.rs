@@ -0,0 +1,209 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::ops::Bound;
+use std::str::FromStr;
+
+use quickwit_common::uri::Uri;
+use quickwit_doc_mapper::tag_pruning::TagFilterAst;
+use quickwit_proto::metastore::{MetastoreError, MetastoreResult};
+use sea_query::{all, any, Cond, Expr, Order, SelectStatement};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{ConnectOptions, Pool, Sqlite};
+use tracing::error;
+use tracing::log::LevelFilter;
+
+use crate::metastore::sql_utils::Splits;
+use crate::metastore::FilterRange;
+use crate::ListSplitsQuery;
+
+/// Opens the SQLite database located at `connection_uri` and creates it if it does not exist.
+///
+/// The pool holds a single connection that is never recycled: SQLite serializes writes anyway,
+/// and a single connection rules out `SQLITE_BUSY` errors between concurrent transactions. It
+/// also keeps in-memory databases (`sqlite://:memory:`) alive for the lifetime of the pool.
+pub(super) async fn establish_connection(connection_uri: &Uri) -> MetastoreResult<Pool<Sqlite>> {
+    let pool_options = SqlitePoolOptions::new()
+        .min_connections(1)
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None);
+    let connect_options: SqliteConnectOptions =
+        SqliteConnectOptions::from_str(connection_uri.as_str())?
+            .create_if_missing(true)
+            .foreign_keys(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            // Index ID patterns are matched with `LIKE`, which is case-insensitive by default.
+            .pragma("case_sensitive_like", "ON")
+            .log_statements(LevelFilter::Info);
+    pool_options
+        .connect_with(connect_options)
+        .await
+        .map_err(|error| {
+            error!(connection_uri=%connection_uri, error=?error, "failed to open SQLite database");
+            MetastoreError::Connection {
+                message: error.to_string(),
+            }
+        })
+}
+
+/// Takes a tag filters AST and returns a sql expression that can be used as
+/// a filter. Tags are stored as a JSON array, so their presence is checked with `json_each`.
+pub(super) fn tags_filter_expression_helper(tags: &TagFilterAst) -> Cond {
+    match tags {
+        TagFilterAst::And(child_asts) => {
+            if child_asts.is_empty() {
+                return all![Expr::cust("TRUE")];
+            }
+
+            child_asts
+                .iter()
+                .map(tags_filter_expression_helper)
+                .fold(Cond::all(), |cond, child_cond| cond.add(child_cond))
+        }
+        TagFilterAst::Or(child_asts) => {
+            if child_asts.is_empty() {
+                return all![Expr::cust("TRUE")];
+            }
+
+            child_asts
+                .iter()
+                .map(tags_filter_expression_helper)
+                .fold(Cond::any(), |cond, child_cond| cond.add(child_cond))
+        }
+
+        TagFilterAst::Tag { is_present, tag } => {
+            // The tag is bound as a value, which rules out SQL injections.
+            let expr = Expr::cust_with_values(
+                "EXISTS (SELECT 1 FROM json_each(tags) WHERE json_each.value = ?)",
+                [tag.clone()],
+            );
+            if *is_present {
+                all![expr]
+            } else {
+                all![expr.not()]
+            }
+        }
+    }
+}
+
+fn append_range_filters<V: Copy + Into<sea_query::Value>>(
+    sql: &mut SelectStatement,
+    field_name: Splits,
+    filter_range: &FilterRange<V>,
+) {
+    if let Bound::Included(value) = filter_range.start {
+        sql.cond_where(Expr::col(field_name).gte(value));
+    };
+
+    if let Bound::Excluded(value) = filter_range.start {
+        sql.cond_where(Expr::col(field_name).gt(value));
+    };
+
+    if let Bound::Included(value) = filter_range.end {
+        sql.cond_where(Expr::col(field_name).lte(value));
+    };
+
+    if let Bound::Excluded(value) = filter_range.end {
+        sql.cond_where(Expr::col(field_name).lt(value));
+    };
+}
+
+/// Appends the filters of a [`ListSplitsQuery`] to a select statement. Timestamps are stored as
+/// UNIX timestamps, so, unlike PostgreSQL, they are compared without any conversion.
+pub(super) fn append_query_filters(sql: &mut SelectStatement, query: &ListSplitsQuery) {
+    // Note: `ListSplitsQuery` builder enforces a non empty `index_uids` list.
+
+    let or_condition = query
+        .index_uids
+        .iter()
+        .fold(Cond::any(), |cond, index_uid| {
+            cond.add(Expr::col(Splits::IndexUid).eq(Expr::val(index_uid.to_string())))
+        });
+    sql.cond_where(or_condition);
+
+    if !query.split_states.is_empty() {
+        sql.cond_where(
+            Expr::col(Splits::SplitState)
+                .is_in(query.split_states.iter().map(|val| val.to_string())),
+        );
+    };
+
+    if let Some(tags) = query.tags.as_ref() {
+        sql.cond_where(tags_filter_expression_helper(tags));
+    };
+
+    match query.time_range.start {
+        Bound::Included(v) => {
+            sql.cond_where(any![
+                Expr::col(Splits::TimeRangeEnd).gte(v),
+                Expr::col(Splits::TimeRangeEnd).is_null()
+            ]);
+        }
+        Bound::Excluded(v) => {
+            sql.cond_where(any![
+                Expr::col(Splits::TimeRangeEnd).gt(v),
+                Expr::col(Splits::TimeRangeEnd).is_null()
+            ]);
+        }
+        Bound::Unbounded => {}
+    };
+
+    match query.time_range.end {
+        Bound::Included(v) => {
+            sql.cond_where(any![
+                Expr::col(Splits::TimeRangeStart).lte(v),
+                Expr::col(Splits::TimeRangeStart).is_null()
+            ]);
+        }
+        Bound::Excluded(v) => {
+            sql.cond_where(any![
+                Expr::col(Splits::TimeRangeStart).lt(v),
+                Expr::col(Splits::TimeRangeStart).is_null()
+            ]);
+        }
+        Bound::Unbounded => {}
+    };
+
+    match &query.mature {
+        Bound::Included(evaluation_datetime) => {
+            sql.cond_where(any![
+                Expr::col(Splits::MaturityTimestamp).eq(0),
+                Expr::col(Splits::MaturityTimestamp).lte(evaluation_datetime.unix_timestamp())
+            ]);
+        }
+        Bound::Excluded(evaluation_datetime) => {
+            sql.cond_where(
+                Expr::col(Splits::MaturityTimestamp).gt(evaluation_datetime.unix_timestamp()),
+            );
+        }
+        Bound::Unbounded => {}
+    };
+    append_range_filters(sql, Splits::UpdateTimestamp, &query.update_timestamp);
+    append_range_filters(sql, Splits::CreateTimestamp, &query.create_timestamp);
+    append_range_filters(sql, Splits::DeleteOpstamp, &query.delete_opstamp);
+
+    if let Some(limit) = query.limit {
+        sql.limit(limit as u64);
+    }
+
+    if let Some(offset) = query.offset {
+        sql.order_by(Splits::SplitId, Order::Asc)
+            .offset(offset as u64);
+    }
+}
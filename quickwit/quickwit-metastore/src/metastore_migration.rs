@@ -0,0 +1,657 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//! Migration of the content of a metastore to another metastore, for instance from the
+//! file-backed metastore to the PostgreSQL metastore.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, ensure, Context};
+use futures::TryStreamExt;
+use quickwit_config::INGEST_V2_SOURCE_ID;
+use quickwit_proto::ingest::Shard;
+use quickwit_proto::metastore::{
+    serde_utils, AcquireShardsRequest, AcquireShardsSubrequest, AddSourceRequest,
+    CreateIndexRequest, DeleteQuery, IndexMetadataRequest, ListDeleteTasksRequest,
+    ListIndexesMetadataRequest, ListShardsRequest, ListShardsSubrequest, ListSplitsRequest,
+    MarkSplitsForDeletionRequest, MetastoreService, MetastoreServiceClient, OpenShardsRequest,
+    OpenShardsSubrequest, PublishSplitsRequest, StageSplitsRequest,
+};
+use quickwit_proto::types::{IndexUid, Position, SplitId};
+use tracing::info;
+
+use crate::checkpoint::{IndexCheckpointDelta, PartitionId, SourceCheckpointDelta};
+use crate::{
+    AddSourceRequestExt, CreateIndexRequestExt, IndexMetadata, IndexMetadataResponseExt,
+    ListIndexesMetadataResponseExt, ListSplitsRequestExt, ListSplitsResponseExt,
+    MetastoreServiceExt, SplitState, StageSplitsRequestExt,
+};
+
+/// Publish token used to restore the positions of the shards that had not been acquired by an
+/// indexer in the source metastore.
+const MIGRATION_PUBLISH_TOKEN: &str = "metastore-migration";
+
+/// Maximum number of split IDs sent in a single publish or mark-for-deletion request.
+const SPLIT_IDS_BATCH_SIZE: usize = 1_000;
+
+/// Summary of the migration of an index.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexMigrationSummary {
+    /// ID of the migrated index.
+    pub index_id: String,
+    /// Number of sources of the index.
+    pub num_sources: usize,
+    /// Number of splits of the index, in any state.
+    pub num_splits: usize,
+    /// Number of delete tasks of the index.
+    pub num_delete_tasks: usize,
+    /// Number of shards of the index.
+    pub num_shards: usize,
+}
+
+/// Migrates all the indexes of the `source` metastore to the `target` metastore, along with their
+/// sources, checkpoints, splits, delete tasks, and shards, through the [`MetastoreService`] API.
+/// Once an index is migrated, its content in the target metastore is verified against the
+/// source metastore.
+///
+/// In dry run mode, the source metastore is only read and the target metastore is only checked
+/// for conflicting indexes.
+///
+/// The indexes must not be modified during the migration, so the nodes using the source
+/// metastore should be stopped beforehand. The split files are not copied: the migrated indexes
+/// keep their index URIs.
+pub async fn migrate_metastore(
+    mut source: MetastoreServiceClient,
+    mut target: MetastoreServiceClient,
+    dry_run: bool,
+) -> anyhow::Result<Vec<IndexMigrationSummary>> {
+    let indexes_metadata = source
+        .list_indexes_metadata(ListIndexesMetadataRequest::all())
+        .await?
+        .deserialize_indexes_metadata()?;
+
+    for index_metadata in &indexes_metadata {
+        if target.index_exists(index_metadata.index_id()).await? {
+            bail!(
+                "index `{}` already exists in the target metastore",
+                index_metadata.index_id()
+            );
+        }
+    }
+    let mut summaries = Vec::with_capacity(indexes_metadata.len());
+
+    for index_metadata in &indexes_metadata {
+        let summary = if dry_run {
+            summarize_index(&mut source, index_metadata).await?
+        } else {
+            let summary = migrate_index(&mut source, &mut target, index_metadata).await?;
+            verify_index_migration(&mut source, &mut target, index_metadata)
+                .await
+                .with_context(|| {
+                    format!(
+                        "failed to verify the migration of index `{}`",
+                        index_metadata.index_id()
+                    )
+                })?;
+            info!(index_id=%index_metadata.index_id(), "migrated index");
+            summary
+        };
+        summaries.push(summary);
+    }
+    Ok(summaries)
+}
+
+async fn summarize_index(
+    source: &mut MetastoreServiceClient,
+    index_metadata: &IndexMetadata,
+) -> anyhow::Result<IndexMigrationSummary> {
+    let index_uid = index_metadata.index_uid.clone();
+    let list_splits_request = ListSplitsRequest::try_from_index_uid(index_uid.clone())?;
+    let mut splits_stream = source.list_splits(list_splits_request).await?;
+    let mut num_splits = 0;
+
+    while let Some(list_splits_response) = splits_stream.try_next().await? {
+        num_splits += list_splits_response.deserialize_split_ids()?.len();
+    }
+    let num_delete_tasks = list_delete_tasks(source, index_uid).await?.len();
+    let num_shards = list_shards(source, index_metadata).await?.len();
+
+    Ok(IndexMigrationSummary {
+        index_id: index_metadata.index_id().to_string(),
+        num_sources: index_metadata.sources.len(),
+        num_splits,
+        num_delete_tasks,
+        num_shards,
+    })
+}
+
+async fn migrate_index(
+    source: &mut MetastoreServiceClient,
+    target: &mut MetastoreServiceClient,
+    index_metadata: &IndexMetadata,
+) -> anyhow::Result<IndexMigrationSummary> {
+    let source_index_uid = index_metadata.index_uid.clone();
+    let create_index_request =
+        CreateIndexRequest::try_from_index_config(index_metadata.index_config.clone())?;
+    let target_index_uid: IndexUid = target
+        .create_index(create_index_request)
+        .await?
+        .index_uid
+        .into();
+
+    for source_config in index_metadata.sources.values() {
+        let add_source_request = AddSourceRequest::try_from_source_config(
+            target_index_uid.clone(),
+            source_config.clone(),
+        )?;
+        target.add_source(add_source_request).await?;
+    }
+    // The target metastore assigns new opstamps to the delete tasks, so the delete opstamps of
+    // the splits must be remapped accordingly.
+    let delete_tasks = list_delete_tasks(source, source_index_uid.clone()).await?;
+    let num_delete_tasks = delete_tasks.len();
+    let mut opstamp_mapping: Vec<(u64, u64)> = Vec::with_capacity(num_delete_tasks);
+
+    for (source_opstamp, mut delete_query) in delete_tasks {
+        delete_query.index_uid = target_index_uid.to_string();
+        let target_delete_task = target.create_delete_task(delete_query).await?;
+        opstamp_mapping.push((source_opstamp, target_delete_task.opstamp));
+    }
+    let list_splits_request = ListSplitsRequest::try_from_index_uid(source_index_uid)?;
+    let mut splits_stream = source.list_splits(list_splits_request).await?;
+    let mut num_splits = 0;
+    let mut published_split_ids: Vec<SplitId> = Vec::new();
+    let mut marked_split_ids: Vec<SplitId> = Vec::new();
+
+    while let Some(list_splits_response) = splits_stream.try_next().await? {
+        let splits = list_splits_response.deserialize_splits()?;
+        num_splits += splits.len();
+
+        let mut splits_metadata = Vec::with_capacity(splits.len());
+
+        for split in splits {
+            match split.split_state {
+                SplitState::Staged => {}
+                SplitState::Published => published_split_ids.push(split.split_id().to_string()),
+                SplitState::MarkedForDeletion => {
+                    marked_split_ids.push(split.split_id().to_string())
+                }
+            }
+            let mut split_metadata = split.split_metadata;
+            split_metadata.index_uid = target_index_uid.clone();
+            split_metadata.delete_opstamp =
+                remap_delete_opstamp(&opstamp_mapping, split_metadata.delete_opstamp);
+            splits_metadata.push(split_metadata);
+        }
+        if !splits_metadata.is_empty() {
+            let stage_splits_request = StageSplitsRequest::try_from_splits_metadata(
+                target_index_uid.clone(),
+                splits_metadata,
+            )?;
+            target.stage_splits(stage_splits_request).await?;
+        }
+    }
+    for staged_split_ids in published_split_ids.chunks(SPLIT_IDS_BATCH_SIZE) {
+        let publish_splits_request = PublishSplitsRequest {
+            index_uid: target_index_uid.to_string(),
+            staged_split_ids: staged_split_ids.to_vec(),
+            ..Default::default()
+        };
+        target.publish_splits(publish_splits_request).await?;
+    }
+    for split_ids in marked_split_ids.chunks(SPLIT_IDS_BATCH_SIZE) {
+        let mark_splits_for_deletion_request =
+            MarkSplitsForDeletionRequest::new(target_index_uid.clone(), split_ids.to_vec());
+        target
+            .mark_splits_for_deletion(mark_splits_for_deletion_request)
+            .await?;
+    }
+    // The checkpoint of the ingest V2 source is restored along with the shards.
+    for source_id in index_metadata.sources.keys() {
+        if source_id == INGEST_V2_SOURCE_ID {
+            continue;
+        }
+        let Some(source_checkpoint) = index_metadata.checkpoint.source_checkpoint(source_id) else {
+            continue;
+        };
+        let source_delta = SourceCheckpointDelta::from_source_checkpoint(source_checkpoint);
+
+        if source_delta.is_empty() {
+            continue;
+        }
+        let index_checkpoint_delta = IndexCheckpointDelta {
+            source_id: source_id.clone(),
+            source_delta,
+        };
+        let publish_splits_request = PublishSplitsRequest {
+            index_uid: target_index_uid.to_string(),
+            index_checkpoint_delta_json_opt: Some(serde_utils::to_json_str(
+                &index_checkpoint_delta,
+            )?),
+            ..Default::default()
+        };
+        target.publish_splits(publish_splits_request).await?;
+    }
+    let shards = list_shards(source, index_metadata).await?;
+    let num_shards = shards.len();
+
+    for shard in shards {
+        migrate_shard(target, &target_index_uid, shard).await?;
+    }
+    Ok(IndexMigrationSummary {
+        index_id: index_metadata.index_id().to_string(),
+        num_sources: index_metadata.sources.len(),
+        num_splits,
+        num_delete_tasks,
+        num_shards,
+    })
+}
+
+/// Opens the shard in the target metastore, then restores its publish token and position by
+/// acquiring it and publishing a checkpoint delta.
+async fn migrate_shard(
+    target: &mut MetastoreServiceClient,
+    target_index_uid: &IndexUid,
+    shard: Shard,
+) -> anyhow::Result<()> {
+    let shard_id = shard.shard_id().clone();
+    let open_shards_request = OpenShardsRequest {
+        subrequests: vec![OpenShardsSubrequest {
+            subrequest_id: 0,
+            index_uid: target_index_uid.to_string(),
+            source_id: shard.source_id.clone(),
+            shard_id: Some(shard_id.clone()),
+            leader_id: shard.leader_id.clone(),
+            follower_id: shard.follower_id.clone(),
+        }],
+    };
+    target.open_shards(open_shards_request).await?;
+
+    let publish_position = shard.publish_position_inclusive.clone().unwrap_or_default();
+
+    let Some(publish_token) = shard.publish_token.clone().or_else(|| {
+        (publish_position != Position::Beginning).then(|| MIGRATION_PUBLISH_TOKEN.to_string())
+    }) else {
+        return Ok(());
+    };
+    let acquire_shards_request = AcquireShardsRequest {
+        subrequests: vec![AcquireShardsSubrequest {
+            index_uid: target_index_uid.to_string(),
+            source_id: shard.source_id.clone(),
+            shard_ids: vec![shard_id.clone()],
+            publish_token: publish_token.clone(),
+        }],
+    };
+    target.acquire_shards(acquire_shards_request).await?;
+
+    if publish_position == Position::Beginning {
+        return Ok(());
+    }
+    let source_delta = SourceCheckpointDelta::from_partition_delta(
+        PartitionId::from(shard_id.as_str()),
+        Position::Beginning,
+        publish_position,
+    )?;
+    let index_checkpoint_delta = IndexCheckpointDelta {
+        source_id: shard.source_id,
+        source_delta,
+    };
+    let publish_splits_request = PublishSplitsRequest {
+        index_uid: target_index_uid.to_string(),
+        index_checkpoint_delta_json_opt: Some(serde_utils::to_json_str(&index_checkpoint_delta)?),
+        publish_token_opt: Some(publish_token),
+        ..Default::default()
+    };
+    target.publish_splits(publish_splits_request).await?;
+    Ok(())
+}
+
+/// Checks that the index metadata, the splits, the delete tasks, and the shards of a migrated
+/// index in the target metastore match the ones in the source metastore.
+async fn verify_index_migration(
+    source: &mut MetastoreServiceClient,
+    target: &mut MetastoreServiceClient,
+    source_index_metadata: &IndexMetadata,
+) -> anyhow::Result<()> {
+    let index_metadata_request =
+        IndexMetadataRequest::for_index_id(source_index_metadata.index_id().to_string());
+    let target_index_metadata = target
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+
+    ensure!(
+        target_index_metadata.index_config == source_index_metadata.index_config,
+        "index configs differ"
+    );
+    ensure!(
+        target_index_metadata.sources == source_index_metadata.sources,
+        "sources differ"
+    );
+    ensure!(
+        target_index_metadata.checkpoint == source_index_metadata.checkpoint,
+        "checkpoints differ"
+    );
+    let source_split_states =
+        list_split_states(source, source_index_metadata.index_uid.clone()).await?;
+    let target_split_states =
+        list_split_states(target, target_index_metadata.index_uid.clone()).await?;
+    ensure!(
+        target_split_states == source_split_states,
+        "splits differ: expected {} splits, found {}",
+        source_split_states.len(),
+        target_split_states.len()
+    );
+    let source_delete_queries: Vec<DeleteQuery> =
+        list_delete_tasks(source, source_index_metadata.index_uid.clone())
+            .await?
+            .into_iter()
+            .map(|(_, delete_query)| delete_query)
+            .collect();
+    let target_delete_queries: Vec<DeleteQuery> =
+        list_delete_tasks(target, target_index_metadata.index_uid.clone())
+            .await?
+            .into_iter()
+            .map(|(_, mut delete_query)| {
+                delete_query.index_uid = source_index_metadata.index_uid.to_string();
+                delete_query
+            })
+            .collect();
+    ensure!(
+        target_delete_queries == source_delete_queries,
+        "delete tasks differ"
+    );
+    let shard_positions = |shards: Vec<Shard>| {
+        let mut shard_positions: Vec<(String, String, Option<Position>)> = shards
+            .into_iter()
+            .map(|shard| {
+                (
+                    shard.source_id.clone(),
+                    shard.shard_id().to_string(),
+                    shard.publish_position_inclusive,
+                )
+            })
+            .collect();
+        shard_positions.sort_unstable();
+        shard_positions
+    };
+    let source_shard_positions = shard_positions(list_shards(source, source_index_metadata).await?);
+    let target_shard_positions =
+        shard_positions(list_shards(target, &target_index_metadata).await?);
+    ensure!(
+        target_shard_positions == source_shard_positions,
+        "shards differ"
+    );
+    Ok(())
+}
+
+/// Returns the delete queries of an index along with their opstamps, sorted by opstamp.
+async fn list_delete_tasks(
+    metastore: &mut MetastoreServiceClient,
+    index_uid: IndexUid,
+) -> anyhow::Result<Vec<(u64, DeleteQuery)>> {
+    let list_delete_tasks_request = ListDeleteTasksRequest::new(index_uid, 0);
+    let mut delete_tasks: Vec<(u64, DeleteQuery)> = metastore
+        .list_delete_tasks(list_delete_tasks_request)
+        .await?
+        .delete_tasks
+        .into_iter()
+        .map(|delete_task| {
+            let delete_query = delete_task
+                .delete_query
+                .context("delete task should have a delete query")?;
+            Ok((delete_task.opstamp, delete_query))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    delete_tasks.sort_unstable_by_key(|(opstamp, _)| *opstamp);
+    Ok(delete_tasks)
+}
+
+async fn list_shards(
+    metastore: &mut MetastoreServiceClient,
+    index_metadata: &IndexMetadata,
+) -> anyhow::Result<Vec<Shard>> {
+    if !index_metadata.sources.contains_key(INGEST_V2_SOURCE_ID) {
+        return Ok(Vec::new());
+    }
+    let list_shards_request = ListShardsRequest {
+        subrequests: vec![ListShardsSubrequest {
+            index_uid: index_metadata.index_uid.to_string(),
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+            shard_state: None,
+        }],
+    };
+    let shards = metastore
+        .list_shards(list_shards_request)
+        .await?
+        .subresponses
+        .into_iter()
+        .flat_map(|subresponse| subresponse.shards)
+        .collect();
+    Ok(shards)
+}
+
+async fn list_split_states(
+    metastore: &mut MetastoreServiceClient,
+    index_uid: IndexUid,
+) -> anyhow::Result<HashMap<SplitId, SplitState>> {
+    let list_splits_request = ListSplitsRequest::try_from_index_uid(index_uid)?;
+    let mut splits_stream = metastore.list_splits(list_splits_request).await?;
+    let mut split_states = HashMap::new();
+
+    while let Some(list_splits_response) = splits_stream.try_next().await? {
+        for split in list_splits_response.deserialize_splits()? {
+            split_states.insert(split.split_metadata.split_id, split.split_state);
+        }
+    }
+    Ok(split_states)
+}
+
+/// Maps the delete opstamp of a split in the source metastore to the opstamp of the last delete
+/// task applied to the split in the target metastore.
+fn remap_delete_opstamp(opstamp_mapping: &[(u64, u64)], delete_opstamp: u64) -> u64 {
+    let num_applied_delete_tasks =
+        opstamp_mapping.partition_point(|(source_opstamp, _)| *source_opstamp <= delete_opstamp);
+
+    if num_applied_delete_tasks == 0 {
+        return 0;
+    }
+    opstamp_mapping[num_applied_delete_tasks - 1].1
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_config::{IndexConfig, SourceConfig, SourceParams};
+    use quickwit_proto::types::ShardId;
+    use quickwit_query::query_ast::qast_json_helper;
+
+    use super::*;
+    use crate::{metastore_for_test, SplitMetadata};
+
+    async fn create_source_index(metastore: &mut MetastoreServiceClient) -> IndexUid {
+        let index_config = IndexConfig::for_test("test-index", "ram:///indexes/test-index");
+        let create_index_request = CreateIndexRequest::try_from_index_config(index_config).unwrap();
+        let index_uid: IndexUid = metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid
+            .into();
+
+        let source_configs = [
+            SourceConfig::for_test("test-source", SourceParams::void()),
+            SourceConfig::ingest_v2_default(),
+        ];
+        for source_config in source_configs {
+            let add_source_request =
+                AddSourceRequest::try_from_source_config(index_uid.clone(), source_config).unwrap();
+            metastore.add_source(add_source_request).await.unwrap();
+        }
+        let delete_query = DeleteQuery {
+            index_uid: index_uid.to_string(),
+            query_ast: qast_json_helper("body:foo", &[]),
+            start_timestamp: Some(1),
+            end_timestamp: Some(2),
+        };
+        let delete_task = metastore.create_delete_task(delete_query).await.unwrap();
+
+        let splits_metadata: Vec<SplitMetadata> = ["split-1", "split-2", "split-3"]
+            .into_iter()
+            .map(|split_id| SplitMetadata {
+                split_id: split_id.to_string(),
+                index_uid: index_uid.clone(),
+                delete_opstamp: delete_task.opstamp,
+                ..Default::default()
+            })
+            .collect();
+        let stage_splits_request =
+            StageSplitsRequest::try_from_splits_metadata(index_uid.clone(), splits_metadata)
+                .unwrap();
+        metastore.stage_splits(stage_splits_request).await.unwrap();
+
+        let index_checkpoint_delta = IndexCheckpointDelta {
+            source_id: "test-source".to_string(),
+            source_delta: SourceCheckpointDelta::from_range(0..10),
+        };
+        let publish_splits_request = PublishSplitsRequest {
+            index_uid: index_uid.to_string(),
+            staged_split_ids: vec!["split-2".to_string(), "split-3".to_string()],
+            index_checkpoint_delta_json_opt: Some(
+                serde_utils::to_json_str(&index_checkpoint_delta).unwrap(),
+            ),
+            ..Default::default()
+        };
+        metastore
+            .publish_splits(publish_splits_request)
+            .await
+            .unwrap();
+        let mark_splits_for_deletion_request =
+            MarkSplitsForDeletionRequest::new(index_uid.clone(), vec!["split-3".to_string()]);
+        metastore
+            .mark_splits_for_deletion(mark_splits_for_deletion_request)
+            .await
+            .unwrap();
+
+        let shard_id = ShardId::from(1);
+        let open_shards_request = OpenShardsRequest {
+            subrequests: vec![OpenShardsSubrequest {
+                subrequest_id: 0,
+                index_uid: index_uid.to_string(),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(shard_id.clone()),
+                leader_id: "test-ingester".to_string(),
+                follower_id: None,
+            }],
+        };
+        metastore.open_shards(open_shards_request).await.unwrap();
+
+        let acquire_shards_request = AcquireShardsRequest {
+            subrequests: vec![AcquireShardsSubrequest {
+                index_uid: index_uid.to_string(),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_ids: vec![shard_id.clone()],
+                publish_token: "test-publish-token".to_string(),
+            }],
+        };
+        metastore
+            .acquire_shards(acquire_shards_request)
+            .await
+            .unwrap();
+
+        let index_checkpoint_delta = IndexCheckpointDelta {
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+            source_delta: SourceCheckpointDelta::from_partition_delta(
+                PartitionId::from(shard_id.as_str()),
+                Position::Beginning,
+                Position::offset(42u64),
+            )
+            .unwrap(),
+        };
+        let publish_splits_request = PublishSplitsRequest {
+            index_uid: index_uid.to_string(),
+            index_checkpoint_delta_json_opt: Some(
+                serde_utils::to_json_str(&index_checkpoint_delta).unwrap(),
+            ),
+            publish_token_opt: Some("test-publish-token".to_string()),
+            ..Default::default()
+        };
+        metastore
+            .publish_splits(publish_splits_request)
+            .await
+            .unwrap();
+        index_uid
+    }
+
+    #[test]
+    fn test_remap_delete_opstamp() {
+        assert_eq!(remap_delete_opstamp(&[], 0), 0);
+        assert_eq!(remap_delete_opstamp(&[], 3), 0);
+
+        let opstamp_mapping = [(2, 1), (5, 2), (6, 3)];
+        assert_eq!(remap_delete_opstamp(&opstamp_mapping, 0), 0);
+        assert_eq!(remap_delete_opstamp(&opstamp_mapping, 1), 0);
+        assert_eq!(remap_delete_opstamp(&opstamp_mapping, 2), 1);
+        assert_eq!(remap_delete_opstamp(&opstamp_mapping, 4), 1);
+        assert_eq!(remap_delete_opstamp(&opstamp_mapping, 5), 2);
+        assert_eq!(remap_delete_opstamp(&opstamp_mapping, 7), 3);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_metastore() {
+        let mut source = metastore_for_test();
+        let mut target = metastore_for_test();
+        let source_index_uid = create_source_index(&mut source).await;
+
+        let expected_summary = IndexMigrationSummary {
+            index_id: "test-index".to_string(),
+            num_sources: 2,
+            num_splits: 3,
+            num_delete_tasks: 1,
+            num_shards: 1,
+        };
+        let summaries = migrate_metastore(source.clone(), target.clone(), true)
+            .await
+            .unwrap();
+        assert_eq!(summaries, [expected_summary.clone()]);
+        assert!(!target.index_exists("test-index").await.unwrap());
+
+        let summaries = migrate_metastore(source.clone(), target.clone(), false)
+            .await
+            .unwrap();
+        assert_eq!(summaries, [expected_summary]);
+
+        let target_index_metadata = target
+            .index_metadata(IndexMetadataRequest::for_index_id("test-index".to_string()))
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap();
+        assert_ne!(target_index_metadata.index_uid, source_index_uid);
+
+        let split_states = list_split_states(&mut target, target_index_metadata.index_uid)
+            .await
+            .unwrap();
+        assert_eq!(split_states.len(), 3);
+        assert_eq!(split_states["split-1"], SplitState::Staged);
+        assert_eq!(split_states["split-2"], SplitState::Published);
+        assert_eq!(split_states["split-3"], SplitState::MarkedForDeletion);
+
+        let error = migrate_metastore(source, target, false).await.unwrap_err();
+        assert!(error.to_string().contains("already exists"));
+    }
+}
@@ -31,6 +31,8 @@ use quickwit_storage::StorageResolver;
 use crate::metastore::file_backed::FileBackedMetastoreFactory;
 #[cfg(feature = "postgres")]
 use crate::metastore::postgres::PostgresqlMetastoreFactory;
+#[cfg(feature = "sqlite")]
+use crate::metastore::sqlite::SqliteMetastoreFactory;
 use crate::{MetastoreFactory, MetastoreResolverError};
 
 type FactoryAndConfig = (Box<dyn MetastoreFactory>, MetastoreConfig);
@@ -66,6 +68,7 @@ impl MetastoreResolver {
             Protocol::Ram => MetastoreBackend::File,
             Protocol::S3 => MetastoreBackend::File,
             Protocol::PostgreSQL => MetastoreBackend::PostgreSQL,
+            Protocol::SQLite => MetastoreBackend::SQLite,
             _ => {
                 return Err(MetastoreResolverError::UnsupportedBackend(
                     "no implementation exists for this backend".to_string(),
@@ -134,6 +137,31 @@ impl MetastoreResolver {
                 PostgresMetastoreConfig::default().into(),
             );
         }
+        #[cfg(feature = "sqlite")]
+        {
+            builder = builder.register(
+                SqliteMetastoreFactory::default(),
+                metastore_configs
+                    .find_sqlite()
+                    .cloned()
+                    .unwrap_or_default()
+                    .into(),
+            );
+        }
+        #[cfg(not(feature = "sqlite"))]
+        {
+            use quickwit_config::SqliteMetastoreConfig;
+
+            use crate::UnsupportedMetastore;
+
+            builder = builder.register(
+                UnsupportedMetastore::new(
+                    MetastoreBackend::SQLite,
+                    "Quickwit was compiled without the `sqlite` feature.",
+                ),
+                SqliteMetastoreConfig::default().into(),
+            );
+        }
         builder
             .build()
             .expect("metastore factory and config backends should match")
@@ -205,4 +233,15 @@ mod tests {
             metastore_resolver.resolve(&postgres_uri).await.unwrap();
         }
     }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_metastore_resolver_sqlite() {
+        let metastore_resolver = MetastoreResolver::unconfigured();
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let metastore_filepath = format!("sqlite://{}/metastore.db", tmp_dir.path().display());
+        let metastore_uri = Uri::from_str(&metastore_filepath).unwrap();
+        metastore_resolver.resolve(&metastore_uri).await.unwrap();
+        assert!(tmp_dir.path().join("metastore.db").exists());
+    }
 }
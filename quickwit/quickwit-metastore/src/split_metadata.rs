@@ -25,6 +25,7 @@ use std::str::FromStr;
 use std::time::Duration;
 
 use bytesize::ByteSize;
+use quickwit_common::uri::Uri;
 use quickwit_proto::types::IndexUid;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DurationMilliSeconds};
@@ -132,6 +133,10 @@ pub struct SplitMetadata {
     /// Number of merge operations that was involved to create
     /// this split.
     pub num_merge_ops: usize,
+
+    /// URI of the storage holding the split file, when the split was moved out of the index
+    /// storage by a tiering policy. `None` means the split lives in the index storage.
+    pub storage_uri: Option<Uri>,
 }
 impl fmt::Debug for SplitMetadata {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -175,6 +180,9 @@ impl fmt::Debug for SplitMetadata {
         debug_struct.field("footer_offsets", &self.footer_offsets);
         debug_struct.field("delete_opstamp", &self.delete_opstamp);
         debug_struct.field("num_merge_ops", &self.num_merge_ops);
+        if let Some(storage_uri) = &self.storage_uri {
+            debug_struct.field("storage_uri", storage_uri);
+        }
         debug_struct.finish()
     }
 }
@@ -280,6 +288,7 @@ impl quickwit_config::TestableForRegression for SplitMetadata {
             tags: ["234".to_string(), "aaa".to_string()].into_iter().collect(),
             footer_offsets: 1000..2000,
             num_merge_ops: 3,
+            storage_uri: None,
         }
     }
 
@@ -422,6 +431,7 @@ mod tests {
             footer_offsets: 0..1024,
             delete_opstamp: 0,
             num_merge_ops: 0,
+            storage_uri: None,
         };
 
         let expected_output = "SplitMetadata { split_id: \"split-1\", index_uid: \
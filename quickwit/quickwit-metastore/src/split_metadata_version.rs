@@ -20,6 +20,7 @@
 use std::collections::BTreeSet;
 use std::ops::{Range, RangeInclusive};
 
+use quickwit_common::uri::Uri;
 use quickwit_proto::types::IndexUid;
 use serde::{Deserialize, Serialize};
 
@@ -91,6 +92,11 @@ pub(crate) struct SplitMetadataV0_7 {
 
     #[serde(default)]
     num_merge_ops: usize,
+
+    /// URI of the storage holding the split file when it was moved by a tiering policy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    storage_uri: Option<Uri>,
 }
 
 impl From<SplitMetadataV0_7> for SplitMetadata {
@@ -126,6 +132,7 @@ impl From<SplitMetadataV0_7> for SplitMetadata {
             tags: v6.tags,
             footer_offsets: v6.footer_offsets,
             num_merge_ops: v6.num_merge_ops,
+            storage_uri: v6.storage_uri,
         }
     }
 }
@@ -147,6 +154,7 @@ impl From<SplitMetadata> for SplitMetadataV0_7 {
             tags: split.tags,
             footer_offsets: split.footer_offsets,
             num_merge_ops: split.num_merge_ops,
+            storage_uri: split.storage_uri,
         }
     }
 }
@@ -9,6 +9,38 @@ pub struct ScrollRequest {
     #[prost(uint32, optional, tag = "2")]
     pub scroll_ttl_secs: ::core::option::Option<u32>,
 }
+/// / Opens a point in time, recording the set of splits currently published for
+/// / the targeted indexes.
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OpenPointInTimeRequest {
+    #[prost(string, repeated, tag = "1")]
+    pub index_id_patterns: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(uint32, tag = "2")]
+    pub keep_alive_secs: u32,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OpenPointInTimeResponse {
+    #[prost(string, tag = "1")]
+    pub pit_id: ::prost::alloc::string::String,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClosePointInTimeRequest {
+    #[prost(string, tag = "1")]
+    pub pit_id: ::prost::alloc::string::String,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClosePointInTimeResponse {
+    #[prost(bool, tag = "1")]
+    pub succeeded: bool,
+}
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -48,6 +80,11 @@ pub struct ReportSplit {
     /// The storage uri. This URI does NOT include the split id.
     #[prost(string, tag = "1")]
     pub storage_uri: ::prost::alloc::string::String,
+    /// Byte range of the split footer (bundle metadata and hotcache), used to warm up the split.
+    #[prost(uint64, tag = "3")]
+    pub split_footer_start: u64,
+    #[prost(uint64, tag = "4")]
+    pub split_footer_end: u64,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -55,6 +92,10 @@ pub struct ReportSplit {
 pub struct ReportSplitsRequest {
     #[prost(message, repeated, tag = "1")]
     pub report_splits: ::prost::alloc::vec::Vec<ReportSplit>,
+    /// If true, the reported splits have just been uploaded. The receiving searcher warms them up
+    /// if split warm-up is enabled, and does not report them to its split cache.
+    #[prost(bool, tag = "3")]
+    pub warm_up: bool,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -184,6 +225,62 @@ pub struct SearchRequest {
     pub search_after: ::core::option::Option<PartialHit>,
     #[prost(enumeration = "CountHits", tag = "17")]
     pub count_hits: i32,
+    /// If set, the search response will include a detailed profile of the
+    /// time spent in the different phases of the search, per split.
+    #[prost(bool, tag = "18")]
+    pub profile: bool,
+    /// If set, the search runs on the splits recorded when the point in time
+    /// was opened instead of the splits currently published.
+    #[prost(message, optional, tag = "19")]
+    pub point_in_time: ::core::option::Option<PointInTime>,
+    /// If set, only the best hit for each distinct value of the collapse field
+    /// is returned.
+    #[prost(message, optional, tag = "20")]
+    pub collapse: ::core::option::Option<CollapseRequest>,
+    /// json serialized runtime mappings: fields computed at query time by a VRL
+    /// script evaluated on the stored documents.
+    #[prost(string, optional, tag = "21")]
+    pub runtime_mappings: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// Collapses the hits on the value of a fast field.
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[derive(Eq, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CollapseRequest {
+    /// Name of the fast field to collapse on.
+    #[prost(string, tag = "1")]
+    pub field: ::prost::alloc::string::String,
+    /// If set, each collapsed hit is expanded with the top hits of its group.
+    #[prost(message, optional, tag = "2")]
+    pub inner_hits: ::core::option::Option<InnerHitsRequest>,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[derive(Eq, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InnerHitsRequest {
+    /// Name under which the inner hits are returned.
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    /// Number of inner hits returned per group.
+    #[prost(uint64, tag = "2")]
+    pub size: u64,
+    /// Sort order of the inner hits. Defaults to the sort order of the request.
+    #[prost(message, repeated, tag = "3")]
+    pub sort_fields: ::prost::alloc::vec::Vec<SortField>,
+}
+/// Reference to a point in time, as returned by `OpenPointInTimeResponse`.
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[derive(Eq, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PointInTime {
+    #[prost(string, tag = "1")]
+    pub pit_id: ::prost::alloc::string::String,
+    /// If set, extends the keep alive of the point in time.
+    #[prost(uint32, optional, tag = "2")]
+    pub keep_alive_secs: ::core::option::Option<u32>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[derive(Eq, Hash)]
@@ -223,6 +320,87 @@ pub struct SearchResponse {
     /// Scroll Id (only set if scroll_secs was set in the request)
     #[prost(string, optional, tag = "6")]
     pub scroll_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Search profile (only set if profile was set in the request)
+    #[prost(message, optional, tag = "7")]
+    pub profile: ::core::option::Option<SearchProfile>,
+    /// Point in time Id (only set if point_in_time was set in the request)
+    #[prost(string, optional, tag = "8")]
+    pub pit_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Storage requests issued by the leaf search and fetch docs phases.
+    #[prost(message, optional, tag = "9")]
+    pub storage_request_stats: ::core::option::Option<StorageRequestStats>,
+}
+/// Profile of a search request, returned when `SearchRequest.profile` is set.
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchProfile {
+    /// Time spent on the root node waiting for the leaf search phase to complete,
+    /// including the merge of the leaf responses.
+    #[prost(uint64, tag = "1")]
+    pub leaf_search_phase_micros: u64,
+    /// Time spent on the root node merging the leaf responses.
+    #[prost(uint64, tag = "2")]
+    pub merge_micros: u64,
+    /// Time spent on the root node waiting for the fetch docs phase to complete.
+    #[prost(uint64, tag = "3")]
+    pub fetch_docs_phase_micros: u64,
+    /// Time spent on the root node finalizing the aggregation result.
+    #[prost(uint64, tag = "4")]
+    pub finalize_aggregation_micros: u64,
+    /// Profile of each of the splits that were searched.
+    #[prost(message, repeated, tag = "5")]
+    pub split_profiles: ::prost::alloc::vec::Vec<SplitSearchProfile>,
+}
+/// Profile of the leaf search on a single split.
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SplitSearchProfile {
+    #[prost(string, tag = "1")]
+    pub split_id: ::prost::alloc::string::String,
+    /// Address of the searcher that executed the split search.
+    #[prost(string, tag = "2")]
+    pub node_addr: ::prost::alloc::string::String,
+    /// The tantivy query executed on the split.
+    #[prost(string, tag = "3")]
+    pub query_plan: ::prost::alloc::string::String,
+    /// True if the response was served from the leaf search cache.
+    /// In that case, all timings are zero.
+    #[prost(bool, tag = "4")]
+    pub leaf_cache_hit: bool,
+    /// Time spent opening the split (fetching the footer and the hotcache).
+    #[prost(uint64, tag = "5")]
+    pub open_index_micros: u64,
+    /// Time spent in each of the warm up phases. These phases are executed concurrently.
+    #[prost(uint64, tag = "6")]
+    pub warm_up_terms_micros: u64,
+    #[prost(uint64, tag = "7")]
+    pub warm_up_term_ranges_micros: u64,
+    #[prost(uint64, tag = "8")]
+    pub warm_up_term_dicts_micros: u64,
+    #[prost(uint64, tag = "9")]
+    pub warm_up_fastfields_micros: u64,
+    #[prost(uint64, tag = "10")]
+    pub warm_up_fieldnorms_micros: u64,
+    #[prost(uint64, tag = "11")]
+    pub warm_up_postings_micros: u64,
+    /// Total time spent in the warm up phase.
+    #[prost(uint64, tag = "12")]
+    pub warm_up_micros: u64,
+    /// Time spent executing the query and collecting the results.
+    #[prost(uint64, tag = "13")]
+    pub search_micros: u64,
+    /// Number of bytes fetched from the index storage.
+    #[prost(uint64, tag = "14")]
+    pub num_bytes_fetched_from_storage: u64,
+    /// Number of bytes served by the searcher caches (split footer cache, fast field cache and
+    /// split cache).
+    #[prost(uint64, tag = "15")]
+    pub num_bytes_fetched_from_cache: u64,
+    /// Index ID of the split. This field is populated by the root.
+    #[prost(string, tag = "16")]
+    pub index_id: ::prost::alloc::string::String,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -257,6 +435,9 @@ pub struct LeafSearchRequest {
     /// split files.
     #[prost(string, tag = "6")]
     pub index_uri: ::prost::alloc::string::String,
+    /// ID of the index the splits belong to. Used to attribute storage requests.
+    #[prost(string, tag = "7")]
+    pub index_id: ::prost::alloc::string::String,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -278,6 +459,10 @@ pub struct SplitIdAndFooterOffsets {
     /// The highest timestamp appearing in the split
     #[prost(int64, optional, tag = "5")]
     pub timestamp_end: ::core::option::Option<i64>,
+    /// The URI of the storage holding the split, when it was moved out of the index storage by a
+    /// tiering policy.
+    #[prost(string, optional, tag = "6")]
+    pub storage_uri: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// Hits returned by a FetchDocRequest.
 ///
@@ -322,6 +507,21 @@ pub struct Hit {
     /// The index id of the hit
     #[prost(string, tag = "4")]
     pub index_id: ::prost::alloc::string::String,
+    /// Top hits of the collapse group of the hit, if requested.
+    #[prost(message, repeated, tag = "5")]
+    pub inner_hits: ::prost::alloc::vec::Vec<InnerHits>,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InnerHits {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    /// Number of hits in the collapse group.
+    #[prost(uint64, tag = "2")]
+    pub num_hits: u64,
+    #[prost(message, repeated, tag = "3")]
+    pub hits: ::prost::alloc::vec::Vec<Hit>,
 }
 /// A partial hit, is a hit for which we have not fetch the content yet.
 /// Instead, it holds a document_uri which is enough information to
@@ -356,6 +556,10 @@ pub struct PartialHit {
     /// The DocId identifies a unique document at the scale of a tantivy segment.
     #[prost(uint32, tag = "4")]
     pub doc_id: u32,
+    /// Value of the collapse field for the given document, if the search request
+    /// collapses the hits. Documents missing the field have no collapse key.
+    #[prost(string, optional, tag = "6")]
+    pub collapse_key: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[derive(Ord, PartialOrd)]
@@ -404,6 +608,25 @@ pub struct LeafSearchResponse {
     pub intermediate_aggregation_result: ::core::option::Option<
         ::prost::alloc::vec::Vec<u8>,
     >,
+    /// Profile of the splits searched (only populated if profile was set in the request).
+    #[prost(message, repeated, tag = "7")]
+    pub split_profiles: ::prost::alloc::vec::Vec<SplitSearchProfile>,
+    /// Storage requests issued by the leaf search(es).
+    #[prost(message, optional, tag = "8")]
+    pub storage_request_stats: ::core::option::Option<StorageRequestStats>,
+}
+/// Storage requests issued to serve a search request. Requests served by the searcher caches are
+/// not accounted for.
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StorageRequestStats {
+    /// Number of storage requests.
+    #[prost(uint64, tag = "1")]
+    pub num_requests: u64,
+    /// Number of bytes downloaded from the storage.
+    #[prost(uint64, tag = "2")]
+    pub num_bytes_downloaded: u64,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -435,6 +658,13 @@ pub struct FetchDocsRequest {
     /// `DocMapper` as json serialized trait.
     #[prost(string, tag = "6")]
     pub doc_mapper: ::prost::alloc::string::String,
+    /// ID of the index the splits belong to. Used to attribute storage requests.
+    #[prost(string, tag = "8")]
+    pub index_id: ::prost::alloc::string::String,
+    /// json serialized runtime mappings of the search request. The values of the
+    /// runtime fields are added to the fetched documents.
+    #[prost(string, optional, tag = "9")]
+    pub runtime_mappings: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -443,6 +673,9 @@ pub struct FetchDocsResponse {
     /// List of complete hits.
     #[prost(message, repeated, tag = "1")]
     pub hits: ::prost::alloc::vec::Vec<LeafHit>,
+    /// Storage requests issued to fetch the docs.
+    #[prost(message, optional, tag = "2")]
+    pub storage_request_stats: ::core::option::Option<StorageRequestStats>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -467,6 +700,12 @@ pub struct ListTermsRequest {
     pub start_key: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
     #[prost(bytes = "vec", optional, tag = "8")]
     pub end_key: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    /// Only list terms starting with this prefix.
+    #[prost(string, optional, tag = "9")]
+    pub prefix: ::core::option::Option<::prost::alloc::string::String>,
+    /// Only list terms fully matching this regular expression.
+    #[prost(string, optional, tag = "10")]
+    pub regex: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -485,6 +724,10 @@ pub struct ListTermsResponse {
     /// The searcherrors that occurred formatted as string.
     #[prost(string, repeated, tag = "4")]
     pub errors: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Number of documents containing each term, summed over all splits.
+    /// They are listed in the same order as `terms`.
+    #[prost(uint64, repeated, tag = "5")]
+    pub doc_freqs: ::prost::alloc::vec::Vec<u64>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -519,6 +762,10 @@ pub struct LeafListTermsResponse {
     /// num_attempted_splits = num_successful_splits + num_failed_splits.
     #[prost(uint64, tag = "4")]
     pub num_attempted_splits: u64,
+    /// Number of documents containing each term, summed over the leaf splits.
+    /// They are listed in the same order as `terms`.
+    #[prost(uint64, repeated, tag = "5")]
+    pub doc_freqs: ::prost::alloc::vec::Vec<u64>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -22,8 +22,8 @@
 use quickwit_common::pubsub::Event;
 
 use super::{
-    AddSourceRequest, CreateIndexRequest, DeleteIndexRequest, DeleteSourceRequest, SourceType,
-    ToggleSourceRequest,
+    AddSourceRequest, CreateIndexRequest, DeleteIndexRequest, DeleteSourceRequest,
+    MarkSplitsForDeletionRequest, PublishSplitsRequest, SourceType, ToggleSourceRequest,
 };
 use crate::types::{IndexUid, SourceId};
 
@@ -69,4 +69,6 @@ impl Event for AddSourceRequest {}
 impl Event for CreateIndexRequest {}
 impl Event for DeleteIndexRequest {}
 impl Event for DeleteSourceRequest {}
+impl Event for MarkSplitsForDeletionRequest {}
+impl Event for PublishSplitsRequest {}
 impl Event for ToggleSourceRequest {}
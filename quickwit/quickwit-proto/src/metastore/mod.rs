@@ -140,7 +140,7 @@ pub enum MetastoreError {
     Unavailable(String),
 }
 
-#[cfg(feature = "postgres")]
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
 impl From<sqlx::Error> for MetastoreError {
     fn from(error: sqlx::Error) -> Self {
         MetastoreError::Db {
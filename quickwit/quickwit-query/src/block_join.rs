@@ -0,0 +1,512 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//! Block-join queries over nested documents.
+//!
+//! The objects of a `nested` field are indexed as separate documents, written right before the
+//! document they belong to. Together, they form a block: the nested documents of a block are
+//! followed by their root document. Nested documents hold the path of their nested field in the
+//! `_nested` field, and root documents hold the `NESTED_FIELD_ROOT_VALUE` term, so the root
+//! documents of a segment are read from a single posting list.
+
+use quickwit_common::shared_consts::{NESTED_FIELD_NAME, NESTED_FIELD_ROOT_VALUE};
+use tantivy::fastfield::AliveBitSet;
+use tantivy::query::{
+    BooleanQuery, ConstScoreQuery, EmptyScorer, EnableScoring, Explanation, Occur, Query, Scorer,
+    TermQuery, Weight,
+};
+use tantivy::schema::{IndexRecordOption, Schema};
+use tantivy::{DocId, DocSet, Score, SegmentReader, TantivyError, Term, TERMINATED};
+
+use crate::query_ast::NestedScoreMode;
+
+/// Returns the term held by the root documents, if the schema has nested fields.
+pub fn root_docs_term(schema: &Schema) -> Option<Term> {
+    nested_path_term(schema, NESTED_FIELD_ROOT_VALUE)
+}
+
+/// Returns the root documents of a segment, in increasing order.
+fn root_docs(reader: &SegmentReader, root_docs_term: &Term) -> tantivy::Result<Box<dyn DocSet>> {
+    let inverted_index = reader.inverted_index(root_docs_term.field())?;
+    let root_docs: Box<dyn DocSet> =
+        match inverted_index.read_postings(root_docs_term, IndexRecordOption::Basic)? {
+            Some(postings) => Box::new(postings),
+            None => Box::new(EmptyScorer),
+        };
+    Ok(root_docs)
+}
+
+/// Returns the number of alive root documents of a segment.
+pub fn num_root_docs(reader: &SegmentReader) -> tantivy::Result<u32> {
+    let Some(root_docs_term) = root_docs_term(reader.schema()) else {
+        return Ok(reader.num_docs());
+    };
+    let mut root_docs = root_docs(reader, &root_docs_term)?;
+    let mut num_root_docs = 0;
+    let mut doc = root_docs.doc();
+
+    while doc != TERMINATED {
+        if !reader.is_deleted(doc) {
+            num_root_docs += 1;
+        }
+        doc = root_docs.advance();
+    }
+    Ok(num_root_docs)
+}
+
+/// Restricts a query to root documents if the schema has nested fields.
+pub fn exclude_nested_docs(query: Box<dyn Query>, schema: &Schema) -> Box<dyn Query> {
+    let Some(root_docs_term) = root_docs_term(schema) else {
+        return query;
+    };
+    let root_docs_query = TermQuery::new(root_docs_term, IndexRecordOption::Basic);
+    Box::new(BooleanQuery::new(vec![
+        (Occur::Must, query),
+        (
+            Occur::Must,
+            Box::new(ConstScoreQuery::new(Box::new(root_docs_query), 0.0)),
+        ),
+    ]))
+}
+
+/// Returns the term matching the nested documents of a given nested field.
+pub(crate) fn nested_path_term(schema: &Schema, path: &str) -> Option<Term> {
+    let nested_field = schema.get_field(NESTED_FIELD_NAME).ok()?;
+    Some(Term::from_field_text(nested_field, path))
+}
+
+/// Tantivy query matching the root documents of the nested documents matched by a child query.
+/// The score of a root document aggregates the scores of its matching nested documents.
+#[derive(Clone, Debug)]
+pub(crate) struct ToParentBlockJoinQuery {
+    child_query: Box<dyn Query>,
+    root_docs_term: Term,
+    score_mode: NestedScoreMode,
+}
+
+impl ToParentBlockJoinQuery {
+    pub fn new(
+        child_query: Box<dyn Query>,
+        root_docs_term: Term,
+        score_mode: NestedScoreMode,
+    ) -> Self {
+        Self {
+            child_query,
+            root_docs_term,
+            score_mode,
+        }
+    }
+}
+
+impl Query for ToParentBlockJoinQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let child_weight = self.child_query.weight(enable_scoring)?;
+        Ok(Box::new(ToParentBlockJoinWeight {
+            child_weight,
+            root_docs_term: self.root_docs_term.clone(),
+            score_mode: self.score_mode,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.child_query.query_terms(visitor);
+        visitor(&self.root_docs_term, false);
+    }
+}
+
+struct ToParentBlockJoinWeight {
+    child_weight: Box<dyn Weight>,
+    root_docs_term: Term,
+    score_mode: NestedScoreMode,
+}
+
+impl Weight for ToParentBlockJoinWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let child_scorer = self.child_weight.scorer(reader, 1.0)?;
+        let mut scorer = ToParentBlockJoinScorer {
+            child_scorer,
+            root_docs: root_docs(reader, &self.root_docs_term)?,
+            alive_bitset_opt: reader.alive_bitset().cloned(),
+            score_mode: self.score_mode,
+            boost,
+            doc: TERMINATED,
+            score: 0.0,
+        };
+        scorer.advance();
+        Ok(Box::new(scorer))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+
+        if scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "document #({doc}) does not match"
+            )));
+        }
+        Ok(Explanation::new("NestedQuery", scorer.score()))
+    }
+}
+
+struct ToParentBlockJoinScorer {
+    child_scorer: Box<dyn Scorer>,
+    root_docs: Box<dyn DocSet>,
+    alive_bitset_opt: Option<AliveBitSet>,
+    score_mode: NestedScoreMode,
+    boost: Score,
+    doc: DocId,
+    score: Score,
+}
+
+impl ToParentBlockJoinScorer {
+    fn is_alive(&self, doc: DocId) -> bool {
+        self.alive_bitset_opt
+            .as_ref()
+            .map(|alive_bitset| alive_bitset.is_alive(doc))
+            .unwrap_or(true)
+    }
+
+    /// Returns the root document of the block of a nested document. Nested documents must be
+    /// passed in increasing order.
+    fn next_root_doc(&mut self, nested_doc: DocId) -> DocId {
+        if self.root_docs.doc() > nested_doc {
+            return self.root_docs.doc();
+        }
+        self.root_docs.seek(nested_doc + 1)
+    }
+}
+
+impl DocSet for ToParentBlockJoinScorer {
+    fn advance(&mut self) -> DocId {
+        loop {
+            let mut child_doc = self.child_scorer.doc();
+
+            if child_doc == TERMINATED {
+                self.doc = TERMINATED;
+                return TERMINATED;
+            }
+            let root_doc = self.next_root_doc(child_doc);
+            let mut child_scores = ChildScores::default();
+
+            while child_doc < root_doc {
+                if self.is_alive(child_doc) {
+                    child_scores.push(self.child_scorer.score());
+                }
+                child_doc = self.child_scorer.advance();
+            }
+            if root_doc == TERMINATED || !self.is_alive(root_doc) {
+                continue;
+            }
+            if let Some(score) = child_scores.aggregate(self.score_mode) {
+                self.doc = root_doc;
+                self.score = score * self.boost;
+                return root_doc;
+            }
+        }
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.child_scorer.size_hint()
+    }
+}
+
+impl Scorer for ToParentBlockJoinScorer {
+    fn score(&mut self) -> Score {
+        self.score
+    }
+}
+
+#[derive(Default)]
+struct ChildScores {
+    num_scores: u32,
+    sum: Score,
+    min: Score,
+    max: Score,
+}
+
+impl ChildScores {
+    fn push(&mut self, score: Score) {
+        if self.num_scores == 0 {
+            self.min = score;
+            self.max = score;
+        } else {
+            self.min = self.min.min(score);
+            self.max = self.max.max(score);
+        }
+        self.num_scores += 1;
+        self.sum += score;
+    }
+
+    fn aggregate(&self, score_mode: NestedScoreMode) -> Option<Score> {
+        if self.num_scores == 0 {
+            return None;
+        }
+        let score = match score_mode {
+            NestedScoreMode::Avg => self.sum / self.num_scores as Score,
+            NestedScoreMode::Max => self.max,
+            NestedScoreMode::Min => self.min,
+            NestedScoreMode::Sum => self.sum,
+            NestedScoreMode::None => 0.0,
+        };
+        Some(score)
+    }
+}
+
+/// Tantivy query matching the documents matched by a query, along with their nested documents.
+///
+/// Deleting documents with this query ensures that no nested document outlives its root document.
+#[derive(Clone, Debug)]
+pub struct IncludeNestedDocsQuery {
+    query: Box<dyn Query>,
+    root_docs_term_opt: Option<Term>,
+}
+
+impl IncludeNestedDocsQuery {
+    pub fn new(query: Box<dyn Query>, schema: &Schema) -> Self {
+        Self {
+            query,
+            root_docs_term_opt: root_docs_term(schema),
+        }
+    }
+}
+
+impl Query for IncludeNestedDocsQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let weight = self.query.weight(enable_scoring)?;
+        let Some(root_docs_term) = &self.root_docs_term_opt else {
+            return Ok(weight);
+        };
+        Ok(Box::new(IncludeNestedDocsWeight {
+            weight,
+            root_docs_term: root_docs_term.clone(),
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.query.query_terms(visitor);
+        if let Some(root_docs_term) = &self.root_docs_term_opt {
+            visitor(root_docs_term, false);
+        }
+    }
+}
+
+struct IncludeNestedDocsWeight {
+    weight: Box<dyn Weight>,
+    root_docs_term: Term,
+}
+
+impl Weight for IncludeNestedDocsWeight {
+    fn scorer(&self, reader: &SegmentReader, _boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let scorer = self.weight.scorer(reader, 1.0)?;
+        let mut include_nested_docs_scorer = IncludeNestedDocsScorer {
+            scorer,
+            root_docs: root_docs(reader, &self.root_docs_term)?,
+            previous_root_doc_opt: None,
+            doc: TERMINATED,
+            block_end: TERMINATED,
+        };
+        include_nested_docs_scorer.next_block();
+        Ok(Box::new(include_nested_docs_scorer))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+
+        if scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "document #({doc}) does not match"
+            )));
+        }
+        Ok(Explanation::new("IncludeNestedDocsQuery", scorer.score()))
+    }
+}
+
+struct IncludeNestedDocsScorer {
+    scorer: Box<dyn Scorer>,
+    root_docs: Box<dyn DocSet>,
+    /// Last root document preceding the current block.
+    previous_root_doc_opt: Option<DocId>,
+    /// Current document.
+    doc: DocId,
+    /// Last document of the current block, which is matched by the underlying query.
+    block_end: DocId,
+}
+
+impl IncludeNestedDocsScorer {
+    /// Moves to the first document of the block of the next document matched by the underlying
+    /// query.
+    fn next_block(&mut self) -> DocId {
+        let matched_doc = self.scorer.doc();
+
+        if matched_doc == TERMINATED {
+            self.doc = TERMINATED;
+            self.block_end = TERMINATED;
+            return TERMINATED;
+        }
+        self.scorer.advance();
+
+        while self.root_docs.doc() < matched_doc {
+            self.previous_root_doc_opt = Some(self.root_docs.doc());
+            self.root_docs.advance();
+        }
+        let block_start = self
+            .previous_root_doc_opt
+            .map(|previous_root_doc| previous_root_doc + 1)
+            .unwrap_or(0);
+        self.doc = block_start;
+        self.block_end = matched_doc;
+        block_start
+    }
+}
+
+impl DocSet for IncludeNestedDocsScorer {
+    fn advance(&mut self) -> DocId {
+        if self.doc < self.block_end {
+            self.doc += 1;
+            return self.doc;
+        }
+        self.next_block()
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+}
+
+impl Scorer for IncludeNestedDocsScorer {
+    fn score(&mut self) -> Score {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::collector::{Count, TopDocs};
+    use tantivy::query::{AllQuery, TermQuery};
+    use tantivy::schema::{Value, STRING};
+    use tantivy::{doc, Index, IndexWriter, TantivyDocument};
+
+    use super::*;
+
+    fn create_nested_index() -> tantivy::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let nested_field = schema_builder.add_text_field(NESTED_FIELD_NAME, STRING);
+        let name_field = schema_builder.add_text_field("name", STRING);
+        let key_field = schema_builder.add_text_field("tags.k", STRING);
+        let value_field = schema_builder.add_text_field("tags.v", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_with_num_threads(1, 15_000_000)?;
+
+        let blocks: [(&str, &[(&str, &str)]); 3] = [
+            ("doc0", &[("env", "prod"), ("team", "x")]),
+            ("doc1", &[("env", "x")]),
+            ("doc2", &[]),
+        ];
+        for (name, tags) in blocks {
+            // With a single indexing thread, the documents of a block are contiguous.
+            for (key, value) in tags {
+                index_writer.add_document(
+                    doc!(nested_field => "tags", key_field => *key, value_field => *value),
+                )?;
+            }
+            index_writer
+                .add_document(doc!(nested_field => NESTED_FIELD_ROOT_VALUE, name_field => name))?;
+        }
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    fn term_query(schema: &Schema, field_name: &str, text: &str) -> Box<dyn Query> {
+        let field = schema.get_field(field_name).unwrap();
+        Box::new(TermQuery::new(
+            Term::from_field_text(field, text),
+            IndexRecordOption::Basic,
+        ))
+    }
+
+    #[test]
+    fn test_to_parent_block_join_query() -> tantivy::Result<()> {
+        let index = create_nested_index()?;
+        let schema = index.schema();
+        let searcher = index.reader()?.searcher();
+
+        let child_query = BooleanQuery::new(vec![
+            (Occur::Must, term_query(&schema, "tags.k", "env")),
+            (Occur::Must, term_query(&schema, "tags.v", "x")),
+        ]);
+        let root_docs_term = root_docs_term(&schema).unwrap();
+        let nested_query = ToParentBlockJoinQuery::new(
+            Box::new(child_query),
+            root_docs_term.clone(),
+            Default::default(),
+        );
+        let top_docs = searcher.search(&nested_query, &TopDocs::with_limit(10))?;
+        let name_field = schema.get_field("name").unwrap();
+        let names: Vec<String> = top_docs
+            .iter()
+            .map(|(_score, doc_address)| {
+                let doc: TantivyDocument = searcher.doc(*doc_address).unwrap();
+                doc.get_first(name_field)
+                    .and_then(|value| value.as_str())
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(names, ["doc1"]);
+
+        let child_query = term_query(&schema, "tags.k", "env");
+        let nested_query =
+            ToParentBlockJoinQuery::new(child_query, root_docs_term, NestedScoreMode::Sum);
+        assert_eq!(searcher.search(&nested_query, &Count)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_root_docs() -> tantivy::Result<()> {
+        let index = create_nested_index()?;
+        let searcher = index.reader()?.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        assert_eq!(segment_reader.max_doc(), 6);
+        assert_eq!(num_root_docs(segment_reader)?, 3);
+
+        let query = exclude_nested_docs(Box::new(AllQuery), &index.schema());
+        assert_eq!(searcher.search(&query, &Count)?, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_include_nested_docs_query() -> tantivy::Result<()> {
+        let index = create_nested_index()?;
+        let schema = index.schema();
+        let searcher = index.reader()?.searcher();
+
+        for (name, expected_num_docs) in [("doc0", 3), ("doc1", 2), ("doc2", 1)] {
+            let query = IncludeNestedDocsQuery::new(term_query(&schema, "name", name), &schema);
+            assert_eq!(searcher.search(&query, &Count)?, expected_num_docs);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,538 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//! Encoding of dense vectors in bytes fast fields, and exact k-nearest neighbors search over
+//! them.
+//!
+//! An encoded vector starts with a two-byte header holding its metric and its quantization.
+//! Vectors of fields using the cosine metric are normalized before being encoded, so that their
+//! cosine similarity with a normalized query vector is their dot product.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use tantivy::query::{EmptyScorer, EnableScoring, Explanation, Query, Scorer, Weight};
+use tantivy::{
+    DocId, DocSet, Score, SegmentId, SegmentOrdinal, SegmentReader, TantivyError, TERMINATED,
+};
+
+const DOT_PRODUCT_METRIC_CODE: u8 = 0;
+const COSINE_METRIC_CODE: u8 = 1;
+
+const NO_QUANTIZATION_CODE: u8 = 0;
+const INT8_QUANTIZATION_CODE: u8 = 1;
+
+const HEADER_NUM_BYTES: usize = 2;
+
+/// Similarity metric used to compare dense vectors.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorMetric {
+    /// Cosine similarity, mapped to `[0, 1]` as `(1 + cosine) / 2`.
+    #[default]
+    Cosine,
+    /// Raw dot product.
+    DotProduct,
+}
+
+/// Quantization applied to dense vectors before they are indexed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorQuantization {
+    /// Components are stored as 32-bit floats.
+    #[default]
+    None,
+    /// Components are scaled by the largest absolute component of the vector and stored as
+    /// 8-bit integers.
+    Int8,
+}
+
+fn normalize(vector: &[f32]) -> Vec<f32> {
+    let norm = vector
+        .iter()
+        .map(|component| component * component)
+        .sum::<f32>()
+        .sqrt();
+    if norm == 0.0 {
+        return vector.to_vec();
+    }
+    vector.iter().map(|component| component / norm).collect()
+}
+
+/// Encodes a dense vector into the bytes stored in its fast field.
+pub fn encode_dense_vector(
+    vector: &[f32],
+    metric: VectorMetric,
+    quantization: VectorQuantization,
+) -> Vec<u8> {
+    let (metric_code, vector) = match metric {
+        VectorMetric::Cosine => (COSINE_METRIC_CODE, normalize(vector)),
+        VectorMetric::DotProduct => (DOT_PRODUCT_METRIC_CODE, vector.to_vec()),
+    };
+    match quantization {
+        VectorQuantization::None => {
+            let mut encoded = Vec::with_capacity(HEADER_NUM_BYTES + vector.len() * 4);
+            encoded.extend_from_slice(&[metric_code, NO_QUANTIZATION_CODE]);
+
+            for component in vector {
+                encoded.extend_from_slice(&component.to_le_bytes());
+            }
+            encoded
+        }
+        VectorQuantization::Int8 => {
+            let max_abs_component = vector
+                .iter()
+                .fold(0.0f32, |max, component| max.max(component.abs()));
+            let scale = max_abs_component / i8::MAX as f32;
+
+            let mut encoded = Vec::with_capacity(HEADER_NUM_BYTES + 4 + vector.len());
+            encoded.extend_from_slice(&[metric_code, INT8_QUANTIZATION_CODE]);
+            encoded.extend_from_slice(&scale.to_le_bytes());
+
+            for component in vector {
+                let quantized = if scale == 0.0 {
+                    0
+                } else {
+                    (component / scale).round().clamp(-127.0, 127.0) as i8
+                };
+                encoded.push(quantized as u8);
+            }
+            encoded
+        }
+    }
+}
+
+fn read_f32(bytes: &[u8]) -> f32 {
+    f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Query vector, along with its normalized version used against cosine vectors.
+#[derive(Clone, Debug)]
+struct QueryVector {
+    vector: Vec<f32>,
+    normalized_vector: Vec<f32>,
+}
+
+impl QueryVector {
+    fn new(vector: Vec<f32>) -> Self {
+        let normalized_vector = normalize(&vector);
+        Self {
+            vector,
+            normalized_vector,
+        }
+    }
+
+    /// Returns the similarity between the query vector and an encoded vector, or `None` if the
+    /// encoded vector is malformed or does not have the dimension of the query vector.
+    fn similarity(&self, encoded: &[u8]) -> Option<f32> {
+        if encoded.len() < HEADER_NUM_BYTES {
+            return None;
+        }
+        let (header, payload) = encoded.split_at(HEADER_NUM_BYTES);
+        let query_vector = match header[0] {
+            DOT_PRODUCT_METRIC_CODE => &self.vector,
+            COSINE_METRIC_CODE => &self.normalized_vector,
+            _ => return None,
+        };
+        let dot_product = match header[1] {
+            NO_QUANTIZATION_CODE => {
+                if payload.len() != query_vector.len() * 4 {
+                    return None;
+                }
+                payload
+                    .chunks_exact(4)
+                    .zip(query_vector)
+                    .map(|(component_bytes, query_component)| {
+                        read_f32(component_bytes) * query_component
+                    })
+                    .sum::<f32>()
+            }
+            INT8_QUANTIZATION_CODE => {
+                if payload.len() != 4 + query_vector.len() {
+                    return None;
+                }
+                let scale = read_f32(&payload[..4]);
+                let quantized_dot_product = payload[4..]
+                    .iter()
+                    .zip(query_vector)
+                    .map(|(&quantized, query_component)| quantized as i8 as f32 * query_component)
+                    .sum::<f32>();
+                scale * quantized_dot_product
+            }
+            _ => return None,
+        };
+        if header[0] == COSINE_METRIC_CODE {
+            Some((1.0 + dot_product) / 2.0)
+        } else {
+            Some(dot_product)
+        }
+    }
+}
+
+/// Tantivy query returning the `k` documents matching a filter whose dense vector is the most
+/// similar to a query vector. Documents are scored by their similarity.
+///
+/// The `k` documents are selected across all the segments of the searcher the query runs on, i.e.
+/// across the split. If the weight is built without a searcher, they are selected per segment.
+///
+/// The search is exhaustive over the filtered candidate set.
+#[derive(Clone, Debug)]
+pub struct KnnTantivyQuery {
+    field_name: String,
+    query_vector: QueryVector,
+    k: usize,
+    filter: Box<dyn Query>,
+}
+
+impl KnnTantivyQuery {
+    pub fn new(
+        field_name: String,
+        query_vector: Vec<f32>,
+        k: usize,
+        filter: Box<dyn Query>,
+    ) -> Self {
+        Self {
+            field_name,
+            query_vector: QueryVector::new(query_vector),
+            k,
+            filter,
+        }
+    }
+}
+
+impl Query for KnnTantivyQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let filter_weight = self.filter.weight(enable_scoring)?;
+        let mut knn_weight = KnnWeight {
+            field_name: self.field_name.clone(),
+            query_vector: self.query_vector.clone(),
+            k: self.k,
+            filter_weight,
+            top_k_per_segment_opt: None,
+        };
+        if let Some(searcher) = enable_scoring.searcher() {
+            let top_k_per_segment = knn_weight.top_k_per_segment(searcher.segment_readers())?;
+            knn_weight.top_k_per_segment_opt = Some(top_k_per_segment);
+        }
+        Ok(Box::new(knn_weight))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a tantivy::Term, bool)) {
+        self.filter.query_terms(visitor);
+    }
+}
+
+struct KnnWeight {
+    field_name: String,
+    query_vector: QueryVector,
+    k: usize,
+    filter_weight: Box<dyn Weight>,
+    /// The `k` most similar documents across the segments of the searcher, grouped by segment and
+    /// sorted by doc ID.
+    top_k_per_segment_opt: Option<HashMap<SegmentId, Vec<ScoredDoc>>>,
+}
+
+impl KnnWeight {
+    /// Returns the `k` most similar documents across all the segments, grouped by segment and
+    /// sorted by doc ID.
+    fn top_k_per_segment(
+        &self,
+        segment_readers: &[SegmentReader],
+    ) -> tantivy::Result<HashMap<SegmentId, Vec<ScoredDoc>>> {
+        let mut top_k: BinaryHeap<ScoredDoc> = BinaryHeap::with_capacity(self.k + 1);
+
+        for (segment_ord, segment_reader) in segment_readers.iter().enumerate() {
+            self.collect_top_k(segment_reader, segment_ord as SegmentOrdinal, &mut top_k)?;
+        }
+        let mut top_k_per_segment: HashMap<SegmentId, Vec<ScoredDoc>> = HashMap::new();
+
+        for scored_doc in top_k.into_sorted_vec() {
+            let segment_id = segment_readers[scored_doc.segment_ord as usize].segment_id();
+            top_k_per_segment
+                .entry(segment_id)
+                .or_default()
+                .push(scored_doc);
+        }
+        for scored_docs in top_k_per_segment.values_mut() {
+            scored_docs.sort_unstable_by_key(|scored_doc| scored_doc.doc);
+        }
+        Ok(top_k_per_segment)
+    }
+
+    /// Pushes the documents of a segment into a min-heap holding the `k` most similar documents
+    /// seen so far.
+    fn collect_top_k(
+        &self,
+        reader: &SegmentReader,
+        segment_ord: SegmentOrdinal,
+        top_k: &mut BinaryHeap<ScoredDoc>,
+    ) -> tantivy::Result<()> {
+        if self.k == 0 {
+            return Ok(());
+        }
+        let Some(bytes_column) = reader.fast_fields().bytes(&self.field_name)? else {
+            return Ok(());
+        };
+        let alive_bitset_opt = reader.alive_bitset();
+        let mut filter_scorer = self.filter_weight.scorer(reader, 1.0)?;
+        let mut vector_bytes = Vec::new();
+        let mut doc = filter_scorer.doc();
+
+        while doc != TERMINATED {
+            let is_deleted = alive_bitset_opt
+                .map(|alive_bitset| alive_bitset.is_deleted(doc))
+                .unwrap_or(false);
+
+            if !is_deleted {
+                if let Some(term_ord) = bytes_column.term_ords(doc).next() {
+                    vector_bytes.clear();
+                    bytes_column.ord_to_bytes(term_ord, &mut vector_bytes)?;
+
+                    if let Some(similarity) = self.query_vector.similarity(&vector_bytes) {
+                        top_k.push(ScoredDoc {
+                            score: similarity,
+                            segment_ord,
+                            doc,
+                        });
+                        if top_k.len() > self.k {
+                            top_k.pop();
+                        }
+                    }
+                }
+            }
+            doc = filter_scorer.advance();
+        }
+        Ok(())
+    }
+}
+
+impl Weight for KnnWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let scored_docs = if let Some(top_k_per_segment) = &self.top_k_per_segment_opt {
+            top_k_per_segment
+                .get(&reader.segment_id())
+                .cloned()
+                .unwrap_or_default()
+        } else {
+            let mut top_k: BinaryHeap<ScoredDoc> = BinaryHeap::with_capacity(self.k + 1);
+            self.collect_top_k(reader, 0, &mut top_k)?;
+            let mut scored_docs = top_k.into_vec();
+            scored_docs.sort_unstable_by_key(|scored_doc| scored_doc.doc);
+            scored_docs
+        };
+        if scored_docs.is_empty() {
+            return Ok(Box::new(EmptyScorer));
+        }
+        Ok(Box::new(KnnScorer {
+            scored_docs,
+            cursor: 0,
+            boost,
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+
+        if scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "document #({doc}) does not match"
+            )));
+        }
+        Ok(Explanation::new("KnnQuery", scorer.score()))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ScoredDoc {
+    score: Score,
+    segment_ord: SegmentOrdinal,
+    doc: DocId,
+}
+
+impl PartialEq for ScoredDoc {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ScoredDoc {}
+
+impl PartialOrd for ScoredDoc {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScoredDoc {
+    /// Orders documents from the most to the least similar, so that a `BinaryHeap` pops the least
+    /// similar document first. On ties, documents with the lowest addresses are kept.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .score
+            .total_cmp(&self.score)
+            .then_with(|| self.segment_ord.cmp(&other.segment_ord))
+            .then_with(|| self.doc.cmp(&other.doc))
+    }
+}
+
+struct KnnScorer {
+    scored_docs: Vec<ScoredDoc>,
+    cursor: usize,
+    boost: Score,
+}
+
+impl DocSet for KnnScorer {
+    fn advance(&mut self) -> DocId {
+        if self.cursor < self.scored_docs.len() {
+            self.cursor += 1;
+        }
+        self.doc()
+    }
+
+    fn doc(&self) -> DocId {
+        self.scored_docs
+            .get(self.cursor)
+            .map(|scored_doc| scored_doc.doc)
+            .unwrap_or(TERMINATED)
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scored_docs.len() as u32
+    }
+}
+
+impl Scorer for KnnScorer {
+    fn score(&mut self) -> Score {
+        self.scored_docs
+            .get(self.cursor)
+            .map(|scored_doc| scored_doc.score * self.boost)
+            .unwrap_or(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::collector::{Count, TopDocs};
+    use tantivy::indexer::NoMergePolicy;
+    use tantivy::query::AllQuery;
+    use tantivy::schema::{BytesOptions, Schema};
+    use tantivy::{doc, Index};
+
+    use super::*;
+
+    #[test]
+    fn test_dense_vector_similarity() {
+        let query_vector = QueryVector::new(vec![1.0, 0.0]);
+
+        let encoded = encode_dense_vector(
+            &[2.0, 0.0],
+            VectorMetric::DotProduct,
+            VectorQuantization::None,
+        );
+        assert_eq!(query_vector.similarity(&encoded), Some(2.0));
+
+        let encoded =
+            encode_dense_vector(&[0.0, 3.0], VectorMetric::Cosine, VectorQuantization::None);
+        assert_eq!(query_vector.similarity(&encoded), Some(0.5));
+
+        let encoded =
+            encode_dense_vector(&[-4.0, 0.0], VectorMetric::Cosine, VectorQuantization::Int8);
+        assert_eq!(query_vector.similarity(&encoded), Some(0.0));
+
+        let encoded = encode_dense_vector(
+            &[0.5, 0.25],
+            VectorMetric::DotProduct,
+            VectorQuantization::Int8,
+        );
+        let similarity = query_vector.similarity(&encoded).unwrap();
+        assert!((similarity - 0.5).abs() < 0.01);
+
+        let encoded = encode_dense_vector(
+            &[1.0, 0.0, 0.0],
+            VectorMetric::DotProduct,
+            VectorQuantization::None,
+        );
+        assert_eq!(query_vector.similarity(&encoded), None);
+        assert_eq!(query_vector.similarity(&[]), None);
+    }
+
+    #[test]
+    fn test_knn_query() -> tantivy::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let vector_field =
+            schema_builder.add_bytes_field("vector", BytesOptions::default().set_fast());
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 15_000_000)?;
+
+        for vector in [[1.0, 0.0], [0.0, 1.0], [0.7, 0.7], [-1.0, 0.0]] {
+            let encoded =
+                encode_dense_vector(&vector, VectorMetric::Cosine, VectorQuantization::None);
+            index_writer.add_document(doc!(vector_field => encoded))?;
+        }
+        index_writer.add_document(doc!())?;
+        index_writer.commit()?;
+
+        let searcher = index.reader()?.searcher();
+        let knn_query =
+            KnnTantivyQuery::new("vector".to_string(), vec![1.0, 0.1], 2, Box::new(AllQuery));
+        let top_docs = searcher.search(&knn_query, &TopDocs::with_limit(10))?;
+        let doc_ids: Vec<DocId> = top_docs
+            .iter()
+            .map(|(_score, doc_address)| doc_address.doc_id)
+            .collect();
+        assert_eq!(doc_ids, [0, 2]);
+        assert!(top_docs[0].0 > top_docs[1].0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_knn_query_selects_k_docs_across_segments() -> tantivy::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let vector_field =
+            schema_builder.add_bytes_field("vector", BytesOptions::default().set_fast());
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 15_000_000)?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+
+        for segment_vectors in [[[1.0, 0.0], [0.0, 1.0]], [[0.9, 0.1], [-1.0, 0.0]]] {
+            for vector in segment_vectors {
+                let encoded =
+                    encode_dense_vector(&vector, VectorMetric::Cosine, VectorQuantization::None);
+                index_writer.add_document(doc!(vector_field => encoded))?;
+            }
+            index_writer.commit()?;
+        }
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+
+        let knn_query =
+            KnnTantivyQuery::new("vector".to_string(), vec![1.0, 0.0], 2, Box::new(AllQuery));
+        let top_docs = searcher.search(&knn_query, &TopDocs::with_limit(10))?;
+        assert_eq!(top_docs.len(), 2);
+        assert_eq!(searcher.search(&knn_query, &Count)?, 2);
+
+        // The most similar document of each segment: `[1.0, 0.0]` and `[0.9, 0.1]`.
+        assert_eq!(top_docs[0].0, 1.0);
+        assert!(top_docs[1].0 > 0.99);
+        assert_ne!(top_docs[0].1.segment_ord, top_docs[1].1.segment_ord);
+        assert_eq!(top_docs[0].1.doc_id, 0);
+        assert_eq!(top_docs[1].1.doc_id, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use serde::Deserialize;
+use serde_with::formats::PreferMany;
+use serde_with::{serde_as, DefaultOnNull, OneOrMany};
+
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{self, QueryAst};
+
+/// # Unsupported features
+/// - num_candidates
+/// - similarity
+#[serde_as]
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct KnnQuery {
+    field: String,
+    query_vector: Vec<NotNaNf32>,
+    k: u32,
+    #[serde_as(deserialize_as = "DefaultOnNull<OneOrMany<_, PreferMany>>")]
+    #[serde(default)]
+    filter: Vec<ElasticQueryDslInner>,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl ConvertableToQueryAst for KnnQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let filter = self
+            .filter
+            .into_iter()
+            .map(|query_dsl| query_dsl.convert_to_query_ast())
+            .collect::<anyhow::Result<Vec<QueryAst>>>()?;
+        let knn_query_ast = query_ast::KnnQuery {
+            field: self.field,
+            query_vector: self.query_vector,
+            k: self.k,
+            filter,
+        };
+        Ok(QueryAst::from(knn_query_ast).boost(self.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elastic_query_dsl::term_query::term_query_from_field_value;
+
+    #[test]
+    fn test_dsl_knn_query_deserialize_simple() {
+        let knn_query_json = r#"{
+            "field": "embedding",
+            "query_vector": [0.5, 1.0],
+            "k": 10,
+            "filter": {"term": {"lang": {"value": "en"}}}
+        }"#;
+        let knn_query: KnnQuery = serde_json::from_str(knn_query_json).unwrap();
+        assert_eq!(
+            knn_query,
+            KnnQuery {
+                field: "embedding".to_string(),
+                query_vector: vec![
+                    NotNaNf32::try_from(0.5).unwrap(),
+                    NotNaNf32::try_from(1.0).unwrap()
+                ],
+                k: 10,
+                filter: vec![ElasticQueryDslInner::Term(term_query_from_field_value(
+                    "lang", "en"
+                ))],
+                boost: None,
+            }
+        );
+        let QueryAst::Knn(knn_query_ast) = knn_query.convert_to_query_ast().unwrap() else {
+            panic!()
+        };
+        assert_eq!(knn_query_ast.field, "embedding");
+        assert_eq!(knn_query_ast.k, 10);
+        assert_eq!(knn_query_ast.filter.len(), 1);
+    }
+}
@@ -21,10 +21,12 @@ use serde::{Deserialize, Serialize};
 
 mod bool_query;
 mod exists_query;
+mod knn_query;
 mod match_bool_prefix;
 mod match_phrase_query;
 mod match_query;
 mod multi_match;
+mod nested_query;
 mod one_field_map;
 mod phrase_prefix_query;
 mod query_string_query;
@@ -42,10 +44,12 @@ pub(crate) use string_or_struct::StringOrStructForSerialization;
 use term_query::TermQuery;
 
 use crate::elastic_query_dsl::exists_query::ExistsQuery;
+use crate::elastic_query_dsl::knn_query::KnnQuery;
 use crate::elastic_query_dsl::match_bool_prefix::MatchBoolPrefixQuery;
 use crate::elastic_query_dsl::match_phrase_query::MatchPhraseQuery;
 use crate::elastic_query_dsl::match_query::MatchQuery;
 use crate::elastic_query_dsl::multi_match::MultiMatchQuery;
+use crate::elastic_query_dsl::nested_query::NestedQuery;
 use crate::elastic_query_dsl::terms_query::TermsQuery;
 use crate::not_nan_f32::NotNaNf32;
 use crate::query_ast::QueryAst;
@@ -79,6 +83,8 @@ pub(crate) enum ElasticQueryDslInner {
     MultiMatch(MultiMatchQuery),
     Range(RangeQuery),
     Exists(ExistsQuery),
+    Knn(KnnQuery),
+    Nested(NestedQuery),
 }
 
 #[derive(Deserialize, Debug, Eq, PartialEq, Clone)]
@@ -126,6 +132,8 @@ impl ConvertableToQueryAst for ElasticQueryDslInner {
             Self::Match(match_query) => match_query.convert_to_query_ast(),
             Self::Exists(exists_query) => exists_query.convert_to_query_ast(),
             Self::MultiMatch(multi_match_query) => multi_match_query.convert_to_query_ast(),
+            Self::Knn(knn_query) => knn_query.convert_to_query_ast(),
+            Self::Nested(nested_query) => nested_query.convert_to_query_ast(),
         }
     }
 }
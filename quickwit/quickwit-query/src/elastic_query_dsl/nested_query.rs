@@ -0,0 +1,82 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use serde::Deserialize;
+
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{self, NestedScoreMode, QueryAst};
+
+/// # Unsupported features
+/// - ignore_unmapped
+/// - inner_hits
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct NestedQuery {
+    path: String,
+    query: Box<ElasticQueryDslInner>,
+    #[serde(default)]
+    score_mode: NestedScoreMode,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl ConvertableToQueryAst for NestedQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let nested_query_ast = query_ast::NestedQuery {
+            path: self.path,
+            query: Box::new(self.query.convert_to_query_ast()?),
+            score_mode: self.score_mode,
+        };
+        Ok(QueryAst::from(nested_query_ast).boost(self.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elastic_query_dsl::term_query::term_query_from_field_value;
+
+    #[test]
+    fn test_dsl_nested_query_deserialize_simple() {
+        let nested_query_json = r#"{
+            "path": "tags",
+            "query": {"term": {"tags.k": {"value": "env"}}},
+            "score_mode": "sum"
+        }"#;
+        let nested_query: NestedQuery = serde_json::from_str(nested_query_json).unwrap();
+        assert_eq!(
+            nested_query,
+            NestedQuery {
+                path: "tags".to_string(),
+                query: Box::new(ElasticQueryDslInner::Term(term_query_from_field_value(
+                    "tags.k", "env"
+                ))),
+                score_mode: NestedScoreMode::Sum,
+                boost: None,
+            }
+        );
+        let QueryAst::Nested(nested_query_ast) = nested_query.convert_to_query_ast().unwrap()
+        else {
+            panic!()
+        };
+        assert_eq!(nested_query_ast.path, "tags");
+        assert_eq!(nested_query_ast.score_mode, NestedScoreMode::Sum);
+        assert!(matches!(*nested_query_ast.query, QueryAst::Term(_)));
+    }
+}
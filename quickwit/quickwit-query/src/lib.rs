@@ -28,6 +28,8 @@
 // For the individual detailed API documentation however, you should refer to elastic
 // documentation.
 
+pub mod block_join;
+pub mod dense_vector;
 mod elastic_query_dsl;
 mod error;
 mod json_literal;
@@ -46,7 +48,7 @@ pub use tantivy::query::Query as TantivyQuery;
 pub use tokenizers::MultiLangTokenizer;
 pub use tokenizers::{
     create_default_quickwit_tokenizer_manager, get_quickwit_fastfield_normalizer_manager,
-    CodeTokenizer, DEFAULT_REMOVE_TOKEN_LENGTH,
+    CodeTokenizer, EdgeNgramFilter, SynonymFilter, DEFAULT_REMOVE_TOKEN_LENGTH,
 };
 
 #[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, Eq, PartialEq)]
@@ -0,0 +1,163 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use serde::{Deserialize, Serialize};
+use tantivy::schema::{FieldType, Schema as TantivySchema};
+
+use crate::dense_vector::KnnTantivyQuery;
+use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
+use crate::query_ast::{BoolQuery, BuildTantivyAst, QueryAst};
+use crate::tokenizers::TokenizerManager;
+use crate::{find_field_or_hit_dynamic, InvalidQuery, NotNaNf32};
+
+/// Returns the `k` documents whose dense vector is the most similar to `query_vector`, among the
+/// documents matching all the `filter` clauses.
+///
+/// `k` applies to each split: the final hits are the most similar documents across splits.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KnnQuery {
+    pub field: String,
+    pub query_vector: Vec<NotNaNf32>,
+    pub k: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filter: Vec<QueryAst>,
+}
+
+impl From<KnnQuery> for QueryAst {
+    fn from(knn_query: KnnQuery) -> Self {
+        QueryAst::Knn(knn_query)
+    }
+}
+
+impl BuildTantivyAst for KnnQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        tokenizer_manager: &TokenizerManager,
+        search_fields: &[String],
+        with_validation: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let (_field, field_entry, path) = find_field_or_hit_dynamic(&self.field, schema)?;
+        if !path.is_empty()
+            || !matches!(field_entry.field_type(), FieldType::Bytes(_))
+            || !field_entry.is_fast()
+        {
+            return Err(InvalidQuery::SchemaError(format!(
+                "field `{}` is not a dense vector field",
+                self.field
+            )));
+        }
+        if self.k == 0 {
+            return Err(InvalidQuery::Other(anyhow::anyhow!(
+                "knn query `k` must be strictly positive"
+            )));
+        }
+        let filter_query = BoolQuery {
+            filter: self.filter.clone(),
+            ..Default::default()
+        };
+        let filter_ast = filter_query.build_tantivy_ast_call(
+            schema,
+            tokenizer_manager,
+            search_fields,
+            with_validation,
+        )?;
+        let query_vector: Vec<f32> = self
+            .query_vector
+            .iter()
+            .map(|&component| component.into())
+            .collect();
+        let knn_query = KnnTantivyQuery::new(
+            field_entry.name().to_string(),
+            query_vector,
+            self.k as usize,
+            filter_ast.simplify().into(),
+        );
+        Ok(knn_query.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{BytesOptions, Schema, TEXT};
+
+    use super::*;
+    use crate::create_default_quickwit_tokenizer_manager;
+    use crate::query_ast::TermQuery;
+
+    fn knn_query(field: &str, k: u32) -> KnnQuery {
+        KnnQuery {
+            field: field.to_string(),
+            query_vector: vec![NotNaNf32::try_from(1.0).unwrap()],
+            k,
+            filter: vec![TermQuery {
+                field: "title".to_string(),
+                value: "hello".to_string(),
+            }
+            .into()],
+        }
+    }
+
+    fn build_tantivy_ast(knn_query: &KnnQuery) -> Result<TantivyQueryAst, InvalidQuery> {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_bytes_field("vector", BytesOptions::default().set_fast());
+        schema_builder.add_bytes_field("blob", BytesOptions::default().set_stored());
+        schema_builder.add_text_field("title", TEXT);
+        let schema = schema_builder.build();
+        knn_query.build_tantivy_ast_call(
+            &schema,
+            &create_default_quickwit_tokenizer_manager(),
+            &[],
+            true,
+        )
+    }
+
+    #[test]
+    fn test_knn_query_serialization() {
+        let query_ast: QueryAst = knn_query("vector", 3).into();
+        let query_ast_json = serde_json::to_value(&query_ast).unwrap();
+        assert_eq!(
+            query_ast_json,
+            serde_json::json!({
+                "type": "knn",
+                "field": "vector",
+                "query_vector": [1.0],
+                "k": 3,
+                "filter": [{"type": "term", "field": "title", "value": "hello"}]
+            })
+        );
+        let deserialized_query_ast: QueryAst = serde_json::from_value(query_ast_json).unwrap();
+        assert_eq!(deserialized_query_ast, query_ast);
+    }
+
+    #[test]
+    fn test_knn_query_build_tantivy_ast() {
+        let tantivy_ast = build_tantivy_ast(&knn_query("vector", 3)).unwrap();
+        let leaf = tantivy_ast.as_leaf().unwrap();
+        assert!(format!("{leaf:?}").starts_with("KnnTantivyQuery"));
+
+        let error = build_tantivy_ast(&knn_query("blob", 3)).unwrap_err();
+        assert!(matches!(error, InvalidQuery::SchemaError(_)));
+
+        let error = build_tantivy_ast(&knn_query("title", 3)).unwrap_err();
+        assert!(matches!(error, InvalidQuery::SchemaError(_)));
+
+        let error = build_tantivy_ast(&knn_query("vector", 0)).unwrap_err();
+        assert!(matches!(error, InvalidQuery::Other(_)));
+    }
+}
@@ -26,6 +26,8 @@ use crate::tokenizers::TokenizerManager;
 mod bool_query;
 mod field_presence;
 mod full_text_query;
+mod knn_query;
+mod nested_query;
 mod phrase_prefix_query;
 mod range_query;
 mod tantivy_query_ast;
@@ -39,6 +41,8 @@ mod wildcard_query;
 pub use bool_query::BoolQuery;
 pub use field_presence::FieldPresenceQuery;
 pub use full_text_query::{FullTextMode, FullTextParams, FullTextQuery};
+pub use knn_query::KnnQuery;
+pub use nested_query::{NestedQuery, NestedScoreMode};
 pub use phrase_prefix_query::PhrasePrefixQuery;
 pub use range_query::RangeQuery;
 use tantivy_query_ast::TantivyQueryAst;
@@ -63,6 +67,8 @@ pub enum QueryAst {
     Range(RangeQuery),
     UserInput(UserInputQuery),
     Wildcard(WildcardQuery),
+    Knn(KnnQuery),
+    Nested(NestedQuery),
     MatchAll,
     MatchNone,
     Boost {
@@ -107,6 +113,22 @@ impl QueryAst {
             QueryAst::UserInput(user_text_query) => {
                 user_text_query.parse_user_query(default_search_fields)
             }
+            QueryAst::Knn(knn_query) => {
+                let filter = parse_user_query_in_asts(knn_query.filter, default_search_fields)?;
+                Ok(KnnQuery {
+                    filter,
+                    ..knn_query
+                }
+                .into())
+            }
+            QueryAst::Nested(nested_query) => {
+                let query = nested_query.query.parse_user_query(default_search_fields)?;
+                Ok(NestedQuery {
+                    query: Box::new(query),
+                    ..nested_query
+                }
+                .into())
+            }
             QueryAst::Boost { underlying, boost } => {
                 let underlying = underlying.parse_user_query(default_search_fields)?;
                 Ok(QueryAst::Boost {
@@ -247,6 +269,18 @@ impl BuildTantivyAst for QueryAst {
                 search_fields,
                 with_validation,
             ),
+            QueryAst::Knn(knn_query) => knn_query.build_tantivy_ast_call(
+                schema,
+                tokenizer_manager,
+                search_fields,
+                with_validation,
+            ),
+            QueryAst::Nested(nested_query) => nested_query.build_tantivy_ast_call(
+                schema,
+                tokenizer_manager,
+                search_fields,
+                with_validation,
+            ),
         }
     }
 }
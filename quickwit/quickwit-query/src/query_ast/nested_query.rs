@@ -0,0 +1,159 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use serde::{Deserialize, Serialize};
+use tantivy::query::TermQuery as TantivyTermQuery;
+use tantivy::schema::{IndexRecordOption, Schema as TantivySchema};
+
+use crate::block_join::{nested_path_term, root_docs_term, ToParentBlockJoinQuery};
+use crate::query_ast::tantivy_query_ast::{TantivyBoolQuery, TantivyQueryAst};
+use crate::query_ast::{BuildTantivyAst, QueryAst};
+use crate::tokenizers::TokenizerManager;
+use crate::InvalidQuery;
+
+/// Defines how the scores of the matching nested objects of a document are combined into the
+/// score of the document.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NestedScoreMode {
+    #[default]
+    Avg,
+    Max,
+    Min,
+    Sum,
+    None,
+}
+
+/// Matches the documents for which at least one object of the nested field `path` matches
+/// `query`. The fields of the nested objects are referred to by their full path in `query`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NestedQuery {
+    pub path: String,
+    pub query: Box<QueryAst>,
+    #[serde(default)]
+    pub score_mode: NestedScoreMode,
+}
+
+impl From<NestedQuery> for QueryAst {
+    fn from(nested_query: NestedQuery) -> Self {
+        QueryAst::Nested(nested_query)
+    }
+}
+
+impl BuildTantivyAst for NestedQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        tokenizer_manager: &TokenizerManager,
+        search_fields: &[String],
+        with_validation: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let (Some(nested_path_term), Some(root_docs_term)) =
+            (nested_path_term(schema, &self.path), root_docs_term(schema))
+        else {
+            return Err(InvalidQuery::SchemaError(format!(
+                "field `{}` is not a nested field",
+                self.path
+            )));
+        };
+        let child_ast = self.query.build_tantivy_ast_call(
+            schema,
+            tokenizer_manager,
+            search_fields,
+            with_validation,
+        )?;
+        let nested_path_query: TantivyQueryAst =
+            TantivyTermQuery::new(nested_path_term, IndexRecordOption::Basic).into();
+        let child_query = TantivyBoolQuery {
+            must: vec![child_ast],
+            filter: vec![nested_path_query],
+            ..Default::default()
+        };
+        let block_join_query = ToParentBlockJoinQuery::new(
+            child_query.simplify().into(),
+            root_docs_term,
+            self.score_mode,
+        );
+        Ok(block_join_query.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_common::shared_consts::NESTED_FIELD_NAME;
+    use tantivy::schema::{Schema, STRING};
+
+    use super::*;
+    use crate::create_default_quickwit_tokenizer_manager;
+    use crate::query_ast::TermQuery;
+
+    fn nested_query() -> NestedQuery {
+        NestedQuery {
+            path: "tags".to_string(),
+            query: Box::new(
+                TermQuery {
+                    field: "tags.k".to_string(),
+                    value: "env".to_string(),
+                }
+                .into(),
+            ),
+            score_mode: NestedScoreMode::Max,
+        }
+    }
+
+    #[test]
+    fn test_nested_query_serialization() {
+        let query_ast: QueryAst = nested_query().into();
+        let query_ast_json = serde_json::to_value(&query_ast).unwrap();
+        assert_eq!(
+            query_ast_json,
+            serde_json::json!({
+                "type": "nested",
+                "path": "tags",
+                "query": {"type": "term", "field": "tags.k", "value": "env"},
+                "score_mode": "max"
+            })
+        );
+        let deserialized_query_ast: QueryAst = serde_json::from_value(query_ast_json).unwrap();
+        assert_eq!(deserialized_query_ast, query_ast);
+    }
+
+    #[test]
+    fn test_nested_query_build_tantivy_ast() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("tags.k", STRING);
+        let schema_without_nested_field = schema_builder.build();
+
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("tags.k", STRING);
+        schema_builder.add_text_field(NESTED_FIELD_NAME, STRING);
+        let schema = schema_builder.build();
+
+        let tokenizer_manager = create_default_quickwit_tokenizer_manager();
+        let tantivy_ast = nested_query()
+            .build_tantivy_ast_call(&schema, &tokenizer_manager, &[], true)
+            .unwrap();
+        let leaf = tantivy_ast.as_leaf().unwrap();
+        assert!(format!("{leaf:?}").starts_with("ToParentBlockJoinQuery"));
+
+        let error = nested_query()
+            .build_tantivy_ast_call(&schema_without_nested_field, &tokenizer_manager, &[], true)
+            .unwrap_err();
+        assert!(matches!(error, InvalidQuery::SchemaError(_)));
+    }
+}
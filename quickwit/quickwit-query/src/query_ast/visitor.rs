@@ -21,8 +21,8 @@ use crate::not_nan_f32::NotNaNf32;
 use crate::query_ast::field_presence::FieldPresenceQuery;
 use crate::query_ast::user_input_query::UserInputQuery;
 use crate::query_ast::{
-    BoolQuery, FullTextQuery, PhrasePrefixQuery, QueryAst, RangeQuery, TermQuery, TermSetQuery,
-    WildcardQuery,
+    BoolQuery, FullTextQuery, KnnQuery, NestedQuery, PhrasePrefixQuery, QueryAst, RangeQuery,
+    TermQuery, TermSetQuery, WildcardQuery,
 };
 
 /// Simple trait to implement a Visitor over the QueryAst.
@@ -45,6 +45,8 @@ pub trait QueryAstVisitor<'a> {
             QueryAst::UserInput(user_text_query) => self.visit_user_text(user_text_query),
             QueryAst::FieldPresence(exists) => self.visit_exists(exists),
             QueryAst::Wildcard(wildcard) => self.visit_wildcard(wildcard),
+            QueryAst::Knn(knn_query) => self.visit_knn(knn_query),
+            QueryAst::Nested(nested_query) => self.visit_nested(nested_query),
         }
     }
 
@@ -111,4 +113,14 @@ pub trait QueryAstVisitor<'a> {
     fn visit_wildcard(&mut self, _wildcard_query: &'a WildcardQuery) -> Result<(), Self::Err> {
         Ok(())
     }
+
+    fn visit_knn(&mut self, knn_query: &'a KnnQuery) -> Result<(), Self::Err> {
+        for ast in &knn_query.filter {
+            self.visit(ast)?;
+        }
+        Ok(())
+    }
+    fn visit_nested(&mut self, nested_query: &'a NestedQuery) -> Result<(), Self::Err> {
+        self.visit(&nested_query.query)
+    }
 }
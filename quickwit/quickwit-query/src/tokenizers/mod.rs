@@ -21,6 +21,7 @@ mod chinese_compatible;
 mod code_tokenizer;
 #[cfg(feature = "multilang")]
 mod multilang;
+mod token_filters;
 mod tokenizer_manager;
 
 use once_cell::sync::Lazy;
@@ -33,6 +34,7 @@ use self::chinese_compatible::ChineseTokenizer;
 pub use self::code_tokenizer::CodeTokenizer;
 #[cfg(feature = "multilang")]
 pub use self::multilang::MultiLangTokenizer;
+pub use self::token_filters::{EdgeNgramFilter, SynonymFilter};
 pub use self::tokenizer_manager::TokenizerManager;
 
 pub const DEFAULT_REMOVE_TOKEN_LENGTH: usize = 255;
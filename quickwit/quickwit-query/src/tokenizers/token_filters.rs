@@ -0,0 +1,249 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use anyhow::bail;
+use tantivy::tokenizer::{Token, TokenFilter, TokenStream, Tokenizer};
+
+/// Expands each token emitted by a token stream into zero, one, or several tokens.
+pub trait TokenExpander: Clone + Send + Sync + 'static {
+    fn expand(&self, token: &Token, output: &mut VecDeque<Token>);
+}
+
+#[derive(Clone)]
+pub struct ExpandingTokenizer<T, E> {
+    inner: T,
+    expander: E,
+}
+
+impl<T: Tokenizer, E: TokenExpander> Tokenizer for ExpandingTokenizer<T, E> {
+    type TokenStream<'a> = ExpandingTokenStream<'a, T::TokenStream<'a>, E>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        ExpandingTokenStream {
+            tail: self.inner.token_stream(text),
+            expander: &self.expander,
+            pending_tokens: VecDeque::new(),
+            token: Token::default(),
+        }
+    }
+}
+
+pub struct ExpandingTokenStream<'a, S, E> {
+    tail: S,
+    expander: &'a E,
+    pending_tokens: VecDeque<Token>,
+    token: Token,
+}
+
+impl<'a, S: TokenStream, E: TokenExpander> TokenStream for ExpandingTokenStream<'a, S, E> {
+    fn advance(&mut self) -> bool {
+        loop {
+            if let Some(token) = self.pending_tokens.pop_front() {
+                self.token = token;
+                return true;
+            }
+            if !self.tail.advance() {
+                return false;
+            }
+            self.expander
+                .expand(self.tail.token(), &mut self.pending_tokens);
+        }
+    }
+
+    fn token(&self) -> &Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.token
+    }
+}
+
+/// Token filter that emits, after each token, the synonyms registered for it.
+///
+/// A synonym made of several whitespace-separated words is emitted as a single token, with its
+/// words separated by a single space, at the position of the original token. Laying out its words
+/// on consecutive positions would overlap the tokens following the original token and make phrase
+/// queries match sequences absent from the text.
+#[derive(Clone)]
+pub struct SynonymFilter {
+    synonyms: Arc<HashMap<String, Vec<(String, usize)>>>,
+}
+
+impl SynonymFilter {
+    /// Creates a new `SynonymFilter` from a list of (term, synonyms) pairs.
+    pub fn new(synonyms: impl IntoIterator<Item = (String, Vec<String>)>) -> Self {
+        let synonyms = synonyms
+            .into_iter()
+            .map(|(term, synonyms)| {
+                let expansions: Vec<(String, usize)> = synonyms
+                    .iter()
+                    .map(|synonym| synonym.split_whitespace().collect::<Vec<&str>>())
+                    .filter(|words| !words.is_empty())
+                    .map(|words| (words.join(" "), words.len()))
+                    .collect();
+                (term, expansions)
+            })
+            .collect();
+        Self {
+            synonyms: Arc::new(synonyms),
+        }
+    }
+}
+
+impl TokenExpander for SynonymFilter {
+    fn expand(&self, token: &Token, output: &mut VecDeque<Token>) {
+        output.push_back(token.clone());
+
+        let Some(expansions) = self.synonyms.get(&token.text) else {
+            return;
+        };
+        for (synonym, num_words) in expansions {
+            if *synonym == token.text {
+                continue;
+            }
+            output.push_back(Token {
+                text: synonym.clone(),
+                position_length: *num_words,
+                ..token.clone()
+            });
+        }
+    }
+}
+
+impl TokenFilter for SynonymFilter {
+    type Tokenizer<T: Tokenizer> = ExpandingTokenizer<T, SynonymFilter>;
+
+    fn transform<T: Tokenizer>(self, tokenizer: T) -> Self::Tokenizer<T> {
+        ExpandingTokenizer {
+            inner: tokenizer,
+            expander: self,
+        }
+    }
+}
+
+/// Token filter that replaces each token with its prefixes, from `min_gram` to `max_gram`
+/// characters long. Tokens shorter than `min_gram` characters are removed.
+#[derive(Clone)]
+pub struct EdgeNgramFilter {
+    min_gram: usize,
+    max_gram: usize,
+}
+
+impl EdgeNgramFilter {
+    /// Creates a new `EdgeNgramFilter`.
+    pub fn new(min_gram: usize, max_gram: usize) -> anyhow::Result<Self> {
+        if min_gram == 0 {
+            bail!("`min_gram` must be strictly positive");
+        }
+        if min_gram > max_gram {
+            bail!("`min_gram` ({min_gram}) must be lower or equal to `max_gram` ({max_gram})");
+        }
+        Ok(Self { min_gram, max_gram })
+    }
+}
+
+impl TokenExpander for EdgeNgramFilter {
+    fn expand(&self, token: &Token, output: &mut VecDeque<Token>) {
+        for (num_chars, (char_offset, char)) in (1..=self.max_gram).zip(token.text.char_indices()) {
+            if num_chars < self.min_gram {
+                continue;
+            }
+            let prefix_len = char_offset + char.len_utf8();
+            output.push_back(Token {
+                text: token.text[..prefix_len].to_string(),
+                ..token.clone()
+            });
+        }
+    }
+}
+
+impl TokenFilter for EdgeNgramFilter {
+    type Tokenizer<T: Tokenizer> = ExpandingTokenizer<T, EdgeNgramFilter>;
+
+    fn transform<T: Tokenizer>(self, tokenizer: T) -> Self::Tokenizer<T> {
+        ExpandingTokenizer {
+            inner: tokenizer,
+            expander: self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::tokenizer::{SimpleTokenizer, TextAnalyzer};
+
+    use super::*;
+
+    fn collect_tokens(text_analyzer: &mut TextAnalyzer, text: &str) -> Vec<(String, usize)> {
+        let mut token_stream = text_analyzer.token_stream(text);
+        let mut tokens = Vec::new();
+        while let Some(token) = token_stream.next() {
+            tokens.push((token.text.clone(), token.position));
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_synonym_filter() {
+        let synonym_filter = SynonymFilter::new([
+            ("tv".to_string(), vec!["television".to_string()]),
+            (
+                "usa".to_string(),
+                vec!["united states".to_string(), "usa".to_string()],
+            ),
+        ]);
+        let mut text_analyzer = TextAnalyzer::builder(SimpleTokenizer::default())
+            .filter(synonym_filter)
+            .build();
+        let tokens = collect_tokens(&mut text_analyzer, "tv usa ok");
+        assert_eq!(
+            tokens,
+            [
+                ("tv".to_string(), 0),
+                ("television".to_string(), 0),
+                ("usa".to_string(), 1),
+                ("united states".to_string(), 1),
+                ("ok".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edge_ngram_filter() {
+        assert!(EdgeNgramFilter::new(0, 2).is_err());
+        assert!(EdgeNgramFilter::new(3, 2).is_err());
+
+        let edge_ngram_filter = EdgeNgramFilter::new(2, 3).unwrap();
+        let mut text_analyzer = TextAnalyzer::builder(SimpleTokenizer::default())
+            .filter(edge_ngram_filter)
+            .build();
+        let tokens = collect_tokens(&mut text_analyzer, "a héllo ok");
+        assert_eq!(
+            tokens,
+            [
+                ("hé".to_string(), 1),
+                ("hél".to_string(), 1),
+                ("ok".to_string(), 2),
+            ]
+        );
+    }
+}
@@ -22,6 +22,7 @@ use std::time::Duration;
 use bytes::Bytes;
 use quickwit_cluster::ClusterSnapshot;
 use quickwit_config::{ConfigFormat, SourceConfig};
+use quickwit_index_management::{IndexRestoreOptions, IndexSnapshotSummary};
 use quickwit_indexing::actors::IndexingServiceCounters;
 pub use quickwit_ingest::CommitType;
 use quickwit_metastore::{IndexMetadata, Split, SplitInfo};
@@ -397,6 +398,47 @@ impl<'a> IndexClient<'a> {
         let file_entries = response.deserialize().await?;
         Ok(file_entries)
     }
+
+    /// Writes a snapshot of the index to `snapshot_uri`. Since the split files are copied, this
+    /// request is not subject to the client timeout.
+    pub async fn snapshot(
+        &self,
+        index_id: &str,
+        snapshot_uri: &str,
+    ) -> Result<IndexSnapshotSummary, Error> {
+        let path = format!("indexes/{index_id}/snapshot");
+        let body = Bytes::from(serde_json::to_vec(
+            &json!({ "snapshot_uri": snapshot_uri }),
+        )?);
+        let response = self
+            .transport
+            .send::<()>(Method::POST, &path, None, None, Some(body), Timeout::none())
+            .await?;
+        let snapshot_summary = response.deserialize().await?;
+        Ok(snapshot_summary)
+    }
+
+    /// Restores an index from a snapshot. Since the split files may be copied, this request is
+    /// not subject to the client timeout.
+    pub async fn restore(
+        &self,
+        restore_options: &IndexRestoreOptions,
+    ) -> Result<IndexMetadata, Error> {
+        let body = Bytes::from(serde_json::to_vec(restore_options)?);
+        let response = self
+            .transport
+            .send::<()>(
+                Method::POST,
+                "indexes/restore",
+                None,
+                None,
+                Some(body),
+                Timeout::none(),
+            )
+            .await?;
+        let index_metadata = response.deserialize().await?;
+        Ok(index_metadata)
+    }
 }
 
 /// Client for splits APIs.
@@ -656,6 +698,7 @@ mod test {
     use std::str::FromStr;
 
     use quickwit_config::{ConfigFormat, SourceConfig};
+    use quickwit_index_management::{IndexRestoreOptions, IndexSnapshotSummary};
     use quickwit_indexing::mock_split;
     use quickwit_ingest::CommitType;
     use quickwit_metastore::IndexMetadata;
@@ -702,6 +745,8 @@ mod test {
             aggregations: None,
             elapsed_time_micros: 100,
             errors: Vec::new(),
+            profile: None,
+            storage_request_stats: None,
         };
         Mock::given(method("POST"))
             .and(path("/api/v1/my-index/search"))
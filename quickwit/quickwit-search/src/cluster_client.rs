@@ -17,6 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::net::SocketAddr;
 use std::time::Duration;
 
 use base64::Engine;
@@ -33,6 +34,7 @@ use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::{debug, error, info, warn};
 
+use crate::collector::merge_storage_request_stats;
 use crate::retry::search::LeafSearchRetryPolicy;
 use crate::retry::search_stream::{LeafSearchStreamRetryPolicy, SuccessfulSplitIds};
 use crate::retry::{retry_client, DefaultRetryPolicy, RetryPolicy};
@@ -93,6 +95,7 @@ impl ClusterClient {
         mut client: SearchServiceClient,
     ) -> crate::Result<LeafSearchResponse> {
         let mut response_res = client.leaf_search(request.clone()).await;
+        set_split_profiles_node_addr(&mut response_res, client.grpc_addr());
         let retry_policy = LeafSearchRetryPolicy {};
         if let Some(retry_request) = retry_policy.retry_request(request, &response_res) {
             assert!(!retry_request.split_offsets.is_empty());
@@ -106,7 +109,8 @@ impl ClusterClient {
                 "Leaf search response error: `{:?}`. Retry once to execute {:?} with {:?}",
                 response_res, retry_request, client
             );
-            let retry_result = client.leaf_search(retry_request).await;
+            let mut retry_result = client.leaf_search(retry_request).await;
+            set_split_profiles_node_addr(&mut retry_result, client.grpc_addr());
             response_res = merge_leaf_search_results(response_res, retry_result);
         }
         response_res
@@ -281,6 +285,9 @@ fn merge_leaf_search_response(
     left_response
         .partial_hits
         .extend(right_response.partial_hits);
+    left_response
+        .split_profiles
+        .extend(right_response.split_profiles);
     let intermediate_aggregation_result: Option<Vec<u8>> = match (
         left_response.intermediate_aggregation_result,
         right_response.intermediate_aggregation_result,
@@ -301,9 +308,29 @@ fn merge_leaf_search_response(
             + right_response.num_attempted_splits,
         failed_splits: right_response.failed_splits,
         partial_hits: left_response.partial_hits,
+        split_profiles: left_response.split_profiles,
+        storage_request_stats: merge_storage_request_stats([
+            &left_response.storage_request_stats,
+            &right_response.storage_request_stats,
+        ]),
     })
 }
 
+/// Records the address of the searcher that produced the split profiles of a leaf search
+/// response.
+fn set_split_profiles_node_addr(
+    response_res: &mut crate::Result<LeafSearchResponse>,
+    node_addr: SocketAddr,
+) {
+    if let Ok(response) = response_res {
+        for split_profile in &mut response.split_profiles {
+            if split_profile.node_addr.is_empty() {
+                split_profile.node_addr = node_addr.to_string();
+            }
+        }
+    }
+}
+
 // Merge initial leaf search results with results obtained from a retry.
 fn merge_leaf_search_results(
     left_search_response_result: crate::Result<LeafSearchResponse>,
@@ -367,6 +394,7 @@ mod tests {
             split_id: split_id.to_string(),
             segment_ord: 1,
             doc_id,
+            collapse_key: None,
         }
     }
 
@@ -380,6 +408,7 @@ mod tests {
                 split_footer_start: 0,
                 timestamp_start: None,
                 timestamp_end: None,
+                storage_uri: None,
             }],
             ..Default::default()
         }
@@ -396,6 +425,7 @@ mod tests {
             search_request: Some(search_request),
             doc_mapper: "doc_mapper".to_string(),
             index_uri: "uri".to_string(),
+            index_id: "test-idx".to_string(),
             split_offsets: vec![
                 SplitIdAndFooterOffsets {
                     split_id: "split_1".to_string(),
@@ -403,6 +433,7 @@ mod tests {
                     split_footer_end: 100,
                     timestamp_start: None,
                     timestamp_end: None,
+                    storage_uri: None,
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
@@ -410,6 +441,7 @@ mod tests {
                     split_footer_end: 100,
                     timestamp_start: None,
                     timestamp_end: None,
+                    storage_uri: None,
                 },
             ],
         }
@@ -437,6 +469,7 @@ mod tests {
                     split_footer_end: 100,
                     timestamp_start: None,
                     timestamp_end: None,
+                    storage_uri: None,
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
@@ -444,6 +477,7 @@ mod tests {
                     split_footer_end: 100,
                     timestamp_start: None,
                     timestamp_end: None,
+                    storage_uri: None,
                 },
             ],
         }
@@ -455,7 +489,10 @@ mod tests {
         let mut mock_search_service = MockSearchService::new();
         mock_search_service.expect_fetch_docs().return_once(
             |_: quickwit_proto::search::FetchDocsRequest| {
-                Ok(quickwit_proto::search::FetchDocsResponse { hits: Vec::new() })
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: Vec::new(),
+                    storage_request_stats: None,
+                })
             },
         );
         let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
@@ -484,7 +521,10 @@ mod tests {
         let mut mock_search_service_2 = MockSearchService::new();
         mock_search_service_2.expect_fetch_docs().return_once(
             |_: quickwit_proto::search::FetchDocsRequest| {
-                Ok(quickwit_proto::search::FetchDocsResponse { hits: Vec::new() })
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: Vec::new(),
+                    storage_request_stats: None,
+                })
             },
         );
         let searcher_pool = searcher_pool_for_test([
@@ -19,24 +19,31 @@
 
 use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
 use itertools::Itertools;
 use quickwit_common::binary_heap::{SortKeyMapper, TopK};
 use quickwit_doc_mapper::{DocMapper, WarmupInfo};
 use quickwit_proto::search::{
     LeafSearchResponse, PartialHit, SearchRequest, SortByValue, SortOrder, SortValue,
-    SplitSearchError,
+    SplitSearchError, SplitSearchProfile, StorageRequestStats,
 };
 use serde::Deserialize;
 use tantivy::aggregation::agg_req::{get_fast_field_names, Aggregations};
 use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tantivy::aggregation::{AggregationLimits, AggregationSegmentCollector};
 use tantivy::collector::{Collector, SegmentCollector};
-use tantivy::columnar::{ColumnType, MonotonicallyMappableToU64};
+use tantivy::columnar::{BytesColumn, ColumnType, MonotonicallyMappableToU64, StrColumn};
 use tantivy::fastfield::Column;
 use tantivy::{DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
 
+use crate::extended_aggregations::{
+    ExtendedAggregations, ExtendedAggregationsSegmentCollector,
+    IntermediateExtendedAggregationResults,
+};
 use crate::filters::{create_timestamp_filter_builder, TimestampFilter, TimestampFilterBuilder};
 use crate::find_trace_ids_collector::{FindTraceIdsCollector, FindTraceIdsSegmentCollector, Span};
 use crate::GlobalDocAddress;
@@ -64,8 +71,8 @@ impl From<SortByComponent> for SortByPair {
 }
 #[derive(Clone)]
 pub(crate) struct SortByPair {
-    first: SortByComponent,
-    second: Option<SortByComponent>,
+    pub first: SortByComponent,
+    pub second: Option<SortByComponent>,
 }
 impl SortByPair {
     pub fn sort_orders(&self) -> (SortOrder, SortOrder) {
@@ -196,7 +203,7 @@ impl SortingFieldExtractorPair {
     ///
     /// See also [`SortingFieldExtractorComponent::extract_typed_sort_value_opt`] for more
     /// information.
-    fn extract_typed_sort_value(
+    pub(crate) fn extract_typed_sort_value(
         &self,
         doc_id: DocId,
         score: Score,
@@ -230,7 +237,7 @@ impl TryFrom<ColumnType> for SortFieldType {
 
 /// Takes a user-defined sorting criteria and resolves it to a
 /// segment specific `SortingFieldExtractorPair`.
-fn get_score_extractor(
+pub(crate) fn get_score_extractor(
     sort_by: &SortByPair,
     segment_reader: &SegmentReader,
 ) -> tantivy::Result<SortingFieldExtractorPair> {
@@ -291,6 +298,7 @@ impl Eq for PartialHitHeapItem {}
 enum AggregationSegmentCollectors {
     FindTraceIdsSegmentCollector(Box<FindTraceIdsSegmentCollector>),
     TantivyAggregationSegmentCollector(AggregationSegmentCollector),
+    ExtendedAggregationsSegmentCollector(Box<ExtendedAggregationsSegmentCollector>),
 }
 
 /// Quickwit collector working at the scale of the segment.
@@ -305,6 +313,8 @@ pub struct QuickwitSegmentCollector {
     aggregation: Option<AggregationSegmentCollectors>,
     search_after: Option<PartialHit>,
     split_search_after_order: Ordering,
+    // If set, `top_k_hits` is left empty and the best hit of each group is kept here instead.
+    collapser_opt: Option<SegmentCollapser>,
 }
 
 impl QuickwitSegmentCollector {
@@ -345,7 +355,11 @@ impl QuickwitSegmentCollector {
             sort_value2: sort_value2.map(Into::into),
             doc_id,
         };
-        self.top_k_hits.add_entry(hit);
+        if let Some(collapser) = &mut self.collapser_opt {
+            collapser.add_hit(hit, &self.top_k_hits.sort_key_mapper);
+        } else {
+            self.top_k_hits.add_entry(hit);
+        }
     }
 
     #[inline]
@@ -358,14 +372,18 @@ impl QuickwitSegmentCollector {
 }
 
 #[derive(Copy, Clone, Debug)]
-struct SegmentPartialHit {
-    sort_value: Option<SortValue>,
-    sort_value2: Option<SortValue>,
-    doc_id: DocId,
+pub(crate) struct SegmentPartialHit {
+    pub sort_value: Option<SortValue>,
+    pub sort_value2: Option<SortValue>,
+    pub doc_id: DocId,
 }
 
 impl SegmentPartialHit {
-    fn into_partial_hit(self, split_id: String, segment_ord: SegmentOrdinal) -> PartialHit {
+    pub(crate) fn into_partial_hit(
+        self,
+        split_id: String,
+        segment_ord: SegmentOrdinal,
+    ) -> PartialHit {
         PartialHit {
             sort_value: self.sort_value.map(|sort_value| SortByValue {
                 sort_value: Some(sort_value),
@@ -376,10 +394,172 @@ impl SegmentPartialHit {
             doc_id: self.doc_id,
             split_id,
             segment_ord,
+            collapse_key: None,
         }
     }
 }
 
+/// Column of the field used to collapse hits.
+///
+/// Collapse keys are extracted as `u64`: term ordinals for text and bytes columns, and the
+/// monotonically mapped value for numeric columns.
+enum CollapseColumn {
+    Str(StrColumn),
+    Bytes(BytesColumn),
+    Numeric {
+        column: Column<u64>,
+        column_type: ColumnType,
+    },
+    Missing,
+}
+
+impl CollapseColumn {
+    fn open(field_name: &str, segment_reader: &SegmentReader) -> tantivy::Result<Self> {
+        let fast_fields = segment_reader.fast_fields();
+        if let Some(str_column) = fast_fields.str(field_name)? {
+            return Ok(CollapseColumn::Str(str_column));
+        }
+        if let Some(bytes_column) = fast_fields.bytes(field_name)? {
+            return Ok(CollapseColumn::Bytes(bytes_column));
+        }
+        if let Some((column, column_type)) = fast_fields.u64_lenient(field_name)? {
+            return Ok(CollapseColumn::Numeric {
+                column,
+                column_type,
+            });
+        }
+        Ok(CollapseColumn::Missing)
+    }
+
+    /// Returns the collapse key of the document, or `None` if the document does not have a value
+    /// for the collapse field. Multivalued fields are collapsed on their first value.
+    fn collapse_key(&self, doc_id: DocId) -> Option<u64> {
+        match self {
+            CollapseColumn::Str(str_column) => str_column.term_ords(doc_id).next(),
+            CollapseColumn::Bytes(bytes_column) => bytes_column.term_ords(doc_id).next(),
+            CollapseColumn::Numeric { column, .. } => column.first(doc_id),
+            CollapseColumn::Missing => None,
+        }
+    }
+
+    /// Formats a collapse key so that it can be compared across segments and splits, and used
+    /// as the value of a term query on the collapse field.
+    fn format_collapse_key(&self, collapse_key: u64) -> tantivy::Result<String> {
+        let formatted_key = match self {
+            CollapseColumn::Str(str_column) => {
+                let mut buffer = String::new();
+                str_column.ord_to_str(collapse_key, &mut buffer)?;
+                buffer
+            }
+            CollapseColumn::Bytes(bytes_column) => {
+                let mut buffer = Vec::new();
+                bytes_column.ord_to_bytes(collapse_key, &mut buffer)?;
+                BASE64_STANDARD.encode(buffer)
+            }
+            CollapseColumn::Numeric {
+                column_type: ColumnType::I64 | ColumnType::DateTime,
+                ..
+            } => i64::from_u64(collapse_key).to_string(),
+            CollapseColumn::Numeric {
+                column_type: ColumnType::F64,
+                ..
+            } => f64::from_u64(collapse_key).to_string(),
+            CollapseColumn::Numeric {
+                column_type: ColumnType::Bool,
+                ..
+            } => (collapse_key != 0).to_string(),
+            CollapseColumn::Numeric { .. } => collapse_key.to_string(),
+            CollapseColumn::Missing => {
+                return Err(TantivyError::InternalError(
+                    "collapse key extracted from a missing column".to_string(),
+                ))
+            }
+        };
+        Ok(formatted_key)
+    }
+}
+
+/// Keeps track of the best hit of the `num_groups` best groups of a segment.
+///
+/// A group can only be evicted by a group whose best hit is better than all the hits of the
+/// evicted group, so the groups evicted here can never make it to the final top K.
+struct SegmentCollapser {
+    collapse_column: CollapseColumn,
+    num_groups: usize,
+    best_hit_per_group: HashMap<Option<u64>, SegmentPartialHit>,
+    // Cached worst group, invalidated every time `best_hit_per_group` changes.
+    worst_group_opt: Option<(Option<u64>, SegmentPartialHitSortingKey)>,
+}
+
+impl SegmentCollapser {
+    fn new(collapse_column: CollapseColumn, num_groups: usize) -> Self {
+        SegmentCollapser {
+            collapse_column,
+            num_groups,
+            best_hit_per_group: HashMap::with_capacity(num_groups),
+            worst_group_opt: None,
+        }
+    }
+
+    fn add_hit(&mut self, hit: SegmentPartialHit, sort_key_mapper: &HitSortingMapper) {
+        if self.num_groups == 0 {
+            return;
+        }
+        let collapse_key = self.collapse_column.collapse_key(hit.doc_id);
+        let sort_key = sort_key_mapper.get_sort_key(&hit);
+
+        if let Some(best_hit) = self.best_hit_per_group.get_mut(&collapse_key) {
+            if sort_key > sort_key_mapper.get_sort_key(best_hit) {
+                *best_hit = hit;
+                self.worst_group_opt = None;
+            }
+            return;
+        }
+        if self.best_hit_per_group.len() < self.num_groups {
+            self.best_hit_per_group.insert(collapse_key, hit);
+            self.worst_group_opt = None;
+            return;
+        }
+        let (worst_collapse_key, worst_sort_key) = self.worst_group_opt.get_or_insert_with(|| {
+            self.best_hit_per_group
+                .iter()
+                .map(|(collapse_key, best_hit)| {
+                    (*collapse_key, sort_key_mapper.get_sort_key(best_hit))
+                })
+                .min_by(|left, right| left.1.cmp(&right.1))
+                .expect("the collapser should hold at least one group")
+        });
+        if sort_key > *worst_sort_key {
+            self.best_hit_per_group.remove(worst_collapse_key);
+            self.best_hit_per_group.insert(collapse_key, hit);
+            self.worst_group_opt = None;
+        }
+    }
+
+    fn harvest(
+        self,
+        sort_key_mapper: &HitSortingMapper,
+        split_id: &str,
+        segment_ord: SegmentOrdinal,
+    ) -> tantivy::Result<Vec<PartialHit>> {
+        let mut groups: Vec<(Option<u64>, SegmentPartialHit)> =
+            self.best_hit_per_group.into_iter().collect();
+        groups.sort_unstable_by_key(|(_, best_hit)| {
+            std::cmp::Reverse(sort_key_mapper.get_sort_key(best_hit))
+        });
+        groups
+            .into_iter()
+            .map(|(collapse_key_opt, best_hit)| {
+                let mut partial_hit = best_hit.into_partial_hit(split_id.to_string(), segment_ord);
+                partial_hit.collapse_key = collapse_key_opt
+                    .map(|collapse_key| self.collapse_column.format_collapse_key(collapse_key))
+                    .transpose()?;
+                Ok(partial_hit)
+            })
+            .collect()
+    }
+}
+
 impl SegmentCollector for QuickwitSegmentCollector {
     type Fruit = tantivy::Result<LeafSearchResponse>;
 
@@ -399,19 +579,29 @@ impl SegmentCollector for QuickwitSegmentCollector {
             Some(AggregationSegmentCollectors::TantivyAggregationSegmentCollector(collector)) => {
                 collector.collect(doc_id, score)
             }
+            Some(AggregationSegmentCollectors::ExtendedAggregationsSegmentCollector(collector)) => {
+                collector.collect(doc_id, score)
+            }
             None => (),
         }
     }
 
     fn harvest(self) -> Self::Fruit {
-        let partial_hits: Vec<PartialHit> = self
-            .top_k_hits
-            .finalize()
-            .into_iter()
-            .map(|segment_partial_hit: SegmentPartialHit| {
-                segment_partial_hit.into_partial_hit(self.split_id.clone(), self.segment_ord)
-            })
-            .collect();
+        let partial_hits: Vec<PartialHit> = if let Some(collapser) = self.collapser_opt {
+            collapser.harvest(
+                &self.top_k_hits.sort_key_mapper,
+                &self.split_id,
+                self.segment_ord,
+            )?
+        } else {
+            self.top_k_hits
+                .finalize()
+                .into_iter()
+                .map(|segment_partial_hit: SegmentPartialHit| {
+                    segment_partial_hit.into_partial_hit(self.split_id.clone(), self.segment_ord)
+                })
+                .collect()
+        };
 
         let intermediate_aggregation_result = match self.aggregation {
             Some(AggregationSegmentCollectors::FindTraceIdsSegmentCollector(collector)) => {
@@ -425,6 +615,11 @@ impl SegmentCollector for QuickwitSegmentCollector {
                     .expect("Collector fruit should be serializable.");
                 Some(serialized)
             }
+            Some(AggregationSegmentCollectors::ExtendedAggregationsSegmentCollector(collector)) => {
+                let serialized = postcard::to_allocvec(&collector.harvest()?)
+                    .expect("Collector fruit should be serializable.");
+                Some(serialized)
+            }
             None => None,
         };
         Ok(LeafSearchResponse {
@@ -433,6 +628,8 @@ impl SegmentCollector for QuickwitSegmentCollector {
             partial_hits,
             failed_splits: Vec::new(),
             num_attempted_splits: 1,
+            split_profiles: Vec::new(),
+            storage_request_stats: None,
         })
     }
 }
@@ -444,16 +641,22 @@ pub enum QuickwitAggregations {
     /// Aggregation used by the Jaeger service to find trace IDs that match a
     /// [`quickwit_proto::jaeger::storage::v1::FindTraceIDsRequest`].
     FindTraceIdsAggregation(FindTraceIdsCollector),
+    /// Aggregations containing `top_hits` or `composite` aggregations, which tantivy does not
+    /// support, alongside classic tantivy aggregations.
+    ExtendedAggregations(ExtendedAggregations),
     /// Your classic Tantivy aggregation.
     TantivyAggregations(Aggregations),
 }
 
 impl QuickwitAggregations {
-    fn fast_field_names(&self) -> HashSet<String> {
+    pub(crate) fn fast_field_names(&self) -> HashSet<String> {
         match self {
             QuickwitAggregations::FindTraceIdsAggregation(collector) => {
                 collector.fast_field_names()
             }
+            QuickwitAggregations::ExtendedAggregations(aggregations) => {
+                aggregations.fast_field_names()
+            }
             QuickwitAggregations::TantivyAggregations(aggregations) => {
                 get_fast_field_names(aggregations)
             }
@@ -465,6 +668,9 @@ impl QuickwitAggregations {
             QuickwitAggregations::FindTraceIdsAggregation(aggreg) => {
                 QuickwitIncrementalAggregations::FindTraceIdsAggregation(aggreg.clone(), Vec::new())
             }
+            QuickwitAggregations::ExtendedAggregations(aggreg) => {
+                QuickwitIncrementalAggregations::ExtendedAggregations(aggreg.clone(), Vec::new())
+            }
             QuickwitAggregations::TantivyAggregations(aggreg) => {
                 QuickwitIncrementalAggregations::TantivyAggregations(aggreg.clone(), Vec::new())
             }
@@ -475,6 +681,7 @@ impl QuickwitAggregations {
 #[derive(Clone)]
 enum QuickwitIncrementalAggregations {
     FindTraceIdsAggregation(FindTraceIdsCollector, Vec<Vec<Span>>),
+    ExtendedAggregations(ExtendedAggregations, Vec<Vec<u8>>),
     TantivyAggregations(Aggregations, Vec<Vec<u8>>),
     NoAggregation,
 }
@@ -491,7 +698,8 @@ impl QuickwitIncrementalAggregations {
                     state.push(new_state);
                 }
             }
-            QuickwitIncrementalAggregations::TantivyAggregations(_, state) => {
+            QuickwitIncrementalAggregations::ExtendedAggregations(_, state)
+            | QuickwitIncrementalAggregations::TantivyAggregations(_, state) => {
                 state.push(intermediate_result);
             }
             QuickwitIncrementalAggregations::NoAggregation => (),
@@ -514,12 +722,14 @@ impl QuickwitIncrementalAggregations {
                                 split_id: String::new(),
                                 segment_ord: 0,
                                 doc_id: 0,
+                                collapse_key: None,
                             });
                         }
                     }
                 }
                 None
             }
+            QuickwitIncrementalAggregations::ExtendedAggregations(_, _) => None,
             QuickwitIncrementalAggregations::TantivyAggregations(_, _) => None,
             QuickwitIncrementalAggregations::NoAggregation => None,
         }
@@ -536,6 +746,12 @@ impl QuickwitIncrementalAggregations {
                 let serialized = postcard::to_allocvec(&merged_fruit).map_err(map_error)?;
                 Ok(Some(serialized))
             }
+            QuickwitIncrementalAggregations::ExtendedAggregations(aggregation, state) => {
+                merge_intermediate_aggregation_result(
+                    &Some(QuickwitAggregations::ExtendedAggregations(aggregation)),
+                    state.iter().map(|vec| vec.as_slice()),
+                )
+            }
             QuickwitIncrementalAggregations::TantivyAggregations(aggregation, state) => {
                 merge_intermediate_aggregation_result(
                     &Some(QuickwitAggregations::TantivyAggregations(aggregation)),
@@ -561,6 +777,7 @@ pub(crate) struct QuickwitCollector {
     pub aggregation: Option<QuickwitAggregations>,
     pub aggregation_limits: AggregationLimits,
     search_after: Option<PartialHit>,
+    pub collapse_field: Option<String>,
 }
 
 impl QuickwitCollector {
@@ -576,6 +793,9 @@ impl QuickwitCollector {
         if let Some(timestamp_filter_builder) = &self.timestamp_filter_builder_opt {
             fast_field_names.insert(timestamp_filter_builder.timestamp_field_name.clone());
         }
+        if let Some(collapse_field) = &self.collapse_field {
+            fast_field_names.insert(collapse_field.clone());
+        }
         fast_field_names
     }
 
@@ -611,6 +831,17 @@ impl Collector for QuickwitCollector {
                     Box::new(collector.for_segment(0, segment_reader)?),
                 ))
             }
+            Some(QuickwitAggregations::ExtendedAggregations(aggs)) => Some(
+                AggregationSegmentCollectors::ExtendedAggregationsSegmentCollector(Box::new(
+                    ExtendedAggregationsSegmentCollector::new(
+                        aggs,
+                        &self.split_id,
+                        segment_ord,
+                        segment_reader,
+                        &self.aggregation_limits,
+                    )?,
+                )),
+            ),
             Some(QuickwitAggregations::TantivyAggregations(aggs)) => Some(
                 AggregationSegmentCollectors::TantivyAggregationSegmentCollector(
                     AggregationSegmentCollector::from_agg_req_and_reader(
@@ -637,6 +868,13 @@ impl Collector for QuickwitCollector {
             // this value isn't actually used.
             Ordering::Equal
         };
+        let collapser_opt = match &self.collapse_field {
+            Some(collapse_field) => {
+                let collapse_column = CollapseColumn::open(collapse_field, segment_reader)?;
+                Some(SegmentCollapser::new(collapse_column, leaf_max_hits))
+            }
+            None => None,
+        };
         Ok(QuickwitSegmentCollector {
             num_hits: 0u64,
             split_id: self.split_id.clone(),
@@ -647,6 +885,7 @@ impl Collector for QuickwitCollector {
             aggregation,
             search_after: self.search_after.clone(),
             split_search_after_order,
+            collapser_opt,
         })
     }
 
@@ -680,6 +919,7 @@ impl Collector for QuickwitCollector {
             sort_order1,
             sort_order2,
             num_hits,
+            self.collapse_field.is_some(),
         )?;
         // ... and drop the first [..start_offsets) hits.
         // note that self.start_offset is 0 when merging from leaf_search, and is only set when
@@ -718,6 +958,26 @@ fn merge_intermediate_aggregation_result<'a>(
             let serialized = postcard::to_allocvec(&merged_fruit).map_err(map_error)?;
             Some(serialized)
         }
+        Some(QuickwitAggregations::ExtendedAggregations(aggregations)) => {
+            let fruits: Vec<IntermediateExtendedAggregationResults> =
+                intermediate_aggregation_results
+                    .map(|intermediate_aggregation_result| {
+                        postcard::from_bytes(intermediate_aggregation_result).map_err(map_error)
+                    })
+                    .collect::<Result<_, _>>()?;
+
+            let mut fruit_iter = fruits.into_iter();
+            if let Some(first_fruit) = fruit_iter.next() {
+                let mut merged_fruit = first_fruit;
+                for fruit in fruit_iter {
+                    merged_fruit.merge(fruit, aggregations)?;
+                }
+                let serialized = postcard::to_allocvec(&merged_fruit).map_err(map_error)?;
+                Some(serialized)
+            } else {
+                None
+            }
+        }
         Some(QuickwitAggregations::TantivyAggregations(_)) => {
             let fruits: Vec<IntermediateAggregationResults> = intermediate_aggregation_results
                 .map(|intermediate_aggregation_result| {
@@ -744,6 +1004,22 @@ fn merge_intermediate_aggregation_result<'a>(
     Ok(merged_intermediate_aggregation_result)
 }
 
+/// Sums the storage request stats of several leaf search or fetch docs responses. Returns `None`
+/// if none of them reported any.
+pub(crate) fn merge_storage_request_stats<'a>(
+    storage_request_stats: impl IntoIterator<Item = &'a Option<StorageRequestStats>>,
+) -> Option<StorageRequestStats> {
+    storage_request_stats
+        .into_iter()
+        .flatten()
+        .fold(None, |merged_stats_opt, stats| {
+            let mut merged_stats = merged_stats_opt.unwrap_or_default();
+            merged_stats.num_requests += stats.num_requests;
+            merged_stats.num_bytes_downloaded += stats.num_bytes_downloaded;
+            Some(merged_stats)
+        })
+}
+
 /// Merges a set of Leaf Results.
 fn merge_leaf_responses(
     aggregations_opt: &Option<QuickwitAggregations>,
@@ -751,6 +1027,7 @@ fn merge_leaf_responses(
     sort_order1: SortOrder,
     sort_order2: SortOrder,
     max_hits: usize,
+    collapse: bool,
 ) -> tantivy::Result<LeafSearchResponse> {
     // Optimization: No merging needed if there is only one result.
     if leaf_responses.len() == 1 {
@@ -777,22 +1054,35 @@ fn merge_leaf_responses(
         .flat_map(|leaf_response| leaf_response.failed_splits.iter())
         .cloned()
         .collect_vec();
-    let all_partial_hits: Vec<PartialHit> = leaf_responses
-        .into_iter()
-        .flat_map(|leaf_response| leaf_response.partial_hits)
-        .collect();
-    let top_k_partial_hits: Vec<PartialHit> = top_k_partial_hits(
-        all_partial_hits.into_iter(),
-        sort_order1,
-        sort_order2,
-        max_hits,
+    let storage_request_stats = merge_storage_request_stats(
+        leaf_responses
+            .iter()
+            .map(|leaf_response| &leaf_response.storage_request_stats),
     );
+    let mut all_partial_hits: Vec<PartialHit> = Vec::new();
+    let mut split_profiles: Vec<SplitSearchProfile> = Vec::new();
+    for leaf_response in leaf_responses {
+        all_partial_hits.extend(leaf_response.partial_hits);
+        split_profiles.extend(leaf_response.split_profiles);
+    }
+    let top_k_partial_hits: Vec<PartialHit> = if collapse {
+        top_k_collapsed_partial_hits(all_partial_hits, sort_order1, sort_order2, max_hits)
+    } else {
+        top_k_partial_hits(
+            all_partial_hits.into_iter(),
+            sort_order1,
+            sort_order2,
+            max_hits,
+        )
+    };
     Ok(LeafSearchResponse {
         intermediate_aggregation_result: merged_intermediate_aggregation_result,
         num_hits,
         partial_hits: top_k_partial_hits,
         failed_splits,
         num_attempted_splits,
+        split_profiles,
+        storage_request_stats,
     })
 }
 
@@ -800,7 +1090,7 @@ fn merge_leaf_responses(
 /// and so that these elements are sorted.
 ///
 /// TODO we could possibly optimize the sort away (but I doubt it matters).
-fn top_k_partial_hits(
+pub(crate) fn top_k_partial_hits(
     partial_hits: impl Iterator<Item = PartialHit>,
     order1: SortOrder,
     order2: SortOrder,
@@ -814,6 +1104,41 @@ fn top_k_partial_hits(
     top_k_hits.finalize()
 }
 
+/// Keeps the best hit for each collapse key, and returns the top-num_hits of these hits,
+/// sorted.
+fn top_k_collapsed_partial_hits(
+    partial_hits: impl IntoIterator<Item = PartialHit>,
+    order1: SortOrder,
+    order2: SortOrder,
+    num_hits: usize,
+) -> Vec<PartialHit> {
+    let sort_key_mapper = HitSortingMapper { order1, order2 };
+    let mut best_hit_per_group: HashMap<Option<String>, PartialHit> = HashMap::new();
+    for partial_hit in partial_hits {
+        add_collapsed_partial_hit(&mut best_hit_per_group, partial_hit, &sort_key_mapper);
+    }
+    top_k_partial_hits(best_hit_per_group.into_values(), order1, order2, num_hits)
+}
+
+fn add_collapsed_partial_hit(
+    best_hit_per_group: &mut HashMap<Option<String>, PartialHit>,
+    partial_hit: PartialHit,
+    sort_key_mapper: &HitSortingMapper,
+) {
+    match best_hit_per_group.entry(partial_hit.collapse_key.clone()) {
+        Entry::Occupied(mut entry) => {
+            if sort_key_mapper.get_sort_key(&partial_hit)
+                > sort_key_mapper.get_sort_key(entry.get())
+            {
+                entry.insert(partial_hit);
+            }
+        }
+        Entry::Vacant(entry) => {
+            entry.insert(partial_hit);
+        }
+    }
+}
+
 pub(crate) fn sort_by_from_request(search_request: &SearchRequest) -> SortByPair {
     let to_sort_by_component = |field_name: &str, order| {
         if field_name == "_score" {
@@ -878,6 +1203,7 @@ pub(crate) fn make_collector_for_split(
         aggregation,
         aggregation_limits,
         search_after: search_request.search_after.clone(),
+        collapse_field: collapse_field_from_request(search_request),
     })
 }
 
@@ -900,11 +1226,19 @@ pub(crate) fn make_merge_collector(
         aggregation,
         aggregation_limits: aggregation_limits.clone(),
         search_after: search_request.search_after.clone(),
+        collapse_field: collapse_field_from_request(search_request),
     })
 }
 
+fn collapse_field_from_request(search_request: &SearchRequest) -> Option<String> {
+    search_request
+        .collapse
+        .as_ref()
+        .map(|collapse| collapse.field.clone())
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-struct SegmentPartialHitSortingKey {
+pub(crate) struct SegmentPartialHitSortingKey {
     sort_value: Option<SortValue>,
     sort_value2: Option<SortValue>,
     doc_id: DocId,
@@ -983,9 +1317,9 @@ impl PartialOrd for PartialHitSortingKey {
 }
 
 #[derive(Clone)]
-struct HitSortingMapper {
-    order1: SortOrder,
-    order2: SortOrder,
+pub(crate) struct HitSortingMapper {
+    pub order1: SortOrder,
+    pub order2: SortOrder,
 }
 
 impl SortKeyMapper<PartialHit> for HitSortingMapper {
@@ -1019,10 +1353,13 @@ impl SortKeyMapper<SegmentPartialHit> for HitSortingMapper {
 pub(crate) struct IncrementalCollector {
     inner: QuickwitCollector,
     top_k_hits: TopK<PartialHit, PartialHitSortingKey, HitSortingMapper>,
+    // Best hit per collapse key, used instead of `top_k_hits` if the hits are collapsed.
+    collapsed_hits_opt: Option<HashMap<Option<String>, PartialHit>>,
     incremental_aggregation: QuickwitIncrementalAggregations,
     num_hits: u64,
     failed_splits: Vec<SplitSearchError>,
     num_attempted_splits: u64,
+    split_profiles: Vec<SplitSearchProfile>,
 }
 
 impl IncrementalCollector {
@@ -1035,13 +1372,16 @@ impl IncrementalCollector {
             .unwrap_or(QuickwitIncrementalAggregations::NoAggregation);
         let (order1, order2) = inner.sort_by.sort_orders();
         let sort_key_mapper = HitSortingMapper { order1, order2 };
+        let collapsed_hits_opt = inner.collapse_field.as_ref().map(|_| HashMap::new());
         IncrementalCollector {
             top_k_hits: TopK::new(inner.max_hits + inner.start_offset, sort_key_mapper),
+            collapsed_hits_opt,
             inner,
             incremental_aggregation,
             num_hits: 0,
             failed_splits: Vec::new(),
             num_attempted_splits: 0,
+            split_profiles: Vec::new(),
         }
     }
 
@@ -1053,10 +1393,22 @@ impl IncrementalCollector {
             failed_splits,
             num_attempted_splits,
             intermediate_aggregation_result,
+            split_profiles,
         } = leaf_response;
 
         self.num_hits += num_hits;
-        self.top_k_hits.add_entries(partial_hits.into_iter());
+        self.split_profiles.extend(split_profiles);
+        if let Some(collapsed_hits) = &mut self.collapsed_hits_opt {
+            for partial_hit in partial_hits {
+                add_collapsed_partial_hit(
+                    collapsed_hits,
+                    partial_hit,
+                    &self.top_k_hits.sort_key_mapper,
+                );
+            }
+        } else {
+            self.top_k_hits.add_entries(partial_hits.into_iter());
+        }
         self.failed_splits.extend(failed_splits);
         self.num_attempted_splits += num_attempted_splits;
         if let Some(intermediate_aggregation_result) = intermediate_aggregation_result {
@@ -1073,7 +1425,8 @@ impl IncrementalCollector {
 
     /// Get the worst top-hit. Can be used to skip splits if they can't possibly do better.
     ///
-    /// Only returns a result if enough hits were recorded already.
+    /// Only returns a result if enough hits were recorded already. Collapsed hits never return
+    /// a result, as a split can always improve the best hit of a group.
     pub(crate) fn peek_worst_hit(&self) -> Option<Cow<PartialHit>> {
         if self.top_k_hits.max_len() == 0 {
             return self
@@ -1081,6 +1434,9 @@ impl IncrementalCollector {
                 .virtual_worst_hit()
                 .map(Cow::Owned);
         }
+        if self.collapsed_hits_opt.is_some() {
+            return None;
+        }
 
         if self.top_k_hits.at_capacity() {
             self.top_k_hits.peek_worst().map(Cow::Borrowed)
@@ -1092,7 +1448,17 @@ impl IncrementalCollector {
     /// Finalize the merge, creating a LeafSearchResponse.
     pub(crate) fn finalize(self) -> tantivy::Result<LeafSearchResponse> {
         let intermediate_aggregation_result = self.incremental_aggregation.finalize()?;
-        let mut partial_hits = self.top_k_hits.finalize();
+        let mut partial_hits = if let Some(collapsed_hits) = self.collapsed_hits_opt {
+            let HitSortingMapper { order1, order2 } = self.top_k_hits.sort_key_mapper;
+            top_k_partial_hits(
+                collapsed_hits.into_values(),
+                order1,
+                order2,
+                self.top_k_hits.max_len(),
+            )
+        } else {
+            self.top_k_hits.finalize()
+        };
         if self.inner.start_offset != 0 {
             partial_hits.drain(0..self.inner.start_offset.min(partial_hits.len()));
         }
@@ -1102,6 +1468,8 @@ impl IncrementalCollector {
             failed_splits: self.failed_splits,
             num_attempted_splits: self.num_attempted_splits,
             intermediate_aggregation_result,
+            split_profiles: self.split_profiles,
+            storage_request_stats: None,
         })
     }
 }
@@ -1111,8 +1479,8 @@ mod tests {
     use std::cmp::Ordering;
 
     use quickwit_proto::search::{
-        LeafSearchResponse, PartialHit, SearchRequest, SortByValue, SortField, SortOrder,
-        SortValue, SplitSearchError,
+        CollapseRequest, LeafSearchResponse, PartialHit, SearchRequest, SortByValue, SortField,
+        SortOrder, SortValue, SplitSearchError,
     };
     use tantivy::collector::Collector;
     use tantivy::TantivyDocument;
@@ -1171,6 +1539,7 @@ mod tests {
             split_id: "split1".to_string(),
             segment_ord: 0u32,
             doc_id: 0u32,
+            collapse_key: None,
         };
         assert_eq!(
             top_k_partial_hits(
@@ -1191,6 +1560,7 @@ mod tests {
             split_id: format!("split_{split_id}"),
             segment_ord: 0u32,
             doc_id: 0u32,
+            collapse_key: None,
         };
         assert_eq!(
             &top_k_partial_hits(
@@ -1498,6 +1868,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_single_split_collapse() {
+        let index = make_index();
+
+        let reader = index.reader().unwrap();
+        let searcher = reader.searcher();
+
+        let mut request = make_request(10, "sort2");
+        request.collapse = Some(CollapseRequest {
+            field: "sort1".to_string(),
+            inner_hits: None,
+        });
+        let collector = super::make_collector_for_split(
+            "fake_split_id".to_string(),
+            &MockDocMapper,
+            &request,
+            Default::default(),
+        )
+        .unwrap();
+        let res = searcher
+            .search(&tantivy::query::AllQuery, &collector)
+            .unwrap();
+        assert_eq!(res.num_hits, 17);
+        let hits: Vec<(u32, Option<&str>)> = res
+            .partial_hits
+            .iter()
+            .map(|partial_hit| (partial_hit.doc_id, partial_hit.collapse_key.as_deref()))
+            .collect();
+        // The best hit of every group has `sort2 == 2`, ties are broken by descending doc id.
+        assert_eq!(
+            hits,
+            [(12, Some("0")), (11, Some("2")), (7, Some("1")), (5, None)]
+        );
+
+        request.max_hits = 2;
+        let collector = super::make_collector_for_split(
+            "fake_split_id".to_string(),
+            &MockDocMapper,
+            &request,
+            Default::default(),
+        )
+        .unwrap();
+        let res = searcher
+            .search(&tantivy::query::AllQuery, &collector)
+            .unwrap();
+        let hits: Vec<(u32, Option<&str>)> = res
+            .partial_hits
+            .iter()
+            .map(|partial_hit| (partial_hit.doc_id, partial_hit.collapse_key.as_deref()))
+            .collect();
+        assert_eq!(hits, [(12, Some("0")), (11, Some("2"))]);
+    }
+
     #[test]
     fn test_search_after() {
         let index = make_index();
@@ -1530,6 +1953,7 @@ mod tests {
                 sort_value2: Some(SortByValue {
                     sort_value: val2.map(SortValue::U64),
                 }),
+                collapse_key: None,
             })
             .collect::<Vec<_>>();
         // we eliminte based on sort value
@@ -1578,6 +2002,7 @@ mod tests {
                 doc_id: 5,
                 sort_value: None,
                 sort_value2: None,
+                collapse_key: None,
             };
             let request = SearchRequest {
                 max_hits: 1000,
@@ -1678,10 +2103,13 @@ mod tests {
                     doc_id: 123,
                     sort_value: Some(SortValue::I64(1234).into()),
                     sort_value2: None,
+                    collapse_key: None,
                 }],
                 failed_splits: Vec::new(),
                 num_attempted_splits: 3,
                 intermediate_aggregation_result: None,
+                split_profiles: Vec::new(),
+                storage_request_stats: None,
             }],
         );
 
@@ -1695,10 +2123,13 @@ mod tests {
                     doc_id: 123,
                     sort_value: Some(SortValue::I64(1234).into()),
                     sort_value2: None,
+                    collapse_key: None,
                 }],
                 failed_splits: Vec::new(),
                 num_attempted_splits: 3,
-                intermediate_aggregation_result: None
+                intermediate_aggregation_result: None,
+                split_profiles: Vec::new(),
+                storage_request_stats: None,
             }
         );
 
@@ -1724,6 +2155,7 @@ mod tests {
                             doc_id: 123,
                             sort_value: Some(SortValue::I64(1234).into()),
                             sort_value2: None,
+                            collapse_key: None,
                         },
                         PartialHit {
                             split_id: "1".to_string(),
@@ -1731,11 +2163,14 @@ mod tests {
                             doc_id: 125,
                             sort_value: Some(SortValue::I64(1236).into()),
                             sort_value2: None,
+                            collapse_key: None,
                         },
                     ],
                     failed_splits: Vec::new(),
                     num_attempted_splits: 3,
                     intermediate_aggregation_result: None,
+                    split_profiles: Vec::new(),
+                    storage_request_stats: None,
                 },
                 LeafSearchResponse {
                     num_hits: 10,
@@ -1745,6 +2180,7 @@ mod tests {
                         doc_id: 3,
                         sort_value: Some(SortValue::I64(1235).into()),
                         sort_value2: None,
+                        collapse_key: None,
                     }],
                     failed_splits: vec![SplitSearchError {
                         error: "fake error".to_string(),
@@ -1753,6 +2189,8 @@ mod tests {
                     }],
                     num_attempted_splits: 2,
                     intermediate_aggregation_result: None,
+                    split_profiles: Vec::new(),
+                    storage_request_stats: None,
                 },
             ],
         );
@@ -1768,6 +2206,7 @@ mod tests {
                         doc_id: 125,
                         sort_value: Some(SortValue::I64(1236).into()),
                         sort_value2: None,
+                        collapse_key: None,
                     },
                     PartialHit {
                         split_id: "2".to_string(),
@@ -1775,6 +2214,7 @@ mod tests {
                         doc_id: 3,
                         sort_value: Some(SortValue::I64(1235).into()),
                         sort_value2: None,
+                        collapse_key: None,
                     },
                 ],
                 failed_splits: vec![SplitSearchError {
@@ -1783,7 +2223,9 @@ mod tests {
                     retryable_error: true,
                 }],
                 num_attempted_splits: 5,
-                intermediate_aggregation_result: None
+                intermediate_aggregation_result: None,
+                split_profiles: Vec::new(),
+                storage_request_stats: None,
             }
         );
 
@@ -1810,6 +2252,7 @@ mod tests {
                             doc_id: 123,
                             sort_value: Some(SortValue::I64(1234).into()),
                             sort_value2: None,
+                            collapse_key: None,
                         },
                         PartialHit {
                             split_id: "1".to_string(),
@@ -1817,11 +2260,14 @@ mod tests {
                             doc_id: 125,
                             sort_value: Some(SortValue::I64(1236).into()),
                             sort_value2: None,
+                            collapse_key: None,
                         },
                     ],
                     failed_splits: Vec::new(),
                     num_attempted_splits: 3,
                     intermediate_aggregation_result: None,
+                    split_profiles: Vec::new(),
+                    storage_request_stats: None,
                 },
                 LeafSearchResponse {
                     num_hits: 10,
@@ -1831,6 +2277,7 @@ mod tests {
                         doc_id: 3,
                         sort_value: Some(SortValue::I64(1235).into()),
                         sort_value2: None,
+                        collapse_key: None,
                     }],
                     failed_splits: vec![SplitSearchError {
                         error: "fake error".to_string(),
@@ -1839,6 +2286,8 @@ mod tests {
                     }],
                     num_attempted_splits: 2,
                     intermediate_aggregation_result: None,
+                    split_profiles: Vec::new(),
+                    storage_request_stats: None,
                 },
             ],
         );
@@ -1854,6 +2303,7 @@ mod tests {
                         doc_id: 123,
                         sort_value: Some(SortValue::I64(1234).into()),
                         sort_value2: None,
+                        collapse_key: None,
                     },
                     PartialHit {
                         split_id: "2".to_string(),
@@ -1861,6 +2311,7 @@ mod tests {
                         doc_id: 3,
                         sort_value: Some(SortValue::I64(1235).into()),
                         sort_value2: None,
+                        collapse_key: None,
                     },
                 ],
                 failed_splits: vec![SplitSearchError {
@@ -1869,9 +2320,71 @@ mod tests {
                     retryable_error: true,
                 }],
                 num_attempted_splits: 5,
-                intermediate_aggregation_result: None
+                intermediate_aggregation_result: None,
+                split_profiles: Vec::new(),
+                storage_request_stats: None,
             }
         );
         // TODO would be nice to test aggregation too.
     }
+
+    #[test]
+    fn test_merge_collectors_collapse() {
+        let make_hit =
+            |split_id: &str, doc_id: u32, sort_value: i64, collapse_key: &str| PartialHit {
+                split_id: split_id.to_string(),
+                segment_ord: 0,
+                doc_id,
+                sort_value: Some(SortValue::I64(sort_value).into()),
+                sort_value2: None,
+                collapse_key: Some(collapse_key.to_string()),
+            };
+        let result = merge_collector_equal_results(
+            &SearchRequest {
+                max_hits: 2,
+                sort_fields: vec![SortField {
+                    field_name: "timestamp".to_string(),
+                    sort_order: SortOrder::Desc as i32,
+                    sort_datetime_format: None,
+                }],
+                collapse: Some(CollapseRequest {
+                    field: "service".to_string(),
+                    inner_hits: None,
+                }),
+                ..Default::default()
+            },
+            vec![
+                LeafSearchResponse {
+                    num_hits: 10,
+                    partial_hits: vec![make_hit("1", 1, 1237, "api"), make_hit("1", 2, 1234, "db")],
+                    failed_splits: Vec::new(),
+                    num_attempted_splits: 1,
+                    intermediate_aggregation_result: None,
+                    split_profiles: Vec::new(),
+                    storage_request_stats: None,
+                },
+                LeafSearchResponse {
+                    num_hits: 20,
+                    partial_hits: vec![
+                        make_hit("2", 1, 1236, "api"),
+                        make_hit("2", 2, 1235, "cache"),
+                        make_hit("2", 3, 1233, "db"),
+                    ],
+                    failed_splits: Vec::new(),
+                    num_attempted_splits: 1,
+                    intermediate_aggregation_result: None,
+                    split_profiles: Vec::new(),
+                    storage_request_stats: None,
+                },
+            ],
+        );
+        assert_eq!(result.num_hits, 30);
+        assert_eq!(
+            result.partial_hits,
+            vec![
+                make_hit("1", 1, 1237, "api"),
+                make_hit("2", 2, 1235, "cache")
+            ]
+        );
+    }
 }
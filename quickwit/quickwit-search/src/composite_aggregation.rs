@@ -0,0 +1,785 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::Context;
+use quickwit_proto::search::{Hit, SortOrder};
+use quickwit_query::OneFieldMap;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tantivy::columnar::{ColumnType, MonotonicallyMappableToU64, StrColumn};
+use tantivy::fastfield::Column;
+use tantivy::{DateTime, DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
+
+use crate::top_hits_aggregation::{
+    IntermediateTopHits, SegmentTopHits, TopHitsAggregation, TopHitsSegmentExtractor,
+};
+use crate::GlobalDocAddress;
+
+/// Maximum number of buckets a `composite` aggregation can return.
+const MAX_COMPOSITE_SIZE: usize = 10_000;
+
+fn default_composite_size() -> usize {
+    10
+}
+
+fn default_source_order() -> SortOrder {
+    SortOrder::Asc
+}
+
+/// The `composite` aggregation builds buckets out of the combination of the values of several
+/// sources, and returns them sorted by key. Buckets can be paginated through by passing the
+/// `after_key` of a response as the `after` parameter of the next request.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CompositeAggregation {
+    #[serde(default = "default_composite_size")]
+    pub size: usize,
+    pub sources: Vec<OneFieldMap<CompositeSource>>,
+    #[serde(default)]
+    pub after: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompositeSource {
+    Terms(TermsCompositeSource),
+    Histogram(HistogramCompositeSource),
+    DateHistogram(DateHistogramCompositeSource),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TermsCompositeSource {
+    pub field: String,
+    #[serde(default = "default_source_order")]
+    pub order: SortOrder,
+    #[serde(default)]
+    pub missing_bucket: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HistogramCompositeSource {
+    pub field: String,
+    pub interval: f64,
+    #[serde(default = "default_source_order")]
+    pub order: SortOrder,
+    #[serde(default)]
+    pub missing_bucket: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DateHistogramCompositeSource {
+    pub field: String,
+    /// Fixed interval, such as `30s`, `1h` or `1d`.
+    pub fixed_interval: String,
+    #[serde(default = "default_source_order")]
+    pub order: SortOrder,
+    #[serde(default)]
+    pub missing_bucket: bool,
+}
+
+impl CompositeSource {
+    fn field(&self) -> &str {
+        match self {
+            CompositeSource::Terms(source) => &source.field,
+            CompositeSource::Histogram(source) => &source.field,
+            CompositeSource::DateHistogram(source) => &source.field,
+        }
+    }
+
+    fn order(&self) -> SortOrder {
+        match self {
+            CompositeSource::Terms(source) => source.order,
+            CompositeSource::Histogram(source) => source.order,
+            CompositeSource::DateHistogram(source) => source.order,
+        }
+    }
+
+    fn missing_bucket(&self) -> bool {
+        match self {
+            CompositeSource::Terms(source) => source.missing_bucket,
+            CompositeSource::Histogram(source) => source.missing_bucket,
+            CompositeSource::DateHistogram(source) => source.missing_bucket,
+        }
+    }
+}
+
+/// Parses a fixed interval, such as `500ms`, `30s`, `1h` or `1d`, into nanoseconds.
+fn parse_fixed_interval_nanos(fixed_interval: &str) -> anyhow::Result<i64> {
+    let unit_start = fixed_interval
+        .find(|ch: char| !ch.is_ascii_digit())
+        .with_context(|| format!("fixed interval `{fixed_interval}` is missing a unit"))?;
+    let (value_str, unit) = fixed_interval.split_at(unit_start);
+    let value: i64 = value_str
+        .parse()
+        .with_context(|| format!("invalid fixed interval `{fixed_interval}`"))?;
+    let unit_nanos: i64 = match unit {
+        "ms" => 1_000_000,
+        "s" => 1_000_000_000,
+        "m" => 60 * 1_000_000_000,
+        "h" => 3_600 * 1_000_000_000,
+        "d" => 86_400 * 1_000_000_000,
+        _ => anyhow::bail!("unsupported unit `{unit}` in fixed interval `{fixed_interval}`"),
+    };
+    if value <= 0 {
+        anyhow::bail!("fixed interval `{fixed_interval}` must be strictly positive");
+    }
+    value
+        .checked_mul(unit_nanos)
+        .with_context(|| format!("fixed interval `{fixed_interval}` is too large"))
+}
+
+impl CompositeAggregation {
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        if self.size == 0 || self.size > MAX_COMPOSITE_SIZE {
+            anyhow::bail!(
+                "`size` of a `composite` aggregation must be between 1 and {MAX_COMPOSITE_SIZE}, \
+                 got {}",
+                self.size
+            );
+        }
+        if self.sources.is_empty() {
+            anyhow::bail!("`composite` aggregation must have at least one source");
+        }
+        for source in &self.sources {
+            match &source.value {
+                CompositeSource::Histogram(histogram) if histogram.interval <= 0.0 => {
+                    anyhow::bail!(
+                        "interval of histogram source `{}` must be strictly positive",
+                        source.field
+                    );
+                }
+                CompositeSource::DateHistogram(date_histogram) => {
+                    parse_fixed_interval_nanos(&date_histogram.fixed_interval)?;
+                }
+                _ => {}
+            }
+        }
+        if let Some(after) = &self.after {
+            for source in &self.sources {
+                if !after.contains_key(&source.field) {
+                    anyhow::bail!(
+                        "`after` key of `composite` aggregation is missing source `{}`",
+                        source.field
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn fast_field_names(&self) -> HashSet<String> {
+        self.sources
+            .iter()
+            .map(|source| source.value.field().to_string())
+            .collect()
+    }
+
+    fn source_orders(&self) -> Vec<SortOrder> {
+        self.sources
+            .iter()
+            .map(|source| source.value.order())
+            .collect()
+    }
+
+    /// Builds the final `composite` result, in the Elasticsearch format.
+    pub(crate) fn into_final_result(
+        &self,
+        intermediate_result: IntermediateCompositeResult,
+        sub_aggregations: &BTreeMap<String, TopHitsAggregation>,
+        docs: &HashMap<GlobalDocAddress, Hit>,
+    ) -> serde_json::Value {
+        let key_to_json = |key: &[Option<CompositeKeyValue>]| -> serde_json::Value {
+            let key_json: serde_json::Map<String, serde_json::Value> = self
+                .sources
+                .iter()
+                .zip(key)
+                .map(|(source, value_opt)| {
+                    let value_json = value_opt
+                        .as_ref()
+                        .map(CompositeKeyValue::to_json)
+                        .unwrap_or(serde_json::Value::Null);
+                    (source.field.clone(), value_json)
+                })
+                .collect();
+            serde_json::Value::Object(key_json)
+        };
+        let after_key_opt = intermediate_result
+            .buckets
+            .last()
+            .map(|bucket| key_to_json(&bucket.key));
+        let buckets: Vec<serde_json::Value> = intermediate_result
+            .buckets
+            .into_iter()
+            .map(|bucket| {
+                let mut bucket_json = serde_json::Map::new();
+                bucket_json.insert("key".to_string(), key_to_json(&bucket.key));
+                bucket_json.insert("doc_count".to_string(), json!(bucket.doc_count));
+                for ((name, top_hits), intermediate_top_hits) in
+                    sub_aggregations.iter().zip(bucket.top_hits)
+                {
+                    bucket_json.insert(
+                        name.clone(),
+                        top_hits.into_final_result(intermediate_top_hits, docs),
+                    );
+                }
+                serde_json::Value::Object(bucket_json)
+            })
+            .collect();
+        let mut result_json = serde_json::Map::new();
+        if let Some(after_key) = after_key_opt {
+            result_json.insert("after_key".to_string(), after_key);
+        }
+        result_json.insert("buckets".to_string(), serde_json::Value::Array(buckets));
+        serde_json::Value::Object(result_json)
+    }
+}
+
+/// Value of a composite source for a bucket, comparable across segments and splits.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) enum CompositeKeyValue {
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    /// Timestamp in nanoseconds.
+    DateTime(i64),
+    Str(String),
+}
+
+impl CompositeKeyValue {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            CompositeKeyValue::Bool(value) => json!(value),
+            CompositeKeyValue::I64(value) => json!(value),
+            CompositeKeyValue::U64(value) => json!(value),
+            CompositeKeyValue::F64(value) => json!(value),
+            // Like Elasticsearch, dates are returned as milliseconds timestamps.
+            CompositeKeyValue::DateTime(timestamp_nanos) => {
+                json!(timestamp_nanos.div_euclid(1_000_000))
+            }
+            CompositeKeyValue::Str(value) => json!(value),
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            CompositeKeyValue::Bool(value) => Some(*value as u64 as f64),
+            CompositeKeyValue::I64(value) | CompositeKeyValue::DateTime(value) => {
+                Some(*value as f64)
+            }
+            CompositeKeyValue::U64(value) => Some(*value as f64),
+            CompositeKeyValue::F64(value) => Some(*value),
+            CompositeKeyValue::Str(_) => None,
+        }
+    }
+
+    fn cmp_values(&self, other: &CompositeKeyValue) -> Ordering {
+        match (self, other) {
+            (CompositeKeyValue::Str(left), CompositeKeyValue::Str(right)) => left.cmp(right),
+            (CompositeKeyValue::Str(_), _) => Ordering::Greater,
+            (_, CompositeKeyValue::Str(_)) => Ordering::Less,
+            (CompositeKeyValue::I64(left), CompositeKeyValue::I64(right))
+            | (CompositeKeyValue::DateTime(left), CompositeKeyValue::DateTime(right)) => {
+                left.cmp(right)
+            }
+            (CompositeKeyValue::U64(left), CompositeKeyValue::U64(right)) => left.cmp(right),
+            (left, right) => {
+                let left = left.as_f64().unwrap_or_default();
+                let right = right.as_f64().unwrap_or_default();
+                left.total_cmp(&right)
+            }
+        }
+    }
+}
+
+/// Compares two composite keys. Missing values come first in ascending order, and last in
+/// descending order.
+fn cmp_composite_keys(
+    left: &[Option<CompositeKeyValue>],
+    right: &[Option<CompositeKeyValue>],
+    orders: &[SortOrder],
+) -> Ordering {
+    for ((left_value_opt, right_value_opt), order) in left.iter().zip(right).zip(orders) {
+        let ordering = match (left_value_opt, right_value_opt) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(left_value), Some(right_value)) => left_value.cmp_values(right_value),
+        };
+        let ordering = if *order == SortOrder::Desc {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct IntermediateCompositeBucket {
+    pub key: Vec<Option<CompositeKeyValue>>,
+    pub doc_count: u64,
+    /// One entry per `top_hits` sub-aggregation, ordered by sub-aggregation name.
+    pub top_hits: Vec<IntermediateTopHits>,
+}
+
+/// Smallest buckets of a composite aggregation, sorted by key.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct IntermediateCompositeResult {
+    pub buckets: Vec<IntermediateCompositeBucket>,
+}
+
+impl IntermediateCompositeResult {
+    pub(crate) fn merge(
+        &mut self,
+        other: IntermediateCompositeResult,
+        composite: &CompositeAggregation,
+        sub_aggregations: &BTreeMap<String, TopHitsAggregation>,
+    ) {
+        let orders = composite.source_orders();
+        let mut buckets = std::mem::take(&mut self.buckets);
+        buckets.extend(other.buckets);
+        buckets.sort_by(|left, right| cmp_composite_keys(&left.key, &right.key, &orders));
+
+        let mut merged_buckets: Vec<IntermediateCompositeBucket> =
+            Vec::with_capacity(composite.size);
+        for bucket in buckets {
+            if let Some(last_bucket) = merged_buckets.last_mut() {
+                if cmp_composite_keys(&last_bucket.key, &bucket.key, &orders) == Ordering::Equal {
+                    last_bucket.doc_count += bucket.doc_count;
+                    for ((last_top_hits, top_hits), top_hits_aggregation) in last_bucket
+                        .top_hits
+                        .iter_mut()
+                        .zip(bucket.top_hits)
+                        .zip(sub_aggregations.values())
+                    {
+                        last_top_hits.merge(top_hits, top_hits_aggregation);
+                    }
+                    continue;
+                }
+            }
+            if merged_buckets.len() == composite.size {
+                break;
+            }
+            merged_buckets.push(bucket);
+        }
+        self.buckets = merged_buckets;
+    }
+}
+
+/// Value of a source in a segment, encoded as a `u64` whose natural order follows the order of
+/// the values.
+///
+/// Text values are encoded as `2 * term_ord + 1`, which leaves room to encode an `after` value
+/// that is not in the segment term dictionary between two term ordinals.
+enum SegmentSourceColumn {
+    Str(StrColumn),
+    Numeric {
+        column: Column<u64>,
+        column_type: ColumnType,
+    },
+    Histogram {
+        column: Column<u64>,
+        column_type: ColumnType,
+        interval: f64,
+    },
+    DateHistogram {
+        column: Column<DateTime>,
+        interval_nanos: i64,
+    },
+    Missing,
+}
+
+fn numeric_to_f64(value: u64, column_type: ColumnType) -> f64 {
+    match column_type {
+        ColumnType::I64 | ColumnType::DateTime => i64::from_u64(value) as f64,
+        ColumnType::F64 => f64::from_u64(value),
+        _ => value as f64,
+    }
+}
+
+impl SegmentSourceColumn {
+    fn open(source: &CompositeSource, segment_reader: &SegmentReader) -> tantivy::Result<Self> {
+        let fast_fields = segment_reader.fast_fields();
+        let column = match source {
+            CompositeSource::Terms(terms) => {
+                if let Some(str_column) = fast_fields.str(&terms.field)? {
+                    SegmentSourceColumn::Str(str_column)
+                } else if let Some((column, column_type)) = fast_fields.u64_lenient(&terms.field)? {
+                    SegmentSourceColumn::Numeric {
+                        column,
+                        column_type,
+                    }
+                } else {
+                    SegmentSourceColumn::Missing
+                }
+            }
+            CompositeSource::Histogram(histogram) => {
+                if let Some((column, column_type)) = fast_fields.u64_lenient(&histogram.field)? {
+                    SegmentSourceColumn::Histogram {
+                        column,
+                        column_type,
+                        interval: histogram.interval,
+                    }
+                } else {
+                    SegmentSourceColumn::Missing
+                }
+            }
+            CompositeSource::DateHistogram(date_histogram) => {
+                let interval_nanos = parse_fixed_interval_nanos(&date_histogram.fixed_interval)
+                    .map_err(|error| TantivyError::InvalidArgument(error.to_string()))?;
+                match fast_fields.date(&date_histogram.field) {
+                    Ok(column) => SegmentSourceColumn::DateHistogram {
+                        column,
+                        interval_nanos,
+                    },
+                    Err(_) => SegmentSourceColumn::Missing,
+                }
+            }
+        };
+        Ok(column)
+    }
+
+    fn histogram_key(value: f64, interval: f64) -> f64 {
+        (value / interval).floor() * interval
+    }
+
+    fn value(&self, doc_id: DocId) -> Option<u64> {
+        match self {
+            SegmentSourceColumn::Str(str_column) => str_column
+                .term_ords(doc_id)
+                .next()
+                .map(|term_ord| 2 * term_ord + 1),
+            SegmentSourceColumn::Numeric { column, .. } => column.first(doc_id),
+            SegmentSourceColumn::Histogram {
+                column,
+                column_type,
+                interval,
+            } => column.first(doc_id).map(|value| {
+                let value = numeric_to_f64(value, *column_type);
+                Self::histogram_key(value, *interval).to_u64()
+            }),
+            SegmentSourceColumn::DateHistogram {
+                column,
+                interval_nanos,
+            } => column.first(doc_id).map(|timestamp| {
+                let timestamp_nanos = timestamp.into_timestamp_nanos();
+                (timestamp_nanos.div_euclid(*interval_nanos) * interval_nanos).to_u64()
+            }),
+            SegmentSourceColumn::Missing => None,
+        }
+    }
+
+    fn to_key_value(&self, value: u64) -> tantivy::Result<CompositeKeyValue> {
+        let key_value = match self {
+            SegmentSourceColumn::Str(str_column) => {
+                let mut buffer = String::new();
+                str_column.ord_to_str((value - 1) / 2, &mut buffer)?;
+                CompositeKeyValue::Str(buffer)
+            }
+            SegmentSourceColumn::Numeric {
+                column_type: ColumnType::I64,
+                ..
+            } => CompositeKeyValue::I64(i64::from_u64(value)),
+            SegmentSourceColumn::Numeric {
+                column_type: ColumnType::F64,
+                ..
+            } => CompositeKeyValue::F64(f64::from_u64(value)),
+            SegmentSourceColumn::Numeric {
+                column_type: ColumnType::Bool,
+                ..
+            } => CompositeKeyValue::Bool(value != 0),
+            SegmentSourceColumn::Numeric {
+                column_type: ColumnType::DateTime,
+                ..
+            } => CompositeKeyValue::DateTime(i64::from_u64(value)),
+            SegmentSourceColumn::Numeric { .. } => CompositeKeyValue::U64(value),
+            SegmentSourceColumn::Histogram { .. } => CompositeKeyValue::F64(f64::from_u64(value)),
+            SegmentSourceColumn::DateHistogram { .. } => {
+                CompositeKeyValue::DateTime(i64::from_u64(value))
+            }
+            SegmentSourceColumn::Missing => {
+                return Err(TantivyError::InternalError(
+                    "composite key value extracted from a missing column".to_string(),
+                ))
+            }
+        };
+        Ok(key_value)
+    }
+
+    /// Encodes a value of an `after` key. Text values are encoded between two term ordinals if
+    /// they are absent from the term dictionary of the segment.
+    fn encode_after_value(&self, after_value: &serde_json::Value) -> tantivy::Result<u64> {
+        let invalid_after_value = || {
+            TantivyError::InvalidArgument(format!(
+                "invalid value `{after_value}` in `after` key of `composite` aggregation"
+            ))
+        };
+        let encoded_value = match self {
+            SegmentSourceColumn::Str(str_column) => {
+                let after_str = after_value.as_str().ok_or_else(invalid_after_value)?;
+                // Binary search of the first term greater or equal to the after value.
+                let mut buffer = String::new();
+                let mut low = 0u64;
+                let mut high = str_column.num_terms() as u64;
+                while low < high {
+                    let mid = low + (high - low) / 2;
+                    buffer.clear();
+                    str_column.ord_to_str(mid, &mut buffer)?;
+                    if buffer.as_str() < after_str {
+                        low = mid + 1;
+                    } else {
+                        high = mid;
+                    }
+                }
+                buffer.clear();
+                let is_exact_match = low < str_column.num_terms() as u64
+                    && str_column.ord_to_str(low, &mut buffer)?
+                    && buffer == after_str;
+                if is_exact_match {
+                    2 * low + 1
+                } else {
+                    2 * low
+                }
+            }
+            SegmentSourceColumn::Numeric { column_type, .. } => match column_type {
+                ColumnType::I64 => after_value
+                    .as_i64()
+                    .ok_or_else(invalid_after_value)?
+                    .to_u64(),
+                ColumnType::F64 => after_value
+                    .as_f64()
+                    .ok_or_else(invalid_after_value)?
+                    .to_u64(),
+                ColumnType::Bool => after_value.as_bool().ok_or_else(invalid_after_value)? as u64,
+                ColumnType::DateTime => {
+                    let timestamp_millis = after_value.as_i64().ok_or_else(invalid_after_value)?;
+                    (timestamp_millis * 1_000_000).to_u64()
+                }
+                _ => after_value.as_u64().ok_or_else(invalid_after_value)?,
+            },
+            SegmentSourceColumn::Histogram { .. } => after_value
+                .as_f64()
+                .ok_or_else(invalid_after_value)?
+                .to_u64(),
+            SegmentSourceColumn::DateHistogram { .. } => {
+                let timestamp_millis = after_value.as_i64().ok_or_else(invalid_after_value)?;
+                (timestamp_millis * 1_000_000).to_u64()
+            }
+            // No document of the segment has a value for the source, so the after value is not
+            // compared to anything but itself.
+            SegmentSourceColumn::Missing => 0,
+        };
+        Ok(encoded_value)
+    }
+}
+
+struct SegmentCompositeSource {
+    column: SegmentSourceColumn,
+    order: SortOrder,
+    missing_bucket: bool,
+}
+
+/// Part of a segment composite key, encoded so that the natural order of the keys follows the
+/// requested order: missing values first in ascending order, and last in descending order.
+type SegmentKeyPart = (u8, u64);
+
+impl SegmentCompositeSource {
+    fn encode(&self, value_opt: Option<u64>) -> SegmentKeyPart {
+        match (self.order, value_opt) {
+            (SortOrder::Asc, None) => (0, 0),
+            (SortOrder::Asc, Some(value)) => (1, value),
+            (SortOrder::Desc, Some(value)) => (0, u64::MAX - value),
+            (SortOrder::Desc, None) => (1, 0),
+        }
+    }
+
+    fn decode(&self, key_part: SegmentKeyPart) -> Option<u64> {
+        match (self.order, key_part) {
+            (SortOrder::Asc, (0, _)) | (SortOrder::Desc, (1, _)) => None,
+            (SortOrder::Asc, (_, value)) => Some(value),
+            (SortOrder::Desc, (_, value)) => Some(u64::MAX - value),
+        }
+    }
+
+    fn key_part(&self, doc_id: DocId) -> Option<SegmentKeyPart> {
+        let value_opt = self.column.value(doc_id);
+        if value_opt.is_none() && !self.missing_bucket {
+            return None;
+        }
+        Some(self.encode(value_opt))
+    }
+}
+
+struct SegmentCompositeBucket {
+    doc_count: u64,
+    top_hits: Vec<SegmentTopHits>,
+}
+
+/// Collects the `size` smallest buckets of a segment greater than the `after` key.
+pub(crate) struct CompositeSegmentCollector {
+    sources: Vec<SegmentCompositeSource>,
+    size: usize,
+    after_key_opt: Option<Vec<SegmentKeyPart>>,
+    buckets: BTreeMap<Vec<SegmentKeyPart>, SegmentCompositeBucket>,
+    top_hits_extractors: Vec<TopHitsSegmentExtractor>,
+    split_id: String,
+    segment_ord: SegmentOrdinal,
+}
+
+impl CompositeSegmentCollector {
+    pub(crate) fn new(
+        composite: &CompositeAggregation,
+        sub_aggregations: &BTreeMap<String, TopHitsAggregation>,
+        split_id: String,
+        segment_ord: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> tantivy::Result<Self> {
+        composite
+            .validate()
+            .map_err(|error| TantivyError::InvalidArgument(error.to_string()))?;
+        let sources: Vec<SegmentCompositeSource> = composite
+            .sources
+            .iter()
+            .map(|source| {
+                Ok(SegmentCompositeSource {
+                    column: SegmentSourceColumn::open(&source.value, segment_reader)?,
+                    order: source.value.order(),
+                    missing_bucket: source.value.missing_bucket(),
+                })
+            })
+            .collect::<tantivy::Result<_>>()?;
+        let after_key_opt = composite
+            .after
+            .as_ref()
+            .map(|after| {
+                composite
+                    .sources
+                    .iter()
+                    .zip(&sources)
+                    .map(|(source, segment_source)| {
+                        // The presence of every source in the after key is checked by
+                        // `validate`.
+                        let after_value = &after[&source.field];
+                        if after_value.is_null() {
+                            return Ok(segment_source.encode(None));
+                        }
+                        let encoded_value =
+                            segment_source.column.encode_after_value(after_value)?;
+                        Ok(segment_source.encode(Some(encoded_value)))
+                    })
+                    .collect::<tantivy::Result<Vec<SegmentKeyPart>>>()
+            })
+            .transpose()?;
+        let top_hits_extractors = sub_aggregations
+            .values()
+            .map(|top_hits| TopHitsSegmentExtractor::new(top_hits, segment_reader))
+            .collect::<tantivy::Result<_>>()?;
+        Ok(CompositeSegmentCollector {
+            sources,
+            size: composite.size,
+            after_key_opt,
+            buckets: BTreeMap::new(),
+            top_hits_extractors,
+            split_id,
+            segment_ord,
+        })
+    }
+
+    pub(crate) fn collect(&mut self, doc_id: DocId, score: Score) {
+        let mut key: Vec<SegmentKeyPart> = Vec::with_capacity(self.sources.len());
+        for source in &self.sources {
+            let Some(key_part) = source.key_part(doc_id) else {
+                return;
+            };
+            key.push(key_part);
+        }
+        if let Some(after_key) = &self.after_key_opt {
+            if key <= *after_key {
+                return;
+            }
+        }
+        if self.buckets.len() >= self.size && !self.buckets.contains_key(&key) {
+            if let Some((last_key, _)) = self.buckets.last_key_value() {
+                if key > *last_key {
+                    return;
+                }
+            }
+        }
+        let bucket = self
+            .buckets
+            .entry(key)
+            .or_insert_with(|| SegmentCompositeBucket {
+                doc_count: 0,
+                top_hits: self
+                    .top_hits_extractors
+                    .iter()
+                    .map(TopHitsSegmentExtractor::new_top_hits)
+                    .collect(),
+            });
+        bucket.doc_count += 1;
+        for (top_hits_extractor, top_hits) in
+            self.top_hits_extractors.iter().zip(&mut bucket.top_hits)
+        {
+            top_hits_extractor.collect(top_hits, doc_id, score);
+        }
+        if self.buckets.len() > self.size {
+            self.buckets.pop_last();
+        }
+    }
+
+    pub(crate) fn harvest(self) -> tantivy::Result<IntermediateCompositeResult> {
+        let mut buckets = Vec::with_capacity(self.buckets.len());
+        for (segment_key, segment_bucket) in self.buckets {
+            let key = self
+                .sources
+                .iter()
+                .zip(segment_key)
+                .map(|(source, key_part)| {
+                    source
+                        .decode(key_part)
+                        .map(|value| source.column.to_key_value(value))
+                        .transpose()
+                })
+                .collect::<tantivy::Result<Vec<Option<CompositeKeyValue>>>>()?;
+            let top_hits = self
+                .top_hits_extractors
+                .iter()
+                .zip(segment_bucket.top_hits)
+                .map(|(top_hits_extractor, top_hits)| {
+                    top_hits_extractor.harvest(top_hits, &self.split_id, self.segment_ord)
+                })
+                .collect();
+            buckets.push(IntermediateCompositeBucket {
+                key,
+                doc_count: segment_bucket.doc_count,
+                top_hits,
+            });
+        }
+        Ok(IntermediateCompositeResult { buckets })
+    }
+}
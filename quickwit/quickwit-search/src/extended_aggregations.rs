@@ -0,0 +1,672 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use quickwit_proto::search::{Hit, PartialHit};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+use tantivy::aggregation::agg_req::{get_fast_field_names, Aggregations};
+use tantivy::aggregation::agg_result::AggregationResults;
+use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
+use tantivy::aggregation::{AggregationLimits, AggregationSegmentCollector};
+use tantivy::collector::SegmentCollector;
+use tantivy::{DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
+
+use crate::composite_aggregation::{
+    CompositeAggregation, CompositeSegmentCollector, IntermediateCompositeResult,
+};
+use crate::top_hits_aggregation::{
+    IntermediateTopHits, SegmentTopHits, TopHitsAggregation, TopHitsSegmentExtractor,
+};
+use crate::GlobalDocAddress;
+
+/// Aggregations containing at least one `top_hits` or `composite` aggregation at the top
+/// level, which are computed by Quickwit, alongside aggregations computed by tantivy.
+#[derive(Debug, Clone)]
+pub struct ExtendedAggregations {
+    pub(crate) tantivy_aggregations: Aggregations,
+    pub(crate) extended_aggregations: BTreeMap<String, ExtendedAggregation>,
+}
+
+fn is_extended_aggregation(aggregation_json: &serde_json::Value) -> bool {
+    let Some(aggregation_object) = aggregation_json.as_object() else {
+        return false;
+    };
+    aggregation_object.contains_key("top_hits") || aggregation_object.contains_key("composite")
+}
+
+impl<'de> Deserialize<'de> for ExtendedAggregations {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        let aggregations_json: serde_json::Map<String, serde_json::Value> =
+            Deserialize::deserialize(deserializer)?;
+        let mut tantivy_aggregations_json = serde_json::Map::new();
+        let mut extended_aggregations = BTreeMap::new();
+
+        for (aggregation_name, aggregation_json) in aggregations_json {
+            if is_extended_aggregation(&aggregation_json) {
+                let extended_aggregation: ExtendedAggregation =
+                    serde_json::from_value(aggregation_json).map_err(D::Error::custom)?;
+                extended_aggregations.insert(aggregation_name, extended_aggregation);
+            } else {
+                tantivy_aggregations_json.insert(aggregation_name, aggregation_json);
+            }
+        }
+        if extended_aggregations.is_empty() {
+            return Err(D::Error::custom(
+                "expected at least one `top_hits` or `composite` aggregation",
+            ));
+        }
+        let tantivy_aggregations: Aggregations =
+            serde_json::from_value(serde_json::Value::Object(tantivy_aggregations_json))
+                .map_err(D::Error::custom)?;
+        Ok(ExtendedAggregations {
+            tantivy_aggregations,
+            extended_aggregations,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExtendedAggregationEntry {
+    #[serde(default)]
+    top_hits: Option<TopHitsAggregation>,
+    #[serde(default)]
+    composite: Option<CompositeAggregation>,
+    #[serde(default, alias = "aggregations")]
+    aggs: Option<BTreeMap<String, ExtendedAggregationEntry>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "ExtendedAggregationEntry")]
+pub(crate) enum ExtendedAggregation {
+    TopHits(TopHitsAggregation),
+    /// `composite` aggregations only support `top_hits` sub-aggregations.
+    Composite {
+        composite: CompositeAggregation,
+        sub_aggregations: BTreeMap<String, TopHitsAggregation>,
+    },
+}
+
+impl TryFrom<ExtendedAggregationEntry> for ExtendedAggregation {
+    type Error = String;
+
+    fn try_from(entry: ExtendedAggregationEntry) -> Result<Self, Self::Error> {
+        match (entry.top_hits, entry.composite, entry.aggs) {
+            (Some(top_hits), None, None) => {
+                top_hits.validate().map_err(|error| error.to_string())?;
+                Ok(ExtendedAggregation::TopHits(top_hits))
+            }
+            (Some(_), None, Some(_)) => {
+                Err("`top_hits` aggregations do not support sub-aggregations".to_string())
+            }
+            (None, Some(composite), sub_aggregations_opt) => {
+                composite.validate().map_err(|error| error.to_string())?;
+                let mut sub_aggregations = BTreeMap::new();
+                for (sub_aggregation_name, sub_aggregation_entry) in
+                    sub_aggregations_opt.unwrap_or_default()
+                {
+                    match ExtendedAggregation::try_from(sub_aggregation_entry)? {
+                        ExtendedAggregation::TopHits(top_hits) => {
+                            sub_aggregations.insert(sub_aggregation_name, top_hits);
+                        }
+                        ExtendedAggregation::Composite { .. } => {
+                            return Err("`composite` aggregations only support `top_hits` \
+                                        sub-aggregations"
+                                .to_string());
+                        }
+                    }
+                }
+                Ok(ExtendedAggregation::Composite {
+                    composite,
+                    sub_aggregations,
+                })
+            }
+            (Some(_), Some(_), _) => {
+                Err("an aggregation cannot be both `top_hits` and `composite`".to_string())
+            }
+            (None, None, _) => Err("expected a `top_hits` or `composite` aggregation".to_string()),
+        }
+    }
+}
+
+impl ExtendedAggregations {
+    pub(crate) fn fast_field_names(&self) -> HashSet<String> {
+        let mut fast_field_names = get_fast_field_names(&self.tantivy_aggregations);
+        for extended_aggregation in self.extended_aggregations.values() {
+            match extended_aggregation {
+                ExtendedAggregation::TopHits(top_hits) => {
+                    fast_field_names.extend(top_hits.fast_field_names());
+                }
+                ExtendedAggregation::Composite {
+                    composite,
+                    sub_aggregations,
+                } => {
+                    fast_field_names.extend(composite.fast_field_names());
+                    for top_hits in sub_aggregations.values() {
+                        fast_field_names.extend(top_hits.fast_field_names());
+                    }
+                }
+            }
+        }
+        fast_field_names
+    }
+
+    /// Builds the final aggregation results, in the Elasticsearch format. `docs` holds the
+    /// documents referenced by the `top_hits` aggregations, fetched during the fetch docs phase.
+    pub(crate) fn into_final_result(
+        self,
+        intermediate_results: IntermediateExtendedAggregationResults,
+        limits: &AggregationLimits,
+        docs: &HashMap<GlobalDocAddress, Hit>,
+    ) -> tantivy::Result<serde_json::Value> {
+        let mut final_results = serde_json::Map::new();
+        if !self.tantivy_aggregations.is_empty() {
+            let tantivy_final_results: AggregationResults = intermediate_results
+                .tantivy_aggregation_results
+                .unwrap_or_default()
+                .into_final_result(self.tantivy_aggregations, limits)?;
+            let tantivy_final_results_json = serde_json::to_value(tantivy_final_results)
+                .map_err(|error| TantivyError::InternalError(error.to_string()))?;
+            if let serde_json::Value::Object(tantivy_final_results_json) =
+                tantivy_final_results_json
+            {
+                final_results.extend(tantivy_final_results_json);
+            }
+        }
+        let mut extended_aggregation_results = intermediate_results.extended_aggregation_results;
+        for (aggregation_name, extended_aggregation) in &self.extended_aggregations {
+            let intermediate_result_opt = extended_aggregation_results.remove(aggregation_name);
+            let final_result = match (extended_aggregation, intermediate_result_opt) {
+                (
+                    ExtendedAggregation::TopHits(top_hits),
+                    Some(IntermediateExtendedAggregationResult::TopHits(intermediate_top_hits)),
+                ) => top_hits.into_final_result(intermediate_top_hits, docs),
+                (ExtendedAggregation::TopHits(top_hits), _) => {
+                    top_hits.into_final_result(IntermediateTopHits::default(), docs)
+                }
+                (
+                    ExtendedAggregation::Composite {
+                        composite,
+                        sub_aggregations,
+                    },
+                    Some(IntermediateExtendedAggregationResult::Composite(intermediate_composite)),
+                ) => composite.into_final_result(intermediate_composite, sub_aggregations, docs),
+                (
+                    ExtendedAggregation::Composite {
+                        composite,
+                        sub_aggregations,
+                    },
+                    _,
+                ) => composite.into_final_result(
+                    IntermediateCompositeResult::default(),
+                    sub_aggregations,
+                    docs,
+                ),
+            };
+            final_results.insert(aggregation_name.clone(), final_result);
+        }
+        Ok(serde_json::Value::Object(final_results))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) enum IntermediateExtendedAggregationResult {
+    TopHits(IntermediateTopHits),
+    Composite(IntermediateCompositeResult),
+}
+
+/// Intermediate results of [`ExtendedAggregations`], mergeable across segments and splits.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct IntermediateExtendedAggregationResults {
+    pub tantivy_aggregation_results: Option<IntermediateAggregationResults>,
+    pub extended_aggregation_results: BTreeMap<String, IntermediateExtendedAggregationResult>,
+}
+
+impl IntermediateExtendedAggregationResults {
+    pub(crate) fn merge(
+        &mut self,
+        other: IntermediateExtendedAggregationResults,
+        aggregations: &ExtendedAggregations,
+    ) -> tantivy::Result<()> {
+        match (
+            &mut self.tantivy_aggregation_results,
+            other.tantivy_aggregation_results,
+        ) {
+            (Some(tantivy_aggregation_results), Some(other_tantivy_aggregation_results)) => {
+                tantivy_aggregation_results.merge_fruits(other_tantivy_aggregation_results)?;
+            }
+            (tantivy_aggregation_results @ None, other_tantivy_aggregation_results) => {
+                *tantivy_aggregation_results = other_tantivy_aggregation_results;
+            }
+            (Some(_), None) => {}
+        }
+        for (aggregation_name, other_result) in other.extended_aggregation_results {
+            let Some(extended_aggregation) =
+                aggregations.extended_aggregations.get(&aggregation_name)
+            else {
+                continue;
+            };
+            let Some(result) = self.extended_aggregation_results.get_mut(&aggregation_name) else {
+                self.extended_aggregation_results
+                    .insert(aggregation_name, other_result);
+                continue;
+            };
+            match (extended_aggregation, result, other_result) {
+                (
+                    ExtendedAggregation::TopHits(top_hits),
+                    IntermediateExtendedAggregationResult::TopHits(top_hits_result),
+                    IntermediateExtendedAggregationResult::TopHits(other_top_hits_result),
+                ) => top_hits_result.merge(other_top_hits_result, top_hits),
+                (
+                    ExtendedAggregation::Composite {
+                        composite,
+                        sub_aggregations,
+                    },
+                    IntermediateExtendedAggregationResult::Composite(composite_result),
+                    IntermediateExtendedAggregationResult::Composite(other_composite_result),
+                ) => composite_result.merge(other_composite_result, composite, sub_aggregations),
+                _ => {
+                    return Err(TantivyError::InternalError(format!(
+                        "mismatching intermediate results for aggregation `{aggregation_name}`"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the hits of all the `top_hits` aggregations, whose documents need to be fetched.
+    pub(crate) fn partial_hits(&self) -> Vec<PartialHit> {
+        let mut partial_hits = Vec::new();
+        for result in self.extended_aggregation_results.values() {
+            match result {
+                IntermediateExtendedAggregationResult::TopHits(top_hits) => {
+                    partial_hits.extend(top_hits.hits.iter().cloned());
+                }
+                IntermediateExtendedAggregationResult::Composite(composite) => {
+                    for bucket in &composite.buckets {
+                        for top_hits in &bucket.top_hits {
+                            partial_hits.extend(top_hits.hits.iter().cloned());
+                        }
+                    }
+                }
+            }
+        }
+        partial_hits
+    }
+}
+
+enum ExtendedAggregationSegmentCollector {
+    TopHits {
+        extractor: TopHitsSegmentExtractor,
+        top_hits: SegmentTopHits,
+    },
+    Composite(Box<CompositeSegmentCollector>),
+}
+
+/// Segment collector for [`ExtendedAggregations`].
+pub(crate) struct ExtendedAggregationsSegmentCollector {
+    tantivy_collector_opt: Option<AggregationSegmentCollector>,
+    extended_collectors: Vec<(String, ExtendedAggregationSegmentCollector)>,
+    split_id: String,
+    segment_ord: SegmentOrdinal,
+}
+
+impl ExtendedAggregationsSegmentCollector {
+    pub(crate) fn new(
+        aggregations: &ExtendedAggregations,
+        split_id: &str,
+        segment_ord: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+        limits: &AggregationLimits,
+    ) -> tantivy::Result<Self> {
+        let tantivy_collector_opt = if aggregations.tantivy_aggregations.is_empty() {
+            None
+        } else {
+            Some(AggregationSegmentCollector::from_agg_req_and_reader(
+                &aggregations.tantivy_aggregations,
+                segment_reader,
+                limits,
+            )?)
+        };
+        let mut extended_collectors = Vec::with_capacity(aggregations.extended_aggregations.len());
+        for (aggregation_name, extended_aggregation) in &aggregations.extended_aggregations {
+            let extended_collector = match extended_aggregation {
+                ExtendedAggregation::TopHits(top_hits) => {
+                    let extractor = TopHitsSegmentExtractor::new(top_hits, segment_reader)?;
+                    let top_hits = extractor.new_top_hits();
+                    ExtendedAggregationSegmentCollector::TopHits {
+                        extractor,
+                        top_hits,
+                    }
+                }
+                ExtendedAggregation::Composite {
+                    composite,
+                    sub_aggregations,
+                } => ExtendedAggregationSegmentCollector::Composite(Box::new(
+                    CompositeSegmentCollector::new(
+                        composite,
+                        sub_aggregations,
+                        split_id.to_string(),
+                        segment_ord,
+                        segment_reader,
+                    )?,
+                )),
+            };
+            extended_collectors.push((aggregation_name.clone(), extended_collector));
+        }
+        Ok(ExtendedAggregationsSegmentCollector {
+            tantivy_collector_opt,
+            extended_collectors,
+            split_id: split_id.to_string(),
+            segment_ord,
+        })
+    }
+
+    pub(crate) fn collect(&mut self, doc_id: DocId, score: Score) {
+        if let Some(tantivy_collector) = &mut self.tantivy_collector_opt {
+            tantivy_collector.collect(doc_id, score);
+        }
+        for (_, extended_collector) in &mut self.extended_collectors {
+            match extended_collector {
+                ExtendedAggregationSegmentCollector::TopHits {
+                    extractor,
+                    top_hits,
+                } => extractor.collect(top_hits, doc_id, score),
+                ExtendedAggregationSegmentCollector::Composite(composite_collector) => {
+                    composite_collector.collect(doc_id, score)
+                }
+            }
+        }
+    }
+
+    pub(crate) fn harvest(self) -> tantivy::Result<IntermediateExtendedAggregationResults> {
+        let tantivy_aggregation_results = self
+            .tantivy_collector_opt
+            .map(|tantivy_collector| tantivy_collector.harvest())
+            .transpose()?;
+        let mut extended_aggregation_results = BTreeMap::new();
+        for (aggregation_name, extended_collector) in self.extended_collectors {
+            let result = match extended_collector {
+                ExtendedAggregationSegmentCollector::TopHits {
+                    extractor,
+                    top_hits,
+                } => IntermediateExtendedAggregationResult::TopHits(extractor.harvest(
+                    top_hits,
+                    &self.split_id,
+                    self.segment_ord,
+                )),
+                ExtendedAggregationSegmentCollector::Composite(composite_collector) => {
+                    IntermediateExtendedAggregationResult::Composite(composite_collector.harvest()?)
+                }
+            };
+            extended_aggregation_results.insert(aggregation_name, result);
+        }
+        Ok(IntermediateExtendedAggregationResults {
+            tantivy_aggregation_results,
+            extended_aggregation_results,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::search::{SortByValue, SortValue};
+
+    use super::*;
+    use crate::composite_aggregation::{CompositeKeyValue, IntermediateCompositeBucket};
+
+    fn partial_hit(split_id: &str, doc_id: u32, sort_value: i64) -> PartialHit {
+        PartialHit {
+            sort_value: Some(SortByValue {
+                sort_value: Some(SortValue::I64(sort_value)),
+            }),
+            sort_value2: None,
+            split_id: split_id.to_string(),
+            segment_ord: 0,
+            doc_id,
+            collapse_key: None,
+        }
+    }
+
+    #[test]
+    fn test_extended_aggregations_deserialization() {
+        let aggregations_json = r#"{
+            "latest": {"top_hits": {"size": 2, "sort": [{"timestamp": "desc"}]}},
+            "by_host": {
+                "composite": {
+                    "size": 5,
+                    "sources": [
+                        {"host": {"terms": {"field": "host"}}},
+                        {"hour": {"date_histogram": {"field": "timestamp", "fixed_interval": "1h", "order": "desc"}}}
+                    ],
+                    "after": {"host": "host-1", "hour": 1704067200000}
+                },
+                "aggs": {"last_log": {"top_hits": {"size": 1}}}
+            },
+            "avg_latency": {"avg": {"field": "latency"}}
+        }"#;
+        let aggregations: ExtendedAggregations = serde_json::from_str(aggregations_json).unwrap();
+        assert_eq!(aggregations.tantivy_aggregations.len(), 1);
+        assert!(aggregations
+            .tantivy_aggregations
+            .contains_key("avg_latency"));
+        assert_eq!(aggregations.extended_aggregations.len(), 2);
+        let ExtendedAggregation::TopHits(top_hits) = &aggregations.extended_aggregations["latest"]
+        else {
+            panic!("expected a top_hits aggregation");
+        };
+        assert_eq!(top_hits.size, 2);
+        let ExtendedAggregation::Composite {
+            composite,
+            sub_aggregations,
+        } = &aggregations.extended_aggregations["by_host"]
+        else {
+            panic!("expected a composite aggregation");
+        };
+        assert_eq!(composite.size, 5);
+        assert_eq!(composite.sources.len(), 2);
+        assert_eq!(sub_aggregations.len(), 1);
+        assert_eq!(sub_aggregations["last_log"].size, 1);
+        let expected_fast_field_names: HashSet<String> = ["host", "latency", "timestamp"]
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(aggregations.fast_field_names(), expected_fast_field_names);
+    }
+
+    #[test]
+    fn test_extended_aggregations_deserialization_errors() {
+        // Plain tantivy aggregations are not extended aggregations.
+        serde_json::from_str::<ExtendedAggregations>(r#"{"avg": {"avg": {"field": "latency"}}}"#)
+            .unwrap_err();
+        serde_json::from_str::<ExtendedAggregations>(r#"{"latest": {"top_hits": {"size": 101}}}"#)
+            .unwrap_err();
+        serde_json::from_str::<ExtendedAggregations>(
+            r#"{"latest": {"top_hits": {"sort": [{"_score": "desc"}]}}}"#,
+        )
+        .unwrap_err();
+        serde_json::from_str::<ExtendedAggregations>(
+            r#"{"by_host": {"composite": {"sources": [{"host": {"terms": {"field": "host"}}}]}, "aggs": {"avg": {"avg": {"field": "latency"}}}}}"#,
+        )
+        .unwrap_err();
+        serde_json::from_str::<ExtendedAggregations>(
+            r#"{"by_hour": {"composite": {"sources": [{"hour": {"date_histogram": {"field": "timestamp", "fixed_interval": "1w"}}}]}}}"#,
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn test_intermediate_extended_aggregation_results_merge() {
+        let aggregations: ExtendedAggregations = serde_json::from_str(
+            r#"{
+                "latest": {"top_hits": {"size": 2, "sort": [{"timestamp": "desc"}]}},
+                "by_host": {
+                    "composite": {"size": 2, "sources": [{"host": {"terms": {"field": "host"}}}]},
+                    "aggs": {"last_log": {"top_hits": {"size": 1, "sort": [{"timestamp": "desc"}]}}}
+                }
+            }"#,
+        )
+        .unwrap();
+        let bucket =
+            |host: &str, doc_count: u64, hits: Vec<PartialHit>| IntermediateCompositeBucket {
+                key: vec![Some(CompositeKeyValue::Str(host.to_string()))],
+                doc_count,
+                top_hits: vec![IntermediateTopHits {
+                    num_hits: doc_count,
+                    hits,
+                }],
+            };
+        let mut results = IntermediateExtendedAggregationResults {
+            tantivy_aggregation_results: None,
+            extended_aggregation_results: BTreeMap::from([
+                (
+                    "latest".to_string(),
+                    IntermediateExtendedAggregationResult::TopHits(IntermediateTopHits {
+                        num_hits: 3,
+                        hits: vec![partial_hit("split1", 1, 30), partial_hit("split1", 2, 10)],
+                    }),
+                ),
+                (
+                    "by_host".to_string(),
+                    IntermediateExtendedAggregationResult::Composite(IntermediateCompositeResult {
+                        buckets: vec![
+                            bucket("host-a", 2, vec![partial_hit("split1", 1, 30)]),
+                            bucket("host-c", 1, vec![partial_hit("split1", 2, 10)]),
+                        ],
+                    }),
+                ),
+            ]),
+        };
+        let other_results = IntermediateExtendedAggregationResults {
+            tantivy_aggregation_results: None,
+            extended_aggregation_results: BTreeMap::from([
+                (
+                    "latest".to_string(),
+                    IntermediateExtendedAggregationResult::TopHits(IntermediateTopHits {
+                        num_hits: 1,
+                        hits: vec![partial_hit("split2", 1, 20)],
+                    }),
+                ),
+                (
+                    "by_host".to_string(),
+                    IntermediateExtendedAggregationResult::Composite(IntermediateCompositeResult {
+                        buckets: vec![
+                            bucket("host-a", 1, vec![partial_hit("split2", 1, 40)]),
+                            bucket("host-b", 1, vec![partial_hit("split2", 2, 20)]),
+                        ],
+                    }),
+                ),
+            ]),
+        };
+        results.merge(other_results, &aggregations).unwrap();
+
+        let IntermediateExtendedAggregationResult::TopHits(top_hits) =
+            &results.extended_aggregation_results["latest"]
+        else {
+            panic!("expected top_hits results");
+        };
+        assert_eq!(top_hits.num_hits, 4);
+        assert_eq!(
+            top_hits.hits,
+            vec![partial_hit("split1", 1, 30), partial_hit("split2", 1, 20)]
+        );
+        let IntermediateExtendedAggregationResult::Composite(composite) =
+            &results.extended_aggregation_results["by_host"]
+        else {
+            panic!("expected composite results");
+        };
+        assert_eq!(
+            composite.buckets,
+            vec![
+                bucket("host-a", 3, vec![partial_hit("split2", 1, 40)]),
+                bucket("host-b", 1, vec![partial_hit("split2", 2, 20)]),
+            ]
+        );
+        assert_eq!(results.partial_hits().len(), 4);
+    }
+
+    #[test]
+    fn test_extended_aggregations_final_result() {
+        let aggregations: ExtendedAggregations = serde_json::from_str(
+            r#"{
+                "by_host": {
+                    "composite": {"sources": [{"host": {"terms": {"field": "host"}}}, {"status": {"terms": {"field": "status", "missing_bucket": true}}}]},
+                    "aggs": {"last_log": {"top_hits": {"size": 1}}}
+                }
+            }"#,
+        )
+        .unwrap();
+        let hit = partial_hit("split1", 1, 30);
+        let results = IntermediateExtendedAggregationResults {
+            tantivy_aggregation_results: None,
+            extended_aggregation_results: BTreeMap::from([(
+                "by_host".to_string(),
+                IntermediateExtendedAggregationResult::Composite(IntermediateCompositeResult {
+                    buckets: vec![IntermediateCompositeBucket {
+                        key: vec![Some(CompositeKeyValue::Str("host-a".to_string())), None],
+                        doc_count: 2,
+                        top_hits: vec![IntermediateTopHits {
+                            num_hits: 2,
+                            hits: vec![hit.clone()],
+                        }],
+                    }],
+                }),
+            )]),
+        };
+        let docs = HashMap::from([(
+            GlobalDocAddress::from_partial_hit(&hit),
+            Hit {
+                json: r#"{"host": "host-a"}"#.to_string(),
+                partial_hit: Some(hit),
+                snippet: None,
+                index_id: "my-index".to_string(),
+                inner_hits: Vec::new(),
+            },
+        )]);
+        let final_result = aggregations
+            .into_final_result(results, &AggregationLimits::default(), &docs)
+            .unwrap();
+        assert_eq!(
+            final_result,
+            serde_json::json!({
+                "by_host": {
+                    "after_key": {"host": "host-a", "status": null},
+                    "buckets": [{
+                        "key": {"host": "host-a", "status": null},
+                        "doc_count": 2,
+                        "last_log": {
+                            "hits": {
+                                "total": {"value": 2, "relation": "eq"},
+                                "hits": [{
+                                    "_index": "my-index",
+                                    "_id": "",
+                                    "_source": {"host": "host-a"},
+                                    "sort": [30],
+                                }]
+                            }
+                        }
+                    }]
+                }
+            })
+        );
+    }
+}
@@ -27,6 +27,7 @@ use quickwit_doc_mapper::DocMapper;
 use quickwit_proto::search::{
     FetchDocsResponse, PartialHit, SnippetRequest, SplitIdAndFooterOffsets,
 };
+use quickwit_query::query_ast::QueryAst;
 use quickwit_storage::Storage;
 use tantivy::query::Query;
 use tantivy::schema::{Document as DocumentTrait, Field, OwnedValue, TantivyDocument, Value};
@@ -34,6 +35,7 @@ use tantivy::{ReloadPolicy, Score, Searcher, SnippetGenerator, Term};
 use tracing::{error, Instrument};
 
 use crate::leaf::open_index_with_caches;
+use crate::runtime_fields::{split_runtime_query_ast, RuntimeFieldsEvaluator, RuntimeMappings};
 use crate::service::SearcherContext;
 use crate::{convert_document_to_json_string, GlobalDocAddress};
 
@@ -48,6 +50,7 @@ async fn fetch_docs_to_map(
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<dyn DocMapper>,
     snippet_request_opt: Option<&SnippetRequest>,
+    runtime_mappings: &RuntimeMappings,
 ) -> anyhow::Result<HashMap<GlobalDocAddress, Document>> {
     let mut split_fetch_docs_futures = Vec::new();
 
@@ -75,6 +78,7 @@ async fn fetch_docs_to_map(
             split_and_offset,
             doc_mapper.clone(),
             snippet_request_opt,
+            runtime_mappings,
         ));
     }
 
@@ -107,7 +111,8 @@ async fn fetch_docs_to_map(
 ///
 /// This function takes a list of partial hits (possibly from different splits)
 /// and the storage associated to an index, fetches the document from
-/// the split document stores, and returns the full hits.
+/// the split document stores, and returns the full hits, including the values
+/// of the runtime fields.
 pub async fn fetch_docs(
     searcher_context: Arc<SearcherContext>,
     partial_hits: Vec<PartialHit>,
@@ -115,6 +120,7 @@ pub async fn fetch_docs(
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<dyn DocMapper>,
     snippet_request_opt: Option<&SnippetRequest>,
+    runtime_mappings: &RuntimeMappings,
 ) -> anyhow::Result<FetchDocsResponse> {
     let global_doc_addrs: Vec<GlobalDocAddress> = partial_hits
         .iter()
@@ -128,6 +134,7 @@ pub async fn fetch_docs(
         splits,
         doc_mapper,
         snippet_request_opt,
+        runtime_mappings,
     )
     .await?;
 
@@ -147,7 +154,10 @@ pub async fn fetch_docs(
             }
         })
         .collect();
-    Ok(FetchDocsResponse { hits })
+    Ok(FetchDocsResponse {
+        hits,
+        storage_request_stats: None,
+    })
 }
 
 // number of concurrent fetch allowed for a single split.
@@ -168,16 +178,19 @@ async fn fetch_docs_in_split(
     split: &SplitIdAndFooterOffsets,
     doc_mapper: Arc<dyn DocMapper>,
     snippet_request_opt: Option<&SnippetRequest>,
+    runtime_mappings: &RuntimeMappings,
 ) -> anyhow::Result<Vec<(GlobalDocAddress, Document)>> {
     global_doc_addrs.sort_by_key(|doc| doc.doc_addr);
     // Opens the index without the ephemeral unbounded cache, this cache is indeed not useful
-    // when fetching docs as we will fetch them only once.
+    // when fetching docs as we will fetch them only once. Runtime fields however need it to read
+    // the fast fields they are evaluated on, once warmed up.
     let index = open_index_with_caches(
         &searcher_context,
         index_storage,
         split,
         Some(doc_mapper.tokenizer_manager()),
-        false,
+        !runtime_mappings.is_empty(),
+        None,
     )
     .await
     .context("open-index-for-split")?;
@@ -189,15 +202,31 @@ async fn fetch_docs_in_split(
         .try_into()?;
     let searcher = Arc::new(index_reader.searcher());
     let fields_snippet_generator_opt = if let Some(snippet_request) = snippet_request_opt {
-        Some(create_fields_snippet_generator(&searcher, doc_mapper.clone(), snippet_request).await?)
+        Some(
+            create_fields_snippet_generator(
+                &searcher,
+                doc_mapper.clone(),
+                snippet_request,
+                runtime_mappings,
+            )
+            .await?,
+        )
     } else {
         None
     };
+    let runtime_fields_evaluator_opt = if runtime_mappings.is_empty() {
+        None
+    } else {
+        Some(Arc::new(
+            RuntimeFieldsEvaluator::for_split(runtime_mappings, &searcher).await?,
+        ))
+    };
 
     let doc_futures = global_doc_addrs.into_iter().map(|global_doc_addr| {
         let moved_searcher = searcher.clone();
         let moved_doc_mapper = doc_mapper.clone();
         let fields_snippet_generator_opt_clone = fields_snippet_generator_opt.clone();
+        let runtime_fields_evaluator_opt_clone = runtime_fields_evaluator_opt.clone();
         async move {
             let doc: TantivyDocument = moved_searcher
                 .doc_async(global_doc_addr.doc_addr)
@@ -206,7 +235,17 @@ async fn fetch_docs_in_split(
 
             let named_field_doc = doc.to_named_doc(moved_searcher.schema());
             let content_json =
-                convert_document_to_json_string(named_field_doc, &*moved_doc_mapper)?;
+                if let Some(runtime_fields_evaluator) = &runtime_fields_evaluator_opt_clone {
+                    let mut doc_json_map = moved_doc_mapper.doc_to_json(named_field_doc.0)?;
+                    runtime_fields_evaluator.add_runtime_fields(
+                        &mut doc_json_map,
+                        global_doc_addr.doc_addr,
+                        &moved_searcher,
+                    )?;
+                    serde_json::to_string(&doc_json_map)?
+                } else {
+                    convert_document_to_json_string(named_field_doc, &*moved_doc_mapper)?
+                };
             if fields_snippet_generator_opt_clone.is_none() {
                 return Ok((
                     global_doc_addr,
@@ -298,10 +337,14 @@ async fn create_fields_snippet_generator(
     searcher: &Searcher,
     doc_mapper: Arc<dyn DocMapper>,
     snippet_request: &SnippetRequest,
+    runtime_mappings: &RuntimeMappings,
 ) -> anyhow::Result<FieldsSnippetGenerator> {
     let schema = searcher.schema();
-    let query_ast_resolved = serde_json::from_str(&snippet_request.query_ast_resolved)
+    let query_ast_resolved: QueryAst = serde_json::from_str(&snippet_request.query_ast_resolved)
         .context("failed to deserialize QueryAst")?;
+    // Runtime fields are not part of the split, so only the indexed part of the query is
+    // highlighted.
+    let (query_ast_resolved, _) = split_runtime_query_ast(query_ast_resolved, runtime_mappings)?;
     let (query, _) = doc_mapper.query(schema.clone(), &query_ast_resolved, false)?;
     let mut snippet_generators = HashMap::new();
     for field_name in &snippet_request.snippet_fields {
@@ -17,9 +17,12 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
 use futures::future::try_join_all;
@@ -28,25 +31,29 @@ use quickwit_directories::{CachingDirectory, HotDirectory, StorageDirectory};
 use quickwit_doc_mapper::{DocMapper, TermRange, WarmupInfo};
 use quickwit_proto::search::{
     CountHits, LeafSearchResponse, PartialHit, SearchRequest, SortOrder, SortValue,
-    SplitIdAndFooterOffsets, SplitSearchError,
+    SplitIdAndFooterOffsets, SplitSearchError, SplitSearchProfile,
 };
 use quickwit_query::query_ast::QueryAst;
 use quickwit_query::tokenizers::TokenizerManager;
 use quickwit_storage::{
-    wrap_storage_with_cache, BundleStorage, MemorySizedCache, OwnedBytes, SplitCache, Storage,
+    wrap_storage_with_cache, wrap_storage_with_request_counter, BundleStorage, MemorySizedCache,
+    OwnedBytes, SplitCache, Storage, StorageRequestCounter,
 };
+use serde_json::Value as JsonValue;
 use tantivy::directory::FileSlice;
 use tantivy::fastfield::FastFieldReaders;
-use tantivy::schema::Field;
+use tantivy::schema::{Field, Schema};
 use tantivy::{Index, ReloadPolicy, Searcher, Term};
 use tracing::*;
 
 use crate::collector::{make_collector_for_split, make_merge_collector, IncrementalCollector};
+use crate::root::SORT_DOC_FIELD_NAMES;
+use crate::runtime_fields::{parse_runtime_mappings, RuntimeFieldsSearch};
 use crate::service::SearcherContext;
 use crate::SearchError;
 
 #[instrument(skip_all)]
-async fn get_split_footer_from_cache_or_fetch(
+pub(crate) async fn get_split_footer_from_cache_or_fetch(
     index_storage: Arc<dyn Storage>,
     split_and_footer_offsets: &SplitIdAndFooterOffsets,
     footer_cache: &MemorySizedCache<String>,
@@ -119,6 +126,10 @@ pub(crate) async fn open_split_bundle(
 /// - A split footer cache given by `SearcherContext.split_footer_cache`.
 /// - A fast fields cache given by `SearcherContext.storage_long_term_cache`.
 /// - An ephemeral unbounded cache directory whose lifetime is tied to the returned `Index`.
+///
+/// If a `directory_read_counter` is passed, it records the number of bytes read by the index
+/// below the hotcache and the ephemeral cache, whether they are served by the caches or by the
+/// storage.
 #[instrument(skip_all, fields(split_footer_start=split_and_footer_offsets.split_footer_start, split_footer_end=split_and_footer_offsets.split_footer_end))]
 pub(crate) async fn open_index_with_caches(
     searcher_context: &SearcherContext,
@@ -126,14 +137,19 @@ pub(crate) async fn open_index_with_caches(
     split_and_footer_offsets: &SplitIdAndFooterOffsets,
     tokenizer_manager: Option<&TokenizerManager>,
     ephemeral_unbounded_cache: bool,
+    directory_read_counter_opt: Option<StorageRequestCounter>,
 ) -> anyhow::Result<Index> {
     let (hotcache_bytes, bundle_storage) =
         open_split_bundle(searcher_context, index_storage, split_and_footer_offsets).await?;
 
-    let bundle_storage_with_cache = wrap_storage_with_cache(
+    let mut bundle_storage_with_cache = wrap_storage_with_cache(
         searcher_context.fast_fields_cache.clone(),
         Arc::new(bundle_storage),
     );
+    if let Some(directory_read_counter) = directory_read_counter_opt {
+        bundle_storage_with_cache =
+            wrap_storage_with_request_counter(bundle_storage_with_cache, directory_read_counter);
+    }
     let directory = StorageDirectory::new(bundle_storage_with_cache);
 
     let hot_directory = if ephemeral_unbounded_cache {
@@ -170,26 +186,36 @@ pub(crate) async fn open_index_with_caches(
 /// * `term_dict_field_names` - A list of fields, where the whole dictionary needs to be loaded.
 /// This is e.g. required for term aggregation, since we don't know in advance which terms are going
 /// to be hit.
+///
+/// Returns the time spent in each of the warmup phases. Phases are executed concurrently.
 #[instrument(skip_all)]
-pub(crate) async fn warmup(searcher: &Searcher, warmup_info: &WarmupInfo) -> anyhow::Result<()> {
+pub(crate) async fn warmup(
+    searcher: &Searcher,
+    warmup_info: &WarmupInfo,
+) -> anyhow::Result<WarmupDurations> {
     debug!(warmup_info=?warmup_info);
-    let warm_up_terms_future = warm_up_terms(searcher, &warmup_info.terms_grouped_by_field)
+    let warm_up_terms_future = timed(warm_up_terms(searcher, &warmup_info.terms_grouped_by_field))
         .instrument(debug_span!("warm_up_terms"));
-    let warm_up_term_ranges_future =
-        warm_up_term_ranges(searcher, &warmup_info.term_ranges_grouped_by_field)
-            .instrument(debug_span!("warm_up_term_ranges"));
-    let warm_up_term_dict_future =
-        warm_up_term_dict_fields(searcher, &warmup_info.term_dict_fields)
-            .instrument(debug_span!("warm_up_term_dicts"));
-    let warm_up_fastfields_future = warm_up_fastfields(searcher, &warmup_info.fast_field_names)
-        .instrument(debug_span!("warm_up_fastfields"));
-    let warm_up_fieldnorms_future = warm_up_fieldnorms(searcher, warmup_info.field_norms)
+    let warm_up_term_ranges_future = timed(warm_up_term_ranges(
+        searcher,
+        &warmup_info.term_ranges_grouped_by_field,
+    ))
+    .instrument(debug_span!("warm_up_term_ranges"));
+    let warm_up_term_dict_future = timed(warm_up_term_dict_fields(
+        searcher,
+        &warmup_info.term_dict_fields,
+    ))
+    .instrument(debug_span!("warm_up_term_dicts"));
+    let warm_up_fastfields_future =
+        timed(warm_up_fastfields(searcher, &warmup_info.fast_field_names))
+            .instrument(debug_span!("warm_up_fastfields"));
+    let warm_up_fieldnorms_future = timed(warm_up_fieldnorms(searcher, warmup_info.field_norms))
         .instrument(debug_span!("warm_up_fieldnorms"));
     // TODO merge warm_up_postings into warm_up_term_dict_fields
-    let warm_up_postings_future = warm_up_postings(searcher, &warmup_info.term_dict_fields)
+    let warm_up_postings_future = timed(warm_up_postings(searcher, &warmup_info.term_dict_fields))
         .instrument(debug_span!("warm_up_postings"));
 
-    tokio::try_join!(
+    let (terms, term_ranges, fastfields, term_dicts, fieldnorms, postings) = tokio::try_join!(
         warm_up_terms_future,
         warm_up_term_ranges_future,
         warm_up_fastfields_future,
@@ -198,7 +224,42 @@ pub(crate) async fn warmup(searcher: &Searcher, warmup_info: &WarmupInfo) -> any
         warm_up_postings_future,
     )?;
 
-    Ok(())
+    Ok(WarmupDurations {
+        terms,
+        term_ranges,
+        term_dicts,
+        fastfields,
+        fieldnorms,
+        postings,
+    })
+}
+
+/// Time spent in each of the warmup phases.
+#[derive(Debug)]
+pub(crate) struct WarmupDurations {
+    terms: Duration,
+    term_ranges: Duration,
+    term_dicts: Duration,
+    fastfields: Duration,
+    fieldnorms: Duration,
+    postings: Duration,
+}
+
+impl WarmupDurations {
+    fn record_into(&self, split_profile: &mut SplitSearchProfile) {
+        split_profile.warm_up_terms_micros = self.terms.as_micros() as u64;
+        split_profile.warm_up_term_ranges_micros = self.term_ranges.as_micros() as u64;
+        split_profile.warm_up_term_dicts_micros = self.term_dicts.as_micros() as u64;
+        split_profile.warm_up_fastfields_micros = self.fastfields.as_micros() as u64;
+        split_profile.warm_up_fieldnorms_micros = self.fieldnorms.as_micros() as u64;
+        split_profile.warm_up_postings_micros = self.postings.as_micros() as u64;
+    }
+}
+
+async fn timed(future: impl Future<Output = anyhow::Result<()>>) -> anyhow::Result<Duration> {
+    let start = Instant::now();
+    future.await?;
+    Ok(start.elapsed())
 }
 
 async fn warm_up_term_dict_fields(
@@ -249,7 +310,7 @@ async fn warm_up_fastfield(
 
 /// Populates the short-lived cache with the data for
 /// all of the fast fields passed as argument.
-async fn warm_up_fastfields(
+pub(crate) async fn warm_up_fastfields(
     searcher: &Searcher,
     fast_field_names: &HashSet<String>,
 ) -> anyhow::Result<()> {
@@ -335,60 +396,223 @@ async fn leaf_search_single_split(
     doc_mapper: Arc<dyn DocMapper>,
 ) -> crate::Result<LeafSearchResponse> {
     rewrite_request(&mut search_request, &split);
-    if let Some(cached_answer) = searcher_context
+    // Profiling does not change the search results, so profiled requests share their leaf cache
+    // entries with regular requests.
+    let profile = std::mem::take(&mut search_request.profile);
+    if let Some(mut cached_answer) = searcher_context
         .leaf_search_cache
         .get(split.clone(), search_request.clone())
     {
+        if profile {
+            cached_answer.split_profiles = vec![SplitSearchProfile {
+                split_id: split.split_id.clone(),
+                leaf_cache_hit: true,
+                ..Default::default()
+            }];
+        }
         return Ok(cached_answer);
     }
 
     let split_id = split.split_id.to_string();
+    let storage_request_counter = StorageRequestCounter::default();
+    let directory_read_counter = StorageRequestCounter::default();
+    let storage = if profile {
+        wrap_storage_with_request_counter(storage, storage_request_counter.clone())
+    } else {
+        storage
+    };
+    let open_index_start = Instant::now();
     let index = open_index_with_caches(
         searcher_context,
         storage,
         &split,
         Some(doc_mapper.tokenizer_manager()),
         true,
+        profile.then(|| directory_read_counter.clone()),
     )
     .await?;
+    let open_index_duration = open_index_start.elapsed();
     let split_schema = index.schema();
 
-    let quickwit_collector = make_collector_for_split(
-        split_id.clone(),
-        doc_mapper.as_ref(),
-        &search_request,
-        searcher_context.get_aggregation_limits(),
-    )?;
     let query_ast: QueryAst = serde_json::from_str(search_request.query_ast.as_str())
         .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
-    let (query, mut warmup_info) = doc_mapper.query(split_schema, &query_ast, false)?;
     let reader = index
         .reader_builder()
         .reload_policy(ReloadPolicy::Manual)
         .try_into()?;
     let searcher = reader.searcher();
 
+    if let Some(runtime_fields_search) = RuntimeFieldsSearch::try_new(&search_request, &query_ast)?
+    {
+        let search_start = Instant::now();
+        let mut leaf_search_response = runtime_fields_search
+            .search(
+                split_id.clone(),
+                searcher,
+                doc_mapper,
+                &search_request,
+                searcher_context.get_aggregation_limits(),
+                searcher_context
+                    .searcher_config
+                    .max_num_runtime_field_docs_per_split,
+            )
+            .await?;
+        let search_duration = search_start.elapsed();
+
+        searcher_context.leaf_search_cache.put(
+            split.clone(),
+            search_request,
+            leaf_search_response.clone(),
+        );
+        if profile {
+            leaf_search_response.split_profiles = vec![SplitSearchProfile {
+                split_id,
+                query_plan: format!("runtime fields search: {query_ast:?}"),
+                open_index_micros: open_index_duration.as_micros() as u64,
+                search_micros: search_duration.as_micros() as u64,
+                num_bytes_fetched_from_storage: storage_request_counter.num_bytes(),
+                ..Default::default()
+            }];
+        }
+        return Ok(leaf_search_response);
+    }
+    let collector_search_request =
+        resolve_fast_field_names(doc_mapper.as_ref(), &split_schema, &search_request)?;
+    let quickwit_collector = make_collector_for_split(
+        split_id.clone(),
+        doc_mapper.as_ref(),
+        &collector_search_request,
+        searcher_context.get_aggregation_limits(),
+    )?;
+    let (query, mut warmup_info) = doc_mapper.query(split_schema, &query_ast, false)?;
+
     let collector_warmup_info = quickwit_collector.warmup_info();
     warmup_info.merge(collector_warmup_info);
     warmup_info.simplify();
 
-    warmup(&searcher, &warmup_info).await?;
+    let query_plan_opt = profile.then(|| format!("{query:?}"));
+    let warmup_start = Instant::now();
+    let warmup_durations = warmup(&searcher, &warmup_info).await?;
+    let warmup_duration = warmup_start.elapsed();
+
     let span = info_span!("tantivy_search");
-    let leaf_search_response = crate::run_cpu_intensive(move || {
+    let search_start = Instant::now();
+    let split_id_clone = split_id.clone();
+    let mut leaf_search_response = crate::run_cpu_intensive(move || {
         let _span_guard = span.enter();
         searcher.search(&query, &quickwit_collector)
     })
     .await
     .map_err(|_| {
-        crate::SearchError::Internal(format!("leaf search panicked. split={split_id}"))
+        crate::SearchError::Internal(format!("leaf search panicked. split={split_id_clone}"))
     })??;
+    let search_duration = search_start.elapsed();
 
-    searcher_context
-        .leaf_search_cache
-        .put(split, search_request, leaf_search_response.clone());
+    searcher_context.leaf_search_cache.put(
+        split.clone(),
+        search_request,
+        leaf_search_response.clone(),
+    );
+    if let Some(query_plan) = query_plan_opt {
+        let num_bytes_fetched_from_storage = storage_request_counter.num_bytes();
+        // The footer is either read from the footer cache or from the storage. The bytes read by
+        // the index are either served by the fast field cache, the split cache or the storage.
+        let num_bytes_read = (split.split_footer_end - split.split_footer_start)
+            + directory_read_counter.num_bytes();
+        let mut split_profile = SplitSearchProfile {
+            split_id,
+            query_plan,
+            open_index_micros: open_index_duration.as_micros() as u64,
+            warm_up_micros: warmup_duration.as_micros() as u64,
+            search_micros: search_duration.as_micros() as u64,
+            num_bytes_fetched_from_storage,
+            num_bytes_fetched_from_cache: num_bytes_read
+                .saturating_sub(num_bytes_fetched_from_storage),
+            ..Default::default()
+        };
+        warmup_durations.record_into(&mut split_profile);
+        leaf_search_response.split_profiles = vec![split_profile];
+    }
     Ok(leaf_search_response)
 }
 
+/// Returns the name of the fast field of the split to sort on when the request sorts by
+/// `field_name`, if it differs from `field_name`.
+pub(crate) fn resolve_sort_field_name(
+    doc_mapper: &dyn DocMapper,
+    split_schema: &Schema,
+    field_name: &str,
+) -> Option<String> {
+    if field_name == "_score" || SORT_DOC_FIELD_NAMES.contains(&field_name) {
+        return None;
+    }
+    doc_mapper.resolve_fast_field_name(split_schema, field_name)
+}
+
+/// Rewrites the sort fields and the fields of the aggregations of the request which are stored
+/// under another name in the split, such as the unmapped fields captured by a dynamic template.
+fn resolve_fast_field_names<'a>(
+    doc_mapper: &dyn DocMapper,
+    split_schema: &Schema,
+    search_request: &'a SearchRequest,
+) -> crate::Result<Cow<'a, SearchRequest>> {
+    let mut resolved_search_request = Cow::Borrowed(search_request);
+
+    for (sort_field_ord, sort_field) in search_request.sort_fields.iter().enumerate() {
+        if let Some(field_name) =
+            resolve_sort_field_name(doc_mapper, split_schema, &sort_field.field_name)
+        {
+            resolved_search_request.to_mut().sort_fields[sort_field_ord].field_name = field_name;
+        }
+    }
+    if let Some(aggregation_request) = &search_request.aggregation_request {
+        let mut aggregation_json: JsonValue = serde_json::from_str(aggregation_request)?;
+
+        if resolve_aggregation_field_names(doc_mapper, split_schema, &mut aggregation_json) {
+            resolved_search_request.to_mut().aggregation_request =
+                Some(aggregation_json.to_string());
+        }
+    }
+    Ok(resolved_search_request)
+}
+
+/// Rewrites the `field` parameters of the aggregations. Returns whether a field was rewritten.
+fn resolve_aggregation_field_names(
+    doc_mapper: &dyn DocMapper,
+    split_schema: &Schema,
+    aggregation_json: &mut JsonValue,
+) -> bool {
+    let mut is_resolved = false;
+
+    match aggregation_json {
+        JsonValue::Object(json_obj) => {
+            for (key, json_value) in json_obj.iter_mut() {
+                if key == "field" {
+                    if let JsonValue::String(field_name) = json_value {
+                        if let Some(resolved_field_name) =
+                            doc_mapper.resolve_fast_field_name(split_schema, field_name)
+                        {
+                            *field_name = resolved_field_name;
+                            is_resolved = true;
+                        }
+                        continue;
+                    }
+                }
+                is_resolved |=
+                    resolve_aggregation_field_names(doc_mapper, split_schema, json_value);
+            }
+        }
+        JsonValue::Array(json_values) => {
+            for json_value in json_values {
+                is_resolved |=
+                    resolve_aggregation_field_names(doc_mapper, split_schema, json_value);
+            }
+        }
+        _ => {}
+    }
+    is_resolved
+}
+
 /// Rewrite a request removing parts which incure additional download or computation with no
 /// effect.
 ///
@@ -558,7 +782,12 @@ pub async fn leaf_search(
 ) -> Result<LeafSearchResponse, SearchError> {
     info!(splits_num = splits.len(), split_offsets = ?PrettySample::new(&splits, 5));
 
-    let split_filter = CanSplitDoBetter::from_request(&request, doc_mapper.timestamp_field_name());
+    // A runtime field shadowing the timestamp field does not follow the time range of the splits.
+    let runtime_mappings = parse_runtime_mappings(request.runtime_mappings.as_deref())?;
+    let timestamp_field_name_opt = doc_mapper
+        .timestamp_field_name()
+        .filter(|timestamp_field_name| !runtime_mappings.contains_key(*timestamp_field_name));
+    let split_filter = CanSplitDoBetter::from_request(&request, timestamp_field_name_opt);
     split_filter.optimize_split_order(&mut splits);
 
     // if client wants full count, or we are doing an aggregation, we want to run every splits.
@@ -206,6 +206,7 @@ mod tests {
             split_footer_end: 100,
             timestamp_start: None,
             timestamp_end: None,
+            storage_uri: None,
         };
 
         let split_2 = SplitIdAndFooterOffsets {
@@ -214,6 +215,7 @@ mod tests {
             split_footer_end: 100,
             timestamp_start: None,
             timestamp_end: None,
+            storage_uri: None,
         };
 
         let query_1 = SearchRequest {
@@ -247,7 +249,10 @@ mod tests {
                 sort_value: Some(SortValue::U64(0u64).into()),
                 sort_value2: None,
                 split_id: "split_1".to_string(),
+                collapse_key: None,
             }],
+            split_profiles: Vec::new(),
+            storage_request_stats: None,
         };
 
         assert!(cache.get(split_1.clone(), query_1.clone()).is_none());
@@ -268,6 +273,7 @@ mod tests {
             split_footer_end: 100,
             timestamp_start: Some(100),
             timestamp_end: Some(199),
+            storage_uri: None,
         };
         let split_2 = SplitIdAndFooterOffsets {
             split_id: "split_2".to_string(),
@@ -275,6 +281,7 @@ mod tests {
             split_footer_end: 100,
             timestamp_start: Some(150),
             timestamp_end: Some(249),
+            storage_uri: None,
         };
         let split_3 = SplitIdAndFooterOffsets {
             split_id: "split_3".to_string(),
@@ -282,6 +289,7 @@ mod tests {
             split_footer_end: 100,
             timestamp_start: Some(150),
             timestamp_end: Some(249),
+            storage_uri: None,
         };
 
         let query_1 = SearchRequest {
@@ -333,7 +341,10 @@ mod tests {
                 sort_value: Some(SortValue::U64(0).into()),
                 sort_value2: None,
                 split_id: "split_1".to_string(),
+                collapse_key: None,
             }],
+            split_profiles: Vec::new(),
+            storage_request_stats: None,
         };
 
         // for split_1, 1 and 1bis cover different timestamp ranges
@@ -25,7 +25,9 @@
 mod client;
 mod cluster_client;
 mod collector;
+mod composite_aggregation;
 mod error;
+mod extended_aggregations;
 mod fetch_docs;
 mod filters;
 mod find_trace_ids_collector;
@@ -34,14 +36,19 @@ mod leaf_cache;
 mod list_fields;
 mod list_fields_cache;
 mod list_terms;
+mod point_in_time;
 mod retry;
 mod root;
+mod root_cache;
+mod runtime_fields;
 mod scroll_context;
 mod search_job_placer;
 mod search_response_rest;
 mod search_stream;
 mod service;
+mod split_warmup;
 mod thread_pool;
+mod top_hits_aggregation;
 
 mod metrics;
 
@@ -87,8 +94,11 @@ pub use crate::root::{
     check_all_index_metadata_found, jobs_to_leaf_requests, root_search, IndexMetasForLeafSearch,
     SearchJob,
 };
+pub use crate::runtime_fields::{
+    RuntimeFieldMapping, RuntimeFieldScript, RuntimeFieldType, RuntimeMappings,
+};
 pub use crate::search_job_placer::{Job, SearchJobPlacer};
-pub use crate::search_response_rest::SearchResponseRest;
+pub use crate::search_response_rest::{ListTermsResponseRest, SearchResponseRest, TermRest};
 pub use crate::search_stream::root_search_stream;
 pub use crate::service::{MockSearchService, SearchService, SearchServiceImpl};
 use crate::thread_pool::run_cpu_intensive;
@@ -169,6 +179,10 @@ fn extract_split_and_footer_offsets(split_metadata: &SplitMetadata) -> SplitIdAn
             .time_range
             .as_ref()
             .map(|time_range| *time_range.end()),
+        storage_uri: split_metadata
+            .storage_uri
+            .as_ref()
+            .map(|storage_uri| storage_uri.to_string()),
     }
 }
 
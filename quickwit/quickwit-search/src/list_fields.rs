@@ -28,6 +28,7 @@ use futures::future::try_join_all;
 use itertools::Itertools;
 use quickwit_common::shared_consts::SPLIT_FIELDS_FILE_NAME;
 use quickwit_common::uri::Uri;
+use quickwit_doc_mapper::DYNAMIC_TEMPLATE_FIELD_PREFIX;
 use quickwit_metastore::SplitMetadata;
 use quickwit_proto::metastore::MetastoreServiceClient;
 use quickwit_proto::search::{
@@ -259,10 +260,17 @@ pub async fn leaf_list_fields(
                 // We don't want to leak the _dynamic hack to the user API.
                 if entry.field_name.starts_with("_dynamic.") {
                     entry.field_name.replace_range(.."_dynamic.".len(), "");
+                } else if entry.field_name.starts_with(DYNAMIC_TEMPLATE_FIELD_PREFIX) {
+                    // Same for the fields of the dynamic templates, e.g. `_dynamic_ids.user_id`.
+                    if let Some(dot_pos) = entry.field_name.find('.') {
+                        entry.field_name.replace_range(..=dot_pos, "");
+                    }
                 }
                 entry
             })
-            .filter(|field| matches_any_pattern(&field.field_name, field_patterns));
+            .filter(|field| matches_any_pattern(&field.field_name, field_patterns))
+            // Removing the prefixes above may break the ordering expected by the merge.
+            .sorted_by(|a, b| (&a.field_name, a.field_type).cmp(&(&b.field_name, b.field_type)));
         iter_per_split.push(list_fields_iter);
     }
     let fields = merge_leaf_list_fields(iter_per_split)?;
@@ -347,8 +355,11 @@ pub fn jobs_to_leaf_requests(
 ) -> crate::Result<Vec<LeafListFieldsRequest>> {
     let search_request_for_leaf = request.clone();
     let mut leaf_search_requests = Vec::new();
-    // Group jobs by index uid.
-    for (index_uid, job_group) in &jobs.into_iter().group_by(|job| job.index_uid.clone()) {
+    // Group jobs by index uid and by storage.
+    for ((index_uid, storage_uri_opt), job_group) in &jobs
+        .into_iter()
+        .group_by(|job| (job.index_uid.clone(), job.offsets.storage_uri.clone()))
+    {
         let index_meta = index_uid_to_id.get(&index_uid).ok_or_else(|| {
             SearchError::Internal(format!(
                 "received list fields job for an unknown index {index_uid}. it should never happen"
@@ -356,7 +367,7 @@ pub fn jobs_to_leaf_requests(
         })?;
         let leaf_search_request = LeafListFieldsRequest {
             index_id: index_meta.index_id.to_string(),
-            index_uri: index_meta.index_uri.to_string(),
+            index_uri: storage_uri_opt.unwrap_or_else(|| index_meta.index_uri.to_string()),
             fields: search_request_for_leaf.fields.clone(),
             split_offsets: job_group.into_iter().map(|job| job.offsets).collect(),
         };
@@ -18,6 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::{HashMap, HashSet};
+use std::iter;
 use std::ops::Bound;
 use std::sync::Arc;
 
@@ -34,6 +35,7 @@ use quickwit_proto::search::{
 };
 use quickwit_proto::types::IndexUid;
 use quickwit_storage::Storage;
+use regex::bytes::Regex;
 use tantivy::schema::{Field, FieldType};
 use tantivy::{ReloadPolicy, Term};
 use tracing::{debug, error, info, instrument};
@@ -62,8 +64,20 @@ pub async fn root_list_terms(
             terms: Vec::new(),
             elapsed_time_micros: 0,
             errors: Vec::new(),
+            doc_freqs: Vec::new(),
         });
     }
+    if let Some(regex) = &list_terms_request.regex {
+        // Filtering terms with a regex reads the term dictionaries until `max_hits` matching
+        // terms are found, so the number of terms has to be bounded.
+        if list_terms_request.max_hits.is_none() {
+            return Err(SearchError::InvalidQuery(
+                "listing terms with a regex requires `max_hits` to be set".to_string(),
+            ));
+        }
+        build_term_regex(regex)
+            .map_err(|err| SearchError::InvalidQuery(format!("invalid regex `{regex}`: {err}")))?;
+    }
 
     for index_metadata in indexes_metadata.iter() {
         let index_config = &index_metadata.index_config;
@@ -84,6 +98,15 @@ pub async fn root_list_terms(
                 "trying to list terms on field which isn't indexed".to_string(),
             ));
         }
+        let has_text_filter =
+            list_terms_request.prefix.is_some() || list_terms_request.regex.is_some();
+        if has_text_filter && !matches!(field_entry.field_type(), FieldType::Str(_)) {
+            return Err(SearchError::InvalidQuery(format!(
+                "prefix and regex filters are only supported on text fields, `{}` is not a text \
+                 field",
+                list_terms_request.field
+            )));
+        }
     }
     let index_uids: Vec<IndexUid> = indexes_metadata
         .iter()
@@ -150,32 +173,105 @@ pub async fn root_list_terms(
 
     // Merging is a cpu-bound task, but probably fast enough to not require
     // spawning it on a blocking thread.
-    let merged_iter = leaf_search_responses
-        .into_iter()
-        .map(|leaf_search_response| leaf_search_response.terms)
-        .kmerge()
-        .dedup();
-    let leaf_list_terms_response: Vec<Vec<u8>> = if let Some(limit) = list_terms_request.max_hits {
-        merged_iter.take(limit as usize).collect()
-    } else {
-        merged_iter.collect()
-    };
+    let (terms, doc_freqs) = merge_terms_with_doc_freqs(
+        leaf_search_responses
+            .into_iter()
+            .map(|leaf_search_response| {
+                (leaf_search_response.terms, leaf_search_response.doc_freqs)
+            }),
+        list_terms_request.max_hits,
+    );
 
     debug!(
-        leaf_list_terms_response_count = leaf_list_terms_response.len(),
+        leaf_list_terms_response_count = terms.len(),
         "Merged leaf search response."
     );
 
     let elapsed = start_instant.elapsed();
 
     Ok(ListTermsResponse {
-        num_hits: leaf_list_terms_response.len() as u64,
-        terms: leaf_list_terms_response,
+        num_hits: terms.len() as u64,
+        terms,
         elapsed_time_micros: elapsed.as_micros() as u64,
         errors: Vec::new(),
+        doc_freqs,
     })
 }
 
+/// Merges several lists of sorted terms into a single sorted list of at most `max_hits` terms.
+///
+/// Each list of terms comes with the document frequencies of its terms. The document frequencies
+/// of a term appearing in several lists are summed up. A missing document frequency, as returned
+/// by nodes that do not compute them, is counted as 0.
+fn merge_terms_with_doc_freqs(
+    terms_and_doc_freqs: impl IntoIterator<Item = (Vec<Vec<u8>>, Vec<u64>)>,
+    max_hits: Option<u64>,
+) -> (Vec<Vec<u8>>, Vec<u64>) {
+    let merged_iter = terms_and_doc_freqs
+        .into_iter()
+        .map(|(terms, doc_freqs)| {
+            terms
+                .into_iter()
+                .zip(doc_freqs.into_iter().chain(iter::repeat(0)))
+        })
+        .kmerge_by(|(left_term, _), (right_term, _)| left_term < right_term)
+        .coalesce(|(left_term, left_doc_freq), (right_term, right_doc_freq)| {
+            if left_term == right_term {
+                Ok((left_term, left_doc_freq + right_doc_freq))
+            } else {
+                Err(((left_term, left_doc_freq), (right_term, right_doc_freq)))
+            }
+        });
+    if let Some(limit) = max_hits {
+        merged_iter.take(limit as usize).unzip()
+    } else {
+        merged_iter.unzip()
+    }
+}
+
+/// Number of terms read at once from a term dictionary when filtering terms with a regex.
+const REGEX_TERMS_CHUNK_SIZE: u64 = if cfg!(test) { 2 } else { 10_000 };
+
+/// Builds the regex used to filter terms. Like in term queries, the regex has to match the whole
+/// term.
+fn build_term_regex(regex: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{regex})$"))
+}
+
+/// Returns the smallest key greater than all the keys starting with `prefix`, or `None` if there
+/// is no such key.
+fn prefix_end_key(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end_key = prefix.to_vec();
+    while let Some(last_byte) = end_key.pop() {
+        if last_byte < u8::MAX {
+            end_key.push(last_byte + 1);
+            return Some(end_key);
+        }
+    }
+    None
+}
+
+/// Computes the range of keys to list, intersecting the `[start_key, end_key)` range of the
+/// request with the range of keys starting with the requested prefix.
+fn key_range(search_request: &ListTermsRequest) -> (Option<Vec<u8>>, Option<Vec<u8>>) {
+    let Some(prefix) = &search_request.prefix else {
+        return (
+            search_request.start_key.clone(),
+            search_request.end_key.clone(),
+        );
+    };
+    let prefix_start_key = prefix.as_bytes().to_vec();
+    let start_key = match &search_request.start_key {
+        Some(start_key) if *start_key > prefix_start_key => start_key.clone(),
+        _ => prefix_start_key,
+    };
+    let end_key = match (&search_request.end_key, prefix_end_key(prefix.as_bytes())) {
+        (Some(end_key), Some(prefix_end_key)) => Some(end_key.clone().min(prefix_end_key)),
+        (end_key_opt, prefix_end_key_opt) => end_key_opt.clone().or(prefix_end_key_opt),
+    };
+    (Some(start_key), end_key)
+}
+
 /// Builds a list of [`LeafListFieldsRequest`], one per index, from a list of [`SearchJob`].
 pub fn jobs_to_leaf_requests(
     request: &ListTermsRequest,
@@ -184,8 +280,11 @@ pub fn jobs_to_leaf_requests(
 ) -> crate::Result<Vec<LeafListTermsRequest>> {
     let search_request_for_leaf = request.clone();
     let mut leaf_search_requests = Vec::new();
-    // Group jobs by index uid.
-    for (index_uid, job_group) in &jobs.into_iter().group_by(|job| job.index_uid.clone()) {
+    // Group jobs by index uid and by storage.
+    for ((index_uid, storage_uri_opt), job_group) in &jobs
+        .into_iter()
+        .group_by(|job| (job.index_uid.clone(), job.offsets.storage_uri.clone()))
+    {
         let index_uri = index_uid_to_uri.get(&index_uid).ok_or_else(|| {
             SearchError::Internal(format!(
                 "received list fields job for an unknown index {index_uid}. it should never happen"
@@ -193,7 +292,7 @@ pub fn jobs_to_leaf_requests(
         })?;
         let leaf_search_request = LeafListTermsRequest {
             list_terms_request: Some(search_request_for_leaf.clone()),
-            index_uri: index_uri.to_string(),
+            index_uri: storage_uri_opt.unwrap_or_else(|| index_uri.to_string()),
             split_offsets: job_group.into_iter().map(|job| job.offsets).collect(),
         };
         leaf_search_requests.push(leaf_search_request);
@@ -209,7 +308,7 @@ async fn leaf_list_terms_single_split(
     storage: Arc<dyn Storage>,
     split: SplitIdAndFooterOffsets,
 ) -> crate::Result<LeafListTermsResponse> {
-    let index = open_index_with_caches(searcher_context, storage, &split, None, true).await?;
+    let index = open_index_with_caches(searcher_context, storage, &split, None, true, None).await?;
     let split_schema = index.schema();
     let reader = index
         .reader_builder()
@@ -227,71 +326,108 @@ async fn leaf_list_terms_single_split(
         })?;
 
     let field_type = split_schema.get_field_entry(field).field_type();
-    let start_term: Option<Term> = search_request
-        .start_key
+    let (start_key, end_key) = key_range(search_request);
+    let start_term: Option<Term> = start_key
         .as_ref()
         .map(|data| term_from_data(field, field_type, data));
-    let end_term: Option<Term> = search_request
-        .end_key
+    let end_term: Option<Term> = end_key
         .as_ref()
         .map(|data| term_from_data(field, field_type, data));
+    let regex_opt: Option<Regex> = search_request
+        .regex
+        .as_deref()
+        .map(build_term_regex)
+        .transpose()
+        .with_context(|| "failed to build regex to list terms")?;
+    // When filtering terms with a regex, we cannot tell in advance how many terms we need to read
+    // from the dictionary to get `max_hits` terms, so the dictionary is read in chunks until
+    // enough terms match.
+    let range_limit: Option<u64> = if regex_opt.is_some() {
+        Some(REGEX_TERMS_CHUNK_SIZE)
+    } else {
+        search_request.max_hits
+    };
+    let max_hits = search_request.max_hits.unwrap_or(u64::MAX) as usize;
+    let end_key_bound: Bound<&[u8]> = end_term
+        .as_ref()
+        .map(Term::serialized_value_bytes)
+        .map(Bound::Excluded)
+        .unwrap_or(Bound::Unbounded);
 
     let mut segment_results = Vec::new();
     for segment_reader in searcher.segment_readers() {
         let inverted_index = segment_reader.inverted_index(field)?.clone();
         let dict = inverted_index.terms();
-        dict.file_slice_for_range(
-            (
-                start_term
-                    .as_ref()
-                    .map(Term::serialized_value_bytes)
-                    .map(Bound::Included)
-                    .unwrap_or(Bound::Unbounded),
-                end_term
-                    .as_ref()
-                    .map(Term::serialized_value_bytes)
-                    .map(Bound::Excluded)
-                    .unwrap_or(Bound::Unbounded),
-            ),
-            search_request.max_hits,
-        )
-        .read_bytes_async()
-        .await
-        .with_context(|| "failed to load sstable range")?;
-
-        let mut range = dict.range();
-        if let Some(limit) = search_request.max_hits {
-            range = range.limit(limit);
-        }
-        if let Some(start_term) = &start_term {
-            range = range.ge(start_term.serialized_value_bytes())
-        }
-        if let Some(end_term) = &end_term {
-            range = range.lt(end_term.serialized_value_bytes())
-        }
-        let mut stream = range
-            .into_stream()
-            .with_context(|| "failed to create stream over sstable")?;
-        let mut segment_result: Vec<Vec<u8>> =
-            Vec::with_capacity(search_request.max_hits.unwrap_or(0) as usize);
-        while stream.advance() {
-            segment_result.push(term_to_data(field, field_type, stream.key()));
+        let mut start_key_bound: Bound<Vec<u8>> = start_term
+            .as_ref()
+            .map(|start_term| Bound::Included(start_term.serialized_value_bytes().to_vec()))
+            .unwrap_or(Bound::Unbounded);
+        let mut segment_terms: Vec<Vec<u8>> = Vec::new();
+        let mut segment_doc_freqs: Vec<u64> = Vec::new();
+        loop {
+            let start_key_bound_ref: Bound<&[u8]> = match &start_key_bound {
+                Bound::Included(start_key) => Bound::Included(start_key.as_slice()),
+                Bound::Excluded(start_key) => Bound::Excluded(start_key.as_slice()),
+                Bound::Unbounded => Bound::Unbounded,
+            };
+            dict.file_slice_for_range((start_key_bound_ref, end_key_bound), range_limit)
+                .read_bytes_async()
+                .await
+                .with_context(|| "failed to load sstable range")?;
+
+            let mut range = dict.range();
+            if let Some(limit) = range_limit {
+                range = range.limit(limit);
+            }
+            match &start_key_bound {
+                Bound::Included(start_key) => range = range.ge(start_key.as_slice()),
+                Bound::Excluded(start_key) => range = range.gt(start_key.as_slice()),
+                Bound::Unbounded => {}
+            }
+            if let Some(end_term) = &end_term {
+                range = range.lt(end_term.serialized_value_bytes())
+            }
+            let mut stream = range
+                .into_stream()
+                .with_context(|| "failed to create stream over sstable")?;
+            let mut num_read_terms: u64 = 0;
+            let mut last_read_key_opt: Option<Vec<u8>> = None;
+
+            while segment_terms.len() < max_hits && stream.advance() {
+                num_read_terms += 1;
+
+                if let Some(regex) = &regex_opt {
+                    if num_read_terms == REGEX_TERMS_CHUNK_SIZE {
+                        last_read_key_opt = Some(stream.key().to_vec());
+                    }
+                    if !regex.is_match(stream.key()) {
+                        continue;
+                    }
+                }
+                segment_terms.push(term_to_data(field, field_type, stream.key()));
+                segment_doc_freqs.push(stream.value().doc_freq as u64);
+            }
+            // The chunk was not fully read: either enough terms were found or the range is
+            // exhausted.
+            let Some(last_read_key) = last_read_key_opt else {
+                break;
+            };
+            if segment_terms.len() >= max_hits {
+                break;
+            }
+            start_key_bound = Bound::Excluded(last_read_key);
         }
-        segment_results.push(segment_result);
+        segment_results.push((segment_terms, segment_doc_freqs));
     }
 
-    let merged_iter = segment_results.into_iter().kmerge().dedup();
-    let merged_results: Vec<Vec<u8>> = if let Some(limit) = search_request.max_hits {
-        merged_iter.take(limit as usize).collect()
-    } else {
-        merged_iter.collect()
-    };
+    let (terms, doc_freqs) = merge_terms_with_doc_freqs(segment_results, search_request.max_hits);
 
     Ok(LeafListTermsResponse {
-        num_hits: merged_results.len() as u64,
-        terms: merged_results,
+        num_hits: terms.len() as u64,
+        terms,
         num_attempted_splits: 1,
         failed_splits: Vec::new(),
+        doc_freqs,
     })
 }
 
@@ -356,16 +492,14 @@ pub async fn leaf_list_terms(
                 Err(err) => Either::Right(err),
             });
 
-    let merged_iter = split_search_responses
-        .into_iter()
-        .map(|leaf_search_response| leaf_search_response.terms)
-        .kmerge()
-        .dedup();
-    let terms: Vec<Vec<u8>> = if let Some(limit) = request.max_hits {
-        merged_iter.take(limit as usize).collect()
-    } else {
-        merged_iter.collect()
-    };
+    let (terms, doc_freqs) = merge_terms_with_doc_freqs(
+        split_search_responses
+            .into_iter()
+            .map(|leaf_search_response| {
+                (leaf_search_response.terms, leaf_search_response.doc_freqs)
+            }),
+        request.max_hits,
+    );
 
     let failed_splits = errors
         .into_iter()
@@ -380,7 +514,84 @@ pub async fn leaf_list_terms(
         terms,
         num_attempted_splits: splits.len() as u64,
         failed_splits,
+        doc_freqs,
     };
 
     Ok(merged_search_response)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_terms_with_doc_freqs() {
+        let terms_and_doc_freqs = vec![
+            (vec![b"a".to_vec(), b"c".to_vec()], vec![1, 2]),
+            (
+                vec![b"b".to_vec(), b"c".to_vec(), b"d".to_vec()],
+                vec![3, 4, 5],
+            ),
+            // Terms without document frequencies.
+            (vec![b"a".to_vec()], Vec::new()),
+        ];
+        let (terms, doc_freqs) = merge_terms_with_doc_freqs(terms_and_doc_freqs.clone(), None);
+        assert_eq!(
+            terms,
+            vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec(), b"d".to_vec()]
+        );
+        assert_eq!(doc_freqs, vec![1, 3, 6, 5]);
+
+        let (terms, doc_freqs) = merge_terms_with_doc_freqs(terms_and_doc_freqs, Some(3));
+        assert_eq!(terms, vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]);
+        assert_eq!(doc_freqs, vec![1, 3, 6]);
+    }
+
+    #[test]
+    fn test_prefix_end_key() {
+        assert_eq!(prefix_end_key(b"abc"), Some(b"abd".to_vec()));
+        assert_eq!(prefix_end_key(b"ab\xff"), Some(b"ac".to_vec()));
+        assert_eq!(prefix_end_key(b"\xff\xff"), None);
+        assert_eq!(prefix_end_key(b""), None);
+    }
+
+    #[test]
+    fn test_key_range() {
+        let mut request = ListTermsRequest {
+            prefix: Some("ab".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            key_range(&request),
+            (Some(b"ab".to_vec()), Some(b"ac".to_vec()))
+        );
+        request.start_key = Some(b"abc".to_vec());
+        request.end_key = Some(b"abd".to_vec());
+        assert_eq!(
+            key_range(&request),
+            (Some(b"abc".to_vec()), Some(b"abd".to_vec()))
+        );
+        request.start_key = Some(b"a".to_vec());
+        request.end_key = Some(b"b".to_vec());
+        assert_eq!(
+            key_range(&request),
+            (Some(b"ab".to_vec()), Some(b"ac".to_vec()))
+        );
+        request.prefix = None;
+        assert_eq!(
+            key_range(&request),
+            (Some(b"a".to_vec()), Some(b"b".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_build_term_regex() {
+        let regex = build_term_regex("sn.*").unwrap();
+        assert!(regex.is_match(b"snoopy"));
+        assert!(!regex.is_match(b"a snoopy"));
+        let regex = build_term_regex("a|b").unwrap();
+        assert!(regex.is_match(b"a"));
+        assert!(!regex.is_match(b"ab"));
+        assert!(build_term_regex("(").is_err());
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::Context;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use quickwit_common::shared_consts::DELETION_GRACE_PERIOD;
+use quickwit_metastore::SplitMetadata;
+use quickwit_proto::metastore::MetastoreServiceClient;
+use quickwit_proto::search::{
+    ClosePointInTimeRequest, ClosePointInTimeResponse, OpenPointInTimeRequest,
+    OpenPointInTimeResponse, PointInTime,
+};
+use serde::{Deserialize, Serialize};
+use tantivy::time::OffsetDateTime;
+use ulid::Ulid;
+
+use crate::{list_all_splits, resolve_index_patterns, ClusterClient, SearchError};
+
+/// Maximum lifetime of a point in time.
+///
+/// Splits are only deleted from the storage `DELETION_GRACE_PERIOD` after being marked for
+/// deletion. Bounding the lifetime of a point in time below that period guarantees that the
+/// splits it records are not garbage collected while it is alive, even if they get merged or
+/// deleted by retention in the meantime. Extending the keep alive never pushes the expiration
+/// beyond this bound, counted from the moment the point in time was opened.
+pub(crate) const MAX_POINT_IN_TIME_LIFETIME: Duration =
+    Duration::from_secs(DELETION_GRACE_PERIOD.as_secs() - 60 * 2);
+
+/// The state of a point in time, replicated in the searchers KV store.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PointInTimeContext {
+    pub index_id_patterns: Vec<String>,
+    pub split_metadatas: Vec<SplitMetadata>,
+    /// Unix timestamp, in seconds, at which the point in time was opened.
+    pub opened_at: i64,
+}
+
+impl PointInTimeContext {
+    pub fn serialize(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap()
+    }
+
+    pub fn load(payload: &[u8]) -> anyhow::Result<Self> {
+        let point_in_time_context =
+            serde_json::from_slice(payload).context("failed to deserialize context")?;
+        Ok(point_in_time_context)
+    }
+
+    /// Returns the time to live to apply when (re)storing the context, given the requested keep
+    /// alive.
+    fn ttl(&self, keep_alive: Duration, now: i64) -> Duration {
+        let max_lifetime_secs = MAX_POINT_IN_TIME_LIFETIME.as_secs() as i64;
+        let remaining_secs = (self.opened_at + max_lifetime_secs - now).max(0) as u64;
+        keep_alive.min(Duration::from_secs(remaining_secs))
+    }
+}
+
+/// Opaque identifier of a point in time.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct PointInTimeId {
+    pit_ulid: Ulid,
+}
+
+impl PointInTimeId {
+    fn new() -> Self {
+        PointInTimeId {
+            pit_ulid: Ulid::new(),
+        }
+    }
+
+    pub fn key(&self) -> [u8; 16] {
+        u128::from(self.pit_ulid).to_le_bytes()
+    }
+}
+
+impl ToString for PointInTimeId {
+    fn to_string(&self) -> String {
+        BASE64_STANDARD.encode(self.key())
+    }
+}
+
+impl FromStr for PointInTimeId {
+    type Err = &'static str;
+
+    fn from_str(pit_id_str: &str) -> Result<Self, Self::Err> {
+        let base64_decoded: Vec<u8> = BASE64_STANDARD
+            .decode(pit_id_str)
+            .map_err(|_| "point in time id is invalid base64.")?;
+        let pit_ulid_bytes: [u8; 16] = base64_decoded
+            .try_into()
+            .map_err(|_| "point in time id is malformed")?;
+        Ok(PointInTimeId {
+            pit_ulid: u128::from_le_bytes(pit_ulid_bytes).into(),
+        })
+    }
+}
+
+fn parse_point_in_time_id(pit_id_str: &str) -> crate::Result<PointInTimeId> {
+    PointInTimeId::from_str(pit_id_str)
+        .map_err(|error| SearchError::InvalidArgument(error.to_string()))
+}
+
+/// Records the set of splits currently published for the targeted indexes and returns the id
+/// of the resulting point in time.
+pub(crate) async fn open_point_in_time(
+    open_request: OpenPointInTimeRequest,
+    mut metastore: MetastoreServiceClient,
+    cluster_client: &ClusterClient,
+) -> crate::Result<OpenPointInTimeResponse> {
+    let keep_alive = Duration::from_secs(open_request.keep_alive_secs as u64);
+    if keep_alive > MAX_POINT_IN_TIME_LIFETIME {
+        return Err(SearchError::InvalidArgument(format!(
+            "Quickwit only supports point in time keep alive up to {} secs",
+            MAX_POINT_IN_TIME_LIFETIME.as_secs()
+        )));
+    }
+    let indexes_metadata =
+        resolve_index_patterns(&open_request.index_id_patterns, &mut metastore).await?;
+    let index_uids = indexes_metadata
+        .into_iter()
+        .map(|index_metadata| index_metadata.index_uid)
+        .collect();
+    let split_metadatas = list_all_splits(index_uids, &mut metastore).await?;
+
+    let point_in_time_context = PointInTimeContext {
+        index_id_patterns: open_request.index_id_patterns,
+        split_metadatas,
+        opened_at: OffsetDateTime::now_utc().unix_timestamp(),
+    };
+    let pit_id = PointInTimeId::new();
+    cluster_client
+        .put_kv(
+            &pit_id.key(),
+            &point_in_time_context.serialize(),
+            keep_alive,
+        )
+        .await;
+    Ok(OpenPointInTimeResponse {
+        pit_id: pit_id.to_string(),
+    })
+}
+
+/// Loads the context of a point in time, extending its keep alive if requested.
+pub(crate) async fn load_point_in_time(
+    point_in_time: &PointInTime,
+    cluster_client: &ClusterClient,
+) -> crate::Result<PointInTimeContext> {
+    let pit_id = parse_point_in_time_id(&point_in_time.pit_id)?;
+    let payload = cluster_client
+        .get_kv(&pit_id.key())
+        .await
+        .filter(|payload| !payload.is_empty())
+        .ok_or_else(|| {
+            SearchError::InvalidArgument(format!(
+                "point in time `{}` not found or expired",
+                point_in_time.pit_id
+            ))
+        })?;
+    let point_in_time_context = PointInTimeContext::load(&payload)
+        .map_err(|err| SearchError::Internal(format!("corrupted point in time context: {err}")))?;
+
+    if let Some(keep_alive_secs) = point_in_time.keep_alive_secs {
+        let keep_alive = Duration::from_secs(keep_alive_secs as u64);
+        let ttl = point_in_time_context.ttl(keep_alive, OffsetDateTime::now_utc().unix_timestamp());
+        cluster_client.put_kv(&pit_id.key(), &payload, ttl).await;
+    }
+    Ok(point_in_time_context)
+}
+
+/// Closes a point in time.
+///
+/// The KV store does not support deletions: the context is overwritten with an expired empty
+/// entry instead.
+pub(crate) async fn close_point_in_time(
+    close_request: ClosePointInTimeRequest,
+    cluster_client: &ClusterClient,
+) -> crate::Result<ClosePointInTimeResponse> {
+    let pit_id = parse_point_in_time_id(&close_request.pit_id)?;
+    let succeeded = cluster_client
+        .get_kv(&pit_id.key())
+        .await
+        .filter(|payload| !payload.is_empty())
+        .is_some();
+    if succeeded {
+        cluster_client
+            .put_kv(&pit_id.key(), &[], Duration::ZERO)
+            .await;
+    }
+    Ok(ClosePointInTimeResponse { succeeded })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_point_in_time_id() {
+        let pit_id = PointInTimeId::new();
+        let pit_id_str = pit_id.to_string();
+        assert_eq!(PointInTimeId::from_str(&pit_id_str).unwrap(), pit_id);
+        assert!(PointInTimeId::from_str("not base64!").is_err());
+        assert!(PointInTimeId::from_str(&BASE64_STANDARD.encode([0u8; 4])).is_err());
+    }
+
+    #[test]
+    fn test_point_in_time_ttl_is_bounded_by_max_lifetime() {
+        let point_in_time_context = PointInTimeContext {
+            index_id_patterns: vec!["test-idx".to_string()],
+            split_metadatas: Vec::new(),
+            opened_at: 1_000,
+        };
+        let max_lifetime_secs = MAX_POINT_IN_TIME_LIFETIME.as_secs() as i64;
+        assert_eq!(
+            point_in_time_context.ttl(Duration::from_secs(60), 1_000),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            point_in_time_context.ttl(Duration::from_secs(60), 1_000 + max_lifetime_secs - 10),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            point_in_time_context.ttl(Duration::from_secs(60), 1_000 + max_lifetime_secs + 10),
+            Duration::ZERO
+        );
+    }
+}
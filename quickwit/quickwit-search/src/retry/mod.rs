@@ -102,8 +102,10 @@ mod tests {
     #[test]
     fn test_should_not_retry_if_result_is_ok() {
         let retry_policy = DefaultRetryPolicy {};
-        let response_res =
-            crate::Result::<FetchDocsResponse>::Ok(FetchDocsResponse { hits: Vec::new() });
+        let response_res = crate::Result::<FetchDocsResponse>::Ok(FetchDocsResponse {
+            hits: Vec::new(),
+            storage_request_stats: None,
+        });
         assert!(retry_policy.retry_request((), &response_res).is_none());
     }
 
@@ -133,6 +135,7 @@ mod tests {
             split_footer_start: 0,
             timestamp_start: None,
             timestamp_end: None,
+            storage_uri: None,
         };
         let client_for_retry = retry_client(
             &search_job_placer,
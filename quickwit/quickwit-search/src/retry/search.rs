@@ -74,6 +74,7 @@ mod tests {
             }),
             doc_mapper: "doc_mapper".to_string(),
             index_uri: "uri".to_string(),
+            index_id: "test-idx".to_string(),
             split_offsets: vec![
                 SplitIdAndFooterOffsets {
                     split_id: "split_1".to_string(),
@@ -81,6 +82,7 @@ mod tests {
                     split_footer_start: 0,
                     timestamp_start: None,
                     timestamp_end: None,
+                    storage_uri: None,
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
@@ -88,6 +90,7 @@ mod tests {
                     split_footer_start: 0,
                     timestamp_start: None,
                     timestamp_end: None,
+                    storage_uri: None,
                 },
             ],
         }
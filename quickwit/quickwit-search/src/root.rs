@@ -18,7 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::{HashMap, HashSet};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
 use futures::future::try_join_all;
@@ -27,20 +27,22 @@ use quickwit_common::shared_consts::{DELETION_GRACE_PERIOD, SCROLL_BATCH_LEN};
 use quickwit_common::uri::Uri;
 use quickwit_common::PrettySample;
 use quickwit_config::build_doc_mapper;
-use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
-use quickwit_doc_mapper::DYNAMIC_FIELD_NAME;
+use quickwit_doc_mapper::tag_pruning::extract_tags_from_query_with_derived_tags;
+use quickwit_doc_mapper::{DerivedTagField, DYNAMIC_FIELD_NAME};
 use quickwit_metastore::{IndexMetadata, ListIndexesMetadataResponseExt, SplitMetadata};
 use quickwit_proto::metastore::{
     ListIndexesMetadataRequest, MetastoreService, MetastoreServiceClient,
 };
 use quickwit_proto::search::{
-    FetchDocsRequest, FetchDocsResponse, Hit, LeafHit, LeafSearchRequest, LeafSearchResponse,
-    PartialHit, SearchRequest, SearchResponse, SnippetRequest, SortDatetimeFormat, SortField,
-    SortValue, SplitIdAndFooterOffsets,
+    CollapseRequest, CountHits, FetchDocsRequest, FetchDocsResponse, Hit, InnerHits,
+    InnerHitsRequest, LeafHit, LeafSearchRequest, LeafSearchResponse, PartialHit, SearchProfile,
+    SearchRequest, SearchResponse, SnippetRequest, SortDatetimeFormat, SortField, SortOrder,
+    SortValue, SplitIdAndFooterOffsets, StorageRequestStats,
 };
 use quickwit_proto::types::{IndexUid, SplitId};
 use quickwit_query::query_ast::{
-    BoolQuery, QueryAst, QueryAstVisitor, RangeQuery, TermQuery, TermSetQuery,
+    BoolQuery, FieldPresenceQuery, KnnQuery, QueryAst, QueryAstVisitor, RangeQuery, TermQuery,
+    TermSetQuery,
 };
 use serde::{Deserialize, Serialize};
 use tantivy::aggregation::agg_result::AggregationResults;
@@ -51,20 +53,28 @@ use tantivy::TantivyError;
 use tracing::{debug, error, info, info_span, instrument};
 
 use crate::cluster_client::ClusterClient;
-use crate::collector::{make_merge_collector, QuickwitAggregations};
+use crate::collector::{make_merge_collector, merge_storage_request_stats, QuickwitAggregations};
+use crate::extended_aggregations::IntermediateExtendedAggregationResults;
 use crate::find_trace_ids_collector::Span;
+use crate::leaf::resolve_sort_field_name;
+use crate::point_in_time::load_point_in_time;
+use crate::root_cache::{RootSearchCache, RootSearchCacheKey};
+use crate::runtime_fields::{
+    collector_uses_runtime_fields, parse_runtime_mappings, runtime_fields_schema,
+    split_runtime_query_ast, RuntimeFields, RuntimeMappings,
+};
 use crate::scroll_context::{ScrollContext, ScrollKeyAndStartOffset};
 use crate::search_job_placer::Job;
 use crate::service::SearcherContext;
 use crate::{
-    extract_split_and_footer_offsets, list_relevant_splits, SearchError, SearchJobPlacer,
-    SearchServiceClient,
+    extract_split_and_footer_offsets, list_relevant_splits, GlobalDocAddress, SearchError,
+    SearchJobPlacer, SearchServiceClient,
 };
 
 /// Maximum accepted scroll TTL.
 const MAX_SCROLL_TTL: Duration = Duration::from_secs(DELETION_GRACE_PERIOD.as_secs() - 60 * 2);
 
-const SORT_DOC_FIELD_NAMES: &[&str] = &["_shard_doc", "_doc"];
+pub(crate) const SORT_DOC_FIELD_NAMES: &[&str] = &["_shard_doc", "_doc"];
 
 /// SearchJob to be assigned to search clients by the [`SearchJobPlacer`].
 #[derive(Debug, Clone, PartialEq)]
@@ -155,6 +165,7 @@ struct RequestMetadata {
     query_ast_resolved: QueryAst,
     indexes_meta_for_leaf_search: IndexesMetasForLeafSearch,
     sort_fields_is_datetime: HashMap<String, bool>,
+    derived_tag_fields: Vec<DerivedTagField>,
 }
 
 /// Validates request against each index's doc mapper and ensures that:
@@ -174,13 +185,36 @@ fn validate_request_and_build_metadata(
         &search_request.sort_fields,
         &search_request.search_after,
     )?;
+    let inner_hits_request_opt = search_request
+        .collapse
+        .as_ref()
+        .and_then(|collapse| collapse.inner_hits.as_ref());
+    if let Some(inner_hits_request) = inner_hits_request_opt {
+        validate_sort_by_fields_and_search_after(&inner_hits_request.sort_fields, &None)?;
+    }
     let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast)
         .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let runtime_mappings = parse_runtime_mappings(search_request.runtime_mappings.as_deref())?;
+    let runtime_fields_schema = runtime_fields_schema(&runtime_mappings);
+    if !runtime_mappings.is_empty() {
+        // Compiles the scripts to report their errors before dispatching the leaf requests.
+        RuntimeFields::compile(&runtime_mappings)?;
+        collector_uses_runtime_fields(search_request, &runtime_mappings)?;
+    }
+    // Sort fields targeting runtime fields are validated against the runtime mappings.
+    let indexed_sort_fields = |sort_fields: &[SortField]| -> Vec<SortField> {
+        sort_fields
+            .iter()
+            .filter(|sort_field| !runtime_mappings.contains_key(&sort_field.field_name))
+            .cloned()
+            .collect()
+    };
     let mut indexes_meta_for_leaf_search: HashMap<IndexUid, IndexMetasForLeafSearch> =
         HashMap::new();
     let mut query_ast_resolved_opt: Option<QueryAst> = None;
     let mut timestamp_field_opt: Option<String> = None;
     let mut sort_fields_is_datetime: HashMap<String, bool> = HashMap::new();
+    let mut derived_tag_fields: Vec<DerivedTagField> = Vec::new();
 
     for index_metadata in indexes_metadata {
         let doc_mapper = build_doc_mapper(
@@ -227,16 +261,52 @@ fn validate_request_and_build_metadata(
 
         // Validate request against the current index schema.
         let schema = doc_mapper.schema();
-        validate_request(&schema, &doc_mapper.timestamp_field_name(), search_request)?;
+        validate_request(
+            &schema,
+            &doc_mapper.timestamp_field_name(),
+            search_request,
+            &runtime_mappings,
+        )?;
 
+        let resolve_field_name =
+            |field_name: &str| resolve_sort_field_name(doc_mapper.as_ref(), &schema, field_name);
         validate_sort_field_types(
             &schema,
-            &search_request.sort_fields,
+            &indexed_sort_fields(&search_request.sort_fields),
+            resolve_field_name,
             &mut sort_fields_is_datetime,
         )?;
+        if let Some(inner_hits_request) = inner_hits_request_opt {
+            validate_sort_field_types(
+                &schema,
+                &indexed_sort_fields(&inner_hits_request.sort_fields),
+                resolve_field_name,
+                &mut sort_fields_is_datetime,
+            )?;
+        }
 
-        // Validates the query by effectively building it against the current schema.
-        doc_mapper.query(doc_mapper.schema(), &query_ast_resolved_for_index, true)?;
+        // Validates the query by effectively building it against the current schema, and the
+        // clauses on runtime fields against the schema of the runtime fields.
+        let (indexed_query_ast, runtime_query_ast_opt) =
+            split_runtime_query_ast(query_ast_resolved_for_index, &runtime_mappings)?;
+        doc_mapper.query(doc_mapper.schema(), &indexed_query_ast, true)?;
+
+        if let Some(runtime_query_ast) = runtime_query_ast_opt {
+            runtime_query_ast
+                .build_tantivy_query(
+                    &runtime_fields_schema,
+                    doc_mapper.tokenizer_manager(),
+                    &[],
+                    true,
+                )
+                .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+        }
+
+        for derived_tag_field in doc_mapper.derived_tag_fields() {
+            if !derived_tag_fields.contains(&derived_tag_field) {
+                derived_tag_fields.push(derived_tag_field);
+            }
+        }
 
         let index_metadata_for_leaf_search = IndexMetasForLeafSearch {
             index_uri: index_metadata.index_uri().clone(),
@@ -261,17 +331,26 @@ fn validate_request_and_build_metadata(
         query_ast_resolved,
         indexes_meta_for_leaf_search,
         sort_fields_is_datetime,
+        derived_tag_fields,
     })
 }
 
 /// Validate sort field types.
+///
+/// `resolve_sort_field_name` returns the name of the fast field sorted on when it differs from
+/// the name of the sort field, e.g. for the unmapped fields captured by a dynamic template.
 fn validate_sort_field_types(
     schema: &Schema,
     sort_fields: &[SortField],
+    resolve_sort_field_name: impl Fn(&str) -> Option<String>,
     sort_field_is_datetime: &mut HashMap<String, bool>,
 ) -> crate::Result<()> {
     for sort_field in sort_fields.iter() {
-        if let Some(sort_field_entry) = get_sort_by_field_entry(&sort_field.field_name, schema)? {
+        let resolved_field_name_opt = resolve_sort_field_name(&sort_field.field_name);
+        let field_name = resolved_field_name_opt
+            .as_deref()
+            .unwrap_or(&sort_field.field_name);
+        if let Some(sort_field_entry) = get_sort_by_field_entry(field_name, schema)? {
             validate_sort_by_field_type(
                 sort_field_entry,
                 sort_field.sort_datetime_format.is_some(),
@@ -353,6 +432,10 @@ fn simplify_search_request_for_scroll_api(req: &SearchRequest) -> crate::Result<
         // request is simplified after initial query, and we cache the hit count, so we don't need
         // to recompute it afterward.
         count_hits: quickwit_proto::search::CountHits::Underestimate as i32,
+        profile: false,
+        point_in_time: None,
+        collapse: None,
+        runtime_mappings: req.runtime_mappings.clone(),
     })
 }
 
@@ -461,6 +544,7 @@ fn validate_request(
     schema: &Schema,
     timestamp_field_name: &Option<&str>,
     search_request: &SearchRequest,
+    runtime_mappings: &RuntimeMappings,
 ) -> crate::Result<()> {
     if timestamp_field_name.is_none()
         && (search_request.start_timestamp.is_some() || search_request.end_timestamp.is_some())
@@ -474,6 +558,17 @@ fn validate_request(
 
     validate_requested_snippet_fields(schema, &search_request.snippet_fields)?;
 
+    if let Some(collapse) = &search_request.collapse {
+        if search_request.scroll_ttl_secs.is_some() {
+            return Err(SearchError::InvalidArgument(
+                "collapse cannot be used in a scroll context".to_string(),
+            ));
+        }
+        if !runtime_mappings.contains_key(&collapse.field) {
+            validate_collapse_request(schema, collapse)?;
+        }
+    }
+
     if let Some(agg) = search_request.aggregation_request.as_ref() {
         let _aggs: QuickwitAggregations = serde_json::from_str(agg).map_err(|_err| {
             let err = serde_json::from_str::<tantivy::aggregation::agg_req::Aggregations>(agg)
@@ -499,6 +594,55 @@ fn validate_request(
     Ok(())
 }
 
+fn validate_collapse_request(schema: &Schema, collapse: &CollapseRequest) -> crate::Result<()> {
+    let field_name = &collapse.field;
+    let dynamic_field_opt = schema.get_field(DYNAMIC_FIELD_NAME).ok();
+    let (collapse_field, _json_path) = schema
+        .find_field_with_default(field_name, dynamic_field_opt)
+        .ok_or_else(|| {
+            SearchError::InvalidArgument(format!("unknown field used in `collapse`: {field_name}"))
+        })?;
+    if !schema.get_field_entry(collapse_field).is_fast() {
+        return Err(SearchError::InvalidArgument(format!(
+            "collapse field must be a fast field, please add the fast property to your field \
+             `{field_name}`",
+        )));
+    }
+    if let Some(inner_hits_request) = &collapse.inner_hits {
+        // The inner hits of a group are fetched with a term query on the collapse field, which
+        // only matches the collapse keys read from the fast field if the field is indexed
+        // verbatim.
+        if !is_indexed_verbatim(schema.get_field_entry(collapse_field)) {
+            return Err(SearchError::InvalidArgument(format!(
+                "inner hits require the collapse field `{field_name}` to be indexed with the \
+                 `raw` tokenizer or to be a non-text field"
+            )));
+        }
+        if inner_hits_request.size > 100 {
+            return Err(SearchError::InvalidArgument(format!(
+                "max value for inner hits size is 100, but got {}",
+                inner_hits_request.size
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether the values of a field are indexed as single untokenized terms.
+fn is_indexed_verbatim(field_entry: &FieldEntry) -> bool {
+    match field_entry.field_type() {
+        FieldType::Str(text_options) => text_options
+            .get_indexing_options()
+            .map(|text_indexing| text_indexing.tokenizer() == "raw")
+            .unwrap_or(false),
+        FieldType::JsonObject(json_options) => json_options
+            .get_text_indexing_options()
+            .map(|text_indexing| text_indexing.tokenizer() == "raw")
+            .unwrap_or(false),
+        field_type => field_type.is_indexed(),
+    }
+}
+
 fn get_scroll_ttl_duration(search_request: &SearchRequest) -> crate::Result<Option<Duration>> {
     let Some(scroll_ttl_secs) = search_request.scroll_ttl_secs else {
         return Ok(None);
@@ -520,6 +664,7 @@ async fn search_partial_hits_phase_with_scroll(
     mut search_request: SearchRequest,
     split_metadatas: &[SplitMetadata],
     cluster_client: &ClusterClient,
+    mut profile_opt: Option<&mut SearchProfile>,
 ) -> crate::Result<(LeafSearchResponse, Option<ScrollKeyAndStartOffset>)> {
     let scroll_ttl_opt = get_scroll_ttl_duration(&search_request)?;
 
@@ -536,6 +681,7 @@ async fn search_partial_hits_phase_with_scroll(
             &search_request,
             split_metadatas,
             cluster_client,
+            profile_opt.as_deref_mut(),
         )
         .await?;
         let cached_partial_hits = leaf_search_resp.partial_hits.clone();
@@ -579,6 +725,7 @@ async fn search_partial_hits_phase_with_scroll(
             &search_request,
             split_metadatas,
             cluster_client,
+            profile_opt,
         )
         .await?;
         Ok((leaf_search_resp, None))
@@ -622,10 +769,16 @@ fn get_count_from_metadata(split_metadatas: &[SplitMetadata]) -> Vec<LeafSearchR
             failed_splits: Vec::new(),
             num_attempted_splits: 1,
             intermediate_aggregation_result: None,
+            split_profiles: Vec::new(),
+            storage_request_stats: None,
         })
         .collect()
 }
 
+/// Runs the leaf search phase and merges the leaf responses.
+///
+/// If a `profile` is passed, the time spent merging the leaf responses and the split profiles
+/// are recorded into it.
 #[instrument(level = "debug", skip_all)]
 pub(crate) async fn search_partial_hits_phase(
     searcher_context: &SearcherContext,
@@ -633,29 +786,20 @@ pub(crate) async fn search_partial_hits_phase(
     search_request: &SearchRequest,
     split_metadatas: &[SplitMetadata],
     cluster_client: &ClusterClient,
+    profile_opt: Option<&mut SearchProfile>,
 ) -> crate::Result<LeafSearchResponse> {
     let leaf_search_responses: Vec<LeafSearchResponse> =
         if is_metadata_count_request(search_request) {
             get_count_from_metadata(split_metadatas)
         } else {
-            let jobs: Vec<SearchJob> = split_metadatas.iter().map(SearchJob::from).collect();
-            let assigned_leaf_search_jobs = cluster_client
-                .search_job_placer
-                .assign_jobs(jobs, &HashSet::default())
-                .await?;
-            let mut leaf_request_tasks = Vec::new();
-            for (client, client_jobs) in assigned_leaf_search_jobs {
-                let leaf_requests = jobs_to_leaf_requests(
-                    search_request,
-                    indexes_metas_for_leaf_search,
-                    client_jobs,
-                )?;
-                for leaf_request in leaf_requests {
-                    leaf_request_tasks
-                        .push(cluster_client.leaf_search(leaf_request, client.clone()));
-                }
-            }
-            try_join_all(leaf_request_tasks).await?
+            leaf_search_with_root_cache(
+                searcher_context,
+                indexes_metas_for_leaf_search,
+                search_request,
+                split_metadatas,
+                cluster_client,
+            )
+            .await?
         };
 
     // Creates a collector which merges responses into one
@@ -669,13 +813,32 @@ pub(crate) async fn search_partial_hits_phase(
     let leaf_search_responses: Vec<tantivy::Result<LeafSearchResponse>> =
         leaf_search_responses.into_iter().map(Ok).collect_vec();
     let span = info_span!("merge_fruits");
-    let leaf_search_response = crate::run_cpu_intensive(move || {
+    let merge_start = Instant::now();
+    let mut leaf_search_response = crate::run_cpu_intensive(move || {
         let _span_guard = span.enter();
         merge_collector.merge_fruits(leaf_search_responses)
     })
     .await
     .context("failed to merge leaf search responses")?
     .map_err(|error: TantivyError| crate::SearchError::Internal(error.to_string()))?;
+    if let Some(profile) = profile_opt {
+        profile.merge_micros = merge_start.elapsed().as_micros() as u64;
+        let split_id_to_index_id: HashMap<&str, &str> = split_metadatas
+            .iter()
+            .map(|split_metadata| {
+                (
+                    split_metadata.split_id.as_str(),
+                    split_metadata.index_uid.index_id(),
+                )
+            })
+            .collect();
+        profile.split_profiles = std::mem::take(&mut leaf_search_response.split_profiles);
+        for split_profile in &mut profile.split_profiles {
+            if let Some(index_id) = split_id_to_index_id.get(split_profile.split_id.as_str()) {
+                split_profile.index_id = index_id.to_string();
+            }
+        }
+    }
     debug!(
         num_hits = leaf_search_response.num_hits,
         failed_splits = ?leaf_search_response.failed_splits,
@@ -691,6 +854,90 @@ pub(crate) async fn search_partial_hits_phase(
     Ok(leaf_search_response)
 }
 
+/// Runs the leaf search of the request on the splits, reusing the responses of the splits found
+/// in the root search cache.
+///
+/// The splits whose response can be cached are searched with a leaf request of their own, so
+/// that their response is not merged with the responses of other splits.
+async fn leaf_search_with_root_cache(
+    searcher_context: &SearcherContext,
+    indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
+    search_request: &SearchRequest,
+    split_metadatas: &[SplitMetadata],
+    cluster_client: &ClusterClient,
+) -> crate::Result<Vec<LeafSearchResponse>> {
+    let is_cacheable = RootSearchCache::is_cacheable(search_request);
+    let mut leaf_search_responses: Vec<LeafSearchResponse> = Vec::new();
+    let mut cache_keys: HashMap<String, RootSearchCacheKey> = HashMap::new();
+    let mut jobs: Vec<SearchJob> = Vec::with_capacity(split_metadatas.len());
+
+    for split_metadata in split_metadatas {
+        let cache_key_opt = if is_cacheable {
+            RootSearchCacheKey::for_split(split_metadata, search_request)
+        } else {
+            None
+        };
+        if let Some(cache_key) = cache_key_opt {
+            if let Some(leaf_search_response) = searcher_context.root_search_cache.get(&cache_key) {
+                leaf_search_responses.push(leaf_search_response);
+                continue;
+            }
+            cache_keys.insert(split_metadata.split_id.clone(), cache_key);
+        }
+        jobs.push(SearchJob::from(split_metadata));
+    }
+    if jobs.is_empty() {
+        return Ok(leaf_search_responses);
+    }
+    let assigned_leaf_search_jobs = cluster_client
+        .search_job_placer
+        .assign_jobs(jobs, &HashSet::default())
+        .await?;
+    let mut leaf_requests: Vec<(
+        Option<RootSearchCacheKey>,
+        SearchServiceClient,
+        LeafSearchRequest,
+    )> = Vec::new();
+    for (client, client_jobs) in assigned_leaf_search_jobs {
+        let (cacheable_jobs, other_jobs): (Vec<SearchJob>, Vec<SearchJob>) = client_jobs
+            .into_iter()
+            .partition(|job| cache_keys.contains_key(&job.offsets.split_id));
+        for job in cacheable_jobs {
+            let mut cache_key_opt = cache_keys.remove(&job.offsets.split_id);
+            for leaf_request in
+                jobs_to_leaf_requests(search_request, indexes_metas_for_leaf_search, vec![job])?
+            {
+                leaf_requests.push((cache_key_opt.take(), client.clone(), leaf_request));
+            }
+        }
+        for leaf_request in
+            jobs_to_leaf_requests(search_request, indexes_metas_for_leaf_search, other_jobs)?
+        {
+            leaf_requests.push((None, client.clone(), leaf_request));
+        }
+    }
+    let leaf_request_tasks =
+        leaf_requests
+            .into_iter()
+            .map(|(cache_key_opt, client, leaf_request)| {
+                let leaf_search_future = cluster_client.leaf_search(leaf_request, client);
+                async move {
+                    leaf_search_future
+                        .await
+                        .map(|leaf_search_response| (cache_key_opt, leaf_search_response))
+                }
+            });
+    for (cache_key_opt, leaf_search_response) in try_join_all(leaf_request_tasks).await? {
+        if let Some(cache_key) = cache_key_opt {
+            searcher_context
+                .root_search_cache
+                .put(cache_key, leaf_search_response.clone());
+        }
+        leaf_search_responses.push(leaf_search_response);
+    }
+    Ok(leaf_search_responses)
+}
+
 pub(crate) fn get_snippet_request(search_request: &SearchRequest) -> Option<SnippetRequest> {
     if search_request.snippet_fields.is_empty() {
         return None;
@@ -708,7 +955,7 @@ pub(crate) async fn fetch_docs_phase(
     split_metadatas: &[SplitMetadata],
     search_request: &SearchRequest,
     cluster_client: &ClusterClient,
-) -> crate::Result<Vec<Hit>> {
+) -> crate::Result<(Vec<Hit>, Option<StorageRequestStats>)> {
     let snippet_request: Option<SnippetRequest> = get_snippet_request(search_request);
     let hit_order: HashMap<(String, u32, u32), usize> = partial_hits
         .iter()
@@ -734,6 +981,7 @@ pub(crate) async fn fetch_docs_phase(
     for (client, client_jobs) in assigned_fetch_docs_jobs {
         let fetch_jobs_requests = jobs_to_fetch_docs_requests(
             snippet_request.clone(),
+            search_request.runtime_mappings.clone(),
             indexes_metas_for_leaf_search,
             client_jobs,
         )?;
@@ -742,6 +990,11 @@ pub(crate) async fn fetch_docs_phase(
         }
     }
     let fetch_docs_responses: Vec<FetchDocsResponse> = try_join_all(fetch_docs_tasks).await?;
+    let storage_request_stats = merge_storage_request_stats(
+        fetch_docs_responses
+            .iter()
+            .map(|response| &response.storage_request_stats),
+    );
 
     // Merge the fetched docs.
     let leaf_hits = fetch_docs_responses
@@ -782,7 +1035,7 @@ pub(crate) async fn fetch_docs_phase(
         .map(|(_position, hit)| hit)
         .collect();
 
-    Ok(hits)
+    Ok((hits, storage_request_stats))
 }
 
 fn build_hit_with_position(
@@ -832,10 +1085,117 @@ fn build_hit_with_position(
             partial_hit: leaf_hit.partial_hit,
             snippet: leaf_hit.leaf_snippet_json,
             index_id,
+            inner_hits: Vec::new(),
         },
     ))
 }
 
+/// Expands each collapsed hit with the top hits of its collapse group.
+///
+/// The top hits of a group are obtained by running the original query, restricted to the
+/// documents of the group, on the same splits.
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip_all, fields(num_hits=hits.len()))]
+async fn expand_inner_hits_phase(
+    searcher_context: &SearcherContext,
+    indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
+    search_request: &SearchRequest,
+    collapse_field: &str,
+    inner_hits_request: &InnerHitsRequest,
+    split_metadatas: &[SplitMetadata],
+    cluster_client: &ClusterClient,
+    hits: &mut [Hit],
+) -> crate::Result<()> {
+    let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast)
+        .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let sort_fields = if inner_hits_request.sort_fields.is_empty() {
+        search_request.sort_fields.clone()
+    } else {
+        inner_hits_request.sort_fields.clone()
+    };
+    let mut inner_hits_futures = Vec::with_capacity(hits.len());
+    for hit in hits.iter() {
+        let collapse_key_opt = hit
+            .partial_hit
+            .as_ref()
+            .and_then(|partial_hit| partial_hit.collapse_key.clone());
+        let group_query_ast =
+            collapse_group_query_ast(query_ast.clone(), collapse_field, collapse_key_opt);
+        let inner_search_request = SearchRequest {
+            query_ast: serde_json::to_string(&group_query_ast)?,
+            max_hits: inner_hits_request.size,
+            start_offset: 0,
+            sort_fields: sort_fields.clone(),
+            aggregation_request: None,
+            scroll_ttl_secs: None,
+            search_after: None,
+            count_hits: CountHits::CountAll as i32,
+            profile: false,
+            point_in_time: None,
+            collapse: None,
+            ..search_request.clone()
+        };
+        inner_hits_futures.push(async move {
+            let leaf_search_response = search_partial_hits_phase(
+                searcher_context,
+                indexes_metas_for_leaf_search,
+                &inner_search_request,
+                split_metadatas,
+                cluster_client,
+                None,
+            )
+            .await?;
+            let (hits, _storage_request_stats) = fetch_docs_phase(
+                indexes_metas_for_leaf_search,
+                &leaf_search_response.partial_hits,
+                split_metadatas,
+                &inner_search_request,
+                cluster_client,
+            )
+            .await?;
+            crate::Result::Ok(InnerHits {
+                name: inner_hits_request.name.clone(),
+                num_hits: leaf_search_response.num_hits,
+                hits,
+            })
+        });
+    }
+    let inner_hits_per_hit: Vec<InnerHits> = try_join_all(inner_hits_futures).await?;
+    for (hit, inner_hits) in hits.iter_mut().zip(inner_hits_per_hit) {
+        hit.inner_hits.push(inner_hits);
+    }
+    Ok(())
+}
+
+/// Restricts a query to the documents of a collapse group. Documents without a value for the
+/// collapse field all belong to the same group.
+fn collapse_group_query_ast(
+    query_ast: QueryAst,
+    collapse_field: &str,
+    collapse_key_opt: Option<String>,
+) -> QueryAst {
+    let mut bool_query = BoolQuery {
+        must: vec![query_ast],
+        ..Default::default()
+    };
+    if let Some(collapse_key) = collapse_key_opt {
+        bool_query.filter.push(
+            TermQuery {
+                field: collapse_field.to_string(),
+                value: collapse_key,
+            }
+            .into(),
+        );
+    } else {
+        bool_query
+            .must_not
+            .push(QueryAst::FieldPresence(FieldPresenceQuery {
+                field: collapse_field.to_string(),
+            }));
+    }
+    QueryAst::Bool(bool_query)
+}
+
 fn get_sort_field_datetime_format(
     sort_field: Option<&SortField>,
 ) -> crate::Result<Option<SortDatetimeFormat>> {
@@ -864,6 +1224,10 @@ async fn root_search_aux(
     cluster_client: &ClusterClient,
 ) -> crate::Result<SearchResponse> {
     debug!(split_metadatas = ?PrettySample::new(&split_metadatas, 5));
+    let mut profile_opt: Option<SearchProfile> =
+        search_request.profile.then(SearchProfile::default);
+
+    let leaf_search_phase_start = Instant::now();
     let (first_phase_result, scroll_key_and_start_offset_opt): (
         LeafSearchResponse,
         Option<ScrollKeyAndStartOffset>,
@@ -873,10 +1237,13 @@ async fn root_search_aux(
         search_request.clone(),
         &split_metadatas[..],
         cluster_client,
+        profile_opt.as_mut(),
     )
     .await?;
+    let leaf_search_phase_duration = leaf_search_phase_start.elapsed();
 
-    let hits = fetch_docs_phase(
+    let fetch_docs_phase_start = Instant::now();
+    let (mut hits, fetch_docs_storage_request_stats) = fetch_docs_phase(
         indexes_metas_for_leaf_search,
         &first_phase_result.partial_hits,
         &split_metadatas[..],
@@ -884,12 +1251,48 @@ async fn root_search_aux(
         cluster_client,
     )
     .await?;
+    if let Some(collapse) = &search_request.collapse {
+        if let Some(inner_hits_request) = &collapse.inner_hits {
+            expand_inner_hits_phase(
+                searcher_context,
+                indexes_metas_for_leaf_search,
+                &search_request,
+                &collapse.field,
+                inner_hits_request,
+                &split_metadatas[..],
+                cluster_client,
+                &mut hits,
+            )
+            .await?;
+        }
+    }
+    let (aggregation_docs, fetch_aggregation_docs_storage_request_stats) =
+        fetch_aggregation_docs_phase(
+            indexes_metas_for_leaf_search,
+            &search_request,
+            first_phase_result
+                .intermediate_aggregation_result
+                .as_deref(),
+            &split_metadatas[..],
+            cluster_client,
+        )
+        .await?;
+    let fetch_docs_phase_duration = fetch_docs_phase_start.elapsed();
 
+    let finalize_aggregation_start = Instant::now();
     let mut aggregation_result_json_opt = finalize_aggregation_if_any(
         &search_request,
         first_phase_result.intermediate_aggregation_result,
+        &aggregation_docs,
         searcher_context,
     )?;
+    let finalize_aggregation_duration = finalize_aggregation_start.elapsed();
+
+    if let Some(profile) = profile_opt.as_mut() {
+        profile.leaf_search_phase_micros = leaf_search_phase_duration.as_micros() as u64;
+        profile.fetch_docs_phase_micros = fetch_docs_phase_duration.as_micros() as u64;
+        profile.finalize_aggregation_micros = finalize_aggregation_duration.as_micros() as u64;
+    }
     // In case there is no index, we don't want the response to contain any aggregation structure
     if indexes_metas_for_leaf_search.is_empty() {
         aggregation_result_json_opt = None;
@@ -904,12 +1307,73 @@ async fn root_search_aux(
         scroll_id: scroll_key_and_start_offset_opt
             .as_ref()
             .map(ToString::to_string),
+        profile: profile_opt,
+        pit_id: None,
+        storage_request_stats: merge_storage_request_stats([
+            &first_phase_result.storage_request_stats,
+            &fetch_docs_storage_request_stats,
+            &fetch_aggregation_docs_storage_request_stats,
+        ]),
     })
 }
 
+/// Fetches the documents returned by the `top_hits` aggregations, if any.
+async fn fetch_aggregation_docs_phase(
+    indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
+    search_request: &SearchRequest,
+    intermediate_aggregation_result_bytes_opt: Option<&[u8]>,
+    split_metadatas: &[SplitMetadata],
+    cluster_client: &ClusterClient,
+) -> crate::Result<(HashMap<GlobalDocAddress, Hit>, Option<StorageRequestStats>)> {
+    let Some(aggregations_json) = search_request.aggregation_request.as_ref() else {
+        return Ok((HashMap::new(), None));
+    };
+    let Some(intermediate_aggregation_result_bytes) = intermediate_aggregation_result_bytes_opt
+    else {
+        return Ok((HashMap::new(), None));
+    };
+    let aggregations: QuickwitAggregations = serde_json::from_str(aggregations_json)?;
+    if !matches!(aggregations, QuickwitAggregations::ExtendedAggregations(_)) {
+        return Ok((HashMap::new(), None));
+    }
+    let intermediate_aggregation_results: IntermediateExtendedAggregationResults =
+        postcard::from_bytes(intermediate_aggregation_result_bytes)?;
+    let partial_hits: Vec<PartialHit> = intermediate_aggregation_results
+        .partial_hits()
+        .into_iter()
+        .unique_by(GlobalDocAddress::from_partial_hit)
+        .collect();
+    if partial_hits.is_empty() {
+        return Ok((HashMap::new(), None));
+    }
+    // Aggregation hits are returned without snippets, and their sort values are returned as is.
+    let fetch_docs_request = SearchRequest {
+        snippet_fields: Vec::new(),
+        sort_fields: Vec::new(),
+        ..search_request.clone()
+    };
+    let (hits, storage_request_stats) = fetch_docs_phase(
+        indexes_metas_for_leaf_search,
+        &partial_hits,
+        split_metadatas,
+        &fetch_docs_request,
+        cluster_client,
+    )
+    .await?;
+    let aggregation_docs = hits
+        .into_iter()
+        .filter_map(|hit| {
+            let global_doc_address = GlobalDocAddress::from_partial_hit(hit.partial_hit.as_ref()?);
+            Some((global_doc_address, hit))
+        })
+        .collect();
+    Ok((aggregation_docs, storage_request_stats))
+}
+
 fn finalize_aggregation(
     intermediate_aggregation_result_bytes_opt: Option<Vec<u8>>,
     aggregations: QuickwitAggregations,
+    aggregation_docs: &HashMap<GlobalDocAddress, Hit>,
     searcher_context: &SearcherContext,
 ) -> crate::Result<Option<String>> {
     let merge_aggregation_result = match aggregations {
@@ -923,6 +1387,22 @@ fn finalize_aggregation(
             let aggs: Vec<Span> = postcard::from_bytes(&intermediate_aggregation_result_bytes)?;
             serde_json::to_string(&aggs)?
         }
+        QuickwitAggregations::ExtendedAggregations(aggregations) => {
+            let intermediate_aggregation_results: IntermediateExtendedAggregationResults =
+                if let Some(intermediate_aggregation_result_bytes) =
+                    intermediate_aggregation_result_bytes_opt
+                {
+                    postcard::from_bytes(&intermediate_aggregation_result_bytes)?
+                } else {
+                    Default::default()
+                };
+            let final_aggregation_results: serde_json::Value = aggregations.into_final_result(
+                intermediate_aggregation_results,
+                &searcher_context.get_aggregation_limits(),
+                aggregation_docs,
+            )?;
+            serde_json::to_string(&final_aggregation_results)?
+        }
         QuickwitAggregations::TantivyAggregations(aggregations) => {
             let intermediate_aggregation_results =
                 if let Some(intermediate_aggregation_result_bytes) =
@@ -946,6 +1426,7 @@ fn finalize_aggregation(
 fn finalize_aggregation_if_any(
     search_request: &SearchRequest,
     intermediate_aggregation_result_bytes_opt: Option<Vec<u8>>,
+    aggregation_docs: &HashMap<GlobalDocAddress, Hit>,
     searcher_context: &SearcherContext,
 ) -> crate::Result<Option<String>> {
     let Some(aggregations_json) = search_request.aggregation_request.as_ref() else {
@@ -955,6 +1436,7 @@ fn finalize_aggregation_if_any(
     let aggregation_result_json = finalize_aggregation(
         intermediate_aggregation_result_bytes_opt,
         aggregations,
+        aggregation_docs,
         searcher_context,
     )?;
     Ok(aggregation_result_json)
@@ -1014,6 +1496,30 @@ pub async fn root_search(
 ) -> crate::Result<SearchResponse> {
     info!(searcher_context = ?searcher_context, search_request = ?search_request);
     let start_instant = tokio::time::Instant::now();
+    let point_in_time_context_opt = match &search_request.point_in_time {
+        Some(point_in_time) => {
+            if search_request.scroll_ttl_secs.is_some() {
+                return Err(SearchError::InvalidArgument(
+                    "scroll cannot be used with a point in time".to_string(),
+                ));
+            }
+            let point_in_time_context = load_point_in_time(point_in_time, cluster_client).await?;
+            if search_request.index_id_patterns.is_empty() {
+                search_request.index_id_patterns = point_in_time_context.index_id_patterns.clone();
+            } else if search_request.index_id_patterns != point_in_time_context.index_id_patterns {
+                return Err(SearchError::InvalidArgument(format!(
+                    "point in time was opened on indexes {:?}, but the request targets {:?}",
+                    point_in_time_context.index_id_patterns, search_request.index_id_patterns
+                )));
+            }
+            Some(point_in_time_context)
+        }
+        None => None,
+    };
+    let pit_id_opt = search_request
+        .point_in_time
+        .as_ref()
+        .map(|point_in_time| point_in_time.pit_id.clone());
     let list_indexes_metadatas_request = ListIndexesMetadataRequest {
         index_id_patterns: search_request.index_id_patterns.clone(),
     };
@@ -1044,6 +1550,7 @@ pub async fn root_search(
         .iter()
         .map(|index_metadata| index_metadata.index_uid.clone())
         .collect_vec();
+    set_default_sort_for_knn_query(&mut search_request)?;
     let request_metadata = validate_request_and_build_metadata(&indexes_metadata, &search_request)?;
     search_request.query_ast = serde_json::to_string(&request_metadata.query_ast_resolved)?;
 
@@ -1052,6 +1559,16 @@ pub async fn root_search(
         &mut search_request,
         &request_metadata.sort_fields_is_datetime,
     )?;
+    if let Some(inner_hits_request) = search_request
+        .collapse
+        .as_mut()
+        .and_then(|collapse| collapse.inner_hits.as_mut())
+    {
+        set_default_sort_datetime_format(
+            &mut inner_hits_request.sort_fields,
+            &request_metadata.sort_fields_is_datetime,
+        );
+    }
 
     // update_search_after_datetime_in_nanos(&mut search_request)?;
     if let Some(timestamp_field) = &request_metadata.timestamp_field_opt {
@@ -1062,18 +1579,40 @@ pub async fn root_search(
             &mut search_request.end_timestamp,
         );
     }
-    let tag_filter_ast = extract_tags_from_query(request_metadata.query_ast_resolved);
+    let tag_filter_ast = extract_tags_from_query_with_derived_tags(
+        request_metadata.query_ast_resolved,
+        &request_metadata.derived_tag_fields,
+    );
 
-    // TODO if search after is set, we sort by timestamp and we don't want to count all results,
-    // we can refine more here. Same if we sort by _shard_doc
-    let split_metadatas: Vec<SplitMetadata> = list_relevant_splits(
-        index_uids,
-        search_request.start_timestamp,
-        search_request.end_timestamp,
-        tag_filter_ast,
-        &mut metastore,
-    )
-    .await?;
+    let split_metadatas: Vec<SplitMetadata> =
+        if let Some(point_in_time_context) = point_in_time_context_opt {
+            // The splits recorded by the point in time are searched even if they have since been
+            // merged or marked for deletion.
+            let index_uids: HashSet<IndexUid> = index_uids.into_iter().collect();
+            point_in_time_context
+                .split_metadatas
+                .into_iter()
+                .filter(|split_metadata| index_uids.contains(&split_metadata.index_uid))
+                .filter(|split_metadata| {
+                    is_split_in_time_range(
+                        split_metadata,
+                        search_request.start_timestamp,
+                        search_request.end_timestamp,
+                    )
+                })
+                .collect()
+        } else {
+            // TODO if search after is set, we sort by timestamp and we don't want to count all
+            // results, we can refine more here. Same if we sort by _shard_doc
+            list_relevant_splits(
+                index_uids,
+                search_request.start_timestamp,
+                search_request.end_timestamp,
+                tag_filter_ast,
+                &mut metastore,
+            )
+            .await?
+        };
 
     let mut search_response = root_search_aux(
         searcher_context,
@@ -1085,9 +1624,32 @@ pub async fn root_search(
     .await?;
 
     search_response.elapsed_time_micros = start_instant.elapsed().as_micros() as u64;
+    search_response.pit_id = pit_id_opt;
     Ok(search_response)
 }
 
+/// Returns true if the time range of the split intersects with the `[start, end)` time range.
+fn is_split_in_time_range(
+    split_metadata: &SplitMetadata,
+    start_timestamp_opt: Option<i64>,
+    end_timestamp_opt: Option<i64>,
+) -> bool {
+    let Some(time_range) = &split_metadata.time_range else {
+        return true;
+    };
+    if let Some(start_timestamp) = start_timestamp_opt {
+        if *time_range.end() < start_timestamp {
+            return false;
+        }
+    }
+    if let Some(end_timestamp) = end_timestamp_opt {
+        if *time_range.start() >= end_timestamp {
+            return false;
+        }
+    }
+    true
+}
+
 /// Converts search after with datetime format to nanoseconds (representation in tantivy).
 /// If the sort field is a datetime field and no datetime format is set, the default format is
 /// milliseconds.
@@ -1096,15 +1658,7 @@ fn convert_search_after_datetime_values(
     search_request: &mut SearchRequest,
     sort_fields_is_datetime: &HashMap<String, bool>,
 ) -> crate::Result<()> {
-    for sort_field in search_request.sort_fields.iter_mut() {
-        if *sort_fields_is_datetime
-            .get(&sort_field.field_name)
-            .unwrap_or(&false)
-            && sort_field.sort_datetime_format.is_none()
-        {
-            sort_field.sort_datetime_format = Some(SortDatetimeFormat::UnixTimestampMillis as i32);
-        }
-    }
+    set_default_sort_datetime_format(&mut search_request.sort_fields, sort_fields_is_datetime);
     if let Some(partial_hit) = search_request.search_after.as_mut() {
         let search_after_values = [
             partial_hit.sort_value.as_mut(),
@@ -1131,6 +1685,59 @@ fn convert_search_after_datetime_values(
     Ok(())
 }
 
+/// Sorts requests containing a kNN query and no explicit sort by descending score, so that the
+/// hits are the most similar documents across all splits.
+fn set_default_sort_for_knn_query(search_request: &mut SearchRequest) -> crate::Result<()> {
+    if !search_request.sort_fields.is_empty() {
+        return Ok(());
+    }
+    let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast)
+        .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let mut contains_knn_query = ContainsKnnQuery::default();
+    contains_knn_query
+        .visit(&query_ast)
+        .expect("can't fail unwrapping Infallible");
+
+    if contains_knn_query.contains_knn_query {
+        search_request.sort_fields.push(SortField {
+            field_name: "_score".to_string(),
+            sort_order: SortOrder::Desc as i32,
+            sort_datetime_format: None,
+        });
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct ContainsKnnQuery {
+    contains_knn_query: bool,
+}
+
+impl<'a> QueryAstVisitor<'a> for ContainsKnnQuery {
+    type Err = std::convert::Infallible;
+
+    fn visit_knn(&mut self, _knn_query: &'a KnnQuery) -> Result<(), Self::Err> {
+        self.contains_knn_query = true;
+        Ok(())
+    }
+}
+
+/// Sets the datetime format of datetime sort fields without a format to milliseconds.
+fn set_default_sort_datetime_format(
+    sort_fields: &mut [SortField],
+    sort_fields_is_datetime: &HashMap<String, bool>,
+) {
+    for sort_field in sort_fields.iter_mut() {
+        if *sort_fields_is_datetime
+            .get(&sort_field.field_name)
+            .unwrap_or(&false)
+            && sort_field.sort_datetime_format.is_none()
+        {
+            sort_field.sort_datetime_format = Some(SortDatetimeFormat::UnixTimestampMillis as i32);
+        }
+    }
+}
+
 /// Convert sort values from input datetime format into nanoseconds.
 /// The conversion is done only for U64 and I64 sort values, an error is returned for other types.
 fn convert_sort_datetime_value_into_nanos(
@@ -1396,7 +2003,8 @@ fn compute_split_cost(_split_metadata: &SplitMetadata) -> usize {
     1
 }
 
-/// Builds a list of [`LeafSearchRequest`], one per index, from a list of [`SearchJob`].
+/// Builds a list of [`LeafSearchRequest`], one per index and storage, from a list of
+/// [`SearchJob`].
 pub fn jobs_to_leaf_requests(
     request: &SearchRequest,
     search_indexes_metadatas: &IndexesMetasForLeafSearch,
@@ -1406,8 +2014,12 @@ pub fn jobs_to_leaf_requests(
     search_request_for_leaf.start_offset = 0;
     search_request_for_leaf.max_hits += request.start_offset;
     let mut leaf_search_requests = Vec::new();
-    // Group jobs by index uid.
-    for (index_uid, job_group) in &jobs.into_iter().group_by(|job| job.index_uid.clone()) {
+    // Group jobs by index uid and by storage, splits moved by a tiering policy being read from
+    // their storage tier.
+    for ((index_uid, storage_uri_opt), job_group) in &jobs
+        .into_iter()
+        .group_by(|job| (job.index_uid.clone(), job.offsets.storage_uri.clone()))
+    {
         let search_index_meta = search_indexes_metadatas.get(&index_uid).ok_or_else(|| {
             SearchError::Internal(format!(
                 "received search job for an unknown index {index_uid}. it should never happen"
@@ -1417,22 +2029,28 @@ pub fn jobs_to_leaf_requests(
             search_request: Some(search_request_for_leaf.clone()),
             split_offsets: job_group.into_iter().map(|job| job.offsets).collect(),
             doc_mapper: search_index_meta.doc_mapper_str.clone(),
-            index_uri: search_index_meta.index_uri.to_string(),
+            index_uri: storage_uri_opt.unwrap_or_else(|| search_index_meta.index_uri.to_string()),
+            index_id: index_uid.index_id().to_string(),
         };
         leaf_search_requests.push(leaf_search_request);
     }
     Ok(leaf_search_requests)
 }
 
-/// Builds a list of [`FetchDocsRequest`], one per index, from a list of [`FetchDocsJob`].
+/// Builds a list of [`FetchDocsRequest`], one per index and storage, from a list of
+/// [`FetchDocsJob`].
 pub fn jobs_to_fetch_docs_requests(
     snippet_request_opt: Option<SnippetRequest>,
+    runtime_mappings_opt: Option<String>,
     indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
     jobs: Vec<FetchDocsJob>,
 ) -> crate::Result<Vec<FetchDocsRequest>> {
     let mut fetch_docs_requests = Vec::new();
-    // Group jobs by index uid.
-    for (index_uid, job_group) in &jobs.into_iter().group_by(|job| job.index_uid.clone()) {
+    // Group jobs by index uid and by storage.
+    for ((index_uid, storage_uri_opt), job_group) in &jobs
+        .into_iter()
+        .group_by(|job| (job.index_uid.clone(), job.offsets.storage_uri.clone()))
+    {
         let index_meta = indexes_metas_for_leaf_search
             .get(&index_uid)
             .ok_or_else(|| {
@@ -1452,9 +2070,11 @@ pub fn jobs_to_fetch_docs_requests(
         let fetch_docs_req = FetchDocsRequest {
             partial_hits,
             split_offsets,
-            index_uri: index_meta.index_uri.to_string(),
+            index_uri: storage_uri_opt.unwrap_or_else(|| index_meta.index_uri.to_string()),
             snippet_request: snippet_request_opt.clone(),
             doc_mapper: index_meta.doc_mapper_str.clone(),
+            index_id: index_uid.index_id().to_string(),
+            runtime_mappings: runtime_mappings_opt.clone(),
         };
         fetch_docs_requests.push(fetch_docs_req);
     }
@@ -1474,12 +2094,14 @@ mod tests {
     use quickwit_metastore::{IndexMetadata, ListSplitsRequestExt, ListSplitsResponseExt};
     use quickwit_proto::metastore::{ListIndexesMetadataResponse, ListSplitsResponse};
     use quickwit_proto::search::{
-        ScrollRequest, SortByValue, SortOrder, SortValue, SplitSearchError,
+        OpenPointInTimeRequest, PointInTime, ScrollRequest, SortByValue, SortOrder, SortValue,
+        SplitSearchError,
     };
     use quickwit_query::query_ast::{qast_helper, qast_json_helper, query_ast_from_user_text};
-    use tantivy::schema::{FAST, STORED, TEXT};
+    use tantivy::schema::{FAST, INDEXED, STORED, STRING, TEXT};
 
     use super::*;
+    use crate::point_in_time::open_point_in_time;
     use crate::{searcher_pool_for_test, MockSearchService};
 
     #[track_caller]
@@ -1492,6 +2114,61 @@ mod tests {
         validate_requested_snippet_fields(&schema, snippet_fields)
     }
 
+    #[test]
+    fn test_validate_collapse_request() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("title", TEXT | FAST);
+        schema_builder.add_text_field("tag", STRING | FAST);
+        schema_builder.add_u64_field("count", FAST);
+        schema_builder.add_u64_field("id", INDEXED | FAST);
+        let schema = schema_builder.build();
+
+        let collapse_request = |field: &str, with_inner_hits: bool| CollapseRequest {
+            field: field.to_string(),
+            inner_hits: with_inner_hits.then(|| InnerHitsRequest {
+                name: "inner".to_string(),
+                size: 3,
+                sort_fields: Vec::new(),
+            }),
+        };
+        for field in ["title", "tag", "count", "id"] {
+            validate_collapse_request(&schema, &collapse_request(field, false)).unwrap();
+        }
+        validate_collapse_request(&schema, &collapse_request("tag", true)).unwrap();
+        validate_collapse_request(&schema, &collapse_request("id", true)).unwrap();
+
+        for field in ["title", "count"] {
+            let error =
+                validate_collapse_request(&schema, &collapse_request(field, true)).unwrap_err();
+            assert!(matches!(error, SearchError::InvalidArgument(_)));
+        }
+    }
+
+    #[test]
+    fn test_jobs_to_leaf_requests_groups_splits_by_storage() {
+        let index_uid = IndexUid::from("test-index:0");
+        let mut search_indexes_metadatas = IndexesMetasForLeafSearch::new();
+        search_indexes_metadatas.insert(
+            index_uid.clone(),
+            IndexMetasForLeafSearch {
+                index_uri: Uri::for_test("ram:///test-index"),
+                doc_mapper_str: "{}".to_string(),
+            },
+        );
+        let mut tiered_job = SearchJob::for_test("split-2", 1);
+        tiered_job.offsets.storage_uri = Some("ram:///cold/test-index".to_string());
+        let jobs = vec![SearchJob::for_test("split-1", 1), tiered_job];
+
+        let leaf_requests =
+            jobs_to_leaf_requests(&SearchRequest::default(), &search_indexes_metadatas, jobs)
+                .unwrap();
+        assert_eq!(leaf_requests.len(), 2);
+        assert_eq!(leaf_requests[0].index_uri, "ram:///test-index");
+        assert_eq!(leaf_requests[0].split_offsets[0].split_id, "split-1");
+        assert_eq!(leaf_requests[1].index_uri, "ram:///cold/test-index");
+        assert_eq!(leaf_requests[1].split_offsets[0].split_id, "split-2");
+    }
+
     #[test]
     fn test_validate_requested_snippet_fields() {
         check_snippet_fields_validation(&["desc".to_string()]).unwrap();
@@ -1537,6 +2214,53 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_set_default_sort_for_knn_query() {
+        let knn_query_ast = serde_json::json!({
+            "type": "bool",
+            "must": [{
+                "type": "knn",
+                "field": "embedding",
+                "query_vector": [1.0, 0.0],
+                "k": 10
+            }]
+        })
+        .to_string();
+        let mut search_request = SearchRequest {
+            query_ast: knn_query_ast.clone(),
+            ..Default::default()
+        };
+        set_default_sort_for_knn_query(&mut search_request).unwrap();
+        assert_eq!(
+            search_request.sort_fields,
+            [SortField {
+                field_name: "_score".to_string(),
+                sort_order: SortOrder::Desc as i32,
+                sort_datetime_format: None,
+            }]
+        );
+
+        let sort_by_timestamp = SortField {
+            field_name: "timestamp".to_string(),
+            sort_order: SortOrder::Asc as i32,
+            sort_datetime_format: None,
+        };
+        let mut search_request = SearchRequest {
+            query_ast: knn_query_ast,
+            sort_fields: vec![sort_by_timestamp.clone()],
+            ..Default::default()
+        };
+        set_default_sort_for_knn_query(&mut search_request).unwrap();
+        assert_eq!(search_request.sort_fields, [sort_by_timestamp]);
+
+        let mut search_request = SearchRequest {
+            query_ast: qast_json_helper("body:hello", &[]),
+            ..Default::default()
+        };
+        set_default_sort_for_knn_query(&mut search_request).unwrap();
+        assert!(search_request.sort_fields.is_empty());
+    }
+
     fn index_metadata_for_multi_indexes_test(index_id: &str, index_uri: &str) -> IndexMetadata {
         let index_uri = Uri::from_str(index_uri).unwrap();
         let doc_mapping_json = r#"{
@@ -1568,6 +2292,7 @@ mod tests {
             indexing_settings,
             search_settings,
             retention_policy: Default::default(),
+            tiering_policy: Default::default(),
         })
     }
 
@@ -1740,6 +2465,7 @@ mod tests {
             indexing_settings,
             search_settings,
             retention_policy: Default::default(),
+            tiering_policy: Default::default(),
         })
     }
 
@@ -1860,7 +2586,13 @@ mod tests {
         schema_builder.add_u64_field("id", FAST);
         let schema = schema_builder.build();
         let mut sort_field_are_datetime = HashMap::new();
-        validate_sort_field_types(&schema, &sort_fields, &mut sort_field_are_datetime).unwrap();
+        validate_sort_field_types(
+            &schema,
+            &sort_fields,
+            |_| None,
+            &mut sort_field_are_datetime,
+        )
+        .unwrap();
         assert_eq!(sort_field_are_datetime.get("_doc"), Some(&false));
         assert_eq!(sort_field_are_datetime.get("_shard_doc"), Some(&false));
     }
@@ -1884,7 +2616,13 @@ mod tests {
         schema_builder.add_u64_field("id", FAST);
         let schema = schema_builder.build();
         let mut sort_field_are_datetime = HashMap::new();
-        validate_sort_field_types(&schema, &sort_fields, &mut sort_field_are_datetime).unwrap();
+        validate_sort_field_types(
+            &schema,
+            &sort_fields,
+            |_| None,
+            &mut sort_field_are_datetime,
+        )
+        .unwrap();
         assert_eq!(sort_field_are_datetime.get("timestamp"), Some(&true));
         assert_eq!(sort_field_are_datetime.get("id"), Some(&false));
     }
@@ -1911,9 +2649,13 @@ mod tests {
             let mut sort_field_are_datetime = HashMap::new();
             sort_field_are_datetime.insert("timestamp".to_string(), false);
             sort_field_are_datetime.insert("id".to_string(), false);
-            let error =
-                validate_sort_field_types(&schema, &sort_fields, &mut sort_field_are_datetime)
-                    .unwrap_err();
+            let error = validate_sort_field_types(
+                &schema,
+                &sort_fields,
+                |_| None,
+                &mut sort_field_are_datetime,
+            )
+            .unwrap_err();
             assert_eq!(
                 error.to_string(),
                 "sort datetime field `timestamp` must be of type datetime on all indexes"
@@ -1922,9 +2664,13 @@ mod tests {
         {
             let mut sort_field_are_datetime = HashMap::new();
             sort_field_are_datetime.insert("id".to_string(), true);
-            let error =
-                validate_sort_field_types(&schema, &sort_fields, &mut sort_field_are_datetime)
-                    .unwrap_err();
+            let error = validate_sort_field_types(
+                &schema,
+                &sort_fields,
+                |_| None,
+                &mut sort_field_are_datetime,
+            )
+            .unwrap_err();
             assert_eq!(
                 error.to_string(),
                 "sort datetime field `id` must be of type datetime on all indexes"
@@ -1973,6 +2719,7 @@ mod tests {
             split_id: "".to_string(),
             segment_ord: 0,
             doc_id: 0,
+            collapse_key: None,
         };
         validate_sort_by_fields_and_search_after(&sort_fields, &Some(partial_hit)).unwrap();
     }
@@ -1999,6 +2746,7 @@ mod tests {
             split_id: "split1".to_string(),
             segment_ord: 1,
             doc_id: 1,
+            collapse_key: None,
         };
         validate_sort_by_fields_and_search_after(&sort_fields, &Some(partial_hit)).unwrap();
     }
@@ -2068,6 +2816,7 @@ mod tests {
             split_id: "split1".to_string(),
             segment_ord: 1,
             doc_id: 1,
+            collapse_key: None,
         };
         let error =
             validate_sort_by_fields_and_search_after(&sort_fields, &Some(partial_hit)).unwrap_err();
@@ -2101,6 +2850,7 @@ mod tests {
             split_id: "".to_string(),
             segment_ord: 1,
             doc_id: 1,
+            collapse_key: None,
         };
         let error =
             validate_sort_by_fields_and_search_after(&sort_fields, &Some(partial_hit)).unwrap_err();
@@ -2134,6 +2884,7 @@ mod tests {
             split_id: "split1".to_string(),
             segment_ord: 1,
             doc_id: 1,
+            collapse_key: None,
         };
         let error =
             validate_sort_by_fields_and_search_after(&sort_fields, &Some(partial_hit)).unwrap_err();
@@ -2197,6 +2948,7 @@ mod tests {
             split_id: split_id.to_string(),
             segment_ord: 1,
             doc_id,
+            collapse_key: None,
         }
     }
 
@@ -2211,6 +2963,7 @@ mod tests {
             split_id: split_id.to_string(),
             segment_ord: 1,
             doc_id,
+            collapse_key: None,
         }
     }
 
@@ -2287,6 +3040,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -2309,6 +3063,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -2380,6 +3135,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -2401,6 +3157,103 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_root_search_reuses_cached_split_responses() -> anyhow::Result<()> {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            start_timestamp: Some(100_000),
+            ..Default::default()
+        };
+        let mut metastore = MetastoreServiceClient::mock();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
+                Ok(ListIndexesMetadataResponse::try_from_indexes_metadata(vec![
+                    index_metadata.clone()
+                ])
+                .unwrap())
+            });
+        let published_splits = Arc::new(RwLock::new(vec![MockSplitBuilder::new("split1")
+            .with_index_uid(&index_uid)
+            .build()]));
+        let published_splits_clone = published_splits.clone();
+        metastore
+            .expect_list_splits()
+            .returning(move |_list_splits_request| {
+                let splits = published_splits_clone.read().unwrap().clone();
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let searched_split_ids: Arc<RwLock<Vec<String>>> = Default::default();
+        let searched_split_ids_clone = searched_split_ids.clone();
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            move |leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                let split_id = leaf_search_req.split_offsets[0].split_id.clone();
+                searched_split_ids_clone
+                    .write()
+                    .unwrap()
+                    .push(split_id.clone());
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: 1,
+                    partial_hits: vec![mock_partial_hit(&split_id, 1, 1)],
+                    failed_splits: Vec::new(),
+                    num_attempted_splits: 1,
+                    ..Default::default()
+                })
+            },
+        );
+        mock_search_service.expect_fetch_docs().returning(
+            |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
+                })
+            },
+        );
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let metastore = MetastoreServiceClient::from(metastore);
+
+        let searcher_context = SearcherContext::for_test();
+        let search_response = root_search(
+            &searcher_context,
+            search_request.clone(),
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 1);
+
+        published_splits.write().unwrap().push(
+            MockSplitBuilder::new("split2")
+                .with_index_uid(&index_uid)
+                .build(),
+        );
+        let search_response = root_search(
+            &searcher_context,
+            search_request,
+            metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 2);
+        assert_eq!(search_response.hits.len(), 2);
+        // the response of `split1` was served from the root search cache
+        assert_eq!(
+            *searched_split_ids.read().unwrap(),
+            vec!["split1".to_string(), "split2".to_string()]
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_root_search_multiple_splits() -> anyhow::Result<()> {
         let search_request = quickwit_proto::search::SearchRequest {
@@ -2451,6 +3304,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -2470,6 +3324,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -2541,6 +3396,7 @@ mod tests {
                             split_id: "split1".to_string(),
                             segment_ord: 0,
                             doc_id: 0,
+                            collapse_key: None,
                         },
                         quickwit_proto::search::PartialHit {
                             sort_value: None,
@@ -2548,6 +3404,7 @@ mod tests {
                             split_id: "split1".to_string(),
                             segment_ord: 0,
                             doc_id: 1,
+                            collapse_key: None,
                         },
                     ],
                     failed_splits: Vec::new(),
@@ -2560,6 +3417,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -2575,6 +3433,7 @@ mod tests {
                             split_id: "split2".to_string(),
                             segment_ord: 0,
                             doc_id: 1,
+                            collapse_key: None,
                         },
                         quickwit_proto::search::PartialHit {
                             sort_value: Some(SortValue::I64(1i64).into()),
@@ -2582,6 +3441,7 @@ mod tests {
                             split_id: "split2".to_string(),
                             segment_ord: 0,
                             doc_id: 0,
+                            collapse_key: None,
                         },
                         quickwit_proto::search::PartialHit {
                             sort_value: None,
@@ -2589,6 +3449,7 @@ mod tests {
                             split_id: "split2".to_string(),
                             segment_ord: 0,
                             doc_id: 2,
+                            collapse_key: None,
                         },
                     ],
                     failed_splits: Vec::new(),
@@ -2601,6 +3462,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -2628,6 +3490,7 @@ mod tests {
                 doc_id: 1,
                 sort_value: Some(SortValue::I64(-1i64).into()),
                 sort_value2: None,
+                collapse_key: None,
             }
         );
         assert_eq!(
@@ -2638,6 +3501,7 @@ mod tests {
                 doc_id: 0,
                 sort_value: Some(SortValue::I64(1i64).into()),
                 sort_value2: None,
+                collapse_key: None,
             }
         );
         assert_eq!(
@@ -2648,6 +3512,7 @@ mod tests {
                 doc_id: 0,
                 sort_value: Some(SortValue::U64(2u64).into()),
                 sort_value2: None,
+                collapse_key: None,
             }
         );
         assert_eq!(
@@ -2658,6 +3523,7 @@ mod tests {
                 doc_id: 1,
                 sort_value: None,
                 sort_value2: None,
+                collapse_key: None,
             }
         );
         assert_eq!(
@@ -2668,6 +3534,7 @@ mod tests {
                 doc_id: 2,
                 sort_value: None,
                 sort_value2: None,
+                collapse_key: None,
             }
         );
         Ok(())
@@ -2722,6 +3589,7 @@ mod tests {
                             split_id: "split1".to_string(),
                             segment_ord: 0,
                             doc_id: 0,
+                            collapse_key: None,
                         },
                         quickwit_proto::search::PartialHit {
                             sort_value: None,
@@ -2729,6 +3597,7 @@ mod tests {
                             split_id: "split1".to_string(),
                             segment_ord: 0,
                             doc_id: 1,
+                            collapse_key: None,
                         },
                     ],
                     failed_splits: Vec::new(),
@@ -2741,6 +3610,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -2756,6 +3626,7 @@ mod tests {
                             split_id: "split2".to_string(),
                             segment_ord: 0,
                             doc_id: 0,
+                            collapse_key: None,
                         },
                         quickwit_proto::search::PartialHit {
                             sort_value: Some(SortValue::I64(-1i64).into()),
@@ -2763,6 +3634,7 @@ mod tests {
                             split_id: "split2".to_string(),
                             segment_ord: 0,
                             doc_id: 1,
+                            collapse_key: None,
                         },
                         quickwit_proto::search::PartialHit {
                             sort_value: None,
@@ -2770,6 +3642,7 @@ mod tests {
                             split_id: "split2".to_string(),
                             segment_ord: 0,
                             doc_id: 2,
+                            collapse_key: None,
                         },
                     ],
                     failed_splits: Vec::new(),
@@ -2782,6 +3655,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -2809,6 +3683,7 @@ mod tests {
                 doc_id: 0,
                 sort_value: Some(SortValue::U64(2u64).into()),
                 sort_value2: None,
+                collapse_key: None,
             }
         );
         assert_eq!(
@@ -2819,6 +3694,7 @@ mod tests {
                 doc_id: 0,
                 sort_value: Some(SortValue::I64(1i64).into()),
                 sort_value2: None,
+                collapse_key: None,
             }
         );
         assert_eq!(
@@ -2829,6 +3705,7 @@ mod tests {
                 doc_id: 1,
                 sort_value: Some(SortValue::I64(-1i64).into()),
                 sort_value2: None,
+                collapse_key: None,
             }
         );
         assert_eq!(
@@ -2839,6 +3716,7 @@ mod tests {
                 doc_id: 2,
                 sort_value: None,
                 sort_value2: None,
+                collapse_key: None,
             }
         );
         assert_eq!(
@@ -2849,6 +3727,7 @@ mod tests {
                 doc_id: 1,
                 sort_value: None,
                 sort_value2: None,
+                collapse_key: None,
             }
         );
         Ok(())
@@ -2910,6 +3789,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -2953,6 +3833,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -3045,6 +3926,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -3084,6 +3966,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -3167,6 +4050,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -3293,6 +4177,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -3382,6 +4267,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -3859,6 +4745,7 @@ mod tests {
                 assert!(fetch_docs_req.partial_hits.len() <= MAX_HITS_PER_PAGE);
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -4043,6 +4930,7 @@ mod tests {
                 assert!(fetch_docs_req.partial_hits.len() <= MAX_HITS_PER_PAGE_LARGE);
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             },
         );
@@ -4132,6 +5020,137 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_root_search_with_point_in_time() {
+        let mut metastore = MetastoreServiceClient::mock();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
+                Ok(ListIndexesMetadataResponse::try_from_indexes_metadata(vec![
+                    index_metadata.clone()
+                ])
+                .unwrap())
+            });
+        let published_split_ids: Arc<RwLock<Vec<&'static str>>> =
+            Arc::new(RwLock::new(vec!["split1", "split2"]));
+        let published_split_ids_clone = published_split_ids.clone();
+        metastore
+            .expect_list_splits()
+            .returning(move |_list_splits_request| {
+                let splits = published_split_ids_clone
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .map(|split_id| {
+                        MockSplitBuilder::new(split_id)
+                            .with_index_uid(&index_uid)
+                            .build()
+                    })
+                    .collect();
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            |leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                let partial_hits: Vec<PartialHit> = leaf_search_req
+                    .split_offsets
+                    .iter()
+                    .map(|split_offsets| mock_partial_hit(&split_offsets.split_id, 1, 1))
+                    .collect();
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: partial_hits.len() as u64,
+                    num_attempted_splits: partial_hits.len() as u64,
+                    partial_hits,
+                    ..Default::default()
+                })
+            },
+        );
+        let kv: Arc<RwLock<HashMap<Vec<u8>, Vec<u8>>>> = Default::default();
+        let kv_clone = kv.clone();
+        mock_search_service
+            .expect_put_kv()
+            .returning(move |put_kv_req| {
+                kv_clone
+                    .write()
+                    .unwrap()
+                    .insert(put_kv_req.key, put_kv_req.payload);
+            });
+        mock_search_service
+            .expect_get_kv()
+            .returning(move |get_kv_req| kv.read().unwrap().get(&get_kv_req.key).cloned());
+        mock_search_service.expect_fetch_docs().returning(
+            |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
+                })
+            },
+        );
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let searcher_context = SearcherContext::for_test();
+        let cluster_client = ClusterClient::new(search_job_placer);
+        let metastore = MetastoreServiceClient::from(metastore);
+
+        let open_request = OpenPointInTimeRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            keep_alive_secs: 60,
+        };
+        let pit_id = open_point_in_time(open_request, metastore.clone(), &cluster_client)
+            .await
+            .unwrap()
+            .pit_id;
+
+        // `split1` and `split2` get merged into `split3` after the point in time is opened.
+        *published_split_ids.write().unwrap() = vec!["split3"];
+
+        let hit_split_ids = |search_response: &SearchResponse| -> Vec<String> {
+            search_response
+                .hits
+                .iter()
+                .map(|hit| hit.partial_hit.as_ref().unwrap().split_id.clone())
+                .sorted()
+                .collect()
+        };
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let search_response = root_search(
+            &searcher_context,
+            search_request.clone(),
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(hit_split_ids(&search_response), ["split3"]);
+        assert!(search_response.pit_id.is_none());
+
+        let pit_search_request = quickwit_proto::search::SearchRequest {
+            point_in_time: Some(PointInTime {
+                pit_id: pit_id.clone(),
+                keep_alive_secs: Some(60),
+            }),
+            ..search_request
+        };
+        let pit_search_response = root_search(
+            &searcher_context,
+            pit_search_request,
+            metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(hit_split_ids(&pit_search_response), ["split1", "split2"]);
+        assert_eq!(pit_search_response.pit_id, Some(pit_id));
+    }
+
     #[tokio::test]
     async fn test_root_search_multi_indices() -> anyhow::Result<()> {
         let search_request = quickwit_proto::search::SearchRequest {
@@ -4226,6 +5245,7 @@ mod tests {
             .returning(|fetch_docs_req| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    storage_request_stats: None,
                 })
             });
         let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service_1)]);
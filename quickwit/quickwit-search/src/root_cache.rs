@@ -0,0 +1,265 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use prost::Message;
+use quickwit_metastore::SplitMetadata;
+use quickwit_proto::search::{LeafSearchResponse, SearchRequest};
+use quickwit_storage::{MemorySizedCache, OwnedBytes};
+
+/// A cache to memoize, on the root, the leaf search responses of individual splits.
+///
+/// Published splits are immutable, so the response of a split to a request does not change as
+/// long as the request covers the whole time range of the split. Entries are keyed on the split ID
+/// and on the request stripped of its time range. As a consequence, a relative time range (e.g.
+/// the last 24 hours of a dashboard) sliding over splits it fully covers keeps hitting the cache
+/// for these splits: only the splits at the edges of the time range and the newly published ones
+/// are searched again.
+pub struct RootSearchCache {
+    content: MemorySizedCache<RootSearchCacheKey>,
+}
+
+impl RootSearchCache {
+    pub fn new(capacity: usize) -> RootSearchCache {
+        RootSearchCache {
+            content: MemorySizedCache::with_capacity_in_bytes(
+                capacity,
+                &quickwit_storage::STORAGE_METRICS.root_search_cache,
+            ),
+        }
+    }
+
+    /// Returns `true` if the leaf responses to this request can be cached.
+    ///
+    /// Scroll requests create a scroll context on each call and profiled requests are expected
+    /// to actually run the search, so neither is cached. Each cached split is searched with a
+    /// leaf request of its own, so only requests with a time range, for which most splits are
+    /// expected to be either cached or not covered, are cached.
+    pub fn is_cacheable(search_request: &SearchRequest) -> bool {
+        search_request.scroll_ttl_secs.is_none()
+            && !search_request.profile
+            && (search_request.start_timestamp.is_some() || search_request.end_timestamp.is_some())
+    }
+
+    pub fn get(&self, key: &RootSearchCacheKey) -> Option<LeafSearchResponse> {
+        let encoded_response = self.content.get(key)?;
+        // this should never fail
+        LeafSearchResponse::decode(&*encoded_response).ok()
+    }
+
+    pub fn put(&self, key: RootSearchCacheKey, mut response: LeafSearchResponse) {
+        // Failed responses are not cached: the next request may succeed on the split.
+        if !response.failed_splits.is_empty() {
+            return;
+        }
+        // Serving the response from the cache does not issue any storage request.
+        response.storage_request_stats = None;
+        let encoded_response = response.encode_to_vec();
+        self.content.put(key, OwnedBytes::new(encoded_response));
+    }
+}
+
+/// A key inside a [`RootSearchCache`].
+#[derive(Debug, Hash, PartialEq, Eq)]
+pub struct RootSearchCacheKey {
+    /// The request this matches. The timerange of the request was removed.
+    request: SearchRequest,
+    /// The split the response was computed on.
+    split_id: String,
+}
+
+impl RootSearchCacheKey {
+    /// Returns the key of the response of the split to the request, or `None` if the request does
+    /// not cover the whole time range of the split.
+    pub fn for_split(
+        split_metadata: &SplitMetadata,
+        search_request: &SearchRequest,
+    ) -> Option<Self> {
+        let is_fully_covered = match &split_metadata.time_range {
+            Some(time_range) => {
+                search_request
+                    .start_timestamp
+                    .map_or(true, |start_timestamp| {
+                        start_timestamp <= *time_range.start()
+                    })
+                    && search_request
+                        .end_timestamp
+                        .map_or(true, |end_timestamp| *time_range.end() < end_timestamp)
+            }
+            None => {
+                search_request.start_timestamp.is_none() && search_request.end_timestamp.is_none()
+            }
+        };
+        if !is_fully_covered {
+            return None;
+        }
+        let mut request = search_request.clone();
+        request.start_timestamp = None;
+        request.end_timestamp = None;
+        // the splits of a point in time are searched as any other split.
+        request.point_in_time = None;
+        Some(RootSearchCacheKey {
+            request,
+            split_id: split_metadata.split_id.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_metastore::SplitMetadata;
+    use quickwit_proto::search::{
+        LeafSearchResponse, SearchRequest, SplitSearchError, StorageRequestStats,
+    };
+
+    use super::{RootSearchCache, RootSearchCacheKey};
+
+    fn mock_split_meta(split_id: &str, time_range: std::ops::RangeInclusive<i64>) -> SplitMetadata {
+        SplitMetadata {
+            split_id: split_id.to_string(),
+            time_range: Some(time_range),
+            ..Default::default()
+        }
+    }
+
+    fn mock_search_request(start_timestamp: i64, end_timestamp: i64) -> SearchRequest {
+        SearchRequest {
+            index_id_patterns: vec!["test-idx".to_string()],
+            query_ast: "test".to_string(),
+            start_timestamp: Some(start_timestamp),
+            end_timestamp: Some(end_timestamp),
+            max_hits: 10,
+            ..Default::default()
+        }
+    }
+
+    fn mock_leaf_search_response() -> LeafSearchResponse {
+        LeafSearchResponse {
+            num_hits: 1234,
+            num_attempted_splits: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_root_search_cache_per_split() {
+        let cache = RootSearchCache::new(64_000_000);
+        let split_1 = mock_split_meta("split_1", 100..=199);
+        let split_2 = mock_split_meta("split_2", 200..=299);
+        let request = mock_search_request(0, 1000);
+
+        let key_1 = RootSearchCacheKey::for_split(&split_1, &request).unwrap();
+        assert!(cache.get(&key_1).is_none());
+        cache.put(key_1, mock_leaf_search_response());
+        assert_eq!(
+            cache
+                .get(&RootSearchCacheKey::for_split(&split_1, &request).unwrap())
+                .unwrap(),
+            mock_leaf_search_response()
+        );
+        // the response of a split is not reused for another split
+        assert!(cache
+            .get(&RootSearchCacheKey::for_split(&split_2, &request).unwrap())
+            .is_none());
+
+        let other_request = SearchRequest {
+            query_ast: "test2".to_string(),
+            ..request
+        };
+        assert!(cache
+            .get(&RootSearchCacheKey::for_split(&split_1, &other_request).unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn test_root_search_cache_sliding_time_range() {
+        let cache = RootSearchCache::new(64_000_000);
+        let split = mock_split_meta("split_1", 100..=199);
+        let key = RootSearchCacheKey::for_split(&split, &mock_search_request(50, 1000)).unwrap();
+        cache.put(key, mock_leaf_search_response());
+
+        // the request still fully covers the split
+        assert!(cache
+            .get(&RootSearchCacheKey::for_split(&split, &mock_search_request(0, 1060)).unwrap())
+            .is_some());
+        // the request only covers part of the split
+        assert!(RootSearchCacheKey::for_split(&split, &mock_search_request(150, 1060)).is_none());
+        assert!(RootSearchCacheKey::for_split(&split, &mock_search_request(50, 199)).is_none());
+        assert!(RootSearchCacheKey::for_split(&split, &mock_search_request(50, 200)).is_some());
+    }
+
+    #[test]
+    fn test_root_search_cache_is_cacheable() {
+        assert!(RootSearchCache::is_cacheable(&mock_search_request(0, 1000)));
+        let request_without_time_range = SearchRequest {
+            start_timestamp: None,
+            end_timestamp: None,
+            ..mock_search_request(0, 1000)
+        };
+        assert!(!RootSearchCache::is_cacheable(&request_without_time_range));
+        let scroll_request = SearchRequest {
+            scroll_ttl_secs: Some(60),
+            ..mock_search_request(0, 1000)
+        };
+        assert!(!RootSearchCache::is_cacheable(&scroll_request));
+        let profile_request = SearchRequest {
+            profile: true,
+            ..mock_search_request(0, 1000)
+        };
+        assert!(!RootSearchCache::is_cacheable(&profile_request));
+    }
+
+    #[test]
+    fn test_root_search_cache_skips_failed_responses() {
+        let cache = RootSearchCache::new(64_000_000);
+        let split = mock_split_meta("split_1", 100..=199);
+        let request = mock_search_request(0, 1000);
+        let failed_response = LeafSearchResponse {
+            failed_splits: vec![SplitSearchError {
+                error: "timeout".to_string(),
+                split_id: "split_1".to_string(),
+                retryable_error: true,
+            }],
+            ..mock_leaf_search_response()
+        };
+        cache.put(
+            RootSearchCacheKey::for_split(&split, &request).unwrap(),
+            failed_response,
+        );
+        assert!(cache
+            .get(&RootSearchCacheKey::for_split(&split, &request).unwrap())
+            .is_none());
+
+        // storage request stats are not replayed on cache hits
+        let response = LeafSearchResponse {
+            storage_request_stats: Some(StorageRequestStats {
+                num_requests: 3,
+                num_bytes_downloaded: 1000,
+            }),
+            ..mock_leaf_search_response()
+        };
+        cache.put(
+            RootSearchCacheKey::for_split(&split, &request).unwrap(),
+            response,
+        );
+        let cached_response = cache
+            .get(&RootSearchCacheKey::for_split(&split, &request).unwrap())
+            .unwrap();
+        assert!(cached_response.storage_request_stats.is_none());
+    }
+}
@@ -0,0 +1,1304 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime fields are fields computed at query time by a VRL script evaluated on the stored
+//! documents, rather than extracted from the documents at indexing time.
+//!
+//! The values of runtime fields are added to the fetched documents. They can also be used in the
+//! query, the sort fields and the aggregations of a request, at the cost of fetching and
+//! evaluating every candidate document of the leaf search: see [`RuntimeFieldsSearch`].
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use futures::{StreamExt, TryStreamExt};
+use quickwit_doc_mapper::DocMapper;
+use quickwit_proto::search::{LeafSearchResponse, SearchRequest};
+use quickwit_query::query_ast::{
+    BoolQuery, FieldPresenceQuery, FullTextQuery, KnnQuery, NestedQuery, PhrasePrefixQuery,
+    QueryAst, QueryAstVisitor, RangeQuery, TermQuery, TermSetQuery, WildcardQuery,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use tantivy::aggregation::AggregationLimits;
+use tantivy::collector::{Collector, DocSetCollector, SegmentCollector};
+use tantivy::columnar::{ColumnType, MonotonicallyMappableToU64};
+use tantivy::common::BitSet;
+use tantivy::query::{
+    AllQuery, BitSetDocSet, BooleanQuery, ConstScorer, EmptyScorer, EnableScoring, Explanation,
+    Occur, Query, Scorer, Weight,
+};
+use tantivy::schema::{
+    Document as DocumentTrait, FieldType, Schema, TantivyDocument, FAST, INDEXED, STRING,
+};
+use tantivy::{
+    DateTime, DocAddress, DocId, Index, IndexReader, ReloadPolicy, Score, Searcher, SegmentId,
+    SegmentOrdinal, SegmentReader, SingleSegmentIndexWriter, TantivyError,
+};
+#[cfg(feature = "vrl")]
+use vrl::compiler::runtime::Runtime;
+#[cfg(feature = "vrl")]
+use vrl::compiler::state::RuntimeState;
+#[cfg(feature = "vrl")]
+use vrl::compiler::{Program, TargetValueRef, TimeZone};
+#[cfg(feature = "vrl")]
+use vrl::value::{Secrets as VrlSecrets, Value as VrlValue};
+
+use crate::collector::{make_collector_for_split, QuickwitAggregations};
+use crate::filters::{create_timestamp_filter_builder, TimestampFilter, TimestampFilterBuilder};
+use crate::leaf::{warm_up_fastfields, warmup};
+use crate::root::SORT_DOC_FIELD_NAMES;
+use crate::SearchError;
+
+/// Memory budget of the in-RAM index holding the values of the runtime fields of a split.
+const RUNTIME_INDEX_MEMORY_BUDGET_NUM_BYTES: usize = 50_000_000;
+
+/// Number of concurrent doc store requests when fetching the candidate documents of a split.
+const NUM_CONCURRENT_REQUESTS: usize = 30;
+
+/// Runtime fields of a search request, keyed by field name.
+pub type RuntimeMappings = BTreeMap<String, RuntimeFieldMapping>;
+
+/// Definition of a runtime field.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct RuntimeFieldMapping {
+    /// Type of the values of the field.
+    #[serde(rename = "type")]
+    pub field_type: RuntimeFieldType,
+    /// VRL script computing the values of the field.
+    pub script: RuntimeFieldScript,
+}
+
+/// Type of the values of a runtime field.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuntimeFieldType {
+    /// String values, matched and aggregated as a whole.
+    Keyword,
+    /// Signed 64-bit integers.
+    Long,
+    /// 64-bit floating point numbers.
+    Double,
+    /// Booleans.
+    Boolean,
+}
+
+/// VRL script of a runtime field, given either as a string or as an object with a `source` key.
+///
+/// The script is evaluated with the stored document as target, and the value of its last
+/// expression is the value of the field. Arrays yield multivalued fields.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum RuntimeFieldScript {
+    /// Source of the script.
+    Source(String),
+    /// Object holding the source of the script.
+    Object {
+        /// Source of the script.
+        source: String,
+    },
+}
+
+impl RuntimeFieldScript {
+    fn source(&self) -> &str {
+        match self {
+            RuntimeFieldScript::Source(source) => source,
+            RuntimeFieldScript::Object { source } => source,
+        }
+    }
+}
+
+impl RuntimeFieldType {
+    /// Converts a value returned by a runtime field script into a value of the field type, or
+    /// returns `None` if the value cannot be converted.
+    fn coerce(self, value: JsonValue) -> Option<JsonValue> {
+        match (self, value) {
+            (_, JsonValue::Null) => None,
+            (RuntimeFieldType::Keyword, JsonValue::String(text)) => Some(JsonValue::String(text)),
+            (RuntimeFieldType::Keyword, JsonValue::Number(number)) => {
+                Some(JsonValue::String(number.to_string()))
+            }
+            (RuntimeFieldType::Keyword, JsonValue::Bool(boolean)) => {
+                Some(JsonValue::String(boolean.to_string()))
+            }
+            (RuntimeFieldType::Long, JsonValue::Number(number)) => number
+                .as_i64()
+                .or_else(|| {
+                    number
+                        .as_f64()
+                        .filter(|float| float.is_finite())
+                        .map(|float| float as i64)
+                })
+                .map(JsonValue::from),
+            (RuntimeFieldType::Long, JsonValue::String(text)) => {
+                text.trim().parse::<i64>().ok().map(JsonValue::from)
+            }
+            (RuntimeFieldType::Double, JsonValue::Number(number)) => {
+                number.as_f64().map(JsonValue::from)
+            }
+            (RuntimeFieldType::Double, JsonValue::String(text)) => text
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|float| float.is_finite())
+                .map(JsonValue::from),
+            (RuntimeFieldType::Boolean, JsonValue::Bool(boolean)) => Some(JsonValue::Bool(boolean)),
+            (RuntimeFieldType::Boolean, JsonValue::String(text)) => {
+                text.trim().parse::<bool>().ok().map(JsonValue::Bool)
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts the value returned by a runtime field script into the value of the field. Values
+    /// that cannot be converted to the field type are dropped.
+    fn field_value(self, value: JsonValue) -> Option<JsonValue> {
+        let JsonValue::Array(values) = value else {
+            return self.coerce(value);
+        };
+        let values: Vec<JsonValue> = values
+            .into_iter()
+            .filter_map(|value| self.coerce(value))
+            .collect();
+        json_field_value(values)
+    }
+}
+
+/// Returns the value of a field in a JSON document given its values: a single value, or an array
+/// for multivalued fields.
+fn json_field_value(mut values: Vec<JsonValue>) -> Option<JsonValue> {
+    if values.len() > 1 {
+        return Some(JsonValue::Array(values));
+    }
+    values.pop()
+}
+
+/// Parses the JSON serialized runtime mappings of a request.
+pub(crate) fn parse_runtime_mappings(
+    runtime_mappings_json_opt: Option<&str>,
+) -> crate::Result<RuntimeMappings> {
+    let Some(runtime_mappings_json) = runtime_mappings_json_opt else {
+        return Ok(RuntimeMappings::new());
+    };
+    let runtime_mappings: RuntimeMappings =
+        serde_json::from_str(runtime_mappings_json).map_err(|error| {
+            SearchError::InvalidArgument(format!("invalid runtime mappings: {error}"))
+        })?;
+
+    for field_name in runtime_mappings.keys() {
+        if field_name.is_empty() || field_name.starts_with('_') {
+            return Err(SearchError::InvalidArgument(format!(
+                "invalid runtime field name `{field_name}`: runtime field names must not be empty \
+                 or start with `_`"
+            )));
+        }
+    }
+    Ok(runtime_mappings)
+}
+
+/// Returns the schema of the in-RAM index holding the values of runtime fields.
+pub(crate) fn runtime_fields_schema(runtime_mappings: &RuntimeMappings) -> Schema {
+    let mut schema_builder = Schema::builder();
+
+    for (field_name, runtime_field_mapping) in runtime_mappings {
+        match runtime_field_mapping.field_type {
+            RuntimeFieldType::Keyword => {
+                schema_builder.add_text_field(field_name, STRING | FAST);
+            }
+            RuntimeFieldType::Long => {
+                schema_builder.add_i64_field(field_name, INDEXED | FAST);
+            }
+            RuntimeFieldType::Double => {
+                schema_builder.add_f64_field(field_name, INDEXED | FAST);
+            }
+            RuntimeFieldType::Boolean => {
+                schema_builder.add_bool_field(field_name, INDEXED | FAST);
+            }
+        }
+    }
+    schema_builder.build()
+}
+
+/// Collects the names of the fields targeted by a query AST.
+#[derive(Default)]
+struct QueryFieldNames<'a> {
+    field_names: HashSet<&'a str>,
+}
+
+impl<'a> QueryAstVisitor<'a> for QueryFieldNames<'a> {
+    type Err = Infallible;
+
+    fn visit_term(&mut self, term_query: &'a TermQuery) -> Result<(), Infallible> {
+        self.field_names.insert(&term_query.field);
+        Ok(())
+    }
+
+    fn visit_term_set(&mut self, term_set_query: &'a TermSetQuery) -> Result<(), Infallible> {
+        self.field_names
+            .extend(term_set_query.terms_per_field.keys().map(String::as_str));
+        Ok(())
+    }
+
+    fn visit_full_text(&mut self, full_text_query: &'a FullTextQuery) -> Result<(), Infallible> {
+        self.field_names.insert(&full_text_query.field);
+        Ok(())
+    }
+
+    fn visit_phrase_prefix(
+        &mut self,
+        phrase_prefix_query: &'a PhrasePrefixQuery,
+    ) -> Result<(), Infallible> {
+        self.field_names.insert(&phrase_prefix_query.field);
+        Ok(())
+    }
+
+    fn visit_range(&mut self, range_query: &'a RangeQuery) -> Result<(), Infallible> {
+        self.field_names.insert(&range_query.field);
+        Ok(())
+    }
+
+    fn visit_exists(&mut self, exists_query: &'a FieldPresenceQuery) -> Result<(), Infallible> {
+        self.field_names.insert(&exists_query.field);
+        Ok(())
+    }
+
+    fn visit_wildcard(&mut self, wildcard_query: &'a WildcardQuery) -> Result<(), Infallible> {
+        self.field_names.insert(&wildcard_query.field);
+        Ok(())
+    }
+
+    fn visit_knn(&mut self, knn_query: &'a KnnQuery) -> Result<(), Infallible> {
+        self.field_names.insert(&knn_query.field);
+        for filter in &knn_query.filter {
+            self.visit(filter)?;
+        }
+        Ok(())
+    }
+
+    fn visit_nested(&mut self, nested_query: &'a NestedQuery) -> Result<(), Infallible> {
+        self.field_names.insert(&nested_query.path);
+        self.visit(&nested_query.query)
+    }
+}
+
+/// How a query AST targets runtime fields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RuntimeFieldsUsage {
+    None,
+    Only,
+    Mixed,
+}
+
+fn runtime_fields_usage(
+    query_ast: &QueryAst,
+    runtime_mappings: &RuntimeMappings,
+) -> RuntimeFieldsUsage {
+    let mut query_field_names = QueryFieldNames::default();
+    // This cannot fail. The error type is Infallible.
+    let _: Result<(), Infallible> = query_field_names.visit(query_ast);
+    let field_names = query_field_names.field_names;
+
+    let num_runtime_fields = field_names
+        .iter()
+        .filter(|field_name| runtime_mappings.contains_key(**field_name))
+        .count();
+    if num_runtime_fields == 0 {
+        RuntimeFieldsUsage::None
+    } else if num_runtime_fields == field_names.len() {
+        RuntimeFieldsUsage::Only
+    } else {
+        RuntimeFieldsUsage::Mixed
+    }
+}
+
+fn unsupported_runtime_query_error() -> SearchError {
+    SearchError::InvalidQuery(
+        "runtime fields can only be queried in top-level `must`, `filter` and `must_not` clauses \
+         that do not also target indexed fields"
+            .to_string(),
+    )
+}
+
+/// Splits a query AST into its indexed part, which runs on the inverted index of the split, and
+/// the clauses targeting runtime fields, which filter the candidate documents matching the
+/// former.
+pub(crate) fn split_runtime_query_ast(
+    query_ast: QueryAst,
+    runtime_mappings: &RuntimeMappings,
+) -> crate::Result<(QueryAst, Option<QueryAst>)> {
+    match runtime_fields_usage(&query_ast, runtime_mappings) {
+        RuntimeFieldsUsage::None => return Ok((query_ast, None)),
+        RuntimeFieldsUsage::Only => return Ok((QueryAst::MatchAll, Some(query_ast))),
+        RuntimeFieldsUsage::Mixed => {}
+    }
+    let QueryAst::Bool(BoolQuery {
+        must,
+        must_not,
+        should,
+        filter,
+    }) = query_ast
+    else {
+        return Err(unsupported_runtime_query_error());
+    };
+    if should
+        .iter()
+        .any(|clause| runtime_fields_usage(clause, runtime_mappings) != RuntimeFieldsUsage::None)
+    {
+        return Err(unsupported_runtime_query_error());
+    }
+    let has_required_clauses = !must.is_empty() || !filter.is_empty();
+    let mut indexed_query = BoolQuery {
+        should,
+        ..Default::default()
+    };
+    let mut runtime_query = BoolQuery::default();
+
+    for (clauses, indexed_clauses, runtime_clauses) in [
+        (must, &mut indexed_query.must, &mut runtime_query.must),
+        (filter, &mut indexed_query.filter, &mut runtime_query.filter),
+        (
+            must_not,
+            &mut indexed_query.must_not,
+            &mut runtime_query.must_not,
+        ),
+    ] {
+        for clause in clauses {
+            match runtime_fields_usage(&clause, runtime_mappings) {
+                RuntimeFieldsUsage::None => indexed_clauses.push(clause),
+                RuntimeFieldsUsage::Only => runtime_clauses.push(clause),
+                RuntimeFieldsUsage::Mixed => return Err(unsupported_runtime_query_error()),
+            }
+        }
+    }
+    // Without required clauses, `should` clauses become required.
+    if has_required_clauses && indexed_query.must.is_empty() && indexed_query.filter.is_empty() {
+        indexed_query.filter.push(QueryAst::MatchAll);
+    }
+    Ok((indexed_query.into(), Some(runtime_query.into())))
+}
+
+/// Returns true if the hits and aggregations of a request are computed on runtime fields, and
+/// checks that they do not also target indexed fields.
+pub(crate) fn collector_uses_runtime_fields(
+    search_request: &SearchRequest,
+    runtime_mappings: &RuntimeMappings,
+) -> crate::Result<bool> {
+    let aggregations_opt: Option<QuickwitAggregations> = search_request
+        .aggregation_request
+        .as_ref()
+        .map(|aggregation_request| serde_json::from_str(aggregation_request))
+        .transpose()
+        .map_err(|error| SearchError::InvalidAggregationRequest(error.to_string()))?;
+    let aggregation_field_names = aggregations_opt
+        .as_ref()
+        .map(|aggregations| aggregations.fast_field_names())
+        .unwrap_or_default();
+
+    let mut field_names: Vec<&str> = search_request
+        .sort_fields
+        .iter()
+        .map(|sort_field| sort_field.field_name.as_str())
+        .filter(|field_name| !SORT_DOC_FIELD_NAMES.contains(field_name))
+        .collect();
+    field_names.extend(aggregation_field_names.iter().map(String::as_str));
+
+    if let Some(collapse) = &search_request.collapse {
+        field_names.push(&collapse.field);
+    }
+    if !field_names
+        .iter()
+        .any(|field_name| runtime_mappings.contains_key(*field_name))
+    {
+        return Ok(false);
+    }
+    for field_name in field_names {
+        if !runtime_mappings.contains_key(field_name) {
+            return Err(SearchError::InvalidArgument(format!(
+                "`{field_name}` cannot be used alongside runtime fields: when the sort fields, \
+                 the aggregations or the collapse field target a runtime field, they must all \
+                 target runtime fields"
+            )));
+        }
+    }
+    for sort_field in &search_request.sort_fields {
+        if let Some(runtime_field_mapping) = runtime_mappings.get(&sort_field.field_name) {
+            if runtime_field_mapping.field_type == RuntimeFieldType::Keyword {
+                return Err(SearchError::InvalidArgument(format!(
+                    "sort by runtime field of type keyword is not supported `{}`",
+                    sort_field.field_name
+                )));
+            }
+        }
+    }
+    if matches!(
+        aggregations_opt,
+        Some(QuickwitAggregations::ExtendedAggregations(_))
+    ) {
+        return Err(SearchError::InvalidAggregationRequest(
+            "`top_hits` and `composite` aggregations are not supported on runtime fields"
+                .to_string(),
+        ));
+    }
+    Ok(true)
+}
+
+#[cfg(feature = "vrl")]
+struct RuntimeFieldProgram {
+    program: Program,
+    timezone: TimeZone,
+    runtime: Runtime,
+}
+
+#[cfg(feature = "vrl")]
+impl RuntimeFieldProgram {
+    fn compile(source: &str) -> anyhow::Result<Self> {
+        let functions = vrl::stdlib::all();
+        let compilation_res = match vrl::compiler::compile(source, &functions) {
+            Ok(compilation_res) => compilation_res,
+            Err(diagnostics) => {
+                let mut formatter = vrl::diagnostic::Formatter::new(source, diagnostics);
+                formatter.enable_colors(false);
+                anyhow::bail!("failed to compile VRL script:\n {formatter}")
+            }
+        };
+        let timezone = TimeZone::parse("UTC").context("failed to parse timezone `UTC`")?;
+        Ok(RuntimeFieldProgram {
+            program: compilation_res.program,
+            timezone,
+            runtime: Runtime::new(RuntimeState::default()),
+        })
+    }
+
+    /// Runs the script on a document and returns the value of its last expression.
+    fn evaluate(&mut self, doc_json: &JsonMap<String, JsonValue>) -> anyhow::Result<JsonValue> {
+        let mut vrl_value: VrlValue = serde_json::from_value(JsonValue::Object(doc_json.clone()))?;
+        let mut metadata = VrlValue::Object(BTreeMap::new());
+        let mut secrets = VrlSecrets::default();
+        let mut target = TargetValueRef {
+            value: &mut vrl_value,
+            metadata: &mut metadata,
+            secrets: &mut secrets,
+        };
+        let runtime_res = self
+            .runtime
+            .resolve(&mut target, &self.program, &self.timezone);
+        self.runtime.clear();
+
+        let output = runtime_res.map_err(|terminate| anyhow::anyhow!("{terminate}"))?;
+        let output_json = serde_json::to_value(output)?;
+        Ok(output_json)
+    }
+}
+
+#[cfg(not(feature = "vrl"))]
+struct RuntimeFieldProgram;
+
+#[cfg(not(feature = "vrl"))]
+impl RuntimeFieldProgram {
+    fn compile(_source: &str) -> anyhow::Result<Self> {
+        anyhow::bail!("VRL is not enabled. please recompile with the `vrl` feature")
+    }
+
+    fn evaluate(&mut self, _doc_json: &JsonMap<String, JsonValue>) -> anyhow::Result<JsonValue> {
+        anyhow::bail!("VRL is not enabled. please recompile with the `vrl` feature")
+    }
+}
+
+struct RuntimeField {
+    name: String,
+    field_type: RuntimeFieldType,
+    program: RuntimeFieldProgram,
+}
+
+/// Runtime fields of a request, with their compiled scripts.
+pub(crate) struct RuntimeFields {
+    runtime_fields: Vec<RuntimeField>,
+}
+
+impl RuntimeFields {
+    pub fn compile(runtime_mappings: &RuntimeMappings) -> crate::Result<Self> {
+        let mut runtime_fields = Vec::with_capacity(runtime_mappings.len());
+
+        for (field_name, runtime_field_mapping) in runtime_mappings {
+            let program = RuntimeFieldProgram::compile(runtime_field_mapping.script.source())
+                .map_err(|error| {
+                    SearchError::InvalidArgument(format!(
+                        "invalid script for runtime field `{field_name}`: {error}"
+                    ))
+                })?;
+            runtime_fields.push(RuntimeField {
+                name: field_name.clone(),
+                field_type: runtime_field_mapping.field_type,
+                program,
+            });
+        }
+        Ok(RuntimeFields { runtime_fields })
+    }
+
+    /// Evaluates the runtime fields on a document and returns their values. Fields without a value
+    /// are omitted.
+    pub fn evaluate(
+        &mut self,
+        doc_json: &JsonMap<String, JsonValue>,
+    ) -> crate::Result<JsonMap<String, JsonValue>> {
+        let mut runtime_values = JsonMap::new();
+
+        for runtime_field in &mut self.runtime_fields {
+            let output = runtime_field.program.evaluate(doc_json).map_err(|error| {
+                SearchError::InvalidQuery(format!(
+                    "failed to evaluate runtime field `{}`: {error}",
+                    runtime_field.name
+                ))
+            })?;
+            if let Some(field_value) = runtime_field.field_type.field_value(output) {
+                runtime_values.insert(runtime_field.name.clone(), field_value);
+            }
+        }
+        Ok(runtime_values)
+    }
+}
+
+/// Returns the names of the fast fields of a split that are not stored, and are therefore missing
+/// from the documents of the doc store.
+fn fast_only_field_names(schema: &Schema) -> HashSet<String> {
+    schema
+        .fields()
+        .filter(|(_, field_entry)| {
+            field_entry.is_fast()
+                && !field_entry.is_stored()
+                && !matches!(field_entry.field_type(), FieldType::JsonObject(_))
+        })
+        .map(|(_, field_entry)| field_entry.name().to_string())
+        .collect()
+}
+
+/// Returns the values of a fast field for a document. Dates are returned as Unix timestamps in
+/// nanoseconds.
+fn fast_field_values(
+    segment_reader: &SegmentReader,
+    field_name: &str,
+    doc_id: DocId,
+) -> tantivy::Result<Vec<JsonValue>> {
+    let fast_fields = segment_reader.fast_fields();
+
+    if let Some(str_column) = fast_fields.str(field_name)? {
+        let mut values = Vec::new();
+        let mut buffer = String::new();
+
+        for term_ord in str_column.term_ords(doc_id) {
+            buffer.clear();
+            str_column.ord_to_str(term_ord, &mut buffer)?;
+            values.push(JsonValue::String(buffer.clone()));
+        }
+        return Ok(values);
+    }
+    let Some((column, column_type)) = fast_fields.u64_lenient(field_name)? else {
+        return Ok(Vec::new());
+    };
+    let values = column
+        .values_for_doc(doc_id)
+        .map(|value| match column_type {
+            ColumnType::I64 => JsonValue::from(i64::from_u64(value)),
+            ColumnType::F64 => JsonValue::from(f64::from_u64(value)),
+            ColumnType::Bool => JsonValue::Bool(value != 0),
+            ColumnType::DateTime => {
+                JsonValue::from(DateTime::from_u64(value).into_timestamp_nanos())
+            }
+            _ => JsonValue::from(value),
+        })
+        .collect();
+    Ok(values)
+}
+
+/// Adds the values of the fast fields that are not stored to a document fetched from the doc
+/// store, so that runtime field scripts can use them.
+fn add_fast_only_fields(
+    doc_json: &mut JsonMap<String, JsonValue>,
+    doc_address: DocAddress,
+    searcher: &Searcher,
+    fast_only_field_names: &HashSet<String>,
+) -> tantivy::Result<()> {
+    let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+
+    for field_name in fast_only_field_names {
+        if doc_json.contains_key(field_name) {
+            continue;
+        }
+        let values = fast_field_values(segment_reader, field_name, doc_address.doc_id)?;
+
+        if let Some(value) = json_field_value(values) {
+            doc_json.insert(field_name.clone(), value);
+        }
+    }
+    Ok(())
+}
+
+/// Fetches the documents on which runtime fields are evaluated, in the order of their addresses.
+async fn fetch_runtime_fields_targets(
+    searcher: &Searcher,
+    doc_mapper: &dyn DocMapper,
+    doc_addresses: &[DocAddress],
+    fast_only_field_names: &HashSet<String>,
+) -> anyhow::Result<Vec<JsonMap<String, JsonValue>>> {
+    let doc_futures = doc_addresses.iter().map(|&doc_address| async move {
+        let doc: TantivyDocument = searcher
+            .doc_async(doc_address)
+            .await
+            .context("searcher-doc-async")?;
+        let named_field_doc = doc.to_named_doc(searcher.schema());
+        let mut doc_json = doc_mapper.doc_to_json(named_field_doc.0)?;
+        add_fast_only_fields(&mut doc_json, doc_address, searcher, fast_only_field_names)?;
+        anyhow::Ok(doc_json)
+    });
+    futures::stream::iter(doc_futures)
+        .buffered(NUM_CONCURRENT_REQUESTS)
+        .try_collect()
+        .await
+}
+
+/// Builds an in-RAM index holding the values of the runtime fields of a list of documents. The
+/// n-th document of the index holds the values of the n-th document of the list.
+fn build_runtime_index(
+    runtime_mappings: &RuntimeMappings,
+    runtime_values: Vec<JsonMap<String, JsonValue>>,
+) -> tantivy::Result<Index> {
+    let schema = runtime_fields_schema(runtime_mappings);
+    let index = Index::create_in_ram(schema.clone());
+    let mut index_writer: SingleSegmentIndexWriter =
+        SingleSegmentIndexWriter::new(index, RUNTIME_INDEX_MEMORY_BUDGET_NUM_BYTES)?;
+
+    for doc_runtime_values in runtime_values {
+        let mut doc = TantivyDocument::default();
+
+        for (field_name, field_value) in doc_runtime_values {
+            let field = schema.get_field(&field_name)?;
+            let field_type = runtime_mappings[&field_name].field_type;
+            let values = match field_value {
+                JsonValue::Array(values) => values,
+                value => vec![value],
+            };
+            for value in values {
+                match field_type {
+                    RuntimeFieldType::Keyword => {
+                        if let Some(text) = value.as_str() {
+                            doc.add_text(field, text);
+                        }
+                    }
+                    RuntimeFieldType::Long => {
+                        if let Some(long) = value.as_i64() {
+                            doc.add_i64(field, long);
+                        }
+                    }
+                    RuntimeFieldType::Double => {
+                        if let Some(double) = value.as_f64() {
+                            doc.add_f64(field, double);
+                        }
+                    }
+                    RuntimeFieldType::Boolean => {
+                        if let Some(boolean) = value.as_bool() {
+                            doc.add_bool(field, boolean);
+                        }
+                    }
+                }
+            }
+        }
+        index_writer.add_document(doc)?;
+    }
+    index_writer.finalize()
+}
+
+/// Collects the addresses of the documents matching a query within the requested time range.
+struct CandidateCollector {
+    timestamp_filter_builder_opt: Option<TimestampFilterBuilder>,
+}
+
+struct CandidateSegmentCollector {
+    segment_ord: SegmentOrdinal,
+    timestamp_filter_opt: Option<TimestampFilter>,
+    doc_addresses: Vec<DocAddress>,
+}
+
+impl Collector for CandidateCollector {
+    type Fruit = Vec<DocAddress>;
+    type Child = CandidateSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_ord: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        let timestamp_filter_opt = match &self.timestamp_filter_builder_opt {
+            Some(timestamp_filter_builder) => timestamp_filter_builder.build(segment_reader)?,
+            None => None,
+        };
+        Ok(CandidateSegmentCollector {
+            segment_ord,
+            timestamp_filter_opt,
+            doc_addresses: Vec::new(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<Vec<DocAddress>>,
+    ) -> tantivy::Result<Vec<DocAddress>> {
+        let mut doc_addresses: Vec<DocAddress> = segment_fruits.into_iter().flatten().collect();
+        doc_addresses.sort_unstable();
+        Ok(doc_addresses)
+    }
+}
+
+impl SegmentCollector for CandidateSegmentCollector {
+    type Fruit = Vec<DocAddress>;
+
+    fn collect(&mut self, doc_id: DocId, _score: Score) {
+        if let Some(timestamp_filter) = &self.timestamp_filter_opt {
+            if !timestamp_filter.is_within_range(doc_id) {
+                return;
+            }
+        }
+        self.doc_addresses
+            .push(DocAddress::new(self.segment_ord, doc_id));
+    }
+
+    fn harvest(self) -> Vec<DocAddress> {
+        self.doc_addresses
+    }
+}
+
+/// Query matching a fixed set of documents, given as sorted doc IDs per segment. It does not
+/// contribute to the score of the documents.
+#[derive(Clone, Debug)]
+struct DocSetQuery {
+    doc_ids_per_segment: Arc<HashMap<SegmentId, Vec<DocId>>>,
+}
+
+impl Query for DocSetQuery {
+    fn weight(&self, _enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        Ok(Box::new(DocSetWeight {
+            doc_ids_per_segment: self.doc_ids_per_segment.clone(),
+        }))
+    }
+}
+
+struct DocSetWeight {
+    doc_ids_per_segment: Arc<HashMap<SegmentId, Vec<DocId>>>,
+}
+
+impl Weight for DocSetWeight {
+    fn scorer(&self, reader: &SegmentReader, _boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let Some(doc_ids) = self.doc_ids_per_segment.get(&reader.segment_id()) else {
+            return Ok(Box::new(EmptyScorer));
+        };
+        let mut doc_bitset = BitSet::with_max_value(reader.max_doc());
+
+        for &doc_id in doc_ids {
+            doc_bitset.insert(doc_id);
+        }
+        Ok(Box::new(ConstScorer::new(
+            BitSetDocSet::from(doc_bitset),
+            0.0,
+        )))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let is_match = self
+            .doc_ids_per_segment
+            .get(&reader.segment_id())
+            .map(|doc_ids| doc_ids.binary_search(&doc).is_ok())
+            .unwrap_or(false);
+        if !is_match {
+            return Err(TantivyError::InvalidArgument(format!(
+                "document #({doc}) does not match"
+            )));
+        }
+        Ok(Explanation::new("DocSetQuery", 0.0))
+    }
+}
+
+/// Leaf search of a split for a request whose query, sort fields or aggregations target runtime
+/// fields.
+///
+/// The indexed part of the query is run on the split to collect the candidate documents. The
+/// candidates are then fetched from the doc store, and the values of their runtime fields are
+/// indexed in a small in-RAM index, on which the runtime part of the query is run. Finally, the
+/// hits and aggregations are computed on the in-RAM index if they target runtime fields, or on
+/// the split, restricted to the candidates matching the runtime part of the query, otherwise.
+pub(crate) struct RuntimeFieldsSearch {
+    runtime_mappings: RuntimeMappings,
+    runtime_fields: RuntimeFields,
+    indexed_query_ast: QueryAst,
+    runtime_query_ast_opt: Option<QueryAst>,
+    collects_runtime_fields: bool,
+}
+
+impl RuntimeFieldsSearch {
+    /// Returns `None` if the request can run as a regular leaf search, because runtime fields are
+    /// only requested in the fetched documents.
+    pub fn try_new(
+        search_request: &SearchRequest,
+        query_ast: &QueryAst,
+    ) -> crate::Result<Option<Self>> {
+        let runtime_mappings = parse_runtime_mappings(search_request.runtime_mappings.as_deref())?;
+
+        if runtime_mappings.is_empty() {
+            return Ok(None);
+        }
+        let (indexed_query_ast, runtime_query_ast_opt) =
+            split_runtime_query_ast(query_ast.clone(), &runtime_mappings)?;
+        let collects_runtime_fields =
+            collector_uses_runtime_fields(search_request, &runtime_mappings)?;
+
+        if runtime_query_ast_opt.is_none() && !collects_runtime_fields {
+            return Ok(None);
+        }
+        let runtime_fields = RuntimeFields::compile(&runtime_mappings)?;
+
+        Ok(Some(RuntimeFieldsSearch {
+            runtime_mappings,
+            runtime_fields,
+            indexed_query_ast,
+            runtime_query_ast_opt,
+            collects_runtime_fields,
+        }))
+    }
+
+    pub async fn search(
+        self,
+        split_id: String,
+        searcher: Searcher,
+        doc_mapper: Arc<dyn DocMapper>,
+        search_request: &SearchRequest,
+        aggregation_limits: AggregationLimits,
+        max_num_candidate_docs: usize,
+    ) -> crate::Result<LeafSearchResponse> {
+        let RuntimeFieldsSearch {
+            runtime_mappings,
+            mut runtime_fields,
+            indexed_query_ast,
+            runtime_query_ast_opt,
+            collects_runtime_fields,
+        } = self;
+        let split_schema = searcher.schema().clone();
+        let (indexed_query, mut warmup_info) =
+            doc_mapper.query(split_schema.clone(), &indexed_query_ast, false)?;
+
+        let candidate_collector = CandidateCollector {
+            timestamp_filter_builder_opt: create_timestamp_filter_builder(
+                doc_mapper.timestamp_field_name(),
+                search_request.start_timestamp,
+                search_request.end_timestamp,
+            ),
+        };
+        let quickwit_collector = if collects_runtime_fields {
+            // The time range is already applied to the candidates, and the timestamp field is not
+            // part of the in-RAM index.
+            let runtime_search_request = SearchRequest {
+                start_timestamp: None,
+                end_timestamp: None,
+                ..search_request.clone()
+            };
+            make_collector_for_split(
+                split_id.clone(),
+                doc_mapper.as_ref(),
+                &runtime_search_request,
+                aggregation_limits,
+            )?
+        } else {
+            let quickwit_collector = make_collector_for_split(
+                split_id.clone(),
+                doc_mapper.as_ref(),
+                search_request,
+                aggregation_limits,
+            )?;
+            warmup_info.merge(quickwit_collector.warmup_info());
+            quickwit_collector
+        };
+        if let Some(timestamp_filter_builder) = &candidate_collector.timestamp_filter_builder_opt {
+            warmup_info
+                .fast_field_names
+                .insert(timestamp_filter_builder.timestamp_field_name.clone());
+        }
+        let fast_only_field_names = fast_only_field_names(&split_schema);
+        warmup_info
+            .fast_field_names
+            .extend(fast_only_field_names.iter().cloned());
+        warmup_info.simplify();
+        warmup(&searcher, &warmup_info).await?;
+
+        let searcher_clone = searcher.clone();
+        let (candidates, indexed_query) = run_cpu_intensive_for_split(&split_id, move || {
+            let candidates = searcher_clone.search(&indexed_query, &candidate_collector)?;
+            crate::Result::Ok((candidates, indexed_query))
+        })
+        .await?;
+
+        if candidates.len() > max_num_candidate_docs {
+            return Err(SearchError::InvalidQuery(format!(
+                "the query matches {} documents in split `{split_id}`, but runtime fields can be \
+                 evaluated on at most {max_num_candidate_docs} documents per split: narrow down \
+                 the query with clauses on indexed fields",
+                candidates.len()
+            )));
+        }
+        let runtime_fields_targets = fetch_runtime_fields_targets(
+            &searcher,
+            doc_mapper.as_ref(),
+            &candidates,
+            &fast_only_field_names,
+        )
+        .await?;
+        let tokenizer_manager = doc_mapper.tokenizer_manager().clone();
+
+        run_cpu_intensive_for_split(&split_id, move || {
+            let runtime_values = runtime_fields_targets
+                .iter()
+                .map(|doc_json| runtime_fields.evaluate(doc_json))
+                .collect::<crate::Result<Vec<_>>>()?;
+            let runtime_index = build_runtime_index(&runtime_mappings, runtime_values)?;
+            let runtime_reader: IndexReader = runtime_index
+                .reader_builder()
+                .reload_policy(ReloadPolicy::Manual)
+                .try_into()?;
+            let runtime_searcher = runtime_reader.searcher();
+            let runtime_query: Box<dyn Query> = match &runtime_query_ast_opt {
+                Some(runtime_query_ast) => runtime_query_ast
+                    .build_tantivy_query(&runtime_index.schema(), &tokenizer_manager, &[], true)
+                    .map_err(|error| SearchError::InvalidQuery(error.to_string()))?,
+                None => Box::new(AllQuery),
+            };
+            if collects_runtime_fields {
+                let mut leaf_search_response =
+                    runtime_searcher.search(&runtime_query, &quickwit_collector)?;
+
+                for partial_hit in &mut leaf_search_response.partial_hits {
+                    let doc_address = candidates[partial_hit.doc_id as usize];
+                    partial_hit.segment_ord = doc_address.segment_ord;
+                    partial_hit.doc_id = doc_address.doc_id;
+                }
+                return Ok(leaf_search_response);
+            }
+            let mut runtime_matches: Vec<DocAddress> = runtime_searcher
+                .search(&runtime_query, &DocSetCollector)?
+                .into_iter()
+                .collect();
+            runtime_matches.sort_unstable();
+
+            let mut doc_ids_per_segment: HashMap<SegmentId, Vec<DocId>> = HashMap::new();
+            for runtime_match in runtime_matches {
+                let doc_address = candidates[runtime_match.doc_id as usize];
+                let segment_id = searcher
+                    .segment_reader(doc_address.segment_ord)
+                    .segment_id();
+                doc_ids_per_segment
+                    .entry(segment_id)
+                    .or_default()
+                    .push(doc_address.doc_id);
+            }
+            let doc_set_query = DocSetQuery {
+                doc_ids_per_segment: Arc::new(doc_ids_per_segment),
+            };
+            let query = BooleanQuery::new(vec![
+                (Occur::Must, indexed_query),
+                (Occur::Must, Box::new(doc_set_query)),
+            ]);
+            let leaf_search_response = searcher.search(&query, &quickwit_collector)?;
+            Ok(leaf_search_response)
+        })
+        .await
+    }
+}
+
+async fn run_cpu_intensive_for_split<F, R>(split_id: &str, cpu_heavy_task: F) -> crate::Result<R>
+where
+    F: FnOnce() -> crate::Result<R> + Send + 'static,
+    R: Send + 'static,
+{
+    crate::run_cpu_intensive(cpu_heavy_task)
+        .await
+        .map_err(|_| SearchError::Internal(format!("leaf search panicked. split={split_id}")))?
+}
+
+/// Adds the values of runtime fields to the documents fetched from a split.
+pub(crate) struct RuntimeFieldsEvaluator {
+    runtime_fields: Mutex<RuntimeFields>,
+    fast_only_field_names: HashSet<String>,
+}
+
+impl RuntimeFieldsEvaluator {
+    pub async fn for_split(
+        runtime_mappings: &RuntimeMappings,
+        searcher: &Searcher,
+    ) -> crate::Result<Self> {
+        let runtime_fields = RuntimeFields::compile(runtime_mappings)?;
+        let fast_only_field_names = fast_only_field_names(searcher.schema());
+        warm_up_fastfields(searcher, &fast_only_field_names).await?;
+
+        Ok(RuntimeFieldsEvaluator {
+            runtime_fields: Mutex::new(runtime_fields),
+            fast_only_field_names,
+        })
+    }
+
+    /// Adds the values of the runtime fields to a document. Runtime fields shadow the fields of
+    /// the document with the same name.
+    pub fn add_runtime_fields(
+        &self,
+        doc_json: &mut JsonMap<String, JsonValue>,
+        doc_address: DocAddress,
+        searcher: &Searcher,
+    ) -> crate::Result<()> {
+        let mut target_doc_json = doc_json.clone();
+        add_fast_only_fields(
+            &mut target_doc_json,
+            doc_address,
+            searcher,
+            &self.fast_only_field_names,
+        )?;
+        let runtime_values = self
+            .runtime_fields
+            .lock()
+            .map_err(|_| {
+                SearchError::Internal("the runtime fields evaluator lock is poisoned".to_string())
+            })?
+            .evaluate(&target_doc_json)?;
+        doc_json.extend(runtime_values);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::search::SortField;
+    use serde_json::json;
+
+    use super::*;
+
+    fn term_query(field: &str, value: &str) -> QueryAst {
+        TermQuery {
+            field: field.to_string(),
+            value: value.to_string(),
+        }
+        .into()
+    }
+
+    fn test_runtime_mappings() -> RuntimeMappings {
+        parse_runtime_mappings(Some(
+            r#"{
+                "duration_secs": {"type": "double", "script": {"source": "to_int!(.duration) / 1000"}},
+                "tier": {"type": "keyword", "script": "upcase!(.tier)"}
+            }"#,
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_runtime_mappings() {
+        assert!(parse_runtime_mappings(None).unwrap().is_empty());
+
+        let runtime_mappings = test_runtime_mappings();
+        assert_eq!(runtime_mappings.len(), 2);
+        assert_eq!(
+            runtime_mappings["duration_secs"].field_type,
+            RuntimeFieldType::Double
+        );
+        assert_eq!(
+            runtime_mappings["duration_secs"].script.source(),
+            "to_int!(.duration) / 1000"
+        );
+        assert_eq!(runtime_mappings["tier"].script.source(), "upcase!(.tier)");
+
+        let error = parse_runtime_mappings(Some(r#"{"foo": {"type": "date", "script": "."}}"#))
+            .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+
+        let error = parse_runtime_mappings(Some(r#"{"_foo": {"type": "long", "script": "."}}"#))
+            .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+    }
+
+    #[test]
+    fn test_runtime_field_value() {
+        assert_eq!(
+            RuntimeFieldType::Long.field_value(json!("42")),
+            Some(json!(42))
+        );
+        assert_eq!(
+            RuntimeFieldType::Long.field_value(json!(4.2)),
+            Some(json!(4))
+        );
+        assert_eq!(RuntimeFieldType::Long.field_value(json!("foo")), None);
+        assert_eq!(
+            RuntimeFieldType::Keyword.field_value(json!(true)),
+            Some(json!("true"))
+        );
+        assert_eq!(
+            RuntimeFieldType::Double.field_value(json!([1, "foo", "2.5"])),
+            Some(json!([1.0, 2.5]))
+        );
+        assert_eq!(
+            RuntimeFieldType::Boolean.field_value(json!([null, "false"])),
+            Some(json!(false))
+        );
+        assert_eq!(RuntimeFieldType::Boolean.field_value(json!(null)), None);
+    }
+
+    #[test]
+    fn test_split_runtime_query_ast() {
+        let runtime_mappings = test_runtime_mappings();
+        {
+            let query_ast = term_query("service", "api");
+            let (indexed_query_ast, runtime_query_ast_opt) =
+                split_runtime_query_ast(query_ast.clone(), &runtime_mappings).unwrap();
+            assert_eq!(indexed_query_ast, query_ast);
+            assert!(runtime_query_ast_opt.is_none());
+        }
+        {
+            let query_ast = term_query("tier", "GOLD");
+            let (indexed_query_ast, runtime_query_ast_opt) =
+                split_runtime_query_ast(query_ast.clone(), &runtime_mappings).unwrap();
+            assert_eq!(indexed_query_ast, QueryAst::MatchAll);
+            assert_eq!(runtime_query_ast_opt, Some(query_ast));
+        }
+        {
+            let query_ast: QueryAst = BoolQuery {
+                must: vec![term_query("service", "api")],
+                must_not: vec![term_query("tier", "GOLD")],
+                ..Default::default()
+            }
+            .into();
+            let (indexed_query_ast, runtime_query_ast_opt) =
+                split_runtime_query_ast(query_ast, &runtime_mappings).unwrap();
+            assert_eq!(
+                indexed_query_ast,
+                BoolQuery {
+                    must: vec![term_query("service", "api")],
+                    ..Default::default()
+                }
+                .into()
+            );
+            assert_eq!(
+                runtime_query_ast_opt,
+                Some(
+                    BoolQuery {
+                        must_not: vec![term_query("tier", "GOLD")],
+                        ..Default::default()
+                    }
+                    .into()
+                )
+            );
+        }
+        {
+            let query_ast: QueryAst = BoolQuery {
+                filter: vec![term_query("tier", "GOLD")],
+                should: vec![term_query("service", "api")],
+                ..Default::default()
+            }
+            .into();
+            let (indexed_query_ast, _) =
+                split_runtime_query_ast(query_ast, &runtime_mappings).unwrap();
+            assert_eq!(
+                indexed_query_ast,
+                BoolQuery {
+                    filter: vec![QueryAst::MatchAll],
+                    should: vec![term_query("service", "api")],
+                    ..Default::default()
+                }
+                .into()
+            );
+        }
+        {
+            let query_ast: QueryAst = BoolQuery {
+                should: vec![term_query("service", "api"), term_query("tier", "GOLD")],
+                ..Default::default()
+            }
+            .into();
+            let error = split_runtime_query_ast(query_ast, &runtime_mappings).unwrap_err();
+            assert!(matches!(error, SearchError::InvalidQuery(_)));
+        }
+    }
+
+    #[test]
+    fn test_collector_uses_runtime_fields() {
+        let runtime_mappings = test_runtime_mappings();
+        let sort_field = |field_name: &str| SortField {
+            field_name: field_name.to_string(),
+            ..Default::default()
+        };
+        let search_request = SearchRequest {
+            sort_fields: vec![sort_field("timestamp")],
+            ..Default::default()
+        };
+        assert!(!collector_uses_runtime_fields(&search_request, &runtime_mappings).unwrap());
+
+        let search_request = SearchRequest {
+            sort_fields: vec![sort_field("duration_secs"), sort_field("_doc")],
+            ..Default::default()
+        };
+        assert!(collector_uses_runtime_fields(&search_request, &runtime_mappings).unwrap());
+
+        let search_request = SearchRequest {
+            sort_fields: vec![sort_field("duration_secs"), sort_field("timestamp")],
+            ..Default::default()
+        };
+        collector_uses_runtime_fields(&search_request, &runtime_mappings).unwrap_err();
+
+        let search_request = SearchRequest {
+            sort_fields: vec![sort_field("tier")],
+            ..Default::default()
+        };
+        collector_uses_runtime_fields(&search_request, &runtime_mappings).unwrap_err();
+
+        let search_request = SearchRequest {
+            aggregation_request: Some(json!({"tiers": {"terms": {"field": "tier"}}}).to_string()),
+            ..Default::default()
+        };
+        assert!(collector_uses_runtime_fields(&search_request, &runtime_mappings).unwrap());
+    }
+
+    #[cfg(feature = "vrl")]
+    #[test]
+    fn test_runtime_fields_evaluate() {
+        let mut runtime_fields = RuntimeFields::compile(&test_runtime_mappings()).unwrap();
+        let doc_json = json!({"duration": 1500, "tier": "gold"});
+        let runtime_fields_json = runtime_fields
+            .evaluate(doc_json.as_object().unwrap())
+            .unwrap();
+        assert_eq!(
+            JsonValue::Object(runtime_fields_json),
+            json!({"duration_secs": 1.5, "tier": "GOLD"})
+        );
+    }
+
+    #[cfg(feature = "vrl")]
+    #[test]
+    fn test_runtime_fields_compile_error() {
+        let runtime_mappings =
+            parse_runtime_mappings(Some(r#"{"foo": {"type": "long", "script": "++"}}"#)).unwrap();
+        let error = RuntimeFields::compile(&runtime_mappings).err().unwrap();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+    }
+}
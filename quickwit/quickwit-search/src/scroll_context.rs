@@ -114,6 +114,7 @@ impl ScrollContext {
             &self.search_request,
             &self.split_metadatas[..],
             cluster_client,
+            None,
         )
         .await?;
         self.cached_partial_hits_start_offset = start_offset;
@@ -249,6 +250,7 @@ mod tests {
             split_id: "split".to_string(),
             segment_ord: 1,
             doc_id: 2,
+            collapse_key: None,
         };
         let scroll = ScrollKeyAndStartOffset::new_with_start_offset(10, 100, partial_hit);
         let scroll_str = scroll.to_string();
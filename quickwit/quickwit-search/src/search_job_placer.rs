@@ -22,6 +22,7 @@ use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
 
 use anyhow::bail;
 use async_trait::async_trait;
@@ -61,6 +62,8 @@ pub trait Job {
 pub struct SearchJobPlacer {
     /// Search clients pool.
     searcher_pool: SearcherPool,
+    /// Splits advertised as stored in the split cache of each searcher.
+    warm_splits: Arc<RwLock<HashMap<SocketAddr, HashSet<String>>>>,
 }
 
 #[async_trait]
@@ -87,7 +90,10 @@ impl EventSubscriber<ReportSplitsRequest> for SearchJobPlacer {
         }
         for (node_addr, report_splits) in splits_per_node {
             if let Some(search_client) = nodes.get_mut(&node_addr) {
-                let report_splits_req = ReportSplitsRequest { report_splits };
+                let report_splits_req = ReportSplitsRequest {
+                    report_splits,
+                    warm_up: evt.warm_up,
+                };
                 let _ = search_client.report_splits(report_splits_req).await;
             }
         }
@@ -103,7 +109,29 @@ impl fmt::Debug for SearchJobPlacer {
 impl SearchJobPlacer {
     /// Returns an [`SearchJobPlacer`] from a search service client pool.
     pub fn new(searcher_pool: SearcherPool) -> Self {
-        Self { searcher_pool }
+        Self {
+            searcher_pool,
+            warm_splits: Default::default(),
+        }
+    }
+
+    /// Registers the set of splits stored in the split cache of the searcher listening on
+    /// `grpc_addr`, as advertised in its chitchat node state, replacing the previously registered
+    /// set. Job placement favors this searcher for these splits as long as it is not more loaded
+    /// than the node picked by rendez-vous hashing.
+    pub fn register_warm_splits(&self, grpc_addr: SocketAddr, split_ids: HashSet<String>) {
+        let mut warm_splits = self.warm_splits.write().unwrap();
+        if split_ids.is_empty() {
+            warm_splits.remove(&grpc_addr);
+        } else {
+            warm_splits.insert(grpc_addr, split_ids);
+        }
+    }
+
+    /// Forgets the splits registered for the searcher listening on `grpc_addr`, e.g. when it
+    /// leaves the cluster.
+    pub fn unregister_warm_splits(&self, grpc_addr: SocketAddr) {
+        self.warm_splits.write().unwrap().remove(&grpc_addr);
     }
 }
 
@@ -177,14 +205,30 @@ impl SearchJobPlacer {
         let mut job_assignments: HashMap<SocketAddr, (SearchServiceClient, Vec<J>)> =
             HashMap::with_capacity(num_nodes);
 
+        let warm_splits = self.warm_splits.read().unwrap();
+
         for job in jobs {
             sort_by_rendez_vous_hash(&mut candidate_nodes, job.split_id());
             // Select the least loaded node.
-            let chosen_node_idx = if candidate_nodes.len() >= 2 {
+            let mut chosen_node_idx = if candidate_nodes.len() >= 2 {
                 usize::from(candidate_nodes[0].load > candidate_nodes[1].load)
             } else {
                 0
             };
+            // Favor a node that already has the split in its split cache, as long as it is not
+            // more loaded than the node we picked.
+            if !warm_splits.is_empty() {
+                let chosen_node_load = candidate_nodes[chosen_node_idx].load;
+                if let Some(warm_node_idx) = candidate_nodes.iter().position(|candidate_node| {
+                    candidate_node.load <= chosen_node_load
+                        && warm_splits
+                            .get(&candidate_node.grpc_addr)
+                            .map(|split_ids| split_ids.contains(job.split_id()))
+                            .unwrap_or(false)
+                }) {
+                    chosen_node_idx = warm_node_idx;
+                }
+            }
             let chosen_node = &mut candidate_nodes[chosen_node_idx];
             chosen_node.load += job.cost();
 
@@ -322,4 +366,53 @@ mod tests {
             assert_eq!(assigned_jobs, expected_assigned_jobs);
         }
     }
+
+    #[tokio::test]
+    async fn test_search_job_placer_favors_warm_splits() {
+        let searcher_pool = searcher_pool_for_test([
+            ("127.0.0.1:1001", MockSearchService::new()),
+            ("127.0.0.1:1002", MockSearchService::new()),
+        ]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let searcher_addr_1: SocketAddr = ([127, 0, 0, 1], 1001).into();
+        let searcher_addr_2: SocketAddr = ([127, 0, 0, 1], 1002).into();
+
+        // Without warm splits, `split6` is assigned to the first searcher.
+        let assigned_client = search_job_placer
+            .assign_job(SearchJob::for_test("split6", 6), &HashSet::new())
+            .await
+            .unwrap();
+        assert_eq!(assigned_client.grpc_addr(), searcher_addr_1);
+
+        search_job_placer
+            .register_warm_splits(searcher_addr_2, HashSet::from_iter(["split6".to_string()]));
+        let assigned_client = search_job_placer
+            .assign_job(SearchJob::for_test("split6", 6), &HashSet::new())
+            .await
+            .unwrap();
+        assert_eq!(assigned_client.grpc_addr(), searcher_addr_2);
+
+        // The other jobs are still spread across searchers.
+        let jobs = vec![
+            SearchJob::for_test("split6", 6),
+            SearchJob::for_test("split5", 5),
+        ];
+        let mut assigned_jobs: Vec<(SocketAddr, Vec<SearchJob>)> = search_job_placer
+            .assign_jobs(jobs, &HashSet::default())
+            .await
+            .unwrap()
+            .map(|(client, jobs)| (client.grpc_addr(), jobs))
+            .collect();
+        assigned_jobs.sort_unstable_by_key(|(grpc_addr, _)| *grpc_addr);
+        assert_eq!(assigned_jobs.len(), 2);
+        assert_eq!(assigned_jobs[1].0, searcher_addr_2);
+        assert_eq!(assigned_jobs[1].1, vec![SearchJob::for_test("split6", 6)]);
+
+        search_job_placer.unregister_warm_splits(searcher_addr_2);
+        let assigned_client = search_job_placer
+            .assign_job(SearchJob::for_test("split6", 6), &HashSet::new())
+            .await
+            .unwrap();
+        assert_eq!(assigned_client.grpc_addr(), searcher_addr_1);
+    }
 }
@@ -19,10 +19,16 @@
 
 use std::convert::TryFrom;
 
+use base64::Engine;
 use quickwit_common::truncate_str;
-use quickwit_proto::search::SearchResponse;
+use quickwit_datetime::DateTimeOutputFormat;
+use quickwit_proto::search::{
+    ListTermsResponse, SearchProfile, SearchResponse, StorageRequestStats,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use tantivy::schema::Type;
+use tantivy::Term;
 
 use crate::error::SearchError;
 
@@ -47,6 +53,14 @@ pub struct SearchResponseRest {
     #[schema(value_type = Object)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aggregations: Option<JsonValue>,
+    /// Search profile, only returned if profiling was requested.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<SearchProfile>,
+    /// Storage requests issued by the leaf search phase.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_request_stats: Option<StorageRequestStats>,
 }
 
 impl TryFrom<SearchResponse> for SearchResponseRest {
@@ -97,6 +111,124 @@ impl TryFrom<SearchResponse> for SearchResponseRest {
             elapsed_time_micros: search_response.elapsed_time_micros,
             errors: search_response.errors,
             aggregations: aggregations_opt,
+            profile: search_response.profile,
+            storage_request_stats: search_response.storage_request_stats,
         })
     }
 }
+
+/// ListTermsResponseRest represents the response returned by the REST list terms API
+/// and is meant to be serialized into JSON.
+#[derive(Serialize, Deserialize, PartialEq, Debug, utoipa::ToSchema)]
+pub struct ListTermsResponseRest {
+    /// Number of terms returned.
+    pub num_hits: u64,
+    /// List of terms returned, sorted in lexicographic order.
+    pub terms: Vec<TermRest>,
+    /// Elapsed time.
+    pub elapsed_time_micros: u64,
+    /// List terms errors.
+    pub errors: Vec<String>,
+}
+
+/// A term and the number of documents containing it.
+#[derive(Serialize, Deserialize, PartialEq, Debug, utoipa::ToSchema)]
+pub struct TermRest {
+    /// Value of the term.
+    #[schema(value_type = Object)]
+    pub term: JsonValue,
+    /// Number of documents containing the term, summed over all the splits. Deleted documents
+    /// are counted until their split gets merged, so this number is approximate.
+    pub doc_count: u64,
+}
+
+impl TryFrom<ListTermsResponse> for ListTermsResponseRest {
+    type Error = SearchError;
+
+    fn try_from(list_terms_response: ListTermsResponse) -> Result<Self, Self::Error> {
+        let doc_counts = list_terms_response
+            .doc_freqs
+            .into_iter()
+            .chain(std::iter::repeat(0));
+        let terms = list_terms_response
+            .terms
+            .iter()
+            .zip(doc_counts)
+            .map(|(term_bytes, doc_count)| {
+                let term = term_to_json(term_bytes)?;
+                Ok(TermRest { term, doc_count })
+            })
+            .collect::<Result<Vec<TermRest>, SearchError>>()?;
+        Ok(ListTermsResponseRest {
+            num_hits: list_terms_response.num_hits,
+            terms,
+            elapsed_time_micros: list_terms_response.elapsed_time_micros,
+            errors: list_terms_response.errors,
+        })
+    }
+}
+
+/// Converts a serialized term, as returned by list terms, into a JSON value.
+fn term_to_json(term_bytes: &[u8]) -> Result<JsonValue, SearchError> {
+    let term_value = Term::wrap(term_bytes).value();
+    let typ = term_value.typ();
+    let json_value_opt = match typ {
+        Type::Str => term_value.as_str().map(JsonValue::from),
+        Type::U64 => term_value.as_u64().map(JsonValue::from),
+        Type::I64 => term_value.as_i64().map(JsonValue::from),
+        Type::F64 => term_value.as_f64().map(JsonValue::from),
+        Type::Bool => term_value.as_bool().map(JsonValue::from),
+        Type::Date => term_value
+            .as_date()
+            .and_then(|date| DateTimeOutputFormat::Rfc3339.format_to_json(date).ok()),
+        Type::Bytes => term_value
+            .as_bytes()
+            .map(|bytes| JsonValue::from(base64::prelude::BASE64_STANDARD.encode(bytes))),
+        Type::IpAddr => term_value
+            .as_ip_addr()
+            .map(|ip_addr| JsonValue::from(ip_addr.to_string())),
+        Type::Facet | Type::Json => None,
+    };
+    json_value_opt.ok_or_else(|| {
+        SearchError::Internal(format!("failed to convert term of type `{typ:?}` to JSON"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_terms_response_rest() {
+        let text_field = tantivy::schema::Field::from_field_id(0);
+        let u64_field = tantivy::schema::Field::from_field_id(1);
+        let list_terms_response = ListTermsResponse {
+            num_hits: 2,
+            terms: vec![
+                Term::from_field_text(text_field, "beagle")
+                    .serialized_term()
+                    .to_vec(),
+                Term::from_field_u64(u64_field, 42)
+                    .serialized_term()
+                    .to_vec(),
+            ],
+            elapsed_time_micros: 10,
+            errors: Vec::new(),
+            doc_freqs: vec![3, 1],
+        };
+        let list_terms_response_rest =
+            ListTermsResponseRest::try_from(list_terms_response).unwrap();
+        assert_eq!(
+            serde_json::to_value(list_terms_response_rest).unwrap(),
+            serde_json::json!({
+                "num_hits": 2,
+                "terms": [
+                    {"term": "beagle", "doc_count": 3},
+                    {"term": 42, "doc_count": 1},
+                ],
+                "elapsed_time_micros": 10,
+                "errors": [],
+            })
+        );
+    }
+}
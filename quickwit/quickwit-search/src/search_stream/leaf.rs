@@ -133,6 +133,7 @@ async fn leaf_search_stream_single_split(
         &split,
         Some(doc_mapper.tokenizer_manager()),
         true,
+        None,
     )
     .await?;
     let split_schema = index.schema();
@@ -21,9 +21,10 @@ use std::collections::HashSet;
 
 use bytes::Bytes;
 use futures::{StreamExt, TryStreamExt};
+use itertools::Itertools;
 use quickwit_common::uri::Uri;
 use quickwit_config::build_doc_mapper;
-use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
+use quickwit_doc_mapper::tag_pruning::extract_tags_from_query_with_derived_tags;
 use quickwit_metastore::IndexMetadataResponseExt;
 use quickwit_proto::metastore::{IndexMetadataRequest, MetastoreService, MetastoreServiceClient};
 use quickwit_proto::search::{LeafSearchStreamRequest, SearchRequest, SearchStreamRequest};
@@ -61,7 +62,10 @@ pub async fn root_search_stream(
     let query_ast: QueryAst = serde_json::from_str(&search_stream_request.query_ast)
         .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
     let query_ast_resolved = query_ast.parse_user_query(doc_mapper.default_search_fields())?;
-    let tags_filter_ast = extract_tags_from_query(query_ast_resolved.clone());
+    let tags_filter_ast = extract_tags_from_query_with_derived_tags(
+        query_ast_resolved.clone(),
+        &doc_mapper.derived_tag_fields(),
+    );
 
     if let Some(timestamp_field) = doc_mapper.timestamp_field_name() {
         refine_start_end_timestamp_from_ast(
@@ -98,35 +102,47 @@ pub async fn root_search_stream(
         .await?;
 
     let mut stream_map: StreamMap<usize, _> = StreamMap::new();
-    for (leaf_ord, (client, client_jobs)) in assigned_leaf_search_jobs.enumerate() {
-        let leaf_request: LeafSearchStreamRequest = jobs_to_leaf_request(
+    for (client, client_jobs) in assigned_leaf_search_jobs {
+        let leaf_requests: Vec<LeafSearchStreamRequest> = jobs_to_leaf_requests(
             &search_stream_request,
             &doc_mapper_str,
             index_uri.as_ref(),
             client_jobs,
         );
-        let leaf_stream = cluster_client
-            .leaf_search_stream(leaf_request, client)
-            .await;
-        stream_map.insert(leaf_ord, leaf_stream);
+        for leaf_request in leaf_requests {
+            let leaf_stream = cluster_client
+                .leaf_search_stream(leaf_request, client.clone())
+                .await;
+            stream_map.insert(stream_map.len(), leaf_stream);
+        }
     }
     Ok(stream_map
         .map(|(_leaf_ord, result)| result)
         .map_ok(|leaf_response| Bytes::from(leaf_response.data)))
 }
 
-fn jobs_to_leaf_request(
+/// Builds one [`LeafSearchStreamRequest`] per storage holding the splits of the jobs.
+fn jobs_to_leaf_requests(
     request: &SearchStreamRequest,
     doc_mapper_str: &str,
     index_uri: &str, // TODO make Uri
     jobs: Vec<SearchJob>,
-) -> LeafSearchStreamRequest {
-    LeafSearchStreamRequest {
-        request: Some(request.clone()),
-        split_offsets: jobs.into_iter().map(Into::into).collect(),
-        doc_mapper: doc_mapper_str.to_string(),
-        index_uri: index_uri.to_string(),
+) -> Vec<LeafSearchStreamRequest> {
+    let mut leaf_requests = Vec::new();
+
+    for (storage_uri_opt, job_group) in &jobs
+        .into_iter()
+        .group_by(|job| job.offsets.storage_uri.clone())
+    {
+        let leaf_request = LeafSearchStreamRequest {
+            request: Some(request.clone()),
+            split_offsets: job_group.map(Into::into).collect(),
+            doc_mapper: doc_mapper_str.to_string(),
+            index_uri: storage_uri_opt.unwrap_or_else(|| index_uri.to_string()),
+        };
+        leaf_requests.push(leaf_request);
     }
+    leaf_requests
 }
 
 #[cfg(test)]
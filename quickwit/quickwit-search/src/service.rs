@@ -29,14 +29,17 @@ use quickwit_config::SearcherConfig;
 use quickwit_doc_mapper::DocMapper;
 use quickwit_proto::metastore::MetastoreServiceClient;
 use quickwit_proto::search::{
-    FetchDocsRequest, FetchDocsResponse, GetKvRequest, Hit, LeafListFieldsRequest,
-    LeafListTermsRequest, LeafListTermsResponse, LeafSearchRequest, LeafSearchResponse,
-    LeafSearchStreamRequest, LeafSearchStreamResponse, ListFieldsRequest, ListFieldsResponse,
-    ListTermsRequest, ListTermsResponse, PutKvRequest, ReportSplitsRequest, ReportSplitsResponse,
-    ScrollRequest, SearchRequest, SearchResponse, SearchStreamRequest, SnippetRequest,
+    ClosePointInTimeRequest, ClosePointInTimeResponse, FetchDocsRequest, FetchDocsResponse,
+    GetKvRequest, Hit, LeafListFieldsRequest, LeafListTermsRequest, LeafListTermsResponse,
+    LeafSearchRequest, LeafSearchResponse, LeafSearchStreamRequest, LeafSearchStreamResponse,
+    ListFieldsRequest, ListFieldsResponse, ListTermsRequest, ListTermsResponse,
+    OpenPointInTimeRequest, OpenPointInTimeResponse, PutKvRequest, ReportSplitsRequest,
+    ReportSplitsResponse, ScrollRequest, SearchRequest, SearchResponse, SearchStreamRequest,
+    SnippetRequest, StorageRequestStats,
 };
 use quickwit_storage::{
-    MemorySizedCache, QuickwitCache, SplitCache, StorageCache, StorageResolver,
+    wrap_storage_with_request_accounting, MemorySizedCache, QuickwitCache, SplitCache,
+    StorageCache, StorageOperation, StorageRequestContext, StorageRequestCounter, StorageResolver,
 };
 use tantivy::aggregation::AggregationLimits;
 use tokio::sync::Semaphore;
@@ -46,9 +49,13 @@ use crate::leaf_cache::LeafSearchCache;
 use crate::list_fields::{leaf_list_fields, root_list_fields};
 use crate::list_fields_cache::ListFieldsCache;
 use crate::list_terms::{leaf_list_terms, root_list_terms};
+use crate::point_in_time::{close_point_in_time, open_point_in_time};
 use crate::root::fetch_docs_phase;
+use crate::root_cache::RootSearchCache;
+use crate::runtime_fields::parse_runtime_mappings;
 use crate::scroll_context::{MiniKV, ScrollContext, ScrollKeyAndStartOffset};
 use crate::search_stream::{leaf_search_stream, root_search_stream};
+use crate::split_warmup::warm_up_splits;
 use crate::{fetch_docs, leaf_search, root_search, ClusterClient, SearchError};
 
 #[derive(Clone)]
@@ -124,6 +131,19 @@ pub trait SearchService: 'static + Send + Sync {
     /// Performs a scroll request.
     async fn scroll(&self, scroll_request: ScrollRequest) -> crate::Result<SearchResponse>;
 
+    /// Opens a point in time, pinning the splits currently published for the targeted indexes.
+    /// Search requests referencing the point in time run on these splits only.
+    async fn open_point_in_time(
+        &self,
+        open_request: OpenPointInTimeRequest,
+    ) -> crate::Result<OpenPointInTimeResponse>;
+
+    /// Closes a point in time before its keep alive expires.
+    async fn close_point_in_time(
+        &self,
+        close_request: ClosePointInTimeRequest,
+    ) -> crate::Result<ClosePointInTimeResponse>;
+
     /// Stores a Key value in the local cache.
     /// This operation is not distributed. The distribution logic lives in
     /// the `ClusterClient`.
@@ -175,6 +195,13 @@ fn deserialize_doc_mapper(doc_mapper_str: &str) -> crate::Result<Arc<dyn DocMapp
     Ok(doc_mapper)
 }
 
+fn storage_request_stats(storage_request_counter: &StorageRequestCounter) -> StorageRequestStats {
+    StorageRequestStats {
+        num_requests: storage_request_counter.num_requests(),
+        num_bytes_downloaded: storage_request_counter.num_bytes(),
+    }
+}
+
 #[async_trait]
 impl SearchService for SearchServiceImpl {
     async fn root_search(&self, search_request: SearchRequest) -> crate::Result<SearchResponse> {
@@ -198,16 +225,23 @@ impl SearchService for SearchServiceImpl {
             .into();
         let index_uri = Uri::from_str(&leaf_search_request.index_uri)?;
         let storage = self.storage_resolver.resolve(&index_uri).await?;
+        let storage_request_counter = StorageRequestCounter::default();
+        let storage_request_context =
+            StorageRequestContext::new(leaf_search_request.index_id, StorageOperation::Search)
+                .with_counter(storage_request_counter.clone());
+        let storage = wrap_storage_with_request_accounting(storage, storage_request_context);
         let doc_mapper = deserialize_doc_mapper(&leaf_search_request.doc_mapper)?;
 
-        let leaf_search_response = leaf_search(
+        let mut leaf_search_response = leaf_search(
             self.searcher_context.clone(),
             search_request,
-            storage.clone(),
+            storage,
             leaf_search_request.split_offsets,
             doc_mapper,
         )
         .await?;
+        leaf_search_response.storage_request_stats =
+            Some(storage_request_stats(&storage_request_counter));
 
         Ok(leaf_search_response)
     }
@@ -218,18 +252,28 @@ impl SearchService for SearchServiceImpl {
     ) -> crate::Result<FetchDocsResponse> {
         let index_uri = Uri::from_str(&fetch_docs_request.index_uri)?;
         let storage = self.storage_resolver.resolve(&index_uri).await?;
+        let storage_request_counter = StorageRequestCounter::default();
+        let storage_request_context =
+            StorageRequestContext::new(fetch_docs_request.index_id, StorageOperation::Search)
+                .with_counter(storage_request_counter.clone());
+        let storage = wrap_storage_with_request_accounting(storage, storage_request_context);
         let snippet_request_opt: Option<&SnippetRequest> =
             fetch_docs_request.snippet_request.as_ref();
         let doc_mapper = deserialize_doc_mapper(&fetch_docs_request.doc_mapper)?;
-        let fetch_docs_response = fetch_docs(
+        let runtime_mappings =
+            parse_runtime_mappings(fetch_docs_request.runtime_mappings.as_deref())?;
+        let mut fetch_docs_response = fetch_docs(
             self.searcher_context.clone(),
             fetch_docs_request.partial_hits,
             storage,
             &fetch_docs_request.split_offsets,
             doc_mapper,
             snippet_request_opt,
+            &runtime_mappings,
         )
         .await?;
+        fetch_docs_response.storage_request_stats =
+            Some(storage_request_stats(&storage_request_counter));
 
         Ok(fetch_docs_response)
     }
@@ -308,6 +352,20 @@ impl SearchService for SearchServiceImpl {
         scroll(scroll_request, &self.cluster_client, &self.searcher_context).await
     }
 
+    async fn open_point_in_time(
+        &self,
+        open_request: OpenPointInTimeRequest,
+    ) -> crate::Result<OpenPointInTimeResponse> {
+        open_point_in_time(open_request, self.metastore.clone(), &self.cluster_client).await
+    }
+
+    async fn close_point_in_time(
+        &self,
+        close_request: ClosePointInTimeRequest,
+    ) -> crate::Result<ClosePointInTimeResponse> {
+        close_point_in_time(close_request, &self.cluster_client).await
+    }
+
     async fn put_kv(&self, put_request: PutKvRequest) {
         let ttl = Duration::from_secs(put_request.ttl_secs as u64);
         self.search_after_cache
@@ -321,6 +379,15 @@ impl SearchService for SearchServiceImpl {
     }
 
     async fn report_splits(&self, report_splits: ReportSplitsRequest) -> ReportSplitsResponse {
+        if report_splits.warm_up {
+            // The warm-up runs in the background: the indexer does not wait for it.
+            tokio::spawn(warm_up_splits(
+                self.searcher_context.clone(),
+                self.storage_resolver.clone(),
+                report_splits.report_splits,
+            ));
+            return ReportSplitsResponse {};
+        }
         if let Some(split_cache) = self.searcher_context.split_cache_opt.as_ref() {
             split_cache.report_splits(report_splits.report_splits);
         }
@@ -396,7 +463,7 @@ pub(crate) async fn scroll(
     }
 
     // Fetch the actual documents.
-    let hits: Vec<Hit> = fetch_docs_phase(
+    let (hits, _storage_request_stats): (Vec<Hit>, _) = fetch_docs_phase(
         &scroll_context.indexes_metas_for_leaf_search,
         &partial_hits[..],
         &scroll_context.split_metadatas[..],
@@ -428,6 +495,9 @@ pub(crate) async fn scroll(
         scroll_id: Some(next_scroll_id.to_string()),
         errors: Vec::new(),
         aggregation: None,
+        profile: None,
+        pit_id: None,
+        storage_request_stats: None,
     })
 }
 /// [`SearcherContext`] provides a common set of variables
@@ -450,6 +520,11 @@ pub struct SearcherContext {
     pub split_cache_opt: Option<Arc<SplitCache>>,
     /// List fields cache. Caches the list fields response for a given split.
     pub list_fields_cache: ListFieldsCache,
+    /// Root search cache. Caches the merged response for a given request and set of splits.
+    pub root_search_cache: RootSearchCache,
+    /// Counting semaphore to limit concurrent split warm-ups. `None` if split warm-up is not
+    /// enabled.
+    pub split_warmup_semaphore_opt: Option<Arc<Semaphore>>,
 }
 
 impl std::fmt::Debug for SearcherContext {
@@ -490,6 +565,17 @@ impl SearcherContext {
             LeafSearchCache::new(searcher_config.partial_request_cache_capacity.as_u64() as usize);
         let list_fields_cache =
             ListFieldsCache::new(searcher_config.partial_request_cache_capacity.as_u64() as usize);
+        let root_search_cache =
+            RootSearchCache::new(searcher_config.root_search_cache_capacity.as_u64() as usize);
+        let split_warmup_semaphore_opt =
+            searcher_config
+                .split_warmup
+                .as_ref()
+                .map(|split_warmup_config| {
+                    Arc::new(Semaphore::new(
+                        split_warmup_config.max_concurrent_warmups.get(),
+                    ))
+                });
 
         Self {
             searcher_config,
@@ -499,7 +585,9 @@ impl SearcherContext {
             split_stream_semaphore,
             leaf_search_cache,
             list_fields_cache,
+            root_search_cache,
             split_cache_opt,
+            split_warmup_semaphore_opt,
         }
     }
 
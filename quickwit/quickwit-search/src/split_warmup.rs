@@ -0,0 +1,224 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use quickwit_common::uri::Uri;
+use quickwit_proto::search::{ReportSplit, SplitIdAndFooterOffsets};
+use quickwit_storage::StorageResolver;
+use tantivy::ReloadPolicy;
+use tracing::{debug, warn};
+
+use crate::leaf::{
+    get_split_footer_from_cache_or_fetch, open_index_with_caches, warm_up_fastfields,
+};
+use crate::service::SearcherContext;
+
+/// Warms up freshly uploaded splits, so that the first search request on them does not pay for
+/// downloading their footer and the configured fast fields:
+/// - the split footer is fetched into `SearcherContext.split_footer_cache`;
+/// - the fast fields listed in the split warm-up config are loaded into
+///   `SearcherContext.fast_fields_cache`.
+///
+/// At most `max_concurrent_warmups` splits are warmed up concurrently. Errors are logged and
+/// swallowed. This is a no-op if split warm-up is not enabled.
+pub(crate) async fn warm_up_splits(
+    searcher_context: Arc<SearcherContext>,
+    storage_resolver: StorageResolver,
+    report_splits: Vec<ReportSplit>,
+) {
+    let Some(split_warmup_semaphore) = searcher_context.split_warmup_semaphore_opt.clone() else {
+        return;
+    };
+    let fast_field_names: Arc<HashSet<String>> = Arc::new(
+        searcher_context
+            .searcher_config
+            .split_warmup
+            .as_ref()
+            .map(|split_warmup_config| split_warmup_config.fast_fields.iter().cloned().collect())
+            .unwrap_or_default(),
+    );
+    let mut warm_up_handles = Vec::with_capacity(report_splits.len());
+
+    for report_split in report_splits {
+        if report_split.split_footer_start >= report_split.split_footer_end {
+            continue;
+        }
+        let Ok(permit) = split_warmup_semaphore.clone().acquire_owned().await else {
+            return;
+        };
+        let searcher_context = searcher_context.clone();
+        let storage_resolver = storage_resolver.clone();
+        let fast_field_names = fast_field_names.clone();
+
+        let warm_up_handle = tokio::spawn(async move {
+            if let Err(error) = warm_up_split(
+                &searcher_context,
+                &storage_resolver,
+                &report_split,
+                &fast_field_names,
+            )
+            .await
+            {
+                warn!(split_id=%report_split.split_id, error=?error, "failed to warm up split");
+            } else {
+                debug!(split_id=%report_split.split_id, "warmed up split");
+            }
+            drop(permit);
+        });
+        warm_up_handles.push(warm_up_handle);
+    }
+    for warm_up_handle in warm_up_handles {
+        let _ = warm_up_handle.await;
+    }
+}
+
+async fn warm_up_split(
+    searcher_context: &SearcherContext,
+    storage_resolver: &StorageResolver,
+    report_split: &ReportSplit,
+    fast_field_names: &HashSet<String>,
+) -> anyhow::Result<()> {
+    let storage_uri = Uri::from_str(&report_split.storage_uri)?;
+    let index_storage = storage_resolver.resolve(&storage_uri).await?;
+    let split_and_footer_offsets = SplitIdAndFooterOffsets {
+        split_id: report_split.split_id.clone(),
+        split_footer_start: report_split.split_footer_start,
+        split_footer_end: report_split.split_footer_end,
+        timestamp_start: None,
+        timestamp_end: None,
+        storage_uri: None,
+    };
+    if fast_field_names.is_empty() {
+        get_split_footer_from_cache_or_fetch(
+            index_storage,
+            &split_and_footer_offsets,
+            &searcher_context.split_footer_cache,
+        )
+        .await?;
+        return Ok(());
+    }
+    // Opening the index fetches the split footer through the split footer cache. The index
+    // storage is wrapped with the fast fields cache, which retains the fast field data.
+    let index = open_index_with_caches(
+        searcher_context,
+        index_storage,
+        &split_and_footer_offsets,
+        None,
+        false,
+        None,
+    )
+    .await?;
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = reader.searcher();
+    warm_up_fastfields(&searcher, fast_field_names).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_config::{SearcherConfig, SplitWarmupConfig};
+    use quickwit_indexing::TestSandbox;
+    use quickwit_metastore::{ListSplitsRequestExt, MetastoreServiceStreamSplitsExt};
+    use quickwit_proto::metastore::{ListSplitsRequest, MetastoreService};
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_warm_up_splits() {
+        let index_id = "test-warm-up-splits";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: ts
+                type: datetime
+                fast: true
+            timestamp_field: ts
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "", &["body"])
+            .await
+            .unwrap();
+        test_sandbox
+            .add_documents(vec![
+                json!({"body": "foo", "ts": 1_700_000_000}),
+                json!({"body": "bar", "ts": 1_700_000_001}),
+            ])
+            .await
+            .unwrap();
+        let split = test_sandbox
+            .metastore()
+            .list_splits(ListSplitsRequest::try_from_index_uid(test_sandbox.index_uid()).unwrap())
+            .await
+            .unwrap()
+            .collect_splits()
+            .await
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        let report_split = ReportSplit {
+            split_id: split.split_id().to_string(),
+            storage_uri: test_sandbox.storage().uri().to_string(),
+            split_footer_start: split.split_metadata.footer_offsets.start,
+            split_footer_end: split.split_metadata.footer_offsets.end,
+        };
+        {
+            // Warm-up is disabled by default.
+            let searcher_context = Arc::new(SearcherContext::for_test());
+            warm_up_splits(
+                searcher_context.clone(),
+                test_sandbox.storage_resolver(),
+                vec![report_split.clone()],
+            )
+            .await;
+            assert!(searcher_context
+                .split_footer_cache
+                .get(split.split_id())
+                .is_none());
+        }
+        {
+            let searcher_config = SearcherConfig {
+                split_warmup: Some(SplitWarmupConfig {
+                    fast_fields: vec!["ts".to_string()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            let searcher_context = Arc::new(SearcherContext::new(searcher_config, None));
+            warm_up_splits(
+                searcher_context.clone(),
+                test_sandbox.storage_resolver(),
+                vec![report_split],
+            )
+            .await;
+            assert!(searcher_context
+                .split_footer_cache
+                .get(split.split_id())
+                .is_some());
+        }
+        test_sandbox.assert_quit().await;
+    }
+}
@@ -1404,6 +1404,76 @@ async fn test_single_node_aggregation() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_single_node_aggregation_on_dynamic_template_field() -> anyhow::Result<()> {
+    let index_id = "single-node-agg-dynamic-template";
+    let doc_mapping_yaml = r#"
+            mode: dynamic
+            dynamic_mapping:
+              indexed: true
+              stored: true
+              fast: false
+            field_mappings:
+              - name: color
+                type: text
+            dynamic_templates:
+              - name: metrics
+                path_match: "metrics.*"
+                mapping:
+                  fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["color"]).await?;
+    let docs = vec![
+        json!({"color": "blue", "metrics": {"cpu": 10.0}}),
+        json!({"color": "blue", "metrics": {"cpu": 15.0}}),
+        json!({"color": "green", "metrics": {"cpu": 20.0}}),
+        json!({"color": "white"}),
+    ];
+    test_sandbox.add_documents(docs).await?;
+    let agg_req = r#"
+ {
+   "cpu_stats": {
+     "stats": {
+       "field": "metrics.cpu"
+     }
+   }
+ }"#;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 3,
+        sort_fields: vec![SortField {
+            field_name: "metrics.cpu".to_string(),
+            sort_order: SortOrder::Desc as i32,
+            sort_datetime_format: None,
+        }],
+        aggregation_request: Some(agg_req.to_string()),
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    let agg_res_json: JsonValue = serde_json::from_str(&single_node_result.aggregation.unwrap())?;
+    assert_eq!(agg_res_json["cpu_stats"]["count"], 3);
+    assert_eq!(agg_res_json["cpu_stats"]["min"], 10.0);
+    assert_eq!(agg_res_json["cpu_stats"]["max"], 20.0);
+    assert_eq!(agg_res_json["cpu_stats"]["sum"], 45.0);
+    let hit_cpus: Vec<JsonValue> = single_node_result
+        .hits
+        .iter()
+        .map(|hit| {
+            let hit_json: JsonValue = serde_json::from_str(&hit.json).unwrap();
+            hit_json["metrics"]["cpu"].clone()
+        })
+        .collect();
+    assert_eq!(hit_cpus, vec![json!(20.0), json!(15.0), json!(10.0)]);
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_node_aggregation_missing_fast_field() {
     let index_id = "single-node-agg-2";
@@ -1693,6 +1763,8 @@ async fn test_single_node_list_terms() -> anyhow::Result<()> {
             field: "title".to_string(),
             start_key: None,
             end_key: None,
+            prefix: None,
+            regex: None,
             start_timestamp: None,
             end_timestamp: None,
             max_hits: Some(100),
@@ -1714,6 +1786,8 @@ async fn test_single_node_list_terms() -> anyhow::Result<()> {
             field: "title".to_string(),
             start_key: None,
             end_key: None,
+            prefix: None,
+            regex: None,
             start_timestamp: None,
             end_timestamp: None,
             max_hits: Some(1),
@@ -1735,6 +1809,8 @@ async fn test_single_node_list_terms() -> anyhow::Result<()> {
             field: "title".to_string(),
             start_key: Some("casper".as_bytes().to_vec()),
             end_key: None,
+            prefix: None,
+            regex: None,
             start_timestamp: None,
             end_timestamp: None,
             max_hits: Some(100),
@@ -1756,6 +1832,8 @@ async fn test_single_node_list_terms() -> anyhow::Result<()> {
             field: "title".to_string(),
             start_key: None,
             end_key: Some("casper".as_bytes().to_vec()),
+            prefix: None,
+            regex: None,
             start_timestamp: None,
             end_timestamp: None,
             max_hits: Some(100),
@@ -1771,6 +1849,54 @@ async fn test_single_node_list_terms() -> anyhow::Result<()> {
         let terms = collect_str_terms(search_response);
         assert_eq!(terms, &["beagle"]);
     }
+    {
+        let request = ListTermsRequest {
+            index_id_patterns: vec![test_sandbox.index_uid().index_id().to_string()],
+            field: "body".to_string(),
+            start_key: None,
+            end_key: None,
+            start_timestamp: None,
+            end_timestamp: None,
+            max_hits: Some(100),
+            prefix: Some("b".to_string()),
+            regex: None,
+        };
+        let search_response = leaf_list_terms(
+            searcher_context.clone(),
+            &request,
+            test_sandbox.storage(),
+            &splits_offsets,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.doc_freqs, &[2, 1]);
+        let terms = collect_str_terms(search_response);
+        assert_eq!(terms, &["beagle", "breed"]);
+    }
+    {
+        let request = ListTermsRequest {
+            index_id_patterns: vec![test_sandbox.index_uid().index_id().to_string()],
+            field: "body".to_string(),
+            start_key: None,
+            end_key: None,
+            start_timestamp: None,
+            end_timestamp: None,
+            max_hits: Some(2),
+            prefix: None,
+            regex: Some("s.*".to_string()),
+        };
+        let search_response = leaf_list_terms(
+            searcher_context.clone(),
+            &request,
+            test_sandbox.storage(),
+            &splits_offsets,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.doc_freqs, &[1, 1]);
+        let terms = collect_str_terms(search_response);
+        assert_eq!(terms, &["scent", "similar"]);
+    }
     test_sandbox.assert_quit().await;
     Ok(())
 }
@@ -0,0 +1,277 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, HashSet};
+
+use quickwit_common::binary_heap::TopK;
+use quickwit_proto::search::{Hit, PartialHit, SortOrder};
+use quickwit_query::OneFieldMap;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tantivy::{DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
+
+use crate::collector::{
+    get_score_extractor, top_k_partial_hits, HitSortingMapper, SegmentPartialHit,
+    SegmentPartialHitSortingKey, SortByComponent, SortByPair, SortingFieldExtractorPair,
+};
+use crate::GlobalDocAddress;
+
+/// Maximum number of hits a `top_hits` aggregation can return, per bucket.
+const MAX_TOP_HITS_SIZE: usize = 100;
+
+fn default_top_hits_size() -> usize {
+    3
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum TopHitsSortParams {
+    Order(SortOrder),
+    Object { order: SortOrder },
+}
+
+impl TopHitsSortParams {
+    fn order(&self) -> SortOrder {
+        match self {
+            TopHitsSortParams::Order(order) | TopHitsSortParams::Object { order } => *order,
+        }
+    }
+}
+
+/// The `top_hits` aggregation returns the best documents of a bucket, according to a sort order
+/// on up to two fast fields. Documents are sorted by descending doc id by default.
+///
+/// The documents themselves are only fetched at the end of the root search, through the fetch
+/// docs phase.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TopHitsAggregation {
+    #[serde(default = "default_top_hits_size")]
+    pub size: usize,
+    #[serde(default)]
+    pub from: usize,
+    #[serde(default)]
+    sort: Vec<OneFieldMap<TopHitsSortParams>>,
+}
+
+impl TopHitsAggregation {
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        if self.size + self.from > MAX_TOP_HITS_SIZE {
+            anyhow::bail!(
+                "`from` + `size` of a `top_hits` aggregation must be at most {MAX_TOP_HITS_SIZE}, \
+                 got {}",
+                self.size + self.from
+            );
+        }
+        if self.sort.len() > 2 {
+            anyhow::bail!(
+                "`top_hits` aggregations can be sorted by up to 2 fields, got {}",
+                self.sort.len()
+            );
+        }
+        if self
+            .sort
+            .iter()
+            .any(|sort_field| sort_field.field == "_score")
+        {
+            anyhow::bail!("`top_hits` aggregations cannot be sorted by `_score`");
+        }
+        Ok(())
+    }
+
+    pub(crate) fn fast_field_names(&self) -> HashSet<String> {
+        let mut fast_field_names = HashSet::new();
+        let sort_by = self.sort_by();
+        sort_by.first.add_fast_field(&mut fast_field_names);
+        if let Some(second) = &sort_by.second {
+            second.add_fast_field(&mut fast_field_names);
+        }
+        fast_field_names
+    }
+
+    fn num_hits_to_collect(&self) -> usize {
+        self.from + self.size
+    }
+
+    fn sort_by(&self) -> SortByPair {
+        let to_sort_by_component = |sort_field: &OneFieldMap<TopHitsSortParams>| {
+            let order = sort_field.value.order();
+            if sort_field.field == "_doc" || sort_field.field == "_shard_doc" {
+                SortByComponent::DocId { order }
+            } else {
+                SortByComponent::FastField {
+                    field_name: sort_field.field.clone(),
+                    order,
+                }
+            }
+        };
+        match &self.sort[..] {
+            [] => SortByComponent::DocId {
+                order: SortOrder::Desc,
+            }
+            .into(),
+            [sort_field] => to_sort_by_component(sort_field).into(),
+            [sort_field1, sort_field2, ..] => SortByPair {
+                first: to_sort_by_component(sort_field1),
+                second: Some(to_sort_by_component(sort_field2)),
+            },
+        }
+    }
+
+    fn sort_key_mapper(&self) -> HitSortingMapper {
+        let (order1, order2) = self.sort_by().sort_orders();
+        HitSortingMapper { order1, order2 }
+    }
+
+    /// Builds the final `top_hits` result, in the Elasticsearch format, out of the merged
+    /// intermediate result and the fetched documents.
+    pub(crate) fn into_final_result(
+        &self,
+        intermediate_top_hits: IntermediateTopHits,
+        docs: &HashMap<GlobalDocAddress, Hit>,
+    ) -> serde_json::Value {
+        let hits: Vec<serde_json::Value> = intermediate_top_hits
+            .hits
+            .into_iter()
+            .skip(self.from)
+            .take(self.size)
+            .filter_map(|partial_hit| {
+                let hit = docs.get(&GlobalDocAddress::from_partial_hit(&partial_hit))?;
+                let source: serde_json::Value =
+                    serde_json::from_str(&hit.json).unwrap_or_else(|_| json!({}));
+                let sort: Vec<serde_json::Value> =
+                    [partial_hit.sort_value, partial_hit.sort_value2]
+                        .into_iter()
+                        .flatten()
+                        .map(|sort_value| sort_value.into_json())
+                        .collect();
+                Some(json!({
+                    "_index": hit.index_id,
+                    "_id": "",
+                    "_source": source,
+                    "sort": sort,
+                }))
+            })
+            .collect();
+        json!({
+            "hits": {
+                "total": {
+                    "value": intermediate_top_hits.num_hits,
+                    "relation": "eq",
+                },
+                "hits": hits,
+            }
+        })
+    }
+}
+
+/// Best hits of a bucket, merged across segments and splits.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct IntermediateTopHits {
+    pub num_hits: u64,
+    pub hits: Vec<PartialHit>,
+}
+
+impl IntermediateTopHits {
+    pub(crate) fn merge(&mut self, other: IntermediateTopHits, top_hits: &TopHitsAggregation) {
+        let HitSortingMapper { order1, order2 } = top_hits.sort_key_mapper();
+        self.num_hits += other.num_hits;
+        self.hits = top_k_partial_hits(
+            std::mem::take(&mut self.hits).into_iter().chain(other.hits),
+            order1,
+            order2,
+            top_hits.num_hits_to_collect(),
+        );
+    }
+}
+
+/// Extracts the sort values of the documents of a segment for a `top_hits` aggregation.
+///
+/// A single extractor is shared by all the buckets of an aggregation, each bucket holding its
+/// own [`SegmentTopHits`].
+pub(crate) struct TopHitsSegmentExtractor {
+    num_hits_to_collect: usize,
+    score_extractor: SortingFieldExtractorPair,
+    sort_key_mapper: HitSortingMapper,
+}
+
+pub(crate) struct SegmentTopHits {
+    num_hits: u64,
+    top_k_hits: TopK<SegmentPartialHit, SegmentPartialHitSortingKey, HitSortingMapper>,
+}
+
+impl TopHitsSegmentExtractor {
+    pub(crate) fn new(
+        top_hits: &TopHitsAggregation,
+        segment_reader: &SegmentReader,
+    ) -> tantivy::Result<Self> {
+        top_hits
+            .validate()
+            .map_err(|error| TantivyError::InvalidArgument(error.to_string()))?;
+        Ok(TopHitsSegmentExtractor {
+            num_hits_to_collect: top_hits.num_hits_to_collect(),
+            score_extractor: get_score_extractor(&top_hits.sort_by(), segment_reader)?,
+            sort_key_mapper: top_hits.sort_key_mapper(),
+        })
+    }
+
+    pub(crate) fn new_top_hits(&self) -> SegmentTopHits {
+        SegmentTopHits {
+            num_hits: 0,
+            top_k_hits: TopK::new(self.num_hits_to_collect, self.sort_key_mapper.clone()),
+        }
+    }
+
+    pub(crate) fn collect(
+        &self,
+        segment_top_hits: &mut SegmentTopHits,
+        doc_id: DocId,
+        score: Score,
+    ) {
+        segment_top_hits.num_hits += 1;
+        let (sort_value, sort_value2) =
+            self.score_extractor.extract_typed_sort_value(doc_id, score);
+        let hit = SegmentPartialHit {
+            sort_value,
+            sort_value2,
+            doc_id,
+        };
+        segment_top_hits.top_k_hits.add_entry(hit);
+    }
+
+    pub(crate) fn harvest(
+        &self,
+        segment_top_hits: SegmentTopHits,
+        split_id: &str,
+        segment_ord: SegmentOrdinal,
+    ) -> IntermediateTopHits {
+        let hits = segment_top_hits
+            .top_k_hits
+            .finalize()
+            .into_iter()
+            .map(|segment_partial_hit| {
+                segment_partial_hit.into_partial_hit(split_id.to_string(), segment_ord)
+            })
+            .collect();
+        IntermediateTopHits {
+            num_hits: segment_top_hits.num_hits,
+            hits,
+        }
+    }
+}
@@ -150,6 +150,12 @@ pub async fn post_delete_request(
         .await?
         .deserialize_index_metadata()?;
     let index_uid: IndexUid = metadata.index_uid.clone();
+    // The delete pipeline downloads splits from the index storage only.
+    if metadata.index_config.tiering_policy.is_some() {
+        return Err(JanitorError::InvalidDeleteQuery(
+            "delete tasks are not supported on indexes with a tiering policy".to_string(),
+        ));
+    }
     let query_ast = query_ast_from_user_text(&delete_request.query, Some(Vec::new()))
         .parse_user_query(&[])
         .map_err(|err| JanitorError::InvalidDeleteQuery(err.to_string()))?;
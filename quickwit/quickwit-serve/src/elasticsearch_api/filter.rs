@@ -24,8 +24,9 @@ use warp::reject::LengthRequired;
 use warp::{Filter, Rejection};
 
 use super::model::{
-    FieldCapabilityQueryParams, FieldCapabilityRequestBody, MultiSearchQueryParams,
-    SearchQueryParamsCount,
+    ClosePointInTimeBody, FieldCapabilityQueryParams, FieldCapabilityRequestBody,
+    MultiSearchQueryParams, OpenPointInTimeQueryParams, SearchQueryParamsCount,
+    TermsEnumRequestBody,
 };
 use crate::elasticsearch_api::model::{
     ElasticBulkOptions, ScrollQueryParams, SearchBody, SearchQueryParams,
@@ -51,10 +52,11 @@ pub(crate) fn elastic_cluster_info_filter() -> impl Filter<Extract = (), Error =
 
 #[utoipa::path(get, tag = "Search", path = "/_search")]
 pub(crate) fn elasticsearch_filter(
-) -> impl Filter<Extract = (SearchQueryParams,), Error = Rejection> + Clone {
+) -> impl Filter<Extract = (SearchQueryParams, SearchBody), Error = Rejection> + Clone {
     warp::path!("_elastic" / "_search")
         .and(warp::get().or(warp::post()).unify())
         .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(json_or_empty())
 }
 
 #[utoipa::path(
@@ -160,6 +162,16 @@ pub(crate) fn elastic_field_capabilities_filter() -> impl Filter<
         .and(json_or_empty())
 }
 
+#[utoipa::path(get, tag = "Metadata", path = "/{index}/_terms_enum")]
+pub(crate) fn elastic_index_terms_enum_filter(
+) -> impl Filter<Extract = (Vec<String>, TermsEnumRequestBody), Error = Rejection> + Clone {
+    warp::path!("_elastic" / String / "_terms_enum")
+        .and_then(extract_index_id_patterns)
+        .and(warp::get().or(warp::post()).unify())
+        .and(warp::body::content_length_limit(BODY_LENGTH_LIMIT.as_u64()))
+        .and(warp::body::json())
+}
+
 #[utoipa::path(get, tag = "Count", path = "/{index}/_count")]
 pub(crate) fn elastic_index_count_filter(
 ) -> impl Filter<Extract = (Vec<String>, SearchQueryParamsCount, SearchBody), Error = Rejection> + Clone
@@ -230,3 +242,21 @@ pub(crate) fn elastic_scroll_filter(
             },
         )
 }
+
+#[utoipa::path(post, tag = "Search", path = "/{index}/_pit")]
+pub(crate) fn elastic_open_point_in_time_filter(
+) -> impl Filter<Extract = (Vec<String>, OpenPointInTimeQueryParams), Error = Rejection> + Clone {
+    warp::path!("_elastic" / String / "_pit")
+        .and_then(extract_index_id_patterns)
+        .and(warp::post())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+}
+
+#[utoipa::path(delete, tag = "Search", path = "/_pit")]
+pub(crate) fn elastic_close_point_in_time_filter(
+) -> impl Filter<Extract = (ClosePointInTimeBody,), Error = Rejection> + Clone {
+    warp::path!("_elastic" / "_pit")
+        .and(warp::delete())
+        .and(warp::body::content_length_limit(BODY_LENGTH_LIMIT.as_u64()))
+        .and(warp::body::json())
+}
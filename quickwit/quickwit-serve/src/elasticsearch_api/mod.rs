@@ -34,15 +34,16 @@ use quickwit_proto::ingest::router::IngestRouterServiceClient;
 use quickwit_proto::metastore::MetastoreServiceClient;
 use quickwit_search::SearchService;
 use rest_handler::{
-    es_compat_cluster_info_handler, es_compat_index_multi_search_handler,
-    es_compat_index_search_handler, es_compat_scroll_handler, es_compat_search_handler,
+    es_compat_close_point_in_time_handler, es_compat_cluster_info_handler,
+    es_compat_index_multi_search_handler, es_compat_index_search_handler,
+    es_compat_open_point_in_time_handler, es_compat_scroll_handler, es_compat_search_handler,
 };
 use serde::{Deserialize, Serialize};
 use warp::{Filter, Rejection};
 
 use self::rest_handler::{
     es_compat_index_count_handler, es_compat_index_field_capabilities_handler,
-    es_compat_index_stats_handler, es_compat_stats_handler,
+    es_compat_index_stats_handler, es_compat_index_terms_enum_handler, es_compat_stats_handler,
 };
 use crate::elasticsearch_api::model::ElasticsearchError;
 use crate::json_api_response::JsonApiResponse;
@@ -64,10 +65,15 @@ pub fn elastic_api_handlers(
         .or(es_compat_index_search_handler(search_service.clone()))
         .or(es_compat_index_count_handler(search_service.clone()))
         .or(es_compat_scroll_handler(search_service.clone()))
+        .or(es_compat_open_point_in_time_handler(search_service.clone()))
+        .or(es_compat_close_point_in_time_handler(
+            search_service.clone(),
+        ))
         .or(es_compat_index_multi_search_handler(search_service.clone()))
         .or(es_compat_index_field_capabilities_handler(
             search_service.clone(),
         ))
+        .or(es_compat_index_terms_enum_handler(search_service.clone()))
         .or(es_compat_bulk_handler(
             ingest_service.clone(),
             ingest_router.clone(),
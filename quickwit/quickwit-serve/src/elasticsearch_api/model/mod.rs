@@ -22,10 +22,14 @@ mod bulk_query_params;
 mod error;
 mod field_capability;
 mod multi_search;
+mod point_in_time;
 mod scroll;
 mod search_body;
+mod search_profile;
 mod search_query_params;
+mod search_response;
 mod stats;
+mod terms_enum;
 
 pub use bulk_body::BulkAction;
 pub use bulk_query_params::ElasticBulkOptions;
@@ -37,12 +41,23 @@ pub use field_capability::{
 pub use multi_search::{
     MultiSearchHeader, MultiSearchQueryParams, MultiSearchResponse, MultiSearchSingleResponse,
 };
+pub(crate) use point_in_time::parse_keep_alive_secs;
+pub use point_in_time::{
+    ClosePointInTimeBody, ClosePointInTimeResponse, OpenPointInTimeQueryParams,
+    OpenPointInTimeResponse, PointInTimeBody,
+};
 use quickwit_proto::search::{SortDatetimeFormat, SortOrder};
 pub use scroll::ScrollQueryParams;
-pub use search_body::SearchBody;
+pub use search_body::{CollapseBody, InnerHitsBody, SearchBody};
+pub use search_profile::ElasticsearchProfile;
 pub use search_query_params::{SearchQueryParams, SearchQueryParamsCount};
+pub use search_response::ElasticsearchSearchResponse;
 use serde::{Deserialize, Serialize};
 pub use stats::{ElasticsearchStatsResponse, StatsResponseEntry};
+pub use terms_enum::{
+    build_list_terms_request_for_es_api, convert_to_es_terms_enum_response, TermsEnumRequestBody,
+    TermsEnumResponse,
+};
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct SortField {
@@ -0,0 +1,90 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use quickwit_search::SearchError;
+use serde::{Deserialize, Serialize};
+
+/// Parses a point in time keep alive (`1m`, `30s`, etc.) into a number of seconds.
+pub(crate) fn parse_keep_alive_secs(keep_alive_str: &str) -> Result<u32, SearchError> {
+    let keep_alive: Duration = humantime::parse_duration(keep_alive_str).map_err(|_err| {
+        SearchError::InvalidArgument(format!("invalid keep alive duration: `{keep_alive_str}`"))
+    })?;
+    Ok(keep_alive.as_secs() as u32)
+}
+
+/// Query parameters of the `_pit` open endpoint.
+#[derive(Debug, Deserialize)]
+pub struct OpenPointInTimeQueryParams {
+    pub keep_alive: String,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct OpenPointInTimeResponse {
+    pub id: String,
+}
+
+/// Body of the `_pit` close endpoint.
+#[derive(Debug, Deserialize)]
+pub struct ClosePointInTimeBody {
+    pub id: String,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ClosePointInTimeResponse {
+    pub succeeded: bool,
+    pub num_freed: u32,
+}
+
+/// The `pit` section of a search request body.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PointInTimeBody {
+    pub id: String,
+    #[serde(default)]
+    pub keep_alive: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keep_alive_secs() {
+        assert_eq!(parse_keep_alive_secs("1m").unwrap(), 60);
+        assert_eq!(parse_keep_alive_secs("30s").unwrap(), 30);
+        assert!(parse_keep_alive_secs("forever").is_err());
+    }
+
+    #[test]
+    fn test_point_in_time_body_deserialization() {
+        let pit_body: PointInTimeBody =
+            serde_json::from_str(r#"{"id": "my-pit-id", "keep_alive": "1m"}"#).unwrap();
+        assert_eq!(
+            pit_body,
+            PointInTimeBody {
+                id: "my-pit-id".to_string(),
+                keep_alive: Some("1m".to_string()),
+            }
+        );
+        let pit_body: PointInTimeBody = serde_json::from_str(r#"{"id": "my-pit-id"}"#).unwrap();
+        assert!(pit_body.keep_alive.is_none());
+    }
+}
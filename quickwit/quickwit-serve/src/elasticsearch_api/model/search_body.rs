@@ -22,10 +22,11 @@ use std::fmt;
 
 use quickwit_proto::search::SortOrder;
 use quickwit_query::{ElasticQueryDsl, OneFieldMap};
+use quickwit_search::RuntimeMappings;
 use serde::de::{MapAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize};
 
-use super::ElasticDateFormat;
+use super::{ElasticDateFormat, PointInTimeBody};
 use crate::elasticsearch_api::model::{default_elasticsearch_sort_order, SortField};
 use crate::elasticsearch_api::TrackTotalHits;
 
@@ -86,6 +87,34 @@ pub struct SearchBody {
     pub stored_fields: Option<BTreeSet<String>>,
     #[serde(default)]
     pub search_after: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub profile: bool,
+    #[serde(default)]
+    pub pit: Option<PointInTimeBody>,
+    #[serde(default)]
+    pub collapse: Option<CollapseBody>,
+    #[serde(default)]
+    pub runtime_mappings: Option<RuntimeMappings>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CollapseBody {
+    pub field: String,
+    #[serde(default)]
+    pub inner_hits: Option<InnerHitsBody>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct InnerHitsBody {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_field_sorts")]
+    pub sort: Option<Vec<SortField>>,
 }
 
 struct FieldSortVecVisitor;
@@ -169,6 +198,32 @@ where D: Deserializer<'de> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_collapse() {
+        let json = r#"
+        {
+            "collapse": {
+                "field": "service",
+                "inner_hits": {
+                    "name": "latest",
+                    "size": 3,
+                    "sort": [{ "timestamp": "desc" }]
+                }
+            }
+        }
+        "#;
+        let search_body: SearchBody = serde_json::from_str(json).unwrap();
+        let collapse = search_body.collapse.unwrap();
+        assert_eq!(collapse.field, "service");
+        let inner_hits = collapse.inner_hits.unwrap();
+        assert_eq!(inner_hits.name.as_deref(), Some("latest"));
+        assert_eq!(inner_hits.size, Some(3));
+        let sort_fields = inner_hits.sort.unwrap();
+        assert_eq!(sort_fields.len(), 1);
+        assert_eq!(sort_fields[0].field, "timestamp");
+        assert_eq!(sort_fields[0].order, SortOrder::Desc);
+    }
+
     #[test]
     fn test_sort_field_array() {
         let json = r#"
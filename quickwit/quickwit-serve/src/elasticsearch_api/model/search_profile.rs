@@ -0,0 +1,237 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_proto::search::{SearchProfile, SplitSearchProfile};
+use serde::Serialize;
+
+/// Search profile shaped like the Elasticsearch `profile` output.
+///
+/// Each split is reported as a shard. Tantivy does not expose a per-query-node breakdown, so each
+/// shard reports a single query entry whose breakdown lists the leaf search phases of the split.
+/// Quickwit-specific root timings are reported under `root`.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ElasticsearchProfile {
+    shards: Vec<ShardProfile>,
+    root: RootProfile,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct ShardProfile {
+    id: String,
+    searches: Vec<SearchPhaseProfile>,
+    aggregations: Vec<serde_json::Value>,
+    leaf_cache_hit: bool,
+    num_bytes_fetched_from_storage: u64,
+    num_bytes_fetched_from_cache: u64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct SearchPhaseProfile {
+    query: Vec<QueryProfile>,
+    rewrite_time: u64,
+    collector: Vec<CollectorProfile>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct QueryProfile {
+    #[serde(rename = "type")]
+    query_type: String,
+    description: String,
+    time_in_nanos: u64,
+    breakdown: QueryBreakdown,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct QueryBreakdown {
+    open_index: u64,
+    warm_up: u64,
+    warm_up_terms: u64,
+    warm_up_term_ranges: u64,
+    warm_up_term_dicts: u64,
+    warm_up_fastfields: u64,
+    warm_up_fieldnorms: u64,
+    warm_up_postings: u64,
+    search: u64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct CollectorProfile {
+    name: &'static str,
+    reason: &'static str,
+    time_in_nanos: u64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct RootProfile {
+    leaf_search_phase_time_in_nanos: u64,
+    merge_time_in_nanos: u64,
+    fetch_docs_phase_time_in_nanos: u64,
+    finalize_aggregation_time_in_nanos: u64,
+}
+
+fn micros_to_nanos(micros: u64) -> u64 {
+    micros.saturating_mul(1_000)
+}
+
+/// Extracts the name of the top-level tantivy query from its debug representation.
+fn query_type(query_plan: &str) -> String {
+    query_plan
+        .split(|ch: char| !ch.is_alphanumeric() && ch != '_')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+impl From<SplitSearchProfile> for ShardProfile {
+    fn from(split_profile: SplitSearchProfile) -> Self {
+        let breakdown = QueryBreakdown {
+            open_index: micros_to_nanos(split_profile.open_index_micros),
+            warm_up: micros_to_nanos(split_profile.warm_up_micros),
+            warm_up_terms: micros_to_nanos(split_profile.warm_up_terms_micros),
+            warm_up_term_ranges: micros_to_nanos(split_profile.warm_up_term_ranges_micros),
+            warm_up_term_dicts: micros_to_nanos(split_profile.warm_up_term_dicts_micros),
+            warm_up_fastfields: micros_to_nanos(split_profile.warm_up_fastfields_micros),
+            warm_up_fieldnorms: micros_to_nanos(split_profile.warm_up_fieldnorms_micros),
+            warm_up_postings: micros_to_nanos(split_profile.warm_up_postings_micros),
+            search: micros_to_nanos(split_profile.search_micros),
+        };
+        let time_in_nanos = breakdown.open_index + breakdown.warm_up + breakdown.search;
+        let query_profile = QueryProfile {
+            query_type: query_type(&split_profile.query_plan),
+            description: split_profile.query_plan,
+            time_in_nanos,
+            breakdown,
+        };
+        let collector_profile = CollectorProfile {
+            name: "QuickwitCollector",
+            reason: "search_top_hits",
+            time_in_nanos: micros_to_nanos(split_profile.search_micros),
+        };
+        ShardProfile {
+            id: format!(
+                "[{}][{}][{}]",
+                split_profile.node_addr, split_profile.index_id, split_profile.split_id
+            ),
+            searches: vec![SearchPhaseProfile {
+                query: vec![query_profile],
+                rewrite_time: 0,
+                collector: vec![collector_profile],
+            }],
+            aggregations: Vec::new(),
+            leaf_cache_hit: split_profile.leaf_cache_hit,
+            num_bytes_fetched_from_storage: split_profile.num_bytes_fetched_from_storage,
+            num_bytes_fetched_from_cache: split_profile.num_bytes_fetched_from_cache,
+        }
+    }
+}
+
+impl From<SearchProfile> for ElasticsearchProfile {
+    fn from(search_profile: SearchProfile) -> Self {
+        let root = RootProfile {
+            leaf_search_phase_time_in_nanos: micros_to_nanos(
+                search_profile.leaf_search_phase_micros,
+            ),
+            merge_time_in_nanos: micros_to_nanos(search_profile.merge_micros),
+            fetch_docs_phase_time_in_nanos: micros_to_nanos(search_profile.fetch_docs_phase_micros),
+            finalize_aggregation_time_in_nanos: micros_to_nanos(
+                search_profile.finalize_aggregation_micros,
+            ),
+        };
+        let shards = search_profile
+            .split_profiles
+            .into_iter()
+            .map(ShardProfile::from)
+            .collect();
+        ElasticsearchProfile { shards, root }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_elasticsearch_profile_from_search_profile() {
+        let search_profile = SearchProfile {
+            leaf_search_phase_micros: 10,
+            merge_micros: 1,
+            fetch_docs_phase_micros: 2,
+            finalize_aggregation_micros: 0,
+            split_profiles: vec![SplitSearchProfile {
+                split_id: "split-1".to_string(),
+                index_id: "my-index".to_string(),
+                node_addr: "127.0.0.1:7281".to_string(),
+                query_plan: "TermQuery(Term(field=0, type=Str, \"hello\"))".to_string(),
+                open_index_micros: 3,
+                warm_up_micros: 4,
+                warm_up_terms_micros: 4,
+                search_micros: 2,
+                num_bytes_fetched_from_storage: 100,
+                num_bytes_fetched_from_cache: 20,
+                ..Default::default()
+            }],
+        };
+        let elasticsearch_profile = ElasticsearchProfile::from(search_profile);
+        let elasticsearch_profile_json = serde_json::to_value(elasticsearch_profile).unwrap();
+        assert_eq!(
+            elasticsearch_profile_json,
+            json!({
+                "shards": [{
+                    "id": "[127.0.0.1:7281][my-index][split-1]",
+                    "searches": [{
+                        "query": [{
+                            "type": "TermQuery",
+                            "description": "TermQuery(Term(field=0, type=Str, \"hello\"))",
+                            "time_in_nanos": 9000,
+                            "breakdown": {
+                                "open_index": 3000,
+                                "warm_up": 4000,
+                                "warm_up_terms": 4000,
+                                "warm_up_term_ranges": 0,
+                                "warm_up_term_dicts": 0,
+                                "warm_up_fastfields": 0,
+                                "warm_up_fieldnorms": 0,
+                                "warm_up_postings": 0,
+                                "search": 2000,
+                            },
+                        }],
+                        "rewrite_time": 0,
+                        "collector": [{
+                            "name": "QuickwitCollector",
+                            "reason": "search_top_hits",
+                            "time_in_nanos": 2000,
+                        }],
+                    }],
+                    "aggregations": [],
+                    "leaf_cache_hit": false,
+                    "num_bytes_fetched_from_storage": 100,
+                    "num_bytes_fetched_from_cache": 20,
+                }],
+                "root": {
+                    "leaf_search_phase_time_in_nanos": 10000,
+                    "merge_time_in_nanos": 1000,
+                    "fetch_docs_phase_time_in_nanos": 2000,
+                    "finalize_aggregation_time_in_nanos": 0,
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,36 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use elasticsearch_dsl::search::SearchResponse as ElasticsearchResponse;
+use serde::Serialize;
+
+use super::ElasticsearchProfile;
+
+/// Elasticsearch search response, extended with the sections that are not modeled by
+/// `elasticsearch_dsl`: `pit_id` when searching a point in time, and `profile` when profiling
+/// was requested.
+#[derive(Serialize)]
+pub struct ElasticsearchSearchResponse {
+    #[serde(flatten)]
+    pub response: ElasticsearchResponse,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pit_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<ElasticsearchProfile>,
+}
@@ -0,0 +1,191 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use hyper::StatusCode;
+use quickwit_proto::search::ListTermsRequest;
+use quickwit_search::ListTermsResponseRest;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use super::ElasticsearchError;
+
+/// Default number of terms returned by the `_terms_enum` API.
+const DEFAULT_TERMS_ENUM_SIZE: u64 = 10;
+
+/// Maximum number of terms returned by the `_terms_enum` API.
+const MAX_TERMS_ENUM_SIZE: u64 = 10_000;
+
+#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TermsEnumRequestBody {
+    pub field: String,
+    #[serde(default)]
+    pub string: Option<String>,
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    #[serde(default)]
+    pub search_after: Option<String>,
+    #[serde(default)]
+    // unsupported currently
+    pub index_filter: JsonValue,
+    #[serde(default)]
+    // unsupported currently
+    pub timeout: Option<JsonValue>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct TermsEnumShards {
+    total: u64,
+    successful: u64,
+    failed: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TermsEnumResponse {
+    _shards: TermsEnumShards,
+    terms: Vec<String>,
+    complete: bool,
+}
+
+pub fn build_list_terms_request_for_es_api(
+    index_id_patterns: Vec<String>,
+    search_body: TermsEnumRequestBody,
+) -> Result<ListTermsRequest, ElasticsearchError> {
+    if search_body.case_insensitive {
+        return Err(ElasticsearchError::new(
+            StatusCode::BAD_REQUEST,
+            "`case_insensitive` is not supported by the `_terms_enum` API".to_string(),
+        ));
+    }
+    let size = search_body.size.unwrap_or(DEFAULT_TERMS_ENUM_SIZE);
+    if size > MAX_TERMS_ENUM_SIZE {
+        return Err(ElasticsearchError::new(
+            StatusCode::BAD_REQUEST,
+            format!("max value for `size` is {MAX_TERMS_ENUM_SIZE}, but got {size}"),
+        ));
+    }
+    // `search_after` is exclusive whereas `start_key` is inclusive: appending a null byte
+    // gives us the smallest key greater than `search_after`.
+    let start_key = search_body.search_after.map(|search_after| {
+        let mut start_key = search_after.into_bytes();
+        start_key.push(0u8);
+        start_key
+    });
+    Ok(ListTermsRequest {
+        index_id_patterns,
+        field: search_body.field,
+        start_timestamp: None,
+        end_timestamp: None,
+        max_hits: Some(size),
+        start_key,
+        end_key: None,
+        prefix: search_body.string,
+        regex: None,
+    })
+}
+
+pub fn convert_to_es_terms_enum_response(
+    list_terms_response: ListTermsResponseRest,
+) -> TermsEnumResponse {
+    let terms = list_terms_response
+        .terms
+        .into_iter()
+        .map(|term_rest| match term_rest.term {
+            JsonValue::String(term) => term,
+            term => term.to_string(),
+        })
+        .collect();
+    TermsEnumResponse {
+        _shards: TermsEnumShards::default(),
+        terms,
+        complete: list_terms_response.errors.is_empty(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_search::TermRest;
+
+    use super::*;
+
+    #[test]
+    fn test_build_list_terms_request_for_es_api() {
+        let search_body: TermsEnumRequestBody = serde_json::from_str(
+            r#"{"field": "service", "string": "qui", "size": 5, "search_after": "quickwit"}"#,
+        )
+        .unwrap();
+        let list_terms_request =
+            build_list_terms_request_for_es_api(vec!["my-index".to_string()], search_body).unwrap();
+        assert_eq!(list_terms_request.index_id_patterns, vec!["my-index"]);
+        assert_eq!(list_terms_request.field, "service");
+        assert_eq!(list_terms_request.prefix.as_deref(), Some("qui"));
+        assert_eq!(list_terms_request.max_hits, Some(5));
+        assert_eq!(
+            list_terms_request.start_key.as_deref(),
+            Some(&b"quickwit\0"[..])
+        );
+    }
+
+    #[test]
+    fn test_build_list_terms_request_for_es_api_case_insensitive() {
+        let search_body: TermsEnumRequestBody =
+            serde_json::from_str(r#"{"field": "service", "case_insensitive": true}"#).unwrap();
+        let error = build_list_terms_request_for_es_api(vec!["my-index".to_string()], search_body)
+            .unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_build_list_terms_request_for_es_api_size_too_large() {
+        let search_body: TermsEnumRequestBody =
+            serde_json::from_str(r#"{"field": "service", "size": 18446744073709551615}"#).unwrap();
+        let error = build_list_terms_request_for_es_api(vec!["my-index".to_string()], search_body)
+            .unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_convert_to_es_terms_enum_response() {
+        let list_terms_response = ListTermsResponseRest {
+            num_hits: 2,
+            terms: vec![
+                TermRest {
+                    term: JsonValue::from("quickwit"),
+                    doc_count: 2,
+                },
+                TermRest {
+                    term: JsonValue::from(42),
+                    doc_count: 1,
+                },
+            ],
+            elapsed_time_micros: 10,
+            errors: Vec::new(),
+        };
+        let terms_enum_response = convert_to_es_terms_enum_response(list_terms_response);
+        assert_eq!(
+            serde_json::to_value(terms_enum_response).unwrap(),
+            serde_json::json!({
+                "_shards": {"total": 0, "successful": 0, "failed": 0},
+                "terms": ["quickwit", "42"],
+                "complete": true,
+            })
+        );
+    }
+}
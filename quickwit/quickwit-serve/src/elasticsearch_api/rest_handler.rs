@@ -33,30 +33,38 @@ use quickwit_config::{validate_index_id_pattern, NodeConfig};
 use quickwit_metastore::*;
 use quickwit_proto::metastore::MetastoreServiceClient;
 use quickwit_proto::search::{
-    CountHits, ListFieldsResponse, PartialHit, ScrollRequest, SearchResponse, SortByValue,
-    SortDatetimeFormat,
+    CollapseRequest, CountHits, InnerHitsRequest, ListFieldsResponse, PartialHit, PointInTime,
+    ScrollRequest, SearchResponse, SortByValue, SortDatetimeFormat,
 };
 use quickwit_proto::types::IndexUid;
 use quickwit_proto::ServiceErrorCode;
 use quickwit_query::query_ast::{QueryAst, UserInputQuery};
 use quickwit_query::BooleanOperand;
-use quickwit_search::{list_all_splits, resolve_index_patterns, SearchError, SearchService};
+use quickwit_search::{
+    list_all_splits, resolve_index_patterns, ListTermsResponseRest, SearchError, SearchService,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use warp::{Filter, Rejection};
 
 use super::filter::{
-    elastic_cluster_info_filter, elastic_field_capabilities_filter, elastic_index_count_filter,
+    elastic_close_point_in_time_filter, elastic_cluster_info_filter,
+    elastic_field_capabilities_filter, elastic_index_count_filter,
     elastic_index_field_capabilities_filter, elastic_index_search_filter,
-    elastic_index_stats_filter, elastic_multi_search_filter, elastic_scroll_filter,
-    elastic_stats_filter, elasticsearch_filter,
+    elastic_index_stats_filter, elastic_index_terms_enum_filter, elastic_multi_search_filter,
+    elastic_open_point_in_time_filter, elastic_scroll_filter, elastic_stats_filter,
+    elasticsearch_filter,
 };
 use super::model::{
-    build_list_field_request_for_es_api, convert_to_es_field_capabilities_response,
-    ElasticsearchError, ElasticsearchStatsResponse, FieldCapabilityQueryParams,
-    FieldCapabilityRequestBody, FieldCapabilityResponse, MultiSearchHeader, MultiSearchQueryParams,
-    MultiSearchResponse, MultiSearchSingleResponse, ScrollQueryParams, SearchBody,
-    SearchQueryParams, SearchQueryParamsCount, StatsResponseEntry,
+    build_list_field_request_for_es_api, build_list_terms_request_for_es_api,
+    convert_to_es_field_capabilities_response, convert_to_es_terms_enum_response,
+    parse_keep_alive_secs, ClosePointInTimeBody, ClosePointInTimeResponse, CollapseBody,
+    ElasticsearchError, ElasticsearchProfile, ElasticsearchSearchResponse,
+    ElasticsearchStatsResponse, FieldCapabilityQueryParams, FieldCapabilityRequestBody,
+    FieldCapabilityResponse, MultiSearchHeader, MultiSearchQueryParams, MultiSearchResponse,
+    MultiSearchSingleResponse, OpenPointInTimeQueryParams, OpenPointInTimeResponse,
+    ScrollQueryParams, SearchBody, SearchQueryParams, SearchQueryParamsCount, SortField,
+    StatsResponseEntry, TermsEnumRequestBody, TermsEnumResponse,
 };
 use super::{make_elastic_api_response, TrackTotalHits};
 use crate::format::BodyFormat;
@@ -89,18 +97,49 @@ pub fn es_compat_cluster_info_handler(
 
 /// GET or POST _elastic/_search
 pub fn es_compat_search_handler(
-    _search_service: Arc<dyn SearchService>,
+    search_service: Arc<dyn SearchService>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    elasticsearch_filter().then(|_params: SearchQueryParams| async move {
-        // TODO
-        let api_error = ApiError {
-            service_code: ServiceErrorCode::NotSupportedYet,
-            message: "_elastic/_search is not supported yet. Please try the index search endpoint \
-                      (_elastic/{index}/search)"
-                .to_string(),
-        };
-        make_json_api_response::<(), _>(Err(api_error), BodyFormat::default())
-    })
+    elasticsearch_filter().and(with_arg(search_service)).then(
+        |search_params: SearchQueryParams,
+         search_body: SearchBody,
+         search_service: Arc<dyn SearchService>| async move {
+            if search_body.pit.is_none() {
+                // TODO
+                let api_error = ApiError {
+                    service_code: ServiceErrorCode::NotSupportedYet,
+                    message: "_elastic/_search is only supported with a point in time (`pit`) for \
+                              now. Please try the index search endpoint (_elastic/{index}/search)"
+                        .to_string(),
+                };
+                return make_json_api_response::<(), _>(Err(api_error), BodyFormat::default());
+            }
+            // The indexes searched are those the point in time was opened on.
+            let search_result =
+                es_compat_index_search(Vec::new(), search_params, search_body, search_service)
+                    .await;
+            make_elastic_api_response(search_result, BodyFormat::default())
+        },
+    )
+}
+
+/// POST _elastic/{index}/_pit
+pub fn es_compat_open_point_in_time_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_open_point_in_time_filter()
+        .and(with_arg(search_service))
+        .then(es_compat_open_point_in_time)
+        .map(|result| make_elastic_api_response(result, BodyFormat::default()))
+}
+
+/// DELETE _elastic/_pit
+pub fn es_compat_close_point_in_time_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_close_point_in_time_filter()
+        .and(with_arg(search_service))
+        .then(es_compat_close_point_in_time)
+        .map(|result| make_elastic_api_response(result, BodyFormat::default()))
 }
 
 /// GET or POST _elastic/{index}/_field_caps
@@ -115,6 +154,16 @@ pub fn es_compat_index_field_capabilities_handler(
         .map(|result| make_elastic_api_response(result, BodyFormat::default()))
 }
 
+/// GET or POST _elastic/{index}/_terms_enum
+pub fn es_compat_index_terms_enum_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_index_terms_enum_filter()
+        .and(with_arg(search_service))
+        .then(es_compat_index_terms_enum)
+        .map(|result| make_elastic_api_response(result, BodyFormat::default()))
+}
+
 /// GET or POST _elastic/_stats
 pub fn es_compat_stats_handler(
     search_service: MetastoreServiceClient,
@@ -223,14 +272,7 @@ fn build_request_for_es_api(
         .or_else(|| search_body.sort.clone())
         .unwrap_or_default()
         .iter()
-        .map(|sort_field| quickwit_proto::search::SortField {
-            field_name: sort_field.field.to_string(),
-            sort_order: sort_field.order as i32,
-            sort_datetime_format: sort_field
-                .date_format
-                .clone()
-                .map(|date_format| SortDatetimeFormat::from(date_format) as i32),
-        })
+        .map(to_proto_sort_field)
         .take_while_inclusive(|sort_field| !is_doc_field(sort_field))
         .collect();
     if sort_fields.len() >= 3 {
@@ -245,6 +287,25 @@ fn build_request_for_es_api(
     let has_doc_id_field = sort_fields.iter().any(is_doc_field);
     let search_after = partial_hit_from_search_after_param(search_body.search_after, &sort_fields)?;
 
+    let point_in_time = search_body
+        .pit
+        .map(|pit| {
+            let keep_alive_secs = pit
+                .keep_alive
+                .as_deref()
+                .map(parse_keep_alive_secs)
+                .transpose()?;
+            Ok::<_, SearchError>(PointInTime {
+                pit_id: pit.id,
+                keep_alive_secs,
+            })
+        })
+        .transpose()?;
+    let collapse = search_body.collapse.map(build_collapse_request);
+    let runtime_mappings = search_body.runtime_mappings.map(|runtime_mappings| {
+        serde_json::to_string(&runtime_mappings).expect("Failed to serialize runtime mappings")
+    });
+
     Ok((
         quickwit_proto::search::SearchRequest {
             index_id_patterns,
@@ -259,11 +320,49 @@ fn build_request_for_es_api(
             scroll_ttl_secs,
             search_after,
             count_hits,
+            profile: search_body.profile,
+            point_in_time,
+            collapse,
+            runtime_mappings,
         },
         has_doc_id_field,
     ))
 }
 
+fn to_proto_sort_field(sort_field: &SortField) -> quickwit_proto::search::SortField {
+    quickwit_proto::search::SortField {
+        field_name: sort_field.field.to_string(),
+        sort_order: sort_field.order as i32,
+        sort_datetime_format: sort_field
+            .date_format
+            .clone()
+            .map(|date_format| SortDatetimeFormat::from(date_format) as i32),
+    }
+}
+
+fn build_collapse_request(collapse_body: CollapseBody) -> CollapseRequest {
+    let inner_hits = collapse_body
+        .inner_hits
+        .map(|inner_hits_body| InnerHitsRequest {
+            // Elasticsearch defaults the name of the inner hits to the name of the collapse
+            // field.
+            name: inner_hits_body
+                .name
+                .unwrap_or_else(|| collapse_body.field.clone()),
+            size: inner_hits_body.size.unwrap_or(3),
+            sort_fields: inner_hits_body
+                .sort
+                .unwrap_or_default()
+                .iter()
+                .map(to_proto_sort_field)
+                .collect(),
+        });
+    CollapseRequest {
+        field: collapse_body.field,
+        inner_hits,
+    }
+}
+
 fn is_doc_field(field: &quickwit_proto::search::SortField) -> bool {
     field.field_name == "_shard_doc" || field.field_name == "_doc"
 }
@@ -348,16 +447,55 @@ async fn es_compat_index_search(
     search_params: SearchQueryParams,
     search_body: SearchBody,
     search_service: Arc<dyn SearchService>,
-) -> Result<ElasticsearchResponse, ElasticsearchError> {
+) -> Result<ElasticsearchSearchResponse, ElasticsearchError> {
     let start_instant = Instant::now();
     let (search_request, append_shard_doc) =
         build_request_for_es_api(index_id_patterns, search_params, search_body)?;
-    let search_response: SearchResponse = search_service.root_search(search_request).await?;
+    let mut search_response: SearchResponse = search_service.root_search(search_request).await?;
     let elapsed = start_instant.elapsed();
+    let profile_opt = search_response
+        .profile
+        .take()
+        .map(ElasticsearchProfile::from);
+    let pit_id_opt = search_response.pit_id.take();
     let mut search_response_rest: ElasticsearchResponse =
         convert_to_es_search_response(search_response, append_shard_doc);
     search_response_rest.took = elapsed.as_millis() as u32;
-    Ok(search_response_rest)
+    Ok(ElasticsearchSearchResponse {
+        response: search_response_rest,
+        pit_id: pit_id_opt,
+        profile: profile_opt,
+    })
+}
+
+async fn es_compat_open_point_in_time(
+    index_id_patterns: Vec<String>,
+    open_params: OpenPointInTimeQueryParams,
+    search_service: Arc<dyn SearchService>,
+) -> Result<OpenPointInTimeResponse, ElasticsearchError> {
+    let keep_alive_secs = parse_keep_alive_secs(&open_params.keep_alive)?;
+    let open_request = quickwit_proto::search::OpenPointInTimeRequest {
+        index_id_patterns,
+        keep_alive_secs,
+    };
+    let open_response = search_service.open_point_in_time(open_request).await?;
+    Ok(OpenPointInTimeResponse {
+        id: open_response.pit_id,
+    })
+}
+
+async fn es_compat_close_point_in_time(
+    close_body: ClosePointInTimeBody,
+    search_service: Arc<dyn SearchService>,
+) -> Result<ClosePointInTimeResponse, ElasticsearchError> {
+    let close_request = quickwit_proto::search::ClosePointInTimeRequest {
+        pit_id: close_body.id,
+    };
+    let close_response = search_service.close_point_in_time(close_request).await?;
+    Ok(ClosePointInTimeResponse {
+        succeeded: true,
+        num_freed: u32::from(close_response.succeeded),
+    })
 }
 
 async fn es_compat_stats(
@@ -405,9 +543,22 @@ async fn es_compat_index_field_capabilities(
     Ok(search_response_rest)
 }
 
+async fn es_compat_index_terms_enum(
+    index_id_patterns: Vec<String>,
+    search_body: TermsEnumRequestBody,
+    search_service: Arc<dyn SearchService>,
+) -> Result<TermsEnumResponse, ElasticsearchError> {
+    let list_terms_request = build_list_terms_request_for_es_api(index_id_patterns, search_body)?;
+    let list_terms_response = search_service.root_list_terms(list_terms_request).await?;
+    let list_terms_response_rest = ListTermsResponseRest::try_from(list_terms_response)?;
+    let terms_enum_response = convert_to_es_terms_enum_response(list_terms_response_rest);
+    Ok(terms_enum_response)
+}
+
 fn convert_hit(hit: quickwit_proto::search::Hit, append_shard_doc: bool) -> ElasticHit {
     let fields: BTreeMap<String, serde_json::Value> =
         serde_json::from_str(&hit.json).unwrap_or_default();
+    let inner_hits = convert_inner_hits(hit.inner_hits, append_shard_doc);
     let mut sort = Vec::new();
     if let Some(partial_hit) = hit.partial_hit {
         if let Some(sort_value) = partial_hit.sort_value {
@@ -433,12 +584,42 @@ fn convert_hit(hit: quickwit_proto::search::Hit, append_shard_doc: bool) -> Elas
         source: Source::from_string(hit.json)
             .unwrap_or_else(|_| Source::from_string("{}".to_string()).unwrap()),
         highlight: Default::default(),
-        inner_hits: Default::default(),
+        inner_hits,
         matched_queries: Vec::default(),
         sort,
     }
 }
 
+fn convert_inner_hits<T: serde::de::DeserializeOwned + Default>(
+    inner_hits: Vec<quickwit_proto::search::InnerHits>,
+    append_shard_doc: bool,
+) -> T {
+    if inner_hits.is_empty() {
+        return T::default();
+    }
+    let inner_hits_json: serde_json::Map<String, serde_json::Value> = inner_hits
+        .into_iter()
+        .map(|inner_hits| {
+            let hits: Vec<ElasticHit> = inner_hits
+                .hits
+                .into_iter()
+                .map(|hit| convert_hit(hit, append_shard_doc))
+                .collect();
+            let inner_hits_json = json!({
+                "hits": {
+                    "total": {
+                        "value": inner_hits.num_hits,
+                        "relation": "eq",
+                    },
+                    "hits": hits,
+                }
+            });
+            (inner_hits.name, inner_hits_json)
+        })
+        .collect();
+    serde_json::from_value(serde_json::Value::Object(inner_hits_json)).unwrap_or_default()
+}
+
 async fn es_compat_index_multi_search(
     payload: Bytes,
     multi_search_params: MultiSearchQueryParams,
@@ -23,11 +23,15 @@ use bytes::Bytes;
 use hyper::header::CONTENT_TYPE;
 use quickwit_common::uri::Uri;
 use quickwit_config::{
-    load_source_config_from_user_config, ConfigFormat, NodeConfig, SourceConfig, SourceParams,
+    build_doc_mapper, load_source_config_from_user_config, ConfigFormat, DocMapping, NodeConfig,
+    SearchSettings, SourceConfig, SourceInputFormat, SourceParams, TransformConfig,
     CLI_INGEST_SOURCE_ID, INGEST_API_SOURCE_ID,
 };
 use quickwit_doc_mapper::{analyze_text, TokenizerConfig};
-use quickwit_index_management::{IndexService, IndexServiceError};
+use quickwit_index_management::{
+    IndexRestoreOptions, IndexService, IndexServiceError, IndexSnapshotSummary, RestoreSplitsMode,
+};
+use quickwit_indexing::actors::{DocSimulator, SimulatedDoc};
 use quickwit_metastore::{
     IndexMetadata, IndexMetadataResponseExt, ListIndexesMetadataResponseExt, ListSplitsQuery,
     ListSplitsRequestExt, MetastoreServiceStreamSplitsExt, Split, SplitInfo, SplitState,
@@ -55,6 +59,8 @@ use crate::with_arg;
         create_index,
         clear_index,
         delete_index,
+        snapshot_index,
+        restore_index,
         get_indexes_metadatas,
         list_splits,
         describe_index,
@@ -63,8 +69,20 @@ use crate::with_arg;
         reset_source_checkpoint,
         toggle_source,
         delete_source,
+        simulate_index,
+        simulate,
     ),
-    components(schemas(ToggleSource, SplitsForDeletion, IndexStats))
+    components(schemas(
+        ToggleSource,
+        SplitsForDeletion,
+        IndexStats,
+        SnapshotIndexRequest,
+        IndexSnapshotSummary,
+        IndexRestoreOptions,
+        RestoreSplitsMode,
+        SimulateIndexRequest,
+        SimulateRequest,
+    ))
 )]
 pub struct IndexApi;
 
@@ -75,9 +93,14 @@ pub fn index_management_handlers(
     // Indexes handlers.
     get_index_metadata_handler(index_service.metastore())
         .or(get_indexes_metadatas_handler(index_service.metastore()))
-        .or(create_index_handler(index_service.clone(), node_config))
+        .or(create_index_handler(
+            index_service.clone(),
+            node_config.clone(),
+        ))
         .or(clear_index_handler(index_service.clone()))
         .or(delete_index_handler(index_service.clone()))
+        .or(snapshot_index_handler(index_service.clone()))
+        .or(restore_index_handler(index_service.clone(), node_config))
         // Splits handlers
         .or(list_splits_handler(index_service.metastore()))
         .or(describe_index_handler(index_service.metastore()))
@@ -90,6 +113,9 @@ pub fn index_management_handlers(
         .or(delete_source_handler(index_service.metastore()))
         // Tokenizer handlers.
         .or(analyze_request_handler())
+        // Doc mapping simulation handlers.
+        .or(simulate_index_handler(index_service.metastore()))
+        .or(simulate_handler())
 }
 
 fn json_body<T: DeserializeOwned + Send>(
@@ -580,6 +606,85 @@ async fn delete_index(
         .await
 }
 
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+struct SnapshotIndexRequest {
+    #[schema(value_type = String)]
+    snapshot_uri: Uri,
+}
+
+fn snapshot_index_handler(
+    index_service: IndexService,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "snapshot")
+        .and(warp::post())
+        .and(json_body())
+        .and(with_arg(index_service))
+        .then(snapshot_index)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/snapshot",
+    request_body = SnapshotIndexRequest,
+    responses(
+        (status = 200, description = "Successfully snapshotted index.", body = IndexSnapshotSummary)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to snapshot."),
+    )
+)]
+/// Writes a snapshot of the index (metadata, sources, checkpoints, and published splits) to the
+/// snapshot URI.
+async fn snapshot_index(
+    index_id: String,
+    snapshot_index_request: SnapshotIndexRequest,
+    mut index_service: IndexService,
+) -> Result<IndexSnapshotSummary, IndexServiceError> {
+    let snapshot_uri = snapshot_index_request.snapshot_uri;
+    info!(index_id = %index_id, snapshot_uri = %snapshot_uri, "snapshot-index");
+    index_service.snapshot_index(&index_id, &snapshot_uri).await
+}
+
+fn restore_index_handler(
+    index_service: IndexService,
+    node_config: Arc<NodeConfig>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / "restore")
+        .and(warp::post())
+        .and(json_body())
+        .and(with_arg(index_service))
+        .and(with_arg(node_config))
+        .then(restore_index)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/indexes/restore",
+    request_body = IndexRestoreOptions,
+    responses(
+        // We return `VersionedIndexMetadata` as it's the serialized model view.
+        (status = 200, description = "Successfully restored index.", body = VersionedIndexMetadata)
+    ),
+)]
+/// Restores an index from a snapshot, optionally under a new index ID.
+async fn restore_index(
+    restore_options: IndexRestoreOptions,
+    mut index_service: IndexService,
+    node_config: Arc<NodeConfig>,
+) -> Result<IndexMetadata, IndexServiceError> {
+    info!(snapshot_uri = %restore_options.snapshot_uri, index_id = ?restore_options.index_id, "restore-index");
+    index_service
+        .restore_index(restore_options, &node_config.default_index_root_uri)
+        .await
+}
+
 fn create_source_handler(
     index_service: IndexService,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
@@ -865,6 +970,164 @@ async fn analyze_request(request: AnalyzeRequest) -> Result<serde_json::Value, I
     Ok(json_value)
 }
 
+/// Maximum number of sample documents of a simulate request.
+const MAX_NUM_SIMULATED_DOCS: usize = 1_000;
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+struct SimulateIndexRequest {
+    /// The source whose transform and input format are applied to the documents. By default,
+    /// the documents are processed as JSON documents, without transform.
+    #[serde(default)]
+    pub source_id: Option<String>,
+    /// The sample documents, at most 1000.
+    #[schema(value_type = Vec<Object>)]
+    pub docs: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+struct SimulateRequest {
+    /// The doc mapping to validate the documents against.
+    pub doc_mapping: DocMapping,
+    /// The VRL transform applied to the documents before the doc mapping.
+    #[serde(default)]
+    #[serde(rename = "transform")]
+    pub transform_config: Option<TransformConfig>,
+    /// The input format of the documents.
+    #[serde(default)]
+    pub input_format: SourceInputFormat,
+    /// The sample documents, at most 1000.
+    #[schema(value_type = Vec<Object>)]
+    pub docs: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct SimulateResponse {
+    docs: Vec<SimulatedDoc>,
+}
+
+fn validate_num_simulated_docs(docs: &[serde_json::Value]) -> Result<(), IndexServiceError> {
+    if docs.len() > MAX_NUM_SIMULATED_DOCS {
+        return Err(IndexServiceError::InvalidConfig(anyhow::anyhow!(
+            "too many documents to simulate: {} (max {MAX_NUM_SIMULATED_DOCS})",
+            docs.len()
+        )));
+    }
+    Ok(())
+}
+
+fn simulate_docs(
+    doc_mapping: &DocMapping,
+    search_settings: &SearchSettings,
+    transform_config_opt: Option<TransformConfig>,
+    input_format: SourceInputFormat,
+    docs: &[serde_json::Value],
+) -> Result<SimulateResponse, IndexServiceError> {
+    let doc_mapper =
+        build_doc_mapper(doc_mapping, search_settings).map_err(IndexServiceError::InvalidConfig)?;
+    let mut doc_simulator = DocSimulator::try_new(doc_mapper, transform_config_opt, input_format)
+        .map_err(IndexServiceError::InvalidConfig)?;
+    let simulated_docs = docs
+        .iter()
+        .map(|doc| doc_simulator.simulate_doc(doc))
+        .collect();
+    Ok(SimulateResponse {
+        docs: simulated_docs,
+    })
+}
+
+fn simulate_index_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "_simulate")
+        .and(warp::post())
+        .and(json_body())
+        .and(with_arg(metastore))
+        .then(simulate_index)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+/// Runs sample documents through the doc mapping of an index and, optionally, the transform of
+/// one of its sources, without indexing them.
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/_simulate",
+    request_body = SimulateIndexRequest,
+    responses(
+        (status = 200, description = "Successfully simulated the processing of the documents.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to simulate the documents against."),
+    )
+)]
+async fn simulate_index(
+    index_id: String,
+    request: SimulateIndexRequest,
+    mut metastore: MetastoreServiceClient,
+) -> Result<SimulateResponse, IndexServiceError> {
+    validate_num_simulated_docs(&request.docs)?;
+
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
+    let mut index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+
+    let (transform_config_opt, input_format) = if let Some(source_id) = request.source_id {
+        let source_config = index_metadata.sources.remove(&source_id).ok_or({
+            MetastoreError::NotFound(EntityKind::Source {
+                index_id,
+                source_id,
+            })
+        })?;
+        (source_config.transform_config, source_config.input_format)
+    } else {
+        (None, SourceInputFormat::default())
+    };
+    simulate_docs(
+        &index_metadata.index_config.doc_mapping,
+        &index_metadata.index_config.search_settings,
+        transform_config_opt,
+        input_format,
+        &request.docs,
+    )
+}
+
+fn simulate_handler() -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("_simulate")
+        .and(warp::post())
+        .and(json_body())
+        .then(simulate)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+/// Runs sample documents through an inline doc mapping and, optionally, a VRL transform, without
+/// indexing them.
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/_simulate",
+    request_body = SimulateRequest,
+    responses(
+        (status = 200, description = "Successfully simulated the processing of the documents.")
+    ),
+)]
+async fn simulate(request: SimulateRequest) -> Result<SimulateResponse, IndexServiceError> {
+    validate_num_simulated_docs(&request.docs)?;
+
+    simulate_docs(
+        &request.doc_mapping,
+        &SearchSettings::default(),
+        request.transform_config,
+        request.input_format,
+        &request.docs,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use std::ops::{Bound, RangeInclusive};
@@ -1382,6 +1645,68 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_snapshot_and_restore_index() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::for_test());
+        let mut node_config = NodeConfig::for_test();
+        node_config.default_index_root_uri = Uri::for_test("ram:///indexes");
+        let index_management_handler =
+            super::index_management_handlers(index_service, Arc::new(node_config))
+                .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"version": "0.7", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true, "indexed": true}]}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/snapshot")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"snapshot_uri": "ram:///snapshots/hdfs-logs"}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_response_json = serde_json::json!({
+            "index_id": "hdfs-logs",
+            "snapshot_uri": "ram:///snapshots/hdfs-logs",
+            "num_splits": 0,
+        });
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+
+        let resp = warp::test::request()
+            .path("/indexes/restore")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"snapshot_uri": "ram:///snapshots/hdfs-logs", "index_id": "hdfs-logs-restored"}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_response_json = serde_json::json!({
+            "index_id": "hdfs-logs-restored",
+            "index_uri": "ram:///indexes/hdfs-logs-restored",
+        });
+        assert_json_include!(
+            actual: resp_json.get("index_config").unwrap(),
+            expected: expected_response_json
+        );
+
+        let resp = warp::test::request()
+            .path("/indexes/restore")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"snapshot_uri": "ram:///snapshots/does-not-exist"}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+
     #[tokio::test]
     async fn test_create_delete_index_and_source() {
         let mut metastore = metastore_for_test();
@@ -1913,4 +2238,105 @@ mod tests {
             expected: expected_response_json
         );
     }
+
+    #[tokio::test]
+    async fn test_simulate_request() {
+        let index_service = IndexService::new(
+            MetastoreServiceClient::from(MetastoreServiceClient::mock()),
+            StorageResolver::unconfigured(),
+        );
+        let index_management_handler =
+            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
+                .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/_simulate")
+            .method("POST")
+            .json(&serde_json::json!({
+                "doc_mapping": {
+                    "field_mappings": [
+                        {"name": "severity", "type": "u64"},
+                        {"name": "body", "type": "text"}
+                    ],
+                    "mode": "dynamic"
+                },
+                "docs": [
+                    {"severity": 1, "body": "hello", "service": "api"},
+                    {"severity": "high"}
+                ]
+            }))
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let actual_response_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let simulated_docs = actual_response_json["docs"].as_array().unwrap();
+        assert_eq!(simulated_docs.len(), 2);
+        assert_json_include!(
+            actual: &simulated_docs[0],
+            expected: serde_json::json!({
+                "doc": {"severity": [1], "body": ["hello"]},
+                "dynamic_fields": ["service"]
+            })
+        );
+        assert!(simulated_docs[0].get("error").is_none());
+        assert!(simulated_docs[1]["error"].is_string());
+
+        let resp = warp::test::request()
+            .path("/_simulate")
+            .method("POST")
+            .json(&serde_json::json!({
+                "doc_mapping": {"timestamp_field": "missing"},
+                "docs": []
+            }))
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let docs = vec![serde_json::json!({"body": "hello"}); MAX_NUM_SIMULATED_DOCS + 1];
+        let resp = warp::test::request()
+            .path("/_simulate")
+            .method("POST")
+            .json(&serde_json::json!({
+                "doc_mapping": {"mode": "dynamic"},
+                "docs": docs
+            }))
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let error_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert!(error_json["message"]
+            .as_str()
+            .unwrap()
+            .contains("too many documents to simulate"));
+    }
+
+    #[tokio::test]
+    async fn test_simulate_index_request_with_unknown_source() {
+        let mut metastore = MetastoreServiceClient::mock();
+        metastore.expect_index_metadata().return_once(|_| {
+            Ok(
+                IndexMetadataResponse::try_from_index_metadata(IndexMetadata::for_test(
+                    "test-index",
+                    "ram:///indexes/test-index",
+                ))
+                .unwrap(),
+            )
+        });
+        let index_service = IndexService::new(
+            MetastoreServiceClient::from(metastore),
+            StorageResolver::unconfigured(),
+        );
+        let index_management_handler =
+            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
+                .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/test-index/_simulate")
+            .method("POST")
+            .json(&serde_json::json!({
+                "source_id": "unknown-source",
+                "docs": [{"body": "hello"}]
+            }))
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
 }
@@ -376,6 +376,7 @@ mod tests {
                     terms: Vec::new(),
                     elapsed_time_micros: 0,
                     errors: Vec::new(),
+                    doc_freqs: Vec::new(),
                 })
             });
         let mock_search_service = Arc::new(mock_search_service);
@@ -413,6 +414,7 @@ mod tests {
                     terms: Vec::new(),
                     elapsed_time_micros: 0,
                     errors: Vec::new(),
+                    doc_freqs: Vec::new(),
                 })
             });
         let mock_search_service = Arc::new(mock_search_service);
@@ -460,6 +462,9 @@ mod tests {
                     errors: vec![],
                     aggregation: None,
                     scroll_id: None,
+                    profile: None,
+                    pit_id: None,
+                    storage_request_stats: None,
                 })
             });
         let mock_search_service = Arc::new(mock_search_service);
@@ -491,6 +496,9 @@ mod tests {
                     errors: vec![],
                     aggregation: None,
                     scroll_id: None,
+                    profile: None,
+                    pit_id: None,
+                    storage_request_stats: None,
                 })
             });
         let mock_search_service = Arc::new(mock_search_service);
@@ -30,6 +30,7 @@ mod indexing_api;
 mod ingest_api;
 mod jaeger_api;
 mod json_api_response;
+mod metastore_cache;
 mod metrics;
 mod metrics_api;
 mod node_info_handler;
@@ -83,7 +84,7 @@ use quickwit_ingest::{
 use quickwit_jaeger::JaegerService;
 use quickwit_janitor::{start_janitor_service, JanitorService};
 use quickwit_metastore::{
-    ControlPlaneMetastore, ListIndexesMetadataResponseExt, MetastoreResolver,
+    CachingMetastore, ControlPlaneMetastore, ListIndexesMetadataResponseExt, MetastoreResolver,
 };
 use quickwit_opentelemetry::otlp::{OtlpGrpcLogsService, OtlpGrpcTracesService};
 use quickwit_proto::control_plane::ControlPlaneServiceClient;
@@ -109,6 +110,7 @@ use warp::{Filter, Rejection};
 
 pub use crate::build_info::{BuildInfo, RuntimeInfo};
 pub use crate::index_api::{ListSplitsQueryParams, ListSplitsResponse};
+use crate::metastore_cache::{setup_published_splits_broadcaster, setup_published_splits_listener};
 pub use crate::metrics::SERVE_METRICS;
 use crate::rate_modulator::RateModulator;
 #[cfg(test)]
@@ -121,6 +123,8 @@ const READINESS_REPORTING_INTERVAL: Duration = if cfg!(any(test, feature = "test
     Duration::from_secs(10)
 };
 
+const WARM_SPLITS_ADVERTISING_INTERVAL: Duration = Duration::from_secs(30);
+
 const METASTORE_CLIENT_MAX_CONCURRENCY_ENV_KEY: &str = "QW_METASTORE_CLIENT_MAX_CONCURRENCY";
 const DEFAULT_METASTORE_CLIENT_MAX_CONCURRENCY: usize = 6;
 
@@ -165,6 +169,8 @@ struct QuickwitServices {
     /// notifications. Otherwise, the subscriptions are dropped.
     _local_shards_update_listener_handle_opt: Option<ListenerHandle>,
     _report_splits_subscription_handle_opt: Option<EventSubscriptionHandle>,
+    _published_splits_subscription_handles_opt: Option<Vec<EventSubscriptionHandle>>,
+    _published_splits_listener_handle_opt: Option<ListenerHandle>,
 }
 
 fn has_node_with_metastore_service(members: &[ClusterMember]) -> bool {
@@ -313,7 +319,9 @@ pub async fn serve_quickwit(
                 .stack_delete_index_layer(broker_layer.clone())
                 .stack_add_source_layer(broker_layer.clone())
                 .stack_delete_source_layer(broker_layer.clone())
-                .stack_toggle_source_layer(broker_layer)
+                .stack_toggle_source_layer(broker_layer.clone())
+                .stack_publish_splits_layer(broker_layer.clone())
+                .stack_mark_splits_for_deletion_layer(broker_layer)
                 .build(metastore);
             Some(metastore)
         } else {
@@ -473,18 +481,56 @@ pub async fn serve_quickwit(
 
     let searcher_context = Arc::new(SearcherContext::new(
         node_config.searcher_config.clone(),
-        split_cache_opt,
+        split_cache_opt.clone(),
     ));
 
+    // Searchers with a metastore cache are notified through the cluster whenever the published
+    // splits of an index change so that they can refresh their cached list of splits.
+    let published_splits_subscription_handles_opt =
+        if node_config.is_service_enabled(QuickwitService::Metastore) {
+            Some(setup_published_splits_broadcaster(
+                cluster.clone(),
+                &event_broker,
+            ))
+        } else {
+            None
+        };
+    let (searcher_metastore, published_splits_listener_handle_opt) =
+        match node_config.searcher_config.metastore_cache {
+            Some(metastore_cache_config)
+                if node_config.is_service_enabled(QuickwitService::Searcher) =>
+            {
+                let caching_metastore = CachingMetastore::new(
+                    metastore_through_control_plane.clone(),
+                    metastore_cache_config.max_staleness(),
+                );
+                let listener_handle =
+                    setup_published_splits_listener(&cluster, caching_metastore.clone()).await;
+                (
+                    MetastoreServiceClient::new(caching_metastore),
+                    Some(listener_handle),
+                )
+            }
+            _ => (metastore_through_control_plane.clone(), None),
+        };
+
     let (search_job_placer, search_service) = setup_searcher(
         &node_config,
         cluster_change_stream,
-        metastore_through_control_plane.clone(),
+        searcher_metastore,
         storage_resolver.clone(),
         searcher_context,
     )
     .await?;
 
+    // Searchers advertise the splits stored in their split cache, including the ones loaded from
+    // disk on startup, in their chitchat node state so that search jobs are placed on them.
+    if let Some(split_cache) = split_cache_opt {
+        if node_config.is_service_enabled(QuickwitService::Searcher) {
+            tokio::spawn(warm_splits_advertising_task(cluster.clone(), split_cache));
+        }
+    }
+
     // The control plane listens for local shards updates to learn about each shard's ingestion
     // throughput. Ingesters (routers) do so to update their shard table.
     let local_shards_update_listener_handle_opt = if node_config
@@ -499,8 +545,11 @@ pub async fn serve_quickwit(
     let report_splits_subscription_handle_opt =
         // DISCLAIMER: This is quirky here: We base our decision to forward the split report depending
         // on the current searcher configuration.
-        if node_config.searcher_config.split_cache.is_some() {
-            // The searcher receive hints about new splits to populate their index.
+        if node_config.searcher_config.split_cache.is_some()
+            || node_config.searcher_config.split_warmup.is_some()
+        {
+            // The searcher receive hints about new splits to populate their split cache or warm
+            // them up.
             Some(event_broker.subscribe::<ReportSplitsRequest>(search_job_placer.clone()))
         } else {
             None
@@ -559,6 +608,8 @@ pub async fn serve_quickwit(
         control_plane_service,
         _local_shards_update_listener_handle_opt: local_shards_update_listener_handle_opt,
         _report_splits_subscription_handle_opt: report_splits_subscription_handle_opt,
+        _published_splits_subscription_handles_opt: published_splits_subscription_handles_opt,
+        _published_splits_listener_handle_opt: published_splits_listener_handle_opt,
         index_manager,
         indexing_service_opt,
         ingest_router_service,
@@ -758,15 +809,19 @@ async fn setup_searcher(
     )
     .await?;
     let search_service_clone = search_service.clone();
+    let search_job_placer_clone = search_job_placer.clone();
     let max_message_size = node_config.grpc_config.max_message_size;
     let searcher_change_stream = cluster_change_stream.filter_map(move |cluster_change| {
         let search_service_clone = search_service_clone.clone();
+        let search_job_placer_clone = search_job_placer_clone.clone();
         Box::pin(async move {
             match cluster_change {
                 ClusterChange::Add(node)
                     if node.enabled_services().contains(&QuickwitService::Searcher) =>
                 {
                     let grpc_addr = node.grpc_advertise_addr();
+                    search_job_placer_clone
+                        .register_warm_splits(grpc_addr, node.warm_split_ids().clone());
 
                     if node.is_self_node() {
                         let search_client =
@@ -782,7 +837,20 @@ async fn setup_searcher(
                         Some(Change::Insert(grpc_addr, search_client))
                     }
                 }
-                ClusterChange::Remove(node) => Some(Change::Remove(node.grpc_advertise_addr())),
+                ClusterChange::Update(node)
+                    if node.enabled_services().contains(&QuickwitService::Searcher) =>
+                {
+                    search_job_placer_clone.register_warm_splits(
+                        node.grpc_advertise_addr(),
+                        node.warm_split_ids().clone(),
+                    );
+                    None
+                }
+                ClusterChange::Remove(node) => {
+                    let grpc_addr = node.grpc_advertise_addr();
+                    search_job_placer_clone.unregister_warm_splits(grpc_addr);
+                    Some(Change::Remove(grpc_addr))
+                }
                 _ => None,
             }
         })
@@ -791,6 +859,17 @@ async fn setup_searcher(
     Ok((search_job_placer, search_service))
 }
 
+async fn warm_splits_advertising_task(cluster: Cluster, split_cache: Arc<SplitCache>) {
+    let mut interval = tokio::time::interval(WARM_SPLITS_ADVERTISING_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let warm_split_ids = split_cache.warm_split_ids();
+        cluster.update_self_node_warm_splits(&warm_split_ids).await;
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn setup_control_plane(
     universe: &Universe,
@@ -0,0 +1,238 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use quickwit_cluster::{Cluster, ListenerHandle};
+use quickwit_common::pubsub::{EventBroker, EventSubscriptionHandle};
+use quickwit_metastore::CachingMetastore;
+use quickwit_proto::metastore::{
+    DeleteIndexRequest, MarkSplitsForDeletionRequest, PublishSplitsRequest,
+};
+use quickwit_proto::types::IndexUid;
+use tracing::warn;
+
+/// Prefix used in chitchat to notify searchers that the published splits of an index have changed.
+/// The key is suffixed with the index UID and the value is a counter incremented on every change.
+const PUBLISHED_SPLITS_PREFIX: &str = "metastore.published_splits:";
+
+/// Bumps the chitchat key of an index to notify searchers that its published splits changed.
+#[derive(Clone)]
+struct PublishedSplitsNotifier {
+    cluster: Cluster,
+    counter: Arc<AtomicU64>,
+}
+
+impl PublishedSplitsNotifier {
+    fn notify(&self, index_uid: &str) {
+        let cluster = self.cluster.clone();
+        let key = format!("{PUBLISHED_SPLITS_PREFIX}{index_uid}");
+        let value = self.counter.fetch_add(1, Ordering::Relaxed) + 1;
+
+        tokio::spawn(async move {
+            cluster.set_self_key_value(key, value).await;
+        });
+    }
+}
+
+/// Broadcasts the events emitted by the local metastore server that change the published splits
+/// of an index (publish, including the splits replaced by merges and tiering, and mark for
+/// deletion) to the cluster so that searchers can invalidate their cached list of published
+/// splits. The key of an index is removed when the index is deleted.
+pub(crate) fn setup_published_splits_broadcaster(
+    cluster: Cluster,
+    event_broker: &EventBroker,
+) -> Vec<EventSubscriptionHandle> {
+    let notifier = PublishedSplitsNotifier {
+        cluster: cluster.clone(),
+        counter: Arc::new(AtomicU64::new(0)),
+    };
+    let publish_notifier = notifier.clone();
+    let publish_splits_subscription_handle =
+        event_broker.subscribe(move |request: PublishSplitsRequest| {
+            if request.staged_split_ids.is_empty() && request.replaced_split_ids.is_empty() {
+                return;
+            }
+            publish_notifier.notify(&request.index_uid);
+        });
+    let mark_splits_for_deletion_subscription_handle =
+        event_broker.subscribe(move |request: MarkSplitsForDeletionRequest| {
+            if request.split_ids.is_empty() {
+                return;
+            }
+            notifier.notify(&request.index_uid);
+        });
+    let delete_index_subscription_handle =
+        event_broker.subscribe(move |request: DeleteIndexRequest| {
+            let cluster = cluster.clone();
+            let key = format!("{PUBLISHED_SPLITS_PREFIX}{}", request.index_uid);
+
+            tokio::spawn(async move {
+                cluster.remove_self_key(&key).await;
+            });
+        });
+    vec![
+        publish_splits_subscription_handle,
+        mark_splits_for_deletion_subscription_handle,
+        delete_index_subscription_handle,
+    ]
+}
+
+/// Invalidates the cached list of published splits of an index whenever a metastore node
+/// broadcasts that the published splits of that index have changed.
+pub(crate) async fn setup_published_splits_listener(
+    cluster: &Cluster,
+    caching_metastore: CachingMetastore,
+) -> ListenerHandle {
+    cluster
+        .subscribe(PUBLISHED_SPLITS_PREFIX, move |event| {
+            let Ok(index_uid) = IndexUid::parse(event.key) else {
+                warn!("failed to parse index UID `{}`", event.key);
+                return;
+            };
+            caching_metastore.invalidate(&index_uid);
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use quickwit_cluster::{create_cluster_for_test, ChannelTransport};
+    use quickwit_common::ServiceStream;
+    use quickwit_metastore::{
+        ListSplitsQuery, ListSplitsRequestExt, ListSplitsResponseExt, SplitState,
+    };
+    use quickwit_proto::metastore::{
+        ListSplitsRequest, ListSplitsResponse, MetastoreService, MetastoreServiceClient,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_published_splits_invalidate_caching_metastore() {
+        let transport = ChannelTransport::default();
+        let cluster =
+            create_cluster_for_test(Vec::new(), &["metastore", "searcher"], &transport, true)
+                .await
+                .unwrap();
+        let event_broker = EventBroker::default();
+        let _subscription_handles =
+            setup_published_splits_broadcaster(cluster.clone(), &event_broker);
+
+        let index_uid = IndexUid::new_with_random_ulid("test-index");
+
+        let mut mock_metastore = MetastoreServiceClient::mock();
+        mock_metastore
+            .expect_list_splits()
+            .times(3)
+            .returning(|_request| {
+                let response = ListSplitsResponse::try_from_splits(Vec::new()).unwrap();
+                Ok(ServiceStream::from(vec![Ok(response)]))
+            });
+        let mut caching_metastore = CachingMetastore::new(
+            MetastoreServiceClient::from(mock_metastore),
+            Duration::from_secs(3600),
+        );
+        let _listener_handle =
+            setup_published_splits_listener(&cluster, caching_metastore.clone()).await;
+
+        let list_splits_request = ListSplitsRequest::try_from_list_splits_query(
+            ListSplitsQuery::for_index(index_uid.clone()).with_split_state(SplitState::Published),
+        )
+        .unwrap();
+        caching_metastore
+            .list_splits(list_splits_request.clone())
+            .await
+            .unwrap();
+
+        event_broker.publish(PublishSplitsRequest {
+            index_uid: index_uid.to_string(),
+            staged_split_ids: vec!["test-split".to_string()],
+            ..Default::default()
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let published_splits_key = format!("{PUBLISHED_SPLITS_PREFIX}{index_uid}");
+        let value = cluster.get_self_key_value(&published_splits_key).await;
+        assert_eq!(value.as_deref(), Some("1"));
+
+        // The cache was invalidated, so the splits are listed again.
+        caching_metastore
+            .list_splits(list_splits_request.clone())
+            .await
+            .unwrap();
+
+        event_broker.publish(MarkSplitsForDeletionRequest {
+            index_uid: index_uid.to_string(),
+            split_ids: vec!["test-split".to_string()],
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let value = cluster.get_self_key_value(&published_splits_key).await;
+        assert_eq!(value.as_deref(), Some("2"));
+
+        caching_metastore
+            .list_splits(list_splits_request)
+            .await
+            .unwrap();
+
+        event_broker.publish(DeleteIndexRequest {
+            index_uid: index_uid.to_string(),
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let value = cluster.get_self_key_value(&published_splits_key).await;
+        assert!(value.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replaced_splits_bump_published_splits_key() {
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["metastore"], &transport, true)
+            .await
+            .unwrap();
+        let event_broker = EventBroker::default();
+        let _subscription_handles =
+            setup_published_splits_broadcaster(cluster.clone(), &event_broker);
+
+        let index_uid = IndexUid::new_with_random_ulid("test-index");
+        let published_splits_key = format!("{PUBLISHED_SPLITS_PREFIX}{index_uid}");
+
+        event_broker.publish(PublishSplitsRequest {
+            index_uid: index_uid.to_string(),
+            ..Default::default()
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let value = cluster.get_self_key_value(&published_splits_key).await;
+        assert!(value.is_none());
+
+        event_broker.publish(PublishSplitsRequest {
+            index_uid: index_uid.to_string(),
+            replaced_split_ids: vec!["test-split".to_string()],
+            ..Default::default()
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let value = cluster.get_self_key_value(&published_splits_key).await;
+        assert_eq!(value.as_deref(), Some("1"));
+    }
+}
@@ -45,7 +45,9 @@ use crate::json_api_response::{ApiError, JsonApiResponse};
 use crate::metrics_api::metrics_handler;
 use crate::node_info_handler::node_info_handler;
 use crate::otlp_api::otlp_ingest_api_handlers;
-use crate::search_api::{search_get_handler, search_post_handler, search_stream_handler};
+use crate::search_api::{
+    list_terms_handler, search_get_handler, search_post_handler, search_stream_handler,
+};
 use crate::ui_handler::ui_handler;
 use crate::{BodyFormat, BuildInfo, QuickwitServices, RuntimeInfo};
 
@@ -180,6 +182,7 @@ fn api_v1_routes(
             .or(search_stream_handler(
                 quickwit_services.search_service.clone(),
             ))
+            .or(list_terms_handler(quickwit_services.search_service.clone()))
             .or(ingest_api_handlers(
                 quickwit_services.ingest_router_service.clone(),
                 quickwit_services.ingest_service.clone(),
@@ -597,6 +600,8 @@ mod tests {
         let quickwit_services = QuickwitServices {
             _report_splits_subscription_handle_opt: None,
             _local_shards_update_listener_handle_opt: None,
+            _published_splits_subscription_handle_opt: None,
+            _published_splits_listener_handle_opt: None,
             cluster,
             control_plane_service,
             indexing_service_opt: None,
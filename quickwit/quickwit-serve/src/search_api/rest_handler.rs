@@ -24,11 +24,14 @@ use futures::stream::StreamExt;
 use hyper::header::HeaderValue;
 use hyper::HeaderMap;
 use percent_encoding::percent_decode_str;
+use quickwit_common::is_false;
 use quickwit_config::validate_index_id_pattern;
-use quickwit_proto::search::{CountHits, OutputFormat, SortField, SortOrder};
+use quickwit_proto::search::{CountHits, ListTermsRequest, OutputFormat, SortField, SortOrder};
 use quickwit_proto::ServiceError;
 use quickwit_query::query_ast::query_ast_from_user_text;
-use quickwit_search::{SearchError, SearchResponseRest, SearchService};
+use quickwit_search::{
+    ListTermsResponseRest, SearchError, SearchResponseRest, SearchService, TermRest,
+};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value as JsonValue;
 use tracing::info;
@@ -42,15 +45,22 @@ use crate::{with_arg, BodyFormat};
 
 #[derive(utoipa::OpenApi)]
 #[openapi(
-    paths(search_get_handler, search_post_handler, search_stream_handler,),
+    paths(
+        search_get_handler,
+        search_post_handler,
+        search_stream_handler,
+        list_terms_handler,
+    ),
     components(schemas(
         BodyFormat,
+        ListTermsResponseRest,
         OutputFormat,
         SearchRequestQueryString,
         SearchResponseRest,
         SortBy,
         SortField,
         SortOrder,
+        TermRest,
     ),)
 )]
 pub struct SearchApi;
@@ -231,6 +241,18 @@ pub struct SearchRequestQueryString {
     #[serde(with = "count_hits_from_bool")]
     #[serde(default = "count_hits_from_bool::default")]
     pub count_all: CountHits,
+    /// If set, the response includes a profile of the time spent in the
+    /// different phases of the search, per split.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub profile: bool,
+    #[param(value_type = Object)]
+    #[schema(value_type = Object)]
+    /// The runtime mappings JSON object. Runtime fields are computed at query time
+    /// and can be used in filters, sorts, aggregations, and returned documents.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime_mappings: Option<JsonValue>,
 }
 
 mod count_hits_from_bool {
@@ -285,6 +307,12 @@ pub fn search_request_from_api_request(
         scroll_ttl_secs: None,
         search_after: None,
         count_hits: search_request.count_all.into(),
+        profile: search_request.profile,
+        point_in_time: None,
+        collapse: None,
+        runtime_mappings: search_request
+            .runtime_mappings
+            .map(|runtime_mappings| runtime_mappings.to_string()),
     };
     Ok(search_request)
 }
@@ -519,6 +547,110 @@ fn search_stream_filter(
         .and(serde_qs::warp::query(serde_qs::Config::default()))
 }
 
+fn default_list_terms_size() -> u64 {
+    10
+}
+
+/// Maximum number of terms returned by the list terms API.
+const MAX_LIST_TERMS_SIZE: u64 = 10_000;
+
+/// This struct represents the list terms query passed to
+/// the REST API.
+#[derive(Deserialize, Debug, Eq, PartialEq, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(deny_unknown_fields)]
+struct ListTermsRequestQueryString {
+    /// Field to list the terms of.
+    #[serde(deserialize_with = "deserialize_non_empty_string")]
+    pub field: String,
+    /// If set, only returns terms starting with this prefix.
+    pub prefix: Option<String>,
+    /// If set, only returns terms fully matching this regular expression.
+    pub regex: Option<String>,
+    /// Maximum number of terms to return (by default 10, at most 10_000).
+    #[serde(default = "default_list_terms_size")]
+    pub size: u64,
+    /// If set, restricts the listing to splits with documents with a
+    /// `timestamp >= start_timestamp`.
+    pub start_timestamp: Option<i64>,
+    /// If set, restricts the listing to splits with documents with a
+    /// `timestamp < end_timestamp`.
+    pub end_timestamp: Option<i64>,
+    /// The output format.
+    #[serde(default)]
+    pub format: BodyFormat,
+}
+
+async fn list_terms_endpoint(
+    index_id_patterns: Vec<String>,
+    list_terms_request: ListTermsRequestQueryString,
+    search_service: &dyn SearchService,
+) -> Result<ListTermsResponseRest, SearchError> {
+    if list_terms_request.size > MAX_LIST_TERMS_SIZE {
+        return Err(SearchError::InvalidArgument(format!(
+            "max value for size is {MAX_LIST_TERMS_SIZE}, but got {}",
+            list_terms_request.size
+        )));
+    }
+    let list_terms_request = ListTermsRequest {
+        index_id_patterns,
+        field: list_terms_request.field,
+        start_timestamp: list_terms_request.start_timestamp,
+        end_timestamp: list_terms_request.end_timestamp,
+        max_hits: Some(list_terms_request.size),
+        start_key: None,
+        end_key: None,
+        prefix: list_terms_request.prefix,
+        regex: list_terms_request.regex,
+    };
+    let list_terms_response = search_service.root_list_terms(list_terms_request).await?;
+    let list_terms_response_rest = ListTermsResponseRest::try_from(list_terms_response)?;
+    Ok(list_terms_response_rest)
+}
+
+async fn list_terms(
+    index_id_patterns: Vec<String>,
+    list_terms_request: ListTermsRequestQueryString,
+    search_service: Arc<dyn SearchService>,
+) -> impl warp::Reply {
+    info!(request =? list_terms_request, "list_terms");
+    let body_format = list_terms_request.format;
+    let result = list_terms_endpoint(index_id_patterns, list_terms_request, &*search_service).await;
+    make_json_api_response(result, body_format)
+}
+
+fn list_terms_filter(
+) -> impl Filter<Extract = (Vec<String>, ListTermsRequestQueryString), Error = Rejection> + Clone {
+    warp::path!(String / "terms")
+        .and_then(extract_index_id_patterns)
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+}
+
+#[utoipa::path(
+    get,
+    tag = "Search",
+    path = "/{index_id}/terms",
+    responses(
+        (status = 200, description = "Successfully listed terms.", body = ListTermsResponseRest)
+    ),
+    params(
+        ListTermsRequestQueryString,
+        ("index_id" = String, Path, description = "The index ID to list the terms of."),
+    )
+)]
+/// List Terms
+///
+/// Lists the terms of a field in lexicographic order, along with the number of documents
+/// containing them.
+pub fn list_terms_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    list_terms_filter()
+        .and(with_arg(search_service))
+        .then(list_terms)
+}
+
 #[cfg(test)]
 mod tests {
     use assert_json_diff::{assert_json_eq, assert_json_include};
@@ -536,7 +668,8 @@ mod tests {
         let mock_search_service_in_arc = Arc::new(mock_search_service);
         search_get_handler(mock_search_service_in_arc.clone())
             .or(search_post_handler(mock_search_service_in_arc.clone()))
-            .or(search_stream_handler(mock_search_service_in_arc))
+            .or(search_stream_handler(mock_search_service_in_arc.clone()))
+            .or(list_terms_handler(mock_search_service_in_arc))
             .recover(recover_fn)
     }
 
@@ -572,6 +705,8 @@ mod tests {
             elapsed_time_micros: 0u64,
             errors: Vec::new(),
             aggregations: None,
+            profile: None,
+            storage_request_stats: None,
         };
         let search_response_json: JsonValue = serde_json::to_value(search_response)?;
         let expected_search_response_json: JsonValue = json!({
@@ -1175,6 +1310,7 @@ mod tests {
                     partial_hit: None,
                     snippet: Some(r#"{"title": [], "body": ["foo <em>bar</em> baz"]}"#.to_string()),
                     index_id: "quickwit-demo-index".to_string(),
+                    inner_hits: Vec::new(),
                 }],
                 num_hits: 1,
                 elapsed_time_micros: 16,
@@ -1264,4 +1400,64 @@ mod tests {
             );
         }
     }
+
+    #[tokio::test]
+    async fn test_rest_list_terms_api() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_list_terms()
+            .with(predicate::function(
+                |list_terms_request: &quickwit_proto::search::ListTermsRequest| {
+                    list_terms_request.index_id_patterns == vec!["quickwit-demo-index"]
+                        && list_terms_request.field == "service"
+                        && list_terms_request.prefix.as_deref() == Some("qu")
+                        && list_terms_request.regex.is_none()
+                        && list_terms_request.max_hits == Some(5)
+                },
+            ))
+            .returning(|_| {
+                Ok(quickwit_proto::search::ListTermsResponse {
+                    num_hits: 1,
+                    // Serialized term: field ID, type code, and value.
+                    terms: vec![b"\0\0\0\0squickwit".to_vec()],
+                    elapsed_time_micros: 0,
+                    errors: Vec::new(),
+                    doc_freqs: vec![3],
+                })
+            });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/terms?field=service&prefix=qu&size=5")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert_json_include!(
+            actual: response_json,
+            expected: json!({
+                "num_hits": 1,
+                "terms": [{"term": "quickwit", "doc_count": 3}],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_list_terms_api_size_too_large() {
+        let rest_search_api_handler = search_handler(MockSearchService::new());
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/terms?field=service&size=18446744073709551615")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_rest_list_terms_api_missing_field() {
+        let rest_search_api_handler = search_handler(MockSearchService::new());
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/terms?prefix=qu")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+    }
 }
@@ -0,0 +1,324 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use aws_sdk_kms::config::Region;
+use aws_sdk_kms::primitives::Blob;
+use aws_sdk_kms::Client as KmsClient;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use quickwit_aws::get_aws_config;
+use quickwit_config::StorageEncryptionConfig;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use tokio::sync::OnceCell;
+use tracing::info;
+
+use crate::{StorageErrorKind, StorageResult};
+
+const NONCE_NUM_BYTES: usize = 12;
+
+/// A data key encrypted with a key encryption key held by a [`KeyProvider`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WrappedKey {
+    /// Identifies the key encryption key.
+    pub key_id: String,
+    /// The encrypted data key.
+    pub ciphertext: Vec<u8>,
+}
+
+/// Wraps and unwraps the per-object data keys used by an
+/// [`EncryptedStorage`](super::EncryptedStorage).
+#[async_trait]
+pub trait KeyProvider: fmt::Debug + Send + Sync + 'static {
+    /// Encrypts a data key with the current key encryption key.
+    async fn wrap_key(&self, data_key: &[u8]) -> StorageResult<WrappedKey>;
+
+    /// Decrypts a data key previously wrapped with [`KeyProvider::wrap_key`].
+    async fn unwrap_key(&self, wrapped_key: &WrappedKey) -> StorageResult<Vec<u8>>;
+}
+
+/// Creates the [`KeyProvider`] described by a [`StorageEncryptionConfig`].
+pub fn key_provider_from_config(
+    storage_encryption_config: &StorageEncryptionConfig,
+) -> anyhow::Result<Arc<dyn KeyProvider>> {
+    let key_provider: Arc<dyn KeyProvider> = match storage_encryption_config {
+        StorageEncryptionConfig::Local { keyfile } => {
+            Arc::new(LocalKeyProvider::from_keyfile(keyfile)?)
+        }
+        StorageEncryptionConfig::Kms {
+            key_id,
+            region,
+            endpoint,
+        } => Arc::new(KmsKeyProvider::new(
+            key_id.clone(),
+            region.clone(),
+            endpoint.clone(),
+        )),
+    };
+    Ok(key_provider)
+}
+
+/// Wraps data keys with a 256-bit key encryption key held locally.
+pub struct LocalKeyProvider {
+    key_id: String,
+    cipher: ChaCha20Poly1305,
+}
+
+impl fmt::Debug for LocalKeyProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalKeyProvider")
+            .field("key_id", &self.key_id)
+            .finish()
+    }
+}
+
+impl LocalKeyProvider {
+    /// Creates a provider from a raw 256-bit key.
+    pub fn new(key_id: impl Into<String>, key: [u8; 32]) -> Self {
+        Self {
+            key_id: key_id.into(),
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+        }
+    }
+
+    /// Creates a provider from a keyfile containing a base64-encoded 256-bit key. The key ID is
+    /// the name of the keyfile.
+    pub fn from_keyfile(keyfile: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(keyfile).with_context(|| {
+            format!(
+                "failed to read storage encryption keyfile `{}`",
+                keyfile.display()
+            )
+        })?;
+        let key_bytes = BASE64_STANDARD
+            .decode(content.trim())
+            .context("storage encryption keyfile must contain a base64-encoded key")?;
+        let key: [u8; 32] = key_bytes.try_into().map_err(|key_bytes: Vec<u8>| {
+            anyhow!(
+                "storage encryption key must be 32 bytes long, got {}",
+                key_bytes.len()
+            )
+        })?;
+        let key_id = keyfile
+            .file_name()
+            .map(|file_name| file_name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Ok(Self::new(key_id, key))
+    }
+}
+
+#[async_trait]
+impl KeyProvider for LocalKeyProvider {
+    async fn wrap_key(&self, data_key: &[u8]) -> StorageResult<WrappedKey> {
+        let mut nonce = [0u8; NONCE_NUM_BYTES];
+        OsRng.fill_bytes(&mut nonce);
+        let payload = Payload {
+            msg: data_key,
+            aad: self.key_id.as_bytes(),
+        };
+        let encrypted_key = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| {
+                StorageErrorKind::Internal.with_error(anyhow!("failed to wrap data key"))
+            })?;
+        let mut ciphertext = nonce.to_vec();
+        ciphertext.extend_from_slice(&encrypted_key);
+        Ok(WrappedKey {
+            key_id: self.key_id.clone(),
+            ciphertext,
+        })
+    }
+
+    async fn unwrap_key(&self, wrapped_key: &WrappedKey) -> StorageResult<Vec<u8>> {
+        if wrapped_key.key_id != self.key_id {
+            return Err(StorageErrorKind::Unauthorized.with_error(anyhow!(
+                "object was encrypted with unknown key `{}`",
+                wrapped_key.key_id
+            )));
+        }
+        if wrapped_key.ciphertext.len() < NONCE_NUM_BYTES {
+            return Err(
+                StorageErrorKind::Internal.with_error(anyhow!("wrapped data key is truncated"))
+            );
+        }
+        let (nonce, encrypted_key) = wrapped_key.ciphertext.split_at(NONCE_NUM_BYTES);
+        let payload = Payload {
+            msg: encrypted_key,
+            aad: self.key_id.as_bytes(),
+        };
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| {
+                StorageErrorKind::Unauthorized.with_error(anyhow!("failed to unwrap data key"))
+            })
+    }
+}
+
+/// Wraps data keys with a key management service compatible with the AWS KMS `Encrypt` and
+/// `Decrypt` APIs.
+pub struct KmsKeyProvider {
+    key_id: String,
+    region: Option<String>,
+    endpoint: Option<String>,
+    kms_client: OnceCell<KmsClient>,
+}
+
+impl fmt::Debug for KmsKeyProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KmsKeyProvider")
+            .field("key_id", &self.key_id)
+            .field("region", &self.region)
+            .field("endpoint", &self.endpoint)
+            .finish()
+    }
+}
+
+impl KmsKeyProvider {
+    /// Creates a provider wrapping data keys with the KMS key `key_id`. The client is created
+    /// lazily on first use.
+    pub fn new(key_id: String, region: Option<String>, endpoint: Option<String>) -> Self {
+        Self {
+            key_id,
+            region,
+            endpoint,
+            kms_client: OnceCell::new(),
+        }
+    }
+
+    async fn kms_client(&self) -> &KmsClient {
+        self.kms_client
+            .get_or_init(|| async {
+                let aws_config = get_aws_config().await;
+                let region = self
+                    .region
+                    .clone()
+                    .map(Region::new)
+                    .or(aws_config.region().cloned());
+                let mut kms_config = aws_sdk_kms::config::Builder::from(aws_config).region(region);
+
+                if let Some(endpoint) = &self.endpoint {
+                    info!(endpoint=%endpoint, "using KMS endpoint defined in storage encryption config");
+                    kms_config.set_endpoint_url(Some(endpoint.clone()));
+                }
+                KmsClient::from_conf(kms_config.build())
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl KeyProvider for KmsKeyProvider {
+    async fn wrap_key(&self, data_key: &[u8]) -> StorageResult<WrappedKey> {
+        let encrypt_output = self
+            .kms_client()
+            .await
+            .encrypt()
+            .key_id(&self.key_id)
+            .plaintext(Blob::new(data_key))
+            .send()
+            .await
+            .map_err(|error| StorageErrorKind::Service.with_error(error))?;
+        let ciphertext = encrypt_output
+            .ciphertext_blob()
+            .ok_or_else(|| {
+                StorageErrorKind::Service
+                    .with_error(anyhow!("KMS response is missing the ciphertext blob"))
+            })?
+            .as_ref()
+            .to_vec();
+        Ok(WrappedKey {
+            key_id: self.key_id.clone(),
+            ciphertext,
+        })
+    }
+
+    async fn unwrap_key(&self, wrapped_key: &WrappedKey) -> StorageResult<Vec<u8>> {
+        let decrypt_output = self
+            .kms_client()
+            .await
+            .decrypt()
+            .key_id(&wrapped_key.key_id)
+            .ciphertext_blob(Blob::new(wrapped_key.ciphertext.as_slice()))
+            .send()
+            .await
+            .map_err(|error| StorageErrorKind::Service.with_error(error))?;
+        let data_key = decrypt_output
+            .plaintext()
+            .ok_or_else(|| {
+                StorageErrorKind::Service
+                    .with_error(anyhow!("KMS response is missing the plaintext blob"))
+            })?
+            .as_ref()
+            .to_vec();
+        Ok(data_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_key_provider() {
+        let key_provider = LocalKeyProvider::new("test-key", [7; 32]);
+        let data_key = [42u8; 32];
+        let wrapped_key = key_provider.wrap_key(&data_key).await.unwrap();
+        assert_eq!(wrapped_key.key_id, "test-key");
+        assert_ne!(&wrapped_key.ciphertext[NONCE_NUM_BYTES..], &data_key);
+
+        let unwrapped_key = key_provider.unwrap_key(&wrapped_key).await.unwrap();
+        assert_eq!(unwrapped_key, data_key);
+
+        let other_key_provider = LocalKeyProvider::new("test-key", [8; 32]);
+        let error = other_key_provider
+            .unwrap_key(&wrapped_key)
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), StorageErrorKind::Unauthorized);
+
+        let other_key_provider = LocalKeyProvider::new("other-key", [7; 32]);
+        let error = other_key_provider
+            .unwrap_key(&wrapped_key)
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), StorageErrorKind::Unauthorized);
+    }
+
+    #[test]
+    fn test_local_key_provider_from_keyfile() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let keyfile = temp_dir.path().join("storage.key");
+
+        std::fs::write(&keyfile, format!("{}\n", BASE64_STANDARD.encode([7u8; 32]))).unwrap();
+        let key_provider = LocalKeyProvider::from_keyfile(&keyfile).unwrap();
+        assert_eq!(key_provider.key_id, "storage.key");
+
+        std::fs::write(&keyfile, BASE64_STANDARD.encode([7u8; 16])).unwrap();
+        LocalKeyProvider::from_keyfile(&keyfile).unwrap_err();
+
+        LocalKeyProvider::from_keyfile(&temp_dir.path().join("does-not-exist")).unwrap_err();
+    }
+}
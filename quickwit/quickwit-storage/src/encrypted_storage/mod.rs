@@ -0,0 +1,476 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+mod key_provider;
+mod object_cipher;
+
+use std::fmt;
+use std::io::Cursor;
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use aws_smithy_http::byte_stream::ByteStream;
+use lru::LruCache;
+use quickwit_common::uri::Uri;
+use tokio::io::{AsyncRead, AsyncWriteExt};
+
+pub use self::key_provider::{
+    key_provider_from_config, KeyProvider, KmsKeyProvider, LocalKeyProvider, WrappedKey,
+};
+use self::object_cipher::{
+    generate_data_key, EncryptionHeader, ObjectCipher, DEFAULT_BLOCK_NUM_BYTES,
+    HEADER_PREFIX_NUM_BYTES,
+};
+use crate::storage::SendableAsync;
+use crate::{BulkDeleteError, OwnedBytes, PutPayload, Storage, StorageErrorKind, StorageResult};
+
+/// Number of object ciphers, i.e. unwrapped data keys, kept in memory. This spares ranged reads
+/// from downloading the header and unwrapping the data key of an object on every call.
+const OBJECT_CIPHER_CACHE_CAPACITY: usize = 10_000;
+
+/// Number of blocks downloaded and decrypted at once by `copy_to`.
+const COPY_NUM_BLOCKS: usize = 64;
+
+/// Encryption state shared by all the [`EncryptedStorage`] instances created by a
+/// `StorageResolver`.
+pub struct StorageEncryption {
+    key_provider: Arc<dyn KeyProvider>,
+    object_ciphers: Mutex<LruCache<(Uri, PathBuf), Arc<ObjectCipher>>>,
+}
+
+impl fmt::Debug for StorageEncryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StorageEncryption")
+            .field("key_provider", &self.key_provider)
+            .finish()
+    }
+}
+
+impl StorageEncryption {
+    /// Creates a new [`StorageEncryption`] wrapping data keys with `key_provider`.
+    pub fn new(key_provider: Arc<dyn KeyProvider>) -> Self {
+        let capacity = NonZeroUsize::new(OBJECT_CIPHER_CACHE_CAPACITY).unwrap();
+        Self {
+            key_provider,
+            object_ciphers: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    fn get_object_cipher(&self, uri: &Uri, path: &Path) -> Option<Arc<ObjectCipher>> {
+        self.object_ciphers
+            .lock()
+            .unwrap()
+            .get(&(uri.clone(), path.to_path_buf()))
+            .cloned()
+    }
+
+    fn put_object_cipher(&self, uri: &Uri, path: &Path, object_cipher: Arc<ObjectCipher>) {
+        self.object_ciphers
+            .lock()
+            .unwrap()
+            .put((uri.clone(), path.to_path_buf()), object_cipher);
+    }
+
+    fn evict_object_cipher(&self, uri: &Uri, path: &Path) {
+        self.object_ciphers
+            .lock()
+            .unwrap()
+            .pop(&(uri.clone(), path.to_path_buf()));
+    }
+
+    async fn load_object_cipher(&self, header_bytes: &[u8]) -> StorageResult<ObjectCipher> {
+        let header = EncryptionHeader::deserialize(header_bytes)?;
+        let data_key = self.key_provider.unwrap_key(&header.wrapped_key).await?;
+        ObjectCipher::with_header_bytes(&header, header_bytes.to_vec(), &data_key)
+    }
+}
+
+/// This storage acts as a proxy to another storage and encrypts every object on the client side.
+///
+/// Each object is encrypted with its own data key (envelope encryption), stored alongside the
+/// object after being wrapped by a [`KeyProvider`]. The plaintext is sealed in fixed-size blocks,
+/// so that `get_slice` only downloads and decrypts the blocks overlapping the requested range.
+/// Ranges and lengths exposed by this storage refer to the plaintext.
+///
+/// The data keys of the objects read by range are cached, assuming these objects are immutable
+/// as splits are.
+pub struct EncryptedStorage {
+    storage: Arc<dyn Storage>,
+    encryption: Arc<StorageEncryption>,
+}
+
+impl fmt::Debug for EncryptedStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedStorage")
+            .field("storage", &self.storage)
+            .field("encryption", &self.encryption)
+            .finish()
+    }
+}
+
+impl EncryptedStorage {
+    /// Wraps `storage` so that objects are encrypted with `encryption`.
+    pub fn new(storage: Arc<dyn Storage>, encryption: Arc<StorageEncryption>) -> Self {
+        Self {
+            storage,
+            encryption,
+        }
+    }
+
+    async fn open_object(&self, path: &Path) -> StorageResult<Arc<ObjectCipher>> {
+        if let Some(object_cipher) = self.encryption.get_object_cipher(self.uri(), path) {
+            return Ok(object_cipher);
+        }
+        let header_prefix = self
+            .storage
+            .get_slice(path, 0..HEADER_PREFIX_NUM_BYTES)
+            .await?;
+        let header_len = EncryptionHeader::parse_header_len(&header_prefix)?;
+        let header_bytes = self.storage.get_slice(path, 0..header_len).await?;
+        let object_cipher = Arc::new(self.encryption.load_object_cipher(&header_bytes).await?);
+        self.encryption
+            .put_object_cipher(self.uri(), path, object_cipher.clone());
+        Ok(object_cipher)
+    }
+
+    async fn read_blocks(
+        &self,
+        path: &Path,
+        object_cipher: &ObjectCipher,
+        blocks: Range<usize>,
+    ) -> StorageResult<Vec<u8>> {
+        let ciphertext_range = object_cipher.blocks_ciphertext_range(&blocks);
+        let ciphertext = self.storage.get_slice(path, ciphertext_range).await?;
+        object_cipher.decrypt_blocks(blocks, &ciphertext)
+    }
+}
+
+#[async_trait]
+impl Storage for EncryptedStorage {
+    async fn check_connectivity(&self) -> anyhow::Result<()> {
+        self.storage.check_connectivity().await
+    }
+
+    async fn put(&self, path: &Path, payload: Box<dyn PutPayload>) -> StorageResult<()> {
+        let data_key = generate_data_key();
+        let wrapped_key = self.encryption.key_provider.wrap_key(&data_key).await?;
+        let header = EncryptionHeader {
+            block_num_bytes: DEFAULT_BLOCK_NUM_BYTES as u32,
+            plaintext_len: payload.len(),
+            wrapped_key,
+        };
+        let object_cipher = Arc::new(ObjectCipher::new(&header, &data_key)?);
+        let encrypted_payload = EncryptedPayload {
+            payload,
+            object_cipher: object_cipher.clone(),
+        };
+        self.encryption.evict_object_cipher(self.uri(), path);
+        self.storage.put(path, Box::new(encrypted_payload)).await?;
+        self.encryption
+            .put_object_cipher(self.uri(), path, object_cipher);
+        Ok(())
+    }
+
+    async fn copy_to(&self, path: &Path, output: &mut dyn SendableAsync) -> StorageResult<()> {
+        let object_cipher = self.open_object(path).await?;
+        let num_blocks = object_cipher.num_blocks();
+
+        for first_block in (0..num_blocks).step_by(COPY_NUM_BLOCKS) {
+            let blocks = first_block..(first_block + COPY_NUM_BLOCKS).min(num_blocks);
+            let plaintext = self.read_blocks(path, &object_cipher, blocks).await?;
+            output.write_all(&plaintext).await?;
+        }
+        output.flush().await?;
+        Ok(())
+    }
+
+    async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
+        if range.is_empty() {
+            return Ok(OwnedBytes::empty());
+        }
+        let object_cipher = self.open_object(path).await?;
+
+        if range.end > object_cipher.plaintext_len() {
+            return Err(StorageErrorKind::Internal.with_error(anyhow!(
+                "range {range:?} is out of bounds for an object of {} bytes",
+                object_cipher.plaintext_len()
+            )));
+        }
+        let blocks = object_cipher.blocks_overlapping_plaintext_range(&range);
+        let offset = range.start - object_cipher.block_plaintext_range(blocks.start).start;
+        let plaintext = self.read_blocks(path, &object_cipher, blocks).await?;
+        Ok(OwnedBytes::new(plaintext).slice(offset..offset + range.len()))
+    }
+
+    async fn get_slice_stream(
+        &self,
+        path: &Path,
+        range: Range<usize>,
+    ) -> StorageResult<Box<dyn AsyncRead + Send + Unpin>> {
+        let bytes = self.get_slice(path, range).await?;
+        Ok(Box::new(Cursor::new(bytes)))
+    }
+
+    async fn get_all(&self, path: &Path) -> StorageResult<OwnedBytes> {
+        let ciphertext = self.storage.get_all(path).await?;
+        let header_len = EncryptionHeader::parse_header_len(&ciphertext)?;
+
+        if ciphertext.len() < header_len {
+            return Err(StorageErrorKind::Internal
+                .with_error(anyhow!("encrypted object is truncated or corrupted")));
+        }
+        let object_cipher = self
+            .encryption
+            .load_object_cipher(&ciphertext[..header_len])
+            .await?;
+        let plaintext = object_cipher
+            .decrypt_blocks(0..object_cipher.num_blocks(), &ciphertext[header_len..])?;
+        Ok(OwnedBytes::new(plaintext))
+    }
+
+    async fn delete(&self, path: &Path) -> StorageResult<()> {
+        self.encryption.evict_object_cipher(self.uri(), path);
+        self.storage.delete(path).await
+    }
+
+    async fn bulk_delete<'a>(&self, paths: &[&'a Path]) -> Result<(), BulkDeleteError> {
+        for path in paths {
+            self.encryption.evict_object_cipher(self.uri(), path);
+        }
+        self.storage.bulk_delete(paths).await
+    }
+
+    async fn exists(&self, path: &Path) -> StorageResult<bool> {
+        self.storage.exists(path).await
+    }
+
+    async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
+        let object_cipher = self.open_object(path).await?;
+        Ok(object_cipher.plaintext_len() as u64)
+    }
+
+    fn uri(&self) -> &Uri {
+        self.storage.uri()
+    }
+}
+
+/// Encrypts a payload on the fly. Blocks are encrypted deterministically, so that the ranges
+/// requested by multipart uploads, including retries, always yield the same bytes.
+#[derive(Clone)]
+struct EncryptedPayload {
+    payload: Box<dyn PutPayload>,
+    object_cipher: Arc<ObjectCipher>,
+}
+
+#[async_trait]
+impl PutPayload for EncryptedPayload {
+    fn len(&self) -> u64 {
+        self.object_cipher.ciphertext_len() as u64
+    }
+
+    async fn range_byte_stream(&self, range: Range<u64>) -> std::io::Result<ByteStream> {
+        let range = range.start as usize..range.end as usize;
+        let header_len = self.object_cipher.header_len();
+        let mut bytes = Vec::with_capacity(range.len());
+
+        if range.start < header_len {
+            let header_bytes = self.object_cipher.header_bytes();
+            bytes.extend_from_slice(&header_bytes[range.start..range.end.min(header_len)]);
+        }
+        if range.end > header_len {
+            let blocks = self
+                .object_cipher
+                .blocks_overlapping_ciphertext_range(&range);
+            let plaintext_range = self.object_cipher.block_plaintext_range(blocks.start).start
+                ..self.object_cipher.block_plaintext_range(blocks.end - 1).end;
+            let plaintext_offset = plaintext_range.start;
+            let plaintext_range_u64 = plaintext_range.start as u64..plaintext_range.end as u64;
+            let mut reader = self
+                .payload
+                .range_byte_stream(plaintext_range_u64)
+                .await?
+                .into_async_read();
+            let mut plaintext = Vec::with_capacity(plaintext_range.len());
+            tokio::io::copy(&mut reader, &mut plaintext).await?;
+
+            let ciphertext_range = self.object_cipher.blocks_ciphertext_range(&blocks);
+            let mut ciphertext = Vec::with_capacity(ciphertext_range.len());
+
+            for block_ord in blocks {
+                let block_plaintext_range = self.object_cipher.block_plaintext_range(block_ord);
+                let block_plaintext = &plaintext[block_plaintext_range.start - plaintext_offset
+                    ..block_plaintext_range.end - plaintext_offset];
+                ciphertext.extend(self.object_cipher.encrypt_block(block_ord, block_plaintext));
+            }
+            let start = range.start.max(header_len) - ciphertext_range.start;
+            let end = range.end - ciphertext_range.start;
+            bytes.extend_from_slice(&ciphertext[start..end]);
+        }
+        Ok(ByteStream::from(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RamStorage, StorageErrorKind};
+
+    fn encrypted_storage_for_test(storage: Arc<dyn Storage>) -> EncryptedStorage {
+        let key_provider = Arc::new(LocalKeyProvider::new("test-key", [7; 32]));
+        let encryption = Arc::new(StorageEncryption::new(key_provider));
+        EncryptedStorage::new(storage, encryption)
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_storage() {
+        let ram_storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let mut encrypted_storage = encrypted_storage_for_test(ram_storage);
+        crate::storage_test_suite(&mut encrypted_storage)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_storage_ranged_reads() {
+        let ram_storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let encrypted_storage = encrypted_storage_for_test(ram_storage.clone());
+        let path = Path::new("split.split");
+        let plaintext: Vec<u8> = (0..3 * DEFAULT_BLOCK_NUM_BYTES + 17)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        encrypted_storage
+            .put(path, Box::new(plaintext.clone()))
+            .await
+            .unwrap();
+
+        let stored_bytes = ram_storage.get_all(path).await.unwrap();
+        // The header of 93 bytes is followed by 4 blocks, each with a 16-byte tag.
+        assert_eq!(stored_bytes.len(), 93 + plaintext.len() + 4 * 16);
+        assert!(!stored_bytes
+            .as_slice()
+            .windows(32)
+            .any(|window| window == &plaintext[..32]));
+
+        assert_eq!(
+            encrypted_storage.file_num_bytes(path).await.unwrap(),
+            plaintext.len() as u64
+        );
+        for range in [
+            0..1,
+            10..DEFAULT_BLOCK_NUM_BYTES + 10,
+            DEFAULT_BLOCK_NUM_BYTES..2 * DEFAULT_BLOCK_NUM_BYTES,
+            3 * DEFAULT_BLOCK_NUM_BYTES - 1..plaintext.len(),
+            0..plaintext.len(),
+        ] {
+            let bytes = encrypted_storage
+                .get_slice(path, range.clone())
+                .await
+                .unwrap();
+            assert_eq!(bytes.as_slice(), &plaintext[range]);
+        }
+        // A fresh storage has to read the header and unwrap the data key.
+        let encrypted_storage = encrypted_storage_for_test(ram_storage.clone());
+        let bytes = encrypted_storage.get_slice(path, 100..200).await.unwrap();
+        assert_eq!(bytes.as_slice(), &plaintext[100..200]);
+
+        let mut output = Vec::new();
+        encrypted_storage.copy_to(path, &mut output).await.unwrap();
+        assert_eq!(output, plaintext);
+
+        encrypted_storage
+            .get_slice(path, 0..plaintext.len() + 1)
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_storage_multipart_payload() {
+        let ram_storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let encrypted_storage = encrypted_storage_for_test(ram_storage);
+        let plaintext: Vec<u8> = (0..2 * DEFAULT_BLOCK_NUM_BYTES + 5)
+            .map(|i| (i % 13) as u8)
+            .collect();
+        let header = EncryptionHeader {
+            block_num_bytes: DEFAULT_BLOCK_NUM_BYTES as u32,
+            plaintext_len: plaintext.len() as u64,
+            wrapped_key: encrypted_storage
+                .encryption
+                .key_provider
+                .wrap_key(&[1; 32])
+                .await
+                .unwrap(),
+        };
+        let object_cipher = Arc::new(ObjectCipher::new(&header, &[1; 32]).unwrap());
+        let payload = EncryptedPayload {
+            payload: Box::new(plaintext),
+            object_cipher,
+        };
+        let ciphertext = payload.read_all().await.unwrap();
+        assert_eq!(ciphertext.len() as u64, payload.len());
+
+        // Reading the payload by parts must yield the same bytes as reading it at once.
+        let mut parts = Vec::new();
+        let mut start = 0;
+        for part_len in [3, 50, 70_000, 1, 60_000] {
+            let end = (start + part_len).min(payload.len());
+            let mut reader = payload
+                .range_byte_stream(start..end)
+                .await
+                .unwrap()
+                .into_async_read();
+            tokio::io::copy(&mut reader, &mut parts).await.unwrap();
+            start = end;
+        }
+        let mut reader = payload
+            .range_byte_stream(start..payload.len())
+            .await
+            .unwrap()
+            .into_async_read();
+        tokio::io::copy(&mut reader, &mut parts).await.unwrap();
+        assert_eq!(parts, ciphertext.as_slice());
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_storage_rejects_tampered_objects() {
+        let ram_storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let encrypted_storage = encrypted_storage_for_test(ram_storage.clone());
+        let path = Path::new("metastore.json");
+        encrypted_storage
+            .put(path, Box::new(b"{\"index_id\": \"foo\"}".to_vec()))
+            .await
+            .unwrap();
+
+        let mut stored_bytes = ram_storage.get_all(path).await.unwrap().to_vec();
+        let last_byte = stored_bytes.last_mut().unwrap();
+        *last_byte ^= 1;
+        ram_storage.put(path, Box::new(stored_bytes)).await.unwrap();
+        let error = encrypted_storage.get_all(path).await.unwrap_err();
+        assert_eq!(error.kind(), StorageErrorKind::Internal);
+
+        ram_storage
+            .put(path, Box::new(b"{\"index_id\": \"foo\"}".to_vec()))
+            .await
+            .unwrap();
+        let error = encrypted_storage.get_all(path).await.unwrap_err();
+        assert_eq!(error.kind(), StorageErrorKind::Internal);
+    }
+}
@@ -0,0 +1,416 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::ops::Range;
+
+use anyhow::anyhow;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+
+use super::WrappedKey;
+use crate::{StorageError, StorageErrorKind, StorageResult};
+
+/// Magic number identifying objects written by an `EncryptedStorage`.
+const MAGIC_NUMBER: &[u8; 4] = b"QWEC";
+
+const FORMAT_VERSION: u8 = 1;
+
+/// Length of the magic number, the format version, and the header length.
+pub(super) const HEADER_PREFIX_NUM_BYTES: usize = 9;
+
+/// Number of plaintext bytes encrypted and authenticated as a unit. Ranged reads download and
+/// decrypt whole blocks.
+pub(super) const DEFAULT_BLOCK_NUM_BYTES: usize = 64 * 1024;
+
+const TAG_NUM_BYTES: usize = 16;
+
+pub(super) const DATA_KEY_NUM_BYTES: usize = 32;
+
+/// Generates a random data key.
+pub(super) fn generate_data_key() -> [u8; DATA_KEY_NUM_BYTES] {
+    let mut data_key = [0u8; DATA_KEY_NUM_BYTES];
+    OsRng.fill_bytes(&mut data_key);
+    data_key
+}
+
+fn corrupted_header_error(message: &str) -> StorageError {
+    StorageErrorKind::Internal.with_error(anyhow!(
+        "object is not encrypted or its encryption header is corrupted: {message}"
+    ))
+}
+
+/// Header written at the beginning of every encrypted object:
+///
+/// ```text
+/// [magic number: 4 bytes][format version: u8][header length: u32]
+/// [block length: u32][plaintext length: u64]
+/// [key ID length: u16][key ID][wrapped data key length: u16][wrapped data key]
+/// ```
+///
+/// Integers are encoded in little endian. The header is authenticated as associated data of
+/// every block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct EncryptionHeader {
+    pub block_num_bytes: u32,
+    pub plaintext_len: u64,
+    pub wrapped_key: WrappedKey,
+}
+
+impl EncryptionHeader {
+    pub fn serialize(&self) -> Vec<u8> {
+        let key_id = self.wrapped_key.key_id.as_bytes();
+        let ciphertext = &self.wrapped_key.ciphertext;
+        let header_len = HEADER_PREFIX_NUM_BYTES + 4 + 8 + 2 + key_id.len() + 2 + ciphertext.len();
+
+        let mut header_bytes = Vec::with_capacity(header_len);
+        header_bytes.extend_from_slice(MAGIC_NUMBER);
+        header_bytes.push(FORMAT_VERSION);
+        header_bytes.extend_from_slice(&(header_len as u32).to_le_bytes());
+        header_bytes.extend_from_slice(&self.block_num_bytes.to_le_bytes());
+        header_bytes.extend_from_slice(&self.plaintext_len.to_le_bytes());
+        header_bytes.extend_from_slice(&(key_id.len() as u16).to_le_bytes());
+        header_bytes.extend_from_slice(key_id);
+        header_bytes.extend_from_slice(&(ciphertext.len() as u16).to_le_bytes());
+        header_bytes.extend_from_slice(ciphertext);
+        header_bytes
+    }
+
+    /// Reads the length of the header from its first [`HEADER_PREFIX_NUM_BYTES`] bytes.
+    pub fn parse_header_len(bytes: &[u8]) -> StorageResult<usize> {
+        if bytes.len() < HEADER_PREFIX_NUM_BYTES || &bytes[..4] != MAGIC_NUMBER {
+            return Err(corrupted_header_error("magic number mismatch"));
+        }
+        if bytes[4] != FORMAT_VERSION {
+            return Err(corrupted_header_error("unsupported format version"));
+        }
+        let header_len = u32::from_le_bytes(bytes[5..9].try_into().unwrap()) as usize;
+
+        if header_len < HEADER_PREFIX_NUM_BYTES {
+            return Err(corrupted_header_error("invalid header length"));
+        }
+        Ok(header_len)
+    }
+
+    pub fn deserialize(header_bytes: &[u8]) -> StorageResult<Self> {
+        let header_len = Self::parse_header_len(header_bytes)?;
+
+        if header_bytes.len() != header_len {
+            return Err(corrupted_header_error("header length mismatch"));
+        }
+        let mut cursor = HeaderCursor {
+            bytes: &header_bytes[HEADER_PREFIX_NUM_BYTES..],
+        };
+        let block_num_bytes = u32::from_le_bytes(cursor.take_array()?);
+        let plaintext_len = u64::from_le_bytes(cursor.take_array()?);
+        let key_id_len = u16::from_le_bytes(cursor.take_array()?) as usize;
+        let key_id = String::from_utf8(cursor.take(key_id_len)?.to_vec())
+            .map_err(|_| corrupted_header_error("key ID is not valid UTF-8"))?;
+        let ciphertext_len = u16::from_le_bytes(cursor.take_array()?) as usize;
+        let ciphertext = cursor.take(ciphertext_len)?.to_vec();
+
+        if block_num_bytes == 0 {
+            return Err(corrupted_header_error("block length is zero"));
+        }
+        if !cursor.bytes.is_empty() {
+            return Err(corrupted_header_error("trailing bytes"));
+        }
+        Ok(Self {
+            block_num_bytes,
+            plaintext_len,
+            wrapped_key: WrappedKey { key_id, ciphertext },
+        })
+    }
+}
+
+struct HeaderCursor<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> HeaderCursor<'a> {
+    fn take(&mut self, num_bytes: usize) -> StorageResult<&'a [u8]> {
+        if self.bytes.len() < num_bytes {
+            return Err(corrupted_header_error("header is truncated"));
+        }
+        let (head, tail) = self.bytes.split_at(num_bytes);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> StorageResult<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+}
+
+/// Encrypts and decrypts the blocks of an object with its data key.
+///
+/// The plaintext is split into blocks of `block_num_bytes` bytes, each sealed with
+/// ChaCha20-Poly1305. Since data keys are never reused across objects, the nonce of a block is
+/// derived from its ordinal and a flag marking the last block. Empty objects hold a single empty
+/// block.
+pub(super) struct ObjectCipher {
+    header_bytes: Vec<u8>,
+    block_num_bytes: usize,
+    plaintext_len: usize,
+    cipher: ChaCha20Poly1305,
+}
+
+impl ObjectCipher {
+    pub fn new(header: &EncryptionHeader, data_key: &[u8]) -> StorageResult<Self> {
+        Self::with_header_bytes(header, header.serialize(), data_key)
+    }
+
+    pub fn with_header_bytes(
+        header: &EncryptionHeader,
+        header_bytes: Vec<u8>,
+        data_key: &[u8],
+    ) -> StorageResult<Self> {
+        if data_key.len() != DATA_KEY_NUM_BYTES {
+            return Err(StorageErrorKind::Internal.with_error(anyhow!(
+                "data key must be {DATA_KEY_NUM_BYTES} bytes long, got {}",
+                data_key.len()
+            )));
+        }
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(data_key));
+        Ok(Self {
+            header_bytes,
+            block_num_bytes: header.block_num_bytes as usize,
+            plaintext_len: header.plaintext_len as usize,
+            cipher,
+        })
+    }
+
+    pub fn header_bytes(&self) -> &[u8] {
+        &self.header_bytes
+    }
+
+    pub fn header_len(&self) -> usize {
+        self.header_bytes.len()
+    }
+
+    pub fn plaintext_len(&self) -> usize {
+        self.plaintext_len
+    }
+
+    pub fn num_blocks(&self) -> usize {
+        ((self.plaintext_len + self.block_num_bytes - 1) / self.block_num_bytes).max(1)
+    }
+
+    pub fn ciphertext_len(&self) -> usize {
+        self.header_len() + self.plaintext_len + self.num_blocks() * TAG_NUM_BYTES
+    }
+
+    pub fn block_plaintext_range(&self, block_ord: usize) -> Range<usize> {
+        let start = block_ord * self.block_num_bytes;
+        let end = (start + self.block_num_bytes).min(self.plaintext_len);
+        start..end
+    }
+
+    pub fn block_ciphertext_range(&self, block_ord: usize) -> Range<usize> {
+        let start = self.header_len() + block_ord * (self.block_num_bytes + TAG_NUM_BYTES);
+        let end = start + self.block_plaintext_range(block_ord).len() + TAG_NUM_BYTES;
+        start..end
+    }
+
+    /// Returns the ciphertext range spanning `blocks`.
+    pub fn blocks_ciphertext_range(&self, blocks: &Range<usize>) -> Range<usize> {
+        self.block_ciphertext_range(blocks.start).start
+            ..self.block_ciphertext_range(blocks.end - 1).end
+    }
+
+    /// Returns the blocks overlapping a non-empty plaintext range.
+    pub fn blocks_overlapping_plaintext_range(&self, range: &Range<usize>) -> Range<usize> {
+        range.start / self.block_num_bytes..(range.end - 1) / self.block_num_bytes + 1
+    }
+
+    /// Returns the blocks overlapping a non-empty ciphertext range, ignoring the header.
+    pub fn blocks_overlapping_ciphertext_range(&self, range: &Range<usize>) -> Range<usize> {
+        let stride = self.block_num_bytes + TAG_NUM_BYTES;
+        let start = range.start.max(self.header_len()) - self.header_len();
+        let end = range.end - self.header_len();
+        start / stride..((end - 1) / stride + 1).min(self.num_blocks())
+    }
+
+    fn nonce(&self, block_ord: usize) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        nonce[..8].copy_from_slice(&(block_ord as u64).to_le_bytes());
+        nonce[8] = (block_ord + 1 == self.num_blocks()) as u8;
+        nonce
+    }
+
+    pub fn encrypt_block(&self, block_ord: usize, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = self.nonce(block_ord);
+        let payload = Payload {
+            msg: plaintext,
+            aad: &self.header_bytes,
+        };
+        self.cipher
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .expect("encrypting an in-memory buffer should not fail")
+    }
+
+    /// Decrypts the contiguous `blocks` contained in `ciphertext`.
+    pub fn decrypt_blocks(
+        &self,
+        blocks: Range<usize>,
+        ciphertext: &[u8],
+    ) -> StorageResult<Vec<u8>> {
+        let blocks_ciphertext_range = self.blocks_ciphertext_range(&blocks);
+
+        if ciphertext.len() != blocks_ciphertext_range.len() {
+            return Err(StorageErrorKind::Internal
+                .with_error(anyhow!("encrypted object is truncated or corrupted")));
+        }
+        let mut plaintext = Vec::with_capacity(ciphertext.len());
+
+        for block_ord in blocks {
+            let block_ciphertext_range = self.block_ciphertext_range(block_ord);
+            let block_ciphertext = &ciphertext[block_ciphertext_range.start
+                - blocks_ciphertext_range.start
+                ..block_ciphertext_range.end - blocks_ciphertext_range.start];
+            let nonce = self.nonce(block_ord);
+            let payload = Payload {
+                msg: block_ciphertext,
+                aad: &self.header_bytes,
+            };
+            let block_plaintext = self
+                .cipher
+                .decrypt(Nonce::from_slice(&nonce), payload)
+                .map_err(|_| {
+                    StorageErrorKind::Internal.with_error(anyhow!(
+                        "failed to authenticate block {block_ord} of encrypted object"
+                    ))
+                })?;
+            plaintext.extend_from_slice(&block_plaintext);
+        }
+        Ok(plaintext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object_cipher_for_test(block_num_bytes: u32, plaintext_len: u64) -> ObjectCipher {
+        let header = EncryptionHeader {
+            block_num_bytes,
+            plaintext_len,
+            wrapped_key: WrappedKey {
+                key_id: "test-key".to_string(),
+                ciphertext: vec![42; 60],
+            },
+        };
+        ObjectCipher::new(&header, &generate_data_key()).unwrap()
+    }
+
+    #[test]
+    fn test_encryption_header_serde() {
+        let header = EncryptionHeader {
+            block_num_bytes: 1024,
+            plaintext_len: 1_000_000,
+            wrapped_key: WrappedKey {
+                key_id: "test-key".to_string(),
+                ciphertext: vec![1, 2, 3],
+            },
+        };
+        let header_bytes = header.serialize();
+        assert_eq!(
+            EncryptionHeader::parse_header_len(&header_bytes).unwrap(),
+            header_bytes.len()
+        );
+        assert_eq!(
+            EncryptionHeader::deserialize(&header_bytes).unwrap(),
+            header
+        );
+
+        EncryptionHeader::deserialize(&header_bytes[..header_bytes.len() - 1]).unwrap_err();
+        EncryptionHeader::parse_header_len(b"hello world").unwrap_err();
+    }
+
+    #[test]
+    fn test_object_cipher_block_ranges() {
+        let object_cipher = object_cipher_for_test(10, 25);
+        let header_len = object_cipher.header_len();
+        assert_eq!(object_cipher.num_blocks(), 3);
+        assert_eq!(object_cipher.ciphertext_len(), header_len + 25 + 3 * 16);
+        assert_eq!(object_cipher.block_plaintext_range(2), 20..25);
+        assert_eq!(
+            object_cipher.block_ciphertext_range(1),
+            header_len + 26..header_len + 52
+        );
+        assert_eq!(
+            object_cipher.blocks_overlapping_plaintext_range(&(9..11)),
+            0..2
+        );
+        assert_eq!(
+            object_cipher.blocks_overlapping_plaintext_range(&(20..25)),
+            2..3
+        );
+        assert_eq!(
+            object_cipher.blocks_overlapping_ciphertext_range(&(0..header_len + 1)),
+            0..1
+        );
+        assert_eq!(
+            object_cipher.blocks_overlapping_ciphertext_range(
+                &(header_len + 26..object_cipher.ciphertext_len())
+            ),
+            1..3
+        );
+        let empty_object_cipher = object_cipher_for_test(10, 0);
+        assert_eq!(empty_object_cipher.num_blocks(), 1);
+        assert_eq!(
+            empty_object_cipher.ciphertext_len(),
+            empty_object_cipher.header_len() + 16
+        );
+    }
+
+    #[test]
+    fn test_object_cipher_encrypt_decrypt_blocks() {
+        let plaintext: Vec<u8> = (0..25).collect();
+        let object_cipher = object_cipher_for_test(10, 25);
+        let ciphertext: Vec<u8> = (0..3)
+            .flat_map(|block_ord| {
+                let block_plaintext = &plaintext[object_cipher.block_plaintext_range(block_ord)];
+                object_cipher.encrypt_block(block_ord, block_plaintext)
+            })
+            .collect();
+        assert_eq!(ciphertext.len(), 25 + 3 * 16);
+
+        let decrypted = object_cipher.decrypt_blocks(0..3, &ciphertext).unwrap();
+        assert_eq!(decrypted, plaintext);
+
+        let decrypted = object_cipher
+            .decrypt_blocks(1..3, &ciphertext[26..])
+            .unwrap();
+        assert_eq!(decrypted, &plaintext[10..]);
+
+        // Blocks cannot be reordered.
+        let mut swapped_ciphertext = ciphertext[26..52].to_vec();
+        swapped_ciphertext.extend_from_slice(&ciphertext[..26]);
+        object_cipher
+            .decrypt_blocks(0..2, &swapped_ciphertext)
+            .unwrap_err();
+
+        let mut tampered_ciphertext = ciphertext;
+        tampered_ciphertext[0] ^= 1;
+        object_cipher
+            .decrypt_blocks(0..1, &tampered_ciphertext[..26])
+            .unwrap_err();
+    }
+}
@@ -0,0 +1,299 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use quickwit_config::HedgedReadsConfig;
+use tokio::time::Instant;
+
+use crate::StorageResult;
+
+/// Number of recent request latencies from which the hedging delay is computed.
+const LATENCY_WINDOW_SIZE: usize = 1_000;
+
+/// Requests are not hedged until that many latencies have been observed. The hedging delay is
+/// then refreshed every `MIN_NUM_SAMPLES` requests.
+const MIN_NUM_SAMPLES: usize = 100;
+
+/// Maximum number of hedged requests that can be issued in a burst.
+const MAX_BUDGET: f64 = 10.0;
+
+struct HedgerState {
+    latencies: VecDeque<Duration>,
+    num_samples_since_refresh: usize,
+    hedge_delay_opt: Option<Duration>,
+    budget: f64,
+}
+
+/// Issues hedged requests to an object storage backend.
+///
+/// If a request has not completed after a delay equal to a percentile of the recent request
+/// latencies, a duplicate request is issued and whichever succeeds first wins. The number of
+/// hedged requests is capped by a budget, replenished by a fraction of a request each time a
+/// request is made.
+///
+/// A hedger is shared by all the storages of a backend.
+pub(crate) struct RequestHedger {
+    backend: &'static str,
+    latency_percentile: u8,
+    min_delay: Duration,
+    budget_per_request: f64,
+    state: Mutex<HedgerState>,
+}
+
+impl RequestHedger {
+    pub fn new(backend: &'static str, hedged_reads_config: &HedgedReadsConfig) -> Self {
+        let state = HedgerState {
+            latencies: VecDeque::with_capacity(LATENCY_WINDOW_SIZE),
+            num_samples_since_refresh: 0,
+            hedge_delay_opt: None,
+            budget: 0.0,
+        };
+        Self {
+            backend,
+            latency_percentile: hedged_reads_config.latency_percentile,
+            min_delay: hedged_reads_config.min_delay(),
+            budget_per_request: hedged_reads_config.max_hedged_requests_percent as f64 / 100.0,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Executes the request built by `request_fn`, hedging it if it takes too long.
+    pub async fn execute<T, F, Fut>(&self, request_fn: F) -> StorageResult<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = StorageResult<T>>,
+    {
+        let start = Instant::now();
+        let Some(hedge_delay) = self.start_request() else {
+            let result = request_fn().await;
+            self.record_latency(&result, start);
+            return result;
+        };
+        let primary_request = request_fn();
+        tokio::pin!(primary_request);
+
+        tokio::select! {
+            result = &mut primary_request => {
+                self.record_latency(&result, start);
+                return result;
+            }
+            _ = tokio::time::sleep(hedge_delay) => {}
+        }
+        if !self.try_consume_budget() {
+            let result = primary_request.await;
+            self.record_latency(&result, start);
+            return result;
+        }
+        crate::STORAGE_METRICS
+            .object_storage_hedged_requests_total
+            .with_label_values([self.backend])
+            .inc();
+        let hedged_request = request_fn();
+        tokio::pin!(hedged_request);
+
+        let result = tokio::select! {
+            result = &mut primary_request => {
+                if result.is_ok() {
+                    result
+                } else {
+                    hedged_request.await
+                }
+            }
+            result = &mut hedged_request => {
+                if result.is_ok() {
+                    crate::STORAGE_METRICS
+                        .object_storage_hedged_requests_won_total
+                        .with_label_values([self.backend])
+                        .inc();
+                    result
+                } else {
+                    primary_request.await
+                }
+            }
+        };
+        self.record_latency(&result, start);
+        result
+    }
+
+    /// Replenishes the budget and returns the current hedging delay, if any.
+    fn start_request(&self) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+        state.budget = (state.budget + self.budget_per_request).min(MAX_BUDGET);
+        state.hedge_delay_opt
+    }
+
+    fn try_consume_budget(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.budget < 1.0 {
+            return false;
+        }
+        state.budget -= 1.0;
+        true
+    }
+
+    fn record_latency<T>(&self, result: &StorageResult<T>, start: Instant) {
+        if result.is_err() {
+            return;
+        }
+        let latency = start.elapsed();
+        let mut state = self.state.lock().unwrap();
+
+        if state.latencies.len() == LATENCY_WINDOW_SIZE {
+            state.latencies.pop_front();
+        }
+        state.latencies.push_back(latency);
+        state.num_samples_since_refresh += 1;
+
+        if state.latencies.len() >= MIN_NUM_SAMPLES
+            && state.num_samples_since_refresh >= MIN_NUM_SAMPLES
+        {
+            let mut latencies: Vec<Duration> = state.latencies.iter().copied().collect();
+            let rank = (latencies.len() - 1) * self.latency_percentile as usize / 100;
+            let (_, percentile_latency, _) = latencies.select_nth_unstable(rank);
+            state.hedge_delay_opt = Some((*percentile_latency).max(self.min_delay));
+            state.num_samples_since_refresh = 0;
+        }
+    }
+
+    #[cfg(test)]
+    fn hedge_delay_opt(&self) -> Option<Duration> {
+        self.state.lock().unwrap().hedge_delay_opt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{StorageError, StorageErrorKind};
+
+    fn hedged_reads_config_for_test() -> HedgedReadsConfig {
+        HedgedReadsConfig {
+            latency_percentile: 90,
+            min_delay_ms: 10,
+            max_hedged_requests_percent: 100,
+        }
+    }
+
+    async fn warm_up(request_hedger: &RequestHedger, latency: Duration) {
+        for _ in 0..MIN_NUM_SAMPLES {
+            request_hedger
+                .execute(|| async move {
+                    tokio::time::sleep(latency).await;
+                    Ok(())
+                })
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_hedger_computes_hedge_delay() {
+        tokio::time::pause();
+        let request_hedger = RequestHedger::new("test", &hedged_reads_config_for_test());
+        assert!(request_hedger.hedge_delay_opt().is_none());
+
+        warm_up(&request_hedger, Duration::from_millis(1)).await;
+        assert_eq!(
+            request_hedger.hedge_delay_opt(),
+            Some(Duration::from_millis(10))
+        );
+        warm_up(&request_hedger, Duration::from_millis(50)).await;
+        assert_eq!(
+            request_hedger.hedge_delay_opt(),
+            Some(Duration::from_millis(50))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_hedger_hedges_slow_requests() {
+        tokio::time::pause();
+        let request_hedger = RequestHedger::new("test", &hedged_reads_config_for_test());
+        warm_up(&request_hedger, Duration::from_millis(20)).await;
+
+        let num_requests = &AtomicUsize::new(0);
+        let start = tokio::time::Instant::now();
+        let response = request_hedger
+            .execute(|| async move {
+                let request_ord = num_requests.fetch_add(1, Ordering::Relaxed);
+                // The first request is stuck, the hedged one completes.
+                if request_ord == 0 {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                } else {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                }
+                Ok(request_ord)
+            })
+            .await
+            .unwrap();
+        assert_eq!(response, 1);
+        assert_eq!(num_requests.load(Ordering::Relaxed), 2);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_request_hedger_falls_back_on_primary_request_on_error() {
+        tokio::time::pause();
+        let request_hedger = RequestHedger::new("test", &hedged_reads_config_for_test());
+        warm_up(&request_hedger, Duration::from_millis(20)).await;
+
+        let num_requests = &AtomicUsize::new(0);
+        let response = request_hedger
+            .execute(|| async move {
+                let request_ord = num_requests.fetch_add(1, Ordering::Relaxed);
+                if request_ord == 0 {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    Ok(request_ord)
+                } else {
+                    Err::<usize, StorageError>(
+                        StorageErrorKind::Service.with_error(anyhow::anyhow!("throttled")),
+                    )
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(response, 0);
+    }
+
+    #[tokio::test]
+    async fn test_request_hedger_respects_budget() {
+        tokio::time::pause();
+        let hedged_reads_config = HedgedReadsConfig {
+            max_hedged_requests_percent: 0,
+            ..hedged_reads_config_for_test()
+        };
+        let request_hedger = RequestHedger::new("test", &hedged_reads_config);
+        warm_up(&request_hedger, Duration::from_millis(20)).await;
+
+        let num_requests = &AtomicUsize::new(0);
+        request_hedger
+            .execute(|| async move {
+                num_requests.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Ok(())
+            })
+            .await
+            .unwrap();
+        assert_eq!(num_requests.load(Ordering::Relaxed), 1);
+    }
+}
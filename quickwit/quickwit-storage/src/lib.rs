@@ -42,7 +42,9 @@ pub use self::payload::PutPayload;
 pub use self::storage::Storage;
 
 mod bundle_storage;
+mod encrypted_storage;
 mod error;
+mod hedging;
 
 mod local_file_storage;
 mod object_storage;
@@ -51,6 +53,7 @@ mod opendal_storage;
 mod payload;
 mod prefix_storage;
 mod ram_storage;
+mod request_counting_storage;
 mod split;
 mod split_cache;
 mod storage_factory;
@@ -68,6 +71,10 @@ pub use self::cache::MockStorageCache;
 pub use self::cache::{
     wrap_storage_with_cache, ByteRangeCache, MemorySizedCache, QuickwitCache, StorageCache,
 };
+pub use self::encrypted_storage::{
+    key_provider_from_config, EncryptedStorage, KeyProvider, KmsKeyProvider, LocalKeyProvider,
+    StorageEncryption, WrappedKey,
+};
 pub use self::local_file_storage::{LocalFileStorage, LocalFileStorageFactory};
 #[cfg(feature = "azure")]
 pub use self::object_storage::{AzureBlobStorage, AzureBlobStorageFactory};
@@ -79,7 +86,11 @@ pub use self::opendal_storage::new_emulated_google_cloud_storage;
 #[cfg(feature = "gcs")]
 pub use self::opendal_storage::GoogleCloudStorageFactory;
 pub use self::ram_storage::{RamStorage, RamStorageBuilder};
-pub use self::split::{SplitPayload, SplitPayloadBuilder};
+pub use self::request_counting_storage::{
+    wrap_storage_with_request_accounting, wrap_storage_with_request_counter, StorageOperation,
+    StorageRequestContext, StorageRequestCounter,
+};
+pub use self::split::{FilePayload, SplitPayload, SplitPayloadBuilder};
 #[cfg(any(test, feature = "testsuite"))]
 pub use self::storage::MockStorage;
 #[cfg(any(test, feature = "testsuite"))]
@@ -104,7 +115,7 @@ pub async fn load_file(
     let parent = uri
         .parent()
         .ok_or_else(|| anyhow::anyhow!("URI `{uri}` is not a valid file URI"))?;
-    let storage = storage_resolver.resolve(&parent).await?;
+    let storage = storage_resolver.resolve_unencrypted(&parent).await?;
     let file_name = uri
         .file_name()
         .ok_or_else(|| anyhow::anyhow!("URI `{uri}` is not a valid file URI"))?;
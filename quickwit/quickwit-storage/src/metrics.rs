@@ -20,12 +20,15 @@
 // See https://prometheus.io/docs/practices/naming/
 
 use once_cell::sync::Lazy;
-use quickwit_common::metrics::{new_counter, new_gauge, IntCounter, IntGauge};
+use quickwit_common::metrics::{
+    new_counter, new_counter_vec, new_gauge, IntCounter, IntCounterVec, IntGauge,
+};
 
 /// Counters associated to storage operations.
 pub struct StorageMetrics {
     pub shortlived_cache: CacheMetrics,
     pub partial_request_cache: CacheMetrics,
+    pub root_search_cache: CacheMetrics,
     pub fast_field_cache: CacheMetrics,
     pub split_footer_cache: CacheMetrics,
     pub searcher_split_cache: CacheMetrics,
@@ -34,6 +37,10 @@ pub struct StorageMetrics {
     pub object_storage_put_parts: IntCounter,
     pub object_storage_download_num_bytes: IntCounter,
     pub object_storage_upload_num_bytes: IntCounter,
+    pub object_storage_hedged_requests_total: IntCounterVec<1>,
+    pub object_storage_hedged_requests_won_total: IntCounterVec<1>,
+    pub storage_requests_total: IntCounterVec<3>,
+    pub storage_request_num_bytes_total: IntCounterVec<3>,
 }
 
 impl Default for StorageMetrics {
@@ -42,6 +49,7 @@ impl Default for StorageMetrics {
             fast_field_cache: CacheMetrics::for_component("fastfields"),
             shortlived_cache: CacheMetrics::for_component("shortlived"),
             partial_request_cache: CacheMetrics::for_component("partial_request"),
+            root_search_cache: CacheMetrics::for_component("root_search"),
             searcher_split_cache: CacheMetrics::for_component("searcher_split"),
 
             split_footer_cache: CacheMetrics::for_component("splitfooter"),
@@ -71,6 +79,32 @@ impl Default for StorageMetrics {
                 "Amount of data uploaded to an object storage.",
                 "quickwit_storage",
             ),
+            object_storage_hedged_requests_total: new_counter_vec(
+                "object_storage_hedged_requests_total",
+                "Number of duplicate ranged reads issued because the original request was slow.",
+                "quickwit_storage",
+                ["backend"],
+            ),
+            object_storage_hedged_requests_won_total: new_counter_vec(
+                "object_storage_hedged_requests_won_total",
+                "Number of hedged ranged reads that completed before the original request.",
+                "quickwit_storage",
+                ["backend"],
+            ),
+            storage_requests_total: new_counter_vec(
+                "storage_requests_total",
+                "Number of storage requests, by index, operation (search, merge, gc, \
+                 indexing_upload) and request kind.",
+                "quickwit_storage",
+                ["index", "operation", "request"],
+            ),
+            storage_request_num_bytes_total: new_counter_vec(
+                "storage_request_num_bytes_total",
+                "Number of bytes transferred by storage requests, by index, operation and \
+                 direction (download, upload).",
+                "quickwit_storage",
+                ["index", "operation", "direction"],
+            ),
         }
     }
 }
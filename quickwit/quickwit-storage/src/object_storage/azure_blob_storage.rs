@@ -49,6 +49,7 @@ use tokio_util::io::StreamReader;
 use tracing::{instrument, warn};
 
 use crate::debouncer::DebouncedStorage;
+use crate::hedging::RequestHedger;
 use crate::storage::SendableAsync;
 use crate::{
     BulkDeleteError, DeleteFailure, MultiPartPolicy, PutPayload, Storage, StorageError,
@@ -58,12 +59,19 @@ use crate::{
 /// Azure object storage resolver.
 pub struct AzureBlobStorageFactory {
     storage_config: AzureStorageConfig,
+    request_hedger_opt: Option<Arc<RequestHedger>>,
 }
 
 impl AzureBlobStorageFactory {
     /// Creates a new Azure blob storage factory.
     pub fn new(storage_config: AzureStorageConfig) -> Self {
-        Self { storage_config }
+        let request_hedger_opt = storage_config
+            .hedged_reads
+            .map(|hedged_reads_config| Arc::new(RequestHedger::new("azure", &hedged_reads_config)));
+        Self {
+            storage_config,
+            request_hedger_opt,
+        }
     }
 }
 
@@ -74,7 +82,8 @@ impl StorageFactory for AzureBlobStorageFactory {
     }
 
     async fn resolve(&self, uri: &Uri) -> Result<Arc<dyn Storage>, StorageResolverError> {
-        let storage = AzureBlobStorage::from_uri(&self.storage_config, uri)?;
+        let mut storage = AzureBlobStorage::from_uri(&self.storage_config, uri)?;
+        storage.set_request_hedger(self.request_hedger_opt.clone());
         Ok(Arc::new(DebouncedStorage::new(storage)))
     }
 }
@@ -86,6 +95,7 @@ pub struct AzureBlobStorage {
     prefix: PathBuf,
     multipart_policy: MultiPartPolicy,
     retry_params: RetryParams,
+    request_hedger_opt: Option<Arc<RequestHedger>>,
 }
 
 impl fmt::Debug for AzureBlobStorage {
@@ -112,6 +122,7 @@ impl AzureBlobStorage {
                 max_attempts: 3,
                 ..Default::default()
             },
+            request_hedger_opt: None,
         }
     }
 
@@ -125,6 +136,7 @@ impl AzureBlobStorage {
             prefix,
             multipart_policy: self.multipart_policy,
             retry_params: self.retry_params,
+            request_hedger_opt: self.request_hedger_opt,
         }
     }
 
@@ -143,6 +155,7 @@ impl AzureBlobStorage {
                 max_attempts: 3,
                 ..Default::default()
             },
+            request_hedger_opt: None,
         }
     }
 
@@ -153,6 +166,11 @@ impl AzureBlobStorage {
         self.multipart_policy = multipart_policy;
     }
 
+    /// Sets the hedger of the ranged reads.
+    pub(crate) fn set_request_hedger(&mut self, request_hedger_opt: Option<Arc<RequestHedger>>) {
+        self.request_hedger_opt = request_hedger_opt;
+    }
+
     /// Builds instance from URI.
     pub fn from_uri(
         azure_storage_config: &AzureStorageConfig,
@@ -416,17 +434,20 @@ impl Storage for AzureBlobStorage {
 
     #[instrument(level = "debug", skip(self, range), fields(range.start = range.start, range.end = range.end))]
     async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
-        self.get_to_vec(path, Some(range.clone()))
-            .await
-            .map(OwnedBytes::new)
-            .map_err(|err| {
-                err.add_context(format!(
-                    "failed to fetch slice {:?} for object: {}/{}",
-                    range,
-                    self.uri,
-                    path.display(),
-                ))
-            })
+        let get_to_vec = || self.get_to_vec(path, Some(range.clone()));
+        let get_to_vec_result = if let Some(request_hedger) = &self.request_hedger_opt {
+            request_hedger.execute(get_to_vec).await
+        } else {
+            get_to_vec().await
+        };
+        get_to_vec_result.map(OwnedBytes::new).map_err(|err| {
+            err.add_context(format!(
+                "failed to fetch slice {:?} for object: {}/{}",
+                range,
+                self.uri,
+                path.display(),
+            ))
+        })
     }
 
     #[instrument(level = "debug", skip(self, range), fields(range.start = range.start, range.end = range.end))]
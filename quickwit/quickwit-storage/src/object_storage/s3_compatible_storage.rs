@@ -21,6 +21,7 @@ use std::collections::HashMap;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::{env, fmt, io};
 
@@ -47,6 +48,7 @@ use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader, ReadBuf};
 use tokio::sync::Semaphore;
 use tracing::{info, instrument, warn};
 
+use crate::hedging::RequestHedger;
 use crate::object_storage::MultiPartPolicy;
 use crate::storage::SendableAsync;
 use crate::{
@@ -93,6 +95,7 @@ pub struct S3CompatibleObjectStorage {
     retry_params: RetryParams,
     disable_multi_object_delete: bool,
     disable_multipart_upload: bool,
+    request_hedger_opt: Option<Arc<RequestHedger>>,
 }
 
 impl fmt::Debug for S3CompatibleObjectStorage {
@@ -174,6 +177,7 @@ impl S3CompatibleObjectStorage {
             retry_params,
             disable_multi_object_delete,
             disable_multipart_upload,
+            request_hedger_opt: None,
         })
     }
 
@@ -204,6 +208,7 @@ impl S3CompatibleObjectStorage {
             retry_params: self.retry_params,
             disable_multi_object_delete: self.disable_multi_object_delete,
             disable_multipart_upload: self.disable_multipart_upload,
+            request_hedger_opt: self.request_hedger_opt,
         }
     }
 
@@ -213,6 +218,11 @@ impl S3CompatibleObjectStorage {
     pub fn set_policy(&mut self, multipart_policy: MultiPartPolicy) {
         self.multipart_policy = multipart_policy;
     }
+
+    /// Sets the hedger of the ranged reads.
+    pub(crate) fn set_request_hedger(&mut self, request_hedger_opt: Option<Arc<RequestHedger>>) {
+        self.request_hedger_opt = request_hedger_opt;
+    }
 }
 
 pub fn parse_s3_uri(uri: &Uri) -> Option<(String, PathBuf)> {
@@ -755,17 +765,20 @@ impl Storage for S3CompatibleObjectStorage {
     #[instrument(level = "debug", skip(self, range), fields(range.start = range.start, range.end = range.end))]
     async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
         let _permit = REQUEST_SEMAPHORE.acquire().await;
-        self.get_to_vec(path, Some(range.clone()))
-            .await
-            .map(OwnedBytes::new)
-            .map_err(|err| {
-                err.add_context(format!(
-                    "failed to fetch slice {:?} for object: {}/{}",
-                    range,
-                    self.uri,
-                    path.display(),
-                ))
-            })
+        let get_to_vec = || self.get_to_vec(path, Some(range.clone()));
+        let get_to_vec_result = if let Some(request_hedger) = &self.request_hedger_opt {
+            request_hedger.execute(get_to_vec).await
+        } else {
+            get_to_vec().await
+        };
+        get_to_vec_result.map(OwnedBytes::new).map_err(|err| {
+            err.add_context(format!(
+                "failed to fetch slice {:?} for object: {}/{}",
+                range,
+                self.uri,
+                path.display(),
+            ))
+        })
     }
 
     #[instrument(level = "debug", skip(self, range), fields(range.start = range.start, range.end = range.end))]
@@ -912,6 +925,7 @@ mod tests {
             retry_params: RetryParams::default(),
             disable_multi_object_delete: false,
             disable_multipart_upload: false,
+            request_hedger_opt: None,
         };
         assert_eq!(
             s3_storage.relative_path("indexes/foo"),
@@ -966,6 +980,7 @@ mod tests {
             retry_params: RetryParams::default(),
             disable_multi_object_delete: true,
             disable_multipart_upload: false,
+            request_hedger_opt: None,
         };
         let _ = s3_storage
             .bulk_delete(&[Path::new("foo"), Path::new("bar")])
@@ -1010,6 +1025,7 @@ mod tests {
             retry_params: RetryParams::default(),
             disable_multi_object_delete: false,
             disable_multipart_upload: false,
+            request_hedger_opt: None,
         };
         let _ = s3_storage
             .bulk_delete(&[Path::new("foo"), Path::new("bar")])
@@ -1095,6 +1111,7 @@ mod tests {
             retry_params: RetryParams::default(),
             disable_multi_object_delete: false,
             disable_multipart_upload: false,
+            request_hedger_opt: None,
         };
         let bulk_delete_error = s3_storage
             .bulk_delete(&[
@@ -23,6 +23,7 @@ use async_trait::async_trait;
 use quickwit_common::uri::Uri;
 use quickwit_config::{S3StorageConfig, StorageBackend};
 
+use crate::hedging::RequestHedger;
 use crate::{
     DebouncedStorage, S3CompatibleObjectStorage, Storage, StorageFactory, StorageResolverError,
 };
@@ -30,12 +31,21 @@ use crate::{
 /// S3 compatible object storage resolver.
 pub struct S3CompatibleObjectStorageFactory {
     storage_config: S3StorageConfig,
+    // Shared by all the resolved storages so that the hedging delay is computed over all the
+    // requests made to the backend.
+    request_hedger_opt: Option<Arc<RequestHedger>>,
 }
 
 impl S3CompatibleObjectStorageFactory {
     /// Creates a new S3-compatible storage factory.
     pub fn new(storage_config: S3StorageConfig) -> Self {
-        Self { storage_config }
+        let request_hedger_opt = storage_config
+            .hedged_reads
+            .map(|hedged_reads_config| Arc::new(RequestHedger::new("s3", &hedged_reads_config)));
+        Self {
+            storage_config,
+            request_hedger_opt,
+        }
     }
 }
 
@@ -46,7 +56,8 @@ impl StorageFactory for S3CompatibleObjectStorageFactory {
     }
 
     async fn resolve(&self, uri: &Uri) -> Result<Arc<dyn Storage>, StorageResolverError> {
-        let storage = S3CompatibleObjectStorage::from_uri(&self.storage_config, uri).await?;
+        let mut storage = S3CompatibleObjectStorage::from_uri(&self.storage_config, uri).await?;
+        storage.set_request_hedger(self.request_hedger_opt.clone());
         Ok(Arc::new(DebouncedStorage::new(storage)))
     }
 }
@@ -20,6 +20,7 @@
 use std::fmt;
 use std::ops::Range;
 use std::path::Path;
+use std::sync::Arc;
 
 use async_trait::async_trait;
 use bytesize::ByteSize;
@@ -27,6 +28,7 @@ use opendal::Operator;
 use quickwit_common::uri::Uri;
 use tokio::io::{AsyncRead, AsyncWriteExt};
 
+use crate::hedging::RequestHedger;
 use crate::storage::SendableAsync;
 use crate::{
     BulkDeleteError, OwnedBytes, PutPayload, Storage, StorageError, StorageErrorKind,
@@ -43,6 +45,7 @@ use crate::{
 pub struct OpendalStorage {
     uri: Uri,
     op: Operator,
+    request_hedger_opt: Option<Arc<RequestHedger>>,
 }
 
 impl fmt::Debug for OpendalStorage {
@@ -61,7 +64,16 @@ impl OpendalStorage {
         cfg: opendal::services::Gcs,
     ) -> Result<Self, StorageResolverError> {
         let op = Operator::new(cfg)?.finish();
-        Ok(Self { uri, op })
+        Ok(Self {
+            uri,
+            op,
+            request_hedger_opt: None,
+        })
+    }
+
+    /// Sets the hedger of the ranged reads.
+    pub(crate) fn set_request_hedger(&mut self, request_hedger_opt: Option<Arc<RequestHedger>>) {
+        self.request_hedger_opt = request_hedger_opt;
     }
 }
 
@@ -102,8 +114,15 @@ impl Storage for OpendalStorage {
     async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
         let path = path.as_os_str().to_string_lossy();
         let range = range.start as u64..range.end as u64;
-        let storage_content = self.op.read_with(&path).range(range).await?;
-
+        let read = || async {
+            let storage_content = self.op.read_with(&path).range(range.clone()).await?;
+            Ok::<_, StorageError>(storage_content)
+        };
+        let storage_content = if let Some(request_hedger) = &self.request_hedger_opt {
+            request_hedger.execute(read).await?
+        } else {
+            read().await?
+        };
         Ok(OwnedBytes::new(storage_content))
     }
 
@@ -29,17 +29,25 @@ use tracing::info;
 
 use super::OpendalStorage;
 use crate::debouncer::DebouncedStorage;
+use crate::hedging::RequestHedger;
 use crate::{Storage, StorageFactory, StorageResolverError};
 
 /// Google cloud storage resolver.
 pub struct GoogleCloudStorageFactory {
     storage_config: GoogleCloudStorageConfig,
+    request_hedger_opt: Option<Arc<RequestHedger>>,
 }
 
 impl GoogleCloudStorageFactory {
     /// Create a new google cloud storage factory via config.
     pub fn new(storage_config: GoogleCloudStorageConfig) -> Self {
-        Self { storage_config }
+        let request_hedger_opt = storage_config.hedged_reads.map(|hedged_reads_config| {
+            Arc::new(RequestHedger::new("google", &hedged_reads_config))
+        });
+        Self {
+            storage_config,
+            request_hedger_opt,
+        }
     }
 }
 
@@ -50,7 +58,8 @@ impl StorageFactory for GoogleCloudStorageFactory {
     }
 
     async fn resolve(&self, uri: &Uri) -> Result<Arc<dyn Storage>, StorageResolverError> {
-        let storage = from_uri(&self.storage_config, uri)?;
+        let mut storage = from_uri(&self.storage_config, uri)?;
+        storage.set_request_hedger(self.request_hedger_opt.clone());
         Ok(Arc::new(DebouncedStorage::new(storage)))
     }
 }
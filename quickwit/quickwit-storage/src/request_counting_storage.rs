@@ -0,0 +1,422 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::ops::Range;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::{fmt, io};
+
+use async_trait::async_trait;
+use quickwit_common::uri::Uri;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::storage::SendableAsync;
+use crate::{BulkDeleteError, OwnedBytes, PutPayload, Storage, StorageResult, STORAGE_METRICS};
+
+/// Operation on behalf of which storage requests are issued.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum StorageOperation {
+    /// Searching an index: reading split footers, fast fields, postings, docs, etc.
+    Search,
+    /// Downloading splits to merge them.
+    Merge,
+    /// Deleting splits during garbage collection.
+    GarbageCollection,
+    /// Uploading freshly indexed or merged splits.
+    IndexingUpload,
+}
+
+impl StorageOperation {
+    /// Returns the label used for this operation in metrics.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StorageOperation::Search => "search",
+            StorageOperation::Merge => "merge",
+            StorageOperation::GarbageCollection => "gc",
+            StorageOperation::IndexingUpload => "indexing_upload",
+        }
+    }
+}
+
+impl fmt::Display for StorageOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Counts the number of requests of any kind (gets, puts, deletes, etc.) issued and the number of
+/// bytes downloaded through a storage wrapped with [`wrap_storage_with_request_counter`] or
+/// [`wrap_storage_with_request_accounting`].
+///
+/// Cloning a counter returns a handle over the same counts.
+#[derive(Clone, Debug, Default)]
+pub struct StorageRequestCounter {
+    inner: Arc<StorageRequestCounterInner>,
+}
+
+#[derive(Debug, Default)]
+struct StorageRequestCounterInner {
+    num_requests: AtomicU64,
+    num_bytes: AtomicU64,
+}
+
+impl StorageRequestCounter {
+    /// Returns the number of requests issued so far.
+    pub fn num_requests(&self) -> u64 {
+        self.inner.num_requests.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of bytes downloaded so far.
+    pub fn num_bytes(&self) -> u64 {
+        self.inner.num_bytes.load(Ordering::Relaxed)
+    }
+
+    fn record_request(&self) {
+        self.inner.num_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_read(&self, num_bytes: u64) {
+        self.inner.num_bytes.fetch_add(num_bytes, Ordering::Relaxed);
+    }
+}
+
+/// Identifies the index and the operation on behalf of which storage requests are issued.
+#[derive(Clone, Debug)]
+pub struct StorageRequestContext {
+    index_id: String,
+    operation: StorageOperation,
+    counter_opt: Option<StorageRequestCounter>,
+}
+
+impl StorageRequestContext {
+    /// Creates a new context for the requests issued on behalf of `operation` on index
+    /// `index_id`.
+    pub fn new(index_id: impl Into<String>, operation: StorageOperation) -> Self {
+        Self {
+            index_id: index_id.into(),
+            operation,
+            counter_opt: None,
+        }
+    }
+
+    /// Records the requests into `counter` on top of the Prometheus metrics.
+    pub fn with_counter(mut self, counter: StorageRequestCounter) -> Self {
+        self.counter_opt = Some(counter);
+        self
+    }
+
+    /// Returns the index ID.
+    pub fn index_id(&self) -> &str {
+        &self.index_id
+    }
+
+    /// Returns the operation.
+    pub fn operation(&self) -> StorageOperation {
+        self.operation
+    }
+}
+
+/// This storage acts as a proxy to another storage and counts the requests issued through it and
+/// the bytes read and written through it.
+///
+/// The requests are recorded into the counter, if any, and into the `storage_requests_total` and
+/// `storage_request_num_bytes_total` metrics when the storage is wrapped with a
+/// [`StorageRequestContext`]. Requests are recorded whether they succeed or not. Bytes are only
+/// recorded for successful requests.
+struct RequestCountingStorage {
+    storage: Arc<dyn Storage>,
+    counter_opt: Option<StorageRequestCounter>,
+    context_opt: Option<StorageRequestContext>,
+}
+
+impl RequestCountingStorage {
+    fn record_request(&self, request_kind: &str) {
+        if let Some(context) = &self.context_opt {
+            STORAGE_METRICS
+                .storage_requests_total
+                .with_label_values([&context.index_id, context.operation.as_str(), request_kind])
+                .inc();
+        }
+        if let Some(counter) = &self.counter_opt {
+            counter.record_request();
+        }
+    }
+
+    fn record_download(&self, num_bytes: u64) {
+        if let Some(context) = &self.context_opt {
+            STORAGE_METRICS
+                .storage_request_num_bytes_total
+                .with_label_values([&context.index_id, context.operation.as_str(), "download"])
+                .inc_by(num_bytes);
+        }
+        if let Some(counter) = &self.counter_opt {
+            counter.record_read(num_bytes);
+        }
+    }
+
+    fn record_upload(&self, num_bytes: u64) {
+        if let Some(context) = &self.context_opt {
+            STORAGE_METRICS
+                .storage_request_num_bytes_total
+                .with_label_values([&context.index_id, context.operation.as_str(), "upload"])
+                .inc_by(num_bytes);
+        }
+    }
+}
+
+impl fmt::Debug for RequestCountingStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestCountingStorage")
+            .field("storage", &self.storage)
+            .field("counter", &self.counter_opt)
+            .field("context", &self.context_opt)
+            .finish()
+    }
+}
+
+/// Counts the bytes written into the output of [`Storage::copy_to`].
+struct WriteCounter<'a> {
+    output: &'a mut dyn SendableAsync,
+    num_bytes: u64,
+}
+
+impl AsyncWrite for WriteCounter<'_> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut *self.output).poll_write(cx, buf);
+        if let Poll::Ready(Ok(num_bytes)) = &poll {
+            self.num_bytes += *num_bytes as u64;
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.output).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.output).poll_shutdown(cx)
+    }
+}
+
+#[async_trait]
+impl Storage for RequestCountingStorage {
+    async fn check_connectivity(&self) -> anyhow::Result<()> {
+        self.storage.check_connectivity().await
+    }
+
+    async fn put(&self, path: &Path, payload: Box<dyn PutPayload>) -> StorageResult<()> {
+        let num_bytes = payload.len();
+        self.record_request("put");
+        self.storage.put(path, payload).await?;
+        self.record_upload(num_bytes);
+        Ok(())
+    }
+
+    async fn copy_to(&self, path: &Path, output: &mut dyn SendableAsync) -> StorageResult<()> {
+        self.record_request("get");
+        let mut write_counter = WriteCounter {
+            output,
+            num_bytes: 0,
+        };
+        let copy_res = self.storage.copy_to(path, &mut write_counter).await;
+        // The bytes written before a failure were downloaded all the same.
+        self.record_download(write_counter.num_bytes);
+        copy_res
+    }
+
+    async fn copy_to_file(&self, path: &Path, output_path: &Path) -> StorageResult<u64> {
+        self.record_request("get");
+        let num_bytes = self.storage.copy_to_file(path, output_path).await?;
+        self.record_download(num_bytes);
+        Ok(num_bytes)
+    }
+
+    async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
+        self.record_request("get");
+        let bytes = self.storage.get_slice(path, range).await?;
+        self.record_download(bytes.len() as u64);
+        Ok(bytes)
+    }
+
+    async fn get_slice_stream(
+        &self,
+        path: &Path,
+        range: Range<usize>,
+    ) -> StorageResult<Box<dyn AsyncRead + Send + Unpin>> {
+        let num_bytes = range.len() as u64;
+        self.record_request("get");
+        let stream = self.storage.get_slice_stream(path, range).await?;
+        self.record_download(num_bytes);
+        Ok(stream)
+    }
+
+    async fn get_all(&self, path: &Path) -> StorageResult<OwnedBytes> {
+        self.record_request("get");
+        let bytes = self.storage.get_all(path).await?;
+        self.record_download(bytes.len() as u64);
+        Ok(bytes)
+    }
+
+    async fn delete(&self, path: &Path) -> StorageResult<()> {
+        self.record_request("delete");
+        self.storage.delete(path).await
+    }
+
+    async fn bulk_delete<'a>(&self, paths: &[&'a Path]) -> Result<(), BulkDeleteError> {
+        self.record_request("bulk_delete");
+        self.storage.bulk_delete(paths).await
+    }
+
+    async fn exists(&self, path: &Path) -> StorageResult<bool> {
+        self.record_request("head");
+        self.storage.exists(path).await
+    }
+
+    async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
+        self.record_request("head");
+        self.storage.file_num_bytes(path).await
+    }
+
+    fn uri(&self) -> &Uri {
+        self.storage.uri()
+    }
+}
+
+/// Wraps a storage so that the requests issued and the bytes downloaded through it are recorded
+/// into `counter`.
+pub fn wrap_storage_with_request_counter(
+    storage: Arc<dyn Storage>,
+    counter: StorageRequestCounter,
+) -> Arc<dyn Storage> {
+    Arc::new(RequestCountingStorage {
+        storage,
+        counter_opt: Some(counter),
+        context_opt: None,
+    })
+}
+
+/// Wraps a storage so that the requests issued through it are recorded into the
+/// `storage_requests_total` and `storage_request_num_bytes_total` metrics, labeled with the index
+/// ID and the operation of `context`, and into the counter of `context` if any.
+pub fn wrap_storage_with_request_accounting(
+    storage: Arc<dyn Storage>,
+    mut context: StorageRequestContext,
+) -> Arc<dyn Storage> {
+    Arc::new(RequestCountingStorage {
+        storage,
+        counter_opt: context.counter_opt.take(),
+        context_opt: Some(context),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RamStorage;
+
+    #[tokio::test]
+    async fn test_request_counting_storage() {
+        let ram_storage = Arc::new(RamStorage::default());
+        ram_storage
+            .put(Path::new("file"), Box::new(b"abcdefghij".to_vec()))
+            .await
+            .unwrap();
+        let counter = StorageRequestCounter::default();
+        let storage = wrap_storage_with_request_counter(ram_storage, counter.clone());
+        assert_eq!(counter.num_bytes(), 0);
+
+        let bytes = storage.get_slice(Path::new("file"), 2..5).await.unwrap();
+        assert_eq!(bytes.as_slice(), b"cde");
+        assert_eq!(counter.num_bytes(), 3);
+
+        storage.get_all(Path::new("file")).await.unwrap();
+        assert_eq!(counter.num_bytes(), 13);
+
+        let mut output = Vec::new();
+        storage
+            .copy_to(Path::new("file"), &mut output)
+            .await
+            .unwrap();
+        assert_eq!(output, b"abcdefghij");
+        assert_eq!(counter.num_bytes(), 23);
+
+        storage
+            .get_slice(Path::new("missing-file"), 0..3)
+            .await
+            .unwrap_err();
+        assert_eq!(counter.num_bytes(), 23);
+        assert_eq!(counter.num_requests(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_request_accounting_storage() {
+        let ram_storage = Arc::new(RamStorage::default());
+        let counter = StorageRequestCounter::default();
+        let context =
+            StorageRequestContext::new("test-request-accounting-storage", StorageOperation::Search)
+                .with_counter(counter.clone());
+        let storage = wrap_storage_with_request_accounting(ram_storage, context);
+
+        storage
+            .put(Path::new("file"), Box::new(b"abcdefghij".to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(counter.num_requests(), 1);
+        assert_eq!(counter.num_bytes(), 0);
+
+        let bytes = storage.get_slice(Path::new("file"), 2..5).await.unwrap();
+        assert_eq!(bytes.as_slice(), b"cde");
+        storage.get_all(Path::new("file")).await.unwrap();
+        assert_eq!(counter.num_requests(), 3);
+        assert_eq!(counter.num_bytes(), 13);
+
+        storage
+            .get_slice(Path::new("missing-file"), 0..3)
+            .await
+            .unwrap_err();
+        assert_eq!(counter.num_requests(), 4);
+        assert_eq!(counter.num_bytes(), 13);
+
+        storage.delete(Path::new("file")).await.unwrap();
+        assert_eq!(counter.num_requests(), 5);
+
+        let num_get_requests = STORAGE_METRICS
+            .storage_requests_total
+            .with_label_values(["test-request-accounting-storage", "search", "get"])
+            .get();
+        assert_eq!(num_get_requests, 3);
+        let num_bytes_downloaded = STORAGE_METRICS
+            .storage_request_num_bytes_total
+            .with_label_values(["test-request-accounting-storage", "search", "download"])
+            .get();
+        assert_eq!(num_bytes_downloaded, 13);
+        let num_bytes_uploaded = STORAGE_METRICS
+            .storage_request_num_bytes_total
+            .with_label_values(["test-request-accounting-storage", "search", "upload"])
+            .get();
+        assert_eq!(num_bytes_uploaded, 10);
+    }
+}
@@ -75,12 +75,24 @@ impl PutPayload for SplitPayload {
     }
 }
 
+/// A payload streaming the content of a file on the local disk.
 #[derive(Clone)]
-struct FilePayload {
+pub struct FilePayload {
     len: u64,
     path: PathBuf,
 }
 
+impl FilePayload {
+    /// Creates a payload for the file at `path`.
+    pub fn from_path(path: &Path) -> io::Result<FilePayload> {
+        let file = std::fs::metadata(path)?;
+        Ok(FilePayload {
+            path: path.to_owned(),
+            len: file.len(),
+        })
+    }
+}
+
 #[async_trait]
 impl PutPayload for FilePayload {
     fn len(&self) -> u64 {
@@ -142,7 +154,6 @@ impl SplitPayloadBuilder {
 
     /// Adds the file to the bundle file.
     pub fn add_file(&mut self, path: &Path) -> io::Result<()> {
-        let file = std::fs::metadata(path)?;
         let file_name = path
             .file_name()
             .and_then(std::ffi::OsStr::to_str)
@@ -154,11 +165,7 @@ impl SplitPayloadBuilder {
                 )
             })?;
 
-        let file_payload = FilePayload {
-            path: path.to_owned(),
-            len: file.len(),
-        };
-
+        let file_payload = FilePayload::from_path(path)?;
         self.add_payload(file_name, Box::new(file_payload));
 
         Ok(())
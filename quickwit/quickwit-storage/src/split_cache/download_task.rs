@@ -59,10 +59,21 @@ async fn download_split(
     } = candidate_split;
     let split_filename = split_file(*split_ulid);
     let target_filepath = root_path.join(&split_filename);
+    // The split is first downloaded to a temporary file and then atomically renamed, so that a
+    // partially downloaded split is never mistaken for a complete one upon restart.
+    let temp_filepath = target_filepath.with_extension("temp");
     let storage = storage_resolver.resolve(storage_uri).await?;
-    let num_bytes = storage
-        .copy_to_file(Path::new(&split_filename), &target_filepath)
-        .await?;
+    let num_bytes = match storage
+        .copy_to_file(Path::new(&split_filename), &temp_filepath)
+        .await
+    {
+        Ok(num_bytes) => num_bytes,
+        Err(storage_error) => {
+            let _ = tokio::fs::remove_file(&temp_filepath).await;
+            return Err(storage_error.into());
+        }
+    };
+    tokio::fs::rename(&temp_filepath, &target_filepath).await?;
     Ok(num_bytes)
 }
 
@@ -20,7 +20,6 @@
 mod download_task;
 mod split_table;
 
-use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::fs::File;
 use std::io::{self, Read, Seek, SeekFrom};
@@ -28,18 +27,20 @@ use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
+use anyhow::Context;
 use async_trait::async_trait;
 use quickwit_common::uri::Uri;
 use quickwit_config::SplitCacheLimits;
 use quickwit_proto::search::ReportSplit;
-use tantivy::directory::OwnedBytes;
+use tantivy::directory::{FileSlice, OwnedBytes};
 use tracing::{error, info, warn};
 use ulid::Ulid;
 
 use crate::split_cache::download_task::{delete_evicted_splits, spawn_download_task};
 use crate::split_cache::split_table::{SplitGuard, SplitTable};
-use crate::{wrap_storage_with_cache, Storage, StorageCache};
+use crate::{wrap_storage_with_cache, BundleStorageFileOffsets, Storage, StorageCache};
 
 /// On disk Cache of splits for searchers.
 ///
@@ -62,7 +63,7 @@ impl SplitCache {
         limits: SplitCacheLimits,
     ) -> io::Result<SplitCache> {
         std::fs::create_dir_all(&root_path)?;
-        let mut existing_splits: BTreeMap<Ulid, u64> = Default::default();
+        let mut existing_splits: Vec<(Ulid, u64, SystemTime)> = Vec::new();
         for dir_entry_res in std::fs::read_dir(&root_path)? {
             let dir_entry = dir_entry_res?;
             let path = dir_entry.path();
@@ -76,24 +77,39 @@ impl SplitCache {
                     // This file is a temporary file that was being downloaded, when Quickwit was
                     // stopped (killed for instance) in a way that prevented
                     // their cleanup. It is important to remove it.
-                    if let Err(io_err) = std::fs::remove_file(&path) {
-                        if io_err.kind() != io::ErrorKind::NotFound {
-                            error!(path=?path, "failed to remove temporary file");
-                        }
-                    }
+                    remove_file_from_cache_dir(&path);
                 }
                 "split" => {
-                    if let Some(split_ulid) = split_id_from_path(&path) {
-                        existing_splits.insert(split_ulid, meta.len());
-                    } else {
+                    let Some(split_ulid) = split_id_from_path(&path) else {
                         warn!(path=%path.display(), ".split file with invalid ulid in split cache directory, ignoring");
+                        continue;
+                    };
+                    if let Err(error) = validate_split_file(&path) {
+                        warn!(path=%path.display(), error=%error, "invalid split file in split cache directory, removing");
+                        remove_file_from_cache_dir(&path);
+                        continue;
                     }
+                    // Cached split files are written once, so their modification time is the
+                    // time at which they were downloaded.
+                    let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                    existing_splits.push((split_ulid, meta.len(), modified));
                 }
                 _ => {
                     warn!(path=%path.display(), "unknown file in split cache directory, ignoring");
                 }
             }
         }
+        existing_splits.sort_by_key(|(split_ulid, _, modified)| (*modified, *split_ulid));
+        let existing_splits: Vec<(Ulid, u64)> = existing_splits
+            .into_iter()
+            .map(|(split_ulid, num_bytes, _)| (split_ulid, num_bytes))
+            .collect();
+        if !existing_splits.is_empty() {
+            info!(
+                num_splits = existing_splits.len(),
+                "loaded existing splits into the searcher split cache"
+            );
+        }
         let mut split_table = SplitTable::with_limits_and_existing_splits(limits, existing_splits);
 
         // In case of a setting change, it could be useful to evict some splits on startup.
@@ -146,6 +162,19 @@ impl SplitCache {
         }
     }
 
+    /// Returns the IDs of the splits currently stored on disk, from the most to the least recently
+    /// accessed.
+    pub fn warm_split_ids(&self) -> Vec<String> {
+        self.split_table
+            .lock()
+            .unwrap()
+            .on_disk_splits()
+            .into_iter()
+            .rev()
+            .map(|split_ulid| split_ulid.to_string())
+            .collect()
+    }
+
     fn cached_split_filepath(&self, split_id: Ulid) -> PathBuf {
         let split_filename = quickwit_common::split_file(split_id);
         self.root_path.join(split_filename)
@@ -177,6 +206,61 @@ impl AsRef<Path> for SplitFilepath {
     }
 }
 
+fn remove_file_from_cache_dir(path: &Path) {
+    if let Err(io_err) = std::fs::remove_file(path) {
+        if io_err.kind() != io::ErrorKind::NotFound {
+            error!(path=?path, "failed to remove file from split cache directory");
+        }
+    }
+}
+
+fn read_exact_at(file: &mut File, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(buf)
+}
+
+fn read_u32_at(file: &mut File, offset: u64) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    read_exact_at(file, offset, &mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+/// Checks that a split file is complete by reading its footer, i.e. the hotcache length, the
+/// bundle metadata length, and the bundle metadata. See docs/internals/split-format.md.
+fn validate_split_file(split_path: &Path) -> anyhow::Result<()> {
+    let mut file = File::open(split_path)?;
+    let num_bytes = file.metadata()?.len();
+
+    let hotcache_len_offset = num_bytes
+        .checked_sub(4)
+        .context("split file is too small")?;
+    let hotcache_num_bytes = read_u32_at(&mut file, hotcache_len_offset)? as u64;
+    let bundle_end = hotcache_len_offset
+        .checked_sub(hotcache_num_bytes)
+        .context("hotcache length exceeds split file size")?;
+    let metadata_len_offset = bundle_end
+        .checked_sub(4)
+        .context("split file is too small")?;
+    let metadata_num_bytes = read_u32_at(&mut file, metadata_len_offset)? as u64;
+    let metadata_start = metadata_len_offset
+        .checked_sub(metadata_num_bytes)
+        .context("bundle metadata length exceeds split file size")?;
+
+    let mut metadata_bytes = vec![0u8; (bundle_end - metadata_start) as usize];
+    read_exact_at(&mut file, metadata_start, &mut metadata_bytes)?;
+    let metadata_file_slice = FileSlice::new(Arc::new(OwnedBytes::new(metadata_bytes)));
+    let bundle_file_offsets = BundleStorageFileOffsets::open(metadata_file_slice)?;
+
+    if let Some(file_range) = bundle_file_offsets
+        .files
+        .values()
+        .find(|file_range| file_range.end > metadata_start)
+    {
+        anyhow::bail!("bundled file range `{file_range:?}` exceeds split file size");
+    }
+    Ok(())
+}
+
 fn split_id_from_path(split_path: &Path) -> Option<Ulid> {
     let split_filename = split_path.file_name()?.to_str()?;
     let split_id_str = split_filename.strip_suffix(".split")?;
@@ -256,3 +340,31 @@ impl StorageCache for SplitCacheBackingStorage {
     async fn put(&self, _path: PathBuf, _byte_range: Range<usize>, _bytes: OwnedBytes) {}
     async fn put_all(&self, _path: PathBuf, _bytes: OwnedBytes) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SplitPayloadBuilder;
+
+    #[tokio::test]
+    async fn test_validate_split_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let split_bytes = SplitPayloadBuilder::get_split_payload(&[], b"fields", b"hotcache")
+            .unwrap()
+            .read_all()
+            .await
+            .unwrap();
+
+        let valid_split_path = temp_dir.path().join("valid.split");
+        std::fs::write(&valid_split_path, &split_bytes).unwrap();
+        validate_split_file(&valid_split_path).unwrap();
+
+        let truncated_split_path = temp_dir.path().join("truncated.split");
+        std::fs::write(&truncated_split_path, &split_bytes[4..]).unwrap();
+        validate_split_file(&truncated_split_path).unwrap_err();
+
+        let empty_split_path = temp_dir.path().join("empty.split");
+        std::fs::write(&empty_split_path, b"").unwrap();
+        validate_split_file(&empty_split_path).unwrap_err();
+    }
+}
@@ -18,7 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::cmp::Ordering;
-use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::collections::{BTreeSet, HashMap};
 use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant};
 
@@ -112,9 +112,13 @@ pub struct SplitTable {
 }
 
 impl SplitTable {
+    /// Creates a split table tracking the splits already present on disk.
+    ///
+    /// `existing_splits` lists the split ULIDs along with their size in bytes, ordered from the
+    /// least recently to the most recently used split.
     pub(crate) fn with_limits_and_existing_splits(
         limits: SplitCacheLimits,
-        existing_filepaths: BTreeMap<Ulid, u64>,
+        existing_splits: Vec<(Ulid, u64)>,
     ) -> SplitTable {
         let origin_time = Instant::now() - NEWLY_REPORTED_SPLIT_LAST_TIME;
         let mut split_table = SplitTable {
@@ -126,15 +130,18 @@ impl SplitTable {
             limits,
             on_disk_bytes: 0u64,
         };
-        split_table.acknowledge_on_disk_splits(existing_filepaths);
+        split_table.acknowledge_on_disk_splits(existing_splits);
         split_table
     }
 
-    fn acknowledge_on_disk_splits(&mut self, existing_filepaths: BTreeMap<Ulid, u64>) {
-        for (split_ulid, num_bytes) in existing_filepaths {
+    fn acknowledge_on_disk_splits(&mut self, existing_splits: Vec<(Ulid, u64)>) {
+        // The existing splits are considered older than any split accessed or reported from now
+        // on, but we preserve their relative order so that the least recently used ones get
+        // evicted first.
+        for (last_accessed, (split_ulid, num_bytes)) in existing_splits.into_iter().enumerate() {
             let split_info = SplitInfo {
                 split_key: SplitKey {
-                    last_accessed: 0,
+                    last_accessed: last_accessed as LastAccessDate,
                     split_ulid,
                 },
                 status: Status::OnDisk { num_bytes },
@@ -142,6 +149,14 @@ impl SplitTable {
             self.insert(split_info);
         }
     }
+
+    /// Returns the ULIDs of the splits present on disk.
+    pub(crate) fn on_disk_splits(&self) -> Vec<Ulid> {
+        self.on_disk_splits
+            .iter()
+            .map(|split_key| split_key.split_ulid)
+            .collect()
+    }
 }
 
 fn compute_timestamp(start: Instant) -> LastAccessDate {
@@ -655,4 +670,34 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_split_table_existing_splits_evicted_from_least_recently_used() {
+        let split_ulids = sorted_split_ulids(3);
+        // The existing splits are listed from the least to the most recently used.
+        let existing_splits = vec![
+            (split_ulids[2], 100),
+            (split_ulids[0], 100),
+            (split_ulids[1], 100),
+        ];
+        let mut split_table = SplitTable::with_limits_and_existing_splits(
+            SplitCacheLimits {
+                max_num_bytes: ByteSize::kb(1),
+                max_num_splits: NonZeroU32::new(2).unwrap(),
+                num_concurrent_downloads: NonZeroU32::new(1).unwrap(),
+            },
+            existing_splits,
+        );
+        assert_eq!(split_table.num_bytes(), 300);
+
+        let mut on_disk_splits = split_table.on_disk_splits();
+        on_disk_splits.sort();
+        assert_eq!(on_disk_splits, split_ulids);
+
+        let evicted_splits = split_table
+            .make_room_for_split_if_necessary(u64::MAX)
+            .unwrap();
+        assert_eq!(evicted_splits, [split_ulids[2]]);
+        assert_eq!(split_table.num_bytes(), 200);
+    }
 }
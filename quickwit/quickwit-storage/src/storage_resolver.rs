@@ -23,7 +23,7 @@ use std::sync::Arc;
 
 use once_cell::sync::Lazy;
 use quickwit_common::uri::{Protocol, Uri};
-use quickwit_config::{StorageBackend, StorageConfigs};
+use quickwit_config::{StorageBackend, StorageConfigs, StorageEncryptionConfig};
 
 use crate::local_file_storage::LocalFileStorageFactory;
 use crate::ram_storage::RamStorageFactory;
@@ -31,19 +31,28 @@ use crate::ram_storage::RamStorageFactory;
 use crate::AzureBlobStorageFactory;
 #[cfg(feature = "gcs")]
 use crate::GoogleCloudStorageFactory;
-use crate::{S3CompatibleObjectStorageFactory, Storage, StorageFactory, StorageResolverError};
+use crate::{
+    key_provider_from_config, EncryptedStorage, KeyProvider, S3CompatibleObjectStorageFactory,
+    Storage, StorageEncryption, StorageFactory, StorageResolverError,
+};
 
 /// Returns the [`Storage`] instance associated with the protocol of a URI. The actual creation of
 /// storage objects is delegated to pre-registered [`StorageFactory`]. The resolver is only
 /// responsible for dispatching to the appropriate factory.
+///
+/// When client-side encryption is enabled, the resolved storages are wrapped into an
+/// [`EncryptedStorage`].
 #[derive(Clone)]
 pub struct StorageResolver {
     per_backend_factories: Arc<HashMap<StorageBackend, Box<dyn StorageFactory>>>,
+    encryption_opt: Option<Arc<StorageEncryption>>,
 }
 
 impl fmt::Debug for StorageResolver {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("StorageResolver").finish()
+        f.debug_struct("StorageResolver")
+            .field("encryption", &self.encryption_opt)
+            .finish()
     }
 }
 
@@ -55,6 +64,20 @@ impl StorageResolver {
 
     /// Resolves the given URI.
     pub async fn resolve(&self, uri: &Uri) -> Result<Arc<dyn Storage>, StorageResolverError> {
+        let storage = self.resolve_unencrypted(uri).await?;
+
+        if let Some(encryption) = &self.encryption_opt {
+            return Ok(Arc::new(EncryptedStorage::new(storage, encryption.clone())));
+        }
+        Ok(storage)
+    }
+
+    /// Resolves the given URI, bypassing client-side encryption. This is meant for files provided
+    /// by users, such as config files or the input files of a file source.
+    pub async fn resolve_unencrypted(
+        &self,
+        uri: &Uri,
+    ) -> Result<Arc<dyn Storage>, StorageResolverError> {
         let backend = match uri.protocol() {
             Protocol::Azure => StorageBackend::Azure,
             Protocol::File => StorageBackend::File,
@@ -132,6 +155,19 @@ impl StorageResolver {
             .expect("storage factory and config backends should match")
     }
 
+    /// Returns a copy of this resolver encrypting the objects of the resolved storages with the
+    /// key provider described by `storage_encryption_config`.
+    pub fn with_encryption(
+        self,
+        storage_encryption_config: &StorageEncryptionConfig,
+    ) -> anyhow::Result<Self> {
+        let key_provider = key_provider_from_config(storage_encryption_config)?;
+        Ok(Self {
+            per_backend_factories: self.per_backend_factories,
+            encryption_opt: Some(Arc::new(StorageEncryption::new(key_provider))),
+        })
+    }
+
     /// Returns a [`StorageResolver`] for testing purposes. Unlike
     /// [`StorageResolver::unconfigured`], this resolver does not return a singleton.
     #[cfg(any(test, feature = "testsuite"))]
@@ -147,6 +183,7 @@ impl StorageResolver {
 #[derive(Default)]
 pub struct StorageResolverBuilder {
     per_backend_factories: HashMap<StorageBackend, Box<dyn StorageFactory>>,
+    key_provider_opt: Option<Arc<dyn KeyProvider>>,
 }
 
 impl StorageResolverBuilder {
@@ -157,10 +194,20 @@ impl StorageResolverBuilder {
         self
     }
 
+    /// Enables client-side encryption of the resolved storages.
+    pub fn encryption(mut self, key_provider: Arc<dyn KeyProvider>) -> Self {
+        self.key_provider_opt = Some(key_provider);
+        self
+    }
+
     /// Builds the [`StorageResolver`].
     pub fn build(self) -> anyhow::Result<StorageResolver> {
+        let encryption_opt = self
+            .key_provider_opt
+            .map(|key_provider| Arc::new(StorageEncryption::new(key_provider)));
         let storage_resolver = StorageResolver {
             per_backend_factories: Arc::new(self.per_backend_factories),
+            encryption_opt,
         };
         Ok(storage_resolver)
     }
@@ -246,4 +293,32 @@ mod tests {
             StorageResolverError::UnsupportedBackend(_)
         ));
     }
+
+    #[tokio::test]
+    async fn test_storage_resolver_with_encryption() {
+        let key_provider = Arc::new(crate::LocalKeyProvider::new("test-key", [7; 32]));
+        let storage_resolver = StorageResolver::builder()
+            .register(RamStorageFactory::default())
+            .encryption(key_provider)
+            .build()
+            .unwrap();
+        let storage_uri = Uri::for_test("ram:///indexes");
+        let storage = storage_resolver.resolve(&storage_uri).await.unwrap();
+        storage
+            .put(Path::new("hello"), Box::new(b"hello_content".to_vec()))
+            .await
+            .unwrap();
+        let data = storage.get_all(Path::new("hello")).await.unwrap();
+        assert_eq!(&data[..], b"hello_content");
+
+        let unencrypted_storage = storage_resolver
+            .resolve_unencrypted(&storage_uri)
+            .await
+            .unwrap();
+        let data = unencrypted_storage
+            .get_all(Path::new("hello"))
+            .await
+            .unwrap();
+        assert_ne!(&data[..], b"hello_content");
+    }
 }
@@ -124,6 +124,7 @@ pub enum QuickwitFeature {
     Jaeger,
     Otlp,
     PostgresqMetastore,
+    SqliteMetastore,
     AwsLambda,
 }
 